@@ -0,0 +1,155 @@
+//! イベントのバックアップモジュール
+//!
+//! 署名済みイベントを 1 行 1 イベントの JSONL 形式で書き出し・読み込みます。
+//! 既定の保存先はデータディレクトリ（~/.local/share/rust-nostr-mcp 等）の `backups/` です。
+
+use anyhow::{Context, Result};
+use nostr_sdk::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// バックアップファイルの既定の保存先ディレクトリ名
+const BACKUP_DIR: &str = "backups";
+
+/// バックアップファイルの既定のパス（`backups/<npub>-<Unix タイムスタンプ>.jsonl`）
+pub fn default_export_path(public_key: &PublicKey, now: u64) -> Result<PathBuf> {
+    let npub = public_key.to_bech32().unwrap_or_else(|_| public_key.to_hex());
+    Ok(crate::storage::data_dir()?
+        .join(BACKUP_DIR)
+        .join(format!("{}-{}.jsonl", npub, now)))
+}
+
+/// イベントを作成日時の古い順に JSONL ファイルへ書き出す
+pub fn write_events_jsonl(path: &Path, events: &[Event]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent).context("バックアップ先ディレクトリの作成に失敗しました")?;
+        }
+    }
+
+    let mut sorted: Vec<&Event> = events.iter().collect();
+    sorted.sort_by_key(|e| (e.created_at, e.id));
+
+    let mut file = std::io::BufWriter::new(
+        fs::File::create(path).context(format!("バックアップファイルの作成に失敗: {}", path.display()))?,
+    );
+    for event in sorted {
+        writeln!(file, "{}", event.as_json()).context("バックアップファイルの書き込みに失敗しました")?;
+    }
+    file.flush().context("バックアップファイルの書き込みに失敗しました")?;
+    Ok(())
+}
+
+/// JSONL ファイルの読み込み結果
+#[derive(Debug, Default)]
+pub struct BackupContents {
+    /// 署名を検証済みのイベント（ファイル内の順序、重複は除外）
+    pub events: Vec<Event>,
+    /// 読み込めなかった行
+    pub errors: Vec<BackupLineError>,
+}
+
+/// 読み込めなかった行の情報
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BackupLineError {
+    /// 行番号（1 始まり）
+    pub line: usize,
+    /// エラー内容
+    pub error: String,
+}
+
+/// JSONL ファイルを読み込み、各イベントの ID と署名を検証する
+pub fn read_events_jsonl(path: &Path) -> Result<BackupContents> {
+    let content = fs::read_to_string(path)
+        .context(format!("バックアップファイルの読み込みに失敗: {}", path.display()))?;
+    Ok(parse_events_jsonl(&content))
+}
+
+fn parse_events_jsonl(content: &str) -> BackupContents {
+    let mut contents = BackupContents::default();
+    let mut seen = std::collections::HashSet::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = match Event::from_json(line) {
+            Ok(event) => match event.verify() {
+                Ok(()) => {
+                    if seen.insert(event.id) {
+                        contents.events.push(event);
+                    }
+                    continue;
+                }
+                Err(e) => format!("署名の検証に失敗しました: {}", e),
+            },
+            Err(e) => format!("イベントのパースに失敗しました: {}", e),
+        };
+        contents.errors.push(BackupLineError { line: i + 1, error });
+    }
+    contents
+}
+
+/// Kind ごとのイベント数を集計
+pub fn count_by_kind(events: &[Event]) -> BTreeMap<u16, u64> {
+    let mut counts = BTreeMap::new();
+    for event in events {
+        *counts.entry(event.kind.as_u16()).or_default() += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_events_jsonl() {
+        let keys = Keys::generate();
+        let newer = EventBuilder::text_note("2 件目")
+            .custom_created_at(Timestamp::from(200))
+            .sign_with_keys(&keys)
+            .unwrap();
+        let older = EventBuilder::new(Kind::Metadata, "{}")
+            .custom_created_at(Timestamp::from(100))
+            .sign_with_keys(&keys)
+            .unwrap();
+
+        let path = std::env::temp_dir()
+            .join(format!("rust-nostr-mcp-backup-{}", std::process::id()))
+            .join("events.jsonl");
+        write_events_jsonl(&path, &[newer.clone(), older.clone()]).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let lines: Vec<Event> = content.lines().map(|l| Event::from_json(l).unwrap()).collect();
+        assert_eq!(lines, vec![older.clone(), newer.clone()]);
+
+        let counts = count_by_kind(&[newer, older]);
+        assert_eq!(counts.get(&0), Some(&1));
+        assert_eq!(counts.get(&1), Some(&1));
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_parse_events_jsonl() {
+        let keys = Keys::generate();
+        let valid = EventBuilder::text_note("本物").sign_with_keys(&keys).unwrap();
+        let mut tampered: serde_json::Value = serde_json::from_str(&valid.as_json()).unwrap();
+        tampered["content"] = serde_json::json!("改ざん");
+
+        let content = format!(
+            "{}\n\n{}\nnot json\n{}\n",
+            valid.as_json(),
+            tampered,
+            valid.as_json()
+        );
+        let contents = parse_events_jsonl(&content);
+
+        assert_eq!(contents.events, vec![valid]);
+        let lines: Vec<usize> = contents.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4]);
+    }
+}
@@ -0,0 +1,490 @@
+//! BOLT11 Lightning インボイスモジュール
+//!
+//! bolt11 インボイスをデコードし、金額・説明・有効期限・支払いハッシュなどを取り出します。
+//! Zap レシート（Kind 9735）の金額集計と検証、インボイスの確認に使用します。
+//! bech32 チェックサムに加えて受取ノードの署名を検証し、署名が不正なインボイスは拒否します。
+
+use anyhow::{anyhow, Result};
+use nostr_sdk::prelude::bitcoin::bech32::{primitives::decode::CheckedHrpstring, Checksum};
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, PublicKey, Secp256k1};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// タイムスタンプ部の長さ（5 ビット単位）
+const TIMESTAMP_WORDS: usize = 7;
+
+/// 末尾の署名部の長さ（5 ビット単位、520 ビット）
+const SIGNATURE_WORDS: usize = 104;
+
+/// 支払いハッシュタグ（`p`）の種別値
+const TAG_PAYMENT_HASH: u8 = 1;
+
+/// 説明タグ（`d`）の種別値
+const TAG_DESCRIPTION: u8 = 13;
+
+/// 受取ノードの公開鍵タグ（`n`）の種別値
+const TAG_PAYEE: u8 = 19;
+
+/// description_hash タグ（`h`）の種別値
+const TAG_DESCRIPTION_HASH: u8 = 23;
+
+/// 有効期間タグ（`x`）の種別値
+const TAG_EXPIRY: u8 = 6;
+
+/// 有効期間が指定されていない場合のデフォルト（秒）
+const DEFAULT_EXPIRY_SECS: u64 = 3600;
+
+/// 1 BTC あたりのミリサトシ
+const MSAT_PER_BTC: u64 = 100_000_000_000;
+
+/// デコードした bolt11 インボイス
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bolt11Invoice {
+    /// ネットワーク（bitcoin / testnet / signet / regtest、不明な通貨プレフィックスはそのまま）
+    pub network: String,
+    /// 金額（ミリサトシ）。金額指定のないインボイスは None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_msat: Option<u64>,
+    /// 作成日時の Unix タイムスタンプ
+    pub timestamp: u64,
+    /// 有効期間（秒）
+    pub expiry: u64,
+    /// 支払いハッシュ（hex）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_hash: Option<String>,
+    /// 説明（`d` タグ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 説明のハッシュ（`h` タグ、hex）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description_hash: Option<String>,
+    /// 受取ノードの公開鍵（`n` タグ、なければ署名から復元した公開鍵、hex）
+    pub payee: String,
+}
+
+impl Bolt11Invoice {
+    /// 金額（sats、1 sat 未満は切り捨て）
+    pub fn amount_sats(&self) -> Option<u64> {
+        self.amount_msat.map(|msat| msat / 1000)
+    }
+
+    /// 有効期限の Unix タイムスタンプ
+    pub fn expires_at(&self) -> u64 {
+        self.timestamp.saturating_add(self.expiry)
+    }
+
+    /// `now` の時点で期限切れか
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at()
+    }
+}
+
+/// bolt11 用の bech32 チェックサム
+///
+/// BIP-173 の bech32 と同じ生成多項式ですが、インボイスは 1023 文字を超えることがあるため
+/// 長さの上限を設けません。
+enum Bolt11Checksum {}
+
+impl Checksum for Bolt11Checksum {
+    type MidstateRepr = u32;
+    const CODE_LENGTH: usize = usize::MAX;
+    const CHECKSUM_LENGTH: usize = 6;
+    const GENERATOR_SH: [u32; 5] = [0x3b6a_57b2, 0x2650_8e6d, 0x1ea1_19fa, 0x3d42_33dd, 0x2a14_62b3];
+    const TARGET_RESIDUE: u32 = 1;
+}
+
+/// インボイスを bech32 デコードし、HRP と 5 ビット単位のデータ部を返す
+fn decode_words(invoice: &str) -> Result<(String, Vec<u8>)> {
+    let invoice = invoice.trim().to_lowercase();
+    let invoice = invoice.strip_prefix("lightning:").unwrap_or(&invoice);
+    let checked = CheckedHrpstring::new::<Bolt11Checksum>(invoice)
+        .map_err(|e| anyhow!("bolt11 のデコードに失敗: {}", e))?;
+    let hrp = checked.hrp().to_lowercase();
+    let words: Vec<u8> = checked.fe32_iter::<std::iter::Empty<u8>>().map(u8::from).collect();
+    if words.len() < TIMESTAMP_WORDS + SIGNATURE_WORDS {
+        return Err(anyhow!("bolt11 のデータ部が短すぎます"));
+    }
+    Ok((hrp, words))
+}
+
+/// 5 ビット単位の値をビッグエンディアンの整数として読む
+fn words_to_u64(words: &[u8]) -> u64 {
+    words.iter().fold(0, |acc, w| (acc << 5) | u64::from(*w))
+}
+
+/// 5 ビット単位の列をバイト列に変換する（端数ビットは 0 で埋める、署名対象の計算用）
+fn words_to_padded_bytes(words: &[u8]) -> Vec<u8> {
+    let len = (words.len() * 5).div_ceil(8);
+    let mut padded = words.to_vec();
+    padded.extend([0, 0]);
+    let mut bytes = words_to_bytes(&padded);
+    bytes.truncate(len);
+    bytes
+}
+
+/// 5 ビット単位の列をバイト列に変換する（端数ビットは捨てる）
+fn words_to_bytes(words: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(words.len() * 5 / 8);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for word in words {
+        acc = (acc << 5) | u32::from(*word);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    bytes
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// HRP の通貨プレフィックスをネットワーク名に変換する
+fn network_name(currency: &str) -> String {
+    match currency {
+        "bc" => "bitcoin",
+        "tb" => "testnet",
+        "tbs" => "signet",
+        "bcrt" => "regtest",
+        other => other,
+    }
+    .to_string()
+}
+
+/// HRP（`ln` + 通貨 + 金額）を通貨プレフィックスと金額（ミリサトシ）に分解する
+///
+/// 金額は 10 進数と任意の乗数（m / u / n / p）からなります。`p` はミリサトシ未満を
+/// 表せないため、末尾が 0 でなければエラーです。
+fn parse_hrp(hrp: &str) -> Result<(String, Option<u64>)> {
+    let rest = hrp.strip_prefix("ln")
+        .ok_or_else(|| anyhow!("Lightning インボイスではありません: {}", hrp))?;
+    let split = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+    let (currency, amount) = rest.split_at(split);
+    if currency.is_empty() {
+        return Err(anyhow!("通貨プレフィックスがありません: {}", hrp));
+    }
+    if amount.is_empty() {
+        return Ok((currency.to_string(), None));
+    }
+
+    let (digits, multiplier) = match amount.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&amount[..i], Some(c)),
+        _ => (amount, None),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) || digits.starts_with('0') {
+        return Err(anyhow!("金額の形式が不正です: {}", amount));
+    }
+    let value: u64 = digits.parse().map_err(|_| anyhow!("金額が大きすぎます: {}", amount))?;
+
+    let msat = match multiplier {
+        None => value.checked_mul(MSAT_PER_BTC),
+        Some('m') => value.checked_mul(MSAT_PER_BTC / 1_000),
+        Some('u') => value.checked_mul(MSAT_PER_BTC / 1_000_000),
+        Some('n') => value.checked_mul(MSAT_PER_BTC / 1_000_000_000),
+        Some('p') if value.is_multiple_of(10) => Some(value / 10),
+        Some('p') => return Err(anyhow!("pico 単位の金額はミリサトシ未満を表せません: {}", amount)),
+        Some(c) => return Err(anyhow!("不明な金額の乗数です: {}", c)),
+    }
+    .ok_or_else(|| anyhow!("金額が大きすぎます: {}", amount))?;
+
+    Ok((currency.to_string(), Some(msat)))
+}
+
+/// タグ付きフィールドを（種別, データ）の組で列挙する
+fn tagged_fields(words: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut fields = Vec::new();
+    let mut rest = &words[TIMESTAMP_WORDS..words.len() - SIGNATURE_WORDS];
+    while rest.len() >= 3 {
+        let tag = rest[0];
+        let len = words_to_u64(&rest[1..3]) as usize;
+        let Some(data) = rest.get(3..3 + len) else { break };
+        fields.push((tag, data));
+        rest = &rest[3 + len..];
+    }
+    fields
+}
+
+/// インボイスの署名を検証し、受取ノードの公開鍵を返す
+///
+/// 署名対象は HRP のバイト列と署名を除くデータ部（0 埋めしたバイト列）の SHA-256 です。
+/// `n` フィールドがある場合はその公開鍵で検証し、ない場合は署名から公開鍵を復元します。
+fn verify_signature(hrp: &str, words: &[u8], payee: Option<&[u8]>) -> Result<PublicKey> {
+    let (data, signature) = words.split_at(words.len() - SIGNATURE_WORDS);
+    let signature = words_to_bytes(signature);
+    let mut preimage = hrp.as_bytes().to_vec();
+    preimage.extend(words_to_padded_bytes(data));
+    let message = Message::from_digest(Sha256::digest(&preimage).into());
+
+    let recovery_id = RecoveryId::from_i32(i32::from(signature[64]))
+        .map_err(|_| anyhow!("bolt11 の署名のリカバリー ID が不正です"))?;
+    let signature = RecoverableSignature::from_compact(&signature[..64], recovery_id)
+        .map_err(|e| anyhow!("bolt11 の署名が不正です: {}", e))?;
+    let secp = Secp256k1::verification_only();
+
+    match payee {
+        Some(payee) => {
+            let payee = PublicKey::from_slice(payee)
+                .map_err(|e| anyhow!("bolt11 の受取ノードの公開鍵が不正です: {}", e))?;
+            secp.verify_ecdsa(&message, &signature.to_standard(), &payee)
+                .map_err(|_| anyhow!("bolt11 の署名が受取ノードの公開鍵と一致しません"))?;
+            Ok(payee)
+        }
+        None => secp.recover_ecdsa(&message, &signature)
+            .map_err(|e| anyhow!("bolt11 の署名から公開鍵を復元できません: {}", e)),
+    }
+}
+
+/// bolt11 インボイスをデコードし、受取ノードの署名を検証する
+///
+/// 長さが仕様と異なる `p` / `h` / `n` フィールドと未対応のフィールドは無視します。
+/// 同じフィールドが複数ある場合は最初のものを使います。
+pub fn decode(invoice: &str) -> Result<Bolt11Invoice> {
+    let (hrp, words) = decode_words(invoice)?;
+    let (currency, amount_msat) = parse_hrp(&hrp)?;
+
+    let payee_field = tagged_fields(&words).into_iter()
+        .find(|(tag, data)| *tag == TAG_PAYEE && data.len() == 53)
+        .map(|(_, data)| words_to_bytes(data));
+    let payee = verify_signature(&hrp, &words, payee_field.as_deref())?;
+
+    let mut decoded = Bolt11Invoice {
+        network: network_name(&currency),
+        amount_msat,
+        timestamp: words_to_u64(&words[..TIMESTAMP_WORDS]),
+        expiry: DEFAULT_EXPIRY_SECS,
+        payment_hash: None,
+        description: None,
+        description_hash: None,
+        payee: hex(&payee.serialize()),
+    };
+    let mut expiry = None;
+
+    for (tag, data) in tagged_fields(&words) {
+        match tag {
+            TAG_PAYMENT_HASH if data.len() == 52 => {
+                decoded.payment_hash = decoded.payment_hash.or_else(|| Some(hex(&words_to_bytes(data))));
+            }
+            TAG_DESCRIPTION_HASH if data.len() == 52 => {
+                decoded.description_hash = decoded.description_hash.or_else(|| Some(hex(&words_to_bytes(data))));
+            }
+            TAG_DESCRIPTION => {
+                decoded.description = decoded.description
+                    .or_else(|| Some(String::from_utf8_lossy(&words_to_bytes(data)).into_owned()));
+            }
+            TAG_EXPIRY if data.len() <= 12 => {
+                expiry = expiry.or(Some(words_to_u64(data)));
+            }
+            _ => {}
+        }
+    }
+    if let Some(expiry) = expiry {
+        decoded.expiry = expiry;
+    }
+
+    Ok(decoded)
+}
+
+/// インボイスの金額（sats）。デコードできない・金額指定がない場合は 0
+pub fn amount_sats(invoice: &str) -> u64 {
+    decode(invoice).ok().and_then(|i| i.amount_sats()).unwrap_or(0)
+}
+
+/// `description` の SHA-256 がインボイスの description_hash と一致するか
+///
+/// NIP-57 の Zap レシートでは、description タグ（Zap リクエストの JSON）の
+/// ハッシュが bolt11 に含まれている必要があります。
+pub fn description_hash_matches(invoice: &str, description: &str) -> bool {
+    match decode(invoice) {
+        Ok(Bolt11Invoice { description_hash: Some(hash), .. }) => {
+            hash == hex(&Sha256::digest(description.as_bytes()))
+        }
+        _ => false,
+    }
+}
+
+/// テスト用に署名済みの bolt11 インボイスを作成する（作成日時は 1_700_000_000）
+///
+/// `fields` は（種別, 5 ビット単位のデータ）の組です。
+#[cfg(test)]
+pub(crate) fn signed_test_invoice(hrp: &str, fields: &[(u8, Vec<u8>)], key: &secp256k1::SecretKey) -> String {
+    use nostr_sdk::prelude::bitcoin::bech32::{Fe32, Fe32IterExt, Hrp};
+
+    let mut words = u64_to_words(1_700_000_000, TIMESTAMP_WORDS);
+    for (tag, data) in fields {
+        words.push(*tag);
+        words.extend(u64_to_words(data.len() as u64, 2));
+        words.extend(data);
+    }
+    let mut preimage = hrp.as_bytes().to_vec();
+    preimage.extend(words_to_padded_bytes(&words));
+    let message = Message::from_digest(Sha256::digest(&preimage).into());
+    let (recovery_id, signature) = Secp256k1::signing_only()
+        .sign_ecdsa_recoverable(&message, key)
+        .serialize_compact();
+    let mut signature = signature.to_vec();
+    signature.push(recovery_id.to_i32() as u8);
+    words.extend(bytes_to_words(&signature));
+
+    let hrp = Hrp::parse(hrp).unwrap();
+    words.into_iter()
+        .map(|w| Fe32::try_from(w).unwrap())
+        .with_checksum::<Bolt11Checksum>(&hrp)
+        .chars()
+        .collect()
+}
+
+/// テスト用の署名済みインボイス（支払いハッシュ 0x00..0x1f、`description` があればその description_hash）
+#[cfg(test)]
+pub(crate) fn test_invoice(hrp: &str, description: Option<&str>) -> String {
+    let payment_hash: Vec<u8> = (0..32).collect();
+    let mut fields = vec![(TAG_PAYMENT_HASH, bytes_to_words(&payment_hash))];
+    if let Some(description) = description {
+        fields.push((TAG_DESCRIPTION_HASH, bytes_to_words(&Sha256::digest(description.as_bytes()))));
+    }
+    signed_test_invoice(hrp, &fields, &secp256k1::SecretKey::from_slice(&[1; 32]).unwrap())
+}
+
+/// バイト列を 5 ビット単位の列に変換する（端数ビットは 0 で埋める）
+#[cfg(test)]
+fn bytes_to_words(bytes: &[u8]) -> Vec<u8> {
+    let mut words = Vec::with_capacity((bytes.len() * 8).div_ceil(5));
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for byte in bytes {
+        acc = (acc << 8) | u32::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            words.push(((acc >> bits) & 31) as u8);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        words.push(((acc << (5 - bits)) & 31) as u8);
+    }
+    words
+}
+
+/// 整数を `len` 個の 5 ビット単位の列（ビッグエンディアン）に変換する
+#[cfg(test)]
+fn u64_to_words(value: u64, len: usize) -> Vec<u8> {
+    (0..len).rev().map(|i| ((value >> (5 * i)) & 31) as u8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_DESCRIPTION: &str = r#"{"kind":9734,"content":"","tags":[]}"#;
+
+    fn key(byte: u8) -> secp256k1::SecretKey {
+        secp256k1::SecretKey::from_slice(&[byte; 32]).unwrap()
+    }
+
+    /// 金額 10u、description_hash = sha256(TEST_DESCRIPTION)、支払いハッシュ = 0x00..0x1f、有効期間 3600 秒
+    fn test_invoice_with_expiry() -> String {
+        let payment_hash: Vec<u8> = (0..32).collect();
+        signed_test_invoice("lnbc10u", &[
+            (TAG_PAYMENT_HASH, bytes_to_words(&payment_hash)),
+            (TAG_DESCRIPTION_HASH, bytes_to_words(&Sha256::digest(TEST_DESCRIPTION.as_bytes()))),
+            (TAG_EXPIRY, u64_to_words(3600, 3)),
+        ], &key(1))
+    }
+
+    /// 金額指定なし、説明 "coffee"、有効期間の指定なし
+    fn test_invoice_no_amount() -> String {
+        signed_test_invoice("lnbc", &[(TAG_DESCRIPTION, bytes_to_words(b"coffee"))], &key(1))
+    }
+
+    #[test]
+    fn test_description_hash() {
+        let invoice = test_invoice_with_expiry();
+        let hash = decode(&invoice).unwrap().description_hash.unwrap();
+        assert!(hash.starts_with("04b335a2"));
+        assert!(description_hash_matches(&invoice, TEST_DESCRIPTION));
+        assert!(description_hash_matches(&invoice.to_uppercase(), TEST_DESCRIPTION));
+        assert!(!description_hash_matches(&invoice, r#"{"kind":9734}"#));
+        assert!(!description_hash_matches(&test_invoice_no_amount(), TEST_DESCRIPTION));
+        assert!(description_hash_matches(&test_invoice("lnbc10u", Some(TEST_DESCRIPTION)), TEST_DESCRIPTION));
+
+        // チェックサムが壊れたインボイスは拒否する
+        let mut broken = invoice.clone();
+        let last = broken.pop().unwrap();
+        broken.push(if last == 'q' { 'p' } else { 'q' });
+        assert!(decode(&broken).is_err());
+        assert!(decode("not an invoice").is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        let invoice = decode(&test_invoice_with_expiry()).unwrap();
+        assert_eq!(invoice.network, "bitcoin");
+        assert_eq!(invoice.amount_msat, Some(1_000_000));
+        assert_eq!(invoice.amount_sats(), Some(1_000));
+        assert_eq!(invoice.timestamp, 1_700_000_000);
+        assert_eq!(invoice.expiry, 3600);
+        assert_eq!(invoice.payment_hash.as_deref(), Some("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"));
+        assert!(invoice.description.is_none());
+        assert!(invoice.is_expired(1_700_003_600));
+        assert!(!invoice.is_expired(1_700_003_599));
+
+        // 区切り文字の 1 を金額として読まない
+        let no_amount = test_invoice_no_amount();
+        let invoice = decode(&no_amount).unwrap();
+        assert_eq!(invoice.amount_msat, None);
+        assert_eq!(amount_sats(&no_amount), 0);
+        assert_eq!(invoice.description.as_deref(), Some("coffee"));
+        assert_eq!(invoice.expiry, DEFAULT_EXPIRY_SECS);
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let secp = Secp256k1::new();
+        let payee = key(1).public_key(&secp);
+
+        // n フィールドがなければ署名から受取ノードの公開鍵を復元する
+        let invoice = decode(&test_invoice_no_amount()).unwrap();
+        assert_eq!(invoice.payee, hex(&payee.serialize()));
+
+        // n フィールドがあればその公開鍵で検証する
+        let with_payee = |signer: u8| signed_test_invoice(
+            "lnbc10u",
+            &[(TAG_PAYEE, bytes_to_words(&payee.serialize()))],
+            &key(signer),
+        );
+        assert_eq!(decode(&with_payee(1)).unwrap().payee, hex(&payee.serialize()));
+        assert!(decode(&with_payee(2)).is_err());
+
+        // 署名後に金額を書き換えたインボイスは、チェックサムが正しくても拒否する
+        let (hrp, words) = decode_words(&test_invoice_with_expiry()).unwrap();
+        assert!(verify_signature(&hrp, &words, None).is_ok());
+        let tampered = verify_signature("lnbc20u", &words, Some(&payee.serialize())).unwrap_err();
+        assert!(tampered.to_string().contains("一致しません"));
+    }
+
+    #[test]
+    fn test_parse_hrp_amounts() {
+        assert_eq!(parse_hrp("lnbc").unwrap(), ("bc".to_string(), None));
+        assert_eq!(parse_hrp("lnbc2").unwrap().1, Some(200_000_000_000));
+        assert_eq!(parse_hrp("lnbc25m").unwrap().1, Some(2_500_000_000));
+        assert_eq!(parse_hrp("lnbc2500u").unwrap().1, Some(250_000_000));
+        // 1 sat 未満の端数もミリサトシで保持する
+        assert_eq!(parse_hrp("lnbc15n").unwrap().1, Some(1_500));
+        assert_eq!(parse_hrp("lnbc10p").unwrap().1, Some(1));
+        assert_eq!(parse_hrp("lntb20m").unwrap(), ("tb".to_string(), Some(2_000_000_000)));
+        assert_eq!(parse_hrp("lnbcrt1u").unwrap(), ("bcrt".to_string(), Some(100_000)));
+
+        // p 単位の端数・ゼロ・先頭の 0・不明な乗数はエラー
+        assert!(parse_hrp("lnbc11p").is_err());
+        assert!(parse_hrp("lnbc0").is_err());
+        assert!(parse_hrp("lnbc01u").is_err());
+        assert!(parse_hrp("lnbc10x").is_err());
+        assert!(parse_hrp("lnbc99999999999999999").is_err());
+        assert!(parse_hrp("bc10u").is_err());
+    }
+}
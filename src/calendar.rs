@@ -0,0 +1,349 @@
+//! カレンダーイベントモジュール (NIP-52)
+//!
+//! 日付ベース (Kind 31922)・時刻ベース (Kind 31923) のカレンダーイベントの作成・取得と、
+//! 出欠の返信 (Kind 31925) を扱います。
+
+use anyhow::{anyhow, Context, Result};
+use nostr_sdk::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::info;
+
+use crate::nostr_client::{current_unix_timestamp, extract_tag_value, slug_from_title, AuthorInfo, NostrClient, PublishResult};
+
+/// カレンダーイベントの取得時に遡る件数の上限
+const MAX_CALENDAR_EVENTS: usize = 2000;
+
+/// カレンダーイベント作成のパラメータ（NIP-52）
+#[derive(Debug, Clone)]
+pub struct CalendarEventParams {
+    /// タイトル
+    pub title: String,
+    /// 開始（YYYY-MM-DD、Unix タイムスタンプ、または RFC 3339 日時）
+    pub start: String,
+    /// 終了（start と同じ形式、任意）
+    pub end: Option<String>,
+    /// 説明
+    pub description: Option<String>,
+    /// 場所
+    pub location: Option<String>,
+    /// タイムゾーン（IANA 形式、時刻ベースのみ）
+    pub timezone: Option<String>,
+    /// ハッシュタグ
+    pub hashtags: Option<Vec<String>>,
+    /// 識別子（d タグ、未指定時は自動生成）
+    pub identifier: Option<String>,
+}
+
+/// カレンダーイベント情報（NIP-52）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CalendarEventInfo {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// naddr 形式のアドレス（RSVP に使用）
+    pub naddr: String,
+    /// 種類（"date": 日付ベース Kind 31922、"time": 時刻ベース Kind 31923）
+    pub event_type: String,
+    /// タイトル
+    pub title: String,
+    /// 説明
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 開始（タグの値そのまま）
+    pub start: String,
+    /// 終了（タグの値そのまま）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    /// 開始日時の Unix タイムスタンプ
+    pub start_timestamp: u64,
+    /// 終了日時の Unix タイムスタンプ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_timestamp: Option<u64>,
+    /// タイムゾーン
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// 場所
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// ハッシュタグ
+    pub hashtags: Vec<String>,
+    /// 作成者の情報
+    pub author: AuthorInfo,
+}
+
+/// カレンダーイベントの日時（NIP-52）
+#[derive(Debug, Clone, PartialEq)]
+enum CalendarTime {
+    /// 日付（YYYY-MM-DD）
+    Date(String),
+    /// Unix タイムスタンプ
+    Time(u64),
+}
+
+impl CalendarTime {
+    /// 比較用の Unix タイムスタンプ（日付は UTC 0 時）
+    fn timestamp(&self) -> u64 {
+        match self {
+            CalendarTime::Date(d) => chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc().timestamp().max(0) as u64)
+                .unwrap_or(0),
+            CalendarTime::Time(ts) => *ts,
+        }
+    }
+}
+
+impl NostrClient {
+    /// カレンダーイベントを作成します（NIP-52）。
+    ///
+    /// `start` が `YYYY-MM-DD` 形式の場合は日付ベース (Kind 31922)、
+    /// Unix タイムスタンプまたは RFC 3339 日時の場合は時刻ベース (Kind 31923) になります。
+    pub async fn create_calendar_event(&self, params: CalendarEventParams) -> Result<(CalendarEventInfo, PublishResult)> {
+        self.require_write_access()?;
+
+        let start = parse_calendar_time(&params.start)?;
+        let end = params.end.as_deref().map(parse_calendar_time).transpose()?;
+
+        let (kind, start_value, end_value) = match (&start, &end) {
+            (CalendarTime::Date(s), None) => (Kind::from(31922), s.clone(), None),
+            (CalendarTime::Date(s), Some(CalendarTime::Date(e))) => (Kind::from(31922), s.clone(), Some(e.clone())),
+            (CalendarTime::Time(s), None) => (Kind::from(31923), s.to_string(), None),
+            (CalendarTime::Time(s), Some(CalendarTime::Time(e))) => (Kind::from(31923), s.to_string(), Some(e.to_string())),
+            _ => return Err(anyhow!("start と end は同じ形式（日付または日時）で指定してください")),
+        };
+
+        if start.timestamp() > end.as_ref().map_or(u64::MAX, |e| e.timestamp()) {
+            return Err(anyhow!("end は start より後の日時を指定してください"));
+        }
+
+        let d_tag = params.identifier.clone()
+            .unwrap_or_else(|| format!("{}-{}", slug_from_title(&params.title), current_unix_timestamp()));
+
+        let mut tags = vec![
+            Tag::identifier(d_tag.clone()),
+            Tag::custom(TagKind::Title, vec![params.title.clone()]),
+            Tag::custom(TagKind::custom("start".to_string()), vec![start_value]),
+        ];
+        if let Some(e) = end_value {
+            tags.push(Tag::custom(TagKind::custom("end".to_string()), vec![e]));
+        }
+        if kind == Kind::from(31923) {
+            if let Some(ref tz) = params.timezone {
+                tags.push(Tag::custom(TagKind::custom("start_tzid".to_string()), vec![tz.clone()]));
+            }
+        }
+        if let Some(ref location) = params.location {
+            tags.push(Tag::custom(TagKind::custom("location".to_string()), vec![location.clone()]));
+        }
+        for t in params.hashtags.iter().flatten() {
+            tags.push(Tag::hashtag(t.clone()));
+        }
+
+        let builder = EventBuilder::new(kind, params.description.clone().unwrap_or_default()).tags(tags);
+
+        let output = self.send_event_builder(builder).await
+            .context("カレンダーイベントの作成に失敗しました")?;
+
+        let event_id = *output.id();
+        info!("カレンダーイベントを作成しました: {}", event_id);
+
+        let pk = self.public_key.ok_or_else(|| anyhow!("公開鍵が取得できません"))?;
+        let calendar_event = CalendarEventInfo {
+            id: event_id.to_hex(),
+            naddr: Coordinate::new(kind, pk).identifier(&d_tag).to_bech32().unwrap_or_default(),
+            event_type: if kind == Kind::from(31922) { "date" } else { "time" }.to_string(),
+            title: params.title,
+            description: params.description,
+            start: params.start,
+            end: params.end,
+            start_timestamp: start.timestamp(),
+            end_timestamp: end.map(|e| e.timestamp()),
+            timezone: params.timezone,
+            location: params.location,
+            hashtags: params.hashtags.unwrap_or_default(),
+            author: AuthorInfo::from_public_key(&pk),
+        };
+        Ok((calendar_event, PublishResult::new(output, self.public_key)))
+    }
+
+    /// 今後のカレンダーイベント (Kind 31922/31923) を取得します。
+    ///
+    /// `follows_only` が true で認証済みの場合はフォロー中ユーザー（と自分）のイベントに限定します。
+    /// リレーは作成日時の新しい順にしか返さないため、最大 `MAX_CALENDAR_EVENTS` 件までページングして
+    /// 取得し、start タグで絞り込んで開始日時順に並べます。上限に達した場合（それより前に作成された
+    /// イベントを見ていない場合）は、戻り値の 2 番目が true になります。
+    pub async fn get_calendar_events(&self, follows_only: bool, days_ahead: u64, limit: u64) -> Result<(Vec<CalendarEventInfo>, bool)> {
+        let mut filter = Filter::new()
+            .kinds(vec![Kind::from(31922), Kind::from(31923)]);
+
+        if follows_only {
+            if let Some(pk) = self.public_key {
+                let mut authors = self.fetch_followed_pubkeys(pk).await;
+                authors.push(pk);
+                filter = filter.authors(authors);
+            }
+        }
+
+        let events = self.paginate_pool_events(filter, MAX_CALENDAR_EVENTS, Duration::from_secs(15))
+            .await
+            .context("カレンダーイベントの取得に失敗しました")?;
+        let truncated = events.len() >= MAX_CALENDAR_EVENTS;
+
+        // 同じアドレスの古いバージョンは除き、最新のものだけを使う
+        let mut latest: HashMap<(Kind, PublicKey, String), Event> = HashMap::new();
+        for event in events {
+            let key = (event.kind, event.pubkey, extract_tag_value(&event, "d").unwrap_or_default());
+            match latest.get(&key) {
+                Some(existing) if existing.created_at >= event.created_at => {}
+                _ => {
+                    latest.insert(key, event);
+                }
+            }
+        }
+        let events_vec: Vec<Event> = latest.into_values().collect();
+        let pubkeys = Self::collect_pubkeys(&events_vec);
+        let profiles = self.fetch_profiles(&pubkeys).await;
+
+        let now = current_unix_timestamp();
+        let horizon = now.saturating_add(days_ahead.saturating_mul(86_400));
+        // 日付ベースのイベントは当日中は「今後」として扱う
+        let today_start = now - now % 86_400;
+
+        let mut calendar: Vec<CalendarEventInfo> = events_vec.iter()
+            .filter_map(|event| Self::event_to_calendar_event(event, &profiles))
+            .filter(|info| {
+                let last = info.end_timestamp.unwrap_or(info.start_timestamp);
+                let threshold = if info.event_type == "date" { today_start } else { now };
+                last >= threshold && info.start_timestamp <= horizon
+            })
+            .collect();
+
+        calendar.sort_by(|a, b| a.start_timestamp.cmp(&b.start_timestamp).then_with(|| a.id.cmp(&b.id)));
+        calendar.truncate(limit as usize);
+
+        Ok((calendar, truncated))
+    }
+
+    /// カレンダーイベントに RSVP します（Kind 31925, NIP-52）。
+    pub async fn rsvp_calendar_event(&self, event_addr: &str, status: &str, free_busy: Option<&str>, note: Option<&str>) -> Result<PublishResult> {
+        self.require_write_access()?;
+
+        if !["accepted", "declined", "tentative"].contains(&status) {
+            return Err(anyhow!("無効な RSVP ステータスです: {}（accepted, declined, tentative）", status));
+        }
+
+        let coordinate = Self::parse_coordinate(event_addr)?;
+        if coordinate.kind != Kind::from(31922) && coordinate.kind != Kind::from(31923) {
+            return Err(anyhow!("カレンダーイベント (Kind 31922/31923) のアドレスを指定してください"));
+        }
+
+        let event = self.fetch_event_by_coordinate(&coordinate, "カレンダーイベント").await?;
+        let a_value = format!("{}:{}:{}", coordinate.kind.as_u16(), coordinate.public_key.to_hex(), coordinate.identifier);
+
+        let mut tags = vec![
+            // 同じイベントへの RSVP は置き換えられるよう、座標を d タグに使用
+            Tag::identifier(a_value.clone()),
+            Tag::custom(TagKind::custom("a".to_string()), vec![a_value]),
+            Tag::event(event.id),
+            Tag::custom(TagKind::custom("status".to_string()), vec![status.to_string()]),
+            Tag::public_key(event.pubkey),
+        ];
+
+        // NIP-52: declined の場合は fb タグを省略
+        if status != "declined" {
+            let fb = free_busy.unwrap_or("busy");
+            if fb != "free" && fb != "busy" {
+                return Err(anyhow!("free_busy は free または busy を指定してください"));
+            }
+            tags.push(Tag::custom(TagKind::custom("fb".to_string()), vec![fb.to_string()]));
+        }
+
+        let builder = EventBuilder::new(Kind::from(31925), note.unwrap_or_default()).tags(tags);
+
+        let output = self.send_event_builder(builder).await
+            .context("RSVP の送信に失敗しました")?;
+
+        let rsvp_id = *output.id();
+        info!("RSVP を送信しました ({}): {}", status, rsvp_id);
+        Ok(PublishResult::new(output, self.public_key))
+    }
+
+    /// カレンダーイベントから表示用情報に変換するヘルパー（start が不正な場合は None）
+    fn event_to_calendar_event(event: &Event, profiles: &HashMap<PublicKey, AuthorInfo>) -> Option<CalendarEventInfo> {
+        let start = extract_tag_value(event, "start")?;
+        let end = extract_tag_value(event, "end");
+        let start_timestamp = parse_calendar_time(&start).ok()?.timestamp();
+        let end_timestamp = end.as_deref()
+            .and_then(|e| parse_calendar_time(e).ok())
+            .map(|e| e.timestamp());
+
+        let identifier = extract_tag_value(event, "d").unwrap_or_default();
+        let title = extract_tag_value(event, "title")
+            .or_else(|| extract_tag_value(event, "name"))
+            .unwrap_or_else(|| "無題".to_string());
+
+        let hashtags: Vec<String> = event.tags.iter()
+            .filter_map(|tag| {
+                if let Some(TagStandard::Hashtag(h)) = tag.as_standardized() {
+                    Some(h.clone())
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Some(CalendarEventInfo {
+            id: event.id.to_hex(),
+            naddr: Coordinate::new(event.kind, event.pubkey)
+                .identifier(&identifier)
+                .to_bech32()
+                .unwrap_or_default(),
+            event_type: if event.kind == Kind::from(31922) { "date" } else { "time" }.to_string(),
+            title,
+            description: Some(event.content.clone()).filter(|c| !c.is_empty()),
+            start,
+            end,
+            start_timestamp,
+            end_timestamp,
+            timezone: extract_tag_value(event, "start_tzid"),
+            location: extract_tag_value(event, "location"),
+            hashtags,
+            author: profiles
+                .get(&event.pubkey)
+                .cloned()
+                .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey)),
+        })
+    }
+}
+
+/// カレンダーの日時文字列をパース（YYYY-MM-DD、Unix タイムスタンプ、RFC 3339 日時）
+fn parse_calendar_time(value: &str) -> Result<CalendarTime> {
+    let value = value.trim();
+    if chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() {
+        return Ok(CalendarTime::Date(value.to_string()));
+    }
+    if let Ok(ts) = value.parse::<u64>() {
+        return Ok(CalendarTime::Time(ts));
+    }
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| CalendarTime::Time(dt.timestamp().max(0) as u64))
+        .map_err(|_| anyhow!("無効な日時です: {}（YYYY-MM-DD、Unix タイムスタンプ、RFC 3339 形式）", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_calendar_time() {
+        assert_eq!(parse_calendar_time("2026-05-01").unwrap(), CalendarTime::Date("2026-05-01".to_string()));
+        assert_eq!(parse_calendar_time("1700000000").unwrap(), CalendarTime::Time(1_700_000_000));
+        assert_eq!(
+            parse_calendar_time("2023-11-14T22:13:20Z").unwrap(),
+            CalendarTime::Time(1_700_000_000)
+        );
+        assert!(parse_calendar_time("next tuesday").is_err());
+        assert_eq!(CalendarTime::Date("1970-01-02".to_string()).timestamp(), 86_400);
+    }
+}
@@ -8,8 +8,18 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 use tracing::{info, warn};
 
+/// 1 分あたりのリクエスト数のデフォルト
+const DEFAULT_REQUESTS_PER_MINUTE: u32 = 120;
+
+/// バースト（連続して送信できるリクエスト数）のデフォルト
+const DEFAULT_BURST: u32 = 60;
+
+/// 自分のコンタクトリストのキャッシュ有効期間のデフォルト（秒）
+const DEFAULT_CONTACT_LIST_CACHE_SECS: u64 = 300;
+
 /// algia 規則に準拠したリレー設定
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RelayConfig {
@@ -32,10 +42,11 @@ impl Default for RelayConfig {
 }
 
 /// 認証モード
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum AuthMode {
     /// 従来のローカル秘密鍵（デフォルト）
+    #[default]
     Local,
     /// NIP-46 Nostr Connect リモートサイニング
     Nip46,
@@ -43,12 +54,165 @@ pub enum AuthMode {
     Bunker,
 }
 
-impl Default for AuthMode {
+/// 支払いの上限設定（`send_zap`・`pay_lightning_address` 共通のガードレール）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PaymentLimits {
+    /// 1 回の支払いの上限（sats）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "max-per-payment-sats")]
+    pub max_per_payment_sats: Option<u64>,
+    /// 直近 24 時間の支払い合計の上限（sats）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "daily-limit-sats")]
+    pub daily_limit_sats: Option<u64>,
+}
+
+/// リアクションのプリセット（`react_to_note` の `reaction` に名前で指定）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReactionPreset {
+    /// リアクションの内容（`+`、絵文字、または `:shortcode:` 形式のカスタム絵文字）
+    pub content: String,
+    /// カスタム絵文字の画像 URL（NIP-30、省略時は自分の絵文字リスト・絵文字セットから解決）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "emoji-url")]
+    pub emoji_url: Option<String>,
+    /// 直近 1 時間にこのプリセットで送信できるリアクションの上限（省略時は無制限）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "max-per-hour")]
+    pub max_per_hour: Option<u32>,
+}
+
+/// Web of Trust の適用方法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustMode {
+    /// 信頼範囲外のアカウントのイベントを除外
+    #[default]
+    Drop,
+    /// 信頼範囲外のアカウントのイベントを後ろに並べ替え
+    Rank,
+}
+
+impl TrustMode {
+    /// 文字列からパース（"drop" / "rank"）
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "drop" => Some(Self::Drop),
+            "rank" => Some(Self::Rank),
+            _ => None,
+        }
+    }
+}
+
+/// 取得したイベントの署名検証の方法（設定ファイルの `verify-signatures`）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureVerification {
+    /// 検証しない
+    #[default]
+    Off,
+    /// 検証結果をノートの `verified` に付与（不正なイベントも返す）
+    Annotate,
+    /// 署名が不正なイベントを除外
+    Drop,
+}
+
+/// コンテンツ警告（NIP-36）や NSFW ラベル付きのノートの扱い（設定ファイルの `sensitive-content`）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SensitiveContentPolicy {
+    /// そのまま返す（警告の理由は `content_warning` に付与）
+    #[default]
+    Show,
+    /// 本文をプレースホルダーに置き換えて返す（`reveal_note` で本文を取得）
+    Flag,
+    /// 結果から除外
+    Hide,
+}
+
+/// Web of Trust フィルタ設定（フォローグラフ上の距離による信頼範囲）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WebOfTrust {
+    /// 信頼範囲とするホップ数（1: フォロー中、2: フォローのフォローまで）
+    #[serde(rename = "max-hops", default = "default_trust_hops")]
+    pub max_hops: u8,
+    /// 適用方法
+    #[serde(default)]
+    pub mode: TrustMode,
+}
+
+fn default_trust_hops() -> u8 {
+    2
+}
+
+/// コンテンツフィルタ（設定ファイルの `filters`、ノートをモデルに渡す前に除外）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContentFilters {
+    /// 本文に含まれるノートを除外する語句（大文字小文字を区別しない）
+    #[serde(default, rename = "muted-words", skip_serializing_if = "Vec::is_empty")]
+    pub muted_words: Vec<String>,
+    /// 本文が一致するノートを除外する正規表現
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regexes: Vec<String>,
+    /// 作成からこの日数に満たないアカウントのノートを除外（0 で無効）
+    #[serde(default, rename = "min-account-age-days", skip_serializing_if = "Option::is_none")]
+    pub min_account_age_days: Option<u64>,
+}
+
+/// NIP-26 委任署名の設定（設定ファイルの `delegation`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationConfig {
+    /// 委任者が発行した delegation タグ（JSON 配列 `["delegation", "<委任者 hex>", "<条件>", "<署名>"]`）
+    pub token: String,
+    /// 委任先（delegatee）の秘密鍵（nsec または hex）
+    #[serde(rename = "delegatee-key")]
+    pub delegatee_key: String,
+}
+
+/// ノート一覧を返すツールの出力量の設定（設定ファイルの `default-limit` / `content-max-chars`）
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OutputOptions {
+    /// `limit` 省略時の取得件数（None の場合は 20）
+    pub default_limit: Option<u64>,
+    /// ノート本文をこの文字数で切り詰める（None の場合は切り詰めない）
+    pub content_max_chars: Option<usize>,
+}
+
+/// 1 つのリレーに対するレート制限
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// 1 分あたりのリクエスト数（0 で無制限）
+    #[serde(rename = "requests-per-minute")]
+    pub requests_per_minute: u32,
+    /// 連続して送信できるリクエスト数
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+}
+
+fn default_burst() -> u32 {
+    DEFAULT_BURST
+}
+
+impl Default for RateLimit {
     fn default() -> Self {
-        Self::Local
+        Self {
+            requests_per_minute: DEFAULT_REQUESTS_PER_MINUTE,
+            burst: DEFAULT_BURST,
+        }
     }
 }
 
+/// レート制限の設定（設定ファイルの `rate-limit`）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// すべてのリレーに適用するデフォルトの制限
+    #[serde(flatten)]
+    pub default: RateLimit,
+    /// リレー URL ごとの制限（デフォルトを上書き）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub relays: HashMap<String, RateLimit>,
+}
+
 /// algia 規則に準拠したメイン設定構造体
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -57,6 +221,9 @@ pub struct Config {
     /// nsec 形式の秘密鍵（ローカルに保存、AI エージェントには渡されない）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub privatekey: Option<String>,
+    /// npub または hex 形式の公開鍵（秘密鍵なしでも自分のタイムライン・通知を読むための読み取り専用設定）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publickey: Option<String>,
     /// Nostr Wallet Connect URI（任意）
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "nwc-uri")]
@@ -81,6 +248,63 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "blossom-servers")]
     pub blossom_servers: Option<Vec<String>>,
+    /// 支払いの上限設定（Zap・Lightning 支払い用）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "payment-limits")]
+    pub payment_limits: Option<PaymentLimits>,
+    /// Web of Trust フィルタ（読み取りツールのデフォルト、任意）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "web-of-trust")]
+    pub web_of_trust: Option<WebOfTrust>,
+    /// 自分のコンタクトリストのキャッシュ有効期間（秒、0 でキャッシュしない）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "contact-list-cache-secs")]
+    pub contact_list_cache_secs: Option<u64>,
+    /// リレーへのリクエストのレート制限（未指定時はデフォルト値で有効）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "rate-limit")]
+    pub rate_limit: Option<RateLimitConfig>,
+    /// 取得したイベントの署名検証: "off"（デフォルト）、"annotate"、"drop"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "verify-signatures")]
+    pub verify_signatures: Option<SignatureVerification>,
+    /// グローバルタイムライン・検索結果に要求する最小 PoW 難易度（NIP-13、デフォルト: 0 = 無効）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "min-pow")]
+    pub min_pow: Option<u8>,
+    /// ミュートワード・正規表現・アカウントの作成からの日数によるコンテンツフィルタ（任意）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filters: Option<ContentFilters>,
+    /// コンテンツ警告・NSFW ラベル付きノートの扱い: "show"（デフォルト）、"flag"、"hide"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "sensitive-content")]
+    pub sensitive_content: Option<SensitiveContentPolicy>,
+    /// タイムライン・ノート検索で表示する言語（ISO 639-1、例: ["ja", "en"]、未指定時はすべて）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub languages: Option<Vec<String>>,
+    /// NIP-26 委任署名（設定時は委任先の鍵で署名し、イベントに delegation タグを付与）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delegation: Option<DelegationConfig>,
+    /// `limit` 省略時の取得件数（デフォルト: 20、最大: 100）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "default-limit")]
+    pub default_limit: Option<u64>,
+    /// ノート一覧を返すツールで本文を切り詰める文字数（未指定時は切り詰めない）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "content-max-chars")]
+    pub content_max_chars: Option<usize>,
+    /// true の場合、書き込みツールはイベントを署名するだけで公開せず、公開するはずだった JSON を返す
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "dry-run")]
+    pub dry_run: Option<bool>,
+    /// `react_to_note` で `reaction` を省略したときのリアクション（プリセット名も可、デフォルト: "+"）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "default-reaction")]
+    pub default_reaction: Option<String>,
+    /// 名前をキーとするリアクションのプリセット
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "reaction-presets")]
+    pub reaction_presets: Option<HashMap<String, ReactionPreset>>,
 }
 
 impl Default for Config {
@@ -111,12 +335,28 @@ impl Default for Config {
         Self {
             relays,
             privatekey: None,
+            publickey: None,
             nwc_uri: None,
             auth_mode: None,
             bunker_uri: None,
             nip46_relays: None,
             nip46_perms: None,
             blossom_servers: None,
+            payment_limits: None,
+            web_of_trust: None,
+            contact_list_cache_secs: None,
+            rate_limit: None,
+            verify_signatures: None,
+            min_pow: None,
+            filters: None,
+            sensitive_content: None,
+            languages: None,
+            delegation: None,
+            default_limit: None,
+            content_max_chars: None,
+            dry_run: None,
+            default_reaction: None,
+            reaction_presets: None,
         }
     }
 }
@@ -202,6 +442,27 @@ impl Config {
         Ok(())
     }
 
+    /// 既存の設定ファイルを `config.json.<Unix 時刻>.bak` にコピーし、コピー先のパスを返します。
+    ///
+    /// 設定ファイルがない場合は None を返します。秘密鍵を置き換える前の退避に使用します。
+    pub fn backup_file() -> Result<Option<PathBuf>> {
+        let config_path = Self::config_path()?;
+        if !config_path.exists() {
+            return Ok(None);
+        }
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let backup_path = config_path.with_file_name(format!("config.json.{}.bak", timestamp));
+        fs::copy(&config_path, &backup_path)
+            .context("設定ファイルのバックアップに失敗しました")?;
+
+        info!("設定ファイルをバックアップしました: {:?}", backup_path);
+        Ok(Some(backup_path))
+    }
+
     /// 設定ファイルが存在しない場合、デフォルト設定で作成します。
     pub fn create_default_if_missing() -> Result<bool> {
         let config_path = Self::config_path()?;
@@ -231,7 +492,6 @@ impl Config {
     }
 
     /// 書き込み有効なリレー URL を取得
-    #[allow(dead_code)]
     pub fn write_relays(&self) -> Vec<String> {
         self.relays_by(|c| c.write)
     }
@@ -253,6 +513,19 @@ impl Config {
             AuthMode::Local
         }
     }
+
+    /// ツールの出力量の設定を取得（0 は未指定として扱う）
+    pub fn output_options(&self) -> OutputOptions {
+        OutputOptions {
+            default_limit: self.default_limit.filter(|&limit| limit > 0),
+            content_max_chars: self.content_max_chars.filter(|&chars| chars > 0),
+        }
+    }
+
+    /// 自分のコンタクトリストのキャッシュ有効期間を取得
+    pub fn contact_list_cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.contact_list_cache_secs.unwrap_or(DEFAULT_CONTACT_LIST_CACHE_SECS))
+    }
 }
 
 #[cfg(test)]
@@ -275,4 +548,38 @@ mod tests {
         assert!(!read_relays.is_empty());
         assert!(!search_relays.is_empty());
     }
+
+    #[test]
+    fn test_reaction_presets() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "relays": {},
+                "default-reaction": "fire",
+                "reaction-presets": {
+                    "fire": {"content": "🔥", "max-per-hour": 10},
+                    "zapface": {"content": ":zapface:", "emoji-url": "https://example.com/zapface.png"}
+                }
+            }"#
+        ).unwrap();
+        assert_eq!(config.default_reaction.as_deref(), Some("fire"));
+        let presets = config.reaction_presets.unwrap();
+        assert_eq!(presets["fire"], ReactionPreset { content: "🔥".to_string(), emoji_url: None, max_per_hour: Some(10) });
+        assert_eq!(presets["zapface"].emoji_url.as_deref(), Some("https://example.com/zapface.png"));
+        assert!(presets["zapface"].max_per_hour.is_none());
+    }
+
+    #[test]
+    fn test_output_options() {
+        assert_eq!(Config::default().output_options(), OutputOptions::default());
+
+        let config: Config = serde_json::from_str(
+            r#"{"relays": {}, "default-limit": 5, "content-max-chars": 280}"#
+        ).unwrap();
+        assert_eq!(config.output_options(), OutputOptions { default_limit: Some(5), content_max_chars: Some(280) });
+
+        let config: Config = serde_json::from_str(
+            r#"{"relays": {}, "default-limit": 0, "content-max-chars": 0}"#
+        ).unwrap();
+        assert_eq!(config.output_options(), OutputOptions::default());
+    }
 }
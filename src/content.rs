@@ -28,6 +28,65 @@ impl MediaInfo {
     }
 }
 
+/// メディアメタデータ（NIP-92 imeta タグ）
+///
+/// 動画イベント (NIP-71) などで、URL ごとの MIME タイプ・解像度・サムネイル等を表します。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MediaMetadata {
+    /// メディアの URL
+    pub url: String,
+    /// MIME タイプ（m）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// 解像度（dim、例: 1920x1080）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dim: Option<String>,
+    /// 再生時間（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    /// サムネイル画像 URL（image）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// SHA-256 ハッシュ（x）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// ファイルサイズ（バイト）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// 代替 URL（fallback）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fallback: Vec<String>,
+}
+
+impl MediaMetadata {
+    /// imeta タグの値（"key value" 形式のリスト）に変換
+    pub fn to_imeta_values(&self) -> Vec<String> {
+        let mut values = vec![format!("url {}", self.url)];
+        if let Some(ref m) = self.mime_type {
+            values.push(format!("m {}", m));
+        }
+        if let Some(ref dim) = self.dim {
+            values.push(format!("dim {}", dim));
+        }
+        if let Some(duration) = self.duration {
+            values.push(format!("duration {}", duration));
+        }
+        if let Some(ref image) = self.image {
+            values.push(format!("image {}", image));
+        }
+        if let Some(ref x) = self.sha256 {
+            values.push(format!("x {}", x));
+        }
+        if let Some(size) = self.size {
+            values.push(format!("size {}", size));
+        }
+        for url in &self.fallback {
+            values.push(format!("fallback {}", url));
+        }
+        values
+    }
+}
+
 /// Nostr 参照情報（NIP-27: nostr: URI）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NostrReference {
@@ -50,15 +109,33 @@ pub struct ParsedContent {
     /// Nostr 参照（NIP-27）
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub references: Vec<NostrReference>,
+    /// メディア以外のリンク URL
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<String>,
 }
 
 impl ParsedContent {
     /// 解析結果が空かどうか
     pub fn is_empty(&self) -> bool {
-        self.media.is_empty() && self.hashtags.is_empty() && self.references.is_empty()
+        self.media.is_empty() && self.hashtags.is_empty() && self.references.is_empty() && self.links.is_empty()
     }
 }
 
+/// 記事の見出し（目次の項目）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Heading {
+    /// 見出しのレベル（1〜6）
+    pub level: u8,
+    /// 見出しのテキスト
+    pub title: String,
+}
+
+/// 1 分あたりに読める文字数（日本語など、空白で単語を区切らない文字）
+const READING_CHARS_PER_MINUTE: f64 = 500.0;
+
+/// 1 分あたりに読める単語数（英語など）
+const READING_WORDS_PER_MINUTE: f64 = 200.0;
+
 // ========================================
 // 正規表現パターン（遅延初期化）
 // ========================================
@@ -100,7 +177,7 @@ const IMAGE_EXTENSIONS: &[&str] = &[
 
 /// 動画ファイルの拡張子
 const VIDEO_EXTENSIONS: &[&str] = &[
-    ".mp4", ".webm", ".mov", ".avi", ".mkv",
+    ".mp4", ".webm", ".mov", ".avi", ".mkv", ".m4v", ".m3u8",
 ];
 
 /// 音声ファイルの拡張子
@@ -153,6 +230,20 @@ pub fn extract_media(content: &str) -> MediaInfo {
     media
 }
 
+/// コンテンツからメディア以外のリンク URL を抽出する（重複なし、出現順）
+///
+/// 文中の URL の直後に続く句読点（. , ! ? ; :）は URL に含めません。
+pub fn extract_links(content: &str) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    for m in url_regex().find_iter(content) {
+        let url = m.as_str().trim_end_matches(['.', ',', '!', '?', ';', ':']);
+        if classify_url(url).is_none() && !links.iter().any(|l| l == url) {
+            links.push(url.to_string());
+        }
+    }
+    links
+}
+
 /// コンテンツからハッシュタグを抽出する
 pub fn extract_hashtags(content: &str) -> Vec<String> {
     let re = hashtag_regex();
@@ -198,15 +289,155 @@ pub fn extract_nostr_references(content: &str) -> Vec<NostrReference> {
         .collect()
 }
 
+/// imeta タグの値（"key value" 形式、先頭の "imeta" を除く）をパースする（NIP-92）
+///
+/// url が含まれない場合は None を返します。
+pub fn parse_imeta<S: AsRef<str>>(values: &[S]) -> Option<MediaMetadata> {
+    let mut meta = MediaMetadata::default();
+
+    for entry in values {
+        let Some((key, value)) = entry.as_ref().split_once(' ') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key {
+            "url" => meta.url = value,
+            "m" => meta.mime_type = Some(value),
+            "dim" => meta.dim = Some(value),
+            "duration" => meta.duration = value.parse().ok(),
+            "image" => meta.image = Some(value),
+            "x" => meta.sha256 = Some(value),
+            "size" => meta.size = value.parse().ok(),
+            "fallback" => meta.fallback.push(value),
+            _ => {}
+        }
+    }
+
+    if meta.url.is_empty() {
+        None
+    } else {
+        Some(meta)
+    }
+}
+
 /// コンテンツを解析して構造化された情報を返す
 pub fn parse_content(content: &str) -> ParsedContent {
     ParsedContent {
         media: extract_media(content),
         hashtags: extract_hashtags(content),
         references: extract_nostr_references(content),
+        links: extract_links(content),
     }
 }
 
+/// 空白で単語を区切らない文字（かな・漢字・ハングル）か
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}'
+        | '\u{f900}'..='\u{faff}' | '\u{ac00}'..='\u{d7af}' | '\u{ff66}'..='\u{ff9f}')
+}
+
+/// Markdown 本文の推定読了時間（分）
+///
+/// かな・漢字などは 1 分あたり 500 文字、それ以外は 1 分あたり 200 単語として計算し、切り上げます。
+/// 本文が空の場合は 0 を返します。
+pub fn estimate_reading_minutes(markdown: &str) -> u64 {
+    let chars = markdown.chars().filter(|&c| is_cjk(c)).count();
+    let words = markdown
+        .split_whitespace()
+        .flat_map(|token| token.split(is_cjk))
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count();
+    let minutes = chars as f64 / READING_CHARS_PER_MINUTE + words as f64 / READING_WORDS_PER_MINUTE;
+    minutes.ceil() as u64
+}
+
+/// Markdown の ATX 見出し（`#` 〜 `######`）から目次を抽出する（コードブロック内は除く）
+pub fn extract_headings(markdown: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m)) {
+            match fence {
+                Some(open) if open == marker => fence = None,
+                None => fence = Some(marker),
+                _ => {}
+            }
+            continue;
+        }
+        if fence.is_some() || indent > 3 {
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        let rest = &trimmed[level..];
+        if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+            continue;
+        }
+        // 閉じの `#` 列（`## 見出し ##`）は除く
+        let rest = rest.trim();
+        let without_closing = rest.trim_end_matches('#');
+        let title = if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+            without_closing.trim()
+        } else {
+            rest
+        };
+        if !title.is_empty() {
+            headings.push(Heading { level: level as u8, title: title.to_string() });
+        }
+    }
+    headings
+}
+
+/// 長い本文をスレッド用に `max_chars` 文字以内のセグメントへ分割する
+///
+/// 段落（空行）・改行・文末（。！？ や後ろに空白が続く . ! ?）・空白の順に区切りを探し、
+/// セグメントが短くなりすぎない（`max_chars` の半分以上）位置で切ります。区切りがない場合は文字数で切ります。
+pub fn split_into_segments(text: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(1);
+    let mut segments = Vec::new();
+    let mut rest = text.trim();
+
+    while !rest.is_empty() {
+        let Some((limit, _)) = rest.char_indices().nth(max_chars) else {
+            segments.push(rest.to_string());
+            break;
+        };
+        let min_cut = rest.char_indices().nth(max_chars / 2).map_or(0, |(i, _)| i).max(1);
+        let window = &rest[..limit];
+
+        let cut = ["\n\n", "\n"]
+            .iter()
+            .find_map(|sep| window.rfind(sep).filter(|&i| i >= min_cut))
+            .or_else(|| {
+                window
+                    .char_indices()
+                    .rev()
+                    .take_while(|&(i, _)| i >= min_cut)
+                    .find(|&(i, c)| {
+                        let next = rest[i + c.len_utf8()..].chars().next();
+                        matches!(c, '。' | '！' | '？')
+                            || (matches!(c, '.' | '!' | '?') && next.is_none_or(char::is_whitespace))
+                    })
+                    .map(|(i, c)| i + c.len_utf8())
+            })
+            .or_else(|| window.rfind(char::is_whitespace).filter(|&i| i >= min_cut))
+            .unwrap_or(limit);
+
+        let segment = rest[..cut].trim();
+        if !segment.is_empty() {
+            segments.push(segment.to_string());
+        }
+        rest = rest[cut..].trim_start();
+    }
+
+    segments
+}
+
 // ========================================
 // テスト
 // ========================================
@@ -334,6 +565,45 @@ mod tests {
         assert_eq!(refs[0].ref_type, "naddr");
     }
 
+    #[test]
+    fn test_extract_media_hls_video() {
+        let content = "Live: https://example.com/stream/index.m3u8";
+        let media = extract_media(content);
+        assert_eq!(media.videos.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_imeta() {
+        let values = vec![
+            "url https://example.com/video.mp4",
+            "m video/mp4",
+            "dim 1920x1080",
+            "duration 12.5",
+            "image https://example.com/thumb.jpg",
+            "fallback https://backup.example.com/video.mp4",
+        ];
+        let meta = parse_imeta(&values).unwrap();
+        assert_eq!(meta.url, "https://example.com/video.mp4");
+        assert_eq!(meta.mime_type.as_deref(), Some("video/mp4"));
+        assert_eq!(meta.duration, Some(12.5));
+        assert_eq!(meta.fallback.len(), 1);
+
+        // 往復変換で同じ内容になる
+        assert_eq!(parse_imeta(&meta.to_imeta_values()).unwrap(), meta);
+    }
+
+    #[test]
+    fn test_parse_imeta_without_url() {
+        assert!(parse_imeta(&["m video/mp4"]).is_none());
+    }
+
+    #[test]
+    fn test_extract_links() {
+        let content = "Read https://example.com/post, https://example.com/a.png and again https://example.com/post.";
+        assert_eq!(extract_links(content), vec!["https://example.com/post"]);
+        assert!(extract_links("no links").is_empty());
+    }
+
     #[test]
     fn test_parse_content_comprehensive() {
         let content = "Hello #nostr! Check nostr:npub1abc123 and https://example.com/photo.jpg";
@@ -343,6 +613,46 @@ mod tests {
         assert!(!parsed.media.is_empty());
     }
 
+    #[test]
+    fn test_split_into_segments() {
+        assert_eq!(split_into_segments("  短い本文  ", 100), vec!["短い本文"]);
+        assert!(split_into_segments("", 100).is_empty());
+
+        // 段落の区切りを優先する
+        let text = "一つ目の段落です。\n\n二つ目の段落です。";
+        assert_eq!(split_into_segments(text, 15), vec!["一つ目の段落です。", "二つ目の段落です。"]);
+
+        // 文末で区切り、URL の途中のドットでは切らない
+        let text = "See https://example.com/a.b now. Second sentence here";
+        let segments = split_into_segments(text, 40);
+        assert_eq!(segments[0], "See https://example.com/a.b now.");
+        assert_eq!(segments[1], "Second sentence here");
+
+        // 区切りがない場合は文字数で切る
+        let segments = split_into_segments(&"あ".repeat(25), 10);
+        assert_eq!(segments.len(), 3);
+        assert!(segments.iter().all(|s| s.chars().count() <= 10));
+    }
+
+    #[test]
+    fn test_estimate_reading_minutes() {
+        assert_eq!(estimate_reading_minutes(""), 0);
+        assert_eq!(estimate_reading_minutes("短い本文"), 1);
+        assert_eq!(estimate_reading_minutes(&"あ".repeat(1000)), 2);
+        assert_eq!(estimate_reading_minutes(&"word ".repeat(401)), 3);
+        // 日本語と英単語が混在する場合はそれぞれの時間を合計する
+        assert_eq!(estimate_reading_minutes(&format!("{}{}", "字".repeat(250), " Rust".repeat(100))), 1);
+        assert_eq!(estimate_reading_minutes(&format!("{}{}", "字".repeat(300), " Rust".repeat(100))), 2);
+    }
+
+    #[test]
+    fn test_extract_headings() {
+        let markdown = "# タイトル\n\n本文\n\n## はじめに ##\n```rust\n# not a heading\n```\n### C#\n    # コード\n#タグ\n####### 深すぎ\n  ## インデント";
+        let headings = extract_headings(markdown);
+        let headings: Vec<(u8, &str)> = headings.iter().map(|h| (h.level, h.title.as_str())).collect();
+        assert_eq!(headings, vec![(1, "タイトル"), (2, "はじめに"), (3, "C#"), (2, "インデント")]);
+    }
+
     #[test]
     fn test_parse_content_empty() {
         let content = "Just plain text";
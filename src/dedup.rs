@@ -0,0 +1,121 @@
+//! イベント重複除去モジュール
+//!
+//! 複数のリレーから届いた同じイベントを ID でまとめ、最初に受信した順序と
+//! 受信元リレー（provenance）を保持します。件数の集計やノート情報の組み立ての前に通すことで、
+//! 同じイベントを二重に数えないようにします。
+
+use nostr_sdk::prelude::*;
+use std::collections::HashMap;
+
+/// 重複除去したイベントと、その受信元リレー
+struct DedupEntry {
+    event: Event,
+    relays: Vec<RelayUrl>,
+}
+
+/// イベント ID をキーにした重複除去バッファ
+///
+/// イベントは最初に受信した順に並び、2 回目以降の受信は受信元リレーの追加としてのみ記録されます。
+#[derive(Default)]
+pub struct EventDedup {
+    order: Vec<EventId>,
+    entries: HashMap<EventId, DedupEntry>,
+    duplicates: u64,
+}
+
+impl EventDedup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// イベントを追加します。初めて受信したイベントなら true を返します。
+    ///
+    /// `relay` を指定すると受信元リレーとして記録します（同じリレーは 1 回のみ）。
+    pub fn insert(&mut self, event: Event, relay: Option<&RelayUrl>) -> bool {
+        if let Some(entry) = self.entries.get_mut(&event.id) {
+            self.duplicates += 1;
+            if let Some(relay) = relay {
+                if !entry.relays.contains(relay) {
+                    entry.relays.push(relay.clone());
+                }
+            }
+            return false;
+        }
+
+        self.order.push(event.id);
+        self.entries.insert(event.id, DedupEntry {
+            event,
+            relays: relay.into_iter().cloned().collect(),
+        });
+        true
+    }
+
+    /// 同じリレーから受信した複数のイベントを追加します。
+    pub fn extend<I: IntoIterator<Item = Event>>(&mut self, events: I, relay: Option<&RelayUrl>) {
+        for event in events {
+            self.insert(event, relay);
+        }
+    }
+
+    /// 重複を除いたイベント数
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// 2 回目以降に受信して除外したイベントの数
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    /// イベントを受信したリレー（受信順）
+    pub fn seen_on(&self, id: &EventId) -> &[RelayUrl] {
+        self.entries.get(id).map(|entry| entry.relays.as_slice()).unwrap_or(&[])
+    }
+
+    /// 最初に受信した順にイベントを参照します。
+    pub fn iter(&self) -> impl Iterator<Item = &Event> {
+        self.order.iter().filter_map(|id| self.entries.get(id).map(|entry| &entry.event))
+    }
+
+    /// 最初に受信した順のイベント一覧に変換します。
+    pub fn into_events(mut self) -> Vec<Event> {
+        self.order
+            .iter()
+            .filter_map(|id| self.entries.remove(id).map(|entry| entry.event))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(keys: &Keys, content: &str) -> Event {
+        EventBuilder::text_note(content).sign_with_keys(keys).unwrap()
+    }
+
+    #[test]
+    fn test_event_dedup_keeps_first_seen_order_and_provenance() {
+        let keys = Keys::generate();
+        let first = note(&keys, "first");
+        let second = note(&keys, "second");
+        let relay_a = RelayUrl::parse("wss://a.example.com").unwrap();
+        let relay_b = RelayUrl::parse("wss://b.example.com").unwrap();
+
+        let mut dedup = EventDedup::new();
+        assert!(dedup.insert(second.clone(), Some(&relay_a)));
+        assert!(dedup.insert(first.clone(), Some(&relay_a)));
+        assert!(!dedup.insert(second.clone(), Some(&relay_b)));
+        assert!(!dedup.insert(second.clone(), Some(&relay_b)));
+        dedup.extend(vec![first.clone()], None);
+
+        assert_eq!(dedup.len(), 2);
+        assert_eq!(dedup.duplicates(), 3);
+        assert_eq!(dedup.seen_on(&second.id), &[relay_a.clone(), relay_b]);
+        assert_eq!(dedup.seen_on(&first.id), &[relay_a]);
+        assert!(dedup.seen_on(&EventId::all_zeros()).is_empty());
+
+        let ids: Vec<EventId> = dedup.into_events().iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![second.id, first.id]);
+    }
+}
@@ -0,0 +1,136 @@
+//! テキスト差分モジュール
+//!
+//! 行単位の差分（LCS ベース）を計算し、記事のバージョン比較に利用します。
+
+use serde::{Deserialize, Serialize};
+
+/// 差分計算の対象とする最大行数（LCS テーブルのメモリ使用量を抑えるため）
+const MAX_DIFF_LINES: usize = 2000;
+
+/// 差分の 1 行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", content = "line", rename_all = "lowercase")]
+pub enum DiffLine {
+    /// 両方に存在する行
+    Equal(String),
+    /// 追加された行
+    Added(String),
+    /// 削除された行
+    Removed(String),
+}
+
+/// 行単位の差分結果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TextDiff {
+    /// 追加行数
+    pub added: usize,
+    /// 削除行数
+    pub removed: usize,
+    /// 変更のない行数
+    pub unchanged: usize,
+    /// 統合差分形式（"+ " / "- " / "  " 接頭辞付き）のテキスト
+    pub unified: String,
+    /// 入力が大きすぎて末尾を比較対象から除外した場合 true
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+impl TextDiff {
+    /// 差分がないかどうか
+    pub fn is_identical(&self) -> bool {
+        self.added == 0 && self.removed == 0
+    }
+}
+
+/// 2 つのテキストの行単位の差分を計算
+pub fn diff_lines(old: &str, new: &str) -> TextDiff {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let truncated = old_lines.len() > MAX_DIFF_LINES || new_lines.len() > MAX_DIFF_LINES;
+    let a = &old_lines[..old_lines.len().min(MAX_DIFF_LINES)];
+    let b = &new_lines[..new_lines.len().min(MAX_DIFF_LINES)];
+
+    let lines = lcs_diff(a, b);
+
+    let mut result = TextDiff {
+        truncated,
+        ..Default::default()
+    };
+    let mut unified = Vec::with_capacity(lines.len());
+    for line in &lines {
+        match line {
+            DiffLine::Equal(l) => {
+                result.unchanged += 1;
+                unified.push(format!("  {}", l));
+            }
+            DiffLine::Added(l) => {
+                result.added += 1;
+                unified.push(format!("+ {}", l));
+            }
+            DiffLine::Removed(l) => {
+                result.removed += 1;
+                unified.push(format!("- {}", l));
+            }
+        }
+    }
+    result.unified = unified.join("\n");
+    result
+}
+
+/// 最長共通部分列 (LCS) から差分行を組み立てる
+fn lcs_diff(a: &[&str], b: &[&str]) -> Vec<DiffLine> {
+    let (n, m) = (a.len(), b.len());
+
+    // table[i][j] = a[i..] と b[j..] の LCS 長
+    let mut table = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if a[i] == b[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            lines.push(DiffLine::Equal(a[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            lines.push(DiffLine::Removed(a[i].to_string()));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(b[j].to_string()));
+            j += 1;
+        }
+    }
+    lines.extend(a[i..].iter().map(|l| DiffLine::Removed(l.to_string())));
+    lines.extend(b[j..].iter().map(|l| DiffLine::Added(l.to_string())));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() {
+        let diff = diff_lines("a\nb\nc", "a\nB\nc\nd");
+        assert_eq!(diff.added, 2);
+        assert_eq!(diff.removed, 1);
+        assert_eq!(diff.unchanged, 2);
+        assert_eq!(diff.unified, "  a\n- b\n+ B\n  c\n+ d");
+        assert!(!diff.is_identical());
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let diff = diff_lines("同じ\n内容", "同じ\n内容");
+        assert!(diff.is_identical());
+        assert_eq!(diff.unchanged, 2);
+    }
+}
@@ -0,0 +1,60 @@
+//! ドライランモジュール
+//!
+//! 書き込みツールを `dry_run` で実行している間、公開しようとした署名済みイベントを
+//! リレーに送信せずに記録します。記録はツール呼び出しのタスクごとに分かれるため、
+//! 同時に実行される他のツール呼び出しには影響しません。
+
+use std::cell::RefCell;
+use std::future::Future;
+
+use nostr_sdk::prelude::*;
+
+tokio::task_local! {
+    /// 実行中のドライランで公開しようとしたイベント
+    static RECORDED: RefCell<Vec<Event>>;
+}
+
+/// `future` をドライランとして実行し、その結果と公開しようとしたイベントを返します。
+pub async fn scope<F: Future>(future: F) -> (F::Output, Vec<Event>) {
+    RECORDED
+        .scope(RefCell::new(Vec::new()), async move {
+            let output = future.await;
+            let events = RECORDED.with(|recorded| recorded.take());
+            (output, events)
+        })
+        .await
+}
+
+/// 現在のタスクがドライランの中で実行されているか
+pub fn is_active() -> bool {
+    RECORDED.try_with(|_| ()).is_ok()
+}
+
+/// 公開しようとしたイベントを記録します（ドライランの外では何もしません）。
+pub fn record(event: &Event) {
+    let _ = RECORDED.try_with(|recorded| recorded.borrow_mut().push(event.clone()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope_records_events() {
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("ドライラン").sign_with_keys(&keys).unwrap();
+
+        assert!(!is_active());
+        record(&event);
+
+        let (active, events) = scope(async {
+            record(&event);
+            is_active()
+        })
+        .await;
+        assert!(active);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, event.id);
+        assert!(!is_active());
+    }
+}
@@ -0,0 +1,491 @@
+//! 読み取り結果のフィルタモジュール
+//!
+//! ミュートリスト (NIP-51, Kind 10000) に基づき、ミュート対象の公開鍵・ハッシュタグ・
+//! 単語・スレッドを含むイベントをモデルに渡す前に除外します。
+//! また、フォローグラフ上の距離（Web of Trust）や PoW（NIP-13）の難易度による除外・並べ替えと、
+//! 設定ファイルの `filters`（ミュートワード・正規表現）による除外と、ノートの言語による絞り込みを行います。
+
+use nostr_sdk::prelude::*;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use tracing::warn;
+
+use crate::config::{ContentFilters, TrustMode};
+
+/// ミュートリスト（NIP-51）
+#[derive(Debug, Clone, Default)]
+pub struct MuteList {
+    /// ミュートした公開鍵（p タグ）
+    pub pubkeys: HashSet<PublicKey>,
+    /// ミュートしたハッシュタグ（t タグ、小文字）
+    pub hashtags: HashSet<String>,
+    /// ミュートした単語（word タグ、小文字）
+    pub words: Vec<String>,
+    /// ミュートしたスレッド（e タグ）
+    pub threads: HashSet<EventId>,
+}
+
+impl MuteList {
+    /// タグ配列（公開タグと復号済みの非公開タグ）からミュートリストを作成
+    pub fn from_tags<'a, I>(tags: I) -> Self
+    where
+        I: IntoIterator<Item = &'a [String]>,
+    {
+        let mut list = Self::default();
+        for values in tags {
+            let (Some(key), Some(value)) = (values.first(), values.get(1)) else {
+                continue;
+            };
+            match key.as_str() {
+                "p" => {
+                    if let Ok(pk) = PublicKey::from_hex(value) {
+                        list.pubkeys.insert(pk);
+                    }
+                }
+                "t" => {
+                    list.hashtags.insert(value.trim_start_matches('#').to_lowercase());
+                }
+                "word" => {
+                    let word = value.trim().to_lowercase();
+                    if !word.is_empty() {
+                        list.words.push(word);
+                    }
+                }
+                "e" => {
+                    if let Ok(id) = EventId::from_hex(value) {
+                        list.threads.insert(id);
+                    }
+                }
+                _ => {}
+            }
+        }
+        list
+    }
+
+    /// ミュート対象がないかどうか
+    pub fn is_empty(&self) -> bool {
+        self.pubkeys.is_empty()
+            && self.hashtags.is_empty()
+            && self.words.is_empty()
+            && self.threads.is_empty()
+    }
+
+    /// 公開鍵がミュートされているか
+    pub fn is_pubkey_muted(&self, pubkey: &PublicKey) -> bool {
+        self.pubkeys.contains(pubkey)
+    }
+
+    /// イベントがミュート対象か（著者・ハッシュタグ・単語・スレッドのいずれかに一致）
+    pub fn is_muted(&self, event: &Event) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        if self.is_pubkey_muted(&event.pubkey) || self.threads.contains(&event.id) {
+            return true;
+        }
+
+        for tag in event.tags.iter() {
+            let values = tag.as_slice();
+            if values.len() < 2 {
+                continue;
+            }
+            match values[0].as_str() {
+                "t" if self.hashtags.contains(&values[1].to_lowercase()) => return true,
+                "e" => {
+                    if let Ok(id) = EventId::from_hex(&values[1]) {
+                        if self.threads.contains(&id) {
+                            return true;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if self.words.is_empty() && self.hashtags.is_empty() {
+            return false;
+        }
+        let content = event.content.to_lowercase();
+        self.words.iter().any(|word| content.contains(word.as_str()))
+            || crate::content::extract_hashtags(&content)
+                .iter()
+                .any(|tag| self.hashtags.contains(tag))
+    }
+}
+
+/// 設定ファイルの `filters` から作成したコンテンツフィルタ
+#[derive(Debug, Clone, Default)]
+pub struct ContentFilter {
+    /// 除外する語句（小文字）
+    words: Vec<String>,
+    /// 除外する正規表現
+    patterns: Vec<Regex>,
+    /// 除外するアカウントの作成からの最小日数（0 で無効）
+    pub min_account_age_days: u64,
+}
+
+impl ContentFilter {
+    /// 設定から作成（不正な正規表現は警告して無視）
+    pub fn from_config(config: &ContentFilters) -> Self {
+        let words = config.muted_words.iter()
+            .map(|w| w.trim().to_lowercase())
+            .filter(|w| !w.is_empty())
+            .collect();
+        let patterns = config.regexes.iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    warn!("filters の正規表現が不正なため無視します: {} ({})", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            words,
+            patterns,
+            min_account_age_days: config.min_account_age_days.unwrap_or(0),
+        }
+    }
+
+    /// 本文がミュートワードまたは正規表現に一致するか
+    pub fn matches(&self, content: &str) -> bool {
+        if !self.words.is_empty() {
+            let lower = content.to_lowercase();
+            if self.words.iter().any(|word| lower.contains(word.as_str())) {
+                return true;
+            }
+        }
+        self.patterns.iter().any(|re| re.is_match(content))
+    }
+}
+
+/// 自分を起点としたフォローグラフ（公開鍵ごとの最短ホップ数）
+#[derive(Debug, Clone, Default)]
+pub struct TrustGraph {
+    distances: HashMap<PublicKey, u8>,
+}
+
+impl TrustGraph {
+    /// 起点とホップ数ごとの公開鍵リスト（`layers[0]` が 1 ホップ目）から作成
+    pub fn from_layers(root: PublicKey, layers: &[Vec<PublicKey>]) -> Self {
+        let mut distances = HashMap::new();
+        distances.insert(root, 0);
+        for (i, layer) in layers.iter().enumerate() {
+            let hops = (i + 1) as u8;
+            for pk in layer {
+                distances.entry(*pk).or_insert(hops);
+            }
+        }
+        Self { distances }
+    }
+
+    /// グラフに含まれるアカウント数（起点を含む）
+    pub fn len(&self) -> usize {
+        self.distances.len()
+    }
+
+    /// 起点からのホップ数（グラフ外の場合は None）
+    pub fn distance(&self, pubkey: &PublicKey) -> Option<u8> {
+        self.distances.get(pubkey).copied()
+    }
+
+    /// 公開鍵が `max_hops` 以内にあるか
+    pub fn is_trusted(&self, pubkey: &PublicKey, max_hops: u8) -> bool {
+        self.distance(pubkey).is_some_and(|d| d <= max_hops)
+    }
+
+    /// 信頼範囲外のアカウントの項目を除外（Drop）または後ろへ並べ替え（Rank）
+    ///
+    /// 戻り値は信頼範囲外だった項目数です。公開鍵を取得できない項目は範囲外として扱います。
+    pub fn apply<T, F>(&self, items: &mut Vec<T>, max_hops: u8, mode: TrustMode, key: F) -> usize
+    where
+        F: Fn(&T) -> Option<PublicKey>,
+    {
+        let trusted = |item: &T| key(item).is_some_and(|pk| self.is_trusted(&pk, max_hops));
+        let outside = items.iter().filter(|item| !trusted(item)).count();
+        match mode {
+            TrustMode::Drop => items.retain(|item| trusted(item)),
+            TrustMode::Rank => items.sort_by_key(|item| !trusted(item)),
+        }
+        outside
+    }
+}
+
+/// センシティブなノートとみなす理由を取得
+///
+/// コンテンツ警告タグ（NIP-36 の `content-warning`）、NSFW ラベル（NIP-32 の `l` タグ）、
+/// `#nsfw` ハッシュタグのいずれかがあるノートを対象とします。理由が書かれていない場合は "nsfw" を返します。
+pub fn sensitive_reason(event: &Event) -> Option<String> {
+    let mut nsfw = false;
+    for tag in event.tags.iter().map(|t| t.as_slice()) {
+        match tag.first().map(|k| k.as_str()) {
+            Some("content-warning") => {
+                let reason = tag.get(1).map(|r| r.trim()).filter(|r| !r.is_empty());
+                return Some(reason.unwrap_or("nsfw").to_string());
+            }
+            Some("l") => {
+                let label = tag.get(1).map(|l| l.to_lowercase()).unwrap_or_default();
+                let namespace = tag.get(2).map(|n| n.to_lowercase()).unwrap_or_default();
+                nsfw |= label == "nsfw" || namespace == "content-warning";
+            }
+            Some("t") => nsfw |= tag.get(1).is_some_and(|t| t.eq_ignore_ascii_case("nsfw")),
+            _ => {}
+        }
+    }
+    nsfw |= crate::content::extract_hashtags(&event.content).iter().any(|t| t.eq_ignore_ascii_case("nsfw"));
+    nsfw.then(|| "nsfw".to_string())
+}
+
+/// 言語判定の結果を採用する最小の信頼度（whatlang の confidence、0.0〜1.0）
+const MIN_LANGUAGE_CONFIDENCE: f64 = 0.3;
+
+/// whatlang の言語コード（ISO 639-3）と ISO 639-1 コードの対応
+const LANGUAGE_CODES: &[(&str, &str)] = &[
+    ("afr", "af"), ("aka", "ak"), ("amh", "am"), ("ara", "ar"), ("aze", "az"), ("bel", "be"),
+    ("ben", "bn"), ("bul", "bg"), ("cat", "ca"), ("ces", "cs"), ("cmn", "zh"), ("dan", "da"),
+    ("deu", "de"), ("ell", "el"), ("eng", "en"), ("epo", "eo"), ("est", "et"), ("fin", "fi"),
+    ("fra", "fr"), ("guj", "gu"), ("heb", "he"), ("hin", "hi"), ("hrv", "hr"), ("hun", "hu"),
+    ("hye", "hy"), ("ind", "id"), ("ita", "it"), ("jav", "jv"), ("jpn", "ja"), ("kan", "kn"),
+    ("kat", "ka"), ("khm", "km"), ("kor", "ko"), ("lat", "la"), ("lav", "lv"), ("lit", "lt"),
+    ("mal", "ml"), ("mar", "mr"), ("mkd", "mk"), ("mya", "my"), ("nep", "ne"), ("nld", "nl"),
+    ("nob", "nb"), ("ori", "or"), ("pan", "pa"), ("pes", "fa"), ("pol", "pl"), ("por", "pt"),
+    ("ron", "ro"), ("rus", "ru"), ("sin", "si"), ("slk", "sk"), ("slv", "sl"), ("sna", "sn"),
+    ("spa", "es"), ("srp", "sr"), ("swe", "sv"), ("tam", "ta"), ("tel", "te"), ("tgl", "tl"),
+    ("tha", "th"), ("tuk", "tk"), ("tur", "tr"), ("ukr", "uk"), ("urd", "ur"), ("uzb", "uz"),
+    ("vie", "vi"), ("yid", "yi"), ("zul", "zu"),
+];
+
+/// 言語コードを ISO 639-1 の小文字に正規化（ISO 639-3 は対応表で変換、`ja-JP` などの地域は無視）
+pub fn normalize_language_code(code: &str) -> String {
+    let code = code.trim().to_lowercase();
+    let base = code.split(['-', '_']).next().unwrap_or_default();
+    LANGUAGE_CODES.iter()
+        .find(|(iso3, _)| *iso3 == base)
+        .map(|(_, iso1)| iso1.to_string())
+        .unwrap_or_else(|| base.to_string())
+}
+
+/// 本文から言語を判定（ISO 639-1）
+///
+/// URL・`nostr:` 参照・ハッシュタグを除いた本文で判定し、信頼度が低い場合（短い文など）は None を返します。
+pub fn detect_language(content: &str) -> Option<String> {
+    let text: Vec<&str> = content
+        .split_whitespace()
+        .filter(|w| !w.starts_with("http://") && !w.starts_with("https://") && !w.starts_with("nostr:") && !w.starts_with('#'))
+        .collect();
+    let info = whatlang::detect(&text.join(" "))?;
+    (info.confidence() >= MIN_LANGUAGE_CONFIDENCE).then(|| normalize_language_code(info.lang().code()))
+}
+
+/// ノートの言語を取得（ISO 639-1）
+///
+/// NIP-32 の言語ラベル（`["l", "ja", "ISO-639-1"]`）があればそれを使い、なければ本文から判定します。
+pub fn note_language(event: &Event) -> Option<String> {
+    event.tags.iter()
+        .map(|t| t.as_slice())
+        .find(|t| t.first().is_some_and(|k| k == "l") && t.get(2).is_some_and(|ns| ns.eq_ignore_ascii_case("ISO-639-1")))
+        .and_then(|t| t.get(1))
+        .map(|code| normalize_language_code(code))
+        .or_else(|| detect_language(&event.content))
+}
+
+/// 言語による絞り込み（設定ファイルの `languages` またはツールの `languages` パラメータ）
+#[derive(Debug, Clone, Default)]
+pub struct LanguageFilter {
+    /// 許可する言語（ISO 639-1）
+    pub languages: Vec<String>,
+}
+
+impl LanguageFilter {
+    /// 言語コードのリストから作成（空の場合は絞り込みなし）
+    pub fn new(codes: &[String]) -> Self {
+        let mut languages: Vec<String> = codes.iter()
+            .map(|c| normalize_language_code(c))
+            .filter(|c| !c.is_empty())
+            .collect();
+        languages.sort();
+        languages.dedup();
+        Self { languages }
+    }
+
+    /// 絞り込みが無効か
+    pub fn is_empty(&self) -> bool {
+        self.languages.is_empty()
+    }
+
+    /// 指定された言語のノートを許可するか（言語が判定できないノートは許可）
+    pub fn allows(&self, language: Option<&str>) -> bool {
+        match language {
+            Some(lang) => self.is_empty() || self.languages.iter().any(|l| l == lang),
+            None => true,
+        }
+    }
+
+    /// 許可しない言語の要素を除外し、除外した件数を返す
+    pub fn apply<T>(&self, items: &mut Vec<T>, language: impl Fn(&T) -> Option<&str>) -> usize {
+        let before = items.len();
+        items.retain(|item| self.allows(language(item)));
+        before - items.len()
+    }
+}
+
+/// イベントの実効 PoW 難易度（NIP-13）
+///
+/// nonce タグがないイベントは 0 とします。nonce タグに目標難易度がある場合は、
+/// 実際のゼロビット数と目標の小さい方を返します（偶然高い難易度になったイベントを過大評価しない）。
+pub fn pow_difficulty(event: &Event) -> u8 {
+    let Some(nonce) = event.tags.iter().map(|t| t.as_slice()).find(|t| t.first().is_some_and(|k| k == "nonce")) else {
+        return 0;
+    };
+    let actual = nip13::get_leading_zero_bits(event.id.as_bytes());
+    match nonce.get(2).and_then(|target| target.parse::<u8>().ok()) {
+        Some(target) => actual.min(target),
+        None => actual,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(items: &[&[&str]]) -> Vec<Vec<String>> {
+        items
+            .iter()
+            .map(|t| t.iter().map(|s| s.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_mute_list_from_tags() {
+        let muted = Keys::generate().public_key();
+        let raw = tags(&[
+            &["p", &muted.to_hex()],
+            &["t", "Spam"],
+            &["word", " Airdrop "],
+            &["p", "invalid"],
+        ]);
+        let list = MuteList::from_tags(raw.iter().map(|t| t.as_slice()));
+
+        assert!(list.is_pubkey_muted(&muted));
+        assert!(list.hashtags.contains("spam"));
+        assert_eq!(list.words, vec!["airdrop".to_string()]);
+        assert_eq!(list.pubkeys.len(), 1);
+    }
+
+    #[test]
+    fn test_mute_list_is_muted() {
+        let keys = Keys::generate();
+        let muted = Keys::generate();
+        let raw = tags(&[&["p", &muted.public_key().to_hex()], &["t", "spam"], &["word", "airdrop"]]);
+        let list = MuteList::from_tags(raw.iter().map(|t| t.as_slice()));
+
+        let sign = |keys: &Keys, content: &str| {
+            EventBuilder::text_note(content).sign_with_keys(keys).unwrap()
+        };
+
+        assert!(list.is_muted(&sign(&muted, "こんにちは")));
+        assert!(list.is_muted(&sign(&keys, "Free AIRDROP now")));
+        assert!(list.is_muted(&sign(&keys, "見てね #Spam")));
+        assert!(!list.is_muted(&sign(&keys, "こんにちは")));
+        assert!(!MuteList::default().is_muted(&sign(&muted, "こんにちは")));
+    }
+
+    #[test]
+    fn test_content_filter_matches() {
+        let filter = ContentFilter::from_config(&ContentFilters {
+            muted_words: vec![" Airdrop ".to_string(), "".to_string()],
+            regexes: vec![r"(?i)\bfree\s+btc\b".to_string(), "[不正".to_string()],
+            min_account_age_days: Some(30),
+        });
+
+        assert!(filter.matches("Claim your AIRDROP"));
+        assert!(filter.matches("get Free  BTC today"));
+        assert!(!filter.matches("freebtc は一致しない"));
+        assert!(!filter.matches("こんにちは"));
+        assert_eq!(filter.patterns.len(), 1);
+        assert_eq!(filter.min_account_age_days, 30);
+        assert!(!ContentFilter::default().matches("airdrop"));
+    }
+
+    #[test]
+    fn test_sensitive_reason() {
+        let keys = Keys::generate();
+        let plain = EventBuilder::text_note("普通のノート").sign_with_keys(&keys).unwrap();
+        assert_eq!(sensitive_reason(&plain), None);
+
+        let warned = EventBuilder::text_note("ネタバレ")
+            .tag(Tag::parse(["content-warning", "spoiler"]).unwrap())
+            .sign_with_keys(&keys)
+            .unwrap();
+        assert_eq!(sensitive_reason(&warned).as_deref(), Some("spoiler"));
+
+        let labeled = EventBuilder::text_note("画像")
+            .tag(Tag::parse(["l", "NSFW", "content-warning"]).unwrap())
+            .sign_with_keys(&keys)
+            .unwrap();
+        assert_eq!(sensitive_reason(&labeled).as_deref(), Some("nsfw"));
+
+        let hashtag = EventBuilder::text_note("見ないで #NSFW").sign_with_keys(&keys).unwrap();
+        assert_eq!(sensitive_reason(&hashtag).as_deref(), Some("nsfw"));
+    }
+
+    #[test]
+    fn test_language_filter() {
+        assert_eq!(normalize_language_code("jpn"), "ja");
+        assert_eq!(normalize_language_code("EN-us"), "en");
+        assert_eq!(detect_language("今日はとても良い天気ですね。散歩に行きましょう。").as_deref(), Some("ja"));
+        assert_eq!(
+            detect_language("I think this is a great idea for the protocol https://example.com #nostr").as_deref(),
+            Some("en")
+        );
+        assert_eq!(detect_language("good morning nostr"), None);
+
+        let keys = Keys::generate();
+        let labeled = EventBuilder::text_note("Bonjour")
+            .tag(Tag::parse(["l", "de", "ISO-639-1"]).unwrap())
+            .sign_with_keys(&keys)
+            .unwrap();
+        assert_eq!(note_language(&labeled).as_deref(), Some("de"));
+
+        let filter = LanguageFilter::new(&["ja".to_string(), "eng".to_string()]);
+        let mut langs = vec![Some("ja"), Some("en"), Some("es"), None];
+        assert_eq!(filter.apply(&mut langs, |l| *l), 1);
+        assert_eq!(langs, vec![Some("ja"), Some("en"), None]);
+        assert!(LanguageFilter::new(&[]).allows(Some("es")));
+        let duplicated = LanguageFilter::new(&["ja".to_string(), "en".to_string(), "jpn".to_string()]);
+        assert_eq!(duplicated.languages, vec!["en".to_string(), "ja".to_string()]);
+    }
+
+    #[test]
+    fn test_pow_difficulty() {
+        let keys = Keys::generate();
+        let plain = EventBuilder::text_note("PoW なし").sign_with_keys(&keys).unwrap();
+        assert_eq!(pow_difficulty(&plain), 0);
+
+        let mined = EventBuilder::text_note("PoW あり").pow(8).sign_with_keys(&keys).unwrap();
+        assert!(pow_difficulty(&mined) >= 8);
+        assert!(mined.check_pow(8));
+    }
+
+    #[test]
+    fn test_trust_graph_apply() {
+        let root = Keys::generate().public_key();
+        let friend = Keys::generate().public_key();
+        let fof = Keys::generate().public_key();
+        let stranger = Keys::generate().public_key();
+        let graph = TrustGraph::from_layers(root, &[vec![friend], vec![fof, friend]]);
+
+        assert_eq!(graph.distance(&root), Some(0));
+        assert_eq!(graph.distance(&friend), Some(1));
+        assert_eq!(graph.distance(&fof), Some(2));
+        assert_eq!(graph.distance(&stranger), None);
+
+        let mut items = vec![stranger, fof, friend];
+        assert_eq!(graph.apply(&mut items, 1, TrustMode::Drop, |pk| Some(*pk)), 2);
+        assert_eq!(items, vec![friend]);
+
+        let mut items = vec![stranger, fof, friend];
+        assert_eq!(graph.apply(&mut items, 2, TrustMode::Rank, |pk| Some(*pk)), 1);
+        assert_eq!(items, vec![fof, friend, stranger]);
+    }
+}
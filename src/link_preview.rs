@@ -0,0 +1,321 @@
+//! リンクプレビューモジュール (OpenGraph)
+//!
+//! ノート中のメディア以外の URL から HTML を取得し、OpenGraph の
+//! タイトル・説明・画像を抽出します。取得は短いタイムアウトとサイズ上限付きで行います。
+//! ノートの URL は第三者が指定できるため、ループバックやプライベートネットワークなど
+//! グローバルでないアドレスには接続しません（リダイレクト先も同様に確認します）。
+//! 第三者が指定した URL への接続（`get_public_url` / `read_body_limited`）は LNURL の取得でも使います。
+
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::debug;
+
+/// ページ取得のタイムアウト
+const LINK_PREVIEW_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 読み込む HTML の最大バイト数（OpenGraph タグは通常 head 内にあるため先頭のみで十分）
+const MAX_HTML_BYTES: usize = 256 * 1024;
+
+/// たどるリダイレクトの最大回数
+const MAX_REDIRECTS: usize = 3;
+
+/// 説明文の最大文字数
+const MAX_DESCRIPTION_CHARS: usize = 300;
+
+/// リンクプレビュー（OpenGraph の主要フィールド）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LinkPreview {
+    /// プレビュー対象の URL
+    pub url: String,
+    /// タイトル（og:title、なければ title 要素）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// 説明（og:description、なければ meta description）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 画像 URL（og:image、絶対 URL に変換済み）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// サイト名（og:site_name）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+}
+
+impl LinkPreview {
+    /// タイトル・説明・画像のいずれも取得できなかったか
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.description.is_none() && self.image.is_none()
+    }
+}
+
+/// meta 要素検出用の正規表現
+fn meta_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<meta\s[^>]*>").unwrap())
+}
+
+/// 属性検出用の正規表現（キャプチャ 1: 属性名、2 / 3: 値）
+fn attribute_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(?is)([a-z:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap())
+}
+
+/// title 要素検出用の正規表現
+fn title_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap())
+}
+
+/// 基本的な HTML エンティティをデコードし、空白を詰める
+fn clean_text(text: &str) -> Option<String> {
+    let decoded = text
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&");
+    let flat = decoded.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!flat.is_empty()).then_some(flat)
+}
+
+/// HTML から OpenGraph のタイトル・説明・画像を抽出する
+///
+/// `base_url` は相対 URL の画像を絶対 URL に変換するために使います。
+pub fn parse_open_graph(html: &str, base_url: &str) -> LinkPreview {
+    let mut preview = LinkPreview { url: base_url.to_string(), ..Default::default() };
+    let mut fallback_description = None;
+
+    for tag in meta_regex().find_iter(html) {
+        let mut key = None;
+        let mut content = None;
+        for cap in attribute_regex().captures_iter(tag.as_str()) {
+            let value = cap.get(2).or_else(|| cap.get(3)).map(|m| m.as_str()).unwrap_or_default();
+            match cap[1].to_lowercase().as_str() {
+                "property" | "name" => key = Some(value.to_lowercase()),
+                "content" => content = clean_text(value),
+                _ => {}
+            }
+        }
+        let (Some(key), Some(content)) = (key, content) else { continue };
+        match key.as_str() {
+            "og:title" => preview.title = preview.title.or(Some(content)),
+            "og:description" => preview.description = preview.description.or(Some(content)),
+            "og:image" | "og:image:url" => preview.image = preview.image.or(Some(content)),
+            "og:site_name" => preview.site_name = preview.site_name.or(Some(content)),
+            "description" => fallback_description = fallback_description.or(Some(content)),
+            _ => {}
+        }
+    }
+
+    if preview.title.is_none() {
+        preview.title = title_regex()
+            .captures(html)
+            .and_then(|cap| clean_text(&cap[1]));
+    }
+    preview.description = preview.description.or(fallback_description).map(|d| {
+        if d.chars().count() > MAX_DESCRIPTION_CHARS {
+            format!("{}…", d.chars().take(MAX_DESCRIPTION_CHARS).collect::<String>())
+        } else {
+            d
+        }
+    });
+    preview.image = preview.image.and_then(|image| {
+        reqwest::Url::parse(base_url)
+            .and_then(|base| base.join(&image))
+            .map(|url| url.to_string())
+            .ok()
+    });
+    preview
+}
+
+/// グローバルに到達可能なアドレスか（ループバック・プライベート・リンクローカルなどを除く）
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                || a >= 240
+                // 100.64.0.0/10（CGNAT）、198.18.0.0/15（ベンチマーク用）
+                || (a == 100 && (b & 0xc0) == 64)
+                || (a == 198 && (b & 0xfe) == 18))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // fc00::/7（ユニークローカル）、fe80::/10（リンクローカル）、2001:db8::/32（ドキュメント用）
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+        }
+    }
+}
+
+/// URL のホストを解決し、すべてのアドレスがグローバルな場合に接続先のアドレスを返す
+async fn resolve_public_addr(url: &reqwest::Url) -> Result<SocketAddr> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(anyhow!("http(s) 以外の URL には接続しません: {}", url));
+    }
+    let host = url.host_str()
+        .ok_or_else(|| anyhow!("ホストのない URL には接続しません: {}", url))?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .with_context(|| format!("ホスト名の解決に失敗: {}", host))?
+        .collect();
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        return Err(anyhow!("グローバルでないアドレス ({}) を指す URL には接続しません: {}", addr.ip(), url));
+    }
+    addrs.into_iter().next().ok_or_else(|| anyhow!("ホスト名を解決できません: {}", host))
+}
+
+/// グローバルなアドレスにだけ接続して URL を GET する
+///
+/// 接続は解決時に確認したアドレスに固定し、リダイレクトは 1 回ずつ接続先を確認しながら
+/// 最大 `MAX_REDIRECTS` 回までたどります。グローバルでないアドレスを指す URL（リダイレクト先を含む）は
+/// エラーとして返します。HTTP エラーのステータスはそのまま返します。
+pub(crate) async fn get_public_url(url: &str, accept: &str, timeout: Duration) -> Result<reqwest::Response> {
+    let mut current = reqwest::Url::parse(url).with_context(|| format!("URL の解析に失敗: {}", url))?;
+
+    let mut redirects = 0;
+    loop {
+        let addr = resolve_public_addr(&current).await?;
+        let host = current.host_str().unwrap_or_default().to_string();
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve(&host, addr)
+            .timeout(timeout)
+            .build()
+            .context("HTTP クライアントの作成に失敗")?;
+        let response = client
+            .get(current.clone())
+            .header("Accept", accept)
+            .send()
+            .await
+            .context("ページの取得に失敗")?;
+        if !response.status().is_redirection() {
+            return Ok(response);
+        }
+        if redirects == MAX_REDIRECTS {
+            return Err(anyhow!("リダイレクトが多すぎます: {}", url));
+        }
+        redirects += 1;
+        let location = response.headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| anyhow!("リダイレクト先がありません ({})", response.status()))?;
+        current = current.join(location).context("リダイレクト先の URL が不正です")?;
+    }
+}
+
+/// レスポンスの本文を最大 `max_bytes` バイトまで読み込む
+///
+/// 戻り値は（本文, 上限に達して残りを読まなかったか）です。
+pub(crate) async fn read_body_limited(response: &mut reqwest::Response, max_bytes: usize) -> Result<(Vec<u8>, bool)> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.context("レスポンスの読み込みに失敗")? {
+        body.extend_from_slice(&chunk);
+        if body.len() > max_bytes {
+            body.truncate(max_bytes);
+            return Ok((body, true));
+        }
+    }
+    Ok((body, false))
+}
+
+/// URL のページを取得して OpenGraph 情報を抽出する
+///
+/// HTML 以外のレスポンス、タイムアウト、HTTP エラー、グローバルでないアドレスを指す URL
+/// （リダイレクト先を含む）はエラーとして返します（`get_public_url`）。
+pub async fn fetch_link_preview(url: &str) -> Result<LinkPreview> {
+    debug!("リンクプレビュー取得: {}", url);
+
+    let mut response = get_public_url(url, "text/html", LINK_PREVIEW_TIMEOUT).await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("ページの取得エラー ({})", response.status()));
+    }
+    let is_html = response.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("html"));
+    if !is_html {
+        return Err(anyhow!("HTML ではないためプレビューできません: {}", url));
+    }
+
+    let final_url = response.url().to_string();
+    let (body, _) = read_body_limited(&mut response, MAX_HTML_BYTES).await?;
+
+    let mut preview = parse_open_graph(&String::from_utf8_lossy(&body), &final_url);
+    preview.url = url.to_string();
+    Ok(preview)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_open_graph() {
+        let html = r#"<html><head>
+            <title>Fallback &amp; Title</title>
+            <meta property="og:title" content="OG &quot;Title&quot;">
+            <meta content='Site' property='og:site_name'>
+            <meta name="description" content="Plain description">
+            <meta property="og:image" content="/img/cover.png">
+            </head><body></body></html>"#;
+        let preview = parse_open_graph(html, "https://example.com/posts/1");
+        assert_eq!(preview.title.as_deref(), Some("OG \"Title\""));
+        assert_eq!(preview.description.as_deref(), Some("Plain description"));
+        assert_eq!(preview.image.as_deref(), Some("https://example.com/img/cover.png"));
+        assert_eq!(preview.site_name.as_deref(), Some("Site"));
+
+        // OpenGraph がなければ title 要素を使う
+        let preview = parse_open_graph("<title>\n  Only  title </title>", "https://example.com/");
+        assert_eq!(preview.title.as_deref(), Some("Only title"));
+        assert!(preview.image.is_none());
+
+        assert!(parse_open_graph("<p>no metadata</p>", "https://example.com/").is_empty());
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        for ip in ["93.184.216.34", "1.1.1.1", "2606:4700::1111"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1", "10.0.0.1", "172.16.5.4", "192.168.1.1", "169.254.169.254", "0.0.0.0",
+            "100.64.0.1", "255.255.255.255", "::1", "::", "fd00::1", "fe80::1", "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_link_preview_rejects_private_hosts() {
+        for url in ["http://127.0.0.1:8080/", "http://[::1]/", "http://169.254.169.254/latest/meta-data/", "ftp://example.com/"] {
+            assert!(fetch_link_preview(url).await.is_err(), "{}", url);
+        }
+        let err = fetch_link_preview("http://10.0.0.1/").await.unwrap_err();
+        assert!(err.to_string().contains("グローバルでないアドレス"));
+    }
+}
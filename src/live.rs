@@ -0,0 +1,322 @@
+//! ライブアクティビティモジュール (NIP-53)
+//!
+//! 配信 (Kind 30311) の一覧とライブチャット (Kind 1311) の取得・投稿を扱います。
+
+use anyhow::{anyhow, Context, Result};
+use nostr_sdk::prelude::*;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::nostr_client::{current_unix_timestamp, extract_tag_value, AuthorInfo, NostrClient, PublishResult};
+
+/// 更新が途絶えた配信を終了とみなすまでの秒数（NIP-53 の推奨値）
+const LIVE_STREAM_STALE_SECS: u64 = 60 * 60;
+
+/// ライブアクティビティ情報（NIP-53）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LiveStreamInfo {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// naddr 形式のアドレス（チャットの取得・投稿に使用）
+    pub naddr: String,
+    /// タイトル
+    pub title: String,
+    /// 概要
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// サムネイル画像 URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// 配信 URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub streaming_url: Option<String>,
+    /// ステータス（planned / live / ended）
+    pub status: String,
+    /// 開始日時の Unix タイムスタンプ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub starts: Option<u64>,
+    /// 現在の視聴者数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_participants: Option<u64>,
+    /// 参加者（ホスト・スピーカー等）
+    pub participants: Vec<LiveParticipant>,
+    /// ハッシュタグ
+    pub hashtags: Vec<String>,
+    /// イベント作成者の情報
+    pub author: AuthorInfo,
+    /// ライブチャット（include_chat 指定時のみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat: Option<Vec<LiveChatMessage>>,
+}
+
+/// ライブアクティビティの参加者
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LiveParticipant {
+    /// 参加者の情報
+    #[serde(flatten)]
+    pub author: AuthorInfo,
+    /// 役割（Host、Speaker 等）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+}
+
+/// ライブチャットメッセージ（Kind 1311）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LiveChatMessage {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// メッセージ本文
+    pub content: String,
+    /// 作成日時（Unix タイムスタンプ）
+    pub created_at: u64,
+    /// 投稿者の情報
+    pub author: AuthorInfo,
+}
+
+impl NostrClient {
+    /// 配信中のライブアクティビティ (Kind 30311) を取得します（NIP-53）。
+    ///
+    /// `follows_only` が true で認証済みの場合は、フォロー中ユーザーが作成または
+    /// 参加者（p タグ）として含まれる配信に限定します。
+    pub async fn get_live_streams(&self, follows_only: bool, include_chat: bool, chat_limit: u64, limit: u64) -> Result<Vec<LiveStreamInfo>> {
+        let base = Filter::new().kind(Kind::LiveEvent).limit(200);
+
+        let filters = match self.public_key {
+            Some(pk) if follows_only => {
+                let followed = self.fetch_followed_pubkeys(pk).await;
+                if followed.is_empty() {
+                    vec![base]
+                } else {
+                    vec![
+                        base.clone().authors(followed.clone()),
+                        base.custom_tag(SingleLetterTag::lowercase(Alphabet::P), followed.iter().map(|pk| pk.to_hex())),
+                    ]
+                }
+            }
+            _ => vec![base],
+        };
+
+        let events = self.fetch_events(filters, Duration::from_secs(15))
+            .await
+            .context("ライブアクティビティの取得に失敗しました")?;
+
+        // 同じアドレスの古いバージョンを除外
+        let mut latest: HashMap<(PublicKey, String), Event> = HashMap::new();
+        for event in events.into_iter() {
+            let key = (event.pubkey, extract_tag_value(&event, "d").unwrap_or_default());
+            match latest.get(&key) {
+                Some(existing) if existing.created_at >= event.created_at => {}
+                _ => {
+                    latest.insert(key, event);
+                }
+            }
+        }
+
+        let now = current_unix_timestamp();
+        let mut live_events: Vec<Event> = latest.into_values()
+            .filter(|event| is_live_stream_active(event, now))
+            .collect();
+        live_events.sort_by_key(|event| Reverse(event.created_at));
+        live_events.truncate(limit as usize);
+
+        let mut pubkeys = Self::collect_pubkeys(&live_events);
+        for event in &live_events {
+            pubkeys.extend(event.tags.public_keys().copied());
+        }
+        pubkeys.sort();
+        pubkeys.dedup();
+        let profiles = self.fetch_profiles(&pubkeys).await;
+
+        let mut streams: Vec<LiveStreamInfo> = live_events.iter()
+            .map(|event| Self::event_to_live_stream(event, &profiles))
+            .collect();
+
+        // すべての配信のチャットを 1 回の取得でまとめて取得する
+        if include_chat && !live_events.is_empty() {
+            let coordinates: Vec<Coordinate> = live_events.iter()
+                .map(|event| {
+                    Coordinate::new(event.kind, event.pubkey)
+                        .identifier(extract_tag_value(event, "d").unwrap_or_default())
+                })
+                .collect();
+            match self.fetch_live_chats(&coordinates, chat_limit).await {
+                Ok(chats) => {
+                    for (info, chat) in streams.iter_mut().zip(chats) {
+                        info.chat = Some(chat);
+                    }
+                }
+                Err(e) => warn!("ライブチャットの取得に失敗: {}", e),
+            }
+        }
+
+        Ok(streams)
+    }
+
+    /// ライブアクティビティのチャットを取得します（Kind 1311, NIP-53）。
+    pub async fn get_live_chat(&self, stream: &str, limit: u64) -> Result<Vec<LiveChatMessage>> {
+        let coordinate = Self::parse_live_coordinate(stream)?;
+        let mut chats = self.fetch_live_chats(&[coordinate], limit).await?;
+        Ok(chats.pop().unwrap_or_default())
+    }
+
+    /// ライブアクティビティのチャットにメッセージを投稿します（Kind 1311, NIP-53）。
+    pub async fn post_live_chat_message(&self, stream: &str, content: &str) -> Result<PublishResult> {
+        self.require_write_access()?;
+
+        let coordinate = Self::parse_live_coordinate(stream)?;
+
+        let builder = EventBuilder::new(Kind::LiveEventMessage, content)
+            .tag(Tag::coordinate(coordinate));
+
+        let output = self.send_event_builder(builder).await
+            .context("ライブチャットへの投稿に失敗しました")?;
+
+        let event_id = *output.id();
+        info!("ライブチャットに投稿しました: {}", event_id);
+        Ok(PublishResult::new(output, self.public_key))
+    }
+
+    /// ライブアクティビティのアドレスをパースし、Kind 30311 であることを確認するヘルパー
+    pub(crate) fn parse_live_coordinate(stream: &str) -> Result<Coordinate> {
+        let coordinate = Self::parse_coordinate(stream)?;
+        if coordinate.kind != Kind::LiveEvent {
+            return Err(anyhow!("ライブアクティビティ (Kind 30311) のアドレスを指定してください"));
+        }
+        Ok(coordinate)
+    }
+
+    /// 複数のライブアクティビティのチャット (Kind 1311) を古い順に取得するヘルパー
+    ///
+    /// すべての配信を 1 つのフィルタ（最大 `limit` × 配信数 件）で取得し、配信ごとに最大 `limit` 件へ振り分けて
+    /// `coordinates` と同じ順に返します。
+    async fn fetch_live_chats(&self, coordinates: &[Coordinate], limit: u64) -> Result<Vec<Vec<LiveChatMessage>>> {
+        if coordinates.is_empty() {
+            return Ok(Vec::new());
+        }
+        // リレーは 1 回の REQ に含められるフィルター数を制限しているため、
+        // すべての配信のアドレスを 1 つのフィルターにまとめ、配信ごとの振り分けは取得後に行う
+        let filter = Filter::new()
+            .kind(Kind::LiveEventMessage)
+            .coordinates(coordinates)
+            .limit((limit as usize).saturating_mul(coordinates.len()));
+
+        let events = self.fetch_events(vec![filter], Duration::from_secs(10))
+            .await
+            .context("ライブチャットの取得に失敗しました")?;
+        let events_vec: Vec<Event> = events.into_iter().collect();
+
+        let pubkeys = Self::collect_pubkeys(&events_vec);
+        let profiles = self.fetch_profiles(&pubkeys).await;
+
+        Ok(coordinates.iter().map(|coordinate| {
+            let mut chat: Vec<&Event> = events_vec.iter()
+                .filter(|event| {
+                    // リレーヒントの違いは無視してアドレスで照合する
+                    event.tags.coordinates().any(|c| {
+                        c.kind == coordinate.kind
+                            && c.public_key == coordinate.public_key
+                            && c.identifier == coordinate.identifier
+                    })
+                })
+                .collect();
+            chat.sort_by_key(|e| Reverse(e.created_at));
+            chat.truncate(limit as usize);
+            chat.reverse();
+
+            chat.into_iter().map(|event| LiveChatMessage {
+                id: event.id.to_hex(),
+                content: event.content.clone(),
+                created_at: event.created_at.as_u64(),
+                author: profiles
+                    .get(&event.pubkey)
+                    .cloned()
+                    .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey)),
+            }).collect()
+        }).collect())
+    }
+
+    /// ライブアクティビティイベントから表示用情報に変換するヘルパー
+    fn event_to_live_stream(event: &Event, profiles: &HashMap<PublicKey, AuthorInfo>) -> LiveStreamInfo {
+        let identifier = extract_tag_value(event, "d").unwrap_or_default();
+        let author_info = |pk: &PublicKey| profiles
+            .get(pk)
+            .cloned()
+            .unwrap_or_else(|| AuthorInfo::from_public_key(pk));
+
+        // p タグ: ["p", <pubkey>, <relay>, <role>, <proof>]
+        let participants: Vec<LiveParticipant> = event.tags.iter()
+            .filter(|tag| tag.kind() == TagKind::p())
+            .filter_map(|tag| {
+                let values = tag.as_slice();
+                let pk = PublicKey::from_hex(values.get(1)?).ok()?;
+                Some(LiveParticipant {
+                    author: author_info(&pk),
+                    role: values.get(3).filter(|r| !r.is_empty()).cloned(),
+                })
+            })
+            .collect();
+
+        let hashtags: Vec<String> = event.tags.iter()
+            .filter_map(|tag| {
+                if let Some(TagStandard::Hashtag(h)) = tag.as_standardized() {
+                    Some(h.clone())
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        LiveStreamInfo {
+            id: event.id.to_hex(),
+            naddr: Coordinate::new(event.kind, event.pubkey)
+                .identifier(&identifier)
+                .to_bech32()
+                .unwrap_or_default(),
+            title: extract_tag_value(event, "title").unwrap_or_else(|| "無題".to_string()),
+            summary: extract_tag_value(event, "summary"),
+            image: extract_tag_value(event, "image"),
+            streaming_url: extract_tag_value(event, "streaming"),
+            status: extract_tag_value(event, "status").unwrap_or_else(|| "live".to_string()),
+            starts: extract_tag_value(event, "starts").and_then(|s| s.parse().ok()),
+            current_participants: extract_tag_value(event, "current_participants").and_then(|s| s.parse().ok()),
+            participants,
+            hashtags,
+            author: author_info(&event.pubkey),
+            chat: None,
+        }
+    }
+}
+
+/// ライブアクティビティが配信中かどうかを判定
+///
+/// status が live であっても、1 時間以上更新されていないイベントは終了とみなします。
+fn is_live_stream_active(event: &Event, now: u64) -> bool {
+    extract_tag_value(event, "status").as_deref() == Some("live")
+        && event.created_at.as_u64() + LIVE_STREAM_STALE_SECS >= now
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_live_stream_active() {
+        let keys = Keys::generate();
+        let live_event = |status: &str, created_at: u64| {
+            EventBuilder::new(Kind::LiveEvent, "")
+                .tags(vec![Tag::custom(TagKind::custom("status".to_string()), vec![status.to_string()])])
+                .custom_created_at(Timestamp::from(created_at))
+                .sign_with_keys(&keys)
+                .unwrap()
+        };
+
+        let now = 1_700_000_000;
+        assert!(is_live_stream_active(&live_event("live", now - 60), now));
+        assert!(!is_live_stream_active(&live_event("ended", now - 60), now));
+        assert!(!is_live_stream_active(&live_event("planned", now - 60), now));
+        // 1 時間以上更新のない配信は終了扱い
+        assert!(!is_live_stream_active(&live_event("live", now - 2 * 60 * 60), now));
+    }
+}
@@ -0,0 +1,217 @@
+//! LNURL-pay モジュール (LUD-06 / LUD-16)
+//!
+//! Lightning アドレス（`name@domain`）または LNURL（`lnurl1...`）を LNURL-pay
+//! エンドポイントに解決し、指定金額のインボイス (bolt11) を取得します。
+//! Zap リクエスト (NIP-57) の添付にも対応します。
+//!
+//! Lightning アドレスや LNURL は第三者のプロフィールから読み取るため、グローバルでないアドレスには
+//! 接続せず（`link_preview::get_public_url`）、レスポンスは `MAX_LNURL_RESPONSE_BYTES` までしか読みません。
+
+use anyhow::{anyhow, Context, Result};
+use nostr_sdk::prelude::bitcoin::bech32;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::debug;
+
+/// LNURL リクエストのタイムアウト
+const LNURL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// LNURL レスポンスの最大バイト数（payRequest・インボイスの JSON には十分な大きさ）
+const MAX_LNURL_RESPONSE_BYTES: usize = 64 * 1024;
+
+/// LNURL-pay エンドポイントの情報（LUD-06 の payRequest レスポンス）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LnurlPayInfo {
+    /// インボイス取得用のコールバック URL
+    pub callback: String,
+    /// 受け取り可能な最小金額（ミリサトシ）
+    pub min_sendable: u64,
+    /// 受け取り可能な最大金額（ミリサトシ）
+    pub max_sendable: u64,
+    /// メタデータ（JSON 文字列）
+    #[serde(default)]
+    pub metadata: String,
+    /// Zap (NIP-57) に対応しているか
+    #[serde(default)]
+    pub allows_nostr: bool,
+    /// Zap レシートを発行する公開鍵
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nostr_pubkey: Option<String>,
+    /// コメントの最大文字数（LUD-12）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment_allowed: Option<u32>,
+}
+
+/// LNURL-pay のエラーレスポンス
+#[derive(Debug, Deserialize)]
+struct LnurlError {
+    status: String,
+    #[serde(default)]
+    reason: String,
+}
+
+/// インボイス取得レスポンス
+#[derive(Debug, Deserialize)]
+struct InvoiceResponse {
+    pr: String,
+}
+
+/// Lightning アドレスまたは LNURL を LNURL-pay エンドポイントの URL に変換
+pub fn pay_endpoint_url(address: &str) -> Result<String> {
+    let address = address.trim();
+    let address = address.strip_prefix("lightning:").unwrap_or(address);
+
+    if address.to_lowercase().starts_with("lnurl1") {
+        // LUD-06: bech32 エンコードされた URL
+        let (hrp, data) = bech32::decode(address)
+            .map_err(|e| anyhow!("無効な LNURL です: {}", e))?;
+        if !hrp.as_str().eq_ignore_ascii_case("lnurl") {
+            return Err(anyhow!("無効な LNURL です: {}", address));
+        }
+        String::from_utf8(data).context("LNURL のデコードに失敗")
+    } else if let Some((name, domain)) = address.split_once('@') {
+        // LUD-16: Lightning アドレス
+        if name.is_empty() || domain.is_empty() || domain.contains('/') {
+            return Err(anyhow!("無効な Lightning アドレスです: {}", address));
+        }
+        let scheme = if domain.ends_with(".onion") { "http" } else { "https" };
+        Ok(format!("{}://{}/.well-known/lnurlp/{}", scheme, domain, name.to_lowercase()))
+    } else {
+        Err(anyhow!(
+            "Lightning アドレス（name@domain）または LNURL（lnurl1...）を指定してください: {}",
+            address
+        ))
+    }
+}
+
+/// LNURL-pay エンドポイントの情報を取得
+pub async fn fetch_pay_info(address: &str) -> Result<LnurlPayInfo> {
+    let url = pay_endpoint_url(address)?;
+
+    debug!("LNURL-pay 情報取得: {}", url);
+
+    let body = get_json(&url).await.context("LNURL-pay エンドポイントからの取得に失敗")?;
+    check_error(&body)?;
+
+    if body.get("tag").and_then(|v| v.as_str()) != Some("payRequest") {
+        return Err(anyhow!("LNURL-pay エンドポイントではありません: {}", url));
+    }
+
+    serde_json::from_value(body).context("LNURL-pay レスポンスのパースに失敗")
+}
+
+/// 指定金額のインボイスを取得
+///
+/// `zap_request` には署名済み Zap リクエスト (Kind 9734) の JSON を指定します。
+pub async fn request_invoice(
+    info: &LnurlPayInfo,
+    amount_msat: u64,
+    zap_request: Option<&str>,
+    comment: Option<&str>,
+) -> Result<String> {
+    if amount_msat < info.min_sendable || amount_msat > info.max_sendable {
+        return Err(anyhow!(
+            "金額が範囲外です（{} 〜 {} sats）",
+            info.min_sendable.div_ceil(1000),
+            info.max_sendable / 1000
+        ));
+    }
+
+    let mut query = vec![("amount", amount_msat.to_string())];
+    if let Some(zap) = zap_request {
+        query.push(("nostr", zap.to_string()));
+    }
+    if let (Some(c), Some(max)) = (comment, info.comment_allowed) {
+        if max > 0 {
+            query.push(("comment", c.chars().take(max as usize).collect()));
+        }
+    }
+
+    debug!("インボイス要求: callback={}, amount={} msat", info.callback, amount_msat);
+
+    let mut callback = reqwest::Url::parse(&info.callback).context("コールバック URL が不正です")?;
+    callback.query_pairs_mut().extend_pairs(&query);
+    let body = get_json(callback.as_str()).await.context("インボイスの要求に失敗")?;
+    check_error(&body)?;
+
+    let invoice: InvoiceResponse = serde_json::from_value(body)
+        .context("インボイスレスポンスに pr が含まれていません")?;
+    Ok(invoice.pr)
+}
+
+/// グローバルなアドレスの URL から JSON を取得（`MAX_LNURL_RESPONSE_BYTES` を超えるレスポンスはエラー）
+async fn get_json(url: &str) -> Result<serde_json::Value> {
+    let mut response = crate::link_preview::get_public_url(url, "application/json", LNURL_TIMEOUT).await?;
+    let (body, truncated) = crate::link_preview::read_body_limited(&mut response, MAX_LNURL_RESPONSE_BYTES).await?;
+    if truncated {
+        return Err(anyhow!("レスポンスが大きすぎます（{} バイト超）", MAX_LNURL_RESPONSE_BYTES));
+    }
+    serde_json::from_slice(&body).context("レスポンスの JSON のパースに失敗")
+}
+
+/// LNURL のエラーレスポンス（status: ERROR）を検出
+fn check_error(body: &serde_json::Value) -> Result<()> {
+    if let Ok(err) = serde_json::from_value::<LnurlError>(body.clone()) {
+        if err.status.eq_ignore_ascii_case("ERROR") {
+            return Err(anyhow!("LNURL エラー: {}", err.reason));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pay_endpoint_url_lightning_address() {
+        assert_eq!(
+            pay_endpoint_url("Alice@getalby.com").unwrap(),
+            "https://getalby.com/.well-known/lnurlp/alice"
+        );
+        assert!(pay_endpoint_url("not-an-address").is_err());
+        assert!(pay_endpoint_url("@example.com").is_err());
+        assert_eq!(pay_endpoint_url("alice@localhost:8080").unwrap(), "https://localhost:8080/.well-known/lnurlp/alice");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_pay_info_rejects_private_hosts() {
+        // LUD-06 の LNURL は任意の URL を指せるため、グローバルでないアドレスには接続しない
+        let lnurl = bech32::encode::<bech32::Bech32>(
+            bech32::Hrp::parse("lnurl").unwrap(),
+            b"http://169.254.169.254/latest/meta-data/",
+        )
+        .unwrap();
+        let err = fetch_pay_info(&lnurl).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("グローバルでないアドレス"));
+        assert!(fetch_pay_info("alice@127.0.0.1").await.is_err());
+    }
+
+    #[test]
+    fn test_pay_endpoint_url_lnurl() {
+        // LUD-01 の例
+        let lnurl = "LNURL1DP68GURN8GHJ7UM9WFMXJCM99E3K7MF0V9CXJ0M385EKVCENXC6R2C35XVUKXEFCV5MKVV34X5EKZD3EV56NYD3HXQURZEPEXEJXXEPNXSCRVWFNV9NXZCN9XQ6XYEFHVGCXXCMYXYMNSERXFQ5FNS";
+        assert_eq!(
+            pay_endpoint_url(lnurl).unwrap(),
+            "https://service.com/api?q=3fc3645b439ce8e7f2553a69e5267081d96dcd340693afabe04be7b0ccd178df"
+        );
+    }
+
+    #[test]
+    fn test_parse_pay_info() {
+        let json = serde_json::json!({
+            "callback": "https://example.com/callback",
+            "minSendable": 1000,
+            "maxSendable": 100000000,
+            "metadata": "[[\"text/plain\",\"test\"]]",
+            "tag": "payRequest",
+            "allowsNostr": true,
+            "nostrPubkey": "abc"
+        });
+        let info: LnurlPayInfo = serde_json::from_value(json).unwrap();
+        assert!(info.allows_nostr);
+        assert_eq!(info.min_sendable, 1000);
+        assert!(check_error(&serde_json::json!({"status": "ERROR", "reason": "bad"})).is_err());
+    }
+}
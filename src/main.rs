@@ -6,15 +6,38 @@
 //! 設定は ~/.config/rust-nostr-mcp/config.json に保存されます。
 //! 秘密鍵はローカルに保存され、AI エージェントには渡されません。
 
+mod backup;
 mod blossom;
+mod bolt11;
+mod calendar;
 mod config;
 mod content;
+mod dedup;
+mod diff;
+mod dry_run;
+mod filters;
+mod link_preview;
+mod live;
+mod lnurl;
+mod markdown;
 mod mcp;
 mod mcp_apps;
+#[cfg(all(test, feature = "mock-relay"))]
+mod mock_relay;
+mod nip05;
+mod nip11;
 mod nip46;
 mod nostr_client;
+mod ots;
+mod pagination;
+mod polls;
+mod rate_limit;
+mod storage;
+mod subscriptions;
 mod tools;
 mod ui_templates;
+mod wiki;
+mod zaps;
 
 use anyhow::Result;
 use tracing::{info, warn};
@@ -54,13 +77,17 @@ fn load_config() -> NostrClientConfig {
     };
 
     let secret_key = config.privatekey.clone();
+    let public_key = config.publickey.clone();
 
-    if secret_key.is_none() {
+    if secret_key.is_none() && public_key.is_some() {
+        info!("公開鍵が設定されています。自分のタイムライン・通知を読める読み取り専用モードで起動します。");
+    } else if secret_key.is_none() {
         warn!("秘密鍵が設定されていません。読み取り専用モードで起動します。");
         warn!("書き込みアクセスを有効にするには、nsec を設定ファイルに追加してください: {:?}", Config::config_path().unwrap_or_default());
     }
 
     let relays = config.read_relays();
+    let write_relays = config.write_relays();
     let search_relays = config.search_relays();
     let nwc_uri = config.nwc_uri.clone();
     let auth_mode = config.effective_auth_mode();
@@ -69,6 +96,24 @@ fn load_config() -> NostrClientConfig {
         info!("  - NWC (Nostr Wallet Connect): 設定済み");
     }
 
+    let payment_limits = config.payment_limits.clone().unwrap_or_default();
+    let web_of_trust = config.web_of_trust;
+    let contact_list_cache_ttl = config.contact_list_cache_ttl();
+    let rate_limit = config.rate_limit.clone().unwrap_or_default();
+    let signature_verification = config.verify_signatures.unwrap_or_default();
+    let min_pow = config.min_pow.unwrap_or(0);
+    let content_filters = config.filters.clone().unwrap_or_default();
+    let sensitive_content = config.sensitive_content.unwrap_or_default();
+    let languages = config.languages.clone().unwrap_or_default();
+    let delegation = config.delegation.clone();
+    let output = config.output_options();
+    let dry_run = config.dry_run.unwrap_or(false);
+    let default_reaction = config.default_reaction.clone();
+    let reaction_presets = config.reaction_presets.clone().unwrap_or_default();
+    if dry_run {
+        warn!("  - ドライラン: 有効（書き込みツールはイベントを公開せずに返します）");
+    }
+
     // NIP-46 設定の構築
     let nip46_config = match auth_mode {
         AuthMode::Nip46 | AuthMode::Bunker => {
@@ -84,11 +129,27 @@ fn load_config() -> NostrClientConfig {
 
     NostrClientConfig {
         secret_key,
+        public_key,
         relays,
+        write_relays,
         search_relays,
         nwc_uri,
+        payment_limits,
+        web_of_trust,
+        contact_list_cache_ttl,
+        rate_limit,
+        signature_verification,
+        min_pow,
+        content_filters,
+        sensitive_content,
+        languages,
         auth_mode,
         nip46_config,
+        delegation,
+        output,
+        dry_run,
+        default_reaction,
+        reaction_presets,
     }
 }
 
@@ -0,0 +1,407 @@
+//! ツール結果の Markdown レンダリングモジュール
+//!
+//! `output_format: "markdown"` が指定されたツール呼び出しの結果（JSON）を、生の JSON を
+//! そのまま表示するホストでも読みやすい Markdown に変換します。対応しているのは
+//! タイムライン系のノート一覧・スレッド・リプライの文脈・プロフィール・長文記事で、それ以外のツールは
+//! None を返し、呼び出し元で JSON のまま返します。
+
+use serde_json::Value;
+use std::fmt::Write;
+
+/// 出力形式として受け付ける値
+pub const OUTPUT_FORMATS: &[&str] = &["json", "markdown"];
+
+/// ツール結果を Markdown に変換します。対応していないツールの場合は None を返します。
+pub fn render(tool: &str, result: &Value) -> Option<String> {
+    let markdown = match tool {
+        "get_nostr_timeline" | "get_watched_feeds" | "search_nostr_notes" | "get_nostr_notes" => {
+            render_notes(result)
+        }
+        "get_nostr_thread" => render_thread(result),
+        "get_reply_context" => render_reply_context(result),
+        "get_nostr_profile" => render_profile(result),
+        "get_nostr_profiles" => render_profiles(result),
+        "get_nostr_articles" | "search_nostr_articles" | "get_nostr_drafts" => render_articles(result),
+        _ => return None,
+    };
+    Some(markdown)
+}
+
+/// JSON の文字列フィールド（空文字列は None）
+fn str_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(|v| v.as_str()).filter(|s| !s.trim().is_empty())
+}
+
+/// 著者の表示名（display → display_name → name → npub の順）
+fn author_label(author: &Value) -> String {
+    ["display", "display_name", "name", "npub"]
+        .iter()
+        .find_map(|key| str_field(author, key))
+        .unwrap_or("不明なユーザー")
+        .to_string()
+}
+
+/// 複数行のテキストを引用ブロックにします（`prefix` はネスト用のインデント）。
+fn blockquote(out: &mut String, text: &str, prefix: &str) {
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            let _ = writeln!(out, "{}>", prefix);
+        } else {
+            let _ = writeln!(out, "{}> {}", prefix, line);
+        }
+    }
+}
+
+/// ノート 1 件を「著者・日時・本文・反応数」の形で書き出します。
+fn write_note(out: &mut String, note: &Value, prefix: &str) {
+    let author = note.get("author").map(author_label).unwrap_or_else(|| "不明なユーザー".to_string());
+    let mut header = format!("**{}**", author);
+    if let Some(nip05) = note.get("author").and_then(|a| str_field(a, "nip05")) {
+        let verified = note["author"]["nip05_verified"].as_bool() == Some(true);
+        let _ = write!(header, " ({}{})", nip05, if verified { " ✓" } else { "" });
+    }
+    if let Some(time) = str_field(note, "formatted_time") {
+        let _ = write!(header, " · {}", time);
+    }
+    let _ = writeln!(out, "{}{}", prefix, header);
+
+    if let Some(reposted_by) = note.get("reposted_by").filter(|v| !v.is_null()) {
+        let _ = writeln!(out, "{}🔁 {} がリポスト", prefix, author_label(reposted_by));
+    }
+    if let Some(warning) = str_field(note, "content_warning") {
+        let _ = writeln!(out, "{}⚠️ コンテンツ警告: {}", prefix, warning);
+    }
+
+    let _ = writeln!(out, "{}", prefix.trim_end());
+    if note["content_hidden"].as_bool() == Some(true) {
+        blockquote(out, "（本文は非表示です）", prefix);
+    } else {
+        blockquote(out, note["content"].as_str().unwrap_or_default(), prefix);
+    }
+    if note["content_truncated"].as_bool() == Some(true) {
+        let _ = writeln!(out, "{}>\n{}> （本文を切り詰めました）", prefix, prefix);
+    }
+    let _ = writeln!(out, "{}", prefix.trim_end());
+
+    let mut footer = Vec::new();
+    if let Some(reactions) = note["reactions"].as_u64() {
+        footer.push(format!("❤️ {}", reactions));
+    }
+    if let Some(replies) = note["replies"].as_u64() {
+        footer.push(format!("💬 {}", replies));
+    }
+    if let Some(reposts) = note["reposts"].as_u64().filter(|&n| n > 0) {
+        footer.push(format!("🔁 {}", reposts));
+    }
+    if let Some(zap_sats) = note["zap_sats"].as_u64().filter(|&n| n > 0) {
+        footer.push(format!("⚡ {} sats", zap_sats));
+    }
+    if let Some(nevent) = str_field(note, "nevent") {
+        footer.push(format!("`{}`", nevent));
+    }
+    if !footer.is_empty() {
+        let _ = writeln!(out, "{}{}", prefix, footer.join(" · "));
+    }
+}
+
+/// ノート一覧（タイムライン・検索結果など）
+fn render_notes(result: &Value) -> String {
+    let notes = result["notes"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let mut out = String::new();
+
+    let title = match str_field(result, "query") {
+        Some(query) => format!("「{}」の検索結果", query),
+        None => "タイムライン".to_string(),
+    };
+    let _ = writeln!(out, "# {}（{} 件）\n", title, notes.len());
+
+    if notes.is_empty() {
+        out.push_str("ノートはありません。\n");
+        return out;
+    }
+    for (i, note) in notes.iter().enumerate() {
+        if i > 0 {
+            out.push_str("\n---\n\n");
+        }
+        write_note(&mut out, note, "");
+    }
+    out
+}
+
+/// スレッド内のリプライを深さに応じてインデントして書き出します。
+fn write_replies(out: &mut String, replies: &[Value], level: usize) {
+    let prefix = "  ".repeat(level);
+    for reply in replies {
+        out.push('\n');
+        let _ = writeln!(out, "{}- ↳", prefix);
+        write_note(out, &reply["note"], &format!("{}  ", prefix));
+        if let Some(children) = reply["replies"].as_array() {
+            write_replies(out, children, level + 1);
+        }
+    }
+}
+
+/// スレッド（ルートノートとネストしたリプライ）
+fn render_thread(result: &Value) -> String {
+    let mut out = String::from("# スレッド\n\n");
+    write_note(&mut out, &result["root"], "");
+
+    let replies = result["replies"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let total = result["total_replies"].as_u64().unwrap_or(replies.len() as u64);
+    let _ = writeln!(out, "\n## リプライ（{} 件）", total);
+    if replies.is_empty() {
+        out.push_str("\nリプライはありません。\n");
+    } else {
+        write_replies(&mut out, replies, 0);
+    }
+    out
+}
+
+/// リプライの文脈（ルート・親・対象のノートと著者の関連ノート）
+fn render_reply_context(result: &Value) -> String {
+    let mut out = String::from("# リプライの文脈\n");
+    for (key, heading) in [("root", "スレッドのルート"), ("parent", "親ノート"), ("note", "返信するノート")] {
+        if let Some(note) = result.get(key).filter(|v| v.is_object()) {
+            let _ = writeln!(out, "\n## {}\n", heading);
+            write_note(&mut out, note, "");
+        }
+    }
+
+    let related = result["related"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let _ = writeln!(out, "\n## 著者の関連ノート（{} 件）", related.len());
+    if related.is_empty() {
+        out.push_str("\n関連するノートはありません。\n");
+    }
+    for note in related {
+        let relation = match note["relation"].as_str() {
+            Some("reply_to_note") => "このノートへの返信",
+            Some("same_thread") => "同じスレッド",
+            Some("shared_hashtag") => "共通のハッシュタグ",
+            _ => "関連",
+        };
+        let _ = writeln!(out, "\n- {}", relation);
+        write_note(&mut out, note, "  ");
+    }
+    out
+}
+
+/// プロフィールの見出しと基本情報を書き出します。
+fn write_profile(out: &mut String, profile: &Value, heading: &str) {
+    let _ = writeln!(out, "{} {}\n", heading, author_label(profile));
+    if let Some(name) = str_field(profile, "name") {
+        if str_field(profile, "display_name").is_some_and(|display| display != name) {
+            let _ = writeln!(out, "- ユーザー名: {}", name);
+        }
+    }
+    let fields = [("nip05", "NIP-05"), ("lud16", "Lightning"), ("website", "ウェブサイト"), ("npub", "npub")];
+    for (key, label) in fields {
+        if let Some(value) = str_field(profile, key) {
+            let value = if key == "npub" { format!("`{}`", value) } else { value.to_string() };
+            let _ = writeln!(out, "- {}: {}", label, value);
+        }
+    }
+    if let Some(about) = str_field(profile, "about") {
+        out.push('\n');
+        blockquote(out, about, "");
+    }
+}
+
+/// プロフィール（統計情報があれば表にする）
+fn render_profile(result: &Value) -> String {
+    let mut out = String::new();
+    write_profile(&mut out, &result["profile"], "#");
+
+    if let Some(stats) = result["profile_card"].get("stats").filter(|v| v.is_object()) {
+        out.push_str("\n| フォロー | フォロワー | ノート |\n| ---: | ---: | ---: |\n");
+        let cell = |key: &str| stats[key].as_u64().map(|n| n.to_string()).unwrap_or_else(|| "-".to_string());
+        let _ = writeln!(out, "| {} | {} | {} |", cell("following"), cell("followers"), cell("notes"));
+    }
+    out
+}
+
+/// 複数のプロフィール
+fn render_profiles(result: &Value) -> String {
+    let profiles = result["profiles"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let mut out = format!("# プロフィール（{} 件）\n", profiles.len());
+    for profile in profiles {
+        out.push('\n');
+        write_profile(&mut out, profile, "##");
+    }
+    if let Some(not_found) = result["not_found"].as_array().filter(|v| !v.is_empty()) {
+        let keys: Vec<&str> = not_found.iter().filter_map(|v| v.as_str()).collect();
+        let _ = writeln!(out, "\n見つからなかったアカウント: {}", keys.join(", "));
+    }
+    out
+}
+
+/// 長文記事の一覧（本文は Markdown のまま埋め込む）
+fn render_articles(result: &Value) -> String {
+    let articles = result["articles"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let mut out = String::new();
+
+    let title = match str_field(result, "query") {
+        Some(query) => format!("「{}」の記事検索結果", query),
+        None => "記事".to_string(),
+    };
+    let _ = writeln!(out, "# {}（{} 件）", title, articles.len());
+    if articles.is_empty() {
+        out.push_str("\n記事はありません。\n");
+        return out;
+    }
+
+    for (i, article) in articles.iter().enumerate() {
+        if i > 0 {
+            out.push_str("\n---\n");
+        }
+        let title = str_field(article, "title").unwrap_or("（無題）");
+        let draft = if article["is_draft"].as_bool() == Some(true) { "（下書き）" } else { "" };
+        let _ = writeln!(out, "\n## {}{}\n", title, draft);
+
+        let mut byline = Vec::new();
+        if let Some(author) = article.get("author").filter(|v| v.is_object()) {
+            byline.push(format!("**{}**", author_label(author)));
+        }
+        if let Some(time) = str_field(article, "formatted_time") {
+            byline.push(time.to_string());
+        }
+        if let Some(minutes) = article["reading_time_minutes"].as_u64().filter(|&m| m > 0) {
+            byline.push(format!("約 {} 分で読めます", minutes));
+        }
+        if let Some(naddr) = str_field(article, "naddr") {
+            byline.push(format!("`{}`", naddr));
+        }
+        if !byline.is_empty() {
+            let _ = writeln!(out, "{}\n", byline.join(" · "));
+        }
+        if let Some(tags) = article["tags"].as_array().filter(|v| !v.is_empty()) {
+            let tags: Vec<String> = tags.iter().filter_map(|t| t.as_str()).map(|t| format!("#{}", t)).collect();
+            let _ = writeln!(out, "{}\n", tags.join(" "));
+        }
+        if let Some(summary) = str_field(article, "summary") {
+            blockquote(&mut out, summary, "");
+            out.push('\n');
+        }
+        match article["content"].as_str() {
+            Some(content) => {
+                let _ = writeln!(out, "{}", content.trim_end());
+            }
+            // 本文を省いた場合は目次を表示する
+            None => {
+                for heading in article["toc"].as_array().map(Vec::as_slice).unwrap_or_default() {
+                    let level = heading["level"].as_u64().unwrap_or(1).saturating_sub(1) as usize;
+                    let _ = writeln!(out, "{}- {}", "  ".repeat(level), heading["title"].as_str().unwrap_or_default());
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn note(author: &str, content: &str) -> Value {
+        json!({
+            "nevent": "nevent1abc",
+            "author": {"display": author, "nip05": "alice@example.com", "nip05_verified": true},
+            "content": content,
+            "formatted_time": "2026-01-01 09:00",
+            "reactions": 3,
+            "replies": 1
+        })
+    }
+
+    #[test]
+    fn test_render_notes() {
+        let result = json!({"notes": [note("Alice", "hello\n\nworld"), note("Bob", "gm")]});
+        let markdown = render("get_nostr_timeline", &result).unwrap();
+
+        assert!(markdown.starts_with("# タイムライン（2 件）"));
+        assert!(markdown.contains("**Alice** (alice@example.com ✓) · 2026-01-01 09:00"));
+        assert!(markdown.contains("> hello\n>\n> world\n"));
+        assert!(markdown.contains("❤️ 3 · 💬 1 · `nevent1abc`"));
+        assert!(markdown.contains("\n---\n"));
+
+        let mut zapped = note("Carol", "zap me");
+        zapped["reposts"] = json!(2);
+        zapped["zap_sats"] = json!(2100);
+        let markdown = render("get_nostr_notes", &json!({"notes": [zapped]})).unwrap();
+        assert!(markdown.contains("❤️ 3 · 💬 1 · 🔁 2 · ⚡ 2100 sats · `nevent1abc`"));
+
+        let search = render("search_nostr_notes", &json!({"query": "nostr", "notes": []})).unwrap();
+        assert!(search.contains("「nostr」の検索結果（0 件）"));
+        assert!(render("post_nostr_note", &result).is_none());
+    }
+
+    #[test]
+    fn test_render_thread_nests_replies() {
+        let result = json!({
+            "root": note("Alice", "root"),
+            "replies": [{"note": note("Bob", "reply"), "replies": [{"note": note("Carol", "nested"), "replies": []}]}],
+            "total_replies": 2
+        });
+        let markdown = render("get_nostr_thread", &result).unwrap();
+
+        assert!(markdown.contains("## リプライ（2 件）"));
+        assert!(markdown.contains("\n- ↳\n  **Bob**"));
+        assert!(markdown.contains("\n    > nested\n"));
+    }
+
+    #[test]
+    fn test_render_reply_context() {
+        let mut related = note("Bob", "続き");
+        related["relation"] = json!("same_thread");
+        let result = json!({
+            "note": note("Bob", "reply"),
+            "root": note("Alice", "root"),
+            "parent": null,
+            "related": [related]
+        });
+        let markdown = render("get_reply_context", &result).unwrap();
+
+        assert!(markdown.contains("## スレッドのルート
+
+**Alice**"));
+        assert!(!markdown.contains("## 親ノート"));
+        assert!(markdown.find("## スレッドのルート") < markdown.find("## 返信するノート"));
+        assert!(markdown.contains("## 著者の関連ノート（1 件）
+
+- 同じスレッド
+  **Bob**"));
+        assert!(markdown.contains("  > 続き
+"));
+    }
+
+    #[test]
+    fn test_render_profile_and_articles() {
+        let profile = json!({
+            "profile": {"name": "alice", "display_name": "Alice", "about": "hi", "npub": "npub1xyz"},
+            "profile_card": {"stats": {"following": 10, "followers": 20, "notes": null}}
+        });
+        let markdown = render("get_nostr_profile", &profile).unwrap();
+        assert!(markdown.starts_with("# Alice\n"));
+        assert!(markdown.contains("- ユーザー名: alice"));
+        assert!(markdown.contains("| 10 | 20 | - |"));
+
+        let articles = json!({"articles": [{
+            "title": "Title", "summary": "sum", "content": "## Body", "is_draft": true,
+            "author": {"name": "alice", "npub": "npub1xyz"}, "tags": ["nostr"]
+        }]});
+        let markdown = render("get_nostr_drafts", &articles).unwrap();
+        assert!(markdown.contains("## Title（下書き）"));
+        assert!(markdown.contains("**alice**"));
+        assert!(markdown.contains("#nostr"));
+        assert!(markdown.contains("> sum\n\n## Body"));
+
+        // 本文を省いた場合は読了時間と目次を表示する
+        let outline = json!({"articles": [{
+            "title": "Guide", "reading_time_minutes": 3,
+            "toc": [{"level": 1, "title": "Intro"}, {"level": 2, "title": "Setup"}]
+        }]});
+        let markdown = render("get_nostr_articles", &outline).unwrap();
+        assert!(markdown.contains("約 3 分で読めます"));
+        assert!(markdown.contains("\n- Intro\n  - Setup\n"));
+    }
+}
@@ -11,13 +11,16 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::io::{BufRead, Write};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
 use crate::config::AuthMode;
+use crate::markdown;
 use crate::mcp_apps;
-use crate::nip46::{Nip46Config, Nip46Session};
-use crate::nostr_client::{NostrClient, NostrClientConfig};
+use crate::nip46::{self, Nip46Config, Nip46Registry, DEFAULT_SIGNER_LABEL};
+use crate::nostr_client::{self, NostrClient, NostrClientConfig};
 use crate::tools::{get_tool_definitions, ToolExecutor};
 
 /// MCP プロトコルバージョン
@@ -27,6 +30,9 @@ const MCP_VERSION: &str = "2024-11-05";
 const SERVER_NAME: &str = "nostr-mcp-server";
 const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// シャットダウン時にバックグラウンドタスク（送信待ちの再送・NIP-46 死活監視）の終了を待つ時間
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// JSON-RPC リクエスト構造体
 #[derive(Debug, Deserialize)]
 struct JsonRpcRequest {
@@ -81,6 +87,47 @@ impl JsonRpcResponse {
     }
 }
 
+/// stdin を専用スレッドで 1 行ずつ読み、チャネルに送る（EOF でチャネルを閉じる）
+///
+/// ブロッキングの読み取りをランタイムの外で行うことで、シグナルを受けたときに
+/// 読み取りの完了を待たずにシャットダウンできるようにする。
+fn spawn_stdin_reader() -> mpsc::UnboundedReceiver<std::io::Result<String>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let is_err = line.is_err();
+            if tx.send(line).is_err() || is_err {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// SIGINT / SIGTERM（Unix 以外では Ctrl+C）を待ち、受け取ったシグナル名を返す
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => "SIGINT",
+                _ = sigterm.recv() => "SIGTERM",
+            },
+            Err(e) => {
+                warn!("SIGTERM のハンドラを登録できません: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl+C"
+    }
+}
+
 /// MCP サーバーの実装
 pub struct McpServer {
     /// Nostr クライアント（NIP-46 切り替えのため RwLock で保護）
@@ -92,9 +139,13 @@ pub struct McpServer {
     /// クライアントが MCP Apps UI 拡張をサポートしているか
     ui_enabled: bool,
     /// NIP-46 セッション（Phase 6）
-    /// McpServer が nip46_session の所有権を保持（ToolExecutor と共有）
+    /// McpServer が nip46_registry の所有権を保持（ToolExecutor と共有）
     #[allow(dead_code)]
-    nip46_session: Arc<Nip46Session>,
+    nip46_registry: Arc<Nip46Registry>,
+    /// バックグラウンドタスクにシャットダウンを通知する送信側
+    shutdown: watch::Sender<bool>,
+    /// シャットダウン時に終了を待つバックグラウンドタスク
+    background_tasks: Vec<JoinHandle<()>>,
 }
 
 impl McpServer {
@@ -106,44 +157,83 @@ impl McpServer {
             perms: None,
             bunker_uri: None,
         });
-        let nip46_session = Arc::new(Nip46Session::new(nip46_config));
+        let nip46_registry = Arc::new(Nip46Registry::new(nip46_config));
 
         // バンカー方式の場合は起動時に自動接続
+        let mut bunker_connected = false;
         if config.auth_mode == AuthMode::Bunker {
             if let Some(ref nip46_cfg) = config.nip46_config {
                 if let Some(ref bunker_uri) = nip46_cfg.bunker_uri {
                     info!("NIP-46 バンカー方式で自動接続を開始...");
-                    if let Err(e) = nip46_session.start_bunker_connect(bunker_uri).await {
-                        warn!("NIP-46 バンカー接続に失敗: {}。ローカルモードにフォールバックします。", e);
+                    let session = nip46_registry.session(DEFAULT_SIGNER_LABEL).await;
+                    match session.start_bunker_connect(bunker_uri).await {
+                        Ok(()) => bunker_connected = true,
+                        Err(e) => warn!("NIP-46 バンカー接続に失敗: {}。ローカルモードにフォールバックします。", e),
                     }
                 }
             }
         }
 
+        let output = config.output;
         let client = Arc::new(RwLock::new(NostrClient::new(config).await?));
-        let tool_executor = ToolExecutor::new(Arc::clone(&client), Arc::clone(&nip46_session));
+
+        // 起動時に接続したバンカーをアクティブなサイナーにする
+        if bunker_connected {
+            let session = nip46_registry.session(DEFAULT_SIGNER_LABEL).await;
+            if let (Some(signer), Some(pubkey)) =
+                (session.get_nostr_connect().await, session.connected_pubkey().await)
+            {
+                client.write().await.enable_nip46_signer(signer, pubkey).await?;
+                nip46_registry.set_active(DEFAULT_SIGNER_LABEL).await;
+            }
+        }
+
+        let tool_executor = ToolExecutor::new(Arc::clone(&client), Arc::clone(&nip46_registry), output);
+
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let background_tasks = vec![
+            // リモートサイナーの死活監視（接続中のみ確認する）
+            nip46::spawn_watchdog(Arc::clone(&nip46_registry), Arc::clone(&client), shutdown_rx.clone()),
+            // リレーに届かなかったイベントの再送
+            nostr_client::spawn_pending_publish_retry(Arc::clone(&client), shutdown_rx.clone()),
+            // フォロワー数の推移の記録
+            nostr_client::spawn_follower_snapshots(Arc::clone(&client), shutdown_rx),
+        ];
 
         Ok(Self {
             client,
             tool_executor,
             initialized: false,
             ui_enabled: false,
-            nip46_session,
+            nip46_registry,
+            shutdown,
+            background_tasks,
         })
     }
 
     /// MCP サーバーを実行し、stdin からリクエストを処理して stdout にレスポンスを書き込みます。
+    ///
+    /// stdin の EOF または SIGINT / SIGTERM を受け取ると、処理中のリクエストを終えてからシャットダウンします。
     pub async fn run(mut self) -> Result<()> {
-        let stdin = std::io::stdin();
+        let mut lines = spawn_stdin_reader();
         let mut stdout = std::io::stdout();
+        let signal = shutdown_signal();
+        tokio::pin!(signal);
 
         info!("MCP サーバー準備完了。リクエストを待機中...");
 
-        for line in stdin.lock().lines() {
-            let line = match line {
-                Ok(l) => l,
-                Err(e) => {
-                    error!("stdin からの読み取りエラー: {}", e);
+        loop {
+            let line = tokio::select! {
+                line = lines.recv() => match line {
+                    Some(Ok(l)) => l,
+                    Some(Err(e)) => {
+                        error!("stdin からの読み取りエラー: {}", e);
+                        break;
+                    }
+                    None => break,
+                },
+                name = &mut signal => {
+                    info!("{} を受信しました", name);
                     break;
                 }
             };
@@ -167,13 +257,35 @@ impl McpServer {
             }
         }
 
-        // クリーンアップ
-        self.client.read().await.disconnect().await;
-        info!("MCP サーバーをシャットダウンします");
+        self.shutdown().await;
 
         Ok(())
     }
 
+    /// バックグラウンドタスクの終了を SHUTDOWN_TIMEOUT まで待ってからリレーを切断します。
+    ///
+    /// 送信待ちキューの再送タスクは終了前に再送時刻に達したイベントをもう一度送信します。
+    async fn shutdown(self) {
+        info!("MCP サーバーをシャットダウンします");
+
+        let _ = self.shutdown.send(true);
+        let tasks = async {
+            for task in self.background_tasks {
+                if let Err(e) = task.await {
+                    warn!("バックグラウンドタスクが異常終了しました: {}", e);
+                }
+            }
+        };
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, tasks).await.is_err() {
+            warn!(
+                "バックグラウンドタスクが {} 秒以内に終了しなかったため、待たずにシャットダウンします",
+                SHUTDOWN_TIMEOUT.as_secs()
+            );
+        }
+
+        self.client.read().await.disconnect().await;
+    }
+
     /// 単一の JSON-RPC リクエストを処理します。
     async fn handle_request(&mut self, request_str: &str) -> Option<JsonRpcResponse> {
         let request: JsonRpcRequest = match serde_json::from_str(request_str) {
@@ -365,24 +477,45 @@ impl McpServer {
 
         info!("tools/call リクエストを処理中。ツール: {}", name);
 
-        match self.tool_executor.execute(name, arguments).await {
-            Ok(result) => {
-                Ok(json!({
-                    "content": [
-                        {
-                            "type": "text",
-                            "text": serde_json::to_string_pretty(&result)?
-                        }
-                    ]
-                }))
+        let outcome = match output_format(&arguments) {
+            Ok(format) => self.tool_executor.execute(name, arguments).await.map(|result| (format, result)),
+            Err(e) => Err(e),
+        };
+
+        match outcome {
+            Ok((output_format, result)) => {
+                // Markdown に対応していないツールは JSON のまま返す
+                let rendered = (output_format == "markdown")
+                    .then(|| markdown::render(name, &result))
+                    .flatten();
+                match rendered {
+                    // UI テンプレートは structuredContent から元の JSON を読み取る
+                    Some(text) => Ok(json!({
+                        "content": [
+                            {
+                                "type": "text",
+                                "text": text
+                            }
+                        ],
+                        "structuredContent": result
+                    })),
+                    None => Ok(json!({
+                        "content": [
+                            {
+                                "type": "text",
+                                "text": serde_json::to_string_pretty(&result)?
+                            }
+                        ]
+                    })),
+                }
             }
             Err(e) => {
-                error!("ツール実行エラー: {}", e);
+                error!("ツール実行エラー: {:#}", e);
                 Ok(json!({
                     "content": [
                         {
                             "type": "text",
-                            "text": format!("エラー: {}", e)
+                            "text": format!("エラー: {:#}", e)
                         }
                     ],
                     "isError": true
@@ -391,3 +524,17 @@ impl McpServer {
         }
     }
 }
+
+/// ツール引数の output_format（省略時は "json"）
+fn output_format(arguments: &Value) -> Result<&'static str> {
+    let requested = match arguments.get("output_format") {
+        None | Some(Value::Null) => return Ok("json"),
+        Some(value) => value.as_str(),
+    };
+    requested
+        .and_then(|format| markdown::OUTPUT_FORMATS.iter().copied().find(|&f| f == format))
+        .ok_or_else(|| anyhow::anyhow!(
+            "output_format には {} のいずれかを指定してください",
+            markdown::OUTPUT_FORMATS.join(" / ")
+        ))
+}
@@ -31,6 +31,11 @@ const UI_RESOURCES: &[UiResourceDef] = &[
         connect_domains: &[],
         resource_domains: &["*"], // プロフィール画像・メディア読み込み
     },
+    UiResourceDef {
+        name: "timeline-list",
+        connect_domains: &[],
+        resource_domains: &["*"], // アバター・メディアプレビュー読み込み
+    },
     UiResourceDef {
         name: "article-card",
         connect_domains: &[],
@@ -46,6 +51,11 @@ const UI_RESOURCES: &[UiResourceDef] = &[
         connect_domains: &[],
         resource_domains: &[],
     },
+    UiResourceDef {
+        name: "poll-card",
+        connect_domains: &[],
+        resource_domains: &["*"], // 作成者のアバター
+    },
     UiResourceDef {
         name: "connect-qr",
         connect_domains: &[],
@@ -64,12 +74,22 @@ struct ToolUiMapping {
 const TOOL_UI_MAPPINGS: &[ToolUiMapping] = &[
     ToolUiMapping {
         tool_name: "get_nostr_timeline",
-        resource_name: "note-card",
+        resource_name: "timeline-list",
         visibility: &["model", "app"],
     },
     ToolUiMapping {
         tool_name: "search_nostr_notes",
-        resource_name: "note-card",
+        resource_name: "timeline-list",
+        visibility: &["model", "app"],
+    },
+    ToolUiMapping {
+        tool_name: "get_watched_feeds",
+        resource_name: "timeline-list",
+        visibility: &["model", "app"],
+    },
+    ToolUiMapping {
+        tool_name: "get_nostr_notes",
+        resource_name: "timeline-list",
         visibility: &["model", "app"],
     },
     ToolUiMapping {
@@ -82,6 +102,11 @@ const TOOL_UI_MAPPINGS: &[ToolUiMapping] = &[
         resource_name: "article-card",
         visibility: &["model", "app"],
     },
+    ToolUiMapping {
+        tool_name: "search_nostr_articles",
+        resource_name: "article-card",
+        visibility: &["model", "app"],
+    },
     ToolUiMapping {
         tool_name: "get_nostr_drafts",
         resource_name: "article-card",
@@ -102,6 +127,17 @@ const TOOL_UI_MAPPINGS: &[ToolUiMapping] = &[
         resource_name: "zap-button",
         visibility: &["model", "app"],
     },
+    // NIP-88: 投票
+    ToolUiMapping {
+        tool_name: "get_poll_results",
+        resource_name: "poll-card",
+        visibility: &["model", "app"],
+    },
+    ToolUiMapping {
+        tool_name: "vote_poll",
+        resource_name: "poll-card",
+        visibility: &["model", "app"],
+    },
     // Phase 6: NIP-46 Nostr Connect
     ToolUiMapping {
         tool_name: "nostr_connect",
@@ -235,9 +271,13 @@ mod tests {
         let meta = meta.unwrap();
         assert_eq!(
             meta["ui"]["resourceUri"],
-            "ui://nostr-mcp/note-card"
+            "ui://nostr-mcp/timeline-list"
         );
 
+        // スレッドは単一ノートのカードで表示する
+        let meta = get_tool_ui_meta("get_nostr_thread").unwrap();
+        assert_eq!(meta["ui"]["resourceUri"], "ui://nostr-mcp/note-card");
+
         // Unknown tool returns None
         assert!(get_tool_ui_meta("unknown_tool").is_none());
     }
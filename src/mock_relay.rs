@@ -0,0 +1,244 @@
+//! テスト用のインプロセス モックリレー（`mock-relay` フィーチャー）
+//!
+//! 127.0.0.1 の空きポートで待ち受け、NIP-01 の EVENT / REQ / CLOSE と NIP-45 の COUNT に
+//! 応答する最小限の WebSocket リレーです。公開リレーに接続せずに `nostr_client.rs` の
+//! 投稿・取得をエンドツーエンドで確認するために使います（`cargo test --features mock-relay`）。
+//!
+//! イベントはメモリ上にのみ保存し、置き換え可能イベント（NIP-01）は同じ作成者・Kind
+//! （アドレス指定可能イベントは d タグも）の最新のものだけを残します。
+
+use anyhow::{Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use nostr_sdk::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_tungstenite::tungstenite::Message;
+
+/// 購読中のクライアントに配信する新着イベントのバッファ数
+const LIVE_EVENT_BUFFER: usize = 1024;
+
+/// インプロセスで動作するモックリレー
+///
+/// ドロップすると待ち受けとすべての接続を終了します。
+pub struct MockRelay {
+    url: String,
+    store: Arc<EventStore>,
+    task: JoinHandle<()>,
+}
+
+impl MockRelay {
+    /// 空きポートでモックリレーを起動します。
+    pub async fn run() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await
+            .context("モックリレーの待ち受けに失敗")?;
+        let url = format!("ws://{}", listener.local_addr()?);
+        let store = Arc::new(EventStore::new());
+
+        let task = tokio::spawn({
+            let store = Arc::clone(&store);
+            async move {
+                // 待ち受けタスクが中断されると接続タスクもまとめて中断される
+                let mut connections = JoinSet::new();
+                while let Ok((stream, _)) = listener.accept().await {
+                    connections.spawn(handle_connection(stream, Arc::clone(&store)));
+                }
+            }
+        });
+
+        Ok(Self { url, store, task })
+    }
+
+    /// リレーの URL（`ws://127.0.0.1:<port>`）
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// 保存されているイベント（受信順）
+    pub async fn events(&self) -> Vec<Event> {
+        self.store.events.read().await.clone()
+    }
+
+    /// 他のユーザーのイベントなどを、クライアントを介さずに直接保存します。
+    pub async fn insert(&self, event: Event) {
+        self.store.insert(event).await;
+    }
+}
+
+impl Drop for MockRelay {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 保存済みイベントと、購読中の接続への新着イベントの配信
+struct EventStore {
+    events: RwLock<Vec<Event>>,
+    live: broadcast::Sender<Event>,
+}
+
+impl EventStore {
+    fn new() -> Self {
+        Self {
+            events: RwLock::new(Vec::new()),
+            live: broadcast::channel(LIVE_EVENT_BUFFER).0,
+        }
+    }
+
+    /// イベントを保存して購読中の接続に配信します。既に保存済みの場合は false を返します。
+    async fn insert(&self, event: Event) -> bool {
+        {
+            let mut events = self.events.write().await;
+            if events.iter().any(|e| e.id == event.id) {
+                return false;
+            }
+            if !event.kind.is_ephemeral() {
+                events.retain(|e| !replaces(&event, e));
+                events.push(event.clone());
+            }
+        }
+        let _ = self.live.send(event);
+        true
+    }
+
+    /// フィルタに一致するイベント（フィルタごとに新しい順で limit 件、重複なし）
+    async fn query(&self, filters: &[Filter]) -> Vec<Event> {
+        let events = self.events.read().await;
+        let mut seen = HashSet::new();
+        let mut matched = Vec::new();
+        for filter in filters {
+            let mut hits: Vec<&Event> = events.iter().filter(|e| filter.match_event(e)).collect();
+            hits.sort_by_key(|e| Reverse(e.created_at));
+            if let Some(limit) = filter.limit {
+                hits.truncate(limit);
+            }
+            matched.extend(hits.into_iter().filter(|e| seen.insert(e.id)).cloned());
+        }
+        matched
+    }
+}
+
+/// `new` が `old` を置き換える置き換え可能イベント（NIP-01）か
+fn replaces(new: &Event, old: &Event) -> bool {
+    if new.kind != old.kind || new.pubkey != old.pubkey {
+        return false;
+    }
+    if new.kind.is_replaceable() {
+        return true;
+    }
+    new.kind.is_addressable() && new.tags.identifier() == old.tags.identifier()
+}
+
+/// 1 つの WebSocket 接続を処理します。
+async fn handle_connection(stream: TcpStream, store: Arc<EventStore>) {
+    let Ok(ws) = tokio_tungstenite::accept_async(stream).await else { return };
+    let (mut sink, mut source) = ws.split();
+    let mut live = store.live.subscribe();
+    let mut subscriptions: HashMap<SubscriptionId, Vec<Filter>> = HashMap::new();
+
+    loop {
+        let replies = tokio::select! {
+            message = source.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    handle_message(&text, &store, &mut subscriptions).await
+                }
+                Some(Ok(Message::Ping(payload))) => {
+                    if sink.send(Message::Pong(payload)).await.is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+            event = live.recv() => match event {
+                Ok(event) => subscriptions.iter()
+                    .filter(|(_, filters)| filters.iter().any(|f| f.match_event(&event)))
+                    .map(|(id, _)| RelayMessage::event(id.clone(), event.clone()))
+                    .collect(),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+        };
+
+        for reply in replies {
+            if sink.send(Message::Text(reply.as_json())).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// クライアントからのメッセージを処理し、返信するメッセージを返します。
+async fn handle_message(
+    text: &str,
+    store: &EventStore,
+    subscriptions: &mut HashMap<SubscriptionId, Vec<Filter>>,
+) -> Vec<RelayMessage> {
+    let message = match ClientMessage::from_json(text) {
+        Ok(message) => message,
+        Err(e) => return vec![RelayMessage::notice(format!("invalid: {}", e))],
+    };
+
+    match message {
+        ClientMessage::Event(event) => {
+            if let Err(e) = event.verify() {
+                return vec![RelayMessage::ok(event.id, false, format!("invalid: {}", e))];
+            }
+            let id = event.id;
+            let message = if store.insert(*event).await { "" } else { "duplicate: already have this event" };
+            vec![RelayMessage::ok(id, true, message)]
+        }
+        ClientMessage::Req { subscription_id, filters } => {
+            let mut replies: Vec<RelayMessage> = store.query(&filters).await
+                .into_iter()
+                .map(|event| RelayMessage::event(subscription_id.clone(), event))
+                .collect();
+            replies.push(RelayMessage::eose(subscription_id.clone()));
+            subscriptions.insert(subscription_id, filters);
+            replies
+        }
+        ClientMessage::Count { subscription_id, filters } => {
+            let count = store.query(&filters.into_iter().map(|f| f.remove_limit()).collect::<Vec<_>>()).await.len();
+            vec![RelayMessage::count(subscription_id, count)]
+        }
+        ClientMessage::Close(subscription_id) => {
+            subscriptions.remove(&subscription_id);
+            Vec::new()
+        }
+        _ => vec![RelayMessage::notice("unsupported: message type")],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_relay_stores_and_serves_events() {
+        let relay = MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        let client = Client::new(keys.clone());
+        client.add_relay(relay.url()).await.unwrap();
+        client.connect().await;
+
+        let note = client.send_event_builder(EventBuilder::text_note("hello")).await.unwrap();
+        assert!(note.failed.is_empty());
+        client.send_event_builder(EventBuilder::metadata(&Metadata::new().name("old"))).await.unwrap();
+        client.send_event_builder(EventBuilder::metadata(&Metadata::new().name("new")).custom_created_at(Timestamp::now() + 1)).await.unwrap();
+
+        // 置き換え可能イベントは最新のものだけが残る
+        let stored = relay.events().await;
+        assert_eq!(stored.len(), 2);
+        assert!(stored.iter().any(|e| e.kind == Kind::Metadata && e.content.contains("new")));
+
+        let filter = Filter::new().author(keys.public_key()).kind(Kind::TextNote);
+        let events = client.fetch_events(vec![filter], std::time::Duration::from_secs(5)).await.unwrap();
+        assert_eq!(events.first().map(|e| e.id), Some(*note.id()));
+
+        client.disconnect().await.unwrap();
+    }
+}
@@ -0,0 +1,200 @@
+//! NIP-05 識別子の検証モジュール
+//!
+//! `name@domain` 形式の識別子を `https://<domain>/.well-known/nostr.json?name=<name>` に問い合わせ、
+//! 返された公開鍵がプロフィールの公開鍵と一致するかを確認します。
+
+use anyhow::{anyhow, Context, Result};
+use nostr_sdk::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{RwLock, Semaphore};
+use tracing::debug;
+
+/// NIP-05 リクエストのタイムアウト
+const NIP05_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 検証結果をキャッシュする時間
+const NIP05_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+/// 取得に失敗した（または検証中の）識別子を再検証するまでの時間
+const NIP05_RETRY_AFTER: Duration = Duration::from_secs(600);
+
+/// 同時に実行する検証リクエストの最大数
+const NIP05_MAX_CONCURRENT: usize = 4;
+
+/// キャッシュされた検証結果（検証日時, 結果）。取得に失敗した・検証中の場合は None
+type CachedNip05 = (Instant, Option<bool>);
+
+/// NIP-05 識別子のバックグラウンド検証とその結果のキャッシュ
+///
+/// 未検証の識別子は呼び出し元を待たせずにバックグラウンドで検証し、結果は次回以降の
+/// プロフィール取得で反映されます。同時リクエスト数は NIP05_MAX_CONCURRENT に制限します。
+pub struct Nip05Verifier {
+    cache: RwLock<HashMap<(PublicKey, String), CachedNip05>>,
+    permits: Semaphore,
+}
+
+impl Nip05Verifier {
+    pub fn new() -> Self {
+        Self {
+            cache: RwLock::new(HashMap::new()),
+            permits: Semaphore::new(NIP05_MAX_CONCURRENT),
+        }
+    }
+
+    /// 各識別子の検証結果を返し、キャッシュにない（期限切れの）ものはバックグラウンドで検証を開始します。
+    ///
+    /// 検証が済んでいない識別子は false を返します。
+    pub async fn check(self: &Arc<Self>, targets: &[(PublicKey, String)]) -> HashMap<(PublicKey, String), bool> {
+        let mut results = HashMap::new();
+        let mut to_verify = Vec::new();
+        {
+            let mut cache = self.cache.write().await;
+            for target in targets {
+                let fresh = cache.get(target).and_then(|(checked_at, verified)| {
+                    let ttl = if verified.is_some() { NIP05_CACHE_TTL } else { NIP05_RETRY_AFTER };
+                    (checked_at.elapsed() < ttl).then_some(verified.unwrap_or(false))
+                });
+                match fresh {
+                    Some(verified) => {
+                        results.insert(target.clone(), verified);
+                    }
+                    None => {
+                        // 検証中も重複して問い合わせないよう、失敗と同じ扱いで登録しておく
+                        cache.insert(target.clone(), (Instant::now(), None));
+                        results.insert(target.clone(), false);
+                        to_verify.push(target.clone());
+                    }
+                }
+            }
+        }
+
+        for (public_key, nip05) in to_verify {
+            let verifier = Arc::clone(self);
+            tokio::spawn(async move {
+                let Ok(_permit) = verifier.permits.acquire().await else { return };
+                let verified = match verify(&nip05, &public_key).await {
+                    Ok(verified) => Some(verified),
+                    Err(e) => {
+                        debug!("NIP-05 の検証に失敗 ({}): {}", nip05, e);
+                        None
+                    }
+                };
+                verifier.cache.write().await.insert((public_key, nip05), (Instant::now(), verified));
+            });
+        }
+
+        results
+    }
+}
+
+/// `.well-known/nostr.json` のレスポンス（names のみ使用）
+#[derive(Debug, Deserialize)]
+struct Nip05Document {
+    #[serde(default)]
+    names: HashMap<String, String>,
+}
+
+/// NIP-05 識別子を（ローカル部, ドメイン）に分解
+///
+/// ドメインのみの場合は `_@domain` として扱います。ローカル部は小文字に正規化します。
+pub fn parse_identifier(nip05: &str) -> Result<(String, String)> {
+    let nip05 = nip05.trim();
+    let (name, domain) = nip05.split_once('@').unwrap_or(("_", nip05));
+    let name = name.to_lowercase();
+    let domain = domain.to_lowercase();
+
+    let valid_name = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    let valid_domain = domain.contains('.')
+        && !domain.starts_with('.')
+        && domain.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ':'));
+    if !valid_name || !valid_domain {
+        return Err(anyhow!("無効な NIP-05 識別子です: {}", nip05));
+    }
+    Ok((name, domain))
+}
+
+/// 識別子の検証に使う `.well-known/nostr.json` の URL
+pub fn well_known_url(name: &str, domain: &str) -> String {
+    format!("https://{}/.well-known/nostr.json?name={}", domain, name)
+}
+
+/// NIP-05 ドキュメントの names に、識別子の公開鍵として `public_key` が登録されているか
+fn names_contain(names: &HashMap<String, String>, name: &str, public_key: &PublicKey) -> bool {
+    names.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .is_some_and(|(_, hex)| hex.eq_ignore_ascii_case(&public_key.to_hex()))
+}
+
+/// NIP-05 識別子が指定された公開鍵を指しているかを検証
+///
+/// NIP-05 の規定どおり HTTP リダイレクトには従いません。
+/// 取得・パースに失敗した場合はエラー、公開鍵が一致しない場合は Ok(false) を返します。
+pub async fn verify(nip05: &str, public_key: &PublicKey) -> Result<bool> {
+    let (name, domain) = parse_identifier(nip05)?;
+    let url = well_known_url(&name, &domain);
+
+    debug!("NIP-05 検証: {}", url);
+
+    let response = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(NIP05_TIMEOUT)
+        .build()?
+        .get(&url)
+        .send()
+        .await
+        .context("NIP-05 ドキュメントの取得に失敗")?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("NIP-05 ドキュメントの取得エラー ({})", response.status()));
+    }
+
+    let document: Nip05Document = response
+        .json()
+        .await
+        .context("NIP-05 ドキュメントのパースに失敗")?;
+    Ok(names_contain(&document.names, &name, public_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_identifier() {
+        assert_eq!(
+            parse_identifier("Alice@Example.com").unwrap(),
+            ("alice".to_string(), "example.com".to_string())
+        );
+        assert_eq!(
+            parse_identifier(" example.com ").unwrap(),
+            ("_".to_string(), "example.com".to_string())
+        );
+        assert!(parse_identifier("alice@").is_err());
+        assert!(parse_identifier("@example.com").is_err());
+        assert!(parse_identifier("alice@localhost").is_err());
+        assert!(parse_identifier("a b@example.com").is_err());
+        assert!(parse_identifier("alice@example.com/path").is_err());
+        assert_eq!(
+            well_known_url("alice", "example.com"),
+            "https://example.com/.well-known/nostr.json?name=alice"
+        );
+    }
+
+    #[test]
+    fn test_names_contain() {
+        let keys = Keys::generate();
+        let other = Keys::generate();
+        let names = HashMap::from([
+            ("Alice".to_string(), keys.public_key().to_hex().to_uppercase()),
+            ("bob".to_string(), other.public_key().to_hex()),
+        ]);
+
+        assert!(names_contain(&names, "alice", &keys.public_key()));
+        assert!(!names_contain(&names, "bob", &keys.public_key()));
+        assert!(!names_contain(&names, "carol", &keys.public_key()));
+    }
+}
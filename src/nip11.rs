@@ -0,0 +1,96 @@
+//! リレー情報ドキュメントモジュール (NIP-11)
+//!
+//! リレーの HTTP エンドポイントから情報ドキュメントを取得し、
+//! 対応 NIP（NIP-45 COUNT 等）の判定に利用します。
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::debug;
+
+/// NIP-11 リクエストのタイムアウト
+const NIP11_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// リレー情報ドキュメント（NIP-11 の主要フィールドのみ）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RelayInformation {
+    /// リレー名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// リレーの説明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 対応 NIP の番号リスト
+    #[serde(default)]
+    pub supported_nips: Vec<u16>,
+    /// ソフトウェアの URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub software: Option<String>,
+    /// ソフトウェアのバージョン
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl RelayInformation {
+    /// 指定された NIP に対応しているか
+    pub fn supports(&self, nip: u16) -> bool {
+        self.supported_nips.contains(&nip)
+    }
+}
+
+/// WebSocket URL を NIP-11 取得用の HTTP(S) URL に変換
+pub fn http_url(relay_url: &str) -> Result<String> {
+    let url = relay_url.trim();
+    if let Some(rest) = url.strip_prefix("wss://") {
+        Ok(format!("https://{}", rest))
+    } else if let Some(rest) = url.strip_prefix("ws://") {
+        Ok(format!("http://{}", rest))
+    } else {
+        Err(anyhow!("無効なリレー URL です: {}", relay_url))
+    }
+}
+
+/// リレー情報ドキュメントを取得（NIP-11）
+pub async fn fetch_relay_information(relay_url: &str) -> Result<RelayInformation> {
+    let url = http_url(relay_url)?;
+
+    debug!("NIP-11 情報取得: {}", url);
+
+    let response = reqwest::Client::new()
+        .get(&url)
+        .header("Accept", "application/nostr+json")
+        .timeout(NIP11_TIMEOUT)
+        .send()
+        .await
+        .context("リレー情報の取得に失敗")?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("リレー情報の取得エラー ({})", response.status()));
+    }
+
+    response
+        .json::<RelayInformation>()
+        .await
+        .context("リレー情報のパースに失敗")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_url() {
+        assert_eq!(http_url("wss://relay.damus.io").unwrap(), "https://relay.damus.io");
+        assert_eq!(http_url("ws://localhost:7777/").unwrap(), "http://localhost:7777/");
+        assert!(http_url("https://example.com").is_err());
+    }
+
+    #[test]
+    fn test_parse_relay_information() {
+        let json = r#"{"name":"test","supported_nips":[1,11,45],"software":"strfry"}"#;
+        let info: RelayInformation = serde_json::from_str(json).unwrap();
+        assert!(info.supports(45));
+        assert!(!info.supports(50));
+        assert_eq!(info.name.as_deref(), Some("test"));
+    }
+}
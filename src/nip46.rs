@@ -16,20 +16,47 @@ use nostr_connect::prelude::{
     NostrConnect, NostrConnectMetadata, NostrConnectURI, RelayUrl, Url,
 };
 use nostr_sdk::prelude::*;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
+use crate::nostr_client::NostrClient;
+
 /// NIP-46 接続のデフォルトタイムアウト（秒）
 const DEFAULT_NIP46_TIMEOUT_SECS: u64 = 120;
 
+/// リモートサイナーの死活確認の間隔（秒）
+const NIP46_WATCHDOG_INTERVAL_SECS: u64 = 60;
+
+/// 死活確認・再接続時の応答待ちタイムアウト（秒）
+const NIP46_PING_TIMEOUT_SECS: u64 = 20;
+
 /// NIP-46 通信用のデフォルトリレー
 const DEFAULT_NIP46_RELAYS: &[&str] = &[
     "wss://relay.nsec.app",
     "wss://relay.damus.io",
 ];
 
+/// `perms` に指定できる NIP-46 メソッド
+const NIP46_PERM_METHODS: &[&str] = &[
+    "sign_event",
+    "get_public_key",
+    "nip04_encrypt",
+    "nip04_decrypt",
+    "nip44_encrypt",
+    "nip44_decrypt",
+    "get_relays",
+    "ping",
+];
+
+/// ラベル省略時のサイナー名
+pub const DEFAULT_SIGNER_LABEL: &str = "default";
+
+/// サイナーのラベルの最大文字数
+const MAX_SIGNER_LABEL_LEN: usize = 32;
+
 /// QR コードの画像サイズ（ピクセル）
 const QR_IMAGE_SIZE: u32 = 256;
 
@@ -51,6 +78,15 @@ pub enum Nip46State {
         /// リモートサイナーのユーザー公開鍵
         user_pubkey: PublicKey,
     },
+    /// 接続済みだがリモートサイナーが応答しない（再接続を試行中）
+    Degraded {
+        /// リモートサイナーのユーザー公開鍵
+        user_pubkey: PublicKey,
+        /// 最後の死活確認の失敗理由
+        reason: String,
+        /// 応答が途絶えた時刻
+        since: Timestamp,
+    },
     /// エラー
     Error(String),
 }
@@ -60,9 +96,8 @@ pub enum Nip46State {
 pub struct Nip46Config {
     /// NIP-46 通信用リレー
     pub relays: Vec<String>,
-    /// 要求する権限（カンマ区切り: "sign_event:1,sign_event:7,nip44_encrypt,nip44_decrypt"）
-    /// Step 6-3 で実装する権限粒度制御で使用
-    #[allow(dead_code)]
+    /// 要求する権限（カンマ区切り: "sign_event:1,sign_event:7,nip44_encrypt,nip44_decrypt"）。
+    /// `nostrconnect://` URI の `perms` パラメータとしてリモートサイナーに提示する
     pub perms: Option<String>,
     /// bunker:// URI（バンカー方式の場合）
     pub bunker_uri: Option<String>,
@@ -78,6 +113,9 @@ pub struct Nip46Session {
     app_keys: Keys,
     /// セッション設定
     config: Nip46Config,
+    /// 接続時にリモートサイナーへ要求した権限（正規化済み）。
+    /// バンカー方式や perms 未指定の場合は None（権限はサイナー側の設定に依存）
+    requested_perms: Arc<RwLock<Option<String>>>,
 }
 
 impl Nip46Session {
@@ -90,6 +128,7 @@ impl Nip46Session {
             signer: Arc::new(RwLock::new(None)),
             app_keys,
             config,
+            requested_perms: Arc::new(RwLock::new(None)),
         }
     }
 
@@ -128,7 +167,21 @@ impl Nip46Session {
             metadata,
         };
 
-        let uri_string = uri.to_string();
+        // nostr-connect の URI 型は perms を持たないため、文字列に直接付与する
+        let mut uri_string = uri.to_string();
+        let perms = self
+            .config
+            .perms
+            .as_deref()
+            .map(normalize_perms)
+            .transpose()?
+            .filter(|perms| !perms.is_empty());
+        if let Some(perms) = &perms {
+            info!("NIP-46 要求権限: {}", perms);
+            uri_string.push_str("&perms=");
+            uri_string.push_str(perms);
+        }
+        *self.requested_perms.write().await = perms;
         info!("nostrconnect:// URI を生成: {}...", &uri_string[..uri_string.len().min(60)]);
 
         // QR コードを生成
@@ -172,6 +225,12 @@ impl Nip46Session {
         let uri = NostrConnectURI::parse(bunker_uri_str)
             .map_err(|e| anyhow!("bunker URI のパースに失敗: {}", e))?;
 
+        // バンカー方式では権限はサイナー側で bunker URI 発行時に設定される
+        if self.config.perms.is_some() {
+            debug!("バンカー方式では nip46-perms は使用されません（サイナー側の設定が適用されます）");
+        }
+        *self.requested_perms.write().await = None;
+
         let signer = NostrConnect::new(
             uri,
             self.app_keys.clone(),
@@ -268,6 +327,180 @@ impl Nip46Session {
         matches!(&*self.state.read().await, Nip46State::Connected { .. })
     }
 
+    /// リモートサイナーの死活を確認する。
+    /// エラー応答（未対応メソッド等）でも返信があれば生存とみなす。
+    pub async fn ping(&self) -> Result<()> {
+        let signer = self
+            .get_nostr_connect()
+            .await
+            .ok_or_else(|| anyhow!("NIP-46 セッションが開始されていません"))?;
+
+        match tokio::time::timeout(
+            Duration::from_secs(NIP46_PING_TIMEOUT_SECS),
+            signer.get_relays(),
+        )
+        .await
+        {
+            Ok(Ok(_)) | Ok(Err(nostr_connect::error::Error::Response(_))) => Ok(()),
+            Ok(Err(e)) => Err(anyhow!("リモートサイナーへの問い合わせに失敗: {}", e)),
+            Err(_) => Err(anyhow!(
+                "リモートサイナーが {} 秒以内に応答しませんでした",
+                NIP46_PING_TIMEOUT_SECS
+            )),
+        }
+    }
+
+    /// 接続済みセッションを degraded 状態にする。
+    /// Connected から遷移した場合のみ true を返す（既に degraded なら理由だけ更新）。
+    async fn mark_degraded(&self, reason: String) -> bool {
+        let mut state = self.state.write().await;
+        match &*state {
+            Nip46State::Connected { user_pubkey } => {
+                *state = Nip46State::Degraded {
+                    user_pubkey: *user_pubkey,
+                    reason,
+                    since: Timestamp::now(),
+                };
+                true
+            }
+            Nip46State::Degraded {
+                user_pubkey, since, ..
+            } => {
+                *state = Nip46State::Degraded {
+                    user_pubkey: *user_pubkey,
+                    reason,
+                    since: *since,
+                };
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// degraded 状態から接続済みに戻す。遷移した場合のみ true を返す。
+    async fn mark_recovered(&self) -> bool {
+        let mut state = self.state.write().await;
+        if let Nip46State::Degraded { user_pubkey, .. } = &*state {
+            *state = Nip46State::Connected {
+                user_pubkey: *user_pubkey,
+            };
+            true
+        } else {
+            false
+        }
+    }
+
+    /// degraded 状態のセッションを、同じリモートサイナーへの新しい接続で置き換える。
+    /// 成功すると新しいサイナーを返し、状態を接続済みに戻す。
+    async fn reconnect(&self) -> Result<(NostrConnect, PublicKey)> {
+        let expected_pubkey = match &*self.state.read().await {
+            Nip46State::Degraded { user_pubkey, .. } => *user_pubkey,
+            _ => return Err(anyhow!("再接続が必要な状態ではありません")),
+        };
+        let old_signer = self
+            .get_nostr_connect()
+            .await
+            .ok_or_else(|| anyhow!("NIP-46 セッションが開始されていません"))?;
+
+        // 初回接続でリモートサイナーの公開鍵は確定しているため、bunker URI として再構築できる
+        let uri = old_signer
+            .bunker_uri()
+            .await
+            .map_err(|e| anyhow!("再接続用 URI の構築に失敗: {}", e))?;
+        let signer = NostrConnect::new(
+            uri,
+            self.app_keys.clone(),
+            Duration::from_secs(DEFAULT_NIP46_TIMEOUT_SECS),
+            None,
+        )
+        .map_err(|e| anyhow!("NostrConnect の作成に失敗: {}", e))?;
+
+        let user_pubkey = match tokio::time::timeout(
+            Duration::from_secs(NIP46_PING_TIMEOUT_SECS),
+            signer.get_public_key(),
+        )
+        .await
+        {
+            Ok(Ok(pubkey)) => pubkey,
+            Ok(Err(e)) => return Err(anyhow!("リモートサイナーへの再接続に失敗: {}", e)),
+            Err(_) => {
+                return Err(anyhow!(
+                    "リモートサイナーが {} 秒以内に応答しませんでした",
+                    NIP46_PING_TIMEOUT_SECS
+                ))
+            }
+        };
+        if user_pubkey != expected_pubkey {
+            return Err(anyhow!(
+                "再接続先のユーザー公開鍵が一致しません: {}",
+                user_pubkey.to_bech32().unwrap_or_default()
+            ));
+        }
+
+        // 再接続中に切断・再接続された場合は結果を破棄する
+        {
+            let mut state = self.state.write().await;
+            if !matches!(&*state, Nip46State::Degraded { .. }) {
+                return Err(anyhow!("再接続中にセッションが変更されました"));
+            }
+            *self.signer.write().await = Some(signer.clone());
+            *state = Nip46State::Connected { user_pubkey };
+        }
+
+        if let Err(e) = old_signer.shutdown().await {
+            debug!("古い NIP-46 接続のシャットダウン中にエラー: {}", e);
+        }
+        Ok((signer, user_pubkey))
+    }
+
+    /// 死活監視の 1 回分の処理。
+    /// 応答がなければ degraded にして再接続を試みる。
+    /// アクティブなサイナーの場合は NostrClient の書き込みも停止・再開する。
+    async fn watchdog_tick(&self, client: &RwLock<NostrClient>, active: bool) {
+        let was_degraded = match &*self.state.read().await {
+            Nip46State::Connected { .. } => false,
+            Nip46State::Degraded { .. } => true,
+            _ => return,
+        };
+
+        match self.ping().await {
+            Ok(()) => {
+                if was_degraded && self.mark_recovered().await {
+                    if active {
+                        client.write().await.set_nip46_degraded(None).await;
+                    }
+                    info!("NIP-46 リモートサイナーの応答が回復しました");
+                }
+            }
+            Err(e) => {
+                let reason = e.to_string();
+                warn!("NIP-46 リモートサイナーの死活確認に失敗: {}", reason);
+                if self.mark_degraded(reason.clone()).await && active {
+                    client.write().await.set_nip46_degraded(Some(reason)).await;
+                }
+
+                match self.reconnect().await {
+                    Ok((signer, user_pubkey)) => {
+                        if !active {
+                            info!("NIP-46 リモートサイナーに再接続しました");
+                            return;
+                        }
+                        match client.write().await.enable_nip46_signer(signer, user_pubkey).await {
+                            Ok(()) => info!("NIP-46 リモートサイナーに再接続しました"),
+                            Err(e) => warn!("再接続後のサイナー切り替えに失敗: {}", e),
+                        }
+                    }
+                    Err(e) => warn!("NIP-46 再接続に失敗: {}。次回の確認で再試行します", e),
+                }
+            }
+        }
+    }
+
+    /// 接続時に要求した権限（未指定・バンカー方式の場合は None）
+    pub async fn requested_perms(&self) -> Option<String> {
+        self.requested_perms.read().await.clone()
+    }
+
     /// 接続済みユーザーの公開鍵を取得
     pub async fn connected_pubkey(&self) -> Option<PublicKey> {
         match &*self.state.read().await {
@@ -305,6 +538,18 @@ impl Nip46Session {
                 "user_pubkey": user_pubkey.to_hex(),
                 "user_npub": user_pubkey.to_bech32().unwrap_or_default()
             }),
+            Nip46State::Degraded {
+                user_pubkey,
+                reason,
+                since,
+            } => serde_json::json!({
+                "status": "degraded",
+                "message": "NIP-46 リモートサイナーが応答しません。再接続を試行中のため、書き込みは一時停止しています。",
+                "reason": reason,
+                "since": since.as_u64(),
+                "user_pubkey": user_pubkey.to_hex(),
+                "user_npub": user_pubkey.to_bech32().unwrap_or_default()
+            }),
             Nip46State::Error(msg) => serde_json::json!({
                 "status": "error",
                 "message": msg
@@ -330,6 +575,122 @@ impl Nip46Session {
     }
 }
 
+/// 複数の NIP-46 セッションをラベルで管理するレジストリ。
+/// 書き込みに使うのはアクティブなサイナー 1 つだけ。
+pub struct Nip46Registry {
+    /// 新しいセッションに適用する設定
+    config: Nip46Config,
+    /// ラベルごとのセッション
+    sessions: RwLock<BTreeMap<String, Arc<Nip46Session>>>,
+    /// NostrClient の署名に使用中のサイナーのラベル
+    active: RwLock<Option<String>>,
+}
+
+impl Nip46Registry {
+    /// 空のレジストリを作成
+    pub fn new(config: Nip46Config) -> Self {
+        Self {
+            config,
+            sessions: RwLock::new(BTreeMap::new()),
+            active: RwLock::new(None),
+        }
+    }
+
+    /// 指定ラベルのセッションを取得し、なければ作成する
+    pub async fn session(&self, label: &str) -> Arc<Nip46Session> {
+        let mut sessions = self.sessions.write().await;
+        sessions
+            .entry(label.to_string())
+            .or_insert_with(|| Arc::new(Nip46Session::new(self.config.clone())))
+            .clone()
+    }
+
+    /// 指定ラベルのセッションを取得
+    pub async fn get(&self, label: &str) -> Option<Arc<Nip46Session>> {
+        self.sessions.read().await.get(label).cloned()
+    }
+
+    /// 全セッションをラベル順に取得
+    pub async fn sessions(&self) -> Vec<(String, Arc<Nip46Session>)> {
+        self.sessions
+            .read()
+            .await
+            .iter()
+            .map(|(label, session)| (label.clone(), session.clone()))
+            .collect()
+    }
+
+    /// セッションを登録から外す。アクティブだった場合は true を返す
+    pub async fn remove(&self, label: &str) -> bool {
+        self.sessions.write().await.remove(label);
+        let mut active = self.active.write().await;
+        if active.as_deref() == Some(label) {
+            *active = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// アクティブなサイナーのラベル
+    pub async fn active_label(&self) -> Option<String> {
+        self.active.read().await.clone()
+    }
+
+    /// アクティブなサイナーを記録する
+    pub async fn set_active(&self, label: &str) {
+        *self.active.write().await = Some(label.to_string());
+    }
+}
+
+/// サイナーのラベルを検証する。省略時は `default`
+pub fn normalize_signer_label(label: Option<&str>) -> Result<String> {
+    let label = label.map(str::trim).unwrap_or("");
+    if label.is_empty() {
+        return Ok(DEFAULT_SIGNER_LABEL.to_string());
+    }
+    if label.chars().count() > MAX_SIGNER_LABEL_LEN
+        || !label
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(anyhow!(
+            "サイナーのラベルは {} 文字以内の英数字・'-'・'_' で指定してください: '{}'",
+            MAX_SIGNER_LABEL_LEN,
+            label
+        ));
+    }
+    Ok(label.to_string())
+}
+
+/// リモートサイナーの死活監視タスクを起動する。
+/// 接続中のセッションを定期的に確認し、応答が途絶えたら書き込みを止めて再接続を試みる。
+pub fn spawn_watchdog(
+    registry: Arc<Nip46Registry>,
+    client: Arc<RwLock<NostrClient>>,
+    mut shutdown: tokio::sync::watch::Receiver<bool>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(NIP46_WATCHDOG_INTERVAL_SECS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        // 最初の tick は即座に完了するため読み捨てる
+        interval.tick().await;
+        loop {
+            // 実行中の確認は最後まで行い、次の tick を待つ間にシャットダウンを受け付ける
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.changed() => break,
+            }
+            let active = registry.active_label().await;
+            for (label, session) in registry.sessions().await {
+                let is_active = active.as_deref() == Some(label.as_str());
+                session.watchdog_tick(&client, is_active).await;
+            }
+        }
+    })
+}
+
 /// NIP-46 接続開始の結果
 #[derive(Debug, Clone)]
 pub struct Nip46ConnectResult {
@@ -339,6 +700,73 @@ pub struct Nip46ConnectResult {
     pub qr_base64: String,
 }
 
+/// `nip46-perms` の設定値を検証し、`perms` パラメータ用に正規化する。
+///
+/// 各要素は `method` または `method:param`（`sign_event:<kind>` など）。
+/// 空白と空要素を除去し、重複は最初の出現のみ残す。
+pub fn normalize_perms(perms: &str) -> Result<String> {
+    let mut normalized: Vec<String> = Vec::new();
+
+    for perm in perms.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (method, param) = match perm.split_once(':') {
+            Some((method, param)) => (method.trim(), Some(param.trim())),
+            None => (perm, None),
+        };
+
+        if !NIP46_PERM_METHODS.contains(&method) {
+            return Err(anyhow!("nip46-perms に不明な権限があります: '{}'", perm));
+        }
+
+        let perm = match param {
+            Some(param) if method == "sign_event" => {
+                let kind: u16 = param
+                    .parse()
+                    .map_err(|_| anyhow!("sign_event の kind が不正です: '{}'", perm))?;
+                format!("sign_event:{}", kind)
+            }
+            Some(_) => {
+                return Err(anyhow!("'{}' はパラメータを指定できません: '{}'", method, perm));
+            }
+            None => method.to_string(),
+        };
+
+        if !normalized.contains(&perm) {
+            normalized.push(perm);
+        }
+    }
+
+    Ok(normalized.join(","))
+}
+
+/// 正規化済みの権限リストから、署名できる Kind と暗号化の可否をまとめる。
+/// `sign_event` を Kind 指定なしで含む場合は全 Kind を署名できる。
+pub fn summarize_perms(perms: &str) -> serde_json::Value {
+    let perms: Vec<&str> = perms.split(',').filter(|p| !p.is_empty()).collect();
+
+    let sign_kinds = if perms.contains(&"sign_event") {
+        serde_json::json!("all")
+    } else {
+        let kinds: Vec<u16> = perms
+            .iter()
+            .filter_map(|p| p.strip_prefix("sign_event:"))
+            .filter_map(|kind| kind.parse().ok())
+            .collect();
+        serde_json::json!(kinds)
+    };
+
+    serde_json::json!({
+        "sign_kinds": sign_kinds,
+        "nip04": {
+            "encrypt": perms.contains(&"nip04_encrypt"),
+            "decrypt": perms.contains(&"nip04_decrypt")
+        },
+        "nip44": {
+            "encrypt": perms.contains(&"nip44_encrypt"),
+            "decrypt": perms.contains(&"nip44_decrypt")
+        }
+    })
+}
+
 /// 文字列から QR コードを PNG 画像として生成し、Base64 エンコードする
 pub fn generate_qr_base64(data: &str) -> Result<String> {
     use ::image::codecs::png::PngEncoder;
@@ -450,6 +878,49 @@ mod tests {
         assert_eq!(relay_urls.unwrap().len(), 1);
     }
 
+    #[test]
+    fn test_normalize_perms() {
+        assert_eq!(
+            normalize_perms(" sign_event:1, sign_event:7,,nip44_encrypt,sign_event:1 ").unwrap(),
+            "sign_event:1,sign_event:7,nip44_encrypt"
+        );
+        assert_eq!(normalize_perms("").unwrap(), "");
+        assert!(normalize_perms("sign_event:abc").is_err());
+        assert!(normalize_perms("nip44_encrypt:1").is_err());
+        assert!(normalize_perms("delete_everything").is_err());
+    }
+
+    #[test]
+    fn test_summarize_perms() {
+        let summary = summarize_perms("sign_event:1,sign_event:7,nip44_encrypt,nip44_decrypt");
+        assert_eq!(summary["sign_kinds"], serde_json::json!([1, 7]));
+        assert_eq!(summary["nip44"]["encrypt"], true);
+        assert_eq!(summary["nip44"]["decrypt"], true);
+        assert_eq!(summary["nip04"]["encrypt"], false);
+
+        let summary = summarize_perms("sign_event,nip04_decrypt");
+        assert_eq!(summary["sign_kinds"], "all");
+        assert_eq!(summary["nip04"]["decrypt"], true);
+    }
+
+    #[tokio::test]
+    async fn test_client_connect_uri_includes_perms() {
+        let config = Nip46Config {
+            relays: vec![],
+            perms: Some("sign_event:1,nip44_encrypt".to_string()),
+            bunker_uri: None,
+        };
+        let session = Nip46Session::new(config);
+        let result = session.start_client_connect().await.unwrap();
+        assert!(result
+            .connect_uri
+            .ends_with("&perms=sign_event:1,nip44_encrypt"));
+        assert_eq!(
+            session.requested_perms().await.as_deref(),
+            Some("sign_event:1,nip44_encrypt")
+        );
+    }
+
     #[tokio::test]
     async fn test_session_initial_state() {
         let config = Nip46Config {
@@ -462,6 +933,68 @@ mod tests {
         assert_eq!(state, Nip46State::Disconnected);
     }
 
+    #[tokio::test]
+    async fn test_degraded_transitions() {
+        let config = Nip46Config {
+            relays: vec![],
+            perms: None,
+            bunker_uri: None,
+        };
+        let session = Nip46Session::new(config);
+        let user_pubkey = Keys::generate().public_key();
+
+        // 未接続のセッションは degraded にならない
+        assert!(!session.mark_degraded("timeout".to_string()).await);
+        assert_eq!(session.state().await, Nip46State::Disconnected);
+
+        *session.state.write().await = Nip46State::Connected { user_pubkey };
+        assert!(session.mark_degraded("timeout".to_string()).await);
+        assert!(!session.mark_degraded("still down".to_string()).await);
+
+        let json = session.status_json().await;
+        assert_eq!(json["status"], "degraded");
+        assert_eq!(json["reason"], "still down");
+        assert!(!session.is_connected().await);
+
+        assert!(session.mark_recovered().await);
+        assert_eq!(session.state().await, Nip46State::Connected { user_pubkey });
+        assert!(!session.mark_recovered().await);
+    }
+
+    #[test]
+    fn test_normalize_signer_label() {
+        assert_eq!(normalize_signer_label(None).unwrap(), DEFAULT_SIGNER_LABEL);
+        assert_eq!(normalize_signer_label(Some("  ")).unwrap(), DEFAULT_SIGNER_LABEL);
+        assert_eq!(normalize_signer_label(Some(" work ")).unwrap(), "work");
+        assert_eq!(normalize_signer_label(Some("project_x-2")).unwrap(), "project_x-2");
+        assert!(normalize_signer_label(Some("with space")).is_err());
+        assert!(normalize_signer_label(Some(&"a".repeat(33))).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_registry_sessions_and_active() {
+        let registry = Nip46Registry::new(Nip46Config {
+            relays: vec![],
+            perms: None,
+            bunker_uri: None,
+        });
+
+        let personal = registry.session("personal").await;
+        let again = registry.session("personal").await;
+        assert!(Arc::ptr_eq(&personal, &again));
+        registry.session("work").await;
+
+        let labels: Vec<String> = registry.sessions().await.into_iter().map(|(l, _)| l).collect();
+        assert_eq!(labels, vec!["personal", "work"]);
+
+        registry.set_active("work").await;
+        assert_eq!(registry.active_label().await.as_deref(), Some("work"));
+        assert!(!registry.remove("personal").await);
+        assert!(registry.remove("work").await);
+        assert_eq!(registry.active_label().await, None);
+        assert!(registry.get("work").await.is_none());
+    }
+
     #[tokio::test]
     async fn test_status_json_disconnected() {
         let config = Nip46Config {
@@ -5,27 +5,69 @@
 
 use anyhow::{anyhow, Context, Result};
 use nostr_sdk::prelude::*;
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
+use crate::config::{SensitiveContentPolicy, SignatureVerification};
+use crate::dedup::EventDedup;
+use crate::nip05::Nip05Verifier;
+use crate::filters::{note_language, pow_difficulty, sensitive_reason, ContentFilter, LanguageFilter, MuteList, TrustGraph};
+use crate::pagination::{fetch_relay_pages, paginate_events};
+use crate::rate_limit::RateLimiter;
+use crate::subscriptions::{SubscriptionInfo, SubscriptionManager};
+use crate::zaps::{zap_receipt_sender, zap_totals_by_note, CachedZapProvider};
+
 /// Nostr クライアントの設定
 #[derive(Debug, Clone)]
 pub struct NostrClientConfig {
     /// nsec または hex 形式の秘密鍵（読み取り専用モードでは不要）
     pub secret_key: Option<String>,
+    /// npub または hex 形式の公開鍵（秘密鍵がない場合に読み取りをパーソナライズする）
+    pub public_key: Option<String>,
     /// 一般操作用リレー URL のリスト
     pub relays: Vec<String>,
+    /// 書き込み有効なリレー URL のリスト（インポート時の送信先）
+    pub write_relays: Vec<String>,
     /// NIP-50 検索対応リレー URL のリスト
     pub search_relays: Vec<String>,
     /// Nostr Wallet Connect URI（NIP-47、Zap 送信用）
     pub nwc_uri: Option<String>,
+    /// 支払いの上限設定（Zap・Lightning 支払い用）
+    pub payment_limits: crate::config::PaymentLimits,
+    /// Web of Trust フィルタのデフォルト設定
+    pub web_of_trust: Option<crate::config::WebOfTrust>,
+    /// 自分のコンタクトリストのキャッシュ有効期間（0 でキャッシュしない）
+    pub contact_list_cache_ttl: Duration,
+    /// リレーへのリクエストのレート制限
+    pub rate_limit: crate::config::RateLimitConfig,
+    /// 取得したイベントの署名検証の方法
+    pub signature_verification: SignatureVerification,
+    /// グローバルタイムライン・検索結果に要求する最小 PoW 難易度（0 で無効）
+    pub min_pow: u8,
+    /// ミュートワード・正規表現・アカウントの作成からの日数によるコンテンツフィルタ
+    pub content_filters: crate::config::ContentFilters,
+    /// コンテンツ警告・NSFW ラベル付きノートの扱い
+    pub sensitive_content: SensitiveContentPolicy,
+    /// タイムライン・ノート検索で表示する言語（空の場合はすべて）
+    pub languages: Vec<String>,
     /// 認証モード（Phase 6: NIP-46 対応）
     pub auth_mode: crate::config::AuthMode,
     /// NIP-46 セッション設定
     pub nip46_config: Option<crate::nip46::Nip46Config>,
+    /// NIP-26 委任署名の設定
+    pub delegation: Option<crate::config::DelegationConfig>,
+    /// ノート一覧を返すツールの出力量の設定
+    pub output: crate::config::OutputOptions,
+    /// 書き込みツールのイベントを公開せずに返すドライランモード
+    pub dry_run: bool,
+    /// `react_to_note` で `reaction` を省略したときのリアクション（プリセット名も可）
+    pub default_reaction: Option<String>,
+    /// 名前をキーとするリアクションのプリセット
+    pub reaction_presets: HashMap<String, crate::config::ReactionPreset>,
 }
 
 /// 著者情報（表示用）
@@ -35,6 +77,9 @@ pub struct AuthorInfo {
     pub pubkey: String,
     /// npub 形式の公開鍵
     pub npub: String,
+    /// プロフィールを受信したリレーをヒントに含めた nprofile（受信元が不明な場合は省略）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nprofile: Option<String>,
     /// ユーザー名（プロフィールの name フィールド）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
@@ -47,6 +92,12 @@ pub struct AuthorInfo {
     /// NIP-05 識別子
     #[serde(skip_serializing_if = "Option::is_none")]
     pub nip05: Option<String>,
+    /// NIP-05 識別子の `.well-known/nostr.json` がこの公開鍵を指していることを確認済みか
+    #[serde(default)]
+    pub nip05_verified: bool,
+    /// 元のプロフィールメタデータ（プロフィールの一括取得用、ノートの著者情報には出力しない）
+    #[serde(skip)]
+    pub metadata: Option<Metadata>,
 }
 
 impl AuthorInfo {
@@ -69,67 +120,196 @@ impl AuthorInfo {
     }
 
     /// 公開鍵からデフォルトの著者情報を作成
-    fn from_public_key(pk: &PublicKey) -> Self {
+    pub(crate) fn from_public_key(pk: &PublicKey) -> Self {
         Self {
             pubkey: pk.to_hex(),
             npub: pk.to_bech32().unwrap_or_default(),
+            nprofile: None,
             name: None,
             display_name: None,
             picture: None,
             nip05: None,
+            nip05_verified: false,
+            metadata: None,
+        }
+    }
+
+    /// プロフィールメタデータ (Kind 0) から著者情報を作成（`relays` はプロフィールを受信したリレー）
+    fn from_metadata(pk: &PublicKey, metadata: Metadata, relays: &[String]) -> Self {
+        Self {
+            pubkey: pk.to_hex(),
+            npub: pk.to_bech32().unwrap_or_default(),
+            nprofile: encode_nprofile(pk, relays),
+            name: metadata.name.clone(),
+            display_name: metadata.display_name.clone(),
+            picture: metadata.picture.clone(),
+            nip05: metadata.nip05.clone(),
+            nip05_verified: false,
+            metadata: Some(metadata),
         }
     }
 }
 
 /// nostr-sdk クライアントのラッパー
+///
+/// 状態は `Arc` で共有するため、バックグラウンドのタスクは複製してからロックを外して使えます。
+#[derive(Clone)]
 pub struct NostrClient {
     /// nostr-sdk クライアント
-    client: Client,
+    pub(crate) client: Client,
     /// 書き込みアクセスの有無（秘密鍵が設定されているか、または NIP-46 接続済み）
     has_write_access: bool,
-    /// 認証済みユーザーの公開鍵
-    public_key: Option<PublicKey>,
+    /// 認証済みユーザーの公開鍵（委任署名時は委任者の公開鍵）
+    pub(crate) public_key: Option<PublicKey>,
+    /// NIP-26 の委任タグ（設定時は委任先の鍵で署名し、公開するイベントに付与する）
+    delegation: Option<DelegationTag>,
     /// NIP-50 検索対応リレー
     search_relays: Vec<String>,
+    /// 書き込み有効なリレー
+    write_relays: Vec<String>,
     /// 接続状態
     connected: Arc<RwLock<bool>>,
     /// プロフィールキャッシュ（繰り返しのルックアップを回避）
     profile_cache: Arc<RwLock<HashMap<PublicKey, AuthorInfo>>>,
+    /// NIP-05 識別子の検証（結果のキャッシュとバックグラウンド検証）
+    nip05_verifier: Arc<Nip05Verifier>,
     /// NWC URI（Zap 送信用、Phase 4）
     #[allow(dead_code)]
     nwc_uri: Option<String>,
+    /// NWC ウォレット（Lightning アドレスへの直接支払い用）
+    nwc: Option<nwc::NWC>,
+    /// 支払いの上限設定
+    payment_limits: crate::config::PaymentLimits,
+    /// 支払い履歴（Unix タイムスタンプ, sats）。日次上限の判定に使用
+    payment_ledger: Arc<RwLock<Vec<(u64, u64)>>>,
+    /// 上限の確認・支払い・履歴の記録を直列化するロック（同時の支払いで上限を超えないようにする）
+    pub(crate) payment_lock: Arc<tokio::sync::Mutex<()>>,
+    /// 設定ファイルの publickey（読み取り専用のパーソナライズ用、NIP-46 切断時に戻す）
+    read_only_public_key: Option<PublicKey>,
     /// NIP-46 サイナーが有効か（Phase 6: 認証モード切り替え）
     nip46_active: Arc<RwLock<bool>>,
+    /// NIP-46 リモートサイナーが応答しない場合の理由（死活監視が設定、書き込みを一時停止）
+    nip46_degraded: Option<String>,
+    /// リレーごとの NIP-45 (COUNT) 対応状況キャッシュ（NIP-11 から判定）
+    nip45_support: Arc<RwLock<HashMap<RelayUrl, bool>>>,
+    /// ミュートリストのキャッシュ（取得日時, ミュートリスト）
+    mute_list: Arc<RwLock<Option<CachedMuteList>>>,
+    /// ノートごとのリアクション数・リプライ数のキャッシュ（取得日時, リアクション数, リプライ数）
+    note_count_cache: Arc<RwLock<HashMap<EventId, CachedNoteCounts>>>,
+    /// URL ごとのリンクプレビューのキャッシュ（取得日時, プレビュー）
+    link_preview_cache: Arc<RwLock<HashMap<String, CachedLinkPreview>>>,
+    /// 受取人ごとの LNURL プロバイダーの nostrPubkey のキャッシュ（取得日時, 公開鍵）
+    pub(crate) zap_provider_cache: Arc<RwLock<HashMap<PublicKey, CachedZapProvider>>>,
+    /// 自分のコンタクトリスト（フォロー中の公開鍵）のキャッシュ
+    contact_list_cache: Arc<RwLock<Option<CachedContactList>>>,
+    /// コンタクトリストのキャッシュ有効期間
+    contact_list_cache_ttl: Duration,
+    /// リレーへのリクエストのレート制限
+    pub(crate) rate_limiter: Arc<RateLimiter>,
+    /// 取得したイベントの署名検証の方法
+    pub(crate) signature_verification: SignatureVerification,
+    /// グローバルタイムライン・検索結果に要求する最小 PoW 難易度のデフォルト
+    min_pow: u8,
+    /// 設定ファイルの `filters` によるコンテンツフィルタ
+    content_filter: Arc<ContentFilter>,
+    /// コンテンツ警告・NSFW ラベル付きノートの扱い
+    sensitive_content: SensitiveContentPolicy,
+    /// タイムライン・ノート検索の言語フィルタのデフォルト
+    language_filter: LanguageFilter,
+    /// アカウントが作成から `min-account-age-days` 以上経っているかのキャッシュ（確認日時, 経っているか）
+    account_age_cache: Arc<RwLock<HashMap<PublicKey, CachedAccountAge>>>,
+    /// Web of Trust フィルタのデフォルト設定
+    web_of_trust: Option<crate::config::WebOfTrust>,
+    /// フォローグラフのキャッシュ（取得日時, ホップ数, グラフ）
+    trust_graph: Arc<RwLock<Option<CachedTrustGraph>>>,
+    /// 長時間の購読（通知・DM・ライブチャット等）
+    subscriptions: Arc<SubscriptionManager>,
+    /// 送信待ちキューの読み書きを直列化するロック
+    pending_publish_lock: Arc<tokio::sync::Mutex<()>>,
+    /// 一時的に接続したリレー（リレーヒント・受信者のリレー）ごとの使用中の数。0 になったらプールから削除する
+    gossip_relay_users: Arc<tokio::sync::Mutex<HashMap<RelayUrl, usize>>>,
+    /// 設定ファイルの `dry-run`（すべての書き込みをドライランにする）
+    dry_run: bool,
+    /// `reaction` 省略時のリアクション（プリセット名も可）
+    default_reaction: String,
+    /// 名前をキーとするリアクションのプリセット
+    reaction_presets: HashMap<String, crate::config::ReactionPreset>,
+    /// プリセットごとのリアクションの送信履歴（Unix タイムスタンプ）。1 時間あたりの上限の判定に使用
+    reaction_ledger: Arc<RwLock<HashMap<String, Vec<u64>>>>,
 }
 
 impl NostrClient {
     /// 指定された設定で新しい Nostr クライアントを作成します。
     pub async fn new(config: NostrClientConfig) -> Result<Self> {
-        let (client, has_write_access, public_key) = if let Some(ref secret_key_str) = config.secret_key {
+        let (client, has_write_access, public_key, delegation) = if let Some(ref delegation_config) = config.delegation {
+            let keys = Self::parse_secret_key(&delegation_config.delegatee_key)
+                .context("設定ファイルの delegation.delegatee-key が不正です")?;
+            let delegation = parse_delegation_token(&delegation_config.token, &keys.public_key())?;
+            let delegator = delegation.delegator_pubkey();
+
+            info!(
+                "NIP-26 委任署名で初期化: 委任者 {}（署名: {}）",
+                delegator.to_bech32()?,
+                keys.public_key().to_bech32()?
+            );
+            if config.secret_key.is_some() {
+                warn!("delegation が設定されているため privatekey は無視されます");
+            }
+
+            let client = Client::new(keys);
+            (client, true, Some(delegator), Some(delegation))
+        } else if let Some(ref secret_key_str) = config.secret_key {
             let keys = Self::parse_secret_key(secret_key_str)?;
             let public_key = keys.public_key();
 
             info!("公開鍵で初期化: {}", public_key.to_bech32()?);
 
             let client = Client::new(keys);
-            (client, true, Some(public_key))
+            (client, true, Some(public_key), None)
         } else {
+            // 公開鍵だけが設定されている場合は、その公開鍵で読み取り専用にパーソナライズする
+            let public_key = match config.public_key.as_deref() {
+                Some(pk_str) => {
+                    let pk = Self::parse_public_key(pk_str.trim())
+                        .context("設定ファイルの publickey が不正です")?;
+                    info!("読み取り専用の公開鍵で初期化: {}", pk.to_bech32()?);
+                    Some(pk)
+                }
+                None => None,
+            };
+
             let client = Client::default();
-            (client, false, None)
+            (client, false, public_key, None)
         };
 
+        if has_write_access && config.public_key.is_some() {
+            warn!("秘密鍵が設定されているため publickey は無視されます");
+        }
+
+        // algia と同様に read / write フラグを分けて登録する。
+        // 取得・購読は read リレーのみ、公開は write リレーのみに送られる
         for relay_url in &config.relays {
-            if let Err(e) = client.add_relay(relay_url).await {
+            if let Err(e) = client.add_read_relay(relay_url).await {
                 warn!("リレー {} の追加に失敗: {}", relay_url, e);
             }
         }
+        for relay_url in &config.write_relays {
+            if let Err(e) = client.add_write_relay(relay_url).await {
+                warn!("リレー {} の追加に失敗: {}", relay_url, e);
+            }
+        }
+        if has_write_access && config.write_relays.is_empty() {
+            warn!("書き込み有効なリレーがありません。投稿するには設定ファイルの relays で write を有効にしてください。");
+        }
 
         // Phase 4: NWC Zapper の設定
+        let mut nwc_wallet = None;
         if let Some(ref nwc_uri_str) = config.nwc_uri {
             match NostrWalletConnectURI::parse(nwc_uri_str) {
                 Ok(uri) => {
                     let nwc_zapper = nwc::NWC::new(uri);
-                    client.set_zapper(nwc_zapper).await;
+                    client.set_zapper(nwc_zapper.clone()).await;
+                    nwc_wallet = Some(nwc_zapper);
                     info!("NWC Zapper を設定しました");
                 }
                 Err(e) => {
@@ -138,18 +318,72 @@ impl NostrClient {
             }
         }
 
+        // 接続はバックグラウンドで行い、完了を待たずに MCP のハンドシェイクに進む。
+        // 取得系のツールは ensure_connected_relays でリレーの準備ができるのを待つ
         client.connect().await;
-        tokio::time::sleep(Duration::from_millis(500)).await;
+        tokio::spawn(check_startup_connectivity(client.clone()));
+
+        let subscriptions = Arc::new(SubscriptionManager::new(client.clone()));
+
+        // 日次の支払い上限が再起動をまたいでも有効になるよう、直近 24 時間の支払い履歴を読み込む
+        let now = current_unix_timestamp();
+        let payment_ledger: Vec<(u64, u64)> = crate::storage::load_payment_log()
+            .into_iter()
+            .filter(|record| record.paid_at + 86_400 > now)
+            .map(|record| (record.paid_at, record.amount_sats))
+            .collect();
+
+        // リアクションの送信上限も同様に、直近 1 時間の送信履歴を読み込む
+        let mut reaction_ledger = crate::storage::load_reaction_log();
+        for sent_at in reaction_ledger.values_mut() {
+            sent_at.retain(|ts| ts + 3_600 > now);
+        }
+        reaction_ledger.retain(|_, sent_at| !sent_at.is_empty());
 
         Ok(Self {
             client,
             has_write_access,
             public_key,
+            delegation,
             search_relays: config.search_relays,
+            write_relays: config.write_relays,
             connected: Arc::new(RwLock::new(true)),
             profile_cache: Arc::new(RwLock::new(HashMap::new())),
+            nip05_verifier: Arc::new(Nip05Verifier::new()),
             nwc_uri: config.nwc_uri,
+            nwc: nwc_wallet,
+            payment_limits: config.payment_limits,
+            payment_ledger: Arc::new(RwLock::new(payment_ledger)),
+            payment_lock: Arc::new(tokio::sync::Mutex::new(())),
+            read_only_public_key: if has_write_access { None } else { public_key },
             nip46_active: Arc::new(RwLock::new(false)),
+            nip46_degraded: None,
+            nip45_support: Arc::new(RwLock::new(HashMap::new())),
+            mute_list: Arc::new(RwLock::new(None)),
+            note_count_cache: Arc::new(RwLock::new(HashMap::new())),
+            link_preview_cache: Arc::new(RwLock::new(HashMap::new())),
+            zap_provider_cache: Arc::new(RwLock::new(HashMap::new())),
+            contact_list_cache: Arc::new(RwLock::new(None)),
+            contact_list_cache_ttl: config.contact_list_cache_ttl,
+            rate_limiter: Arc::new(RateLimiter::new(config.rate_limit)),
+            signature_verification: config.signature_verification,
+            min_pow: config.min_pow,
+            content_filter: Arc::new(ContentFilter::from_config(&config.content_filters)),
+            sensitive_content: config.sensitive_content,
+            language_filter: LanguageFilter::new(&config.languages),
+            account_age_cache: Arc::new(RwLock::new(HashMap::new())),
+            web_of_trust: config.web_of_trust,
+            trust_graph: Arc::new(RwLock::new(None)),
+            subscriptions,
+            pending_publish_lock: Arc::new(tokio::sync::Mutex::new(())),
+            gossip_relay_users: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            dry_run: config.dry_run,
+            default_reaction: config.default_reaction
+                .map(|reaction| reaction.trim().to_string())
+                .filter(|reaction| !reaction.is_empty())
+                .unwrap_or_else(|| "+".to_string()),
+            reaction_presets: config.reaction_presets,
+            reaction_ledger: Arc::new(RwLock::new(reaction_ledger)),
         })
     }
 
@@ -169,19 +403,41 @@ impl NostrClient {
     }
 
     /// 書き込みアクセスの有無を確認
-    #[allow(dead_code)]
     pub fn has_write_access(&self) -> bool {
         self.has_write_access
     }
 
+    /// 設定ファイルでドライランモードが有効か
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// この呼び出しでイベントを公開せずに記録するか（設定ファイルの `dry-run` またはツール呼び出しの `dry_run`）
+    fn is_dry_run(&self) -> bool {
+        self.dry_run || crate::dry_run::is_active()
+    }
+
+    /// ドライラン中に送金など取り消せない操作を拒否するヘルパー
+    pub fn refuse_in_dry_run(&self, action: &str) -> Result<()> {
+        if self.is_dry_run() {
+            return Err(anyhow!("ドライラン中は{}を実行できません（dry_run を外して再実行してください）", action));
+        }
+        Ok(())
+    }
+
     /// 認証済みの場合、公開鍵を取得
-    #[allow(dead_code)]
     pub fn public_key(&self) -> Option<PublicKey> {
         self.public_key
     }
 
     /// 書き込みアクセスを要求し、ない場合はエラーを返す
-    fn require_write_access(&self) -> Result<()> {
+    pub(crate) fn require_write_access(&self) -> Result<()> {
+        if let Some(reason) = &self.nip46_degraded {
+            return Err(anyhow!(
+                "NIP-46 リモートサイナーが応答しないため書き込みを一時停止しています（{}）。再接続を試行中です。nostr_connect_status で状態を確認してください。",
+                reason
+            ));
+        }
         if !self.has_write_access {
             return Err(anyhow!(
                 "読み取り専用モードではこの操作はできません。設定ファイルに nsec を設定するか、NIP-46 で接続してください。"
@@ -201,15 +457,87 @@ impl NostrClient {
             user_pubkey.to_bech32().unwrap_or_default()
         );
 
+        // 別のアイデンティティに切り替わる場合は自分に紐づくキャッシュを破棄する
+        if self.public_key != Some(user_pubkey) {
+            *self.mute_list.write().await = None;
+            *self.contact_list_cache.write().await = None;
+            *self.trust_graph.write().await = None;
+        }
+
         self.client.set_signer(signer).await;
         self.has_write_access = true;
         self.public_key = Some(user_pubkey);
+        self.delegation = None;
+        self.nip46_degraded = None;
         *self.nip46_active.write().await = true;
 
         info!("NIP-46 リモートサイナーが有効化されました");
         Ok(())
     }
 
+    /// 新しく生成した鍵に切り替え、初期プロフィール (Kind 0) とリレーリスト (NIP-65, Kind 10002) を公開します。
+    ///
+    /// 鍵の保存と NIP-46 サイナー使用中でないことの確認は呼び出し元で行います。
+    /// リレーリストには設定中の read / write リレーを登録します。
+    /// 公開に失敗しても切り替えは完了し、失敗の理由を `publish_errors` に含めます。
+    pub async fn switch_to_new_account(&mut self, keys: Keys, metadata: Metadata) -> Result<NewAccount> {
+        let public_key = keys.public_key();
+        info!("新しいアカウントに切り替え: {}", public_key.to_bech32()?);
+
+        *self.mute_list.write().await = None;
+        *self.contact_list_cache.write().await = None;
+        *self.trust_graph.write().await = None;
+        self.client.set_signer(keys).await;
+        self.has_write_access = true;
+        self.public_key = Some(public_key);
+        self.delegation = None;
+        self.read_only_public_key = None;
+
+        let relays: Vec<(RelayUrl, Option<RelayMetadata>)> = self.client.relays().await
+            .into_iter()
+            .filter_map(|(url, relay)| {
+                let flags = relay.flags();
+                match (flags.has_read(), flags.has_write()) {
+                    (true, true) => Some((url, None)),
+                    (true, false) => Some((url, Some(RelayMetadata::Read))),
+                    (false, true) => Some((url, Some(RelayMetadata::Write))),
+                    (false, false) => None,
+                }
+            })
+            .collect();
+
+        let mut publish_errors = Vec::new();
+        let profile = match self.send_event_builder(EventBuilder::metadata(&metadata)).await {
+            Ok(output) => Some(PublishResult::new(output, Some(public_key))),
+            Err(e) => {
+                publish_errors.push(format!("プロフィール: {}", e));
+                None
+            }
+        };
+        let relay_list = match self.send_event_builder(EventBuilder::relay_list(relays.clone())).await {
+            Ok(output) => Some(PublishResult::new(output, Some(public_key))),
+            Err(e) => {
+                publish_errors.push(format!("リレーリスト: {}", e));
+                None
+            }
+        };
+
+        Ok(NewAccount {
+            pubkey: public_key.to_hex(),
+            npub: public_key.to_bech32()?,
+            relays: relays.iter()
+                .map(|(url, metadata)| RelayListEntry {
+                    url: url.to_string(),
+                    read: metadata.is_none_or(|m| m == RelayMetadata::Read),
+                    write: metadata.is_none_or(|m| m == RelayMetadata::Write),
+                })
+                .collect(),
+            profile,
+            relay_list,
+            publish_errors,
+        })
+    }
+
     /// NIP-46 リモートサイナーを無効化し、元の状態に戻す
     pub async fn disable_nip46_signer(&mut self) {
         info!("NIP-46 サイナーを無効化");
@@ -220,8 +548,29 @@ impl NostrClient {
             // ローカル鍵がなければ書き込みを無効化
             // (client の signer はそのまま残るが、has_write_access で制御)
             self.has_write_access = false;
-            self.public_key = None;
+            self.public_key = self.read_only_public_key;
+        }
+        self.nip46_degraded = None;
+    }
+
+    /// NIP-46 リモートサイナーの応答状況を反映する。
+    /// `Some(理由)` で書き込みを一時停止し、`None` で再開する。
+    pub async fn set_nip46_degraded(&mut self, reason: Option<String>) {
+        if !*self.nip46_active.read().await {
+            return;
         }
+        self.has_write_access = reason.is_none();
+        self.nip46_degraded = reason;
+    }
+
+    /// NIP-26 の委任署名の条件（委任署名を使っていない場合は None）
+    pub fn delegation_conditions(&self) -> Option<Conditions> {
+        self.delegation.as_ref().map(DelegationTag::conditions)
+    }
+
+    /// NIP-46 リモートサイナーが応答しない場合の理由
+    pub fn nip46_degraded(&self) -> Option<&str> {
+        self.nip46_degraded.as_deref()
     }
 
     /// NIP-46 サイナーが有効かどうか
@@ -230,7 +579,7 @@ impl NostrClient {
     }
 
     /// 公開鍵のリストに対してプロフィールを取得（キャッシュ付き）
-    async fn fetch_profiles(&self, pubkeys: &[PublicKey]) -> HashMap<PublicKey, AuthorInfo> {
+    pub(crate) async fn fetch_profiles(&self, pubkeys: &[PublicKey]) -> HashMap<PublicKey, AuthorInfo> {
         let mut results = HashMap::new();
         let mut to_fetch = Vec::new();
 
@@ -247,6 +596,7 @@ impl NostrClient {
         }
 
         if to_fetch.is_empty() {
+            self.apply_nip05_verification(&mut results).await;
             return results;
         }
 
@@ -256,20 +606,15 @@ impl NostrClient {
             .kind(Kind::Metadata)
             .limit(to_fetch.len());
 
-        match self.client.fetch_events(vec![filter], Duration::from_secs(5)).await {
+        match self.fetch_events(vec![filter], Duration::from_secs(5)).await {
             Ok(events) => {
+                let events: Vec<Event> = events.into_iter().collect();
+                let seen_on = self.seen_on_relays(&events).await;
                 let mut cache = self.profile_cache.write().await;
 
                 for event in events {
                     if let Ok(metadata) = serde_json::from_str::<Metadata>(&event.content) {
-                        let author_info = AuthorInfo {
-                            pubkey: event.pubkey.to_hex(),
-                            npub: event.pubkey.to_bech32().unwrap_or_default(),
-                            name: metadata.name,
-                            display_name: metadata.display_name,
-                            picture: metadata.picture,
-                            nip05: metadata.nip05,
-                        };
+                        let author_info = AuthorInfo::from_metadata(&event.pubkey, metadata, relay_hints(&seen_on, &event.id));
                         cache.insert(event.pubkey, author_info.clone());
                         results.insert(event.pubkey, author_info);
                     }
@@ -288,1635 +633,9224 @@ impl NostrClient {
             }
         }
 
+        self.apply_nip05_verification(&mut results).await;
         results
     }
 
+    /// 著者情報の NIP-05 識別子の検証結果を nip05_verified に反映するヘルパー
+    ///
+    /// 未検証の識別子はバックグラウンドで検証を開始し、それまでは未確認（false）とします。
+    async fn apply_nip05_verification(&self, authors: &mut HashMap<PublicKey, AuthorInfo>) {
+        let targets: Vec<(PublicKey, String)> = authors.iter()
+            .filter_map(|(pk, author)| author.nip05.as_ref().map(|nip05| (*pk, nip05.trim().to_string())))
+            .filter(|(_, nip05)| !nip05.is_empty())
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+
+        let verified = self.nip05_verifier.check(&targets).await;
+        for (target, verified) in verified {
+            if let Some(author) = authors.get_mut(&target.0) {
+                author.nip05_verified = verified;
+            }
+        }
+    }
+
     /// イベントリストからノート情報のリストに変換するヘルパー
-    fn events_to_notes(&self, events: &[Event], profiles: &HashMap<PublicKey, AuthorInfo>) -> Vec<NoteInfo> {
-        events.iter().map(|event| {
+    ///
+    /// 設定ファイルの `filters` に一致するノートは除外し、`sensitive-content` の設定を適用します。
+    async fn events_to_notes(&self, events: &[Event], profiles: &HashMap<PublicKey, AuthorInfo>) -> Vec<NoteInfo> {
+        let mut seen_on = self.seen_on_relays(events).await;
+        let mut events = self.apply_content_filter(events).await;
+        if self.sensitive_content == SensitiveContentPolicy::Hide {
+            events.retain(|event| sensitive_reason(event).is_none());
+        }
+        events.into_iter().map(|event| {
+            let author_pk = event_author(event);
             let author = profiles
-                .get(&event.pubkey)
+                .get(&author_pk)
                 .cloned()
-                .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey));
+                .unwrap_or_else(|| AuthorInfo::from_public_key(&author_pk));
+            let seen_on = seen_on.remove(&event.id).unwrap_or_default();
 
-            NoteInfo {
+            let mut note = NoteInfo {
                 id: event.id.to_hex(),
-                nevent: event.id.to_bech32().unwrap_or_default(),
+                nevent: encode_nevent(&event.id, Some(event.pubkey), &seen_on),
                 author,
                 content: event.content.clone(),
                 created_at: event.created_at.as_u64(),
                 reactions: None,
                 replies: None,
-            }
+                reposts: None,
+                zap_sats: None,
+                verified: self.signature_status(event),
+                seen_on,
+                reposted_by: None,
+                content_warning: None,
+                content_hidden: false,
+                language: note_language(event),
+                signed_by: signed_by(event, &author_pk),
+            };
+            self.apply_sensitive_policy(event, &mut note);
+            note
         }).collect()
     }
 
+    /// イベントを受信したリレーを取得するヘルパー（nostr-sdk のデータベースが記録した受信元）
+    pub(crate) async fn seen_on_relays(&self, events: &[Event]) -> HashMap<EventId, Vec<String>> {
+        seen_on_relays_in(&self.client, events).await
+    }
+
+    /// 設定ファイルの `filters` に一致するイベントを除外するヘルパー
+    ///
+    /// 本文がミュートワード・正規表現に一致するイベントと、作成から `min-account-age-days` に
+    /// 満たないアカウントのイベントを除外します。
+    async fn apply_content_filter<'a>(&self, events: &'a [Event]) -> Vec<&'a Event> {
+        let mut kept: Vec<&Event> = events.iter()
+            .filter(|event| !self.content_filter.matches(&event.content))
+            .collect();
+
+        if self.content_filter.min_account_age_days > 0 && !kept.is_empty() {
+            let pubkeys: Vec<PublicKey> = kept.iter()
+                .map(|event| event.pubkey)
+                .collect::<std::collections::HashSet<_>>()
+                .into_iter()
+                .collect();
+            let young = self.young_accounts(&pubkeys).await;
+            kept.retain(|event| !young.contains(&event.pubkey));
+        }
+
+        if kept.len() < events.len() {
+            debug!("filters に一致するイベントを {} 件除外しました", events.len() - kept.len());
+        }
+        kept
+    }
+
+    /// コンテンツ警告・NSFW ラベル付きのノートに `sensitive-content` の設定を適用するヘルパー
+    ///
+    /// 警告の理由を `content_warning` に付け、`flag`（ノート自体を返す場合の `hide` を含む）では
+    /// 本文をプレースホルダーに置き換えます。
+    fn apply_sensitive_policy(&self, event: &Event, note: &mut NoteInfo) {
+        let Some(reason) = sensitive_reason(event) else { return };
+        if self.sensitive_content != SensitiveContentPolicy::Show {
+            note.content = format!("（センシティブな内容: {}。reveal_note で本文を表示できます）", reason);
+            note.content_hidden = true;
+        }
+        note.content_warning = Some(reason);
+    }
+
+    /// 作成から `min-account-age-days` に満たないアカウントを判定するヘルパー
+    ///
+    /// 基準日より前のイベントが 1 件も見つからないアカウントを新しいアカウントとみなします。
+    /// 自分自身と、確認に失敗したアカウントは対象外です。
+    async fn young_accounts(&self, pubkeys: &[PublicKey]) -> std::collections::HashSet<PublicKey> {
+        let cutoff = current_unix_timestamp().saturating_sub(self.content_filter.min_account_age_days * 86_400);
+        let mut young = std::collections::HashSet::new();
+        let mut unknown = Vec::new();
+        {
+            let cache = self.account_age_cache.read().await;
+            for pk in pubkeys {
+                if Some(*pk) == self.public_key {
+                    continue;
+                }
+                match cache.get(pk) {
+                    Some((_, true)) => {}
+                    Some((checked_at, false)) if checked_at.elapsed() < YOUNG_ACCOUNT_CACHE_TTL => {
+                        young.insert(*pk);
+                    }
+                    _ => unknown.push(*pk),
+                }
+            }
+        }
+
+        for chunk in unknown.chunks(ACCOUNT_AGE_FILTERS_PER_REQUEST) {
+            let filters = chunk.iter()
+                .map(|pk| Filter::new().author(*pk).until(Timestamp::from(cutoff)).limit(1))
+                .collect();
+            let events = match self.fetch_events(filters, Duration::from_secs(5)).await {
+                Ok(events) => events,
+                Err(e) => {
+                    debug!("アカウントの作成時期の確認に失敗: {}", e);
+                    continue;
+                }
+            };
+            let established: std::collections::HashSet<PublicKey> = events.into_iter()
+                .filter(|event| event.created_at.as_u64() <= cutoff)
+                .map(|event| event.pubkey)
+                .collect();
+
+            let mut cache = self.account_age_cache.write().await;
+            for pk in chunk {
+                let is_established = established.contains(pk);
+                cache.insert(*pk, (std::time::Instant::now(), is_established));
+                if !is_established {
+                    young.insert(*pk);
+                }
+            }
+        }
+        young
+    }
+
     /// イベントリストからユニークな公開鍵を収集
-    fn collect_pubkeys(events: &[Event]) -> Vec<PublicKey> {
+    pub(crate) fn collect_pubkeys(events: &[Event]) -> Vec<PublicKey> {
         events.iter()
-            .map(|e| e.pubkey)
+            .map(event_author)
             .collect::<std::collections::HashSet<_>>()
             .into_iter()
             .collect()
     }
 
-    /// ノートをタイムスタンプ降順でソートし、指定数に切り詰める
+    /// ノートをタイムスタンプ降順でソートし、指定数に切り詰める（リポストされたノートはリポストの日時で並べる）
     fn sort_and_truncate(notes: &mut Vec<NoteInfo>, limit: usize) {
-        notes.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        notes.sort_by_key(|x| Reverse(x.reposted_by.as_ref().map_or(x.created_at, |r| r.created_at)));
         notes.truncate(limit);
     }
 
+    /// リポスト (Kind 6, NIP-18) を元のノートに置き換えるヘルパー
+    ///
+    /// content に埋め込まれた元のノートを優先し、なければ e タグのノートを取得します。
+    /// 元のノートが見つからない・ミュート対象のリポストと、すでに含まれるノートのリポストは除外します。
+    /// 戻り値は (ノートのリスト, 元のノートの ID → リポストイベント)。
+    async fn resolve_reposts(&self, events: Vec<Event>) -> (Vec<Event>, HashMap<EventId, Event>) {
+        let (reposts, mut notes): (Vec<Event>, Vec<Event>) = events.into_iter()
+            .partition(|e| e.kind == Kind::Repost);
+        if reposts.is_empty() {
+            return (notes, HashMap::new());
+        }
+
+        let mut originals: HashMap<EventId, Event> = HashMap::new();
+        let mut targets: Vec<Option<EventId>> = Vec::with_capacity(reposts.len());
+        for repost in &reposts {
+            match embedded_reposted_note(repost) {
+                Some(original) => {
+                    targets.push(Some(original.id));
+                    originals.insert(original.id, original);
+                }
+                None => targets.push(repost.tags.event_ids().next().copied()),
+            }
+        }
+
+        let missing: Vec<EventId> = targets.iter()
+            .flatten()
+            .filter(|id| !originals.contains_key(id))
+            .copied()
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .collect();
+        if !missing.is_empty() {
+            let filter = Filter::new().ids(missing).kind(Kind::TextNote);
+            match self.fetch_events(vec![filter], Duration::from_secs(5)).await {
+                Ok(events) => originals.extend(events.into_iter().map(|e| (e.id, e))),
+                Err(e) => debug!("リポスト元のノートの取得に失敗: {}", e),
+            }
+        }
+        let originals: HashMap<EventId, Event> = self.filter_muted(originals.into_values().collect()).await
+            .into_iter()
+            .map(|e| (e.id, e))
+            .collect();
+
+        // 新しいリポストから順に採用し、同じノートは 1 件にまとめる
+        let mut reposts: Vec<(Event, Option<EventId>)> = reposts.into_iter().zip(targets).collect();
+        reposts.sort_by_key(|(repost, _)| Reverse(repost.created_at));
+        let mut included: std::collections::HashSet<EventId> = notes.iter().map(|e| e.id).collect();
+        let mut reposted_by = HashMap::new();
+        for (repost, target) in reposts {
+            let Some(original) = target.and_then(|id| originals.get(&id)) else { continue };
+            if !included.insert(original.id) {
+                continue;
+            }
+            notes.push(original.clone());
+            reposted_by.insert(original.id, repost);
+        }
+        (notes, reposted_by)
+    }
+
+    /// リポストで置き換えたノートに、リポストした著者の情報（`reposted_by`）を付けるヘルパー
+    async fn attach_reposted_by(
+        &self,
+        notes: &mut [NoteInfo],
+        reposts: &HashMap<EventId, Event>,
+        profiles: &HashMap<PublicKey, AuthorInfo>,
+    ) {
+        if reposts.is_empty() {
+            return;
+        }
+        let repost_events: Vec<Event> = reposts.values().cloned().collect();
+        let seen_on = self.seen_on_relays(&repost_events).await;
+
+        for note in notes {
+            let Some(repost) = EventId::from_hex(&note.id).ok().and_then(|id| reposts.get(&id)) else { continue };
+            note.reposted_by = Some(RepostInfo {
+                id: repost.id.to_hex(),
+                nevent: encode_nevent(&repost.id, Some(repost.pubkey), relay_hints(&seen_on, &repost.id)),
+                author: profiles
+                    .get(&repost.pubkey)
+                    .cloned()
+                    .unwrap_or_else(|| AuthorInfo::from_public_key(&repost.pubkey)),
+                created_at: repost.created_at.as_u64(),
+            });
+        }
+    }
+
     /// 新しいノート (Kind 1) を投稿します。
-    pub async fn post_note(&self, content: &str) -> Result<EventId> {
+    pub async fn post_note(&self, content: &str) -> Result<PublishResult> {
         self.require_write_access()?;
 
-        let builder = EventBuilder::text_note(content);
-        let output = self.client.send_event_builder(builder).await
+        // NIP-27: 本文でメンションしたユーザーを p タグで通知し、受信用リレーにも届ける
+        let mentioned = mentioned_pubkeys(content);
+        let builder = EventBuilder::text_note(content)
+            .tags(mentioned.iter().map(|pk| Tag::public_key(*pk)))
+            .tags(self.custom_emoji_tags(content).await);
+        let output = self.send_event_builder_to_inboxes(builder, &mentioned, false).await
             .context("ノートの公開に失敗しました")?;
 
         let event_id = *output.id();
         info!("ノートを公開しました。イベント ID: {}", event_id);
-        Ok(event_id)
+        Ok(PublishResult::new(output, self.public_key))
     }
 
-    /// タイムラインを取得します（認証済みの場合はフォロー中のユーザー、それ以外はグローバル）。
-    pub async fn get_timeline(&self, limit: u64) -> Result<Vec<NoteInfo>> {
-        let filter = if let Some(pk) = self.public_key {
-            let contact_filter = Filter::new()
-                .author(pk)
-                .kind(Kind::ContactList)
-                .limit(1);
-
-            let contacts: Vec<Event> = self.client
-                .fetch_events(vec![contact_filter], Duration::from_secs(5))
-                .await
-                .ok()
-                .into_iter()
-                .flatten()
-                .collect();
+    /// セグメントを連続した Kind 1 ノートのスレッドとして投稿します（NIP-10）。
+    ///
+    /// 2 件目以降は 1 件目を root、直前のノートを reply とする e タグで連結します。
+    /// `numbered` の場合は各ノートの末尾に「(1/3)」形式の番号を付けます。
+    /// 途中で公開に失敗した場合は、それまでに公開したノートの ID を含むエラーを返します。
+    pub async fn post_thread(&self, segments: &[String], numbered: bool) -> Result<Vec<PublishResult>> {
+        self.require_write_access()?;
 
-            if let Some(contact_event) = contacts.into_iter().next() {
-                let followed: Vec<PublicKey> = contact_event.tags.iter()
-                    .filter_map(|tag| {
-                        if let Some(TagStandard::PublicKey { public_key, .. }) = tag.as_standardized() {
-                            Some(*public_key)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
+        let segments: Vec<&str> = segments.iter().map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        if segments.is_empty() {
+            return Err(anyhow!("投稿する内容がありません"));
+        }
+        if segments.len() > MAX_THREAD_NOTES {
+            return Err(anyhow!(
+                "スレッドは最大 {} 件までです（{} 件）。max_length を大きくするか内容を減らしてください",
+                MAX_THREAD_NOTES,
+                segments.len()
+            ));
+        }
 
-                if !followed.is_empty() {
-                    debug!("フォロー中アカウント: {} 件", followed.len());
-                    Filter::new()
-                        .authors(followed)
-                        .kind(Kind::TextNote)
-                        .limit(limit as usize)
-                } else {
-                    Filter::new()
-                        .kind(Kind::TextNote)
-                        .limit(limit as usize)
-                }
-            } else {
-                Filter::new()
-                    .kind(Kind::TextNote)
-                    .limit(limit as usize)
-            }
+        let total = segments.len();
+        let emojis = if segments.iter().any(|s| !emoji_shortcodes(s).is_empty()) {
+            self.own_emoji_map().await
         } else {
-            Filter::new()
-                .kind(Kind::TextNote)
-                .limit(limit as usize)
+            HashMap::new()
         };
+        let mut published: Vec<PublishResult> = Vec::with_capacity(total);
+        for (i, segment) in segments.iter().enumerate() {
+            let content = if numbered && total > 1 {
+                format!("{}\n\n({}/{})", segment, i + 1, total)
+            } else {
+                segment.to_string()
+            };
 
-        let events = self.client
-            .fetch_events(vec![filter], Duration::from_secs(10))
-            .await
-            .context("タイムラインの取得に失敗しました")?;
+            let mentioned = mentioned_pubkeys(&content);
+            let mut tags = Vec::new();
+            if let (Some(root), Some(parent)) = (published.first(), published.last()) {
+                tags.push(Tag::parse(vec!["e".to_string(), root.id.to_hex(), String::new(), "root".to_string()]).unwrap());
+                tags.push(Tag::parse(vec!["e".to_string(), parent.id.to_hex(), String::new(), "reply".to_string()]).unwrap());
+            }
+            tags.extend(mentioned.iter().map(|pk| Tag::public_key(*pk)));
+            tags.extend(emoji_tags(&content, &emojis));
 
-        let events_vec: Vec<Event> = events.into_iter().collect();
-        let pubkeys = Self::collect_pubkeys(&events_vec);
-        let profiles = self.fetch_profiles(&pubkeys).await;
-        let mut notes = self.events_to_notes(&events_vec, &profiles);
-        Self::sort_and_truncate(&mut notes, limit as usize);
+            let builder = EventBuilder::text_note(content).tags(tags);
+            let output = match self.send_event_builder_to_inboxes(builder, &mentioned, false).await {
+                Ok(output) => output,
+                Err(e) => {
+                    let done: Vec<String> = published.iter().map(|p| p.id.to_hex()).collect();
+                    return Err(e.context(format!(
+                        "スレッドの {}/{} 件目の公開に失敗しました（公開済み: [{}]）",
+                        i + 1,
+                        total,
+                        done.join(", ")
+                    )));
+                }
+            };
+            published.push(PublishResult::new(output, self.public_key));
+        }
 
-        // リアクション数とリプライ数を取得
-        self.enrich_notes_with_counts(&mut notes).await;
+        info!("{} 件のノートをスレッドとして公開しました。ルート: {}", total, published[0].id);
+        Ok(published)
+    }
 
-        Ok(notes)
+    /// リレープール内のリレー URL 一覧（レート制限の対象）
+    async fn pool_relay_urls(&self) -> Vec<String> {
+        self.client.relays().await.keys().map(|url| url.to_string()).collect()
     }
 
-    /// ノートにリアクション数とリプライ数を付与するヘルパー
-    async fn enrich_notes_with_counts(&self, notes: &mut [NoteInfo]) {
-        if notes.is_empty() {
-            return;
-        }
+    /// 読み取り有効なリレー（イベントの取得先・リレーヒント）の URL 一覧
+    pub(crate) async fn read_relay_urls(&self) -> Vec<RelayUrl> {
+        self.client.relays().await
+            .into_iter()
+            .filter(|(_, relay)| relay.flags().has_read())
+            .map(|(url, _)| url)
+            .collect()
+    }
 
-        let event_ids: Vec<EventId> = notes.iter()
-            .filter_map(|n| EventId::from_hex(&n.id).ok())
-            .collect();
+    /// 接続中の読み取りリレーの URL を取得するヘルパー
+    pub(crate) async fn connected_read_relay_urls(&self) -> Vec<RelayUrl> {
+        self.client.relays().await
+            .into_iter()
+            .filter(|(_, relay)| relay.flags().has_read() && relay.is_connected())
+            .map(|(url, _)| url)
+            .collect()
+    }
 
-        if event_ids.is_empty() {
-            return;
+    /// 接続中の読み取りリレーがあることを確認するヘルパー
+    ///
+    /// 起動直後などで接続処理中のリレーがある場合は RELAY_READY_TIMEOUT まで、
+    /// すべて切断されている場合は自動再接続を RELAY_RECONNECT_WAIT だけ待ち、それでも接続がなければ
+    /// 空の結果やタイムアウトの代わりに「接続中のリレーがない」ことを示すエラーを返します。
+    pub(crate) async fn ensure_connected_relays(&self) -> Result<()> {
+        let started = std::time::Instant::now();
+        loop {
+            let statuses = read_relay_statuses(&self.client).await;
+            match relay_connectivity_problem(&statuses) {
+                None => return Ok(()),
+                Some(problem) if statuses.is_empty() || started.elapsed() >= relay_wait_limit(&statuses) => {
+                    return Err(anyhow!(problem));
+                }
+                Some(_) => tokio::time::sleep(Duration::from_millis(200)).await,
+            }
         }
+    }
 
-        // リアクション (Kind 7) を一括取得
-        let reaction_filter = Filter::new()
-            .kind(Kind::Reaction)
-            .events(event_ids.clone())
-            .limit(1000);
+    /// レート制限に従ってイベントを取得するヘルパー
+    ///
+    /// 接続中のリレーがない場合は ensure_connected_relays のエラーを返します。
+    /// 署名検証が `drop` の場合は、署名が不正なイベントを除外します。
+    pub(crate) async fn fetch_events(&self, filters: Vec<Filter>, timeout: Duration) -> Result<Events> {
+        self.ensure_connected_relays().await?;
+        self.rate_limiter.acquire(&self.pool_relay_urls().await).await;
+        if self.signature_verification != SignatureVerification::Drop {
+            return Ok(self.client.fetch_events(filters, timeout).await?);
+        }
 
-        // リプライ (Kind 1 で e タグ参照) を一括取得
-        let reply_filter = Filter::new()
-            .kind(Kind::TextNote)
-            .events(event_ids.clone())
-            .limit(1000);
+        let events = self.client.fetch_events(filters.clone(), timeout).await?;
+        let mut verified = Events::new(&filters);
+        verified.extend(drop_invalid_signatures(events.into_iter().collect()));
+        Ok(verified)
+    }
 
-        let (reactions_result, replies_result) = tokio::join!(
-            self.client.fetch_events(vec![reaction_filter], Duration::from_secs(5)),
-            self.client.fetch_events(vec![reply_filter], Duration::from_secs(5))
-        );
+    /// 読み取りリレーごとにイベントを取得し、ID で重複除去するヘルパー
+    ///
+    /// 件数の集計に使うため、応答したリレーの順に EventDedup へ追加して受信元リレーを記録します。
+    /// すべてのリレーで取得に失敗した場合はエラーを返します。
+    async fn fetch_events_deduped(&self, filters: Vec<Filter>, timeout: Duration) -> Result<EventDedup> {
+        self.ensure_connected_relays().await?;
+        let relays = self.connected_read_relay_urls().await;
+        let urls: Vec<String> = relays.iter().map(|url| url.to_string()).collect();
+        self.rate_limiter.acquire(&urls).await;
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for url in relays {
+            let client = self.client.clone();
+            let filters = filters.clone();
+            tasks.spawn(async move {
+                let result = client.fetch_events_from(vec![url.clone()], filters, timeout).await;
+                (url, result)
+            });
+        }
 
-        // リアクション数をカウント
-        let mut reaction_counts: HashMap<String, u64> = HashMap::new();
-        if let Ok(events) = reactions_result {
-            for event in events {
-                for tag in event.tags.iter() {
-                    let values = tag.as_slice();
-                    if values.len() >= 2 && values[0] == "e" {
-                        *reaction_counts.entry(values[1].to_string()).or_insert(0) += 1;
-                    }
+        let mut dedup = EventDedup::new();
+        let mut last_error = None;
+        let mut succeeded = false;
+        while let Some(Ok((url, result))) = tasks.join_next().await {
+            match result {
+                Ok(events) => {
+                    succeeded = true;
+                    let events: Vec<Event> = events.into_iter().collect();
+                    let events = if self.signature_verification == SignatureVerification::Drop {
+                        drop_invalid_signatures(events)
+                    } else {
+                        events
+                    };
+                    dedup.extend(events, Some(&url));
+                }
+                Err(e) => {
+                    debug!("リレー {} からの取得に失敗: {}", url, e);
+                    last_error = Some(e);
                 }
             }
         }
-
-        // リプライ数をカウント
-        let mut reply_counts: HashMap<String, u64> = HashMap::new();
-        if let Ok(events) = replies_result {
-            for event in events {
-                for tag in event.tags.iter() {
-                    let values = tag.as_slice();
-                    if values.len() >= 2 && values[0] == "e" {
-                        *reply_counts.entry(values[1].to_string()).or_insert(0) += 1;
-                    }
-                }
+        if !succeeded {
+            if let Some(e) = last_error {
+                return Err(e.into());
             }
         }
 
-        // ノートに付与
-        for note in notes.iter_mut() {
-            note.reactions = Some(*reaction_counts.get(&note.id).unwrap_or(&0));
-            note.replies = Some(*reply_counts.get(&note.id).unwrap_or(&0));
+        if dedup.duplicates() > 0 {
+            let single_relay = dedup.iter().filter(|event| dedup.seen_on(&event.id).len() == 1).count();
+            debug!(
+                "重複イベントを {} 件除外しました（{} 件中 {} 件は 1 つのリレーのみから取得）",
+                dedup.duplicates(),
+                dedup.len(),
+                single_relay
+            );
         }
+        Ok(dedup)
     }
 
-    /// NIP-50 対応リレーでノートを検索します。
-    pub async fn search_notes(&self, query: &str, limit: u64) -> Result<Vec<NoteInfo>> {
-        let search_client = Client::default();
+    /// 識別子のリレーヒントも問い合わせ先に加えてイベントを取得するヘルパー
+    ///
+    /// ヒントのうちプールの読み取りリレーにないものを一時的に接続し、通常の取得結果とまとめます。
+    async fn fetch_events_with_hints(&self, filters: Vec<Filter>, hints: &[String], timeout: Duration) -> Result<Events> {
+        let read_relays = self.read_relay_urls().await;
+        let hints: Vec<RelayUrl> = hints.iter()
+            .filter_map(|url| RelayUrl::parse(url).ok())
+            .filter(|url| !read_relays.contains(url))
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .take(MAX_FETCH_RELAY_HINTS)
+            .collect();
+        let hints = self.connect_gossip_relays(hints).await;
+        if hints.is_empty() {
+            return self.fetch_events(filters, timeout).await;
+        }
 
-        for relay_url in &self.search_relays {
-            if let Err(e) = search_client.add_relay(relay_url).await {
-                warn!("検索リレー {} の追加に失敗: {}", relay_url, e);
+        let urls: Vec<String> = hints.iter().map(|url| url.to_string()).collect();
+        self.rate_limiter.acquire(&urls).await;
+        let (events, hinted) = tokio::join!(
+            self.fetch_events(filters.clone(), timeout),
+            self.client.fetch_events_from(hints.clone(), filters.clone(), timeout),
+        );
+        self.release_gossip_relays(&hints).await;
+        let hinted = match hinted {
+            Ok(events) if self.signature_verification == SignatureVerification::Drop => {
+                let mut verified = Events::new(&filters);
+                verified.extend(drop_invalid_signatures(events.into_iter().collect()));
+                Ok(verified)
+            }
+            other => other.map_err(anyhow::Error::from),
+        };
+
+        match (events, hinted) {
+            (Ok(events), Ok(hinted)) => Ok(events.merge(hinted)),
+            (Ok(events), Err(e)) | (Err(e), Ok(events)) => {
+                debug!("リレーヒントを含む取得の一部に失敗: {}", e);
+                Ok(events)
             }
+            (Err(e), Err(_)) => Err(e),
         }
+    }
 
-        search_client.connect().await;
-        tokio::time::sleep(Duration::from_millis(300)).await;
+    /// 通常の取得・公開に使わないよう、read/write フラグなしでリレーをプールに追加して接続するヘルパー
+    ///
+    /// 接続できたリレーだけを返します。使い終わったら `release_gossip_relays` で削除してください。
+    async fn connect_gossip_relays(&self, urls: Vec<RelayUrl>) -> Vec<RelayUrl> {
+        let mut connected = Vec::new();
+        for url in urls {
+            // 同時に使う他のリクエストが削除しないよう、追加と使用中の数の更新を同じロックの中で行う
+            {
+                let mut users = self.gossip_relay_users.lock().await;
+                let opts = RelayOptions::default().flags(RelayServiceFlags::GOSSIP);
+                match self.client.pool().add_relay(url.clone(), opts).await {
+                    // 設定ファイルのリレーなど、もともとプールにあるリレーは削除の対象にしない
+                    Ok(added) if added || users.contains_key(&url) => *users.entry(url.clone()).or_default() += 1,
+                    Ok(_) => {}
+                    Err(e) => {
+                        debug!("リレー {} の追加に失敗: {}", url, e);
+                        continue;
+                    }
+                }
+            }
+            if let Err(e) = self.client.connect_relay(url.clone()).await {
+                debug!("リレー {} への接続に失敗: {}", url, e);
+                self.release_gossip_relays(std::slice::from_ref(&url)).await;
+                continue;
+            }
+            connected.push(url);
+        }
+        connected
+    }
 
-        let filter = Filter::new()
-            .kind(Kind::TextNote)
-            .search(query)
-            .limit(limit as usize);
+    /// `connect_gossip_relays` で接続したリレーの使用を終え、他に使っていなければプールから削除するヘルパー
+    async fn release_gossip_relays(&self, urls: &[RelayUrl]) {
+        let mut users = self.gossip_relay_users.lock().await;
+        for url in urls {
+            let Some(count) = users.get_mut(url) else { continue };
+            *count -= 1;
+            if *count > 0 {
+                continue;
+            }
+            users.remove(url);
+            // 使用中に読み取り・書き込み用として追加された場合はプールに残す
+            match self.client.relay(url).await {
+                Ok(relay) if relay.flags().has_any(RelayServiceFlags::READ | RelayServiceFlags::WRITE) => {
+                    relay.flags().remove(RelayServiceFlags::GOSSIP);
+                }
+                Ok(_) => {
+                    if let Err(e) = self.client.force_remove_relay(url.clone()).await {
+                        debug!("一時的に接続したリレー {} の削除に失敗: {}", url, e);
+                    }
+                }
+                Err(_) => {}
+            }
+        }
+    }
 
-        let events = search_client
-            .fetch_events(vec![filter], Duration::from_secs(15))
-            .await
-            .context("ノートの検索に失敗しました")?;
+    /// 署名検証の結果（検証が無効な場合は None）
+    fn signature_status(&self, event: &Event) -> Option<bool> {
+        match self.signature_verification {
+            SignatureVerification::Off => None,
+            _ => Some(event.verify().is_ok()),
+        }
+    }
 
-        let events_vec: Vec<Event> = events.into_iter().collect();
-        let pubkeys = Self::collect_pubkeys(&events_vec);
-        let profiles = self.fetch_profiles(&pubkeys).await;
-        let mut notes = self.events_to_notes(&events_vec, &profiles);
-        Self::sort_and_truncate(&mut notes, limit as usize);
+    /// レート制限に従って署名・公開するヘルパー
+    pub(crate) async fn send_event_builder(&self, builder: EventBuilder) -> Result<Output<EventId>> {
+        let event = self.sign_event_builder(builder).await
+            .context("イベントの署名に失敗しました")?;
+        self.send_event(event).await
+    }
 
-        let _ = search_client.disconnect().await;
+    /// 自分のイベントとして署名するヘルパー
+    ///
+    /// NIP-26 の委任署名が設定されている場合は delegation タグを付与し、
+    /// 署名したイベントが委任の条件（種類・作成日時）を満たさなければエラーを返します。
+    pub(crate) async fn sign_event_builder(&self, builder: EventBuilder) -> Result<Event> {
+        let Some(delegation) = &self.delegation else {
+            return Ok(self.client.sign_event_builder(builder).await?);
+        };
 
-        Ok(notes)
+        let tag = Tag::from_standardized(TagStandard::Delegation {
+            delegator: delegation.delegator_pubkey(),
+            conditions: delegation.conditions(),
+            sig: delegation.signature(),
+        });
+        let event = self.client.sign_event_builder(builder.tag(tag)).await?;
+        delegation.validate(&event.pubkey, &EventProperties::from_event(&event))
+            .map_err(|e| anyhow!(
+                "委任の条件を満たさないため公開できません（kind {}、条件: {}）: {}",
+                event.kind.as_u16(),
+                delegation.conditions(),
+                e
+            ))?;
+        Ok(event)
     }
 
-    /// 指定されたユーザーのプロフィール情報を取得します。
-    pub async fn get_profile(&self, npub: &str) -> Result<ProfileInfo> {
-        let npub = npub.trim();
-
-        let public_key = if npub.starts_with("npub") {
-            PublicKey::from_bech32(npub)
-                .context("無効な npub 形式です")?
-        } else {
-            PublicKey::from_hex(npub)
-                .context("無効な hex 公開鍵です")?
-        };
+    /// レート制限に従って署名済みイベントを公開するヘルパー
+    ///
+    /// 接続の失敗などでどのリレーにも送信できなかった場合は、イベントを送信待ちキューに保存してから
+    /// エラーを返します。キューのイベントはバックグラウンドで再送されます。すべてのリレーに
+    /// 拒否された場合（`blocked:`・`invalid:` など）はキューに保存せずにエラーを返します。
+    pub(crate) async fn send_event(&self, event: Event) -> Result<Output<EventId>> {
+        if self.is_dry_run() {
+            info!("ドライラン: イベント {} (kind {}) を公開せずに記録しました", event.id, event.kind.as_u16());
+            crate::dry_run::record(&event);
+            return Ok(Output { val: event.id, success: std::collections::HashSet::new(), failed: HashMap::new() });
+        }
+        self.rate_limiter.acquire(&self.pool_relay_urls().await).await;
+        let output = send_event_to_write_relays(&self.client, &event).await;
+        if !output.success.is_empty() {
+            return Ok(output);
+        }
+        let error = describe_publish_failures(&output);
+        // リレーに拒否されたイベントは再送しても受け付けられないため、キューに入れない
+        if is_rejected_by_all(&output) {
+            return Err(anyhow!("すべてのリレーがイベントを拒否しました（{}）", error));
+        }
 
-        let filter = Filter::new()
-            .author(public_key)
-            .kind(Kind::Metadata)
-            .limit(1);
+        let id = event.id;
+        if let Err(e) = self.queue_pending_publish(event, &error).await {
+            warn!("送信待ちキューへの保存に失敗: {}", e);
+            return Err(anyhow!("どのリレーにも送信できませんでした: {}", error));
+        }
+        Err(anyhow!(
+            "どのリレーにも送信できませんでした（{}）。イベント {} を送信待ちキューに保存し、バックグラウンドで再送します（get_pending_publishes で確認、flush_pending ですぐに再送）",
+            error,
+            id.to_hex()
+        ))
+    }
 
-        let events = self.client
-            .fetch_events(vec![filter], Duration::from_secs(10))
-            .await
-            .context("プロフィールの取得に失敗しました")?;
+    /// 受信者の受信用リレーにも届くようにイベントを署名・公開するヘルパー（NIP-65 アウトボックスモデル）
+    ///
+    /// 通常どおり write リレーに公開したうえで、共通のリレーがなくても受信者が受け取れるよう
+    /// 受信者の read リレー（`dm` の場合は NIP-17 の DM リレーを優先）にも送信します。
+    async fn send_event_builder_to_inboxes(
+        &self,
+        builder: EventBuilder,
+        recipients: &[PublicKey],
+        dm: bool,
+    ) -> Result<Output<EventId>> {
+        let event = self.sign_event_builder(builder).await
+            .context("イベントの署名に失敗しました")?;
+        let mut output = self.send_event(event.clone()).await?;
+        if self.is_dry_run() {
+            return Ok(output);
+        }
 
-        let profile_event = events
+        let inboxes: Vec<RelayUrl> = self.fetch_inbox_relays(recipients, dm).await
             .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("{} のプロフィールが見つかりません", npub))?;
+            .filter(|url| !output.success.contains(url) && !output.failed.contains_key(url))
+            .collect();
+        if inboxes.is_empty() {
+            return Ok(output);
+        }
 
-        let metadata: Metadata = serde_json::from_str(&profile_event.content)
-            .context("プロフィールメタデータのパースに失敗しました")?;
+        // 受信者のリレーは通常の取得・公開に使わないよう、read/write フラグなしでプールに追加する
+        let connected = self.connect_gossip_relays(inboxes).await;
+        if connected.is_empty() {
+            return Ok(output);
+        }
 
-        Ok(ProfileInfo {
-            pubkey: public_key.to_hex(),
-            npub: public_key.to_bech32()?,
-            name: metadata.name,
-            display_name: metadata.display_name,
-            about: metadata.about,
-            picture: metadata.picture,
-            banner: metadata.banner,
-            nip05: metadata.nip05,
-            lud16: metadata.lud16,
-            website: metadata.website,
-        })
+        let urls: Vec<String> = connected.iter().map(|url| url.to_string()).collect();
+        self.rate_limiter.acquire(&urls).await;
+        match self.client.send_event_to(connected.clone(), event).await {
+            Ok(extra) => {
+                debug!("受信者のリレーに送信: 成功 {} 件、失敗 {} 件", extra.success.len(), extra.failed.len());
+                output.success.extend(extra.success);
+                output.failed.extend(extra.failed);
+            }
+            Err(e) => warn!("受信者のリレーへの送信に失敗: {}", e),
+        }
+        self.release_gossip_relays(&connected).await;
+        Ok(output)
     }
 
-    // ========================================
-    // Phase 3: プロフィール統計情報
-    // ========================================
+    /// 受信者の受信用リレーを取得するヘルパー
+    ///
+    /// NIP-65 (Kind 10002) の read リレー（マーカーなしを含む）を使い、`dm` の場合は
+    /// NIP-17 の DM リレー (Kind 10050) があればそちらを優先します。自分自身は除外します。
+    async fn fetch_inbox_relays(&self, recipients: &[PublicKey], dm: bool) -> Vec<RelayUrl> {
+        let recipients: Vec<PublicKey> = recipients.iter()
+            .copied()
+            .filter(|pk| Some(*pk) != self.public_key)
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .collect();
+        if recipients.is_empty() {
+            return Vec::new();
+        }
 
-    /// ユーザーのプロフィール統計情報（フォロー数・フォロワー数・ノート数）を取得します。
-    pub async fn get_profile_stats(&self, pubkey_str: &str) -> Result<ProfileStats> {
-        let public_key = Self::parse_public_key(pubkey_str)?;
+        let mut kinds = vec![Kind::RelayList];
+        if dm {
+            kinds.push(Kind::from(KIND_DM_RELAY_LIST));
+        }
+        let filter = Filter::new().authors(recipients.clone()).kinds(kinds);
+        let events = match self.fetch_events(vec![filter], Duration::from_secs(5)).await {
+            Ok(events) => events,
+            Err(e) => {
+                debug!("受信者のリレーリストの取得に失敗: {}", e);
+                return Vec::new();
+            }
+        };
 
-        // フォロー数: Kind 3 (ContactList) の p タグ数
-        let contact_filter = Filter::new()
-            .author(public_key)
-            .kind(Kind::ContactList)
-            .limit(1);
+        // 受信者・Kind ごとに最新のイベントだけを使う
+        let mut latest: HashMap<(PublicKey, Kind), Event> = HashMap::new();
+        for event in events {
+            let key = (event.pubkey, event.kind);
+            if latest.get(&key).is_none_or(|e| e.created_at < event.created_at) {
+                latest.insert(key, event);
+            }
+        }
 
-        // ノート数: Kind 1 の件数（上限付き）
-        let notes_filter = Filter::new()
-            .author(public_key)
-            .kind(Kind::TextNote)
-            .limit(5000);
+        let mut relays: Vec<RelayUrl> = Vec::new();
+        for pk in recipients {
+            let dm_relays: Vec<RelayUrl> = latest.get(&(pk, Kind::from(KIND_DM_RELAY_LIST)))
+                .map(|event| {
+                    event.tags.iter()
+                        .filter_map(|tag| match tag.as_slice() {
+                            [name, url, ..] if name == "relay" => RelayUrl::parse(url).ok(),
+                            _ => None,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
 
-        // フォロワー数: Kind 3 で対象ユーザーを p タグで参照しているイベント
-        let followers_filter = Filter::new()
-            .kind(Kind::ContactList)
-            .pubkey(public_key)
-            .limit(5000);
+            let user_relays = if !dm_relays.is_empty() {
+                dm_relays
+            } else {
+                latest.get(&(pk, Kind::RelayList))
+                    .map(|event| {
+                        nip65::extract_relay_list(event)
+                            .filter(|(_, metadata)| !matches!(metadata, Some(RelayMetadata::Write)))
+                            .map(|(url, _)| url.clone())
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            };
 
-        let (contacts_result, notes_result, followers_result) = tokio::join!(
-            self.client.fetch_events(vec![contact_filter], Duration::from_secs(10)),
-            self.client.fetch_events(vec![notes_filter], Duration::from_secs(10)),
-            self.client.fetch_events(vec![followers_filter], Duration::from_secs(10))
-        );
+            for url in user_relays.into_iter().take(INBOX_RELAYS_PER_RECIPIENT) {
+                if !relays.contains(&url) {
+                    relays.push(url);
+                }
+            }
+        }
+        relays.truncate(MAX_INBOX_RELAYS);
+        relays
+    }
 
-        // フォロー数
-        let following = contacts_result
-            .ok()
-            .and_then(|events| events.into_iter().next())
-            .map(|event| {
-                event.tags.iter()
-                    .filter(|tag| {
-                        let values = tag.as_slice();
-                        values.len() >= 2 && values[0] == "p"
-                    })
-                    .count() as u64
-            })
-            .unwrap_or(0);
+    /// ユーザーのフォローリスト (Kind 3) から公開鍵の一覧を取得するヘルパー
+    ///
+    /// 自分のコンタクトリストは `contact_list_cache_ttl` の間キャッシュします。
+    pub(crate) async fn fetch_followed_pubkeys(&self, pk: PublicKey) -> Vec<PublicKey> {
+        let is_own = self.public_key == Some(pk) && !self.contact_list_cache_ttl.is_zero();
+        if is_own {
+            if let Some((fetched_at, follows)) = self.contact_list_cache.read().await.as_ref() {
+                if fetched_at.elapsed() < self.contact_list_cache_ttl {
+                    return follows.clone();
+                }
+            }
+        }
 
-        // ノート数
-        let notes = notes_result
-            .map(|events| events.into_iter().count() as u64)
-            .unwrap_or(0);
+        let follows = self.fetch_contact_list_pubkeys(pk).await;
 
-        // フォロワー数（ユニークな著者のみカウント）
-        let followers = followers_result
-            .map(|events| {
-                events.into_iter()
-                    .map(|e| e.pubkey)
-                    .collect::<std::collections::HashSet<_>>()
-                    .len() as u64
-            })
-            .unwrap_or(0);
+        // 取得失敗と区別できないため、空のリストはキャッシュしない
+        if is_own && !follows.is_empty() {
+            *self.contact_list_cache.write().await = Some((std::time::Instant::now(), follows.clone()));
+        }
+        follows
+    }
 
-        Ok(ProfileStats {
-            following,
-            followers,
-            notes,
-        })
+    /// 自分のコンタクトリストのキャッシュを破棄します（フォロー変更後や再取得時に使用）。
+    pub async fn invalidate_contact_list_cache(&self) {
+        *self.contact_list_cache.write().await = None;
     }
 
-    // ========================================
-    // Phase 1: NIP-23 長文コンテンツサポート
-    // ========================================
+    /// リレーからコンタクトリスト (Kind 3) を取得し、フォロー中の公開鍵を返すヘルパー
+    async fn fetch_contact_list_pubkeys(&self, pk: PublicKey) -> Vec<PublicKey> {
+        let contact_filter = Filter::new()
+            .author(pk)
+            .kind(Kind::ContactList)
+            .limit(1);
 
-    /// 長文記事 (Kind 30023) を投稿します。
-    pub async fn post_article(&self, params: ArticleParams) -> Result<ArticleInfo> {
-        self.publish_article_event(params, Kind::LongFormTextNote, false).await
-    }
+        let contacts: Vec<Event> = self.fetch_events(vec![contact_filter], Duration::from_secs(5))
+            .await
+            .ok()
+            .into_iter()
+            .flatten()
+            .collect();
 
-    /// 長文記事 (Kind 30023) を取得します。
-    pub async fn get_articles(&self, author: Option<&str>, tags: Option<&[String]>, limit: u64) -> Result<Vec<ArticleInfo>> {
-        self.fetch_articles_by_kind(Kind::LongFormTextNote, author, tags, limit).await
+        contacts.into_iter()
+            .max_by_key(|e| e.created_at)
+            .map(|contact_event| {
+                contact_event.tags.iter()
+                    .filter_map(|tag| {
+                        if let Some(TagStandard::PublicKey { public_key, .. }) = tag.as_standardized() {
+                            Some(*public_key)
+                        } else {
+                            None
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
-    /// 記事を下書き (Kind 30024) として保存します。
-    pub async fn save_draft(&self, params: ArticleParams) -> Result<ArticleInfo> {
-        self.publish_article_event(params, Kind::from(30024), true).await
+    /// タイムラインを取得します（認証済みの場合はフォロー中のユーザー、それ以外はグローバル）。
+    ///
+    /// `min_pow` はグローバルタイムラインの場合のみ適用します（フォロー中のユーザーには適用しない）。
+    /// `languages` で許可しない言語のノートは件数を絞る前に除外し、戻り値はノートと除外した件数です。
+    pub async fn get_timeline(
+        &self,
+        limit: u64,
+        min_pow: u8,
+        include_replies: bool,
+        languages: &LanguageFilter,
+    ) -> Result<(Vec<NoteInfo>, usize)> {
+        let mut filter = Filter::new().kinds([Kind::TextNote, Kind::Repost]);
+        let mut min_pow = min_pow;
+
+        if let Some(followed) = self.timeline_authors().await {
+            filter = filter.authors(followed);
+            min_pow = 0;
+        }
+        let mut fetch_limit = pow_fetch_limit(limit, min_pow);
+        if !include_replies {
+            // リプライを除くと件数が減るため多めに取得する
+            fetch_limit = (fetch_limit as u64 * REPLY_FILTER_FETCH_MULTIPLIER).min(POW_FETCH_MAX).max(limit) as usize;
+        }
+        if !languages.is_empty() {
+            // 言語で絞ると件数が減るため多めに取得する
+            fetch_limit = (fetch_limit as u64 * LANGUAGE_FILTER_FETCH_MULTIPLIER).min(POW_FETCH_MAX).max(limit) as usize;
+        }
+
+        let events = self.paginate_pool_events(filter, fetch_limit, Duration::from_secs(10))
+            .await
+            .context("タイムラインの取得に失敗しました")?;
+
+        let events_vec = Self::filter_by_pow(events, min_pow);
+        let events_vec = self.filter_muted(events_vec).await;
+        let (mut events_vec, reposts) = self.resolve_reposts(events_vec).await;
+        if !include_replies {
+            events_vec.retain(|event| !is_reply(event));
+        }
+        let mut pubkeys = Self::collect_pubkeys(&events_vec);
+        for pk in reposts.values().map(|e| e.pubkey) {
+            if !pubkeys.contains(&pk) {
+                pubkeys.push(pk);
+            }
+        }
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let mut notes = self.events_to_notes(&events_vec, &profiles).await;
+        self.attach_reposted_by(&mut notes, &reposts, &profiles).await;
+        let excluded = languages.apply(&mut notes, |n| n.language.as_deref());
+        Self::sort_and_truncate(&mut notes, limit as usize);
+
+        // リアクション数とリプライ数を取得
+        self.enrich_notes_with_counts(&mut notes).await;
+
+        Ok((notes, excluded))
     }
 
-    /// ユーザーの下書き記事 (Kind 30024) を取得します。
-    pub async fn get_drafts(&self, limit: u64) -> Result<Vec<ArticleInfo>> {
-        self.fetch_articles_by_kind(Kind::from(30024), None, None, limit).await
+    /// タイムラインの対象となるフォロー中のアカウント（未認証・フォローなしの場合は None = グローバル）
+    async fn timeline_authors(&self) -> Option<Vec<PublicKey>> {
+        let pk = self.public_key?;
+        let followed = self.fetch_followed_pubkeys(pk).await;
+        if followed.is_empty() {
+            return None;
+        }
+        debug!("フォロー中アカウント: {} 件", followed.len());
+        Some(followed)
     }
 
-    /// 記事/下書きを公開する共通ヘルパー
-    async fn publish_article_event(&self, params: ArticleParams, kind: Kind, is_draft: bool) -> Result<ArticleInfo> {
-        self.require_write_access()?;
+    /// 直近 `hours` 時間のタイムラインを最大 `window` 件取得し、ノート本文の代わりに集計結果を返します。
+    ///
+    /// 投稿数の多い著者・よく使われたハッシュタグ・リアクションの多いノートをそれぞれ上位 `top` 件まで返します。
+    pub async fn get_timeline_digest(&self, hours: u64, window: u64, top: usize) -> Result<TimelineDigest> {
+        let since = current_unix_timestamp().saturating_sub(hours * 3600);
+        let mut filter = Filter::new()
+            .kind(Kind::TextNote)
+            .since(Timestamp::from(since))
+            .limit(window as usize);
 
-        let d_tag = params.identifier.unwrap_or_else(|| {
-            slug_from_title(&params.title)
-        });
+        let authors = self.timeline_authors().await;
+        let scope = if authors.is_some() { "following" } else { "global" };
+        if let Some(authors) = authors {
+            filter = filter.authors(authors);
+        }
 
-        let mut tags = build_article_tags(&params.title, &params.summary, &params.image, &params.tags, &d_tag);
+        let events = self.fetch_events(vec![filter], Duration::from_secs(15))
+            .await
+            .context("タイムラインの取得に失敗しました")?;
+        let mut events_vec = self.filter_muted(events.into_iter().collect()).await;
+        events_vec.sort_by_key(|e| Reverse(e.created_at));
+        events_vec.truncate(window as usize);
 
-        // 公開記事の場合のみ published_at を追加
-        if !is_draft {
-            let ts = params.published_at.unwrap_or_else(current_unix_timestamp);
-            tags.push(Tag::custom(
-                TagKind::custom("published_at".to_string()),
-                vec![ts.to_string()],
-            ));
+        // 著者・ハッシュタグの集計
+        let mut author_counts: HashMap<PublicKey, u64> = HashMap::new();
+        let mut hashtag_counts: HashMap<String, u64> = HashMap::new();
+        for event in &events_vec {
+            *author_counts.entry(event.pubkey).or_default() += 1;
+            for tag in event_hashtags(event) {
+                *hashtag_counts.entry(tag).or_default() += 1;
+            }
         }
+        let unique_authors = author_counts.len();
+        let top_authors = top_counts(author_counts, top);
+        let top_hashtags = top_counts(hashtag_counts, top);
+
+        // リアクション数・リプライ数の集計
+        let event_ids: Vec<EventId> = events_vec.iter().map(|e| e.id).collect();
+        let interactions = if event_ids.is_empty() {
+            HashMap::new()
+        } else {
+            self.fetch_note_interactions(&event_ids).await
+        };
+        let total_reactions: u64 = interactions.values().map(|c| c.reactions).sum();
+        let total_replies: u64 = interactions.values().map(|c| c.replies).sum();
+        let mut ranked: Vec<(&Event, (u64, u64))> = events_vec.iter()
+            .map(|e| (e, interactions.get(&e.id).map_or((0, 0), |c| (c.reactions, c.replies))))
+            .filter(|(_, (reactions, _))| *reactions > 0)
+            .collect();
+        ranked.sort_by_key(|(e, (reactions, replies))| (Reverse(*reactions), Reverse(*replies), Reverse(e.created_at)));
+        ranked.truncate(top);
 
-        let builder = EventBuilder::new(kind, &params.content).tags(tags);
+        let mut pubkeys: Vec<PublicKey> = top_authors.iter().map(|(pk, _)| *pk).collect();
+        pubkeys.extend(ranked.iter().map(|(e, _)| e.pubkey));
+        pubkeys.sort();
+        pubkeys.dedup();
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let seen_on = self.seen_on_relays(&events_vec).await;
+        let author_info = |pk: &PublicKey| profiles
+            .get(pk)
+            .cloned()
+            .unwrap_or_else(|| AuthorInfo::from_public_key(pk));
+
+        Ok(TimelineDigest {
+            scope: scope.to_string(),
+            hours,
+            total_notes: events_vec.len() as u64,
+            unique_authors: unique_authors as u64,
+            total_reactions,
+            total_replies,
+            oldest_at: events_vec.last().map(|e| e.created_at.as_u64()),
+            newest_at: events_vec.first().map(|e| e.created_at.as_u64()),
+            top_authors: top_authors.iter()
+                .map(|(pk, notes)| DigestAuthor { author: author_info(pk), notes: *notes })
+                .collect(),
+            top_hashtags: top_hashtags.into_iter()
+                .map(|(hashtag, count)| DigestHashtag { hashtag, count })
+                .collect(),
+            top_notes: ranked.into_iter()
+                .map(|(event, (reactions, replies))| DigestNote {
+                    id: event.id.to_hex(),
+                    nevent: encode_nevent(&event.id, Some(event.pubkey), relay_hints(&seen_on, &event.id)),
+                    author: author_info(&event.pubkey).display(),
+                    preview: truncate_preview(&event.content, DIGEST_PREVIEW_CHARS),
+                    reactions,
+                    replies,
+                    created_at: event.created_at.as_u64(),
+                })
+                .collect(),
+        })
+    }
 
-        let label = if is_draft { "下書き" } else { "記事" };
-        let output = self.client.send_event_builder(builder).await
-            .context(format!("{}の公開に失敗しました", label))?;
+    /// ハッシュタグ付きのノートを直近 `days` 日間とその前の同じ長さの期間について最大 `window` 件取得し、
+    /// 日ごとの投稿数・よく投稿したアカウント・一緒に使われたハッシュタグを集計します。
+    ///
+    /// 2 つの期間の投稿数を比べた増減率も返します。取得件数が `window` に達した場合は
+    /// 古い期間の一部しか集計できていないため `sampled` を true にします（ミュートリスト適用）。
+    pub async fn get_hashtag_stats(&self, hashtag: &str, days: u64, window: u64, top: usize) -> Result<HashtagStats> {
+        let (hashtag, spellings) = normalize_hashtag(hashtag)?;
+        let until = current_unix_timestamp();
+        let current_since = until.saturating_sub(days * 86_400);
+        let since = current_since.saturating_sub(days * 86_400);
 
-        let event_id = *output.id();
-        info!("{}を公開しました。イベント ID: {}", label, event_id);
+        let filter = Filter::new()
+            .kind(Kind::TextNote)
+            .hashtags(spellings)
+            .since(Timestamp::from(since));
+        let events = self.paginate_pool_events(filter, window as usize, Duration::from_secs(15))
+            .await
+            .context("ハッシュタグのノートの取得に失敗しました")?;
+        let sampled = events.len() >= window as usize;
+        let events = self.filter_muted(events).await;
+
+        let mut author_counts: HashMap<PublicKey, u64> = HashMap::new();
+        let mut tag_counts: HashMap<String, u64> = HashMap::new();
+        let mut current_notes = 0;
+        for event in &events {
+            *author_counts.entry(event.pubkey).or_default() += 1;
+            for tag in event_hashtags(event).into_iter().filter(|t| *t != hashtag) {
+                *tag_counts.entry(tag).or_default() += 1;
+            }
+            if event.created_at.as_u64() >= current_since {
+                current_notes += 1;
+            }
+        }
+        let previous_notes = events.len() as u64 - current_notes;
+        let unique_authors = author_counts.len() as u64;
+        let top_authors = top_counts(author_counts, top);
 
-        let naddr = self.public_key.and_then(|pk| {
-            Coordinate::new(kind, pk).identifier(&d_tag).to_bech32().ok()
-        });
+        let pubkeys: Vec<PublicKey> = top_authors.iter().map(|(pk, _)| *pk).collect();
+        let profiles = self.fetch_profiles(&pubkeys).await;
 
-        Ok(ArticleInfo {
-            id: event_id.to_hex(),
-            nevent: event_id.to_bech32().unwrap_or_default(),
-            naddr,
-            identifier: d_tag,
-            title: params.title,
-            summary: params.summary,
-            image: params.image,
-            content: params.content,
-            author: self.public_key.map(|pk| AuthorInfo::from_public_key(&pk)),
-            published_at: params.published_at,
-            created_at: current_unix_timestamp(),
-            tags: params.tags,
-            is_draft,
+        Ok(HashtagStats {
+            hashtag,
+            days,
+            since,
+            until,
+            total_notes: events.len() as u64,
+            unique_authors,
+            current_notes,
+            previous_notes,
+            change_percent: (previous_notes > 0 && !sampled)
+                .then(|| (current_notes as f64 - previous_notes as f64) * 100.0 / previous_notes as f64),
+            sampled,
+            daily: daily_note_counts(&events, since, until),
+            top_authors: top_authors.into_iter()
+                .map(|(pk, notes)| DigestAuthor {
+                    author: profiles.get(&pk).cloned().unwrap_or_else(|| AuthorInfo::from_public_key(&pk)),
+                    notes,
+                })
+                .collect(),
+            related_hashtags: top_counts(tag_counts, top).into_iter()
+                .map(|(hashtag, count)| DigestHashtag { hashtag, count })
+                .collect(),
         })
     }
 
-    /// 記事/下書きを取得する共通ヘルパー
-    async fn fetch_articles_by_kind(
-        &self,
-        kind: Kind,
-        author: Option<&str>,
-        tags: Option<&[String]>,
-        limit: u64,
-    ) -> Result<Vec<ArticleInfo>> {
-        let is_draft = kind == Kind::from(30024);
-
-        // 下書き取得は認証必須
-        let mut filter = if is_draft {
-            let pk = self.public_key
-                .ok_or_else(|| anyhow!("下書きの取得には認証が必要です。設定ファイルに nsec を設定してください。"))?;
-            Filter::new().author(pk).kind(kind).limit(limit as usize)
-        } else {
-            let mut f = Filter::new().kind(kind).limit(limit as usize);
-            if let Some(author_str) = author {
-                let pk = Self::parse_public_key(author_str)?;
-                f = f.author(pk);
+    /// ウォッチリストにアカウントを追加します（登録済みの場合はラベルを更新）。
+    ///
+    /// 戻り値は (公開鍵, 新規追加かどうか)。
+    pub fn add_watch_account(&self, pubkey_str: &str, label: Option<&str>) -> Result<(PublicKey, bool)> {
+        let pk = Self::parse_public_key(pubkey_str)?;
+        let label = label.map(str::trim).filter(|l| !l.is_empty()).map(str::to_string);
+
+        let mut list = crate::storage::load_watch_list();
+        let added = match list.get_mut(&pk.to_hex()) {
+            Some(existing) => {
+                if label.is_some() {
+                    existing.label = label;
+                }
+                false
+            }
+            None => {
+                list.insert(pk.to_hex(), crate::storage::WatchedAccount {
+                    label,
+                    added_at: current_unix_timestamp(),
+                });
+                true
             }
-            f
         };
+        crate::storage::save_watch_list(&list)?;
+        Ok((pk, added))
+    }
 
-        if let Some(hashtags) = tags {
-            filter = filter.hashtags(hashtags.to_vec());
+    /// ウォッチリストからアカウントを削除します。登録されていなかった場合は false を返します。
+    pub fn remove_watch_account(&self, pubkey_str: &str) -> Result<bool> {
+        let pk = Self::parse_public_key(pubkey_str)?;
+        let mut list = crate::storage::load_watch_list();
+        if list.remove(&pk.to_hex()).is_none() {
+            return Ok(false);
         }
+        crate::storage::save_watch_list(&list)?;
+        Ok(true)
+    }
 
-        let timeout = if is_draft { 10 } else { 15 };
-        let events = self.client
-            .fetch_events(vec![filter], Duration::from_secs(timeout))
-            .await
-            .context(format!("{}の取得に失敗しました", if is_draft { "下書き" } else { "記事" }))?;
-
-        let events_vec: Vec<Event> = events.into_iter().collect();
-        let pubkeys = Self::collect_pubkeys(&events_vec);
-        let profiles = self.fetch_profiles(&pubkeys).await;
-
-        let mut articles: Vec<ArticleInfo> = events_vec.iter().map(|event| {
-            let mut article = Self::event_to_article(event, &profiles);
-            if is_draft {
-                article.is_draft = true;
-            }
-            article
-        }).collect();
-
-        articles.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-        articles.truncate(limit as usize);
-
-        Ok(articles)
+    /// アドレス帳に別名を登録します（登録済みの場合は公開鍵とメモを更新）。
+    ///
+    /// 戻り値は (正規化した別名, 公開鍵, 以前に登録されていた hex 公開鍵)。
+    pub fn add_contact_alias(&self, alias: &str, pubkey_str: &str, note: Option<&str>) -> Result<(String, PublicKey, Option<String>)> {
+        let alias = crate::storage::normalize_alias(alias)
+            .ok_or_else(|| anyhow!("「{}」は別名として使えません（空白・@・:・/ を含まない 64 文字以内で、公開鍵と紛らわしくない名前を指定してください）", alias))?;
+        let pk = Self::parse_public_key(pubkey_str)?;
+        let note = note.map(str::trim).filter(|n| !n.is_empty()).map(str::to_string);
+
+        let mut book = crate::storage::load_address_book();
+        let previous = book.insert(alias.clone(), crate::storage::ContactAlias {
+            pubkey: pk.to_hex(),
+            note,
+            added_at: current_unix_timestamp(),
+        });
+        crate::storage::save_address_book(&book)?;
+        Ok((alias, pk, previous.map(|entry| entry.pubkey)))
     }
 
-    /// 公開鍵文字列をパース（npub または hex 対応）
-    fn parse_public_key(key_str: &str) -> Result<PublicKey> {
-        let key_str = key_str.trim();
-        if key_str.starts_with("npub") {
-            PublicKey::from_bech32(key_str).context("無効な npub 形式です")
-        } else {
-            PublicKey::from_hex(key_str).context("無効な hex 公開鍵です")
+    /// アドレス帳から別名を削除します。登録されていなかった場合は false を返します。
+    pub fn remove_contact_alias(&self, alias: &str) -> Result<bool> {
+        let Some(alias) = crate::storage::normalize_alias(alias) else { return Ok(false) };
+        let mut book = crate::storage::load_address_book();
+        if book.remove(&alias).is_none() {
+            return Ok(false);
         }
+        crate::storage::save_address_book(&book)?;
+        Ok(true)
     }
 
-    /// イベントから記事情報に変換するヘルパー
-    fn event_to_article(event: &Event, profiles: &HashMap<PublicKey, AuthorInfo>) -> ArticleInfo {
-        let author = profiles
-            .get(&event.pubkey)
-            .cloned()
-            .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey));
-
-        let identifier = event.tags.iter()
-            .find_map(|tag| {
-                if let Some(TagStandard::Identifier(id)) = tag.as_standardized() {
-                    Some(id.clone())
-                } else {
-                    None
-                }
+    /// アドレス帳の別名を返します（`alias` を指定した場合はその別名のみ）。
+    ///
+    /// 登録された公開鍵のプロフィールも取得して表示名を添えます。
+    pub async fn get_contact_aliases(&self, alias: Option<&str>) -> Vec<ContactAliasInfo> {
+        let mut book = crate::storage::load_address_book();
+        if let Some(alias) = alias {
+            let alias = crate::storage::normalize_alias(alias).unwrap_or_default();
+            book.retain(|name, _| *name == alias);
+        }
+        let entries: Vec<(String, PublicKey, crate::storage::ContactAlias)> = book.into_iter()
+            .filter_map(|(name, entry)| PublicKey::from_hex(&entry.pubkey).ok().map(|pk| (name, pk, entry)))
+            .collect();
+        let pubkeys: Vec<PublicKey> = entries.iter().map(|(_, pk, _)| *pk).collect();
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        entries.into_iter()
+            .map(|(alias, pk, entry)| ContactAliasInfo {
+                alias,
+                author: profiles.get(&pk).cloned().unwrap_or_else(|| AuthorInfo::from_public_key(&pk)),
+                note: entry.note,
+                added_at: entry.added_at,
             })
-            .unwrap_or_default();
+            .collect()
+    }
 
-        let title = extract_tag_value(event, "title")
-            .unwrap_or_else(|| "無題".to_string());
+    /// ウォッチリストのアカウントの最近のノートをまとめて取得します。
+    ///
+    /// `hours` を指定するとその期間内のノートに限定します。
+    /// 明示的に監視しているアカウントのため、ミュートリストは適用しません。
+    pub async fn get_watched_feeds(&self, limit: u64, hours: Option<u64>) -> Result<WatchedFeeds> {
+        let list = crate::storage::load_watch_list();
+        let watched: Vec<(PublicKey, crate::storage::WatchedAccount)> = list
+            .into_iter()
+            .filter_map(|(hex, account)| PublicKey::from_hex(&hex).ok().map(|pk| (pk, account)))
+            .collect();
+        if watched.is_empty() {
+            return Ok(WatchedFeeds { accounts: Vec::new(), notes: Vec::new() });
+        }
 
-        let summary = extract_tag_value(event, "summary");
-        let image = extract_tag_value(event, "image");
+        let mut filter = Filter::new()
+            .kind(Kind::TextNote)
+            .authors(watched.iter().map(|(pk, _)| *pk))
+            .limit(limit as usize);
+        if let Some(hours) = hours {
+            filter = filter.since(Timestamp::from(current_unix_timestamp().saturating_sub(hours * 3600)));
+        }
 
-        let published_at = extract_tag_value(event, "published_at")
-            .and_then(|s| s.parse::<u64>().ok());
+        let events = self.fetch_events(vec![filter], Duration::from_secs(10))
+            .await
+            .context("ウォッチリストのノートの取得に失敗しました")?;
+        let events_vec: Vec<Event> = events.into_iter().collect();
 
-        let tags: Vec<String> = event.tags.iter()
-            .filter_map(|tag| {
-                if let Some(TagStandard::Hashtag(h)) = tag.as_standardized() {
-                    Some(h.clone())
-                } else {
-                    None
+        let pubkeys: Vec<PublicKey> = watched.iter().map(|(pk, _)| *pk).collect();
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let mut notes = self.events_to_notes(&events_vec, &profiles).await;
+        Self::sort_and_truncate(&mut notes, limit as usize);
+        self.enrich_notes_with_counts(&mut notes).await;
+
+        let accounts = watched
+            .into_iter()
+            .map(|(pk, account)| {
+                let hex = pk.to_hex();
+                let own: Vec<&NoteInfo> = notes.iter().filter(|n| n.author.pubkey == hex).collect();
+                WatchedAccountInfo {
+                    author: profiles
+                        .get(&pk)
+                        .cloned()
+                        .unwrap_or_else(|| AuthorInfo::from_public_key(&pk)),
+                    label: account.label,
+                    added_at: account.added_at,
+                    recent_notes: own.len() as u64,
+                    last_active_at: own.iter().map(|n| n.created_at).max(),
                 }
             })
             .collect();
 
-        let naddr = Coordinate::new(event.kind, event.pubkey)
-            .identifier(&identifier)
-            .to_bech32()
-            .ok();
+        Ok(WatchedFeeds { accounts, notes })
+    }
 
-        ArticleInfo {
-            id: event.id.to_hex(),
-            nevent: event.id.to_bech32().unwrap_or_default(),
-            naddr,
-            identifier,
-            title,
-            summary,
-            image,
-            content: event.content.clone(),
-            author: Some(author),
-            published_at,
-            created_at: event.created_at.as_u64(),
-            tags: if tags.is_empty() { None } else { Some(tags) },
-            is_draft: event.kind == Kind::from(30024),
+    /// ノートにリアクション数・リプライ数・リポスト数と Zap の合計額を付与するヘルパー
+    ///
+    /// NIP-45 (COUNT) 対応リレーがあればノートごとに COUNT を発行し、なければ一括取得して数えます。
+    /// リプライは COUNT では mention と区別できないため、常に一括取得して NIP-10 のマーカーで数えます。
+    /// Zap は金額の合計が必要なため、常にレシート (Kind 9735) を一括取得して、キャッシュ済みの
+    /// LNURL プロバイダーで真正性を確認します（`fetch_note_zap_totals`）。
+    /// 結果は短時間キャッシュします。
+    async fn enrich_notes_with_counts(&self, notes: &mut [NoteInfo]) {
+        if notes.is_empty() {
+            return;
         }
-    }
 
-    // ========================================
-    // Phase 2: タイムライン拡張機能
-    // ========================================
+        let event_ids: Vec<EventId> = notes.iter()
+            .filter_map(|n| EventId::from_hex(&n.id).ok())
+            .collect();
 
-    /// スレッド形式でノートとリプライを取得します（NIP-10 対応）。
-    pub async fn get_thread(&self, note_id: &str, depth: u64) -> Result<ThreadInfo> {
-        let event_id = Self::parse_event_id(note_id)?;
+        if event_ids.is_empty() {
+            return;
+        }
 
-        // ルートノートを取得
-        let root_filter = Filter::new()
-            .id(event_id)
-            .limit(1);
+        // キャッシュ済みの件数を使用
+        let mut counts: HashMap<EventId, NoteCounts> = HashMap::new();
+        {
+            let cache = self.note_count_cache.read().await;
+            for id in &event_ids {
+                if let Some((fetched_at, cached)) = cache.get(id) {
+                    if fetched_at.elapsed() < NOTE_COUNT_CACHE_TTL {
+                        counts.insert(*id, *cached);
+                    }
+                }
+            }
+        }
 
-        let root_events = self.client
-            .fetch_events(vec![root_filter], Duration::from_secs(10))
-            .await
-            .context("ルートノートの取得に失敗しました")?;
+        let missing: Vec<EventId> = event_ids.into_iter()
+            .filter(|id| !counts.contains_key(id))
+            .collect();
 
-        let root_event = root_events
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("ノートが見つかりません: {}", note_id))?;
+        if !missing.is_empty() {
+            let counted = async {
+                let (mut fetched, uncounted) = self.count_note_interactions_nip45(&missing).await;
+                if !uncounted.is_empty() {
+                    fetched.extend(self.fetch_note_interactions(&uncounted).await);
+                }
+                fetched
+            };
+            let (mut fetched, reply_counts, zap_totals) = tokio::join!(
+                counted,
+                self.fetch_note_reply_counts(&missing),
+                self.fetch_note_zap_totals(&missing)
+            );
+            for (id, replies) in reply_counts {
+                fetched.entry(id).or_default().replies = replies;
+            }
+            for (id, zap_sats) in zap_totals {
+                fetched.entry(id).or_default().zap_sats = zap_sats;
+            }
 
-        // リプライを取得（e タグでルートノートを参照しているイベント）
-        let reply_filter = Filter::new()
-            .kind(Kind::TextNote)
-            .event(event_id)
-            .limit(200);
+            let now = std::time::Instant::now();
+            let mut cache = self.note_count_cache.write().await;
+            cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < NOTE_COUNT_CACHE_TTL);
+            for (id, fetched) in &fetched {
+                cache.insert(*id, (now, *fetched));
+            }
+            counts.extend(fetched);
+        }
 
-        let reply_events = self.client
-            .fetch_events(vec![reply_filter], Duration::from_secs(10))
-            .await
-            .context("リプライの取得に失敗しました")?;
+        // ノートに付与
+        for note in notes.iter_mut() {
+            let note_counts = EventId::from_hex(&note.id)
+                .ok()
+                .and_then(|id| counts.get(&id).copied())
+                .unwrap_or_default();
+            note.reactions = Some(note_counts.reactions);
+            note.replies = Some(note_counts.replies);
+            note.reposts = Some(note_counts.reposts);
+            note.zap_sats = note_counts.zap_sats;
+        }
+    }
 
-        let reply_events_vec: Vec<Event> = reply_events.into_iter().collect();
+    /// ノートごとのリアクション数・リポスト数を NIP-45 COUNT で取得するヘルパー
+    ///
+    /// 戻り値は（取得できた件数, COUNT で取得できなかったノート ID）です。リプライ数と Zap の合計額は含みません。
+    async fn count_note_interactions_nip45(
+        &self,
+        event_ids: &[EventId],
+    ) -> (HashMap<EventId, NoteCounts>, Vec<EventId>) {
+        let relays = self.nip45_relays().await;
+        if relays.is_empty() {
+            return (HashMap::new(), event_ids.to_vec());
+        }
 
-        // リアクション数を取得
-        let reaction_filter = Filter::new()
-            .kind(Kind::Reaction)
-            .event(event_id)
-            .limit(500);
+        let mut tasks = tokio::task::JoinSet::new();
+        for id in event_ids {
+            let id = *id;
+            let relays = relays.clone();
+            let limiter = self.rate_limiter.clone();
+            tasks.spawn(async move {
+                let reaction_filter = Filter::new().kind(Kind::Reaction).event(id);
+                let repost_filter = Filter::new().kind(Kind::Repost).event(id);
+                let (reactions, reposts) = tokio::join!(
+                    count_on_relays(&limiter, relays.clone(), reaction_filter),
+                    count_on_relays(&limiter, relays, repost_filter)
+                );
+                let counts = match (reactions, reposts) {
+                    (Some(reactions), Some(reposts)) => Some(NoteCounts { reactions, reposts, ..Default::default() }),
+                    _ => None,
+                };
+                (id, counts)
+            });
+        }
 
-        let reaction_count = match self.client
-            .fetch_events(vec![reaction_filter], Duration::from_secs(5))
-            .await {
-            Ok(events) => events.into_iter().count() as u64,
-            Err(_) => 0,
-        };
+        let mut counts = HashMap::new();
+        while let Some(result) = tasks.join_next().await {
+            match result {
+                Ok((id, Some(count))) => {
+                    counts.insert(id, count);
+                }
+                Ok((_, None)) => {}
+                Err(e) => warn!("COUNT タスクの実行に失敗: {}", e),
+            }
+        }
 
-        // プロフィールを取得
-        let mut all_events = vec![root_event.clone()];
-        all_events.extend(reply_events_vec.iter().cloned());
-        let pubkeys = Self::collect_pubkeys(&all_events);
-        let profiles = self.fetch_profiles(&pubkeys).await;
+        let uncounted = event_ids.iter()
+            .filter(|id| !counts.contains_key(id))
+            .copied()
+            .collect();
+        (counts, uncounted)
+    }
 
-        // ルートノート情報を作成
-        let root_author = profiles
-            .get(&root_event.pubkey)
-            .cloned()
-            .unwrap_or_else(|| AuthorInfo::from_public_key(&root_event.pubkey));
+    /// ノートごとのリアクション数・リポスト数をイベントを一括取得して数えるヘルパー
+    async fn fetch_note_interactions(&self, event_ids: &[EventId]) -> HashMap<EventId, NoteCounts> {
+        // リアクション (Kind 7) を一括取得
+        let reaction_filter = Filter::new()
+            .kind(Kind::Reaction)
+            .events(event_ids.to_vec())
+            .limit(1000);
 
-        let root_note = NoteInfo {
-            id: root_event.id.to_hex(),
-            nevent: root_event.id.to_bech32().unwrap_or_default(),
-            author: root_author,
-            content: root_event.content.clone(),
-            created_at: root_event.created_at.as_u64(),
-            reactions: Some(reaction_count),
-            replies: Some(reply_events_vec.len() as u64),
-        };
+        // リポスト (Kind 6 で e タグ参照) を一括取得
+        let repost_filter = Filter::new()
+            .kind(Kind::Repost)
+            .events(event_ids.to_vec())
+            .limit(1000);
 
-        // リプライをスレッド構造に変換
-        let replies = self.build_thread_replies(&reply_events_vec, &profiles, &event_id, depth);
+        let (reactions_result, reposts_result) = tokio::join!(
+            self.fetch_events_deduped(vec![reaction_filter], Duration::from_secs(5)),
+            self.fetch_events_deduped(vec![repost_filter], Duration::from_secs(5))
+        );
 
-        Ok(ThreadInfo {
-            root: root_note,
-            replies,
-            total_replies: reply_events_vec.len() as u64,
-            depth,
-        })
-    }
+        let mut counts: HashMap<EventId, NoteCounts> =
+            event_ids.iter().map(|id| (*id, NoteCounts::default())).collect();
 
-    /// リプライイベントからスレッド構造を構築するヘルパー
-    fn build_thread_replies(
-        &self,
-        events: &[Event],
-        profiles: &HashMap<PublicKey, AuthorInfo>,
-        parent_id: &EventId,
-        max_depth: u64,
-    ) -> Vec<ThreadReply> {
-        if max_depth == 0 {
-            return vec![];
+        // 参照しているノートごとに数える（同じ e タグが複数あっても 1 件として数える）
+        let mut tally = |events: &EventDedup, field: fn(&mut NoteCounts) -> &mut u64| {
+            for event in events.iter() {
+                for id in event.tags.event_ids().collect::<std::collections::HashSet<_>>() {
+                    if let Some(entry) = counts.get_mut(id) {
+                        *field(entry) += 1;
+                    }
+                }
+            }
+        };
+        if let Ok(events) = reactions_result {
+            tally(&events, |c| &mut c.reactions);
+        }
+        if let Ok(events) = reposts_result {
+            tally(&events, |c| &mut c.reposts);
         }
 
-        let mut replies: Vec<ThreadReply> = events
-            .iter()
-            .filter(|event| {
-                // NIP-10: 最後の e タグが reply マーカー（親への参照）
-                event.tags.iter().any(|tag| {
-                    let values = tag.as_slice();
-                    values.len() >= 2
-                        && values[0] == "e"
-                        && values[1] == parent_id.to_hex()
-                })
-            })
-            .map(|event| {
-                let author = profiles
-                    .get(&event.pubkey)
-                    .cloned()
-                    .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey));
+        counts
+    }
 
-                let child_replies = self.build_thread_replies(
-                    events,
-                    profiles,
-                    &event.id,
-                    max_depth - 1,
-                );
+    /// ノートごとのリプライ数をリプライ (Kind 1) を一括取得して数えるヘルパー
+    ///
+    /// NIP-10 の root / reply マーカー（またはマーカーなしの e タグ）で対象を指すものだけを数え、
+    /// mention マーカーで言及しているだけのノートは数えません。
+    async fn fetch_note_reply_counts(&self, event_ids: &[EventId]) -> HashMap<EventId, u64> {
+        let reply_filter = Filter::new()
+            .kind(Kind::TextNote)
+            .events(event_ids.to_vec())
+            .limit(1000);
 
-                ThreadReply {
-                    note: NoteInfo {
-                        id: event.id.to_hex(),
-                        nevent: event.id.to_bech32().unwrap_or_default(),
-                        author,
-                        content: event.content.clone(),
-                        created_at: event.created_at.as_u64(),
-                        reactions: None,
-                        replies: Some(child_replies.len() as u64),
-                    },
-                    replies: child_replies,
+        let mut counts: HashMap<EventId, u64> = event_ids.iter().map(|id| (*id, 0)).collect();
+        if let Ok(events) = self.fetch_events_deduped(vec![reply_filter], Duration::from_secs(5)).await {
+            for event in events.iter() {
+                for (id, count) in counts.iter_mut() {
+                    if is_reply_to(event, id) {
+                        *count += 1;
+                    }
                 }
-            })
-            .collect();
+            }
+        }
+        counts
+    }
 
-        replies.sort_by(|a, b| a.note.created_at.cmp(&b.note.created_at));
-        replies
+    /// ノートごとに受け取った Zap の合計額（sats）を、レシート (Kind 9735) を一括取得して集計するヘルパー
+    ///
+    /// タイムラインの表示のたびに LNURL エンドポイントへ問い合わせないよう、レシートの署名者は
+    /// キャッシュ済みのプロバイダー（`get_zap_receipts` などで確認したもの）だけで確認します。
+    /// 無効なレシートは除外し、未検証のレシートがあるノートは合計額を None にします。
+    /// 取得に失敗した場合は空のマップを返します。
+    async fn fetch_note_zap_totals(&self, event_ids: &[EventId]) -> HashMap<EventId, Option<u64>> {
+        let zap_filter = Filter::new()
+            .kind(Kind::ZapReceipt)
+            .events(event_ids.to_vec())
+            .limit(1000);
+        let receipts: Vec<Event> = match self.fetch_events_deduped(vec![zap_filter], Duration::from_secs(5)).await {
+            Ok(events) => events.into_events(),
+            Err(e) => {
+                debug!("Zap レシートの取得に失敗: {}", e);
+                return HashMap::new();
+            }
+        };
+        let problems = self.check_zap_receipts(&receipts, false).await;
+        zap_totals_by_note(&receipts, &problems, event_ids)
     }
 
-    /// イベント ID で単一のイベントを取得するヘルパー
-    async fn fetch_event_by_id(&self, event_id: EventId, context: &str) -> Result<Event> {
-        let filter = Filter::new().id(event_id).limit(1);
-        let events = self.client
-            .fetch_events(vec![filter], Duration::from_secs(5))
-            .await
-            .context(format!("{}の取得に失敗しました", context))?;
-        events
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("{}が見つかりません", context))
-    }
+    /// NIP-45 (COUNT) に対応した接続中のリレーを取得するヘルパー
+    ///
+    /// 対応状況は NIP-11 の supported_nips から判定し、リレーごとにキャッシュします。
+    async fn nip45_relays(&self) -> Vec<Relay> {
+        let relays: Vec<Relay> = self.client.relays().await
+            .into_values()
+            .filter(|relay| relay.is_connected() && relay.flags().has_read())
+            .collect();
 
-    /// ノートにリアクション (Kind 7, NIP-25) を送信します。
-    pub async fn react_to_note(&self, note_id: &str, reaction: &str) -> Result<EventId> {
-        self.require_write_access()?;
+        let unknown: Vec<RelayUrl> = {
+            let cache = self.nip45_support.read().await;
+            relays.iter()
+                .map(|relay| relay.url().clone())
+                .filter(|url| !cache.contains_key(url))
+                .collect()
+        };
 
-        let event_id = Self::parse_event_id(note_id)?;
-        let target_event = self.fetch_event_by_id(event_id, "リアクション対象のノート").await?;
+        if !unknown.is_empty() {
+            let mut tasks = tokio::task::JoinSet::new();
+            for url in unknown {
+                tasks.spawn(async move {
+                    let supported = crate::nip11::fetch_relay_information(url.as_str())
+                        .await
+                        .map(|info| info.supports(45))
+                        .unwrap_or(false);
+                    (url, supported)
+                });
+            }
 
-        // NIP-25: リアクションイベントを作成
-        let builder = EventBuilder::new(Kind::Reaction, reaction)
-            .tags(vec![
-                Tag::event(event_id),
-                Tag::public_key(target_event.pubkey),
-            ]);
+            let mut cache = self.nip45_support.write().await;
+            while let Some(Ok((url, supported))) = tasks.join_next().await {
+                debug!("NIP-45 対応状況: {} = {}", url, supported);
+                cache.insert(url, supported);
+            }
+        }
 
-        let output = self.client.send_event_builder(builder).await
-            .context("リアクションの送信に失敗しました")?;
+        let cache = self.nip45_support.read().await;
+        relays.into_iter()
+            .filter(|relay| cache.get(relay.url()).copied().unwrap_or(false))
+            .collect()
+    }
 
-        let reaction_id = *output.id();
-        info!("リアクションを送信しました。イベント ID: {}", reaction_id);
-        Ok(reaction_id)
+    /// NIP-45 COUNT でイベント数を取得（対応リレーがない、または全て失敗した場合は None）
+    ///
+    /// 複数リレーの結果のうち最大値を採用します。
+    async fn count_events_nip45(&self, filter: &Filter) -> Option<u64> {
+        let relays = self.nip45_relays().await;
+        if relays.is_empty() {
+            return None;
+        }
+        count_on_relays(&self.rate_limiter, relays, filter.clone()).await
     }
 
-    /// 既存のノートに返信を投稿します（NIP-10 対応）。
-    pub async fn reply_to_note(&self, note_id: &str, content: &str) -> Result<EventId> {
-        self.require_write_access()?;
+    /// イベント数を取得するヘルパー（NIP-45 COUNT を優先し、非対応時は取得して数える）
+    ///
+    /// 戻り値はイベント数と、使用した方法（"nip45" または "fetch"）です。
+    /// 取得に失敗した場合、件数は 0 ではなく None（不明）になります。
+    async fn count_events(&self, filter: Filter, fetch_limit: usize) -> (Option<u64>, &'static str) {
+        if let Some(count) = self.count_events_nip45(&filter).await {
+            return (Some(count), "nip45");
+        }
+
+        let count = match self.fetch_events_deduped(vec![filter.limit(fetch_limit)], Duration::from_secs(10)).await {
+            Ok(events) => Some(events.len() as u64),
+            Err(e) => {
+                warn!("件数の取得に失敗しました: {}", e);
+                None
+            }
+        };
+        (count, "fetch")
+    }
 
-        let event_id = Self::parse_event_id(note_id)?;
-        let target_event = self.fetch_event_by_id(event_id, "返信対象のノート").await?;
+    /// 自分のミュートリスト (NIP-51, Kind 10000) を取得します（未認証時は空）。
+    ///
+    /// 非公開項目（暗号化された content）も復号して含めます。結果は一定時間キャッシュします。
+    pub async fn mute_list(&self) -> Arc<MuteList> {
+        let Some(pk) = self.public_key else {
+            return Arc::new(MuteList::default());
+        };
 
-        // NIP-10: root と reply のマーカーを設定
-        // 対象ノート自体にルートがある場合はそれを引き継ぐ
-        let mut tags = Vec::new();
+        if let Some((fetched_at, list)) = self.mute_list.read().await.as_ref() {
+            if fetched_at.elapsed() < MUTE_LIST_CACHE_TTL {
+                return list.clone();
+            }
+        }
 
-        // ルートイベントの検出
-        let root_id = target_event.tags.iter().find_map(|tag| {
-            let values = tag.as_slice();
-            if values.len() >= 4 && values[0] == "e" && values[3] == "root" {
-                EventId::from_hex(&values[1]).ok()
-            } else {
+        let filter = Filter::new()
+            .author(pk)
+            .kind(Kind::MuteList)
+            .limit(1);
+
+        let event = match self.fetch_events(vec![filter], Duration::from_secs(10)).await {
+            Ok(events) => events.into_iter().max_by_key(|e| e.created_at),
+            Err(e) => {
+                warn!("ミュートリストの取得に失敗: {}", e);
                 None
             }
-        });
+        };
 
-        if let Some(root) = root_id {
-            // 既存スレッドへの返信: root を引き継ぎ、対象ノートを reply とする
-            tags.push(Tag::parse(vec!["e".to_string(), root.to_hex(), String::new(), "root".to_string()]).unwrap());
-            tags.push(Tag::parse(vec!["e".to_string(), event_id.to_hex(), String::new(), "reply".to_string()]).unwrap());
-        } else {
-            // 新規スレッド開始: 対象ノートが root かつ reply
-            tags.push(Tag::parse(vec!["e".to_string(), event_id.to_hex(), String::new(), "root".to_string()]).unwrap());
-            tags.push(Tag::parse(vec!["e".to_string(), event_id.to_hex(), String::new(), "reply".to_string()]).unwrap());
+        let mut tags: Vec<Vec<String>> = Vec::new();
+        if let Some(event) = &event {
+            tags.extend(event.tags.iter().map(|tag| tag.as_slice().to_vec()));
+            if !event.content.is_empty() {
+                tags.extend(self.decrypt_private_list_tags(pk, &event.content).await);
+            }
         }
 
-        // 対象ノートの著者を p タグで追加
-        tags.push(Tag::public_key(target_event.pubkey));
+        let list = Arc::new(MuteList::from_tags(tags.iter().map(|t| t.as_slice())));
+        debug!(
+            "ミュートリスト: 公開鍵 {} 件, ハッシュタグ {} 件, 単語 {} 件",
+            list.pubkeys.len(),
+            list.hashtags.len(),
+            list.words.len()
+        );
+        *self.mute_list.write().await = Some((std::time::Instant::now(), list.clone()));
+        list
+    }
 
-        let builder = EventBuilder::text_note(content)
-            .tags(tags);
+    /// NIP-51 リストの非公開項目（自分宛てに暗号化されたタグ配列）を復号するヘルパー
+    ///
+    /// NIP-44 を優先し、旧形式（`?iv=` を含む）は NIP-04 で復号します。
+    async fn decrypt_private_list_tags(&self, pk: PublicKey, content: &str) -> Vec<Vec<String>> {
+        let Ok(signer) = self.client.signer().await else {
+            return Vec::new();
+        };
 
-        let output = self.client.send_event_builder(builder).await
-            .context("返信の投稿に失敗しました")?;
+        let decrypted = if content.contains("?iv=") {
+            signer.nip04_decrypt(&pk, content).await
+        } else {
+            signer.nip44_decrypt(&pk, content).await
+        };
 
-        let reply_id = *output.id();
-        info!("返信を投稿しました。イベント ID: {}", reply_id);
-        Ok(reply_id)
+        match decrypted {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("リストの非公開項目のパースに失敗: {}", e);
+                Vec::new()
+            }),
+            Err(e) => {
+                warn!("リストの非公開項目の復号に失敗: {}", e);
+                Vec::new()
+            }
+        }
     }
 
-    /// ユーザーへのメンションとリアクションの通知を取得します。
-    pub async fn get_notifications(&self, since: Option<u64>, limit: u64) -> Result<Vec<NotificationInfo>> {
-        let pk = self.public_key
-            .ok_or_else(|| anyhow!("通知の取得には認証が必要です。設定ファイルに nsec を設定してください。"))?;
-
-        // メンション（p タグで自分を参照しているテキストノート）
-        let mut mention_filter = Filter::new()
-            .kind(Kind::TextNote)
-            .pubkey(pk)
-            .limit(limit as usize);
+    /// ミュートリストに一致するイベントを除外するヘルパー
+    async fn filter_muted(&self, events: Vec<Event>) -> Vec<Event> {
+        let mute_list = self.mute_list().await;
+        if mute_list.is_empty() {
+            return events;
+        }
 
-        if let Some(since_ts) = since {
-            mention_filter = mention_filter.since(Timestamp::from(since_ts));
+        let before = events.len();
+        let events: Vec<Event> = events.into_iter().filter(|e| !mute_list.is_muted(e)).collect();
+        if events.len() < before {
+            debug!("ミュート対象のイベントを {} 件除外しました", before - events.len());
         }
+        events
+    }
 
-        // リアクション（p タグで自分を参照しているリアクション）
-        let mut reaction_filter = Filter::new()
-            .kind(Kind::Reaction)
-            .pubkey(pk)
-            .limit(limit as usize);
+    /// Web of Trust フィルタのデフォルト設定を取得
+    pub fn web_of_trust(&self) -> Option<crate::config::WebOfTrust> {
+        self.web_of_trust
+    }
+
+    /// 設定ファイルの最小 PoW 難易度（0 で無効）
+    pub fn min_pow(&self) -> u8 {
+        self.min_pow
+    }
+
+    /// 設定ファイルの `languages` による言語フィルタ
+    pub fn language_filter(&self) -> &LanguageFilter {
+        &self.language_filter
+    }
 
-        if let Some(since_ts) = since {
-            reaction_filter = reaction_filter.since(Timestamp::from(since_ts));
+    /// PoW 難易度が `min_pow` 未満のイベントを除外するヘルパー
+    fn filter_by_pow(events: Vec<Event>, min_pow: u8) -> Vec<Event> {
+        if min_pow == 0 {
+            return events;
         }
+        let total = events.len();
+        let filtered: Vec<Event> = events.into_iter().filter(|e| pow_difficulty(e) >= min_pow).collect();
+        debug!("PoW {} 未満のイベントを {} 件除外しました", min_pow, total - filtered.len());
+        filtered
+    }
 
-        let events = self.client
-            .fetch_events(vec![mention_filter, reaction_filter], Duration::from_secs(15))
-            .await
-            .context("通知の取得に失敗しました")?;
+    /// 自分を起点としたフォローグラフを取得します（`max_hops` は 1 または 2）。
+    ///
+    /// 1 ホップ目は自分のコンタクトリスト、2 ホップ目はフォロー中の各アカウントの
+    /// コンタクトリストから構築します。結果は一定時間キャッシュします。
+    pub async fn trust_graph(&self, max_hops: u8) -> Result<Arc<TrustGraph>> {
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("Web of Trust フィルタには認証が必要です。設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))?;
+        let max_hops = max_hops.clamp(1, 2);
 
-        let events_vec: Vec<Event> = events.into_iter()
-            .filter(|e| e.pubkey != pk) // 自分自身の投稿を除外
-            .collect();
+        if let Some((fetched_at, hops, graph)) = self.trust_graph.read().await.as_ref() {
+            if *hops >= max_hops && fetched_at.elapsed() < TRUST_GRAPH_CACHE_TTL {
+                return Ok(graph.clone());
+            }
+        }
 
-        let pubkeys = Self::collect_pubkeys(&events_vec);
-        let profiles = self.fetch_profiles(&pubkeys).await;
+        let follows = self.fetch_followed_pubkeys(pk).await;
+        let mut layers = vec![follows.clone()];
 
-        let mut notifications: Vec<NotificationInfo> = events_vec.iter().map(|event| {
-            let author = profiles
-                .get(&event.pubkey)
-                .cloned()
-                .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey));
+        if max_hops >= 2 && !follows.is_empty() {
+            let filters: Vec<Filter> = follows
+                .chunks(TRUST_GRAPH_AUTHORS_PER_FILTER)
+                .map(|chunk| {
+                    Filter::new()
+                        .kind(Kind::ContactList)
+                        .authors(chunk.to_vec())
+                        .limit(chunk.len())
+                })
+                .collect();
 
-            let notification_type = match event.kind {
-                Kind::Reaction => "reaction".to_string(),
-                Kind::TextNote => "mention".to_string(),
-                _ => "other".to_string(),
-            };
+            let events = self.fetch_events(filters, Duration::from_secs(20))
+                .await
+                .context("フォローのコンタクトリストの取得に失敗しました")?;
 
-            // リアクションの場合、対象ノートの ID を取得
-            let target_note_id = event.tags.iter().find_map(|tag| {
-                let values = tag.as_slice();
-                if values.len() >= 2 && values[0] == "e" {
-                    Some(values[1].to_string())
-                } else {
-                    None
+            // 著者ごとに最新のコンタクトリストのみ使用
+            let mut latest: HashMap<PublicKey, Event> = HashMap::new();
+            for event in events {
+                match latest.get(&event.pubkey) {
+                    Some(existing) if existing.created_at >= event.created_at => {}
+                    _ => {
+                        latest.insert(event.pubkey, event);
+                    }
                 }
-            });
+            }
 
-            NotificationInfo {
-                id: event.id.to_hex(),
-                nevent: event.id.to_bech32().unwrap_or_default(),
-                notification_type,
-                author,
-                content: event.content.clone(),
-                target_note_id,
-                created_at: event.created_at.as_u64(),
+            layers.push(
+                latest
+                    .values()
+                    .flat_map(|event| event.tags.public_keys().copied())
+                    .collect(),
+            );
+        }
+
+        let graph = Arc::new(TrustGraph::from_layers(pk, &layers));
+        debug!("フォローグラフ: {} ホップ, {} アカウント", max_hops, graph.len());
+        *self.trust_graph.write().await = Some((std::time::Instant::now(), max_hops, graph.clone()));
+        Ok(graph)
+    }
+
+    /// NIP-50 検索リレーに接続した一時クライアントを作成するヘルパー
+    async fn connect_search_client(&self) -> Client {
+        self.rate_limiter.acquire(&self.search_relays).await;
+
+        let search_client = Client::default();
+
+        for relay_url in &self.search_relays {
+            if let Err(e) = search_client.add_relay(relay_url).await {
+                warn!("検索リレー {} の追加に失敗: {}", relay_url, e);
             }
-        }).collect();
+        }
 
-        notifications.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-        notifications.truncate(limit as usize);
+        search_client.connect().await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
 
-        Ok(notifications)
+        search_client
     }
 
-    // ========================================
-    // Phase 4: Zap サポート (NIP-57)
-    // ========================================
+    /// NIP-50 対応リレーでノートを検索します（`min_pow` 未満の PoW のノートは除外）。
+    ///
+    /// `languages` で許可しない言語のノートは件数を絞る前に除外し、戻り値はノートと除外した件数です。
+    pub async fn search_notes(
+        &self,
+        query: &str,
+        limit: u64,
+        min_pow: u8,
+        languages: &LanguageFilter,
+    ) -> Result<(Vec<NoteInfo>, usize)> {
+        let search_client = self.connect_search_client().await;
+
+        let filter = Filter::new()
+            .kind(Kind::TextNote)
+            .search(query);
+
+        let search_relays: Vec<RelayUrl> = search_client.relays().await.into_keys().collect();
+        let drop_invalid = self.signature_verification == SignatureVerification::Drop;
+        let mut fetch_limit = pow_fetch_limit(limit, min_pow);
+        if !languages.is_empty() {
+            // 言語で絞ると件数が減るため多めに取得する
+            fetch_limit = (fetch_limit as u64 * LANGUAGE_FILTER_FETCH_MULTIPLIER).min(POW_FETCH_MAX).max(limit) as usize;
+        }
+        let events = paginate_events(filter, search_relays, fetch_limit, |requests| {
+            fetch_relay_pages(&search_client, requests, Duration::from_secs(15), drop_invalid)
+        })
+        .await
+        .context("ノートの検索に失敗しました")?;
+
+        let events_vec = Self::filter_by_pow(events, min_pow);
+        let events_vec = self.filter_muted(events_vec).await;
+        let pubkeys = Self::collect_pubkeys(&events_vec);
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let mut notes = self.events_to_notes(&events_vec, &profiles).await;
+        let excluded = languages.apply(&mut notes, |n| n.language.as_deref());
+        Self::sort_and_truncate(&mut notes, limit as usize);
+
+        let _ = search_client.disconnect().await;
+
+        Ok((notes, excluded))
+    }
 
-    /// ノートの Zap レシート (Kind 9735) を取得します。
-    pub async fn get_zap_receipts(&self, note_id: &str, limit: u64) -> Result<Vec<ZapReceiptInfo>> {
-        let event_id = Self::parse_event_id(note_id)?;
+    /// NIP-50 対応リレーで長文記事 (Kind 30023) を検索します。
+    pub async fn search_articles(&self, query: &str, limit: u64) -> Result<Vec<ArticleInfo>> {
+        let search_client = self.connect_search_client().await;
 
-        // Kind 9735 (Zap Receipt) を取得
         let filter = Filter::new()
-            .kind(Kind::ZapReceipt)
-            .event(event_id)
+            .kind(Kind::LongFormTextNote)
+            .search(query)
             .limit(limit as usize);
 
-        let events = self.client
-            .fetch_events(vec![filter], Duration::from_secs(10))
+        let result = search_client
+            .fetch_events(vec![filter], Duration::from_secs(15))
             .await
-            .context("Zap レシートの取得に失敗しました")?;
+            .context("記事の検索に失敗しました");
 
-        let events_vec: Vec<Event> = events.into_iter().collect();
-        let mut receipts = Vec::new();
+        let _ = search_client.disconnect().await;
 
-        for event in &events_vec {
-            let receipt = self.parse_zap_receipt(event).await;
-            receipts.push(receipt);
-        }
+        let events_vec: Vec<Event> = result?.into_iter().collect();
+        let pubkeys = Self::collect_pubkeys(&events_vec);
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let seen_on = seen_on_relays_in(&search_client, &events_vec).await;
+
+        let mut articles: Vec<ArticleInfo> = events_vec.iter()
+            .map(|event| Self::event_to_article(event, &profiles, relay_hints(&seen_on, &event.id)))
+            .collect();
 
-        receipts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-        receipts.truncate(limit as usize);
+        articles.sort_by_key(|x| Reverse(x.created_at));
+        articles.truncate(limit as usize);
 
-        Ok(receipts)
+        Ok(articles)
     }
 
-    /// Zap レシートイベントをパースするヘルパー
-    async fn parse_zap_receipt(&self, event: &Event) -> ZapReceiptInfo {
-        // bolt11 タグから金額を抽出
-        let bolt11 = extract_tag_value(event, "bolt11").unwrap_or_default();
-        let amount_sats = Self::extract_bolt11_amount(&bolt11);
+    /// 名前でユーザーを検索します（NIP-50 で Kind 0 を検索し、キャッシュ済みプロフィールも照合）。
+    pub async fn search_users(&self, query: &str, limit: u64) -> Result<Vec<AuthorInfo>> {
+        let search_client = self.connect_search_client().await;
 
-        // description タグから Zap リクエストを取得（送信者・コメント情報）
-        let description = extract_tag_value(event, "description");
-        let (sender_pubkey, comment) = if let Some(ref desc) = description {
-            Self::parse_zap_request_description(desc)
-        } else {
-            (None, None)
-        };
+        let filter = Filter::new()
+            .kind(Kind::Metadata)
+            .search(query)
+            .limit(limit as usize);
 
-        // 送信者のプロフィールを取得
-        let sender = if let Some(pk_hex) = &sender_pubkey {
-            if let Ok(pk) = PublicKey::from_hex(pk_hex) {
-                let profiles = self.fetch_profiles(&[pk]).await;
-                profiles.get(&pk).cloned()
-            } else {
-                None
-            }
-        } else {
-            None
+        let result = search_client
+            .fetch_events(vec![filter], Duration::from_secs(15))
+            .await
+            .map(|events| events.into_iter().collect::<Vec<Event>>());
+        let seen_on = match &result {
+            Ok(events) => seen_on_relays_in(&search_client, events).await,
+            Err(_) => HashMap::new(),
         };
 
-        // 対象ノート ID とpubkey を取得
-        let target_note_id = event.tags.iter().find_map(|tag| {
-            let values = tag.as_slice();
-            if values.len() >= 2 && values[0] == "e" {
-                Some(values[1].to_string())
-            } else {
-                None
-            }
-        });
-
-        let target_pubkey = event.tags.iter().find_map(|tag| {
-            let values = tag.as_slice();
-            if values.len() >= 2 && values[0] == "p" {
-                Some(values[1].to_string())
-            } else {
-                None
-            }
-        });
+        let _ = search_client.disconnect().await;
 
-        ZapReceiptInfo {
-            id: event.id.to_hex(),
-            nevent: event.id.to_bech32().unwrap_or_default(),
-            sender,
-            amount_sats,
-            comment,
-            target_note_id,
-            target_pubkey,
-            created_at: event.created_at.as_u64(),
-        }
-    }
+        let mut candidates: HashMap<PublicKey, AuthorInfo> = HashMap::new();
 
-    /// bolt11 インボイスから金額（sats）を抽出
-    fn extract_bolt11_amount(bolt11: &str) -> u64 {
-        // bolt11 形式: lnbc{amount}{multiplier}...
-        // multiplier: m = milli (0.001), u = micro (0.000001), n = nano, p = pico
-        let bolt11_lower = bolt11.to_lowercase();
-        if let Some(start) = bolt11_lower.strip_prefix("lnbc") {
-            // 数字部分を取得
-            let num_str: String = start.chars().take_while(|c| c.is_ascii_digit()).collect();
-            if let Ok(num) = num_str.parse::<u64>() {
-                let after_num = &start[num_str.len()..];
-                if after_num.starts_with('m') {
-                    return num * 100_000; // milli-BTC → sats
-                } else if after_num.starts_with('u') {
-                    return num * 100; // micro-BTC → sats
-                } else if after_num.starts_with('n') {
-                    return num / 10; // nano-BTC → sats
-                } else if after_num.starts_with('p') {
-                    return num / 10_000; // pico-BTC → sats
-                } else {
-                    return num * 100_000_000; // BTC → sats
+        match result {
+            Ok(events) => {
+                let mut cache = self.profile_cache.write().await;
+                for event in events {
+                    if let Ok(metadata) = serde_json::from_str::<Metadata>(&event.content) {
+                        let author = AuthorInfo::from_metadata(&event.pubkey, metadata, relay_hints(&seen_on, &event.id));
+                        cache.insert(event.pubkey, author.clone());
+                        candidates.insert(event.pubkey, author);
+                    }
                 }
             }
+            Err(e) => warn!("ユーザー検索に失敗（キャッシュのみで照合）: {}", e),
         }
-        0
-    }
 
-    /// Zap リクエストの description JSON から送信者 pubkey とコメントを抽出
-    fn parse_zap_request_description(description: &str) -> (Option<String>, Option<String>) {
-        if let Ok(event) = serde_json::from_str::<serde_json::Value>(description) {
-            let pubkey = event.get("pubkey")
-                .and_then(|v| v.as_str())
-                .map(String::from);
-            let comment = event.get("content")
-                .and_then(|v| v.as_str())
-                .filter(|s| !s.is_empty())
-                .map(String::from);
-            (pubkey, comment)
-        } else {
-            (None, None)
+        // キャッシュ済みプロフィールとの照合（検索リレーが応答しない場合の代替）
+        {
+            let cache = self.profile_cache.read().await;
+            for (pk, author) in cache.iter() {
+                if user_match_score(author, query) > 0 {
+                    candidates.entry(*pk).or_insert_with(|| author.clone());
+                }
+            }
         }
-    }
 
-    /// ノートまたはプロフィールに Zap を送信します（NWC 設定が必要）。
-    pub async fn send_zap(&self, target: &str, amount_sats: u64, comment: Option<&str>) -> Result<serde_json::Value> {
-        self.require_write_access()?;
+        let mut ranked: Vec<(u32, AuthorInfo)> = candidates
+            .into_values()
+            .map(|author| (user_match_score(&author, query), author))
+            .collect();
 
-        if !self.client.has_zapper().await {
-            return Err(anyhow!(
-                "Zap 送信には NWC (Nostr Wallet Connect) の設定が必要です。\
-                設定ファイルに \"nwc-uri\" を追加してください。"
-            ));
-        }
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.display().cmp(&b.1.display())));
+        ranked.truncate(limit as usize);
 
-        // target がイベント ID かpubkey かを判定
-        let zap_entity: ZapEntity = if target.starts_with("npub") || (!target.starts_with("note") && !target.starts_with("nevent") && target.len() == 64 && target.chars().all(|c| c.is_ascii_hexdigit())) {
-            // pubkey として解釈を試みる（ただし64文字hex以外も考慮）
-            if let Ok(pk) = Self::parse_public_key(target) {
-                ZapEntity::from(pk)
-            } else if let Ok(eid) = Self::parse_event_id(target) {
-                ZapEntity::from(eid)
-            } else {
-                return Err(anyhow!("無効な target です。イベント ID または公開鍵を指定してください。"));
-            }
-        } else {
-            // イベント ID として解釈
-            if let Ok(eid) = Self::parse_event_id(target) {
-                ZapEntity::from(eid)
-            } else if let Ok(pk) = Self::parse_public_key(target) {
-                ZapEntity::from(pk)
-            } else {
-                return Err(anyhow!("無効な target です。イベント ID または公開鍵を指定してください。"));
-            }
-        };
+        Ok(ranked.into_iter().map(|(_, author)| author).collect())
+    }
 
-        let details = if let Some(msg) = comment {
-            Some(ZapDetails::new(ZapType::Public).message(msg))
-        } else {
-            Some(ZapDetails::new(ZapType::Public))
-        };
+    /// 指定されたユーザーのプロフィール情報を取得します。
+    pub async fn get_profile(&self, npub: &str) -> Result<ProfileInfo> {
+        let npub = npub.trim();
+        let (public_key, hints) = Self::parse_profile_pointer(npub)?;
 
-        self.client.zap(zap_entity, amount_sats, details).await
-            .context("Zap の送信に失敗しました")?;
+        let filter = Filter::new()
+            .author(public_key)
+            .kind(Kind::Metadata)
+            .limit(1);
 
-        info!("Zap を送信しました: {} sats → {}", amount_sats, target);
+        let events = self.fetch_events_with_hints(vec![filter], &hints, Duration::from_secs(10))
+            .await
+            .context("プロフィールの取得に失敗しました")?;
 
-        Ok(serde_json::json!({
-            "success": true,
-            "amount_sats": amount_sats,
-            "target": target,
-            "message": format!("{} sats の Zap を送信しました。", amount_sats)
-        }))
-    }
+        let profile_event = events
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("{} のプロフィールが見つかりません", npub))?;
 
-    // ========================================
-    // Phase 4: ダイレクトメッセージ (NIP-04)
-    // ========================================
+        let metadata: Metadata = serde_json::from_str(&profile_event.content)
+            .context("プロフィールメタデータのパースに失敗しました")?;
 
-    /// 暗号化されたダイレクトメッセージを送信します（NIP-04）。
-    pub async fn send_dm(&self, recipient: &str, content: &str) -> Result<EventId> {
-        self.require_write_access()?;
+        Ok(ProfileInfo {
+            pubkey: public_key.to_hex(),
+            npub: public_key.to_bech32()?,
+            name: metadata.name,
+            display_name: metadata.display_name,
+            about: metadata.about,
+            picture: metadata.picture,
+            banner: metadata.banner,
+            nip05: metadata.nip05,
+            lud16: metadata.lud16,
+            website: metadata.website,
+        })
+    }
 
-        let recipient_pk = Self::parse_public_key(recipient)?;
+    /// 複数のユーザーのプロフィールをまとめて取得（プロフィールキャッシュを使用）
+    ///
+    /// 指定順（重複は除く）に返します。プロフィールが見つからないユーザーは `metadata` が None です。
+    pub async fn get_profiles(&self, pubkeys: &[String]) -> Result<Vec<AuthorInfo>> {
+        let mut keys: Vec<PublicKey> = Vec::with_capacity(pubkeys.len());
+        for pubkey in pubkeys {
+            let (pk, _) = Self::parse_profile_pointer(pubkey)?;
+            if !keys.contains(&pk) {
+                keys.push(pk);
+            }
+        }
 
-        // NIP-04: signer を使って暗号化
-        let signer = self.client.signer().await
-            .map_err(|e| anyhow!("署名者の取得に失敗: {}", e))?;
-        let encrypted = signer.nip04_encrypt(&recipient_pk, content).await
-            .map_err(|e| anyhow!("メッセージの暗号化に失敗: {}", e))?;
+        let mut profiles = self.fetch_profiles(&keys).await;
+        Ok(keys.iter()
+            .map(|pk| profiles.remove(pk).unwrap_or_else(|| AuthorInfo::from_public_key(pk)))
+            .collect())
+    }
 
-        // Kind 4 (Encrypted Direct Message) イベントを作成
-        let builder = EventBuilder::new(Kind::EncryptedDirectMessage, encrypted)
-            .tags(vec![Tag::public_key(recipient_pk)]);
+    // ========================================
+    // Phase 3: プロフィール統計情報
+    // ========================================
 
-        let output = self.client.send_event_builder(builder).await
-            .context("ダイレクトメッセージの送信に失敗しました")?;
+    /// ユーザーのプロフィール統計情報（フォロー数・フォロワー数・ノート数）を取得します。
+    ///
+    /// フォロワー数とノート数は NIP-45 (COUNT) 対応リレーで数え、非対応時は取得して数えます。
+    pub async fn get_profile_stats(&self, pubkey_str: &str) -> Result<ProfileStats> {
+        let public_key = Self::parse_public_key(pubkey_str)?;
 
-        let event_id = *output.id();
-        info!("DM を送信しました。イベント ID: {}", event_id);
-        Ok(event_id)
+        // フォロー数: Kind 3 (ContactList) の p タグ数
+        let contact_filter = Filter::new()
+            .author(public_key)
+            .kind(Kind::ContactList)
+            .limit(1);
+
+        // ノート数: Kind 1 の件数
+        let notes_filter = Filter::new()
+            .author(public_key)
+            .kind(Kind::TextNote);
+
+        // フォロワー数: Kind 3 で対象ユーザーを p タグで参照しているイベント
+        let followers_filter = Filter::new()
+            .kind(Kind::ContactList)
+            .pubkey(public_key);
+
+        let (contacts_result, notes, followers) = tokio::join!(
+            self.fetch_events(vec![contact_filter], Duration::from_secs(10)),
+            self.count_events(notes_filter, 5000),
+            self.count_followers(followers_filter)
+        );
+
+        // フォロー数（コンタクトリストがなければ 0、取得に失敗した場合は不明）
+        let following = contacts_result
+            .ok()
+            .map(|events| {
+                events.into_iter()
+                    .max_by_key(|e| e.created_at)
+                    .map_or(0, |event| event.tags.public_keys().count() as u64)
+            });
+
+        Ok(ProfileStats {
+            following,
+            followers: followers.0,
+            notes: notes.0,
+            count_method: combine_count_methods(&[notes.1, followers.1]).to_string(),
+        })
     }
 
-    /// ダイレクトメッセージの会話を取得します（NIP-04）。
-    pub async fn get_dms(&self, with: Option<&str>, limit: u64) -> Result<Vec<DirectMessageInfo>> {
-        let pk = self.public_key
-            .ok_or_else(|| anyhow!("DM の取得には認証が必要です。設定ファイルに nsec を設定してください。"))?;
+    /// フォロワー数を数えるヘルパー（NIP-45 COUNT を優先し、非対応時は取得してユニークな著者を数える）
+    ///
+    /// 取得して数える場合、同じ著者の古いコンタクトリストが複数のリレーに残っていることがあるため
+    /// 著者で重複を除きます。取得に失敗した場合、件数は None（不明）になります。
+    async fn count_followers(&self, filter: Filter) -> (Option<u64>, &'static str) {
+        if let Some(count) = self.count_events_nip45(&filter).await {
+            return (Some(count), "nip45");
+        }
 
-        let signer = self.client.signer().await
-            .map_err(|e| anyhow!("署名者の取得に失敗: {}", e))?;
+        let followers = match self.fetch_events_deduped(vec![filter.limit(5000)], Duration::from_secs(10)).await {
+            Ok(events) => Some(
+                events.iter()
+                    .map(|e| e.pubkey)
+                    .collect::<std::collections::HashSet<_>>()
+                    .len() as u64
+            ),
+            Err(e) => {
+                warn!("フォロワー数の取得に失敗しました: {}", e);
+                None
+            }
+        };
+        (followers, "fetch")
+    }
 
-        // 相手の公開鍵（指定されている場合）
-        let peer_pk = if let Some(with_str) = with {
-            Some(Self::parse_public_key(with_str)?)
-        } else {
-            None
+    /// 2 つのアカウントのフォロー関係をコンタクトリストから判定します。
+    ///
+    /// `pubkey_a` 未指定時は自分の公開鍵を使用します。
+    pub async fn check_follow_relationship(&self, pubkey_a: Option<&str>, pubkey_b: &str) -> Result<FollowRelationship> {
+        let a = match pubkey_a {
+            Some(a) => Self::parse_public_key(a)?,
+            None => self.public_key
+                .ok_or_else(|| anyhow!("pubkey_a を指定するか、設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))?,
         };
+        let b = Self::parse_public_key(pubkey_b)?;
 
-        // 受信 DM: 自分宛の Kind 4 イベント
-        let mut received_filter = Filter::new()
-            .kind(Kind::EncryptedDirectMessage)
-            .pubkey(pk)
-            .limit(limit as usize);
+        let (a_follows, b_follows) = tokio::join!(
+            self.fetch_followed_pubkeys(a),
+            self.fetch_followed_pubkeys(b)
+        );
+
+        let a_follows_b = a_follows.contains(&b);
+        let b_follows_a = b_follows.contains(&a);
+        let a_set: std::collections::HashSet<&PublicKey> = a_follows.iter().collect();
+        let common_follows = b_follows.iter().filter(|pk| a_set.contains(pk)).count() as u64;
 
-        if let Some(ref peer) = peer_pk {
-            received_filter = received_filter.author(*peer);
+        let relationship = match (a_follows_b, b_follows_a) {
+            (true, true) => "mutual",
+            (true, false) => "a_follows_b",
+            (false, true) => "b_follows_a",
+            (false, false) => "none",
         }
+        .to_string();
 
-        // 送信 DM: 自分が送った Kind 4 イベント
-        let mut sent_filter = Filter::new()
-            .kind(Kind::EncryptedDirectMessage)
-            .author(pk)
-            .limit(limit as usize);
+        let profiles = self.fetch_profiles(&[a, b]).await;
+        let author = |pk: &PublicKey| {
+            profiles
+                .get(pk)
+                .cloned()
+                .unwrap_or_else(|| AuthorInfo::from_public_key(pk))
+        };
+
+        Ok(FollowRelationship {
+            a: author(&a),
+            b: author(&b),
+            a_follows_b,
+            b_follows_a,
+            relationship,
+            common_follows,
+            a_following_count: a_follows.len() as u64,
+            b_following_count: b_follows.len() as u64,
+        })
+    }
 
-        if let Some(ref peer) = peer_pk {
-            sent_filter = sent_filter.pubkey(*peer);
+    /// フォロー中のアカウントがフォローしているアカウント（フォローのフォロー）から、
+    /// おすすめのアカウントを返します。
+    ///
+    /// 自分・フォロー済み・ミュート中のアカウントを除き、フォロー中の何人にフォローされているか
+    /// （重なり）の多い順に `limit` 件まで返します。`min_overlap` 人未満のアカウントは除外します。
+    pub async fn recommend_follows(&self, limit: usize, min_overlap: usize) -> Result<FollowRecommendations> {
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))?;
+
+        let follows = self.fetch_followed_pubkeys(pk).await;
+        if follows.is_empty() {
+            return Err(anyhow!("フォロー中のアカウントが見つからないため、おすすめを作成できません"));
         }
+        let (lists, mute_list) = tokio::join!(self.latest_contact_lists(&follows), self.mute_list());
+        let lists = lists.context("フォローのコンタクトリストの取得に失敗しました")?;
 
-        let events = self.client
-            .fetch_events(vec![received_filter, sent_filter], Duration::from_secs(15))
-            .await
-            .context("DM の取得に失敗しました")?;
+        let ranked = rank_follow_candidates(pk, &follows, &lists, min_overlap, |candidate| mute_list.is_pubkey_muted(candidate));
+        let candidates = ranked.len() as u64;
+        let ranked: Vec<(PublicKey, Vec<PublicKey>)> = ranked.into_iter().take(limit).collect();
 
-        let events_vec: Vec<Event> = events.into_iter()
+        let mut pubkeys: Vec<PublicKey> = ranked.iter()
+            .flat_map(|(candidate, followed_by)| std::iter::once(*candidate).chain(followed_by.iter().take(RECOMMENDATION_SAMPLE_FOLLOWERS).copied()))
             .collect();
-
-        let pubkeys = Self::collect_pubkeys(&events_vec);
+        pubkeys.sort();
+        pubkeys.dedup();
         let profiles = self.fetch_profiles(&pubkeys).await;
+        let author = |pk: &PublicKey| profiles.get(pk).cloned().unwrap_or_else(|| AuthorInfo::from_public_key(pk));
+
+        let analyzed = lists.len() as u64;
+        let recommendations = ranked.iter()
+            .map(|(candidate, followed_by)| FollowRecommendation {
+                account: author(candidate),
+                followed_by_count: followed_by.len() as u64,
+                overlap_ratio: followed_by.len() as f64 / analyzed.max(1) as f64,
+                followed_by: followed_by.iter().take(RECOMMENDATION_SAMPLE_FOLLOWERS).map(author).collect(),
+            })
+            .collect();
 
-        let mut messages = Vec::new();
-
-        for event in &events_vec {
-            let is_sent = event.pubkey == pk;
-            let peer_pubkey = if is_sent {
-                // 送信メッセージ: p タグから相手の pubkey を取得
-                event.tags.iter().find_map(|tag| {
-                    let values = tag.as_slice();
-                    if values.len() >= 2 && values[0] == "p" {
-                        PublicKey::from_hex(&values[1]).ok()
-                    } else {
-                        None
-                    }
-                })
-            } else {
-                Some(event.pubkey)
-            };
-
-            let Some(peer) = peer_pubkey else { continue };
+        Ok(FollowRecommendations {
+            following_count: follows.len() as u64,
+            analyzed_contact_lists: analyzed,
+            candidates,
+            recommendations,
+        })
+    }
 
-            // NIP-04 復号
-            let decrypted = if is_sent {
-                signer.nip04_decrypt(&peer, &event.content).await
-            } else {
-                signer.nip04_decrypt(&event.pubkey, &event.content).await
-            };
+    /// 直近 `days` 日間のアカウントの投稿と、受け取った反応を集計します。
+    ///
+    /// 投稿頻度（日別・時間帯別）、受け取ったリアクション・リプライ・リポスト・Zap の合計、
+    /// 反応の多いノートと、よく反応してくれるアカウント（フォロワーかどうか付き）をそれぞれ
+    /// 上位 `top` 件まで返します。反応は対象アカウントを p タグで参照するイベントから数えます。
+    pub async fn get_account_analytics(&self, pubkey_str: &str, days: u64, top: usize) -> Result<AccountAnalytics> {
+        let pk = Self::parse_public_key(pubkey_str)?;
+        let until = current_unix_timestamp();
+        let since = until.saturating_sub(days * 86_400);
 
-            let content = match decrypted {
-                Ok(text) => text,
-                Err(e) => {
-                    debug!("DM 復号に失敗（スキップ）: {}", e);
-                    continue;
-                }
-            };
+        let notes_filter = Filter::new()
+            .author(pk)
+            .kind(Kind::TextNote)
+            .since(Timestamp::from(since));
+        let interactions_filter = Filter::new()
+            .kinds([Kind::Reaction, Kind::TextNote, Kind::Repost, Kind::ZapReceipt])
+            .pubkey(pk)
+            .since(Timestamp::from(since));
+        let (notes, interactions) = tokio::join!(
+            self.paginate_pool_events(notes_filter, MAX_ANALYTICS_NOTES, Duration::from_secs(15)),
+            self.paginate_pool_events(interactions_filter, MAX_ANALYTICS_INTERACTIONS, Duration::from_secs(15))
+        );
+        let notes = notes.context("投稿の取得に失敗しました")?;
+        let mut interactions = interactions.context("受け取った反応の取得に失敗しました")?;
+        let partial = notes.len() >= MAX_ANALYTICS_NOTES || interactions.len() >= MAX_ANALYTICS_INTERACTIONS;
+
+        // 真正性を確認できない Zap レシートは数えない
+        let mut zaps: Vec<Event> = interactions.iter().filter(|e| e.kind == Kind::ZapReceipt).cloned().collect();
+        let invalid_zap_count = self.retain_valid_zap_receipts(&mut zaps).await;
+        if invalid_zap_count > 0 {
+            let valid: std::collections::HashSet<EventId> = zaps.iter().map(|e| e.id).collect();
+            interactions.retain(|e| e.kind != Kind::ZapReceipt || valid.contains(&e.id));
+        }
 
-            let author = profiles
-                .get(&event.pubkey)
-                .cloned()
-                .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey));
+        let tally = tally_account_interactions(&pk, &notes, &interactions);
+        let posting = posting_stats(&notes, days);
 
-            messages.push(DirectMessageInfo {
-                id: event.id.to_hex(),
-                nevent: event.id.to_bech32().unwrap_or_default(),
-                author,
-                content,
-                direction: if is_sent { "sent".to_string() } else { "received".to_string() },
-                peer_pubkey: peer.to_hex(),
-                created_at: event.created_at.as_u64(),
-            });
-        }
+        // 反応の多いノート
+        let mut ranked: Vec<(&Event, InteractionCounts)> = notes.iter()
+            .filter_map(|e| tally.by_note.get(&e.id).map(|counts| (e, *counts)))
+            .filter(|(_, counts)| counts.total() > 0)
+            .collect();
+        ranked.sort_by_key(|(e, counts)| (Reverse(counts.total()), Reverse(counts.zap_sats), Reverse(e.created_at)));
+        ranked.truncate(top);
+        let seen_on = self.seen_on_relays(&notes).await;
+
+        // よく反応してくれるアカウントと、そのアカウントが自分をフォローしているか
+        let unique_interactors = tally.by_account.len() as u64;
+        let mut interactors: Vec<(PublicKey, InteractionCounts)> = tally.by_account.into_iter().collect();
+        interactors.sort_by_key(|(pk, counts)| (Reverse(counts.total()), Reverse(counts.zap_sats), *pk));
+        interactors.truncate(top);
+        let pubkeys: Vec<PublicKey> = interactors.iter().map(|(pk, _)| *pk).collect();
+        let (profiles, followers) = tokio::join!(
+            self.fetch_profiles(&pubkeys),
+            self.followers_among(&pk, &pubkeys)
+        );
 
-        messages.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-        messages.truncate(limit as usize);
+        Ok(AccountAnalytics {
+            pubkey: pk.to_hex(),
+            npub: pk.to_bech32().unwrap_or_default(),
+            days,
+            since,
+            until,
+            posting,
+            received: tally.received,
+            mentions: tally.mentions,
+            invalid_zap_count,
+            top_notes: ranked.into_iter()
+                .map(|(event, counts)| AnalyticsNote {
+                    id: event.id.to_hex(),
+                    nevent: encode_nevent(&event.id, Some(event.pubkey), relay_hints(&seen_on, &event.id)),
+                    preview: truncate_preview(&event.content, DIGEST_PREVIEW_CHARS),
+                    created_at: event.created_at.as_u64(),
+                    counts,
+                })
+                .collect(),
+            unique_interactors,
+            top_interactors: interactors.into_iter()
+                .map(|(interactor, counts)| AccountInteraction {
+                    account: profiles
+                        .get(&interactor)
+                        .cloned()
+                        .unwrap_or_else(|| AuthorInfo::from_public_key(&interactor)),
+                    follows_you: followers.contains(&interactor),
+                    counts,
+                })
+                .collect(),
+            partial,
+        })
+    }
 
-        Ok(messages)
+    /// `candidates` のうち、最新のコンタクトリストで `target` をフォローしているアカウント
+    async fn followers_among(&self, target: &PublicKey, candidates: &[PublicKey]) -> std::collections::HashSet<PublicKey> {
+        let Ok(lists) = self.latest_contact_lists(candidates).await else {
+            return std::collections::HashSet::new();
+        };
+        lists.into_values()
+            .filter(|event| event.tags.public_keys().any(|pk| pk == target))
+            .map(|event| event.pubkey)
+            .collect()
     }
 
-    // ========================================
-    // Phase 4: リレーリスト (NIP-65)
-    // ========================================
+    /// アカウントごとの最新のコンタクトリスト (Kind 3) を取得するヘルパー
+    async fn latest_contact_lists(&self, authors: &[PublicKey]) -> Result<HashMap<PublicKey, Event>> {
+        if authors.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let filters: Vec<Filter> = authors
+            .chunks(TRUST_GRAPH_AUTHORS_PER_FILTER)
+            .map(|chunk| Filter::new().kind(Kind::ContactList).authors(chunk.to_vec()))
+            .collect();
+        let events = self.fetch_events(filters, Duration::from_secs(10)).await?;
+
+        let mut latest: HashMap<PublicKey, Event> = HashMap::new();
+        for event in events {
+            match latest.get(&event.pubkey) {
+                Some(existing) if existing.created_at >= event.created_at => {}
+                _ => {
+                    latest.insert(event.pubkey, event);
+                }
+            }
+        }
+        Ok(latest)
+    }
 
-    /// ユーザーのリレーリスト (Kind 10002, NIP-65) を取得します。
-    pub async fn get_relay_list(&self, pubkey_str: &str) -> Result<RelayListInfo> {
-        let public_key = Self::parse_public_key(pubkey_str)?;
+    /// 自分のフォロワーを確認し、前回との差分とフォロワー数をローカルに記録します。
+    ///
+    /// 自分を p タグで参照するコンタクトリスト (Kind 3) の著者を現在のフォロワーとし、新規フォロワーの
+    /// 通知と共通の既知のフォロワー一覧に記録します。増加は一覧に初めて記録した日時（前回のスナップショット
+    /// 以降のもの）で数えるため、コンタクトリストの作成日時には左右されません。
+    /// 一覧にあって見つからなかったアカウントは最新のコンタクトリストを取得し直し、
+    /// 自分が外れていることを確認できた場合のみフォロー解除として記録して一覧から除きます
+    /// （取得できなかった場合はリレーの取りこぼしとみなしてフォロワーのまま扱います）。
+    pub async fn record_follower_snapshot(&self) -> Result<crate::storage::FollowerHistory> {
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))?;
 
         let filter = Filter::new()
-            .author(public_key)
-            .kind(Kind::RelayList)
-            .limit(1);
-
-        let events = self.client
-            .fetch_events(vec![filter], Duration::from_secs(10))
+            .kind(Kind::ContactList)
+            .pubkey(pk)
+            .limit(5000);
+        let events = self.fetch_events(vec![filter], Duration::from_secs(15))
             .await
-            .context("リレーリストの取得に失敗しました")?;
-
-        let event = events
+            .context("フォロワーの取得に失敗しました")?;
+        let current: Vec<String> = events.into_iter()
+            .filter(|e| e.pubkey != pk)
+            .map(|e| e.pubkey.to_hex())
+            .collect::<std::collections::BTreeSet<_>>()
             .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("{} のリレーリストが見つかりません", pubkey_str))?;
+            .collect();
 
-        let relays: Vec<RelayListEntry> = nip65::extract_relay_list(&event)
-            .map(|(url, metadata)| {
-                let (read, write) = match metadata {
-                    Some(RelayMetadata::Read) => (true, false),
-                    Some(RelayMetadata::Write) => (false, true),
-                    None => (true, true), // メタデータなし = 両方
-                };
-                RelayListEntry {
-                    url: url.to_string(),
-                    read,
-                    write,
-                }
-            })
+        let now = current_unix_timestamp();
+        let account = pk.to_hex();
+        let mut history = crate::storage::load_follower_history(&account);
+        let mut known = crate::storage::load_known_followers(&account);
+        record_new_followers(&mut known, &current, now);
+
+        let missing: Vec<PublicKey> = known.keys()
+            .filter(|hex| current.binary_search(hex).is_err())
+            .filter_map(|hex| PublicKey::from_hex(hex).ok())
             .collect();
+        let lists = self.latest_contact_lists(&missing).await.unwrap_or_else(|e| {
+            warn!("フォロー解除の確認に失敗しました: {}", e);
+            HashMap::new()
+        });
+        let mut lost = Vec::new();
+        for follower in missing {
+            if let Some(list) = lists.get(&follower).filter(|list| !list.tags.public_keys().any(|p| *p == pk)) {
+                let hex = follower.to_hex();
+                known.remove(&hex);
+                lost.push((hex, list.created_at.as_u64()));
+            }
+        }
 
-        Ok(RelayListInfo {
-            pubkey: public_key.to_hex(),
-            npub: public_key.to_bech32().unwrap_or_default(),
-            relays,
-        })
+        // 前回のスナップショット以降に初めて確認したフォロワー（通知の確認で先に記録した分を含む）。
+        // 前回と同じ秒に確認したフォロワーは、前回記録していなければ増加とする
+        let last = history.last_snapshot_at().unwrap_or(0);
+        let mut gained: Vec<(String, u64)> = known.iter()
+            .filter(|(_, first_seen)| **first_seen > 0 && **first_seen >= last)
+            .filter(|(hex, first_seen)| !history.changes.iter()
+                .any(|c| c.change == "gained" && c.pubkey == **hex && c.at == **first_seen))
+            .map(|(hex, first_seen)| (hex.clone(), *first_seen))
+            .collect();
+        gained.sort();
+
+        crate::storage::save_known_followers(&account, &known)?;
+        history.record(now, known.len() as u64, &gained, &lost);
+        crate::storage::save_follower_history(&account, &history)?;
+        Ok(history)
     }
 
-    /// イベント ID 文字列をパース（nevent、note、hex 対応）
-    fn parse_event_id(id_str: &str) -> Result<EventId> {
-        let id_str = id_str.trim();
-        if id_str.starts_with("nevent") {
-            let nip19 = Nip19Event::from_bech32(id_str)
-                .context("無効な nevent 形式です")?;
-            Ok(nip19.event_id)
-        } else if id_str.starts_with("note") {
-            EventId::from_bech32(id_str).context("無効な note 形式です")
-        } else {
-            EventId::from_hex(id_str).context("無効な hex イベント ID です")
+    /// 直近 `days` 日間のフォロワー数の推移と、増えた・減ったフォロワーを返します。
+    ///
+    /// 呼び出し時にもスナップショットを記録します。推移はローカルに記録したスナップショットに
+    /// 基づくため、記録を始める前の期間は含みません。増減はそれぞれ新しい順に `limit` 件まで返します。
+    pub async fn get_follower_growth(&self, days: u64, limit: usize) -> Result<FollowerGrowth> {
+        let history = self.record_follower_snapshot().await?;
+        let until = current_unix_timestamp();
+        let since = until.saturating_sub(days * 86_400);
+
+        let followers = history.snapshots.last().map_or(0, |s| s.followers);
+        let baseline = history.snapshots.iter()
+            .rev()
+            .find(|s| s.taken_at <= since)
+            .or_else(|| history.snapshots.first())
+            .cloned();
+        let change = baseline.as_ref().map_or(0, |b| followers as i64 - b.followers as i64);
+
+        // 日ごとの最後のスナップショット
+        let mut daily: Vec<crate::storage::FollowerSnapshot> = Vec::new();
+        for snapshot in history.snapshots.iter().filter(|s| s.taken_at >= since) {
+            match daily.last_mut() {
+                Some(last) if last.taken_at / 86_400 == snapshot.taken_at / 86_400 => *last = snapshot.clone(),
+                _ => daily.push(snapshot.clone()),
+            }
         }
+
+        let recent = |kind: &str| -> Vec<(PublicKey, u64)> {
+            history.changes.iter()
+                .rev()
+                .filter(|c| c.change == kind && c.at >= since)
+                .filter_map(|c| PublicKey::from_hex(&c.pubkey).ok().map(|pk| (pk, c.at)))
+                .collect()
+        };
+        let gained = recent("gained");
+        let lost = recent("lost");
+
+        let mut pubkeys: Vec<PublicKey> = gained.iter().take(limit)
+            .chain(lost.iter().take(limit))
+            .map(|(pk, _)| *pk)
+            .collect();
+        pubkeys.sort();
+        pubkeys.dedup();
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let to_info = |changes: &[(PublicKey, u64)]| -> Vec<FollowerChangeInfo> {
+            changes.iter()
+                .take(limit)
+                .map(|(pk, at)| FollowerChangeInfo {
+                    account: profiles.get(pk).cloned().unwrap_or_else(|| AuthorInfo::from_public_key(pk)),
+                    at: *at,
+                })
+                .collect()
+        };
+
+        Ok(FollowerGrowth {
+            followers,
+            days,
+            since,
+            until,
+            tracking_since: history.snapshots.first().map(|s| s.taken_at),
+            baseline,
+            change,
+            gained_count: gained.len() as u64,
+            lost_count: lost.len() as u64,
+            gained: to_info(&gained),
+            lost: to_info(&lost),
+            daily,
+        })
     }
 
     // ========================================
-    // NIP-B7: Blossom メディアアップロード
+    // Phase 1: NIP-23 長文コンテンツサポート
     // ========================================
 
-    /// Blossom アップロード用の認証イベント (Kind 24242) を署名
+    /// 長文記事 (Kind 30023) を投稿します。
+    pub async fn post_article(&self, params: ArticleParams) -> Result<(ArticleInfo, PublishResult)> {
+        self.publish_article_event(params, Kind::LongFormTextNote, false).await
+    }
+
+    /// 公開した記事を紹介するノート (Kind 1) を投稿します。
     ///
-    /// BUD-02 に基づき、`t=upload`、`x=<sha256>`、`expiration` タグを含む
-    /// Kind 24242 イベントを署名します（リレーには公開しない）。
-    pub async fn sign_blossom_auth(
-        &self,
-        sha256_hex: &str,
-        file_size: u64,
-        content_type: &str,
-    ) -> Result<Event> {
+    /// タイトル・要約・`nostr:naddr...` 参照を本文に含め、記事を a タグ、ハッシュタグを t タグで付けるため、
+    /// 長文記事に対応していないクライアントのフォロワーにも記事が届きます。
+    pub async fn announce_article(&self, article: &ArticleInfo) -> Result<PublishResult> {
         self.require_write_access()?;
 
-        let expiration = Timestamp::from(Timestamp::now().as_u64() + 300); // 5分後に有効期限切れ
+        let naddr = article.naddr.as_deref()
+            .ok_or_else(|| anyhow!("記事の naddr がないため紹介ノートを作成できません"))?;
+        let coordinate = Self::parse_coordinate(naddr)?;
 
-        let tags = vec![
-            Tag::custom(
-                TagKind::custom("t".to_string()),
-                vec!["upload".to_string()],
-            ),
-            Tag::custom(
-                TagKind::custom("x".to_string()),
-                vec![sha256_hex.to_string()],
-            ),
-            Tag::expiration(expiration),
-            Tag::custom(
-                TagKind::custom("size".to_string()),
-                vec![file_size.to_string()],
-            ),
-        ];
+        let mut content = article.title.clone();
+        if let Some(summary) = article.summary.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            content.push_str("\n\n");
+            content.push_str(summary);
+        }
+        content.push_str(&format!("\n\nnostr:{}", naddr));
 
-        let builder = EventBuilder::new(Kind::from(24242), format!("Upload {}", content_type))
-            .tags(tags);
+        let mut tags = vec![Tag::coordinate(coordinate)];
+        tags.extend(article.tags.iter().flatten().map(Tag::hashtag));
 
-        let event = self
-            .client
-            .sign_event_builder(builder)
-            .await
-            .context("Blossom 認証イベントの署名に失敗")?;
+        let builder = EventBuilder::text_note(content).tags(tags);
+        let output = self.send_event_builder(builder).await
+            .context("記事の紹介ノートの公開に失敗しました")?;
 
-        debug!(
-            "Blossom 認証イベントを署名: kind={}, id={}",
-            event.kind,
-            event.id
-        );
+        info!("記事の紹介ノートを公開しました。イベント ID: {}", output.id());
+        Ok(PublishResult::new(output, self.public_key))
+    }
 
-        Ok(event)
+    /// 長文記事 (Kind 30023) を取得します。
+    pub async fn get_articles(&self, author: Option<&str>, tags: Option<&[String]>, limit: u64) -> Result<Vec<ArticleInfo>> {
+        self.fetch_articles_by_kind(Kind::LongFormTextNote, author, tags, limit).await
     }
 
-    /// ユーザーの Blossom サーバーリスト (Kind 10063) を取得
-    pub async fn get_blossom_servers(&self, pubkey_str: Option<&str>) -> Result<Vec<String>> {
-        let pubkey = if let Some(pk_str) = pubkey_str {
-            Self::parse_public_key(pk_str)?
-        } else if let Some(pk) = self.public_key {
-            pk
-        } else {
-            return Err(anyhow!(
-                "公開鍵が必要です。pubkey パラメータを指定するか、認証してください。"
+    /// 記事を下書き (Kind 30024) として保存します。
+    pub async fn save_draft(&self, params: ArticleParams) -> Result<(ArticleInfo, PublishResult)> {
+        self.publish_article_event(params, Kind::from(30024), true).await
+    }
+
+    /// ユーザーの下書き記事 (Kind 30024) を取得します。
+    pub async fn get_drafts(&self, limit: u64) -> Result<Vec<ArticleInfo>> {
+        self.fetch_articles_by_kind(Kind::from(30024), None, None, limit).await
+    }
+
+    /// 記事/下書きを公開する共通ヘルパー
+    async fn publish_article_event(&self, params: ArticleParams, kind: Kind, is_draft: bool) -> Result<(ArticleInfo, PublishResult)> {
+        self.require_write_access()?;
+
+        let d_tag = params.identifier.unwrap_or_else(|| {
+            slug_from_title(&params.title)
+        });
+
+        let mut tags = build_article_tags(&params.title, &params.summary, &params.image, &params.tags, &d_tag);
+
+        // 公開記事の場合のみ published_at を追加
+        if !is_draft {
+            let ts = params.published_at.unwrap_or_else(current_unix_timestamp);
+            tags.push(Tag::custom(
+                TagKind::custom("published_at".to_string()),
+                vec![ts.to_string()],
             ));
-        };
+        }
 
-        let filter = Filter::new()
-            .author(pubkey)
-            .kind(Kind::from(10063))
-            .limit(1);
+        let builder = EventBuilder::new(kind, &params.content).tags(tags);
 
-        let events = self
-            .client
-            .fetch_events(vec![filter], Duration::from_secs(5))
-            .await
-            .context("Blossom サーバーリストの取得に失敗")?;
+        let label = if is_draft { "下書き" } else { "記事" };
+        let output = self.send_event_builder(builder).await
+            .context(format!("{}の公開に失敗しました", label))?;
 
-        let servers: Vec<String> = events
-            .iter()
-            .flat_map(|event| {
-                event
-                    .tags
-                    .iter()
-                    .filter_map(|tag| {
-                        let values = tag.as_slice();
-                        if values.len() >= 2 && values[0] == "server" {
-                            Some(values[1].to_string())
-                        } else {
-                            None
-                        }
-                    })
-            })
-            .collect();
+        let event_id = *output.id();
+        info!("{}を公開しました。イベント ID: {}", label, event_id);
+        let published = PublishResult::new(output, self.public_key);
 
-        debug!("Blossom サーバーリスト取得: {} 件", servers.len());
-        Ok(servers)
+        let naddr = self.public_key.and_then(|pk| {
+            Coordinate::new(kind, pk).identifier(&d_tag).to_bech32().ok()
+        });
+
+        let article = ArticleInfo {
+            id: event_id.to_hex(),
+            nevent: published.nevent.clone(),
+            naddr,
+            identifier: d_tag,
+            title: params.title,
+            summary: params.summary,
+            image: params.image,
+            content: params.content,
+            author: self.public_key.map(|pk| AuthorInfo::from_public_key(&pk)),
+            published_at: params.published_at,
+            created_at: current_unix_timestamp(),
+            tags: params.tags,
+            is_draft,
+        };
+
+        // 記事は置き換え可能なため、公開した版をローカルに保存して編集履歴を残す（ドライランでは残さない）
+        if !is_draft && !self.is_dry_run() {
+            if let Err(e) = Self::record_article_snapshot(&article) {
+                warn!("記事のバージョン履歴の保存に失敗: {}", e);
+            }
+        }
+        Ok((article, published))
     }
 
-    /// Blossom サーバーリスト (Kind 10063) を公開
-    pub async fn publish_blossom_servers(&self, servers: &[String]) -> Result<EventId> {
-        self.require_write_access()?;
+    /// 記事/下書きを取得する共通ヘルパー
+    async fn fetch_articles_by_kind(
+        &self,
+        kind: Kind,
+        author: Option<&str>,
+        tags: Option<&[String]>,
+        limit: u64,
+    ) -> Result<Vec<ArticleInfo>> {
+        let is_draft = kind == Kind::from(30024);
 
-        let tags: Vec<Tag> = servers
-            .iter()
-            .map(|url| {
-                Tag::custom(
-                    TagKind::custom("server".to_string()),
-                    vec![url.clone()],
-                )
-            })
-            .collect();
+        // 下書き取得は認証必須
+        let mut filter = if is_draft {
+            let pk = self.public_key
+                .ok_or_else(|| anyhow!("下書きの取得には認証が必要です。設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))?;
+            Filter::new().author(pk).kind(kind).limit(limit as usize)
+        } else {
+            let mut f = Filter::new().kind(kind).limit(limit as usize);
+            if let Some(author_str) = author {
+                let pk = Self::parse_public_key(author_str)?;
+                f = f.author(pk);
+            }
+            f
+        };
 
-        let builder = EventBuilder::new(Kind::from(10063), "").tags(tags);
+        if let Some(hashtags) = tags {
+            filter = filter.hashtags(hashtags.to_vec());
+        }
 
-        let output = self
-            .client
-            .send_event_builder(builder)
+        let timeout = if is_draft { 10 } else { 15 };
+        let events = self.fetch_events(vec![filter], Duration::from_secs(timeout))
             .await
-            .context("Blossom サーバーリストの公開に失敗")?;
+            .context(format!("{}の取得に失敗しました", if is_draft { "下書き" } else { "記事" }))?;
 
-        let event_id = *output.id();
-        info!(
-            "Blossom サーバーリストを公開: {} ({} サーバー)",
-            event_id,
-            servers.len()
-        );
-        Ok(event_id)
+        let events_vec: Vec<Event> = events.into_iter().collect();
+        let pubkeys = Self::collect_pubkeys(&events_vec);
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let seen_on = self.seen_on_relays(&events_vec).await;
+
+        let mut articles: Vec<ArticleInfo> = events_vec.iter().map(|event| {
+            let mut article = Self::event_to_article(event, &profiles, relay_hints(&seen_on, &event.id));
+            if is_draft {
+                article.is_draft = true;
+            }
+            article
+        }).collect();
+
+        articles.sort_by_key(|x| Reverse(x.created_at));
+        articles.truncate(limit as usize);
+
+        Ok(articles)
     }
 
-    /// メディアファイルを Blossom サーバーにアップロード (NIP-B7)
+    /// 公開鍵文字列をパース（アドレス帳の別名、npub、nprofile、hex 対応）
+    pub(crate) fn parse_public_key(key_str: &str) -> Result<PublicKey> {
+        Self::parse_profile_pointer(key_str).map(|(public_key, _)| public_key)
+    }
+
+    /// 公開鍵文字列をパースし、nprofile に埋め込まれたリレーヒントも返す（`nostr:` URI 対応）
     ///
-    /// 1. ファイルの SHA-256 ハッシュを計算
-    /// 2. Kind 24242 認証イベントを署名
-    /// 3. BUD-02 の PUT /upload でアップロード
-    pub async fn upload_media(
-        &self,
-        data: Vec<u8>,
-        content_type: &str,
-        server_url: &str,
-    ) -> Result<crate::blossom::BlobDescriptor> {
-        self.require_write_access()?;
+    /// アドレス帳に登録された別名（"alice" など）を最初に確認します。
+    fn parse_profile_pointer(key_str: &str) -> Result<(PublicKey, Vec<String>)> {
+        if let Some(hex) = crate::storage::resolve_alias(key_str) {
+            let public_key = PublicKey::from_hex(&hex).context("アドレス帳に登録された公開鍵が無効です")?;
+            return Ok((public_key, Vec::new()));
+        }
+        let key_str = key_str.trim().trim_start_matches("nostr:");
+        if key_str.starts_with("nprofile") {
+            let profile = Nip19Profile::from_bech32(key_str).context("無効な nprofile 形式です")?;
+            let relays = profile.relays.iter().map(|url| url.to_string()).collect();
+            Ok((profile.public_key, relays))
+        } else if key_str.starts_with("npub") {
+            let public_key = PublicKey::from_bech32(key_str).context("無効な npub 形式です")?;
+            Ok((public_key, Vec::new()))
+        } else if let Ok(public_key) = PublicKey::from_hex(key_str) {
+            Ok((public_key, Vec::new()))
+        } else if crate::storage::normalize_alias(key_str).is_some() {
+            Err(anyhow!("無効な公開鍵です。「{}」はアドレス帳にも登録されていません", key_str.trim()))
+        } else {
+            Err(anyhow!("無効な hex 公開鍵です: {}", key_str))
+        }
+    }
 
-        let sha256_hex = crate::blossom::compute_sha256(&data);
-        let file_size = data.len() as u64;
+    /// イベントから記事情報に変換するヘルパー
+    fn event_to_article(event: &Event, profiles: &HashMap<PublicKey, AuthorInfo>, relays: &[String]) -> ArticleInfo {
+        let author = profiles
+            .get(&event.pubkey)
+            .cloned()
+            .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey));
 
-        // Kind 24242 認証イベントを署名
-        let auth_event = self
-            .sign_blossom_auth(&sha256_hex, file_size, content_type)
-            .await?;
+        let identifier = event.tags.iter()
+            .find_map(|tag| {
+                if let Some(TagStandard::Identifier(id)) = tag.as_standardized() {
+                    Some(id.clone())
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_default();
 
-        // 認証ヘッダーを構築
-        let event_json = serde_json::to_string(&auth_event)
-            .context("認証イベントの JSON 化に失敗")?;
-        let auth_header = crate::blossom::create_auth_header(&event_json);
+        let title = extract_tag_value(event, "title")
+            .unwrap_or_else(|| "無題".to_string());
 
-        // Blossom サーバーにアップロード
-        let descriptor =
-            crate::blossom::upload_blob(server_url, data, content_type, &auth_header).await?;
+        let summary = extract_tag_value(event, "summary");
+        let image = extract_tag_value(event, "image");
+
+        let published_at = extract_tag_value(event, "published_at")
+            .and_then(|s| s.parse::<u64>().ok());
+
+        let tags: Vec<String> = event.tags.iter()
+            .filter_map(|tag| {
+                if let Some(TagStandard::Hashtag(h)) = tag.as_standardized() {
+                    Some(h.clone())
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let naddr = Coordinate::new(event.kind, event.pubkey)
+            .identifier(&identifier)
+            .to_bech32()
+            .ok();
+
+        ArticleInfo {
+            id: event.id.to_hex(),
+            nevent: encode_nevent(&event.id, Some(event.pubkey), relays),
+            naddr,
+            identifier,
+            title,
+            summary,
+            image,
+            content: event.content.clone(),
+            author: Some(author),
+            published_at,
+            created_at: event.created_at.as_u64(),
+            tags: if tags.is_empty() { None } else { Some(tags) },
+            is_draft: event.kind == Kind::from(30024),
+        }
+    }
+
+    // ========================================
+    // ローカル下書き（リレーに送信しない）
+    // ========================================
+
+    /// 記事の下書きをデータディレクトリに保存します（リレーには送信しません）。
+    ///
+    /// 同じ識別子の下書きは上書きします。
+    pub fn save_local_draft(&self, params: ArticleParams) -> Result<crate::storage::LocalDraft> {
+        let account = self.local_draft_account()?;
+        let now = current_unix_timestamp();
+        let draft = crate::storage::LocalDraft {
+            identifier: params.identifier.unwrap_or_else(|| slug_from_title(&params.title)),
+            title: params.title,
+            content: params.content,
+            summary: params.summary,
+            image: params.image,
+            tags: params.tags.unwrap_or_default(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        let mut drafts = crate::storage::load_local_drafts(&account);
+        let draft = crate::storage::upsert_local_draft(&mut drafts, draft);
+        crate::storage::save_local_drafts(&account, &drafts)?;
+        info!("ローカル下書きを保存しました: {}", draft.identifier);
+        Ok(draft)
+    }
+
+    /// ローカル下書きを更新日時の新しい順に取得します。
+    pub fn list_local_drafts(&self) -> Result<Vec<crate::storage::LocalDraft>> {
+        let account = self.local_draft_account()?;
+        let mut drafts: Vec<_> = crate::storage::load_local_drafts(&account).into_values().collect();
+        drafts.sort_by_key(|d| Reverse(d.updated_at));
+        Ok(drafts)
+    }
+
+    /// ローカル下書きを長文記事 (Kind 30023) として公開し、ローカルから削除します（ドライランでは削除しません）。
+    pub async fn publish_local_draft(&self, identifier: &str, published_at: Option<u64>) -> Result<(ArticleInfo, PublishResult)> {
+        let account = self.local_draft_account()?;
+        let mut drafts = crate::storage::load_local_drafts(&account);
+        let draft = drafts
+            .get(identifier)
+            .cloned()
+            .ok_or_else(|| anyhow!("ローカル下書きが見つかりません: {}", identifier))?;
+
+        let published = self.post_article(ArticleParams {
+            title: draft.title,
+            content: draft.content,
+            identifier: Some(draft.identifier),
+            summary: draft.summary,
+            image: draft.image,
+            tags: if draft.tags.is_empty() { None } else { Some(draft.tags) },
+            published_at,
+        }).await?;
+
+        // ドライランでは公開していないため、下書きを残す
+        if !self.is_dry_run() {
+            drafts.remove(identifier);
+            crate::storage::save_local_drafts(&account, &drafts)?;
+        }
+        Ok(published)
+    }
+
+    /// ローカル下書きの保存先アカウント（hex 公開鍵）を取得するヘルパー
+    fn local_draft_account(&self) -> Result<String> {
+        self.public_key
+            .map(|pk| pk.to_hex())
+            .ok_or_else(|| anyhow!("ローカル下書きには認証が必要です。設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))
+    }
+
+    // ========================================
+    // 記事のシリーズ（NIP-51 キュレーションセット）
+    // ========================================
+
+    /// 記事のシリーズをキュレーションセット (Kind 30004) として作成します。
+    ///
+    /// `articles`（naddr または `30023:<pubkey>:<d>` 形式）の順番がシリーズ内の順番になります。
+    /// 同じ識別子のシリーズが既にある場合はエラーを返します（`add_article_to_publication` で追加してください）。
+    pub async fn create_publication(&self, params: PublicationParams) -> Result<(PublicationInfo, PublishResult)> {
+        self.require_write_access()?;
+        let pk = self.public_key.ok_or_else(|| anyhow!("公開鍵が設定されていません"))?;
+
+        let title = params.title.trim();
+        if title.is_empty() {
+            return Err(anyhow!("シリーズのタイトルを指定してください"));
+        }
+        let identifier = params.identifier
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| slug_from_title(title));
+
+        let mut entries: Vec<Coordinate> = Vec::new();
+        for article in &params.articles {
+            insert_publication_entry(&mut entries, Self::parse_article_coordinate(article)?, None)?;
+        }
+
+        if self.fetch_latest_addressable(pk, Kind::from(KIND_CURATION_SET), &identifier, "シリーズ").await?.is_some() {
+            return Err(anyhow!(
+                "シリーズ「{}」は既に存在します。記事の追加には add_article_to_publication を使用してください",
+                identifier
+            ));
+        }
+
+        let mut tags = vec![
+            Tag::identifier(&identifier),
+            Tag::custom(TagKind::Title, vec![title.to_string()]),
+        ];
+        if let Some(description) = params.description.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+            tags.push(Tag::custom(TagKind::Description, vec![description.to_string()]));
+        }
+        if let Some(image) = params.image.as_deref().map(str::trim).filter(|i| !i.is_empty()) {
+            tags.push(Tag::custom(TagKind::Image, vec![image.to_string()]));
+        }
+        tags.extend(entries.iter().cloned().map(Tag::coordinate));
+
+        let output = self.send_event_builder(EventBuilder::new(Kind::from(KIND_CURATION_SET), "").tags(tags.clone()))
+            .await
+            .context("シリーズの公開に失敗しました")?;
+        info!("シリーズ「{}」を公開しました（{} 件）。イベント ID: {}", identifier, entries.len(), output.id());
+
+        let info = self.build_publication_info(pk, &identifier, &tags, current_unix_timestamp(), true).await;
+        Ok((info, PublishResult::new(output, self.public_key)))
+    }
+
+    /// 自分のシリーズに記事を追加します。
+    ///
+    /// `position`（1 始まり）を指定するとその位置に挿入し、省略時は末尾に追加します。
+    /// 既に含まれている記事は、`position` を指定した場合のみその位置に移動します。
+    /// 戻り値は (更新後のシリーズ, 公開結果, 記事の位置)。
+    pub async fn add_article_to_publication(&self, publication: &str, article: &str, position: Option<usize>) -> Result<(PublicationInfo, PublishResult, usize)> {
+        self.require_write_access()?;
+        let pk = self.public_key.ok_or_else(|| anyhow!("公開鍵が設定されていません"))?;
+
+        let coordinate = self.resolve_publication_coordinate(publication)?;
+        if coordinate.public_key != pk {
+            return Err(anyhow!("自分のシリーズにのみ記事を追加できます"));
+        }
+        let existing = self.fetch_latest_addressable(pk, Kind::from(KIND_CURATION_SET), &coordinate.identifier, "シリーズ").await?
+            .ok_or_else(|| anyhow!("シリーズ「{}」が見つかりません。先に create_publication で作成してください", coordinate.identifier))?;
+
+        let mut entries = publication_entries(existing.tags.iter());
+        let placed = insert_publication_entry(&mut entries, Self::parse_article_coordinate(article)?, position)?;
+
+        // a タグ以外（d・title など）はそのまま引き継ぎ、a タグを新しい順番で付け直す
+        let mut tags: Vec<Tag> = existing.tags.iter()
+            .filter(|tag| tag.as_slice().first().is_none_or(|k| k != "a"))
+            .cloned()
+            .collect();
+        tags.extend(entries.iter().cloned().map(Tag::coordinate));
+
+        let output = self.send_event_builder(EventBuilder::new(Kind::from(KIND_CURATION_SET), existing.content.clone()).tags(tags.clone()))
+            .await
+            .context("シリーズの公開に失敗しました")?;
+        info!("シリーズ「{}」に記事を追加しました（{} 件目）。イベント ID: {}", coordinate.identifier, placed, output.id());
+
+        let info = self.build_publication_info(pk, &coordinate.identifier, &tags, current_unix_timestamp(), true).await;
+        Ok((info, PublishResult::new(output, self.public_key), placed))
+    }
+
+    /// シリーズ（naddr、`30004:<pubkey>:<d>` 形式、または自分のシリーズの識別子）を取得し、記事を順番に返します。
+    ///
+    /// `include_content` が false の場合、記事の本文はプレビューに切り詰めます。
+    pub async fn get_publication(&self, publication: &str, include_content: bool) -> Result<PublicationInfo> {
+        let coordinate = self.resolve_publication_coordinate(publication)?;
+        let event = self.fetch_event_by_coordinate(&coordinate, "シリーズ").await?;
+        let tags: Vec<Tag> = event.tags.iter().cloned().collect();
+        Ok(self.build_publication_info(event.pubkey, &coordinate.identifier, &tags, event.created_at.as_u64(), include_content).await)
+    }
+
+    /// シリーズの指定をキュレーションセットの座標にするヘルパー（識別子のみの場合は自分のシリーズ）
+    fn resolve_publication_coordinate(&self, publication: &str) -> Result<Coordinate> {
+        let publication = publication.trim();
+        if publication.starts_with("naddr") || publication.starts_with("nostr:") || publication.contains(':') {
+            let coordinate = Self::parse_coordinate(publication)?;
+            if coordinate.kind != Kind::from(KIND_CURATION_SET) {
+                return Err(anyhow!("シリーズ (Kind 30004) のアドレスを指定してください"));
+            }
+            return Ok(coordinate);
+        }
+        let pk = self.public_key.ok_or_else(|| anyhow!(
+            "識別子だけでシリーズを指定するには認証が必要です。naddr を指定してください。"
+        ))?;
+        Ok(Coordinate::new(Kind::from(KIND_CURATION_SET), pk).identifier(publication))
+    }
+
+    /// 記事のアドレスをパースするヘルパー（長文記事 Kind 30023 のみ、リレーヒントは除く）
+    fn parse_article_coordinate(article: &str) -> Result<Coordinate> {
+        let coordinate = Self::parse_coordinate(article)?;
+        if coordinate.kind != Kind::LongFormTextNote {
+            return Err(anyhow!("長文記事 (Kind 30023) のアドレスを指定してください: {}", article));
+        }
+        Ok(Coordinate::new(coordinate.kind, coordinate.public_key).identifier(coordinate.identifier))
+    }
+
+    /// シリーズのタグから情報を組み立て、記事を取得して順番に並べるヘルパー
+    async fn build_publication_info(&self, pk: PublicKey, identifier: &str, tags: &[Tag], created_at: u64, include_content: bool) -> PublicationInfo {
+        let tag_value = |key: &str| tags.iter().find_map(|tag| {
+            let values = tag.as_slice();
+            (values.len() >= 2 && values[0] == key).then(|| values[1].clone())
+        });
+        let entries = publication_entries(tags);
+
+        let articles: Vec<Event> = if entries.is_empty() {
+            Vec::new()
+        } else {
+            let filter = Filter::new()
+                .kind(Kind::LongFormTextNote)
+                .authors(entries.iter().map(|c| c.public_key))
+                .identifiers(entries.iter().map(|c| c.identifier.clone()));
+            match self.fetch_events(vec![filter], Duration::from_secs(10)).await {
+                Ok(events) => events.into_iter().collect(),
+                Err(e) => {
+                    warn!("シリーズの記事の取得に失敗: {}", e);
+                    Vec::new()
+                }
+            }
+        };
+
+        let mut authors: Vec<PublicKey> = articles.iter().map(|e| e.pubkey).collect();
+        authors.push(pk);
+        authors.sort();
+        authors.dedup();
+        let profiles = self.fetch_profiles(&authors).await;
+        let seen_on = self.seen_on_relays(&articles).await;
+
+        let entries: Vec<PublicationEntry> = entries.iter()
+            .enumerate()
+            .map(|(i, coordinate)| {
+                let article = articles.iter()
+                    .filter(|e| e.kind == coordinate.kind && e.pubkey == coordinate.public_key && e.tags.identifier() == Some(coordinate.identifier.as_str()))
+                    .max_by_key(|e| e.created_at)
+                    .map(|event| {
+                        let mut article = Self::event_to_article(event, &profiles, relay_hints(&seen_on, &event.id));
+                        if !include_content {
+                            article.content = truncate_preview(&article.content, PUBLICATION_PREVIEW_CHARS);
+                        }
+                        article
+                    });
+                PublicationEntry {
+                    position: i + 1,
+                    naddr: coordinate.to_bech32().unwrap_or_default(),
+                    found: article.is_some(),
+                    article,
+                }
+            })
+            .collect();
+
+        PublicationInfo {
+            naddr: Coordinate::new(Kind::from(KIND_CURATION_SET), pk).identifier(identifier).to_bech32().unwrap_or_default(),
+            identifier: identifier.to_string(),
+            title: tag_value("title").unwrap_or_else(|| "無題".to_string()),
+            description: tag_value("description"),
+            image: tag_value("image"),
+            author: profiles.get(&pk).cloned().unwrap_or_else(|| AuthorInfo::from_public_key(&pk)),
+            created_at,
+            articles: entries,
+        }
+    }
+
+    // ========================================
+    // 記事のバージョン履歴
+    // ========================================
+
+    /// 公開した記事をバージョン履歴に保存するヘルパー
+    fn record_article_snapshot(article: &ArticleInfo) -> Result<()> {
+        let Some(author) = &article.author else { return Ok(()) };
+        let mut history = crate::storage::load_article_history(&author.pubkey);
+        let version = crate::storage::ArticleVersion {
+            event_id: article.id.clone(),
+            title: article.title.clone(),
+            summary: article.summary.clone(),
+            image: article.image.clone(),
+            tags: article.tags.clone().unwrap_or_default(),
+            content: article.content.clone(),
+            created_at: article.created_at,
+        };
+        if crate::storage::record_article_version(&mut history, &article.identifier, version) {
+            crate::storage::save_article_history(&author.pubkey, &history)?;
+        }
+        Ok(())
+    }
+
+    /// 自分の記事の識別子（d タグ）を取得するヘルパー（d タグ、naddr、`30023:<pubkey>:<d>` 形式に対応）
+    fn own_article_identifier(&self, pk: PublicKey, article: &str) -> Result<String> {
+        let article = article.trim();
+        if !(article.starts_with("naddr") || article.starts_with("nostr:") || article.contains(':')) {
+            return Ok(article.to_string());
+        }
+        let coordinate = Self::parse_article_coordinate(article)?;
+        if coordinate.public_key != pk {
+            return Err(anyhow!("バージョン履歴は自分の記事のみ確認できます"));
+        }
+        Ok(coordinate.identifier)
+    }
+
+    /// 記事のバージョン履歴を読み込み、リレー上の最新版も履歴に取り込むヘルパー
+    ///
+    /// 他のクライアントで編集された版も比較できるよう、見つかった最新版が未記録なら追加します。
+    async fn sync_article_history(&self, pk: PublicKey, identifier: &str) -> crate::storage::ArticleHistory {
+        let account = pk.to_hex();
+        match self.fetch_latest_addressable(pk, Kind::LongFormTextNote, identifier, "記事").await {
+            Ok(Some(event)) => {
+                let article = Self::event_to_article(&event, &HashMap::new(), &[]);
+                if let Err(e) = Self::record_article_snapshot(&article) {
+                    warn!("記事のバージョン履歴の保存に失敗: {}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("記事の最新版の取得に失敗: {}", e),
+        }
+        crate::storage::load_article_history(&account)
+    }
+
+    /// 自分の記事のバージョン履歴を返します。
+    ///
+    /// バージョンは古い順に 1 から番号を振り、直前のバージョンからの追加・削除行数を付けます。
+    /// 本文は含めないため、内容の確認には `diff_article_versions` を使います。
+    pub async fn get_article_history(&self, article: &str) -> Result<ArticleHistoryInfo> {
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))?;
+        let identifier = self.own_article_identifier(pk, article)?;
+        let history = self.sync_article_history(pk, &identifier).await;
+        let versions = history.get(&identifier)
+            .filter(|versions| !versions.is_empty())
+            .ok_or_else(|| anyhow!("記事「{}」のバージョン履歴がありません", identifier))?;
+
+        let summaries = versions.iter()
+            .enumerate()
+            .map(|(i, version)| {
+                let (added_lines, removed_lines) = match i.checked_sub(1).map(|prev| &versions[prev]) {
+                    Some(previous) => {
+                        let diff = crate::diff::diff_lines(&previous.content, &version.content);
+                        (diff.added, diff.removed)
+                    }
+                    None => (version.content.lines().count(), 0),
+                };
+                ArticleVersionSummary {
+                    version: i + 1,
+                    event_id: version.event_id.clone(),
+                    title: version.title.clone(),
+                    summary: version.summary.clone(),
+                    created_at: version.created_at,
+                    characters: version.content.chars().count(),
+                    added_lines,
+                    removed_lines,
+                }
+            })
+            .collect();
+
+        Ok(ArticleHistoryInfo {
+            naddr: Coordinate::new(Kind::LongFormTextNote, pk).identifier(&identifier).to_bech32().unwrap_or_default(),
+            identifier,
+            versions: summaries,
+        })
+    }
+
+    /// バージョン履歴のある自分の記事の一覧を返します（最終更新の新しい順）。
+    pub fn list_article_histories(&self) -> Result<Vec<ArticleHistoryOverview>> {
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))?;
+        let mut overviews: Vec<ArticleHistoryOverview> = crate::storage::load_article_history(&pk.to_hex())
+            .into_iter()
+            .filter_map(|(identifier, versions)| {
+                let latest = versions.last()?;
+                Some(ArticleHistoryOverview {
+                    naddr: Coordinate::new(Kind::LongFormTextNote, pk).identifier(&identifier).to_bech32().unwrap_or_default(),
+                    title: latest.title.clone(),
+                    versions: versions.len(),
+                    updated_at: latest.created_at,
+                    identifier,
+                })
+            })
+            .collect();
+        overviews.sort_by_key(|o| Reverse(o.updated_at));
+        Ok(overviews)
+    }
+
+    /// 自分の記事の 2 つのバージョンを比較します。
+    ///
+    /// バージョン番号は `get_article_history` の番号（1 始まり）で、負の値は最新から数えます
+    /// （-1 が最新）。省略時は直前のバージョンと最新のバージョンを比較します。
+    pub async fn diff_article_versions(&self, article: &str, from: Option<i64>, to: Option<i64>) -> Result<ArticleVersionDiff> {
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))?;
+        let identifier = self.own_article_identifier(pk, article)?;
+        let history = self.sync_article_history(pk, &identifier).await;
+        let versions = history.get(&identifier)
+            .filter(|versions| !versions.is_empty())
+            .ok_or_else(|| anyhow!("記事「{}」のバージョン履歴がありません", identifier))?;
+
+        if from.is_none() && versions.len() < 2 {
+            return Err(anyhow!("記事「{}」のバージョンは 1 つしかないため比較できません", identifier));
+        }
+        let (from, to) = resolve_diff_versions(from, to, versions.len())?;
+        let (old, new) = (&versions[from - 1], &versions[to - 1]);
+
+        let old_tags: std::collections::BTreeSet<&String> = old.tags.iter().collect();
+        let new_tags: std::collections::BTreeSet<&String> = new.tags.iter().collect();
+        let changed = |a: &Option<String>, b: &Option<String>| (a != b).then(|| b.clone().unwrap_or_default());
+
+        Ok(ArticleVersionDiff {
+            naddr: Coordinate::new(Kind::LongFormTextNote, pk).identifier(&identifier).to_bech32().unwrap_or_default(),
+            identifier,
+            from,
+            to,
+            from_event_id: old.event_id.clone(),
+            to_event_id: new.event_id.clone(),
+            from_created_at: old.created_at,
+            to_created_at: new.created_at,
+            title_changed: (old.title != new.title).then(|| TextChange { before: old.title.clone(), after: new.title.clone() }),
+            summary_changed: changed(&old.summary, &new.summary)
+                .map(|after| TextChange { before: old.summary.clone().unwrap_or_default(), after }),
+            image_changed: changed(&old.image, &new.image)
+                .map(|after| TextChange { before: old.image.clone().unwrap_or_default(), after }),
+            tags_added: new_tags.difference(&old_tags).map(|t| t.to_string()).collect(),
+            tags_removed: old_tags.difference(&new_tags).map(|t| t.to_string()).collect(),
+            diff: crate::diff::diff_lines(&old.content, &new.content),
+        })
+    }
+
+    // ========================================
+    // Phase 2: タイムライン拡張機能
+    // ========================================
+
+    /// スレッド形式でノートとリプライを取得します（NIP-10 対応）。
+    pub async fn get_thread(&self, note_id: &str, depth: u64) -> Result<ThreadInfo> {
+        // ルートノートを取得（識別子のリレーヒントも使う）
+        let root_event = self.resolve_event(note_id, "ルートノート").await?;
+        let event_id = root_event.id;
+
+        // リプライを取得（e タグでルートノートを参照しているイベント）
+        let reply_filter = Filter::new()
+            .kind(Kind::TextNote)
+            .event(event_id)
+            .limit(200);
+
+        let reply_events = self.fetch_events(vec![reply_filter], Duration::from_secs(10))
+            .await
+            .context("リプライの取得に失敗しました")?;
+
+        let reply_events_vec = self.filter_muted(reply_events.into_iter().collect()).await;
+
+        // リアクション数を取得
+        let reaction_filter = Filter::new()
+            .kind(Kind::Reaction)
+            .event(event_id)
+            .limit(500);
+
+        let reaction_count = match self.fetch_events_deduped(vec![reaction_filter], Duration::from_secs(5))
+            .await {
+            Ok(events) => events.len() as u64,
+            Err(_) => 0,
+        };
+
+        // プロフィールを取得
+        let mut all_events = vec![root_event.clone()];
+        all_events.extend(reply_events_vec.iter().cloned());
+        let pubkeys = Self::collect_pubkeys(&all_events);
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let mut seen_on = self.seen_on_relays(&all_events).await;
+
+        // ルートノート情報を作成
+        let root_author_pk = event_author(&root_event);
+        let root_author = profiles
+            .get(&root_author_pk)
+            .cloned()
+            .unwrap_or_else(|| AuthorInfo::from_public_key(&root_author_pk));
+
+        let root_seen_on = seen_on.remove(&root_event.id).unwrap_or_default();
+        let mut root_note = NoteInfo {
+            id: root_event.id.to_hex(),
+            nevent: encode_nevent(&root_event.id, Some(root_event.pubkey), &root_seen_on),
+            author: root_author,
+            content: root_event.content.clone(),
+            created_at: root_event.created_at.as_u64(),
+            reactions: Some(reaction_count),
+            replies: Some(reply_events_vec.len() as u64),
+            reposts: None,
+            zap_sats: None,
+            verified: self.signature_status(&root_event),
+            seen_on: root_seen_on,
+            reposted_by: None,
+            content_warning: None,
+            content_hidden: false,
+            language: note_language(&root_event),
+            signed_by: signed_by(&root_event, &root_author_pk),
+        };
+        // 指定されたノート自体は hide の設定でも除外せず、プレースホルダーに置き換える
+        self.apply_sensitive_policy(&root_event, &mut root_note);
+
+        // リプライをスレッド構造に変換
+        let replies = self.build_thread_replies(&reply_events_vec, &profiles, &seen_on, &event_id, depth);
+
+        Ok(ThreadInfo {
+            root: root_note,
+            replies,
+            total_replies: reply_events_vec.len() as u64,
+            depth,
+        })
+    }
+
+    /// ノートへのリプライを書くための文脈をまとめて取得します。
+    ///
+    /// 対象のノートに加え、スレッドのルートノート・直接の親ノート（NIP-10）と、
+    /// 対象ノートの著者の最近のノートのうち関連するもの（同じスレッド、対象ノートへの返信、
+    /// 共通のハッシュタグ）を新しい順に `related_limit` 件まで返します。
+    pub async fn get_reply_context(&self, note_id: &str, related_limit: usize) -> Result<ReplyContext> {
+        let target = self.resolve_event(note_id, "ノート").await?;
+        let root_id = reply_root(&target);
+        let parent_id = reply_parent(&target);
+
+        // e タグのリレーヒントも問い合わせ先に加える
+        let hints: Vec<String> = target.tags.iter()
+            .map(|tag| tag.as_slice())
+            .filter(|values| values.len() >= 3 && values[0] == "e" && !values[2].is_empty())
+            .map(|values| values[2].clone())
+            .collect();
+        let mut filters = vec![Filter::new()
+            .author(target.pubkey)
+            .kind(Kind::TextNote)
+            .limit(REPLY_CONTEXT_AUTHOR_WINDOW)];
+        let ancestors: Vec<EventId> = root_id.into_iter().chain(parent_id).collect();
+        if !ancestors.is_empty() {
+            filters.push(Filter::new().ids(ancestors.clone()).limit(ancestors.len()));
+        }
+        let events: Vec<Event> = self.fetch_events_with_hints(filters, &hints, Duration::from_secs(10))
+            .await
+            .context("リプライの文脈の取得に失敗しました")?
+            .into_iter()
+            .collect();
+
+        let find = |id: Option<EventId>| id.and_then(|id| events.iter().find(|e| e.id == id).cloned());
+        let root = find(root_id);
+        let parent = find(parent_id).filter(|_| parent_id != root_id);
+
+        let thread_root = root_id.unwrap_or(target.id);
+        let hashtags = event_hashtags(&target);
+        let mut recent: Vec<&Event> = events.iter()
+            .filter(|e| e.pubkey == target.pubkey && e.id != target.id && Some(e.id) != root_id && Some(e.id) != parent_id)
+            .collect();
+        recent.sort_by_key(|e| Reverse(e.created_at));
+        let related: Vec<(Event, &'static str)> = self.filter_muted(recent.into_iter().cloned().collect()).await
+            .into_iter()
+            .filter_map(|e| note_relation(&e, &target, thread_root, &hashtags).map(|relation| (e, relation)))
+            .take(related_limit)
+            .collect();
+
+        let mut all_events = vec![target.clone()];
+        all_events.extend(root.iter().cloned());
+        all_events.extend(parent.iter().cloned());
+        all_events.extend(related.iter().map(|(e, _)| e.clone()));
+        let profiles = self.fetch_profiles(&Self::collect_pubkeys(&all_events)).await;
+        let mut notes = self.events_to_notes(&all_events, &profiles).await;
+        // 対象・ルート・親ノートにはリアクション数・リプライ数を付ける
+        let context_ids: Vec<String> = all_events.iter().take(all_events.len() - related.len()).map(|e| e.id.to_hex()).collect();
+        let context_count = notes.iter().take_while(|n| context_ids.contains(&n.id)).count();
+        self.enrich_notes_with_counts(&mut notes[..context_count]).await;
+
+        let mut take = |id: &EventId| notes.iter()
+            .position(|n| n.id == id.to_hex())
+            .map(|i| notes.remove(i));
+        let note = take(&target.id)
+            .ok_or_else(|| anyhow!("ノートが設定ファイルのフィルタで除外されています"))?;
+        let root = root.and_then(|e| take(&e.id));
+        let parent = parent.and_then(|e| take(&e.id));
+        let related = related.iter()
+            .filter_map(|(e, relation)| take(&e.id).map(|note| RelatedNote { note, relation: relation.to_string() }))
+            .collect();
+
+        Ok(ReplyContext {
+            is_reply: is_reply(&target),
+            note,
+            root,
+            parent,
+            related,
+        })
+    }
+
+    /// ノートのリポスト (Kind 6, NIP-18) と引用ノート (Kind 1) を取得します。
+    pub async fn get_note_reposts(&self, note_id: &str, limit: u64) -> Result<NoteRepostsInfo> {
+        let event_id = self.resolve_event_id(note_id).await?;
+
+        // リポスト: e タグで対象ノートを参照する Kind 6
+        let repost_filter = Filter::new()
+            .kind(Kind::Repost)
+            .event(event_id)
+            .limit(limit as usize);
+
+        // 引用: q タグで対象ノートを参照する Kind 1
+        let quote_filter = Filter::new()
+            .kind(Kind::TextNote)
+            .custom_tag(SingleLetterTag::lowercase(Alphabet::Q), vec![event_id.to_hex()])
+            .limit(limit as usize);
+
+        // 旧形式の引用: mention マーカー付き e タグや nevent 参照を含む Kind 1
+        let mention_filter = Filter::new()
+            .kind(Kind::TextNote)
+            .event(event_id)
+            .limit(limit as usize);
+
+        let (reposts_result, quotes_result, mentions_result) = tokio::join!(
+            self.fetch_events(vec![repost_filter], Duration::from_secs(10)),
+            self.fetch_events(vec![quote_filter], Duration::from_secs(10)),
+            self.fetch_events(vec![mention_filter], Duration::from_secs(10))
+        );
+
+        let repost_events: Vec<Event> = reposts_result
+            .context("リポストの取得に失敗しました")?
+            .into_iter()
+            .collect();
+
+        let quotes = quotes_result.context("引用ノートの取得に失敗しました")?;
+        let mentions = mentions_result.context("引用ノート（旧形式）の取得に失敗しました")?;
+
+        let mut seen = std::collections::HashSet::new();
+        let quote_events: Vec<Event> = quotes
+            .into_iter()
+            .chain(mentions.into_iter().filter(|event| is_quote_of(event, &event_id)))
+            .filter(|event| seen.insert(event.id))
+            .collect();
+
+        let mut all_events = repost_events.clone();
+        all_events.extend(quote_events.iter().cloned());
+        let pubkeys = Self::collect_pubkeys(&all_events);
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let seen_on = self.seen_on_relays(&repost_events).await;
+
+        let mut reposts: Vec<RepostInfo> = repost_events.iter().map(|event| {
+            RepostInfo {
+                id: event.id.to_hex(),
+                nevent: encode_nevent(&event.id, Some(event.pubkey), relay_hints(&seen_on, &event.id)),
+                author: profiles
+                    .get(&event.pubkey)
+                    .cloned()
+                    .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey)),
+                created_at: event.created_at.as_u64(),
+            }
+        }).collect();
+        reposts.sort_by_key(|x| Reverse(x.created_at));
+        reposts.truncate(limit as usize);
+
+        let mut quotes = self.events_to_notes(&quote_events, &profiles).await;
+        Self::sort_and_truncate(&mut quotes, limit as usize);
+
+        Ok(NoteRepostsInfo {
+            note_id: event_id.to_hex(),
+            reposts,
+            quotes,
+        })
+    }
+
+    /// ノートのエンゲージメント（リアクション・リプライ・リポスト・引用・Zap）をまとめて取得します。
+    pub async fn get_note_engagement(&self, note_id: &str) -> Result<NoteEngagement> {
+        let event_id = self.resolve_event_id(note_id).await?;
+
+        let reaction_filter = Filter::new().kind(Kind::Reaction).event(event_id);
+        let reply_filter = Filter::new().kind(Kind::TextNote).event(event_id);
+        let repost_filter = Filter::new().kind(Kind::Repost).event(event_id);
+        let quote_filter = Filter::new()
+            .kind(Kind::TextNote)
+            .custom_tag(SingleLetterTag::lowercase(Alphabet::Q), vec![event_id.to_hex()]);
+
+        // Zap は金額の合計が必要なため、常にレシートを取得する
+        let zap_filter = Filter::new()
+            .kind(Kind::ZapReceipt)
+            .event(event_id)
+            .limit(1000);
+
+        // COUNT では e タグのマーカーを区別できないため、リプライは取得して NIP-10 のマーカーで数える
+        let count_replies = async {
+            let replies = match self.fetch_events_deduped(vec![reply_filter.limit(1000)], Duration::from_secs(10)).await {
+                Ok(events) => Some(events.iter().filter(|event| is_reply_to(event, &event_id)).count() as u64),
+                Err(e) => {
+                    warn!("リプライの取得に失敗しました: {}", e);
+                    None
+                }
+            };
+            (replies, "fetch")
+        };
+
+        let (reactions, replies, reposts, quotes, zaps_result) = tokio::join!(
+            self.count_events(reaction_filter, 1000),
+            count_replies,
+            self.count_events(repost_filter, 1000),
+            self.count_events(quote_filter, 1000),
+            self.fetch_events_deduped(vec![zap_filter], Duration::from_secs(10))
+        );
+
+        // Zap レシートを取得できなかった場合、Zap の件数・金額は不明とする
+        let (zap_count, zap_total_sats, invalid_zap_count) = match zaps_result {
+            Ok(dedup) => {
+                let mut zap_events = dedup.into_events();
+                let invalid = self.retain_valid_zap_receipts(&mut zap_events).await;
+                let total_sats = zap_events.iter()
+                    .map(|event| {
+                        crate::bolt11::amount_sats(&extract_tag_value(event, "bolt11").unwrap_or_default())
+                    })
+                    .fold(0u64, u64::saturating_add);
+                (Some(zap_events.len() as u64), Some(total_sats), Some(invalid))
+            }
+            Err(e) => {
+                warn!("Zap レシートの取得に失敗しました: {}", e);
+                (None, None, None)
+            }
+        };
+
+        let count_method = combine_count_methods(&[reactions.1, replies.1, reposts.1, quotes.1]);
+
+        Ok(NoteEngagement {
+            note_id: event_id.to_hex(),
+            reactions: reactions.0,
+            replies: replies.0,
+            reposts: reposts.0,
+            quotes: quotes.0,
+            zap_count,
+            zap_total_sats,
+            invalid_zap_count,
+            count_method: count_method.to_string(),
+        })
+    }
+
+    /// リプライイベントからスレッド構造を構築するヘルパー
+    fn build_thread_replies(
+        &self,
+        events: &[Event],
+        profiles: &HashMap<PublicKey, AuthorInfo>,
+        seen_on: &HashMap<EventId, Vec<String>>,
+        parent_id: &EventId,
+        max_depth: u64,
+    ) -> Vec<ThreadReply> {
+        if max_depth == 0 {
+            return vec![];
+        }
+
+        let mut replies: Vec<ThreadReply> = events
+            .iter()
+            .filter(|event| reply_parent(event) == Some(*parent_id))
+            .filter(|event| {
+                self.sensitive_content != SensitiveContentPolicy::Hide || sensitive_reason(event).is_none()
+            })
+            .map(|event| {
+                let author_pk = event_author(event);
+                let author = profiles
+                    .get(&author_pk)
+                    .cloned()
+                    .unwrap_or_else(|| AuthorInfo::from_public_key(&author_pk));
+
+                let child_replies = self.build_thread_replies(
+                    events,
+                    profiles,
+                    seen_on,
+                    &event.id,
+                    max_depth - 1,
+                );
+                let event_seen_on = seen_on.get(&event.id).cloned().unwrap_or_default();
+
+                let mut note = NoteInfo {
+                    id: event.id.to_hex(),
+                    nevent: encode_nevent(&event.id, Some(event.pubkey), &event_seen_on),
+                    author,
+                    content: event.content.clone(),
+                    created_at: event.created_at.as_u64(),
+                    reactions: None,
+                    replies: Some(child_replies.len() as u64),
+                    reposts: None,
+                    zap_sats: None,
+                    verified: self.signature_status(event),
+                    seen_on: event_seen_on,
+                    reposted_by: None,
+                    content_warning: None,
+                    content_hidden: false,
+                    language: note_language(event),
+                    signed_by: signed_by(event, &author_pk),
+                };
+                self.apply_sensitive_policy(event, &mut note);
+                ThreadReply {
+                    note,
+                    replies: child_replies,
+                }
+            })
+            .collect();
+
+        replies.sort_by_key(|a| a.note.created_at);
+        replies
+    }
+
+    /// イベント ID で単一のイベントを取得するヘルパー
+    async fn fetch_event_by_id(&self, event_id: EventId, context: &str) -> Result<Event> {
+        let filter = Filter::new().id(event_id).limit(1);
+        let events = self.fetch_events(vec![filter], Duration::from_secs(5))
+            .await
+            .context(format!("{}の取得に失敗しました", context))?;
+        events
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("{}が見つかりません", context))
+    }
+
+    /// ノートにリアクション (Kind 7, NIP-25) を送信します。
+    ///
+    /// `reaction` に設定ファイルの `reaction-presets` の名前を指定するとプリセットの内容で送信し、
+    /// 省略した場合は `default-reaction`（未設定時は「+」）を使います。
+    /// 戻り値は (公開結果, 送信したリアクション, 使用したプリセット名)。
+    pub async fn react_to_note(&self, note_id: &str, reaction: Option<&str>) -> Result<(PublishResult, String, Option<String>)> {
+        self.require_write_access()?;
+
+        let requested = reaction.map(str::trim).filter(|r| !r.is_empty()).unwrap_or(&self.default_reaction);
+        let preset = self.reaction_presets.get_key_value(requested);
+        let content = preset.map_or(requested, |(_, preset)| preset.content.as_str());
+
+        // 上限の確認と枠の確保を 1 回の書き込みロックで行い、同時に送信しても上限を超えないようにする
+        // （ドライランでは送信しないため、確認だけして枠は確保しない）
+        let reserved = match preset {
+            Some((name, preset)) => {
+                let now = current_unix_timestamp();
+                let mut ledger = self.reaction_ledger.write().await;
+                let sent_at = ledger.entry(name.clone()).or_default();
+                sent_at.retain(|ts| ts + 3_600 > now);
+                check_reaction_rate(name, preset, sent_at, now)?;
+                if self.is_dry_run() {
+                    None
+                } else {
+                    sent_at.push(now);
+                    Some((name, now))
+                }
+            }
+            None => None,
+        };
+
+        let output = match self.send_reaction(note_id, content, preset).await {
+            Ok(output) => output,
+            Err(e) => {
+                // 送信できなかった分の枠を戻す
+                if let Some((name, reserved_at)) = reserved {
+                    let mut ledger = self.reaction_ledger.write().await;
+                    if let Some(sent_at) = ledger.get_mut(name) {
+                        if let Some(pos) = sent_at.iter().rposition(|ts| *ts == reserved_at) {
+                            sent_at.remove(pos);
+                        }
+                    }
+                }
+                return Err(e);
+            }
+        };
+
+        // 再起動しても上限が有効になるよう、送信履歴を保存する（ドライランでは保存しない）
+        if reserved.is_some() && !self.is_dry_run() {
+            let ledger = self.reaction_ledger.read().await;
+            if let Err(e) = crate::storage::save_reaction_log(&ledger) {
+                warn!("リアクションの送信履歴の保存に失敗しました: {}", e);
+            }
+        }
+        Ok((
+            PublishResult::new(output, self.public_key),
+            content.to_string(),
+            preset.map(|(name, _)| name.clone()),
+        ))
+    }
+
+    /// リアクションのイベントを作成して送信する（react_to_note の送信部分）
+    async fn send_reaction(
+        &self,
+        note_id: &str,
+        content: &str,
+        preset: Option<(&String, &crate::config::ReactionPreset)>,
+    ) -> Result<Output<EventId>> {
+        let target_event = self.resolve_event(note_id, "リアクション対象のノート").await?;
+        let event_id = target_event.id;
+
+        // NIP-30: `:shortcode:` 形式のリアクションはカスタム絵文字として emoji タグを付ける
+        // （プリセットに画像 URL があればそれを使い、なければ自分の絵文字から解決する）
+        let emoji = match preset {
+            Some((name, preset)) if preset.emoji_url.is_some() => preset_emoji_tags(name, preset)?,
+            _ => self.custom_emoji_tags(content).await,
+        };
+        if content.len() > 2 && content.starts_with(':') && content.ends_with(':') && emoji.is_empty() {
+            return Err(anyhow!("カスタム絵文字 {} が絵文字リスト・絵文字セットに見つかりません", content));
+        }
+
+        // NIP-25: リアクションイベントを作成
+        let builder = EventBuilder::new(Kind::Reaction, content)
+            .tags(vec![
+                Tag::event(event_id),
+                Tag::public_key(target_event.pubkey),
+            ])
+            .tags(emoji);
+
+        let output = self.send_event_builder(builder).await
+            .context("リアクションの送信に失敗しました")?;
+        info!("リアクションを送信しました。イベント ID: {}", output.id());
+        Ok(output)
+    }
+
+    /// 設定ファイルのリアクションのプリセットと、直近 1 時間の使用回数を取得します。
+    pub async fn get_reaction_presets(&self) -> ReactionPresets {
+        let now = current_unix_timestamp();
+        let ledger = self.reaction_ledger.read().await;
+        let mut presets: Vec<ReactionPresetInfo> = self.reaction_presets.iter()
+            .map(|(name, preset)| ReactionPresetInfo {
+                name: name.clone(),
+                content: preset.content.clone(),
+                emoji_url: preset.emoji_url.clone(),
+                max_per_hour: preset.max_per_hour,
+                sent_last_hour: ledger.get(name)
+                    .map_or(0, |sent_at| sent_at.iter().filter(|ts| *ts + 3_600 > now).count() as u32),
+            })
+            .collect();
+        presets.sort_by(|a, b| a.name.cmp(&b.name));
+        ReactionPresets {
+            default_reaction: self.default_reaction.clone(),
+            presets,
+        }
+    }
+
+    /// 既存のノートに返信を投稿します（NIP-10 対応）。
+    pub async fn reply_to_note(&self, note_id: &str, content: &str) -> Result<PublishResult> {
+        self.require_write_access()?;
+
+        let target_event = self.resolve_event(note_id, "返信対象のノート").await?;
+        let event_id = target_event.id;
+
+        // NIP-10: root と reply のマーカーを設定
+        // 対象ノート自体にルートがある場合はそれを引き継ぐ
+        let mut tags = Vec::new();
+
+        // ルートイベントの検出
+        let root_id = target_event.tags.iter().find_map(|tag| {
+            let values = tag.as_slice();
+            if values.len() >= 4 && values[0] == "e" && values[3] == "root" {
+                EventId::from_hex(&values[1]).ok()
+            } else {
+                None
+            }
+        });
+
+        if let Some(root) = root_id {
+            // 既存スレッドへの返信: root を引き継ぎ、対象ノートを reply とする
+            tags.push(Tag::parse(vec!["e".to_string(), root.to_hex(), String::new(), "root".to_string()]).unwrap());
+            tags.push(Tag::parse(vec!["e".to_string(), event_id.to_hex(), String::new(), "reply".to_string()]).unwrap());
+        } else {
+            // 新規スレッド開始: 対象ノートが root かつ reply
+            tags.push(Tag::parse(vec!["e".to_string(), event_id.to_hex(), String::new(), "root".to_string()]).unwrap());
+            tags.push(Tag::parse(vec!["e".to_string(), event_id.to_hex(), String::new(), "reply".to_string()]).unwrap());
+        }
+
+        // 対象ノートの著者と本文でメンションしたユーザーを p タグで追加
+        let mut recipients = vec![target_event.pubkey];
+        for pk in mentioned_pubkeys(content) {
+            if !recipients.contains(&pk) {
+                recipients.push(pk);
+            }
+        }
+        tags.extend(recipients.iter().map(|pk| Tag::public_key(*pk)));
+        tags.extend(self.custom_emoji_tags(content).await);
+
+        let builder = EventBuilder::text_note(content)
+            .tags(tags);
+
+        // 返信先・メンション先の受信用リレーにも届ける
+        let output = self.send_event_builder_to_inboxes(builder, &recipients, false).await
+            .context("返信の投稿に失敗しました")?;
+
+        let reply_id = *output.id();
+        info!("返信を投稿しました。イベント ID: {}", reply_id);
+        Ok(PublishResult::new(output, self.public_key))
+    }
+
+    /// ユーザーへの通知（メンション・リアクション・Zap・リポスト・新規フォロワー）を取得します。
+    ///
+    /// `types` には `NOTIFICATION_TYPES` のうち取得する種類を指定します（空の場合はすべて）。
+    /// 新規フォロワーは Kind 3 を既知のフォロワー一覧（ローカルに保存）と比較して検出します。
+    pub async fn get_notifications(&self, since: Option<u64>, types: &[String], limit: u64) -> Result<Vec<NotificationInfo>> {
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("通知の取得には認証が必要です。設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))?;
+
+        let wants = |t: &str| types.is_empty() || types.iter().any(|x| x == t);
+
+        // p タグで自分を参照しているイベント（メンション・リアクション・Zap・リポスト）
+        let kinds: Vec<Kind> = [
+            ("mention", Kind::TextNote),
+            ("reaction", Kind::Reaction),
+            ("zap", Kind::ZapReceipt),
+            ("repost", Kind::Repost),
+        ]
+        .into_iter()
+        .filter(|(t, _)| wants(t))
+        .map(|(_, kind)| kind)
+        .collect();
+
+        let filters: Vec<Filter> = kinds
+            .into_iter()
+            .map(|kind| {
+                let mut filter = Filter::new().kind(kind).pubkey(pk).limit(limit as usize);
+                if let Some(since_ts) = since {
+                    filter = filter.since(Timestamp::from(since_ts));
+                }
+                filter
+            })
+            .collect();
+
+        let (events_result, new_followers) = tokio::join!(
+            async {
+                if filters.is_empty() {
+                    return Ok(Vec::new());
+                }
+                self.fetch_events(filters, Duration::from_secs(15))
+                    .await
+                    .map(|events| events.into_iter().collect::<Vec<Event>>())
+            },
+            async {
+                if wants("follow") {
+                    self.fetch_new_followers(pk, since).await
+                } else {
+                    Vec::new()
+                }
+            }
+        );
+
+        let mute_list = self.mute_list().await;
+        let mut events_vec: Vec<Event> = events_result
+            .context("通知の取得に失敗しました")?
+            .into_iter()
+            .filter(|e| e.pubkey != pk) // 自分自身の投稿を除外
+            .filter(|e| !mute_list.is_muted(e))
+            .collect();
+        // 偽装された Zap レシートは通知しない
+        self.retain_valid_zap_receipts(&mut events_vec).await;
+
+        // Zap は送信者（Zap リクエストの pubkey）を通知元とする
+        let (events_vec, sources): (Vec<Event>, Vec<PublicKey>) = events_vec
+            .into_iter()
+            .map(|e| {
+                let source = match e.kind {
+                    Kind::ZapReceipt => zap_receipt_sender(&e).unwrap_or(e.pubkey),
+                    _ => e.pubkey,
+                };
+                (e, source)
+            })
+            .filter(|(_, source)| !mute_list.is_pubkey_muted(source))
+            .unzip();
+        let new_followers: Vec<(PublicKey, u64)> = new_followers
+            .into_iter()
+            .filter(|(follower, _)| !mute_list.is_pubkey_muted(follower))
+            .collect();
+
+        let mut pubkeys = sources.clone();
+        pubkeys.extend(new_followers.iter().map(|(follower, _)| *follower));
+        pubkeys.sort();
+        pubkeys.dedup();
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let seen_on = self.seen_on_relays(&events_vec).await;
+        let author_of = |pk: &PublicKey| {
+            profiles
+                .get(pk)
+                .cloned()
+                .unwrap_or_else(|| AuthorInfo::from_public_key(pk))
+        };
+
+        let mut notifications: Vec<NotificationInfo> = events_vec.iter().zip(&sources).map(|(event, source)| {
+            let notification_type = match event.kind {
+                Kind::Reaction => "reaction",
+                Kind::TextNote => "mention",
+                Kind::ZapReceipt => "zap",
+                Kind::Repost => "repost",
+                _ => "other",
+            }
+            .to_string();
+
+            // リアクション・Zap・リポストの場合、対象ノートの ID を取得
+            let target_note_id = extract_tag_value(event, "e");
+
+            let (content, amount_sats) = if event.kind == Kind::ZapReceipt {
+                let comment = extract_tag_value(event, "description")
+                    .and_then(|desc| Self::parse_zap_request_description(&desc).1);
+                let amount = crate::bolt11::amount_sats(&extract_tag_value(event, "bolt11").unwrap_or_default());
+                (comment.unwrap_or_default(), Some(amount))
+            } else if event.kind == Kind::Repost {
+                // リポストの content は元ノートの JSON のため含めない
+                (String::new(), None)
+            } else {
+                (event.content.clone(), None)
+            };
+
+            NotificationInfo {
+                id: event.id.to_hex(),
+                nevent: encode_nevent(&event.id, Some(event.pubkey), relay_hints(&seen_on, &event.id)),
+                notification_type,
+                author: author_of(source),
+                content,
+                target_note_id,
+                amount_sats,
+                created_at: event.created_at.as_u64(),
+            }
+        }).collect();
+
+        notifications.extend(new_followers.into_iter().map(|(follower, followed_at)| NotificationInfo {
+            id: String::new(),
+            nevent: String::new(),
+            notification_type: "follow".to_string(),
+            author: author_of(&follower),
+            content: String::new(),
+            target_note_id: None,
+            amount_sats: None,
+            created_at: followed_at,
+        }));
+
+        notifications.sort_by_key(|x| Reverse(x.created_at));
+        notifications.truncate(limit as usize);
+
+        Ok(notifications)
+    }
+
+    /// 新規フォロワーを検出します。
+    ///
+    /// 自分を含むコンタクトリスト (Kind 3) を取得し、既知のフォロワー一覧にない著者を
+    /// 初めて確認した日時とともに記録します。戻り値は `since` 以降に一覧に初めて記録された
+    /// フォロワー（公開鍵, 初めて確認した日時）です。初回は既存のフォロワーを記録するだけで、
+    /// 新規フォロワーとしては返しません。
+    async fn fetch_new_followers(&self, pk: PublicKey, since: Option<u64>) -> Vec<(PublicKey, u64)> {
+        let filter = Filter::new()
+            .kind(Kind::ContactList)
+            .pubkey(pk)
+            .limit(5000);
+
+        let events = match self.fetch_events(vec![filter], Duration::from_secs(15)).await {
+            Ok(events) => events,
+            Err(e) => {
+                warn!("フォロワーの取得に失敗しました: {}", e);
+                return Vec::new();
+            }
+        };
+
+        let current: Vec<String> = events
+            .into_iter()
+            .filter(|e| e.pubkey != pk)
+            .map(|e| e.pubkey.to_hex())
+            .collect();
+
+        let account = pk.to_hex();
+        let mut known = crate::storage::load_known_followers(&account);
+        if record_new_followers(&mut known, &current, current_unix_timestamp()) {
+            if let Err(e) = crate::storage::save_known_followers(&account, &known) {
+                warn!("フォロワー一覧の保存に失敗しました: {}", e);
+            }
+        }
+
+        let since = since.unwrap_or(0);
+        current
+            .into_iter()
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .filter_map(|hex| {
+                let followed_at = *known.get(&hex)?;
+                if followed_at == 0 || followed_at < since {
+                    return None;
+                }
+                PublicKey::from_hex(&hex).ok().map(|follower| (follower, followed_at))
+            })
+            .collect()
+    }
+
+    /// 通知の既読カーソル（最後に既読にした日時）を取得します。
+    pub fn notifications_read_at(&self) -> Option<u64> {
+        let pk = self.public_key?;
+        crate::storage::load_read_state(&pk.to_hex()).notifications_read_at
+    }
+
+    /// 通知を既読にします。`until` 未指定時は現在時刻まで既読にします。既読カーソルはローカルに保存されます。
+    pub fn mark_notifications_read(&self, until: Option<u64>) -> Result<u64> {
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("通知の既読管理には認証が必要です。"))?;
+        let at = until.unwrap_or_else(current_unix_timestamp);
+
+        let mut state = crate::storage::load_read_state(&pk.to_hex());
+        state.notifications_read_at = Some(at);
+        crate::storage::save_read_state(&pk.to_hex(), &state)?;
+        Ok(at)
+    }
+
+    // ========================================
+    // Lightning インボイス (LUD-06 / LUD-16)
+    // ========================================
+
+    /// Lightning アドレスまたはユーザーのプロフィールからインボイスを取得します。
+    ///
+    /// `target` には Lightning アドレス、LNURL、またはユーザーの公開鍵を指定します。
+    /// 公開鍵の場合はプロフィールの lud16 / lud06 を使用し、認証済みでエンドポイントが
+    /// Zap に対応していれば Zap リクエスト (Kind 9734) を添付します。NWC は不要です。
+    pub async fn get_lightning_invoice(
+        &self,
+        target: &str,
+        amount_sats: u64,
+        comment: Option<&str>,
+        note_id: Option<&str>,
+        zap: bool,
+    ) -> Result<LightningInvoiceInfo> {
+        if amount_sats == 0 {
+            return Err(anyhow!("金額は 1 sat 以上を指定してください"));
+        }
+        let amount_msat = amount_sats.checked_mul(1000)
+            .ok_or_else(|| anyhow!("金額が大きすぎます: {} sats", amount_sats))?;
+
+        let (address, recipient) = self.resolve_lightning_target(target).await?;
+        let pay_info = crate::lnurl::fetch_pay_info(&address).await?;
+
+        // Zap リクエストの作成（受取人が Nostr ユーザーで、署名可能な場合のみ）
+        let zap_request = match recipient {
+            Some(pk) if zap && pay_info.allows_nostr && self.has_write_access => {
+                // Zap レシートは自分が読むリレーに公開してもらう
+                let relays = self.read_relay_urls().await.into_iter()
+                    .filter_map(|url| Url::parse(url.as_str()).ok());
+                let mut data = ZapRequestData::new(pk, relays)
+                    .amount(amount_msat)
+                    .message(comment.unwrap_or_default());
+                if let Some(id) = note_id {
+                    data = data.event_id(self.resolve_event_id(id).await?);
+                }
+                let event = self.sign_event_builder(EventBuilder::public_zap_request(data)).await
+                    .context("Zap リクエストの署名に失敗しました")?;
+                Some(event.as_json())
+            }
+            _ => None,
+        };
+
+        let bolt11 = crate::lnurl::request_invoice(
+            &pay_info,
+            amount_msat,
+            zap_request.as_deref(),
+            if zap_request.is_some() { None } else { comment },
+        ).await?;
+
+        // LUD-06: インボイスの金額が要求額と一致することを確認
+        let decoded = crate::bolt11::decode(&bolt11)
+            .context("LNURL エンドポイントが返したインボイスをデコードできません")?;
+        if decoded.amount_msat != Some(amount_msat) {
+            return Err(anyhow!(
+                "インボイスの金額 ({} msat) が要求額 ({} msat) と一致しません",
+                decoded.amount_msat.unwrap_or(0), amount_msat
+            ));
+        }
+
+        info!("インボイスを取得しました: {} sats → {}", amount_sats, address);
+
+        Ok(LightningInvoiceInfo {
+            bolt11,
+            amount_sats,
+            payment_hash: decoded.payment_hash.clone(),
+            expires_at: decoded.expires_at(),
+            lightning_address: address,
+            recipient_pubkey: recipient.map(|pk| pk.to_hex()),
+            is_zap: zap_request.is_some(),
+        })
+    }
+
+    /// Lightning アドレスからインボイスを取得し、NWC ウォレットで支払います。
+    ///
+    /// `send_zap` と同じ支払い上限（`payment-limits`）が適用されます。
+    pub async fn pay_lightning_address(
+        &self,
+        target: &str,
+        amount_sats: u64,
+        comment: Option<&str>,
+        note_id: Option<&str>,
+    ) -> Result<LightningPaymentInfo> {
+        let nwc = self.nwc.as_ref().ok_or_else(|| anyhow!(
+            "Lightning 支払いには NWC (Nostr Wallet Connect) の設定が必要です。\
+            設定ファイルに \"nwc-uri\" を追加するか、get_lightning_invoice で取得したインボイスを外部ウォレットで支払ってください。"
+        ))?;
+
+        // 上限の確認から履歴の記録までをロックする（send_zap と同じ）
+        let _payment = self.payment_lock.lock().await;
+        self.check_payment_limits(amount_sats).await?;
+        self.refuse_in_dry_run("Lightning 支払い")?;
+
+        let invoice = self.get_lightning_invoice(target, amount_sats, comment, note_id, true).await?;
+
+        let response = nwc.pay_invoice(PayInvoiceRequest::new(invoice.bolt11.clone())).await
+            .map_err(|e| anyhow!("NWC での支払いに失敗しました: {}", e))?;
+        self.record_payment(crate::storage::PaymentRecord {
+            paid_at: current_unix_timestamp(),
+            amount_sats,
+            kind: "lightning".to_string(),
+            recipient_pubkey: invoice.recipient_pubkey.clone(),
+            lightning_address: Some(invoice.lightning_address.clone()),
+            note_id: note_id.map(String::from),
+            payment_hash: invoice.payment_hash.clone(),
+        }).await;
+
+        info!("Lightning 支払いが完了しました: {} sats → {}", amount_sats, invoice.lightning_address);
+
+        Ok(LightningPaymentInfo {
+            invoice,
+            preimage: response.preimage,
+        })
+    }
+
+    /// NWC ウォレットとの接続を確認します（NIP-47）。
+    ///
+    /// NWC URI のリレーに接続できるかを確かめ、ウォレットサービスの情報イベント (Kind 13194) と
+    /// `get_info` リクエストから、対応メソッドとこの接続に許可されたメソッドを取得します。
+    /// 残高は get_balance が許可されている場合のみ取得します。ウォレットへの問い合わせの
+    /// 失敗はエラーにせず、結果に記録します。
+    pub async fn check_wallet_connection(&self) -> Result<WalletConnectionStatus> {
+        let (Some(uri_str), Some(nwc)) = (self.nwc_uri.as_deref(), self.nwc.as_ref()) else {
+            return Err(anyhow!(
+                "NWC (Nostr Wallet Connect) が設定されていません。設定ファイルに \"nwc-uri\" を追加してください。"
+            ));
+        };
+        let uri = NostrWalletConnectURI::parse(uri_str).context("NWC URI のパースに失敗しました")?;
+
+        // NWC URI のリレーへの到達確認とウォレットサービスの情報イベントの取得
+        let relay = Relay::new(uri.relay_url.clone());
+        let started = std::time::Instant::now();
+        relay.connect(Some(WALLET_CHECK_TIMEOUT)).await;
+        let relay_reachable = relay.is_connected();
+        let relay_latency_ms = relay_reachable.then(|| started.elapsed().as_millis() as u64);
+
+        let mut info_event = None;
+        if relay_reachable {
+            let filter = Filter::new()
+                .kind(Kind::WalletConnectInfo)
+                .author(uri.public_key)
+                .limit(1);
+            match relay.fetch_events(vec![filter], WALLET_CHECK_TIMEOUT, ReqExitPolicy::ExitOnEOSE).await {
+                Ok(events) => info_event = events.into_iter().max_by_key(|e| e.created_at),
+                Err(e) => debug!("ウォレットサービスの情報イベントの取得に失敗: {}", e),
+            }
+        }
+        if let Err(e) = relay.disconnect() {
+            debug!("NWC リレーの切断に失敗: {}", e);
+        }
+        let (supported_methods, encryption, notifications) = info_event
+            .as_ref()
+            .map(parse_wallet_info_event)
+            .unwrap_or_default();
+
+        // get_info でこの接続に許可されたメソッドとノード情報を取得
+        let (info, get_info_error) = if relay_reachable {
+            match tokio::time::timeout(WALLET_CHECK_TIMEOUT, nwc.get_info()).await {
+                Ok(Ok(info)) => (Some(info), None),
+                Ok(Err(e)) => (None, Some(e.to_string())),
+                Err(_) => (None, Some("ウォレットからの応答がタイムアウトしました".to_string())),
+            }
+        } else {
+            (None, Some("NWC リレーに接続できません".to_string()))
+        };
+        let permitted_methods = info.as_ref().map(|i| i.methods.clone()).unwrap_or_default();
+
+        let balance_sats = if permitted_methods.iter().any(|m| m == "get_balance") {
+            match tokio::time::timeout(WALLET_CHECK_TIMEOUT, nwc.get_balance()).await {
+                Ok(Ok(msat)) => Some(msat / 1000),
+                Ok(Err(e)) => {
+                    debug!("残高の取得に失敗: {}", e);
+                    None
+                }
+                Err(_) => None,
+            }
+        } else {
+            None
+        };
+
+        // 許可されたメソッドが分からない場合はウォレットサービスの対応メソッドで判断する
+        let methods = if permitted_methods.is_empty() { &supported_methods } else { &permitted_methods };
+        let can_pay = relay_reachable && methods.iter().any(|m| m == "pay_invoice");
+
+        let now = current_unix_timestamp();
+        let spent_24h_sats: u64 = self.payment_ledger.read().await
+            .iter()
+            .filter(|(ts, _)| ts + 86_400 > now)
+            .map(|(_, sats)| sats)
+            .sum();
+        let budget = WalletBudget {
+            max_per_payment_sats: self.payment_limits.max_per_payment_sats,
+            daily_limit_sats: self.payment_limits.daily_limit_sats,
+            spent_24h_sats,
+            remaining_daily_sats: self.payment_limits.daily_limit_sats.map(|d| d.saturating_sub(spent_24h_sats)),
+        };
+
+        Ok(WalletConnectionStatus {
+            relay_url: uri.relay_url.to_string(),
+            relay_reachable,
+            relay_latency_ms,
+            wallet_pubkey: uri.public_key.to_hex(),
+            supported_methods,
+            encryption,
+            notifications,
+            permitted_methods,
+            alias: info.as_ref().map(|i| i.alias.clone()).filter(|a| !a.is_empty()),
+            network: info.as_ref().map(|i| i.network.clone()).filter(|n| !n.is_empty()),
+            block_height: info.as_ref().map(|i| i.block_height).filter(|h| *h > 0),
+            get_info_error,
+            balance_sats,
+            budget,
+            can_pay,
+        })
+    }
+
+    /// 支払い上限（1 回あたり・直近 24 時間）を超えないか確認するヘルパー
+    pub(crate) async fn check_payment_limits(&self, amount_sats: u64) -> Result<()> {
+        let ledger = self.payment_ledger.read().await;
+        check_payment_budget(&self.payment_limits, &ledger, current_unix_timestamp(), amount_sats)
+    }
+
+    /// 完了した支払いを履歴に記録するヘルパー
+    ///
+    /// 日次上限の判定に使うメモリ上の履歴に加え、支払いレポート用にローカルの支払い履歴にも保存します。
+    pub(crate) async fn record_payment(&self, record: crate::storage::PaymentRecord) {
+        let now = current_unix_timestamp();
+        {
+            let mut ledger = self.payment_ledger.write().await;
+            ledger.retain(|(ts, _)| ts + 86_400 > now);
+            ledger.push((record.paid_at, record.amount_sats));
+        }
+        if let Err(e) = crate::storage::append_payment_record(record) {
+            warn!("支払い履歴の保存に失敗しました: {}", e);
+        }
+    }
+
+    /// インボイス取得先を Lightning アドレスと受取人の公開鍵に解決するヘルパー
+    async fn resolve_lightning_target(&self, target: &str) -> Result<(String, Option<PublicKey>)> {
+        let target = target.trim();
+        if target.contains('@') || target.to_lowercase().starts_with("lnurl") {
+            return Ok((target.to_string(), None));
+        }
+
+        let pk = Self::parse_public_key(target)
+            .map_err(|_| anyhow!("Lightning アドレス、LNURL、または公開鍵を指定してください: {}", target))?;
+        self.ensure_connected_relays().await?;
+        self.rate_limiter.acquire(&self.pool_relay_urls().await).await;
+        let metadata = self.client.fetch_metadata(pk, Duration::from_secs(10)).await
+            .context("受取人のプロフィールの取得に失敗しました")?;
+
+        let address = metadata.lud16
+            .filter(|a| !a.is_empty())
+            .or(metadata.lud06.filter(|a| !a.is_empty()))
+            .ok_or_else(|| anyhow!("このユーザーは Lightning アドレス (lud16 / lud06) を設定していません"))?;
+
+        Ok((address, Some(pk)))
+    }
+
+    // ========================================
+    // Phase 4: ダイレクトメッセージ (NIP-04)
+    // ========================================
+
+    /// 暗号化されたダイレクトメッセージを送信します（NIP-04）。
+    ///
+    /// NIP-26 の委任署名では送信できません（暗号化の鍵が委任先の鍵になり、委任者として送れないため）。
+    pub async fn send_dm(&self, recipient: &str, content: &str) -> Result<PublishResult> {
+        self.require_write_access()?;
+        if self.delegation.is_some() {
+            return Err(anyhow!(
+                "NIP-26 の委任署名では DM を送信できません。暗号化に委任先の鍵が使われ、相手からは委任者の DM として復号・返信できないためです。"
+            ));
+        }
+
+        let recipient_pk = Self::parse_public_key(recipient)?;
+
+        // NIP-04: signer を使って暗号化
+        let signer = self.client.signer().await
+            .map_err(|e| anyhow!("署名者の取得に失敗: {}", e))?;
+        let encrypted = signer.nip04_encrypt(&recipient_pk, content).await
+            .map_err(|e| anyhow!("メッセージの暗号化に失敗: {}", e))?;
+
+        // Kind 4 (Encrypted Direct Message) イベントを作成
+        let builder = EventBuilder::new(Kind::EncryptedDirectMessage, encrypted)
+            .tags(vec![Tag::public_key(recipient_pk)]);
+
+        // 受信者の DM リレーにも届ける
+        let output = self.send_event_builder_to_inboxes(builder, &[recipient_pk], true).await
+            .context("ダイレクトメッセージの送信に失敗しました")?;
+
+        let event_id = *output.id();
+        info!("DM を送信しました。イベント ID: {}", event_id);
+        Ok(PublishResult::new(output, self.public_key))
+    }
+
+    /// ダイレクトメッセージの会話を取得します（NIP-04）。
+    pub async fn get_dms(&self, with: Option<&str>, limit: u64) -> Result<Vec<DirectMessageInfo>> {
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("DM の取得には認証が必要です。設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))?;
+
+        // 読み取り専用（publickey のみ）の場合は復号せずメタデータだけを返す
+        let signer = self.client.signer().await.ok();
+
+        // 相手の公開鍵（指定されている場合）
+        let peer_pk = if let Some(with_str) = with {
+            Some(Self::parse_public_key(with_str)?)
+        } else {
+            None
+        };
+
+        let events_vec = self.fetch_dm_events(pk, peer_pk, limit).await?;
+
+        let pubkeys = Self::collect_pubkeys(&events_vec);
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let seen_on = self.seen_on_relays(&events_vec).await;
+
+        let mut messages = Vec::new();
+
+        for event in &events_vec {
+            let is_sent = event.pubkey == pk;
+            let Some(peer) = dm_peer(event, &pk) else { continue };
+
+            // NIP-04 復号
+            let (content, decrypted) = match &signer {
+                Some(signer) => match signer.nip04_decrypt(&peer, &event.content).await {
+                    Ok(text) => (text, true),
+                    Err(e) => {
+                        debug!("DM 復号に失敗（スキップ）: {}", e);
+                        continue;
+                    }
+                },
+                None => (UNDECRYPTED_DM_CONTENT.to_string(), false),
+            };
+
+            let author = profiles
+                .get(&event.pubkey)
+                .cloned()
+                .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey));
+
+            messages.push(DirectMessageInfo {
+                id: event.id.to_hex(),
+                nevent: encode_nevent(&event.id, Some(event.pubkey), relay_hints(&seen_on, &event.id)),
+                author,
+                content,
+                decrypted,
+                direction: if is_sent { "sent".to_string() } else { "received".to_string() },
+                peer_pubkey: peer.to_hex(),
+                created_at: event.created_at.as_u64(),
+            });
+        }
+
+        messages.sort_by_key(|x| Reverse(x.created_at));
+        messages.truncate(limit as usize);
+
+        Ok(messages)
+    }
+
+    /// DM イベント (Kind 4) を送受信の両方向から取得するヘルパー
+    async fn fetch_dm_events(&self, pk: PublicKey, peer: Option<PublicKey>, limit: u64) -> Result<Vec<Event>> {
+        // 受信 DM: 自分宛の Kind 4 イベント
+        let mut received_filter = Filter::new()
+            .kind(Kind::EncryptedDirectMessage)
+            .pubkey(pk)
+            .limit(limit as usize);
+
+        if let Some(peer) = peer {
+            received_filter = received_filter.author(peer);
+        }
+
+        // 送信 DM: 自分が送った Kind 4 イベント
+        let mut sent_filter = Filter::new()
+            .kind(Kind::EncryptedDirectMessage)
+            .author(pk)
+            .limit(limit as usize);
+
+        if let Some(peer) = peer {
+            sent_filter = sent_filter.pubkey(peer);
+        }
+
+        let events = self.fetch_events(vec![received_filter, sent_filter], Duration::from_secs(15))
+            .await
+            .context("DM の取得に失敗しました")?;
+
+        Ok(events.into_iter().collect())
+    }
+
+    /// DM を会話相手ごとにまとめ、最新メッセージのプレビューと未読数を返します。
+    ///
+    /// 未読数はローカルに保存された既読カーソル（`mark_dms_read`）より新しい受信メッセージの数です。
+    /// 復号は各会話の最新メッセージのみに行います。
+    pub async fn get_dm_conversations(&self, limit: u64) -> Result<Vec<DmConversation>> {
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("DM の取得には認証が必要です。設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))?;
+
+        let signer = self.client.signer().await.ok();
+
+        let events_vec = self.fetch_dm_events(pk, None, DM_CONVERSATION_FETCH_LIMIT).await?;
+        let read_state = crate::storage::load_read_state(&pk.to_hex());
+
+        // 会話相手ごとに集計
+        let mut threads: HashMap<PublicKey, (Event, u64, u64)> = HashMap::new();
+        for event in events_vec {
+            let Some(peer) = dm_peer(&event, &pk) else { continue };
+            let is_unread = event.pubkey != pk
+                && event.created_at.as_u64() > read_state.dm_cursor(&peer.to_hex());
+
+            let entry = threads.entry(peer).or_insert_with(|| (event.clone(), 0, 0));
+            entry.1 += 1;
+            if is_unread {
+                entry.2 += 1;
+            }
+            if event.created_at > entry.0.created_at {
+                entry.0 = event;
+            }
+        }
+
+        let mut sorted: Vec<(PublicKey, (Event, u64, u64))> = threads.into_iter().collect();
+        sorted.sort_by_key(|(_, (last, _, _))| Reverse(last.created_at));
+        sorted.truncate(limit as usize);
+
+        let peers: Vec<PublicKey> = sorted.iter().map(|(peer, _)| *peer).collect();
+        let profiles = self.fetch_profiles(&peers).await;
+
+        let mut conversations = Vec::with_capacity(sorted.len());
+        for (peer, (last, message_count, unread_count)) in sorted {
+            let preview = match &signer {
+                Some(signer) => match signer.nip04_decrypt(&peer, &last.content).await {
+                    Ok(text) => truncate_preview(&text, DM_PREVIEW_CHARS),
+                    Err(e) => {
+                        debug!("DM 復号に失敗: {}", e);
+                        "（復号できないメッセージ）".to_string()
+                    }
+                },
+                None => UNDECRYPTED_DM_CONTENT.to_string(),
+            };
+
+            conversations.push(DmConversation {
+                peer: profiles
+                    .get(&peer)
+                    .cloned()
+                    .unwrap_or_else(|| AuthorInfo::from_public_key(&peer)),
+                last_message: preview,
+                last_direction: if last.pubkey == pk { "sent" } else { "received" }.to_string(),
+                last_message_at: last.created_at.as_u64(),
+                message_count,
+                unread_count,
+            });
+        }
+
+        Ok(conversations)
+    }
+
+    /// DM を既読にします（`peer` 未指定時はすべての会話）。既読カーソルはローカルに保存されます。
+    pub async fn mark_dms_read(&self, peer: Option<&str>) -> Result<()> {
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("DM の既読管理には認証が必要です。"))?;
+        let peer_hex = peer.map(Self::parse_public_key).transpose()?.map(|p| p.to_hex());
+
+        let mut state = crate::storage::load_read_state(&pk.to_hex());
+        state.mark_dms_read(peer_hex.as_deref(), current_unix_timestamp());
+        crate::storage::save_read_state(&pk.to_hex(), &state)
+    }
+
+    // ========================================
+    // Phase 4: リレーリスト (NIP-65)
+    // ========================================
+
+    /// ユーザーのリレーリスト (Kind 10002, NIP-65) を取得します。
+    pub async fn get_relay_list(&self, pubkey_str: &str) -> Result<RelayListInfo> {
+        let public_key = Self::parse_public_key(pubkey_str)?;
+
+        let filter = Filter::new()
+            .author(public_key)
+            .kind(Kind::RelayList)
+            .limit(1);
+
+        let events = self.fetch_events(vec![filter], Duration::from_secs(10))
+            .await
+            .context("リレーリストの取得に失敗しました")?;
+
+        let event = events
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("{} のリレーリストが見つかりません", pubkey_str))?;
+
+        let relays: Vec<RelayListEntry> = nip65::extract_relay_list(&event)
+            .map(|(url, metadata)| {
+                let (read, write) = match metadata {
+                    Some(RelayMetadata::Read) => (true, false),
+                    Some(RelayMetadata::Write) => (false, true),
+                    None => (true, true), // メタデータなし = 両方
+                };
+                RelayListEntry {
+                    url: url.to_string(),
+                    read,
+                    write,
+                }
+            })
+            .collect();
+
+        Ok(RelayListInfo {
+            pubkey: public_key.to_hex(),
+            npub: public_key.to_bech32().unwrap_or_default(),
+            relays,
+        })
+    }
+
+    /// リレーごとに接続時間・標準フィルタでの取得時間・取得件数を計測し、速い順に並べて返します。
+    ///
+    /// `relays` が空の場合はリレープールのリレーを計測します。各リレーには専用の接続を張るため、
+    /// リレープールの接続には影響しません。NIP-11 の情報ドキュメントから NIP-50（検索）と
+    /// NIP-45（COUNT）への対応も確認します。
+    pub async fn benchmark_relays(&self, relays: &[String]) -> Result<Vec<RelayBenchmark>> {
+        let pool: Vec<RelayUrl> = self.client.relays().await.into_keys().collect();
+        let mut urls: Vec<RelayUrl> = if relays.is_empty() {
+            pool.clone()
+        } else {
+            relays.iter()
+                .map(|relay| RelayUrl::parse(relay.trim()).map_err(|e| anyhow!("無効なリレー URL です: {}: {}", relay, e)))
+                .collect::<Result<_>>()?
+        };
+        urls.sort();
+        urls.dedup();
+        if urls.is_empty() {
+            return Err(anyhow!("計測するリレーがありません。relays を指定してください。"));
+        }
+        if urls.len() > MAX_BENCHMARK_RELAYS {
+            return Err(anyhow!("一度に計測できるリレーは {} 件までです（指定: {} 件）", MAX_BENCHMARK_RELAYS, urls.len()));
+        }
+
+        let url_strs: Vec<String> = urls.iter().map(|url| url.to_string()).collect();
+        self.rate_limiter.acquire(&url_strs).await;
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for url in urls {
+            let in_pool = pool.contains(&url);
+            tasks.spawn(benchmark_relay(url, in_pool));
+        }
+        let mut results = Vec::new();
+        while let Some(result) = tasks.join_next().await {
+            if let Ok(benchmark) = result {
+                results.push(benchmark);
+            }
+        }
+
+        results.sort_by_key(|b| b.ranking_key());
+        for (i, benchmark) in results.iter_mut().enumerate() {
+            benchmark.rank = i + 1;
+        }
+        Ok(results)
+    }
+
+    /// NIP-66 のリレーモニターが公開したリレー発見イベント (Kind 30166) から、正常に稼働しているリレーを探します。
+    ///
+    /// `monitors` を指定しない場合は、モニターの告知 (Kind 10166) を公開しているモニターのレポートだけを使います。
+    /// モニターごとの最新のレポートをリレー単位にまとめ、モニターの告知 (Kind 10166) の確認間隔の 2 倍以内に
+    /// レポートしたモニターの割合を `availability` とします。条件に合うリレーを `availability`・レポートした
+    /// モニター数・接続の RTT の順に並べて最大 `limit` 件返し、`add_to_pool` 件までを読み取りリレーとして
+    /// このセッションのリレープールに追加します（設定ファイルは変更しません）。
+    pub async fn discover_relays(&self, query: RelayDiscoveryQuery, limit: usize, add_to_pool: usize) -> Result<RelayDiscovery> {
+        let now = current_unix_timestamp();
+        let monitor_relays = if query.monitor_relays.is_empty() {
+            DEFAULT_MONITOR_RELAYS.iter().map(|url| url.to_string()).collect()
+        } else {
+            query.monitor_relays.clone()
+        };
+
+        // モニターのレポートは専用のリレーに集まっているため、リレープールとは別に接続する
+        self.rate_limiter.acquire(&monitor_relays).await;
+        let monitor_client = Client::default();
+        for url in &monitor_relays {
+            if let Err(e) = monitor_client.add_relay(url).await {
+                warn!("モニターリレー {} の追加に失敗: {}", url, e);
+            }
+        }
+        monitor_client.connect().await;
+
+        let mut filter = Filter::new()
+            .kind(Kind::from(KIND_RELAY_DISCOVERY))
+            .since(Timestamp::from(now.saturating_sub(RELAY_DISCOVERY_LOOKBACK_SECS)))
+            .limit(RELAY_DISCOVERY_FETCH_LIMIT);
+        if !query.monitors.is_empty() {
+            let monitors: Vec<PublicKey> = query.monitors.iter()
+                .map(|pk| Self::parse_public_key(pk))
+                .collect::<Result<_>>()?;
+            filter = filter.authors(monitors);
+        }
+        let reports = monitor_client.fetch_events(vec![filter], Duration::from_secs(15)).await;
+        let reports: Vec<Event> = match reports {
+            Ok(events) => events.into_iter().collect(),
+            Err(e) => {
+                let _ = monitor_client.disconnect().await;
+                return Err(anyhow!("リレーモニターのレポートの取得に失敗しました: {}", e));
+            }
+        };
+
+        let monitors: Vec<PublicKey> = reports.iter()
+            .map(|e| e.pubkey)
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let announcements = if monitors.is_empty() {
+            Vec::new()
+        } else {
+            let filter = Filter::new().kind(Kind::from(KIND_RELAY_MONITOR)).authors(monitors.clone());
+            monitor_client.fetch_events(vec![filter], Duration::from_secs(10)).await
+                .map(|events| events.into_iter().collect())
+                .unwrap_or_default()
+        };
+        let _ = monitor_client.disconnect().await;
+
+        // 信頼するモニターの指定がない場合は、告知 (Kind 10166) を公開しているモニターのレポートだけを使う
+        let (reports, monitors) = if query.monitors.is_empty() {
+            let reports = announced_monitor_reports(reports, &announcements);
+            let monitors = monitors.into_iter().filter(|pk| reports.iter().any(|e| e.pubkey == *pk)).collect();
+            (reports, monitors)
+        } else {
+            (reports, monitors)
+        };
+
+        let frequencies = monitor_frequencies(&announcements);
+        let pool: Vec<RelayUrl> = self.client.relays().await.into_keys().collect();
+        let mut relays: Vec<DiscoveredRelay> = aggregate_relay_reports(&reports, &frequencies, now)
+            .into_iter()
+            .filter(|relay| query.matches(relay))
+            .collect();
+        let matched = relays.len();
+        relays.truncate(limit);
+        for relay in &mut relays {
+            relay.in_pool = RelayUrl::parse(&relay.url).is_ok_and(|url| pool.contains(&url));
+        }
+
+        let mut added_to_pool = Vec::new();
+        for relay in relays.iter_mut().filter(|r| !r.in_pool).take(add_to_pool) {
+            match self.client.add_read_relay(relay.url.as_str()).await {
+                Ok(_) => {
+                    if let Err(e) = self.client.connect_relay(relay.url.as_str()).await {
+                        warn!("リレー {} への接続に失敗: {}", relay.url, e);
+                        continue;
+                    }
+                    relay.in_pool = true;
+                    added_to_pool.push(relay.url.clone());
+                }
+                Err(e) => warn!("リレー {} の追加に失敗: {}", relay.url, e),
+            }
+        }
+        if !added_to_pool.is_empty() {
+            info!("リレープールに {} 件のリレーを追加しました: {:?}", added_to_pool.len(), added_to_pool);
+        }
+
+        Ok(RelayDiscovery {
+            monitors: monitors.len() as u64,
+            reports: reports.len() as u64,
+            matched: matched as u64,
+            relays,
+            added_to_pool,
+        })
+    }
+
+    // ========================================
+    // アカウントの移行
+    // ========================================
+
+    /// 現在のアカウントから新しい公開鍵への移行を行います。
+    ///
+    /// 移行先は `new_pubkey`（npub / nprofile / hex）または `new_secret_key_file`（nsec / hex の秘密鍵を
+    /// 書いたファイル）で指定します。秘密鍵をツールの引数として渡さないよう、ファイルから読み込みます。
+    ///
+    /// 1. `new_secret_key_file` がある場合、プロフィール・コンタクトリスト・リレーリスト・NIP-51 リストを新しい鍵で署名し直して公開
+    ///    （非公開項目は復号して新しい鍵宛てに NIP-44 で暗号化し直す）
+    /// 2. 現在のアカウントのプロフィールの about の先頭に移行先を追記
+    /// 3. 移行先を案内する最後のノートを投稿
+    /// 4. `notify_followers` が 1 以上の場合、その人数までのフォロワーに DM で移行を通知
+    ///
+    /// 各手順の失敗は `errors` に記録し、残りの手順を続けます。
+    pub async fn migrate_account(
+        &self,
+        new_pubkey: Option<&str>,
+        new_secret_key_file: Option<&std::path::Path>,
+        message: Option<&str>,
+        notify_followers: usize,
+    ) -> Result<AccountMigration> {
+        self.require_write_access()?;
+        let pk = self.public_key.ok_or_else(|| anyhow!("公開鍵が設定されていません"))?;
+
+        let new_keys = new_secret_key_file
+            .map(|path| {
+                let content = std::fs::read_to_string(path)
+                    .with_context(|| format!("秘密鍵ファイルの読み込みに失敗しました: {}", path.display()))?;
+                Self::parse_secret_key(content.trim())
+                    .with_context(|| format!("秘密鍵ファイルの内容が nsec / hex の秘密鍵ではありません: {}", path.display()))
+            })
+            .transpose()?;
+        let new_public_key = match (new_pubkey, &new_keys) {
+            (Some(new_pubkey), keys) => {
+                let new_public_key = Self::parse_public_key(new_pubkey)?;
+                if keys.as_ref().is_some_and(|keys| keys.public_key() != new_public_key) {
+                    return Err(anyhow!("new_secret_key_file の秘密鍵が new_pubkey のものではありません"));
+                }
+                new_public_key
+            }
+            (None, Some(keys)) => keys.public_key(),
+            (None, None) => return Err(anyhow!("new_pubkey または new_secret_key_file を指定してください")),
+        };
+        if pk == new_public_key {
+            return Err(anyhow!("移行先が現在のアカウントと同じです"));
+        }
+        let new_npub = new_public_key.to_bech32()?;
+
+        let filter = Filter::new()
+            .author(pk)
+            .kinds(std::iter::once(Kind::Metadata).chain(MIGRATED_LIST_KINDS.iter().map(|k| Kind::from(*k))))
+            .limit(200);
+        let events = self.fetch_events(vec![filter], Duration::from_secs(10))
+            .await
+            .context("移行するイベントの取得に失敗しました")?;
+        let latest = latest_replaceable_events(events.into_iter().collect());
+
+        let mut errors = Vec::new();
+
+        // 1. 新しい鍵へのコピー
+        let mut copied = Vec::new();
+        if let Some(new_keys) = &new_keys {
+            for event in &latest {
+                let identifier = event.tags.identifier().map(str::to_string);
+                let (builder, private_items_dropped) = self.migrated_event_builder(pk, event, new_keys).await;
+                let result = match builder.sign_with_keys(new_keys) {
+                    Ok(signed) => self.send_event(signed).await.map(|output| PublishResult::new(output, Some(new_public_key))),
+                    Err(e) => Err(anyhow!("署名に失敗: {}", e)),
+                };
+                match result {
+                    Ok(published) => copied.push(MigratedEvent {
+                        kind: event.kind.as_u16(),
+                        identifier,
+                        published: Some(published),
+                        private_items_dropped,
+                    }),
+                    Err(e) => {
+                        errors.push(format!("Kind {} のコピー: {}", event.kind.as_u16(), e));
+                        copied.push(MigratedEvent {
+                            kind: event.kind.as_u16(),
+                            identifier,
+                            published: None,
+                            private_items_dropped,
+                        });
+                    }
+                }
+            }
+        }
+
+        // 2. 現在のプロフィールに移行先を追記
+        let notice = format!("このアカウントは nostr:{} に移行しました。", new_npub);
+        let mut metadata = latest.iter()
+            .find(|event| event.kind == Kind::Metadata)
+            .and_then(|event| Metadata::from_json(&event.content).ok())
+            .unwrap_or_default();
+        metadata.about = Some(match metadata.about.take().filter(|about| !about.trim().is_empty()) {
+            Some(about) if about.contains(&new_npub) => about,
+            Some(about) => format!("{}\n\n{}", notice, about),
+            None => notice.clone(),
+        });
+        let profile_update = match self.send_event_builder(EventBuilder::metadata(&metadata)).await {
+            Ok(output) => Some(PublishResult::new(output, Some(pk))),
+            Err(e) => {
+                errors.push(format!("プロフィールの更新: {}", e));
+                None
+            }
+        };
+
+        // 3. 移行を案内する最後のノート
+        let content = match message {
+            Some(message) if message.contains(&new_npub) => message.to_string(),
+            Some(message) => format!("{}\n\nnostr:{}", message, new_npub),
+            None => format!("{}今後はこちらをフォローしてください。", notice),
+        };
+        let final_note = match self.post_note(&content).await {
+            Ok(result) => Some(result),
+            Err(e) => {
+                errors.push(format!("移行のお知らせの投稿: {}", e));
+                None
+            }
+        };
+
+        // 4. フォロワーへの DM 通知
+        let mut notified = Vec::new();
+        if notify_followers > 0 {
+            let filter = Filter::new()
+                .kind(Kind::ContactList)
+                .pubkey(pk)
+                .limit(notify_followers * 2);
+            match self.fetch_events(vec![filter], Duration::from_secs(10)).await {
+                Ok(events) => {
+                    let mut followers: Vec<PublicKey> = Vec::new();
+                    for event in events.into_iter() {
+                        if event.pubkey != new_public_key && !followers.contains(&event.pubkey) {
+                            followers.push(event.pubkey);
+                        }
+                    }
+                    for follower in followers.into_iter().take(notify_followers) {
+                        match self.send_dm(&follower.to_hex(), &content).await {
+                            Ok(_) => notified.push(follower.to_bech32().unwrap_or_default()),
+                            Err(e) => errors.push(format!("{} への DM: {}", follower.to_bech32().unwrap_or_default(), e)),
+                        }
+                    }
+                }
+                Err(e) => errors.push(format!("フォロワーの取得: {}", e)),
+            }
+        }
+
+        Ok(AccountMigration {
+            new_npub,
+            copied,
+            profile_update,
+            final_note,
+            notified,
+            errors,
+        })
+    }
+
+    /// 移行元のイベントを新しい鍵で公開し直すためのビルダーを作成するヘルパー
+    ///
+    /// NIP-51 リストの非公開項目は移行元の鍵で復号し、新しい鍵宛てに NIP-44 で暗号化し直します。
+    /// 復号・暗号化できなかった場合は非公開項目を含めず、戻り値の bool を true にします。
+    async fn migrated_event_builder(&self, pk: PublicKey, event: &Event, new_keys: &Keys) -> (EventBuilder, bool) {
+        let tags: Vec<Tag> = event.tags.iter().cloned().collect();
+        // Kind 0 は JSON、Kind 3 は旧形式のリレー一覧（JSON）をそのまま引き継ぐ
+        if event.kind == Kind::Metadata || event.kind == Kind::ContactList || event.content.is_empty() {
+            return (EventBuilder::new(event.kind, event.content.clone()).tags(tags), false);
+        }
+
+        let private_tags = self.decrypt_private_list_tags(pk, &event.content).await;
+        let content = if private_tags.is_empty() {
+            None
+        } else {
+            serde_json::to_string(&private_tags).ok().and_then(|json| {
+                nip44::encrypt(new_keys.secret_key(), &new_keys.public_key(), json, nip44::Version::V2).ok()
+            })
+        };
+        let dropped = content.is_none();
+        (EventBuilder::new(event.kind, content.unwrap_or_default()).tags(tags), dropped)
+    }
+
+    // ========================================
+    // NIP-38: ユーザーステータス
+    // ========================================
+
+    /// ユーザーステータス (Kind 30315, NIP-38) を設定します。
+    ///
+    /// `status_type` は "general" または "music"。空の `content` はステータスのクリアになります。
+    /// `expires_in` を指定すると、その秒数後に失効する expiration タグを付与します。
+    pub async fn set_user_status(
+        &self,
+        status_type: &str,
+        content: &str,
+        url: Option<&str>,
+        expires_in: Option<u64>,
+    ) -> Result<PublishResult> {
+        self.require_write_access()?;
+
+        if status_type != "general" && status_type != "music" {
+            return Err(anyhow!("無効なステータス種別です: {}（general または music）", status_type));
+        }
+
+        let mut tags = vec![Tag::identifier(status_type.to_string())];
+
+        if let Some(link) = url {
+            tags.push(Tag::custom(TagKind::custom("r".to_string()), vec![link.to_string()]));
+        }
+
+        if let Some(secs) = expires_in {
+            tags.push(Tag::expiration(Timestamp::from(current_unix_timestamp().saturating_add(secs))));
+        }
+
+        let builder = EventBuilder::new(Kind::from(30315), content).tags(tags);
+
+        let output = self.send_event_builder(builder).await
+            .context("ステータスの設定に失敗しました")?;
+
+        let event_id = *output.id();
+        info!("ステータスを設定しました ({}): {}", status_type, event_id);
+        Ok(PublishResult::new(output, self.public_key))
+    }
+
+    /// 複数ユーザーの有効なステータス (Kind 30315, NIP-38) を取得します。
+    ///
+    /// 失効済み・空のステータスは除外し、ユーザーごとに種別ごとの最新のものを返します。
+    pub async fn get_user_statuses(&self, pubkeys: &[PublicKey]) -> Result<HashMap<PublicKey, Vec<UserStatusInfo>>> {
+        if pubkeys.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let filter = Filter::new()
+            .authors(pubkeys.to_vec())
+            .kind(Kind::from(30315))
+            .identifiers(vec!["general", "music"]);
+
+        let events = self.fetch_events(vec![filter], Duration::from_secs(10))
+            .await
+            .context("ステータスの取得に失敗しました")?;
+
+        // (著者, 種別) ごとに最新のイベントのみ採用
+        let mut latest: HashMap<(PublicKey, String), Event> = HashMap::new();
+        for event in events {
+            let status_type = extract_tag_value(&event, "d").unwrap_or_else(|| "general".to_string());
+            let key = (event.pubkey, status_type);
+            if latest.get(&key).is_none_or(|e| e.created_at < event.created_at) {
+                latest.insert(key, event);
+            }
+        }
+
+        let mut statuses: HashMap<PublicKey, Vec<UserStatusInfo>> = HashMap::new();
+        for ((pk, status_type), event) in latest {
+            if event.is_expired() || event.content.is_empty() {
+                continue;
+            }
+            statuses.entry(pk).or_default().push(UserStatusInfo {
+                status_type,
+                content: event.content.clone(),
+                url: extract_tag_value(&event, "r"),
+                expiration: event.tags.expiration().map(|t| t.as_u64()),
+                created_at: event.created_at.as_u64(),
+            });
+        }
+
+        for list in statuses.values_mut() {
+            list.sort_by(|a, b| a.status_type.cmp(&b.status_type));
+        }
+
+        Ok(statuses)
+    }
+
+    /// 指定ユーザー（npub / hex）のステータスを取得します（NIP-38）。
+    pub async fn get_user_status(&self, pubkey_strs: &[String]) -> Result<Vec<(PublicKey, Vec<UserStatusInfo>)>> {
+        let pubkeys = pubkey_strs.iter()
+            .map(|pk_str| Self::parse_public_key(pk_str))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut statuses = self.get_user_statuses(&pubkeys).await?;
+
+        Ok(pubkeys.into_iter()
+            .map(|pk| {
+                let list = statuses.remove(&pk).unwrap_or_default();
+                (pk, list)
+            })
+            .collect())
+    }
+
+    // ========================================
+    // NIP-30 / NIP-51: カスタム絵文字
+    // ========================================
+
+    /// 絵文字リスト (Kind 10030) と絵文字セット (Kind 30030) を取得します（`pubkey` 省略時は自分）。
+    pub async fn get_emoji_sets(&self, pubkey_str: Option<&str>) -> Result<EmojiCollection> {
+        let pubkey = match pubkey_str {
+            Some(pk_str) => Self::parse_public_key(pk_str)?,
+            None => self.public_key.ok_or_else(|| anyhow!(
+                "公開鍵が必要です。pubkey パラメータを指定するか、認証してください。"
+            ))?,
+        };
+        self.fetch_emoji_collection(pubkey).await
+    }
+
+    /// 絵文字リストと、作成した絵文字セット・絵文字リストの a タグで参照している他のユーザーの絵文字セットを取得するヘルパー
+    async fn fetch_emoji_collection(&self, pubkey: PublicKey) -> Result<EmojiCollection> {
+        let filters = vec![
+            Filter::new().author(pubkey).kind(Kind::from(KIND_EMOJI_LIST)).limit(1),
+            Filter::new().author(pubkey).kind(Kind::from(KIND_EMOJI_SET)),
+        ];
+        let events: Vec<Event> = self.fetch_events(filters, Duration::from_secs(10))
+            .await
+            .context("絵文字セットの取得に失敗しました")?
+            .into_iter()
+            .collect();
+
+        let list = events.iter()
+            .filter(|e| e.kind == Kind::from(KIND_EMOJI_LIST))
+            .max_by_key(|e| e.created_at);
+        let referenced: Vec<Coordinate> = list.map(emoji_set_references).unwrap_or_default();
+        let mut sets = latest_replaceable_events(
+            events.iter().filter(|e| e.kind == Kind::from(KIND_EMOJI_SET)).cloned().collect(),
+        );
+
+        let others: Vec<Filter> = referenced.iter()
+            .filter(|c| c.public_key != pubkey)
+            .map(|c| Filter::from(c).limit(1))
+            .collect();
+        if !others.is_empty() {
+            match self.fetch_events(others, Duration::from_secs(10)).await {
+                Ok(events) => {
+                    for coordinate in referenced.iter().filter(|c| c.public_key != pubkey) {
+                        let latest = events.iter()
+                            .filter(|e| e.pubkey == coordinate.public_key && e.tags.identifier() == Some(coordinate.identifier.as_str()))
+                            .max_by_key(|e| e.created_at);
+                        sets.extend(latest.cloned());
+                    }
+                }
+                Err(e) => warn!("参照している絵文字セットの取得に失敗: {}", e),
+            }
+        }
+
+        let authors: Vec<PublicKey> = sets.iter().map(|e| e.pubkey).collect::<std::collections::BTreeSet<_>>().into_iter().collect();
+        let profiles = self.fetch_profiles(&authors).await;
+        let mut sets: Vec<EmojiSetInfo> = sets.iter()
+            .map(|event| {
+                let in_emoji_list = referenced.iter().any(|c| {
+                    c.public_key == event.pubkey && event.tags.identifier() == Some(c.identifier.as_str())
+                });
+                Self::event_to_emoji_set(event, &profiles, in_emoji_list)
+            })
+            .collect();
+        // 絵文字リストで参照しているセットを先に並べる
+        sets.sort_by_key(|set| !set.in_emoji_list);
+
+        Ok(EmojiCollection {
+            emojis: list.map(parse_emoji_tags).unwrap_or_default(),
+            sets,
+        })
+    }
+
+    /// 自分の絵文字リスト・絵文字セットのショートコード → 画像 URL（未認証・取得失敗時は空）
+    async fn own_emoji_map(&self) -> HashMap<String, String> {
+        let Some(pk) = self.public_key else {
+            return HashMap::new();
+        };
+        match self.fetch_emoji_collection(pk).await {
+            Ok(collection) => collection.shortcode_map(),
+            Err(e) => {
+                warn!("カスタム絵文字の取得に失敗: {}", e);
+                HashMap::new()
+            }
+        }
+    }
+
+    /// 本文中の `:shortcode:` を自分の絵文字から解決し、emoji タグ（NIP-30）を返します。
+    ///
+    /// ショートコードを含まない場合は絵文字を取得しません。
+    async fn custom_emoji_tags(&self, content: &str) -> Vec<Tag> {
+        if emoji_shortcodes(content).is_empty() {
+            return Vec::new();
+        }
+        emoji_tags(content, &self.own_emoji_map().await)
+    }
+
+    /// 自分の絵文字セットに絵文字を追加します（同じショートコードは URL を置き換え、セットがなければ作成）。
+    ///
+    /// 新しく作成したセットは、投稿時に使えるよう絵文字リスト (Kind 10030) の a タグにも追加します。
+    /// 戻り値は (更新後のセット, 公開結果, セットを新規作成したか)。
+    pub async fn add_emoji(&self, identifier: &str, shortcode: &str, url: &str, title: Option<&str>) -> Result<(EmojiSetInfo, PublishResult, bool)> {
+        self.require_write_access()?;
+        let pk = self.public_key.ok_or_else(|| anyhow!("公開鍵が設定されていません"))?;
+
+        let identifier = identifier.trim();
+        if identifier.is_empty() {
+            return Err(anyhow!("絵文字セットの識別子を指定してください"));
+        }
+        let shortcode = shortcode.trim().trim_matches(':');
+        if !is_valid_shortcode(shortcode) {
+            return Err(anyhow!("ショートコードは英数字・_・- のみで指定してください: {}", shortcode));
+        }
+        let url = url.trim();
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            return Err(anyhow!("絵文字の画像 URL は http(s):// で始まる必要があります: {}", url));
+        }
+
+        let existing = self.fetch_latest_addressable(pk, Kind::from(KIND_EMOJI_SET), identifier, "絵文字セット").await?;
+        let created = existing.is_none();
+        let mut emojis = existing.as_ref().map(parse_emoji_tags).unwrap_or_default();
+        match emojis.iter_mut().find(|e| e.shortcode == shortcode) {
+            Some(emoji) => emoji.url = url.to_string(),
+            None => emojis.push(CustomEmoji { shortcode: shortcode.to_string(), url: url.to_string() }),
+        }
+
+        let (set, published) = self.publish_emoji_set(pk, identifier, existing.as_ref(), title, &emojis).await?;
+        if created {
+            if let Err(e) = self.add_emoji_set_to_list(pk, identifier).await {
+                warn!("絵文字リストへの絵文字セットの追加に失敗: {}", e);
+            }
+        }
+        Ok((set, published, created))
+    }
+
+    /// 自分の絵文字セットから絵文字を削除します。
+    pub async fn remove_emoji(&self, identifier: &str, shortcode: &str) -> Result<(EmojiSetInfo, PublishResult)> {
+        self.require_write_access()?;
+        let pk = self.public_key.ok_or_else(|| anyhow!("公開鍵が設定されていません"))?;
+
+        let identifier = identifier.trim();
+        let shortcode = shortcode.trim().trim_matches(':');
+        let existing = self.fetch_latest_addressable(pk, Kind::from(KIND_EMOJI_SET), identifier, "絵文字セット").await?
+            .ok_or_else(|| anyhow!("絵文字セット「{}」が見つかりません", identifier))?;
+        let mut emojis = parse_emoji_tags(&existing);
+        let before = emojis.len();
+        emojis.retain(|e| e.shortcode != shortcode);
+        if emojis.len() == before {
+            return Err(anyhow!("絵文字セット「{}」に :{}: はありません", identifier, shortcode));
+        }
+
+        self.publish_emoji_set(pk, identifier, Some(&existing), None, &emojis).await
+    }
+
+    /// 絵文字セットを公開するヘルパー（既存のセットの emoji タグ以外のタグと content は引き継ぐ）
+    async fn publish_emoji_set(
+        &self,
+        pk: PublicKey,
+        identifier: &str,
+        existing: Option<&Event>,
+        title: Option<&str>,
+        emojis: &[CustomEmoji],
+    ) -> Result<(EmojiSetInfo, PublishResult)> {
+        let title = title.map(str::trim).filter(|t| !t.is_empty()).map(str::to_string)
+            .or_else(|| existing.and_then(|e| extract_tag_value(e, "title")));
+
+        let mut tags = vec![Tag::identifier(identifier)];
+        if let Some(title) = &title {
+            tags.push(Tag::custom(TagKind::Title, vec![title.clone()]));
+        }
+        if let Some(existing) = existing {
+            tags.extend(existing.tags.iter()
+                .filter(|tag| !matches!(tag.as_slice().first().map(String::as_str), Some("d" | "title" | "emoji")))
+                .cloned());
+        }
+        tags.extend(emojis.iter().map(CustomEmoji::to_tag));
+
+        let content = existing.map(|e| e.content.clone()).unwrap_or_default();
+        let builder = EventBuilder::new(Kind::from(KIND_EMOJI_SET), content).tags(tags);
+        let output = self.send_event_builder(builder).await
+            .context("絵文字セットの公開に失敗しました")?;
+        info!("絵文字セット「{}」を公開しました（{} 件）。イベント ID: {}", identifier, emojis.len(), output.id());
+
+        let set = EmojiSetInfo {
+            naddr: Coordinate::new(Kind::from(KIND_EMOJI_SET), pk).identifier(identifier).to_bech32().unwrap_or_default(),
+            identifier: identifier.to_string(),
+            title,
+            author: AuthorInfo::from_public_key(&pk),
+            in_emoji_list: true,
+            emojis: emojis.to_vec(),
+            created_at: current_unix_timestamp(),
+        };
+        Ok((set, PublishResult::new(output, self.public_key)))
+    }
+
+    /// 絵文字リスト (Kind 10030) に自分の絵文字セットへの a タグを追加するヘルパー（登録済みなら何もしない）
+    async fn add_emoji_set_to_list(&self, pk: PublicKey, identifier: &str) -> Result<()> {
+        let filter = Filter::new().author(pk).kind(Kind::from(KIND_EMOJI_LIST)).limit(1);
+        let list = self.fetch_events(vec![filter], Duration::from_secs(10))
+            .await
+            .context("絵文字リストの取得に失敗しました")?
+            .into_iter()
+            .max_by_key(|e| e.created_at);
+
+        let coordinate = Coordinate::new(Kind::from(KIND_EMOJI_SET), pk).identifier(identifier);
+        if list.as_ref().is_some_and(|list| emoji_set_references(list).contains(&coordinate)) {
+            return Ok(());
+        }
+
+        let mut tags: Vec<Tag> = list.as_ref().map(|l| l.tags.iter().cloned().collect()).unwrap_or_default();
+        tags.push(Tag::coordinate(coordinate));
+        let content = list.map(|l| l.content).unwrap_or_default();
+        self.send_event_builder(EventBuilder::new(Kind::from(KIND_EMOJI_LIST), content).tags(tags))
+            .await
+            .context("絵文字リストの公開に失敗しました")?;
+        Ok(())
+    }
+
+    /// イベントから絵文字セット情報に変換するヘルパー
+    fn event_to_emoji_set(event: &Event, profiles: &HashMap<PublicKey, AuthorInfo>, in_emoji_list: bool) -> EmojiSetInfo {
+        let identifier = event.tags.identifier().unwrap_or_default().to_string();
+        EmojiSetInfo {
+            naddr: Coordinate::new(event.kind, event.pubkey)
+                .identifier(&identifier)
+                .to_bech32()
+                .unwrap_or_default(),
+            title: extract_tag_value(event, "title"),
+            identifier,
+            author: profiles.get(&event.pubkey).cloned().unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey)),
+            in_emoji_list,
+            emojis: parse_emoji_tags(event),
+            created_at: event.created_at.as_u64(),
+        }
+    }
+
+    // ========================================
+    // NIP-58: バッジ
+    // ========================================
+
+    /// ユーザーが受け入れたバッジ一覧（Kind 30008 の profile_badges）を取得します。
+    ///
+    /// バッジ定義 (Kind 30009) と授与イベント (Kind 8) を照合し、授与が確認できたかを `verified` に設定します。
+    pub async fn get_user_badges(&self, pubkey_str: &str) -> Result<Vec<ProfileBadgeInfo>> {
+        let public_key = Self::parse_public_key(pubkey_str)?;
+
+        let filter = Filter::new()
+            .author(public_key)
+            .kind(Kind::ProfileBadges)
+            .identifier("profile_badges")
+            .limit(1);
+
+        let events = self.fetch_events(vec![filter], Duration::from_secs(10))
+            .await
+            .context("プロフィールバッジの取得に失敗しました")?;
+
+        let Some(profile_badges) = events.into_iter().max_by_key(|e| e.created_at) else {
+            return Ok(Vec::new());
+        };
+
+        // a タグ（バッジ定義）と直後の e タグ（授与イベント）の組を抽出
+        let mut pairs: Vec<(Coordinate, Option<EventId>)> = Vec::new();
+        for tag in profile_badges.tags.iter() {
+            let values = tag.as_slice();
+            if values.len() < 2 {
+                continue;
+            }
+            match values[0].as_str() {
+                "a" => {
+                    if let Ok(coordinate) = Coordinate::parse(&values[1]) {
+                        pairs.push((coordinate, None));
+                    }
+                }
+                "e" => {
+                    if let Some(last) = pairs.last_mut() {
+                        if last.1.is_none() {
+                            last.1 = EventId::from_hex(&values[1]).ok();
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if pairs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let definition_filters: Vec<Filter> = pairs.iter()
+            .map(|(coordinate, _)| Filter::from(coordinate).limit(1))
+            .collect();
+        let award_ids: Vec<EventId> = pairs.iter().filter_map(|(_, id)| *id).collect();
+
+        // 授与イベントの参照がなければ、空の ids フィルタ（全イベントに一致しうる）を送らない
+        let fetch_awards = async {
+            if award_ids.is_empty() {
+                return HashMap::new();
+            }
+            let award_filter = Filter::new().ids(award_ids).kind(Kind::BadgeAward);
+            self.fetch_events(vec![award_filter], Duration::from_secs(10))
+                .await
+                .map(|events| events.into_iter().map(|e| (e.id, e)).collect())
+                .unwrap_or_default()
+        };
+
+        let (definitions_result, awards) = tokio::join!(
+            self.fetch_events(definition_filters, Duration::from_secs(10)),
+            fetch_awards
+        );
+
+        let definitions: Vec<Event> = definitions_result
+            .context("バッジ定義の取得に失敗しました")?
+            .into_iter()
+            .collect();
+
+        let issuers: Vec<PublicKey> = pairs.iter().map(|(c, _)| c.public_key).collect();
+        let profiles = self.fetch_profiles(&issuers).await;
+
+        let badges = pairs.into_iter()
+            .filter_map(|(coordinate, award_id)| {
+                let definition = definitions.iter()
+                    .filter(|e| {
+                        e.pubkey == coordinate.public_key
+                            && extract_tag_value(e, "d").as_deref() == Some(coordinate.identifier.as_str())
+                    })
+                    .max_by_key(|e| e.created_at)?;
+
+                // 授与イベントが定義の作成者によるもので、このユーザー宛てか確認
+                let verified = award_id
+                    .and_then(|id| awards.get(&id))
+                    .is_some_and(|award| {
+                        award.pubkey == coordinate.public_key
+                            && award.tags.iter().any(|tag| {
+                                let values = tag.as_slice();
+                                values.len() >= 2 && values[0] == "p" && values[1] == public_key.to_hex()
+                            })
+                    });
+
+                Some(ProfileBadgeInfo {
+                    badge: Self::event_to_badge_definition(definition, &profiles),
+                    award_id: award_id.map(|id| id.to_hex()),
+                    verified,
+                })
+            })
+            .collect();
+
+        Ok(badges)
+    }
+
+    /// バッジ定義 (Kind 30009) を取得します（naddr または `30009:<pubkey>:<d>` 形式）。
+    pub async fn get_badge_definition(&self, badge: &str) -> Result<BadgeDefinitionInfo> {
+        let coordinate = Self::parse_coordinate(badge)?;
+        if coordinate.kind != Kind::BadgeDefinition {
+            return Err(anyhow!("バッジ定義 (Kind 30009) のアドレスを指定してください"));
+        }
+
+        let event = self.fetch_event_by_coordinate(&coordinate, "バッジ定義").await?;
+        let profiles = self.fetch_profiles(&[event.pubkey]).await;
+
+        Ok(Self::event_to_badge_definition(&event, &profiles))
+    }
+
+    /// バッジを授与します（Kind 8, NIP-58）。自分が作成したバッジ定義のみ授与できます。
+    pub async fn award_badge(&self, badge: &str, recipients: &[String]) -> Result<PublishResult> {
+        self.require_write_access()?;
+
+        let coordinate = Self::parse_coordinate(badge)?;
+        if coordinate.kind != Kind::BadgeDefinition {
+            return Err(anyhow!("バッジ定義 (Kind 30009) のアドレスを指定してください"));
+        }
+        if Some(coordinate.public_key) != self.public_key {
+            return Err(anyhow!("自分が作成したバッジ定義のみ授与できます"));
+        }
+        if recipients.is_empty() {
+            return Err(anyhow!("授与先のユーザーを 1 人以上指定してください"));
+        }
+
+        let mut tags = vec![Tag::coordinate(Coordinate::new(coordinate.kind, coordinate.public_key).identifier(coordinate.identifier.clone()))];
+        for recipient in recipients {
+            tags.push(Tag::public_key(Self::parse_public_key(recipient)?));
+        }
+
+        let builder = EventBuilder::new(Kind::BadgeAward, "").tags(tags);
+
+        let output = self.send_event_builder(builder).await
+            .context("バッジの授与に失敗しました")?;
+
+        let event_id = *output.id();
+        info!("バッジを授与しました ({} 人): {}", recipients.len(), event_id);
+        Ok(PublishResult::new(output, self.public_key))
+    }
+
+    /// バッジ定義イベントから表示用情報に変換するヘルパー
+    fn event_to_badge_definition(event: &Event, profiles: &HashMap<PublicKey, AuthorInfo>) -> BadgeDefinitionInfo {
+        let identifier = extract_tag_value(event, "d").unwrap_or_default();
+
+        BadgeDefinitionInfo {
+            naddr: Coordinate::new(event.kind, event.pubkey)
+                .identifier(&identifier)
+                .to_bech32()
+                .unwrap_or_default(),
+            name: extract_tag_value(event, "name").unwrap_or_else(|| identifier.clone()),
+            identifier,
+            description: extract_tag_value(event, "description"),
+            image: extract_tag_value(event, "image"),
+            thumb: extract_tag_value(event, "thumb"),
+            issuer: profiles
+                .get(&event.pubkey)
+                .cloned()
+                .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey)),
+            created_at: event.created_at.as_u64(),
+        }
+    }
+
+    // ========================================
+    // NIP-71: 動画
+    // ========================================
+
+    /// 動画イベント (Kind 21/22, NIP-71) を取得します。
+    ///
+    /// `short` が Some(true) の場合はショート動画 (Kind 22) のみ、Some(false) の場合は
+    /// 通常動画 (Kind 21) のみを取得します。`follows_only` が true で認証済みの場合は
+    /// フォロー中ユーザーの動画に限定します。
+    pub async fn get_video_feed(&self, follows_only: bool, short: Option<bool>, limit: u64) -> Result<Vec<VideoInfo>> {
+        let kinds = match short {
+            Some(true) => vec![Kind::from(22)],
+            Some(false) => vec![Kind::from(21)],
+            None => vec![Kind::from(21), Kind::from(22)],
+        };
+
+        let mut filter = Filter::new().kinds(kinds).limit(limit as usize);
+
+        if follows_only {
+            if let Some(pk) = self.public_key {
+                let followed = self.fetch_followed_pubkeys(pk).await;
+                if !followed.is_empty() {
+                    filter = filter.authors(followed);
+                }
+            }
+        }
+
+        let events = self.fetch_events(vec![filter], Duration::from_secs(10))
+            .await
+            .context("動画の取得に失敗しました")?;
+
+        let mut events_vec: Vec<Event> = events.into_iter().collect();
+        events_vec.sort_by_key(|e| Reverse(e.created_at));
+        events_vec.truncate(limit as usize);
+
+        let pubkeys = Self::collect_pubkeys(&events_vec);
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let seen_on = self.seen_on_relays(&events_vec).await;
+
+        Ok(events_vec.iter()
+            .filter_map(|event| Self::event_to_video(event, &profiles, relay_hints(&seen_on, &event.id)))
+            .collect())
+    }
+
+    /// 動画イベント (Kind 21/22, NIP-71) を投稿します。
+    pub async fn post_video(&self, params: VideoParams) -> Result<(VideoInfo, PublishResult)> {
+        self.require_write_access()?;
+
+        let kind = if params.short { Kind::from(22) } else { Kind::from(21) };
+
+        let mut tags = vec![
+            Tag::custom(TagKind::Title, vec![params.title.clone()]),
+            Tag::custom(
+                TagKind::custom("published_at".to_string()),
+                vec![current_unix_timestamp().to_string()],
+            ),
+            Tag::custom(TagKind::custom("imeta".to_string()), params.media.to_imeta_values()),
+        ];
+        if let Some(duration) = params.media.duration {
+            tags.push(Tag::custom(TagKind::custom("duration".to_string()), vec![duration.to_string()]));
+        }
+        tags.push(Tag::custom(TagKind::custom("alt".to_string()), vec![format!("動画: {}", params.title)]));
+        for t in params.hashtags.iter().flatten() {
+            tags.push(Tag::hashtag(t.clone()));
+        }
+
+        let builder = EventBuilder::new(kind, params.description.as_deref().unwrap_or_default()).tags(tags);
+
+        let event = self.sign_event_builder(builder).await
+            .context("動画イベントの署名に失敗しました")?;
+        let output = self.send_event(event.clone()).await
+            .context("動画の投稿に失敗しました")?;
+
+        info!("動画を投稿しました: {}", event.id);
+
+        let published = PublishResult::new(output, self.public_key);
+        let profiles = self.fetch_profiles(&[event.pubkey]).await;
+        let video = Self::event_to_video(&event, &profiles, &published.published_to)
+            .ok_or_else(|| anyhow!("投稿した動画イベントの変換に失敗しました"))?;
+        Ok((video, published))
+    }
+
+    /// 動画イベントから表示用情報に変換するヘルパー（imeta がない場合は None）
+    fn event_to_video(event: &Event, profiles: &HashMap<PublicKey, AuthorInfo>, relays: &[String]) -> Option<VideoInfo> {
+        let variants: Vec<crate::content::MediaMetadata> = event.tags.iter()
+            .filter(|tag| tag.kind() == TagKind::custom("imeta".to_string()))
+            .filter_map(|tag| crate::content::parse_imeta(&tag.as_slice()[1..]))
+            .collect();
+
+        if variants.is_empty() {
+            return None;
+        }
+
+        let thumbnail = variants.iter().find_map(|v| v.image.clone());
+        let duration = extract_tag_value(event, "duration")
+            .and_then(|d| d.parse().ok())
+            .or_else(|| variants.iter().find_map(|v| v.duration));
+
+        let hashtags: Vec<String> = event.tags.iter()
+            .filter_map(|tag| {
+                if let Some(TagStandard::Hashtag(h)) = tag.as_standardized() {
+                    Some(h.clone())
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Some(VideoInfo {
+            id: event.id.to_hex(),
+            nevent: encode_nevent(&event.id, Some(event.pubkey), relays),
+            video_type: if event.kind == Kind::from(22) { "short" } else { "normal" }.to_string(),
+            title: extract_tag_value(event, "title").unwrap_or_else(|| "無題".to_string()),
+            description: Some(event.content.clone()).filter(|c| !c.is_empty()),
+            url: variants[0].url.clone(),
+            thumbnail,
+            duration,
+            variants,
+            hashtags,
+            created_at: event.created_at.as_u64(),
+            author: profiles
+                .get(&event.pubkey)
+                .cloned()
+                .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey)),
+        })
+    }
+
+    // ========================================
+    // NIP-56: 通報
+    // ========================================
+
+    /// ユーザーまたはノートを通報します（Kind 1984, NIP-56）。
+    ///
+    /// `note_id` が指定された場合はノートへの通報（e タグ + 著者の p タグ）、
+    /// それ以外は `pubkey` で指定したユーザーへの通報になります。
+    pub async fn report_content(
+        &self,
+        pubkey: Option<&str>,
+        note_id: Option<&str>,
+        report_type: &str,
+        reason: Option<&str>,
+    ) -> Result<PublishResult> {
+        self.require_write_access()?;
+
+        let report: Report = report_type.trim().to_lowercase().parse()
+            .map_err(|_| anyhow!(
+                "無効な通報種別です: {}（spam, illegal, impersonation, nudity, profanity, malware, other のいずれか）",
+                report_type
+            ))?;
+
+        let tags = if let Some(id_str) = note_id {
+            let event_id = self.resolve_event_id(id_str).await?;
+            let author = match pubkey {
+                Some(pk_str) => Self::parse_public_key(pk_str)?,
+                None => self.fetch_event_by_id(event_id, "通報対象のノート").await?.pubkey,
+            };
+            vec![
+                Tag::event_report(event_id, report.clone()),
+                Tag::public_key_report(author, report),
+            ]
+        } else if let Some(pk_str) = pubkey {
+            let public_key = Self::parse_public_key(pk_str)?;
+            vec![Tag::public_key_report(public_key, report)]
+        } else {
+            return Err(anyhow!("pubkey または note_id のいずれかを指定してください"));
+        };
+
+        let builder = EventBuilder::new(Kind::Reporting, reason.unwrap_or_default())
+            .tags(tags);
+
+        let output = self.send_event_builder(builder).await
+            .context("通報の送信に失敗しました")?;
+
+        let event_id = *output.id();
+        info!("通報を送信しました。イベント ID: {}", event_id);
+        Ok(PublishResult::new(output, self.public_key))
+    }
+
+    /// アドレス可能イベントの座標をパース（naddr または `<kind>:<pubkey>:<d>` 形式）
+    pub(crate) fn parse_coordinate(coordinate_str: &str) -> Result<Coordinate> {
+        Coordinate::parse(coordinate_str.trim().trim_start_matches("nostr:"))
+            .map_err(|_| anyhow!("無効なアドレスです（naddr または <kind>:<pubkey>:<d> 形式）: {}", coordinate_str))
+    }
+
+    /// 作成者・Kind・d タグで指定したアドレス可能イベントの最新版を取得するヘルパー（存在しない場合は None）
+    async fn fetch_latest_addressable(&self, pk: PublicKey, kind: Kind, identifier: &str, context: &str) -> Result<Option<Event>> {
+        let filter = Filter::new()
+            .author(pk)
+            .kind(kind)
+            .identifier(identifier)
+            .limit(1);
+        let events = self.fetch_events(vec![filter], Duration::from_secs(10))
+            .await
+            .context(format!("{}の取得に失敗しました", context))?;
+        Ok(events.into_iter().max_by_key(|e| e.created_at))
+    }
+
+    /// 座標で指定されたアドレス可能イベントの最新版を取得するヘルパー
+    pub(crate) async fn fetch_event_by_coordinate(&self, coordinate: &Coordinate, context: &str) -> Result<Event> {
+        let hints: Vec<String> = coordinate.relays.iter().map(|url| url.to_string()).collect();
+        let events = self.fetch_events_with_hints(vec![Filter::from(coordinate)], &hints, Duration::from_secs(10))
+            .await
+            .context(format!("{}の取得に失敗しました", context))?;
+        events
+            .into_iter()
+            .max_by_key(|e| e.created_at)
+            .ok_or_else(|| anyhow!("{}が見つかりません", context))
+    }
+
+    /// イベント ID 文字列をパース（nevent、note、hex 対応）
+    ///
+    /// naddr はイベントを取得しないと ID が決まらないため、`resolve_event_id` を使います。
+    fn parse_event_id(id_str: &str) -> Result<EventId> {
+        match Self::parse_event_pointer(id_str)? {
+            (EventPointer::Id(id), _) => Ok(id),
+            (EventPointer::Coordinate(_), _) => Err(anyhow!(
+                "naddr は指定できません。nevent、note、hex のいずれかを指定してください: {}",
+                id_str.trim()
+            )),
+        }
+    }
+
+    /// イベント識別子をパースし、埋め込まれたリレーヒントも返す（nevent、naddr、note、hex、`nostr:` URI 対応）
+    fn parse_event_pointer(id_str: &str) -> Result<(EventPointer, Vec<String>)> {
+        let id_str = id_str.trim().trim_start_matches("nostr:");
+        if id_str.starts_with("nevent") {
+            let nip19 = Nip19Event::from_bech32(id_str)
+                .context("無効な nevent 形式です")?;
+            Ok((EventPointer::Id(nip19.event_id), nip19.relays))
+        } else if id_str.starts_with("naddr") {
+            let coordinate = Coordinate::from_bech32(id_str)
+                .context("無効な naddr 形式です")?;
+            let relays = coordinate.relays.iter().map(|url| url.to_string()).collect();
+            Ok((EventPointer::Coordinate(coordinate), relays))
+        } else if id_str.starts_with("note") {
+            let id = EventId::from_bech32(id_str).context("無効な note 形式です")?;
+            Ok((EventPointer::Id(id), Vec::new()))
+        } else {
+            let id = EventId::from_hex(id_str).context("無効な hex イベント ID です")?;
+            Ok((EventPointer::Id(id), Vec::new()))
+        }
+    }
+
+    /// イベント識別子（nevent、naddr、note、hex）が指すイベントを取得するヘルパー
+    ///
+    /// 埋め込まれたリレーヒントも問い合わせ先に加えます。naddr の場合は最新版を返します。
+    pub(crate) async fn resolve_event(&self, id_str: &str, context: &str) -> Result<Event> {
+        let (pointer, hints) = Self::parse_event_pointer(id_str)?;
+        let filter = match &pointer {
+            EventPointer::Id(id) => Filter::new().id(*id).limit(1),
+            EventPointer::Coordinate(coordinate) => Filter::from(coordinate),
+        };
+        let events = self.fetch_events_with_hints(vec![filter], &hints, Duration::from_secs(10))
+            .await
+            .context(format!("{}の取得に失敗しました", context))?;
+        events
+            .into_iter()
+            .max_by_key(|e| e.created_at)
+            .ok_or_else(|| anyhow!("{}が見つかりません", context))
+    }
+
+    /// イベント識別子（nevent、naddr、note、hex）をイベント ID に解決するヘルパー
+    ///
+    /// naddr の場合はリレーヒントも使って最新版を取得し、その ID を返します。
+    pub(crate) async fn resolve_event_id(&self, id_str: &str) -> Result<EventId> {
+        match Self::parse_event_pointer(id_str)? {
+            (EventPointer::Id(id), _) => Ok(id),
+            (EventPointer::Coordinate(_), _) => Ok(self.resolve_event(id_str, "naddr が指すイベント").await?.id),
+        }
+    }
+
+    /// 本文中の `nostr:` 参照（npub、nprofile、note、nevent、naddr）が指すプロフィールとノートを取得
+    ///
+    /// 戻り値は bech32 値をキーにしたマップで、取得できなかった参照は含まれません。
+    /// 参照が多い場合は先頭の MAX_RESOLVED_REFERENCES 件のみを解決します。参照先のノートには
+    /// タイムラインと同じ `filters` と `sensitive-content` の設定を適用します。
+    pub async fn resolve_references(&self, references: &[String]) -> HashMap<String, ResolvedReference> {
+        let mut profile_refs: Vec<(String, PublicKey)> = Vec::new();
+        let mut event_refs: Vec<(String, EventPointer)> = Vec::new();
+        let mut hints: Vec<String> = Vec::new();
+
+        let mut seen = std::collections::HashSet::new();
+        for bech32 in references.iter().filter(|r| seen.insert(r.as_str())).take(MAX_RESOLVED_REFERENCES) {
+            if bech32.starts_with("npub") || bech32.starts_with("nprofile") {
+                if let Ok((pk, _)) = Self::parse_profile_pointer(bech32) {
+                    profile_refs.push((bech32.clone(), pk));
+                }
+            } else if let Ok((pointer, relays)) = Self::parse_event_pointer(bech32) {
+                hints.extend(relays);
+                event_refs.push((bech32.clone(), pointer));
+            }
+        }
+
+        let mut events: Vec<Event> = Vec::new();
+        if !event_refs.is_empty() {
+            let filters = pointer_filters(event_refs.iter().map(|(_, p)| p));
+            match self.fetch_events_with_hints(filters, &hints, Duration::from_secs(10)).await {
+                Ok(fetched) => events = fetched.into_iter().collect(),
+                Err(e) => debug!("参照先のノートの取得に失敗（スキップ）: {}", e),
+            }
+        }
+        // タイムラインと同じく、設定ファイルの `filters` に一致するノートと
+        // `sensitive-content: hide` で隠すノートはプレビューしない
+        let kept: std::collections::HashSet<EventId> = self.apply_content_filter(&events).await
+            .into_iter()
+            .filter(|event| self.sensitive_content != SensitiveContentPolicy::Hide || sensitive_reason(event).is_none())
+            .map(|event| event.id)
+            .collect();
+        events.retain(|event| kept.contains(&event.id));
+
+        let mut pubkeys: Vec<PublicKey> = profile_refs.iter().map(|(_, pk)| *pk).collect();
+        pubkeys.extend(events.iter().map(|e| e.pubkey));
+        pubkeys.sort();
+        pubkeys.dedup();
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let author_of = |pk: &PublicKey| profiles
+            .get(pk)
+            .cloned()
+            .unwrap_or_else(|| AuthorInfo::from_public_key(pk));
+
+        let mut resolved = HashMap::new();
+        for (bech32, pk) in profile_refs {
+            let author = author_of(&pk);
+            resolved.insert(bech32, ResolvedReference {
+                display_name: author.display(),
+                npub: author.npub,
+                nip05: author.nip05,
+                kind: None,
+                title: None,
+                preview: None,
+                created_at: None,
+                content_warning: None,
+            });
+        }
+        for (bech32, pointer) in event_refs {
+            let Some(event) = find_pointed_event(&events, &pointer) else { continue };
+            let author = author_of(&event.pubkey);
+            let content_warning = sensitive_reason(event);
+            let preview = match &content_warning {
+                Some(reason) if self.sensitive_content != SensitiveContentPolicy::Show => {
+                    format!("（センシティブな内容: {}）", reason)
+                }
+                _ => {
+                    let preview = extract_tag_value(event, "summary").unwrap_or_else(|| event.content.clone());
+                    truncate_preview(&preview, REFERENCE_PREVIEW_CHARS)
+                }
+            };
+            resolved.insert(bech32, ResolvedReference {
+                display_name: author.display(),
+                npub: author.npub,
+                nip05: author.nip05,
+                kind: Some(event.kind.as_u16()),
+                title: extract_tag_value(event, "title"),
+                preview: Some(preview),
+                created_at: Some(event.created_at.as_u64()),
+                content_warning,
+            });
+        }
+        resolved
+    }
+
+    /// 複数のイベント識別子（nevent、naddr、note、hex）が指すノートを 1 回の取得でまとめて取得
+    ///
+    /// 戻り値は（指定順のノート, 見つからなかった識別子）です。リアクション数・リプライ数も付与します。
+    pub async fn get_notes_by_ids(&self, ids: &[String]) -> Result<(Vec<NoteInfo>, Vec<String>)> {
+        let mut pointers = Vec::with_capacity(ids.len());
+        let mut hints = Vec::new();
+        for id in ids {
+            let (pointer, relays) = Self::parse_event_pointer(id)?;
+            hints.extend(relays);
+            pointers.push((id.clone(), pointer));
+        }
+
+        let filters = pointer_filters(pointers.iter().map(|(_, p)| p));
+        let events: Vec<Event> = self.fetch_events_with_hints(filters, &hints, Duration::from_secs(10))
+            .await
+            .context("ノートの取得に失敗しました")?
+            .into_iter()
+            .collect();
+
+        // 指定順に並べ、同じイベントを指す識別子は 1 件にまとめる
+        let mut ordered: Vec<Event> = Vec::new();
+        let mut not_found = Vec::new();
+        for (id, pointer) in &pointers {
+            match find_pointed_event(&events, pointer) {
+                Some(event) if !ordered.iter().any(|e| e.id == event.id) => ordered.push(event.clone()),
+                Some(_) => {}
+                None => not_found.push(id.clone()),
+            }
+        }
+
+        let profiles = self.fetch_profiles(&Self::collect_pubkeys(&ordered)).await;
+        let mut notes = self.events_to_notes(&ordered, &profiles).await;
+        self.enrich_notes_with_counts(&mut notes).await;
+        Ok((notes, not_found))
+    }
+
+    /// URL の OpenGraph プレビューを並行して取得（キャッシュ付き）
+    ///
+    /// 戻り値は URL をキーにしたマップで、HTML でない・タイムアウトした・情報がない URL は含まれません。
+    /// URL が多い場合は先頭の MAX_LINK_PREVIEWS 件のみを取得します。
+    pub async fn fetch_link_previews(&self, urls: &[String]) -> HashMap<String, crate::link_preview::LinkPreview> {
+        let mut previews = HashMap::new();
+        let mut to_fetch = Vec::new();
+
+        let mut seen = std::collections::HashSet::new();
+        {
+            let cache = self.link_preview_cache.read().await;
+            for url in urls.iter().filter(|u| seen.insert(u.as_str())).take(MAX_LINK_PREVIEWS) {
+                match cache.get(url) {
+                    Some((fetched_at, preview)) if fetched_at.elapsed() < LINK_PREVIEW_CACHE_TTL => {
+                        if let Some(preview) = preview {
+                            previews.insert(url.clone(), preview.clone());
+                        }
+                    }
+                    _ => to_fetch.push(url.clone()),
+                }
+            }
+        }
+
+        if to_fetch.is_empty() {
+            return previews;
+        }
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for url in to_fetch {
+            tasks.spawn(async move {
+                let preview = match crate::link_preview::fetch_link_preview(&url).await {
+                    Ok(preview) if !preview.is_empty() => Some(preview),
+                    Ok(_) => None,
+                    Err(e) => {
+                        debug!("リンクプレビューの取得に失敗（スキップ）: {}: {}", url, e);
+                        None
+                    }
+                };
+                (url, preview)
+            });
+        }
+
+        let now = std::time::Instant::now();
+        let mut fetched = Vec::new();
+        while let Some(result) = tasks.join_next().await {
+            match result {
+                Ok(entry) => fetched.push(entry),
+                Err(e) => warn!("リンクプレビュータスクの実行に失敗: {}", e),
+            }
+        }
+
+        let mut cache = self.link_preview_cache.write().await;
+        cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < LINK_PREVIEW_CACHE_TTL);
+        for (url, preview) in fetched {
+            if let Some(ref preview) = preview {
+                previews.insert(url.clone(), preview.clone());
+            }
+            cache.insert(url, (now, preview));
+        }
+        previews
+    }
+
+    /// ノートの本文を `sensitive-content` の設定に関係なく取得
+    ///
+    /// `flag` の設定でプレースホルダーに置き換えられたノートを明示的に表示するために使います。
+    pub async fn reveal_note(&self, note_id: &str) -> Result<NoteInfo> {
+        let event = self.resolve_event(note_id, "ノート").await?;
+        let author_pk = event_author(&event);
+        let profiles = self.fetch_profiles(&[author_pk]).await;
+        let author = profiles
+            .get(&author_pk)
+            .cloned()
+            .unwrap_or_else(|| AuthorInfo::from_public_key(&author_pk));
+        let seen_on = self.seen_on_relays(std::slice::from_ref(&event)).await
+            .remove(&event.id)
+            .unwrap_or_default();
+
+        Ok(NoteInfo {
+            id: event.id.to_hex(),
+            nevent: encode_nevent(&event.id, Some(event.pubkey), &seen_on),
+            author,
+            content: event.content.clone(),
+            created_at: event.created_at.as_u64(),
+            reactions: None,
+            replies: None,
+            reposts: None,
+            zap_sats: None,
+            verified: self.signature_status(&event),
+            seen_on,
+            reposted_by: None,
+            content_warning: sensitive_reason(&event),
+            content_hidden: false,
+            language: note_language(&event),
+            signed_by: signed_by(&event, &author_pk),
+        })
+    }
+
+    // ========================================
+    // バックアップ（エクスポート・インポート）
+    // ========================================
+
+    /// 自分が作成したイベントを全リレーから取得し、JSONL ファイルに書き出します。
+    ///
+    /// `kinds` が空の場合はすべての Kind を対象にします。書き出し先のファイルが既にある場合は、
+    /// `overwrite` が true のときだけ上書きします。
+    pub async fn export_my_events(&self, path: Option<&str>, kinds: &[u16], max_events: usize, overwrite: bool) -> Result<EventExport> {
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("エクスポートには認証が必要です。設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))?;
+
+        let path = match path {
+            Some(p) => std::path::PathBuf::from(p),
+            None => crate::backup::default_export_path(&pk, current_unix_timestamp())?,
+        };
+        if path.exists() && !overwrite {
+            return Err(anyhow!(
+                "{} は既に存在します。上書きする場合は overwrite を true にしてください。",
+                path.display()
+            ));
+        }
+
+        let (events, truncated) = self.fetch_authored_events(pk, kinds, None, None, max_events).await?;
+
+        crate::backup::write_events_jsonl(&path, &events)?;
+        info!("{} 件のイベントをエクスポートしました: {}", events.len(), path.display());
+
+        Ok(EventExport {
+            path: path.display().to_string(),
+            count: events.len() as u64,
+            kinds: crate::backup::count_by_kind(&events),
+            oldest_at: events.last().map(|e| e.created_at.as_u64()),
+            newest_at: events.first().map(|e| e.created_at.as_u64()),
+            truncated,
+        })
+    }
+
+    /// 著者のイベントを新しい順に最大 `max_events` 件取得するヘルパー
+    ///
+    /// `paginate_pool_events` でリレーごとに `until` を遡って取得します。戻り値の bool は上限に達したかどうかです。
+    async fn fetch_authored_events(
+        &self,
+        author: PublicKey,
+        kinds: &[u16],
+        since: Option<u64>,
+        until: Option<u64>,
+        max_events: usize,
+    ) -> Result<(Vec<Event>, bool)> {
+        let mut filter = Filter::new().author(author);
+        if !kinds.is_empty() {
+            filter = filter.kinds(kinds.iter().map(|k| Kind::from(*k)));
+        }
+        if let Some(since) = since {
+            filter = filter.since(Timestamp::from(since));
+        }
+        if let Some(until) = until {
+            filter = filter.until(Timestamp::from(until));
+        }
+
+        let events = self.paginate_pool_events(filter, max_events, Duration::from_secs(30))
+            .await
+            .context("イベントの取得に失敗しました")?;
+        debug!("著者のイベント取得: {} 件", events.len());
+        let truncated = events.len() >= max_events;
+        Ok((events, truncated))
+    }
+
+    /// 自分の最近のイベントを指定したリレーに再送信します（新しく参加したリレーへの履歴のコピー用）。
+    ///
+    /// 古い順に送信します。リレーがプールにない場合は一時的に追加して完了後に削除し、
+    /// プールにある場合は書き込み用のフラグを一時的に付けて完了後に元に戻します（エラー時も同様）。
+    pub async fn rebroadcast_events(
+        &self,
+        relay: &str,
+        kinds: &[u16],
+        since: Option<u64>,
+        until: Option<u64>,
+        max_events: usize,
+    ) -> Result<EventRebroadcast> {
+        self.require_write_access()?;
+        self.refuse_in_dry_run("イベントの再送信")?;
+        let pk = self.public_key
+            .ok_or_else(|| anyhow!("再送信には認証が必要です。"))?;
+        let url = RelayUrl::parse(relay)
+            .map_err(|e| anyhow!("無効なリレー URL です: {}: {}", relay, e))?;
+
+        let (mut events, truncated) = self.fetch_authored_events(pk, kinds, since, until, max_events).await?;
+        events.reverse();
+
+        // プールにあるリレーには足りないフラグだけを一時的に付け、終了後に元に戻す
+        let added_flags = match self.client.relay(&url).await {
+            Ok(existing) => {
+                let mut added = RelayServiceFlags::NONE;
+                if !existing.flags().has_write() {
+                    added.add(RelayServiceFlags::WRITE);
+                }
+                if !existing.flags().has_ping() {
+                    added.add(RelayServiceFlags::PING);
+                }
+                Some(added)
+            }
+            Err(_) => None,
+        };
+
+        let result = self.send_events_to_relay(&url, events, truncated).await;
+
+        match added_flags {
+            Some(added) => {
+                if let Ok(relay) = self.client.relay(&url).await {
+                    relay.flags().remove(added);
+                }
+            }
+            None => {
+                if let Err(e) = self.client.remove_relay(url.clone()).await {
+                    debug!("一時的に追加したリレーの削除に失敗: {}", e);
+                }
+            }
+        }
+        let result = result?;
+        info!("{} に {} 件のイベントを再送信しました", url, result.published);
+        Ok(result)
+    }
+
+    /// rebroadcast_events の送信部分。リレーを書き込み用に追加・接続して古い順に送信する
+    async fn send_events_to_relay(&self, url: &RelayUrl, events: Vec<Event>, truncated: bool) -> Result<EventRebroadcast> {
+        self.client.add_write_relay(url.clone()).await
+            .map_err(|e| anyhow!("リレーの追加に失敗しました: {}", e))?;
+        self.client.connect_relay(url.clone()).await
+            .map_err(|e| anyhow!("リレーへの接続に失敗しました: {}", e))?;
+
+        let mut result = EventRebroadcast {
+            relay: url.to_string(),
+            found: events.len() as u64,
+            published: 0,
+            failed: 0,
+            rejected: Vec::new(),
+            truncated,
+        };
+        for event in events {
+            let id = event.id;
+            self.rate_limiter.acquire(&[url.as_str()]).await;
+            let reason = match self.client.send_event_to([url.clone()], event).await {
+                Ok(output) if output.success.contains(url) => {
+                    result.published += 1;
+                    continue;
+                }
+                Ok(output) => output.failed.get(url).cloned().unwrap_or_default(),
+                Err(e) => e.to_string(),
+            };
+            result.failed += 1;
+            if result.rejected.len() < IMPORT_MAX_REPORTED_ERRORS {
+                result.rejected.push(RejectedEvent { id: id.to_hex(), reason });
+            }
+        }
+        Ok(result)
+    }
+
+    /// JSONL バックアップの署名済みイベントを検証し、書き込み有効なリレーに再送信します。
+    ///
+    /// ID・署名が不正な行はスキップします。`dry_run` の場合は検証のみ行い、送信しません。
+    pub async fn import_events(&self, path: &str, dry_run: bool) -> Result<EventImport> {
+        let dry_run = dry_run || self.is_dry_run();
+        let contents = crate::backup::read_events_jsonl(std::path::Path::new(path))?;
+        let mut result = EventImport {
+            path: path.to_string(),
+            valid: contents.events.len() as u64,
+            invalid: contents.errors.len() as u64,
+            errors: contents.errors.into_iter().take(IMPORT_MAX_REPORTED_ERRORS).collect(),
+            published: 0,
+            failed: 0,
+            relays: Vec::new(),
+            dry_run,
+        };
+        if dry_run || contents.events.is_empty() {
+            return Ok(result);
+        }
+
+        let relays = self.connect_write_relays().await?;
+        for event in contents.events {
+            let id = event.id;
+            self.rate_limiter.acquire(&relays).await;
+            match self.client.send_event_to(&relays, event).await {
+                Ok(output) if !output.success.is_empty() => result.published += 1,
+                Ok(output) => {
+                    debug!("イベント {} はどのリレーにも受け付けられませんでした: {:?}", id, output.failed);
+                    result.failed += 1;
+                }
+                Err(e) => {
+                    debug!("イベント {} の送信に失敗: {}", id, e);
+                    result.failed += 1;
+                }
+            }
+        }
+        info!("{} 件のイベントを再送信しました（失敗 {} 件）", result.published, result.failed);
+
+        result.relays = relays;
+        Ok(result)
+    }
+
+    /// 書き込み有効なリレーをプールに追加して接続し、URL の一覧を返すヘルパー
+    async fn connect_write_relays(&self) -> Result<Vec<String>> {
+        let mut relays = Vec::new();
+        for url in &self.write_relays {
+            match self.client.add_write_relay(url.as_str()).await {
+                Ok(_) => {
+                    if let Err(e) = self.client.connect_relay(url.as_str()).await {
+                        warn!("リレー {} への接続に失敗: {}", url, e);
+                        continue;
+                    }
+                    relays.push(url.clone());
+                }
+                Err(e) => warn!("リレー {} の追加に失敗: {}", url, e),
+            }
+        }
+        if relays.is_empty() {
+            return Err(anyhow!("書き込み有効なリレーがありません。設定ファイルの relays で write を有効にしてください。"));
+        }
+        Ok(relays)
+    }
+
+    // ========================================
+    // NIP-03: OpenTimestamps
+    // ========================================
+
+    /// イベントの OpenTimestamps 証明を作成・アップグレードし、確定したら Kind 1040 を公開します。
+    ///
+    /// 初回はカレンダーサーバーに送信して作成途中の証明を保存します。Bitcoin のブロックに
+    /// 取り込まれるまで数時間かかるため、時間をおいて再度呼び出すと証明をアップグレードし、
+    /// Bitcoin のアテステーションを含む証明をアテステーションイベントとして公開します。
+    pub async fn timestamp_event(&self, event_id: &str) -> Result<OtsStatus> {
+        self.require_write_access()?;
+        // 作成途中の証明をファイルに保存・削除するため、ドライランでは実行しない
+        self.refuse_in_dry_run("OpenTimestamps 証明の作成")?;
+
+        let target = self.resolve_event(event_id, "タイムスタンプ対象のイベント").await?;
+        let id = target.id;
+        let id_hex = id.to_hex();
+
+        let mut proof = match crate::ots::load_proof(&id_hex)? {
+            Some(proof) => proof,
+            None => {
+                let proof = crate::ots::stamp(id.as_bytes(), crate::ots::DEFAULT_CALENDARS).await?;
+                crate::ots::save_proof(&id_hex, &proof)?;
+                info!("OpenTimestamps 証明を作成しました: {}", id_hex);
+                return Ok(OtsStatus::pending(&id_hex, &proof, true));
+            }
+        };
+
+        if proof.bitcoin_heights().is_empty() {
+            if crate::ots::upgrade(&mut proof).await? == 0 {
+                return Ok(OtsStatus::pending(&id_hex, &proof, false));
+            }
+            crate::ots::save_proof(&id_hex, &proof)?;
+        }
+        // 公開する証明には確定した（Bitcoin の）アテステーションだけを含める
+        let Some(completed) = proof.completed() else {
+            return Ok(OtsStatus::pending(&id_hex, &proof, false));
+        };
+        let block = completed.bitcoin_heights()[0];
+
+        // NIP-03: e タグ（リレーヒント付き）と k タグで対象イベントを参照する
+        let relay_hint = self.read_relay_urls().await.into_iter().next()
+            .map(|url| url.to_string())
+            .unwrap_or_default();
+        let tags = vec![
+            Tag::parse(vec!["e".to_string(), id_hex.clone(), relay_hint]).unwrap(),
+            Tag::parse(vec!["k".to_string(), target.kind.as_u16().to_string()]).unwrap(),
+        ];
+        let builder = EventBuilder::new(Kind::OpenTimestamps, crate::ots::encode_base64(&completed)).tags(tags);
+        let output = self.send_event_builder(builder).await
+            .context("OpenTimestamps アテステーションの公開に失敗しました")?;
+
+        // 公開済みの証明はイベントとしてリレーに残るため、ローカルの作成途中の証明は削除する
+        crate::ots::remove_proof(&id_hex)?;
+        info!("OpenTimestamps アテステーションを公開しました: {} (ブロック {})", output.id(), block);
+
+        let publish = PublishResult::new(output, self.public_key);
+        Ok(OtsStatus {
+            event_id: id_hex,
+            status: "attested".to_string(),
+            pending_calendars: Vec::new(),
+            bitcoin_block: Some(block),
+            attestation_id: Some(publish.id.to_hex()),
+            created: false,
+            published_to: Some(publish.published_to),
+            failed_relays: Some(publish.failed_relays),
+        })
+    }
+
+    /// 公開したイベントを、受け付けたリレーそれぞれから再取得して配信を確認します。
+    ///
+    /// OK を返しても保存しないリレーを見つけるため、リレーごとに個別に ID で取得します。
+    pub async fn verify_publish(&self, id: EventId, relays: &[String]) -> PublishVerification {
+        let mut tasks = tokio::task::JoinSet::new();
+        for relay in relays {
+            let relay = relay.clone();
+            let client = self.client.clone();
+            let limiter = self.rate_limiter.clone();
+            tasks.spawn(async move {
+                limiter.acquire(&[relay.as_str()]).await;
+                let filter = Filter::new().id(id).limit(1);
+                let timeout = Duration::from_secs(PUBLISH_VERIFY_TIMEOUT_SECS);
+                let found = match client.fetch_events_from([relay.as_str()], vec![filter], timeout).await {
+                    Ok(events) if events.iter().any(|e| e.id == id) => Ok(()),
+                    Ok(_) => Err("イベントが見つかりません".to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                (relay, found)
+            });
+        }
+
+        let mut served_by = Vec::new();
+        let mut missing = Vec::new();
+        while let Some(result) = tasks.join_next().await {
+            match result {
+                Ok((relay, Ok(()))) => served_by.push(relay),
+                Ok((relay, Err(reason))) => missing.push(FailedRelay { relay, reason }),
+                Err(e) => warn!("配信確認タスクの実行に失敗: {}", e),
+            }
+        }
+        served_by.sort();
+        missing.sort_by(|a, b| a.relay.cmp(&b.relay));
+        if !missing.is_empty() {
+            warn!("イベント {} を受け付けたが返さないリレーがあります: {:?}", id, missing);
+        }
+
+        PublishVerification { verified: !served_by.is_empty(), served_by, missing }
+    }
+
+    // ========================================
+    // 送信待ちキュー
+    // ========================================
+
+    /// どのリレーにも送信できなかった署名済みイベントを送信待ちキューに保存するヘルパー
+    async fn queue_pending_publish(&self, event: Event, error: &str) -> Result<()> {
+        let _guard = self.pending_publish_lock.lock().await;
+        let mut queue = crate::storage::load_pending_publishes();
+        if queue.iter().any(|pending| pending.event.id == event.id) {
+            return Ok(());
+        }
+
+        let now = current_unix_timestamp();
+        let id = event.id;
+        queue.push(crate::storage::PendingPublish {
+            event,
+            queued_at: now,
+            attempts: 0,
+            next_attempt_at: now + pending_publish_backoff(0),
+            last_error: Some(error.to_string()),
+        });
+        crate::storage::save_pending_publishes(&queue)?;
+        info!("イベント {} を送信待ちキューに保存しました（{} 件待機中）", id, queue.len());
+        Ok(())
+    }
+
+    /// 送信待ちキューのイベントを取得します。
+    pub async fn get_pending_publishes(&self) -> Vec<PendingPublishInfo> {
+        let _guard = self.pending_publish_lock.lock().await;
+        crate::storage::load_pending_publishes()
+            .iter()
+            .map(PendingPublishInfo::from)
+            .collect()
+    }
+
+    /// 送信待ちキューのイベントを再送します。
+    ///
+    /// `force` が false の場合は再送時刻に達したもの（自動再送の上限回数まで）だけを、
+    /// true の場合はすべてを直ちに再送します。`event_ids` を指定した場合は、そのイベントだけを直ちに再送します。
+    /// 送信できなかったイベントは試行回数に応じて次の再送時刻を延ばします。
+    pub async fn flush_pending_publishes(&self, event_ids: &[String], force: bool) -> Result<PendingFlush> {
+        let ids = event_ids.iter()
+            .map(|id| Self::parse_event_id(id))
+            .collect::<Result<Vec<EventId>>>()?;
+        self.refuse_in_dry_run("送信待ちイベントの再送")?;
+
+        // 送信中はキューのロックを持たず、対象を選んだ後と結果を反映するときだけロックする
+        let now = current_unix_timestamp();
+        let due: Vec<Event> = {
+            let _guard = self.pending_publish_lock.lock().await;
+            crate::storage::load_pending_publishes()
+                .into_iter()
+                .filter(|pending| if !ids.is_empty() {
+                    ids.contains(&pending.event.id)
+                } else {
+                    force || (pending.attempts < PENDING_PUBLISH_MAX_ATTEMPTS && pending.next_attempt_at <= now)
+                })
+                .map(|pending| pending.event)
+                .collect()
+        };
+        let mut result = PendingFlush { published: Vec::new(), failed: Vec::new(), discarded: 0, remaining: 0 };
+        if due.is_empty() {
+            result.remaining = self.get_pending_publishes().await.len();
+            return Ok(result);
+        }
+
+        let relay_urls = self.pool_relay_urls().await;
+        // イベント ID → 失敗の理由（キューから除くものは None）
+        let mut outcomes: HashMap<EventId, Option<String>> = HashMap::new();
+        for event in due {
+            let id = event.id;
+            self.rate_limiter.acquire(&relay_urls).await;
+            let output = send_event_to_write_relays(&self.client, &event).await;
+            if !output.success.is_empty() {
+                info!("送信待ちのイベント {} を再送しました", id);
+                result.published.push(FlushedPublish {
+                    id: id.to_hex(),
+                    result: PublishResult::new(output, Some(event.pubkey)),
+                });
+                outcomes.insert(id, None);
+                continue;
+            }
+            let reason = describe_publish_failures(&output);
+            result.failed.push(RejectedEvent { id: id.to_hex(), reason: reason.clone() });
+            if is_rejected_by_all(&output) {
+                // 拒否されたイベントは再送しても受け付けられないため、キューから除く
+                warn!("送信待ちのイベント {} はすべてのリレーに拒否されたため削除します: {}", id, reason);
+                result.discarded += 1;
+                outcomes.insert(id, None);
+            } else {
+                outcomes.insert(id, Some(reason));
+            }
+        }
+
+        let _guard = self.pending_publish_lock.lock().await;
+        let mut remaining = Vec::new();
+        for mut pending in crate::storage::load_pending_publishes() {
+            match outcomes.get(&pending.event.id) {
+                Some(None) => continue,
+                Some(Some(reason)) => {
+                    pending.attempts += 1;
+                    pending.next_attempt_at = now + pending_publish_backoff(pending.attempts);
+                    pending.last_error = Some(reason.clone());
+                }
+                None => {}
+            }
+            remaining.push(pending);
+        }
+        result.remaining = remaining.len();
+        crate::storage::save_pending_publishes(&remaining)?;
+        Ok(result)
+    }
+
+    /// 送信待ちキューのイベントを送信せずに削除します。`event_ids` が空の場合はすべて削除します。
+    pub async fn discard_pending_publishes(&self, event_ids: &[String]) -> Result<PendingFlush> {
+        let ids = event_ids.iter()
+            .map(|id| Self::parse_event_id(id))
+            .collect::<Result<Vec<EventId>>>()?;
+
+        let _guard = self.pending_publish_lock.lock().await;
+        let mut queue = crate::storage::load_pending_publishes();
+        let before = queue.len();
+        queue.retain(|pending| !ids.is_empty() && !ids.contains(&pending.event.id));
+        crate::storage::save_pending_publishes(&queue)?;
+
+        Ok(PendingFlush {
+            published: Vec::new(),
+            failed: Vec::new(),
+            discarded: before - queue.len(),
+            remaining: queue.len(),
+        })
+    }
+
+    // ========================================
+    // 購読（リアルタイム取得）
+    // ========================================
+
+    /// 種類に応じたフィルタで長時間の購読を開始します。
+    ///
+    /// 購読開始以降に届いたイベントをバッファし、`poll_subscription` で取り出せます。
+    /// `target` は `live_chat` の場合のみ使用します（ライブアクティビティの naddr）。
+    pub async fn start_subscription(
+        &self,
+        kind: &str,
+        target: Option<&str>,
+        name: Option<&str>,
+    ) -> Result<SubscriptionInfo> {
+        let since = Timestamp::now();
+        let (default_name, filters) = match kind {
+            "notifications" => {
+                let pk = self.require_subscription_auth()?;
+                let filter = Filter::new()
+                    .kinds([Kind::TextNote, Kind::Reaction, Kind::ZapReceipt, Kind::Repost])
+                    .pubkey(pk)
+                    .since(since);
+                (kind.to_string(), vec![filter])
+            }
+            "dms" => {
+                let pk = self.require_subscription_auth()?;
+                let filter = Filter::new()
+                    .kind(Kind::EncryptedDirectMessage)
+                    .pubkey(pk)
+                    .since(since);
+                (kind.to_string(), vec![filter])
+            }
+            "timeline" => {
+                let pk = self.require_subscription_auth()?;
+                let follows = self.fetch_followed_pubkeys(pk).await;
+                if follows.is_empty() {
+                    return Err(anyhow!("フォロー中のユーザーがいないため、タイムラインを購読できません。"));
+                }
+                let filter = Filter::new()
+                    .kind(Kind::TextNote)
+                    .authors(follows)
+                    .since(since);
+                (kind.to_string(), vec![filter])
+            }
+            "live_chat" => {
+                let stream = target
+                    .ok_or_else(|| anyhow!("live_chat の購読には target（ライブアクティビティの naddr）が必要です"))?;
+                let coordinate = Self::parse_live_coordinate(stream)?;
+                let filter = Filter::new()
+                    .kind(Kind::LiveEventMessage)
+                    .coordinate(&coordinate)
+                    .since(since);
+                (format!("live_chat:{}", coordinate.identifier), vec![filter])
+            }
+            other => {
+                return Err(anyhow!(
+                    "不明な購読の種類です: {}（{} のいずれかを指定してください）",
+                    other,
+                    SUBSCRIPTION_TYPES.join(", ")
+                ));
+            }
+        };
+
+        let name = name.map(|n| n.to_string()).unwrap_or(default_name);
+        self.rate_limiter.acquire(&self.pool_relay_urls().await).await;
+        self.subscriptions.subscribe(&name, filters).await
+    }
+
+    /// 購読でバッファされたイベントを古い順に最大 `limit` 件取り出します。
+    ///
+    /// ミュート対象は除外し、DM (Kind 4) は復号して返します。
+    pub async fn poll_subscription(&self, name: &str, limit: u64) -> Result<(Vec<SubscriptionEvent>, SubscriptionInfo)> {
+        let (mut events, info) = self.subscriptions.drain(name, limit as usize).await?;
+        if self.signature_verification == SignatureVerification::Drop {
+            events = drop_invalid_signatures(events);
+        }
+        let events = self.filter_muted(events).await;
+
+        let pubkeys = Self::collect_pubkeys(&events);
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let seen_on = self.seen_on_relays(&events).await;
+
+        let mut results = Vec::with_capacity(events.len());
+        for event in events {
+            let content = if event.kind == Kind::EncryptedDirectMessage {
+                self.decrypt_subscription_dm(&event).await
+            } else {
+                event.content.clone()
+            };
+            results.push(SubscriptionEvent {
+                id: event.id.to_hex(),
+                nevent: encode_nevent(&event.id, Some(event.pubkey), relay_hints(&seen_on, &event.id)),
+                kind: event.kind.as_u16(),
+                author: profiles
+                    .get(&event.pubkey)
+                    .cloned()
+                    .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey)),
+                content,
+                created_at: event.created_at.as_u64(),
+            });
+        }
+
+        Ok((results, info))
+    }
+
+    /// 購読を停止します。
+    pub async fn stop_subscription(&self, name: &str) -> Result<()> {
+        self.subscriptions.unsubscribe(name).await
+    }
+
+    /// すべての購読の概要を取得します。
+    pub async fn list_subscriptions(&self) -> Vec<SubscriptionInfo> {
+        self.subscriptions.list().await
+    }
+
+    /// 購読に認証が必要な種類で公開鍵を取得するヘルパー
+    fn require_subscription_auth(&self) -> Result<PublicKey> {
+        self.public_key
+            .ok_or_else(|| anyhow!("この購読には認証が必要です。設定ファイルに nsec（読み取りのみなら publickey）を設定してください。"))
+    }
+
+    /// 購読で受信した DM を復号するヘルパー（失敗時は代替テキスト）
+    async fn decrypt_subscription_dm(&self, event: &Event) -> String {
+        let decrypted = match self.client.signer().await {
+            Ok(signer) => signer.nip04_decrypt(&event.pubkey, &event.content).await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        decrypted.unwrap_or_else(|e| {
+            debug!("DM 復号に失敗: {}", e);
+            "（復号できないメッセージ）".to_string()
+        })
+    }
+
+    // ========================================
+    // NIP-B7: Blossom メディアアップロード
+    // ========================================
+
+    /// Blossom アップロード用の認証イベント (Kind 24242) を署名
+    ///
+    /// BUD-02 に基づき、`t=upload`、`x=<sha256>`、`expiration` タグを含む
+    /// Kind 24242 イベントを署名します（リレーには公開しない）。
+    pub async fn sign_blossom_auth(
+        &self,
+        sha256_hex: &str,
+        file_size: u64,
+        content_type: &str,
+    ) -> Result<Event> {
+        self.require_write_access()?;
+
+        let expiration = Timestamp::from(Timestamp::now().as_u64() + 300); // 5分後に有効期限切れ
+
+        let tags = vec![
+            Tag::custom(
+                TagKind::custom("t".to_string()),
+                vec!["upload".to_string()],
+            ),
+            Tag::custom(
+                TagKind::custom("x".to_string()),
+                vec![sha256_hex.to_string()],
+            ),
+            Tag::expiration(expiration),
+            Tag::custom(
+                TagKind::custom("size".to_string()),
+                vec![file_size.to_string()],
+            ),
+        ];
+
+        let builder = EventBuilder::new(Kind::from(24242), format!("Upload {}", content_type))
+            .tags(tags);
+
+        let event = self
+            .sign_event_builder(builder)
+            .await
+            .context("Blossom 認証イベントの署名に失敗")?;
+
+        debug!(
+            "Blossom 認証イベントを署名: kind={}, id={}",
+            event.kind,
+            event.id
+        );
+
+        Ok(event)
+    }
+
+    /// ユーザーの Blossom サーバーリスト (Kind 10063) を取得
+    pub async fn get_blossom_servers(&self, pubkey_str: Option<&str>) -> Result<Vec<String>> {
+        let pubkey = if let Some(pk_str) = pubkey_str {
+            Self::parse_public_key(pk_str)?
+        } else if let Some(pk) = self.public_key {
+            pk
+        } else {
+            return Err(anyhow!(
+                "公開鍵が必要です。pubkey パラメータを指定するか、認証してください。"
+            ));
+        };
+
+        let filter = Filter::new()
+            .author(pubkey)
+            .kind(Kind::from(10063))
+            .limit(1);
+
+        let events = self
+            .client
+            .fetch_events(vec![filter], Duration::from_secs(5))
+            .await
+            .context("Blossom サーバーリストの取得に失敗")?;
+
+        let servers: Vec<String> = events
+            .iter()
+            .flat_map(|event| {
+                event
+                    .tags
+                    .iter()
+                    .filter_map(|tag| {
+                        let values = tag.as_slice();
+                        if values.len() >= 2 && values[0] == "server" {
+                            Some(values[1].to_string())
+                        } else {
+                            None
+                        }
+                    })
+            })
+            .collect();
+
+        debug!("Blossom サーバーリスト取得: {} 件", servers.len());
+        Ok(servers)
+    }
+
+    /// Blossom サーバーリスト (Kind 10063) を公開
+    pub async fn publish_blossom_servers(&self, servers: &[String]) -> Result<PublishResult> {
+        self.require_write_access()?;
+
+        let tags: Vec<Tag> = servers
+            .iter()
+            .map(|url| {
+                Tag::custom(
+                    TagKind::custom("server".to_string()),
+                    vec![url.clone()],
+                )
+            })
+            .collect();
+
+        let builder = EventBuilder::new(Kind::from(10063), "").tags(tags);
+
+        let output = self
+            .send_event_builder(builder)
+            .await
+            .context("Blossom サーバーリストの公開に失敗")?;
+
+        let event_id = *output.id();
+        info!(
+            "Blossom サーバーリストを公開: {} ({} サーバー)",
+            event_id,
+            servers.len()
+        );
+        Ok(PublishResult::new(output, self.public_key))
+    }
+
+    /// メディアファイルを Blossom サーバーにアップロード (NIP-B7)
+    ///
+    /// 1. ファイルの SHA-256 ハッシュを計算
+    /// 2. Kind 24242 認証イベントを署名
+    /// 3. BUD-02 の PUT /upload でアップロード
+    pub async fn upload_media(
+        &self,
+        data: Vec<u8>,
+        content_type: &str,
+        server_url: &str,
+    ) -> Result<crate::blossom::BlobDescriptor> {
+        self.require_write_access()?;
+        self.refuse_in_dry_run("メディアのアップロード")?;
+
+        let sha256_hex = crate::blossom::compute_sha256(&data);
+        let file_size = data.len() as u64;
+
+        // Kind 24242 認証イベントを署名
+        let auth_event = self
+            .sign_blossom_auth(&sha256_hex, file_size, content_type)
+            .await?;
+
+        // 認証ヘッダーを構築
+        let event_json = serde_json::to_string(&auth_event)
+            .context("認証イベントの JSON 化に失敗")?;
+        let auth_header = crate::blossom::create_auth_header(&event_json);
+
+        // Blossom サーバーにアップロード
+        let descriptor =
+            crate::blossom::upload_blob(server_url, data, content_type, &auth_header).await?;
+
+        // SHA-256 の検証
+        if descriptor.sha256 != sha256_hex {
+            warn!(
+                "Blossom サーバーから返された SHA-256 が一致しません: expected={}, got={}",
+                sha256_hex, descriptor.sha256
+            );
+        }
+
+        Ok(descriptor)
+    }
+
+    /// すべてのリレーから切断します。
+    pub async fn disconnect(&self) {
+        let _ = self.client.disconnect().await;
+        let mut connected = self.connected.write().await;
+        *connected = false;
+    }
+}
+
+// ========================================
+// データ構造体
+// ========================================
+
+/// ノートの情報（表示用）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NoteInfo {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// リンク用の nevent 形式のイベント ID
+    pub nevent: String,
+    /// 著者情報
+    pub author: AuthorInfo,
+    /// ノートの内容
+    pub content: String,
+    /// 作成日時の Unix タイムスタンプ
+    pub created_at: u64,
+    /// リアクション数（将来の拡張用）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reactions: Option<u64>,
+    /// リプライ数（将来の拡張用）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replies: Option<u64>,
+    /// リポスト数（Kind 6）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reposts: Option<u64>,
+    /// 受け取った Zap の合計額（sats、真正性を確認できたレシートのみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zap_sats: Option<u64>,
+    /// 署名検証の結果（`verify-signatures` が有効な場合のみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+    /// イベントを受信したリレー
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seen_on: Vec<String>,
+    /// タイムラインでリポストを元のノートに置き換えた場合の、リポストの情報
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reposted_by: Option<RepostInfo>,
+    /// コンテンツ警告（NIP-36）や NSFW ラベルの理由
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_warning: Option<String>,
+    /// `sensitive-content: flag` により本文をプレースホルダーに置き換えたか
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub content_hidden: bool,
+    /// ノートの言語（ISO 639-1、NIP-32 の言語ラベルまたは本文から判定、判定できない場合は省略）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// NIP-26 の委任で署名されたノートの署名者（npub 形式、author は委任者）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_by: Option<String>,
+}
+
+/// ウォッチリストのアカウントの最近の活動（`get_watched_feeds`）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WatchedFeeds {
+    /// 監視中のアカウント
+    pub accounts: Vec<WatchedAccountInfo>,
+    /// 監視中のアカウントのノート（新しい順）
+    pub notes: Vec<NoteInfo>,
+}
+
+/// ウォッチリストのアカウントと取得範囲内での活動状況
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WatchedAccountInfo {
+    /// アカウントの情報
+    pub author: AuthorInfo,
+    /// 用途のメモ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// ウォッチリストに登録した日時
+    pub added_at: u64,
+    /// 取得したノートのうち、このアカウントのものの数
+    pub recent_notes: u64,
+    /// 取得したノートのうち最新の日時
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_active_at: Option<u64>,
+}
+
+/// タイムラインの集計結果（`get_timeline_digest`）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TimelineDigest {
+    /// 集計対象（"following" または "global"）
+    pub scope: String,
+    /// 集計期間（時間）
+    pub hours: u64,
+    /// ノート数
+    pub total_notes: u64,
+    /// 投稿したアカウント数
+    pub unique_authors: u64,
+    /// リアクションの合計
+    pub total_reactions: u64,
+    /// リプライの合計
+    pub total_replies: u64,
+    /// 最も古いノートの日時
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest_at: Option<u64>,
+    /// 最も新しいノートの日時
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub newest_at: Option<u64>,
+    /// 投稿数の多い著者
+    pub top_authors: Vec<DigestAuthor>,
+    /// よく使われたハッシュタグ
+    pub top_hashtags: Vec<DigestHashtag>,
+    /// リアクションの多いノート
+    pub top_notes: Vec<DigestNote>,
+}
+
+/// 集計結果の著者
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DigestAuthor {
+    /// 著者情報
+    pub author: AuthorInfo,
+    /// 期間内のノート数
+    pub notes: u64,
+}
+
+/// 集計結果のハッシュタグ
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DigestHashtag {
+    /// ハッシュタグ（小文字、# なし）
+    pub hashtag: String,
+    /// 使用されたノート数
+    pub count: u64,
+}
+
+/// 集計結果のノート（本文はプレビューのみ）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DigestNote {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// nevent 形式のイベント ID
+    pub nevent: String,
+    /// 著者の表示名
+    pub author: String,
+    /// 本文のプレビュー
+    pub preview: String,
+    /// リアクション数
+    pub reactions: u64,
+    /// リプライ数
+    pub replies: u64,
+    /// 作成日時の Unix タイムスタンプ
+    pub created_at: u64,
+}
+
+/// アカウントの分析結果（`get_account_analytics`）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AccountAnalytics {
+    /// hex 形式の公開鍵
+    pub pubkey: String,
+    /// npub 形式の公開鍵
+    pub npub: String,
+    /// 集計期間（日数）
+    pub days: u64,
+    /// 集計期間の開始日時
+    pub since: u64,
+    /// 集計期間の終了日時
+    pub until: u64,
+    /// 投稿頻度
+    pub posting: PostingStats,
+    /// 受け取った反応の合計
+    pub received: InteractionCounts,
+    /// リプライ以外で言及されたノート数
+    pub mentions: u64,
+    /// 真正性を確認できず集計から除外した Zap レシートの数
+    pub invalid_zap_count: u64,
+    /// 反応の多いノート
+    pub top_notes: Vec<AnalyticsNote>,
+    /// 反応してくれたアカウント数
+    pub unique_interactors: u64,
+    /// よく反応してくれるアカウント
+    pub top_interactors: Vec<AccountInteraction>,
+    /// 取得件数の上限に達したため、期間の一部しか集計できていない場合は true
+    pub partial: bool,
+}
+
+/// 期間内の投稿頻度
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct PostingStats {
+    /// ノート数（リプライを含む）
+    pub notes: u64,
+    /// リプライ以外のノート数
+    pub original_notes: u64,
+    /// リプライ数
+    pub replies: u64,
+    /// 1 日あたりのノート数
+    pub notes_per_day: f64,
+    /// 投稿した日数
+    pub active_days: u64,
+    /// 日別のノート数（投稿した日のみ、古い順）
+    pub daily: Vec<DailyPostCount>,
+    /// 時間帯（UTC の時）別のノート数
+    pub hourly: [u64; 24],
+}
+
+/// 1 日のノート数
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DailyPostCount {
+    /// 日の開始日時（UTC 0 時）の Unix タイムスタンプ
+    pub day_start: u64,
+    /// その日のノート数
+    pub notes: u64,
+}
+
+/// 受け取った反応の件数
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct InteractionCounts {
+    /// リアクション数
+    pub reactions: u64,
+    /// リプライ数
+    pub replies: u64,
+    /// リポスト数
+    pub reposts: u64,
+    /// Zap 回数
+    pub zaps: u64,
+    /// Zap の合計 sats
+    pub zap_sats: u64,
+}
+
+impl InteractionCounts {
+    /// 反応の合計件数（Zap は回数で数える）
+    pub fn total(&self) -> u64 {
+        self.reactions
+            .saturating_add(self.replies)
+            .saturating_add(self.reposts)
+            .saturating_add(self.zaps)
+    }
+
+    /// 反応を 1 件数える（Zap レシート以外の `zap_sats` は 0）
+    ///
+    /// Zap の金額はレシートの bolt11 から読むため、極端な値でも溢れないよう飽和加算します。
+    fn add(&mut self, kind: Kind, zap_sats: u64) {
+        match kind {
+            Kind::Reaction => self.reactions = self.reactions.saturating_add(1),
+            Kind::TextNote => self.replies = self.replies.saturating_add(1),
+            Kind::Repost => self.reposts = self.reposts.saturating_add(1),
+            _ => {
+                self.zaps = self.zaps.saturating_add(1);
+                self.zap_sats = self.zap_sats.saturating_add(zap_sats);
+            }
+        }
+    }
+}
+
+/// 反応の多いノート（本文はプレビューのみ）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AnalyticsNote {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// nevent 形式のイベント ID
+    pub nevent: String,
+    /// 本文のプレビュー
+    pub preview: String,
+    /// 作成日時の Unix タイムスタンプ
+    pub created_at: u64,
+    /// 受け取った反応
+    #[serde(flatten)]
+    pub counts: InteractionCounts,
+}
+
+/// 反応してくれたアカウントと、その反応の件数
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AccountInteraction {
+    /// アカウント情報
+    pub account: AuthorInfo,
+    /// 最新のコンタクトリストで自分をフォローしているか
+    pub follows_you: bool,
+    /// 反応の件数
+    #[serde(flatten)]
+    pub counts: InteractionCounts,
+}
+
+/// ハッシュタグの集計結果（`get_hashtag_stats`）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HashtagStats {
+    /// ハッシュタグ（小文字、# なし）
+    pub hashtag: String,
+    /// 比較する期間の長さ（日数）
+    pub days: u64,
+    /// 集計期間（前の期間を含む）の開始日時
+    pub since: u64,
+    /// 集計期間の終了日時
+    pub until: u64,
+    /// ノート数
+    pub total_notes: u64,
+    /// 投稿したアカウント数
+    pub unique_authors: u64,
+    /// 直近 `days` 日間のノート数
+    pub current_notes: u64,
+    /// その前の `days` 日間のノート数
+    pub previous_notes: u64,
+    /// 前の期間からの増減率（%、前の期間のノートがない・取得件数の上限に達した場合は省略）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_percent: Option<f64>,
+    /// 取得件数の上限に達し、古いノートの一部しか集計できていない場合は true
+    pub sampled: bool,
+    /// 日ごとのノート数（古い順、投稿がない日も含む）
+    pub daily: Vec<DailyPostCount>,
+    /// よく投稿したアカウント
+    pub top_authors: Vec<DigestAuthor>,
+    /// 一緒に使われたハッシュタグ
+    pub related_hashtags: Vec<DigestHashtag>,
+}
+
+/// フォロワー数の推移（`get_follower_growth`）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FollowerGrowth {
+    /// 現在のフォロワー数
+    pub followers: u64,
+    /// 集計期間（日数）
+    pub days: u64,
+    /// 集計期間の開始日時
+    pub since: u64,
+    /// 集計期間の終了日時
+    pub until: u64,
+    /// 記録を始めた日時（最初のスナップショット）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracking_since: Option<u64>,
+    /// 増減の基準にしたスナップショット（期間の開始時点、記録がなければ最初のスナップショット）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<crate::storage::FollowerSnapshot>,
+    /// 基準からのフォロワー数の増減
+    pub change: i64,
+    /// 期間内に増えたフォロワー数
+    pub gained_count: u64,
+    /// 期間内に減ったフォロワー数
+    pub lost_count: u64,
+    /// 期間内に増えたフォロワー（新しい順）
+    pub gained: Vec<FollowerChangeInfo>,
+    /// 期間内に減ったフォロワー（新しい順）
+    pub lost: Vec<FollowerChangeInfo>,
+    /// 日ごとの最後のスナップショット（古い順）
+    pub daily: Vec<crate::storage::FollowerSnapshot>,
+}
+
+/// 増えた・減ったフォロワー
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FollowerChangeInfo {
+    /// アカウント情報
+    pub account: AuthorInfo,
+    /// フォロー・フォロー解除の日時（コンタクトリストの作成日時）
+    pub at: u64,
+}
+
+/// 本文中の `nostr:` 参照の解決結果
+///
+/// プロフィール参照では著者情報のみ、ノート・記事参照では本文のプレビューなども含みます。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ResolvedReference {
+    /// 参照先（ノートの場合は著者）の表示名
+    pub display_name: String,
+    /// 参照先（ノートの場合は著者）の npub
+    pub npub: String,
+    /// 参照先（ノートの場合は著者）の NIP-05 識別子
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nip05: Option<String>,
+    /// 参照先イベントの Kind
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<u16>,
+    /// 記事などのタイトル（title タグ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// 本文（記事の場合は summary）のプレビュー
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+    /// 参照先イベントの作成日時の Unix タイムスタンプ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// 参照先イベントのコンテンツ警告・NSFW ラベルの理由（`sensitive-content` が show 以外ならプレビューを伏せる）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_warning: Option<String>,
+}
+
+/// アカウント移行でコピーしたイベント
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MigratedEvent {
+    /// イベントの Kind
+    pub kind: u16,
+    /// パラメータ付き置換可能イベントの d タグ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+    /// 新しい鍵での公開結果（失敗した場合は None）
+    pub published: Option<PublishResult>,
+    /// 非公開項目を復号・再暗号化できず、コピーに含めなかった場合 true
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub private_items_dropped: bool,
+}
+
+/// アカウント移行の結果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AccountMigration {
+    /// 移行先の npub
+    pub new_npub: String,
+    /// 新しい鍵で公開し直したイベント（新しい鍵を指定しなかった場合は空）
+    pub copied: Vec<MigratedEvent>,
+    /// 移行先を追記したプロフィールの公開結果
+    pub profile_update: Option<PublishResult>,
+    /// 移行を案内するノートの公開結果
+    pub final_note: Option<PublishResult>,
+    /// DM で移行を通知したフォロワー（npub）
+    pub notified: Vec<String>,
+    /// 失敗した手順とその理由
+    pub errors: Vec<String>,
+}
+
+/// 作成したアカウントと初期イベントの公開結果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct NewAccount {
+    /// hex 形式の公開鍵
+    pub pubkey: String,
+    /// npub 形式の公開鍵
+    pub npub: String,
+    /// リレーリストに登録したリレー
+    pub relays: Vec<RelayListEntry>,
+    /// プロフィール (Kind 0) の公開結果（失敗した場合は None）
+    pub profile: Option<PublishResult>,
+    /// リレーリスト (Kind 10002) の公開結果（失敗した場合は None）
+    pub relay_list: Option<PublishResult>,
+    /// 公開に失敗したイベントとその理由
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub publish_errors: Vec<String>,
+}
+
+/// プロフィール情報
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ProfileInfo {
+    /// hex 形式の公開鍵
+    pub pubkey: String,
+    /// npub 形式の公開鍵
+    pub npub: String,
+    /// ユーザー名
+    pub name: Option<String>,
+    /// 表示名
+    pub display_name: Option<String>,
+    /// 自己紹介文
+    pub about: Option<String>,
+    /// プロフィール画像 URL
+    pub picture: Option<String>,
+    /// バナー画像 URL
+    pub banner: Option<String>,
+    /// NIP-05 識別子
+    pub nip05: Option<String>,
+    /// Lightning アドレス (LUD-16)
+    pub lud16: Option<String>,
+    /// ウェブサイト URL
+    pub website: Option<String>,
+}
+
+/// プロフィール統計情報（Phase 3: プロフィールカード用）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ProfileStats {
+    /// フォロー中の数（取得に失敗した場合は null）
+    pub following: Option<u64>,
+    /// フォロワー数（推定値、リレーの対応状況に依存。取得に失敗した場合は null）
+    pub followers: Option<u64>,
+    /// ノート投稿数（推定値。取得に失敗した場合は null）
+    pub notes: Option<u64>,
+    /// 件数の取得方法（"nip45"、"fetch"、"mixed"）
+    pub count_method: String,
+}
+
+/// 2 つのアカウントのフォロー関係
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FollowRelationship {
+    /// アカウント A
+    pub a: AuthorInfo,
+    /// アカウント B
+    pub b: AuthorInfo,
+    /// A が B をフォローしているか
+    pub a_follows_b: bool,
+    /// B が A をフォローしているか
+    pub b_follows_a: bool,
+    /// 関係の種類（"mutual" / "a_follows_b" / "b_follows_a" / "none"）
+    pub relationship: String,
+    /// 両者が共通してフォローしているアカウント数
+    pub common_follows: u64,
+    /// A のフォロー数
+    pub a_following_count: u64,
+    /// B のフォロー数
+    pub b_following_count: u64,
+}
+
+/// フォローのおすすめ（フォローのフォローの分析結果）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FollowRecommendations {
+    /// 自分のフォロー数
+    pub following_count: u64,
+    /// 取得できたフォロー中のアカウントのコンタクトリスト数
+    pub analyzed_contact_lists: u64,
+    /// 条件を満たした候補の総数
+    pub candidates: u64,
+    /// おすすめのアカウント（重なりの多い順）
+    pub recommendations: Vec<FollowRecommendation>,
+}
+
+/// おすすめのアカウント
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FollowRecommendation {
+    /// アカウント情報
+    pub account: AuthorInfo,
+    /// このアカウントをフォローしている、自分のフォロー中のアカウント数
+    pub followed_by_count: u64,
+    /// 分析したコンタクトリストのうち、このアカウントを含む割合（0.0〜1.0）
+    pub overlap_ratio: f64,
+    /// このアカウントをフォローしている、自分のフォロー中のアカウント（一部）
+    pub followed_by: Vec<AuthorInfo>,
+}
+
+/// リプライを書くための文脈（`get_reply_context`）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ReplyContext {
+    /// 対象のノート
+    pub note: NoteInfo,
+    /// 対象のノートがリプライかどうか
+    pub is_reply: bool,
+    /// スレッドのルートノート（対象がリプライで、取得できた場合）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<NoteInfo>,
+    /// 直接の親ノート（ルートと異なる場合のみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<NoteInfo>,
+    /// 著者の最近のノートのうち、対象のノートに関連するもの（新しい順）
+    pub related: Vec<RelatedNote>,
+}
+
+/// 対象のノートに関連する著者のノート
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RelatedNote {
+    /// ノート
+    pub note: NoteInfo,
+    /// 関連の種類（"same_thread" / "reply_to_note" / "shared_hashtag"）
+    pub relation: String,
+}
+
+/// スレッド情報（Phase 2）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ThreadInfo {
+    /// ルートノート
+    pub root: NoteInfo,
+    /// リプライ一覧（ネスト構造）
+    pub replies: Vec<ThreadReply>,
+    /// リプライの総数
+    pub total_replies: u64,
+    /// 取得したリプライの深さ
+    pub depth: u64,
+}
+
+/// スレッドのリプライ（ネスト可能）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ThreadReply {
+    /// リプライノート
+    pub note: NoteInfo,
+    /// さらにネストされたリプライ
+    pub replies: Vec<ThreadReply>,
+}
+
+/// 通知情報（Phase 2）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NotificationInfo {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// nevent 形式のイベント ID
+    pub nevent: String,
+    /// 通知の種類（"mention" / "reaction" / "zap" / "repost" / "follow"）
+    pub notification_type: String,
+    /// 通知元の著者情報
+    pub author: AuthorInfo,
+    /// コンテンツ（リアクションの場合は絵文字、メンションの場合はノート内容）
+    pub content: String,
+    /// リアクション・Zap・リポスト対象のノート ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_note_id: Option<String>,
+    /// Zap の金額（sats、Zap の場合のみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_sats: Option<u64>,
+    /// 作成日時の Unix タイムスタンプ（フォローの場合はフォローを検出したコンタクトリストの日時）
+    pub created_at: u64,
+}
+
+/// リポスト情報（NIP-18）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RepostInfo {
+    /// hex 形式のリポストイベント ID
+    pub id: String,
+    /// nevent 形式のリポストイベント ID
+    pub nevent: String,
+    /// リポストした著者の情報
+    pub author: AuthorInfo,
+    /// 作成日時の Unix タイムスタンプ
+    pub created_at: u64,
+}
+
+/// ノートのリポスト・引用一覧（NIP-18）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NoteRepostsInfo {
+    /// 対象ノートの hex イベント ID
+    pub note_id: String,
+    /// リポスト (Kind 6) 一覧
+    pub reposts: Vec<RepostInfo>,
+    /// 引用ノート (Kind 1) 一覧
+    pub quotes: Vec<NoteInfo>,
+}
+
+/// ノートのエンゲージメント集計
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NoteEngagement {
+    /// 対象ノートの hex イベント ID
+    pub note_id: String,
+    /// リアクション数 (Kind 7)
+    ///
+    /// 各件数は取得に失敗した場合 null（不明）になり、0 件とは区別されます。
+    pub reactions: Option<u64>,
+    /// リプライ数 (Kind 1)
+    pub replies: Option<u64>,
+    /// リポスト数 (Kind 6)
+    pub reposts: Option<u64>,
+    /// 引用数（q タグ付き Kind 1）
+    pub quotes: Option<u64>,
+    /// Zap 数 (Kind 9735)
+    pub zap_count: Option<u64>,
+    /// Zap 合計金額（sats）
+    pub zap_total_sats: Option<u64>,
+    /// 真正性を確認できず集計から除外した Zap レシートの数
+    pub invalid_zap_count: Option<u64>,
+    /// 件数の取得方法（"nip45"、"fetch"、"mixed"）
+    pub count_method: String,
+}
+
+/// ユーザーステータス情報（NIP-38）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UserStatusInfo {
+    /// ステータス種別（"general" または "music"）
+    pub status_type: String,
+    /// ステータス本文
+    pub content: String,
+    /// 関連リンク（r タグ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// 失効日時の Unix タイムスタンプ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<u64>,
+    /// 作成日時の Unix タイムスタンプ
+    pub created_at: u64,
+}
+
+/// バッジ定義情報（NIP-58, Kind 30009）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BadgeDefinitionInfo {
+    /// naddr 形式のアドレス
+    pub naddr: String,
+    /// 識別子（d タグ）
+    pub identifier: String,
+    /// バッジ名
+    pub name: String,
+    /// 説明
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// バッジ画像 URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// サムネイル画像 URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumb: Option<String>,
+    /// 発行者の情報
+    pub issuer: AuthorInfo,
+    /// 作成日時の Unix タイムスタンプ
+    pub created_at: u64,
+}
+
+/// プロフィールに表示されているバッジ（NIP-58, Kind 30008）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ProfileBadgeInfo {
+    /// バッジ定義
+    pub badge: BadgeDefinitionInfo,
+    /// 授与イベント (Kind 8) の hex ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub award_id: Option<String>,
+    /// 発行者による授与イベントが確認できたか
+    pub verified: bool,
+}
+
+/// 動画投稿のパラメータ（NIP-71）
+#[derive(Debug, Clone)]
+pub struct VideoParams {
+    /// タイトル
+    pub title: String,
+    /// 説明
+    pub description: Option<String>,
+    /// 動画ファイルのメタデータ（imeta タグ）
+    pub media: crate::content::MediaMetadata,
+    /// ショート動画 (Kind 22) として投稿するか
+    pub short: bool,
+    /// ハッシュタグ
+    pub hashtags: Option<Vec<String>>,
+}
+
+/// 動画情報（Kind 21/22, NIP-71）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct VideoInfo {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// nevent 形式のイベント ID
+    pub nevent: String,
+    /// 種類（"normal": Kind 21、"short": Kind 22）
+    pub video_type: String,
+    /// タイトル
+    pub title: String,
+    /// 説明
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 主となる動画 URL
+    pub url: String,
+    /// サムネイル画像 URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+    /// 再生時間（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    /// 解像度・形式ごとのバリエーション（imeta タグ）
+    pub variants: Vec<crate::content::MediaMetadata>,
+    /// ハッシュタグ
+    pub hashtags: Vec<String>,
+    /// 作成日時（Unix タイムスタンプ）
+    pub created_at: u64,
+    /// 投稿者の情報
+    pub author: AuthorInfo,
+}
+
+// ========================================
+// Phase 4: データ構造体
+// ========================================
+
+/// Lightning インボイス情報（LUD-06 / LUD-16）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LightningInvoiceInfo {
+    /// bolt11 インボイス
+    pub bolt11: String,
+    /// 金額（sats）
+    pub amount_sats: u64,
+    /// 支払いハッシュ（hex）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_hash: Option<String>,
+    /// インボイスの有効期限（Unix タイムスタンプ）
+    pub expires_at: u64,
+    /// 解決に使用した Lightning アドレスまたは LNURL
+    pub lightning_address: String,
+    /// 受取人の公開鍵（プロフィールから解決した場合）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient_pubkey: Option<String>,
+    /// Zap リクエストを添付したか（支払い後に Zap レシートが発行される）
+    pub is_zap: bool,
+}
+
+/// Lightning 支払い結果
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LightningPaymentInfo {
+    /// 支払ったインボイス
+    pub invoice: LightningInvoiceInfo,
+    /// 支払いのプリイメージ（支払い完了の証明）
+    pub preimage: String,
+}
+
+/// このサーバーでの支払い上限と使用状況（payment-limits）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WalletBudget {
+    /// 1 回の支払いの上限（sats）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_per_payment_sats: Option<u64>,
+    /// 直近 24 時間の支払い合計の上限（sats）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_limit_sats: Option<u64>,
+    /// 直近 24 時間に支払った合計（sats）
+    pub spent_24h_sats: u64,
+    /// 日次上限までの残り（sats）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_daily_sats: Option<u64>,
+}
+
+/// リアクションのプリセットの一覧
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReactionPresets {
+    /// `reaction` 省略時のリアクション（プリセット名の場合あり）
+    pub default_reaction: String,
+    /// プリセット（名前順）
+    pub presets: Vec<ReactionPresetInfo>,
+}
+
+/// リアクションのプリセット
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReactionPresetInfo {
+    /// プリセット名（`react_to_note` の `reaction` に指定）
+    pub name: String,
+    /// 送信するリアクション
+    pub content: String,
+    /// カスタム絵文字の画像 URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emoji_url: Option<String>,
+    /// 1 時間あたりの上限
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_per_hour: Option<u32>,
+    /// 直近 1 時間にこのサーバーから送信した回数
+    pub sent_last_hour: u32,
+}
+
+/// カスタム絵文字（NIP-30）
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CustomEmoji {
+    /// ショートコード（本文中では `:shortcode:`）
+    pub shortcode: String,
+    /// 画像 URL
+    pub url: String,
+}
+
+impl CustomEmoji {
+    /// emoji タグ（`["emoji", <shortcode>, <url>]`）
+    fn to_tag(&self) -> Tag {
+        Tag::custom(TagKind::custom("emoji"), vec![self.shortcode.clone(), self.url.clone()])
+    }
+}
+
+/// 絵文字セット（NIP-51, Kind 30030）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct EmojiSetInfo {
+    /// naddr 形式のアドレス
+    pub naddr: String,
+    /// 識別子（d タグ）
+    pub identifier: String,
+    /// タイトル
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// 作成者の情報
+    pub author: AuthorInfo,
+    /// 絵文字リスト (Kind 10030) で参照されているか
+    pub in_emoji_list: bool,
+    /// 絵文字
+    pub emojis: Vec<CustomEmoji>,
+    /// 作成日時の Unix タイムスタンプ
+    pub created_at: u64,
+}
+
+/// 絵文字リストと絵文字セット（`get_emoji_sets`）
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct EmojiCollection {
+    /// 絵文字リスト (Kind 10030) に直接登録された絵文字
+    pub emojis: Vec<CustomEmoji>,
+    /// 絵文字セット（絵文字リストで参照しているもの、作成したものの順）
+    pub sets: Vec<EmojiSetInfo>,
+}
+
+impl EmojiCollection {
+    /// ショートコード → 画像 URL
+    ///
+    /// 同じショートコードは、絵文字リストの絵文字、参照しているセット、その他のセットの順に優先します。
+    fn shortcode_map(&self) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        let emojis = self.emojis.iter()
+            .chain(self.sets.iter().filter(|s| s.in_emoji_list).flat_map(|s| &s.emojis))
+            .chain(self.sets.iter().filter(|s| !s.in_emoji_list).flat_map(|s| &s.emojis));
+        for emoji in emojis {
+            map.entry(emoji.shortcode.clone()).or_insert_with(|| emoji.url.clone());
+        }
+        map
+    }
+}
+
+/// アドレス帳の別名（`resolve_alias`）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ContactAliasInfo {
+    /// 正規化した別名
+    pub alias: String,
+    /// 登録された公開鍵のプロフィール
+    pub author: AuthorInfo,
+    /// メモ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// 登録（更新）した日時
+    pub added_at: u64,
+}
+
+/// リレー発見の条件（`discover_relays`）
+#[derive(Debug, Clone, Default)]
+pub struct RelayDiscoveryQuery {
+    /// すべてに対応している必要がある NIP
+    pub nips: Vec<u16>,
+    /// 国コード（ISO 3166-1 alpha-2、大文字小文字を区別しない）
+    pub country: Option<String>,
+    /// ジオハッシュの接頭辞
+    pub geohash: Option<String>,
+    /// ネットワーク（"clearnet"、"tor" など）
+    pub network: Option<String>,
+    /// 認証（NIP-42）や支払いが必要なリレーを除外するか
+    pub open_only: bool,
+    /// レポートを信頼するモニターの公開鍵（空の場合はすべて）
+    pub monitors: Vec<String>,
+    /// レポートを取得するリレー（空の場合は DEFAULT_MONITOR_RELAYS）
+    pub monitor_relays: Vec<String>,
+    /// 稼働率の目安の下限（0〜1）
+    pub min_availability: f64,
+}
+
+impl RelayDiscoveryQuery {
+    /// 正常に稼働していて、条件に合うリレーか
+    fn matches(&self, relay: &DiscoveredRelay) -> bool {
+        relay.fresh_monitors > 0
+            && relay.availability >= self.min_availability
+            && self.nips.iter().all(|nip| relay.supported_nips.contains(nip))
+            && self.country.as_ref().is_none_or(|c| relay.countries.iter().any(|rc| rc.eq_ignore_ascii_case(c)))
+            && self.geohash.as_ref().is_none_or(|g| relay.geohashes.iter().any(|rg| rg.starts_with(&g.to_lowercase())))
+            && self.network.as_ref().is_none_or(|n| relay.network.as_deref().is_none_or(|rn| rn.eq_ignore_ascii_case(n)))
+            && !(self.open_only && relay.requirements.iter().any(|r| r == "auth" || r == "payment"))
+    }
+}
+
+/// リレー発見の結果（`discover_relays`）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RelayDiscovery {
+    /// レポートを公開していたモニターの数
+    pub monitors: u64,
+    /// 取得したレポート (Kind 30166) の数
+    pub reports: u64,
+    /// 条件に合ったリレーの数
+    pub matched: u64,
+    /// 条件に合ったリレー（良い順、最大 limit 件）
+    pub relays: Vec<DiscoveredRelay>,
+    /// リレープールに追加したリレー
+    pub added_to_pool: Vec<String>,
+}
+
+/// NIP-66 のモニターのレポートから集計したリレーの状態
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct DiscoveredRelay {
+    /// リレー URL
+    pub url: String,
+    /// レポートしたモニターの数
+    pub monitors: u64,
+    /// 確認間隔内に最新のレポートをしたモニターの数
+    pub fresh_monitors: u64,
+    /// 稼働率の目安（fresh_monitors / monitors）
+    pub availability: f64,
+    /// 最新のレポートの日時
+    pub last_seen_at: u64,
+    /// 接続の RTT（ミリ秒、最新のレポートの中央値）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtt_open_ms: Option<u64>,
+    /// 読み取りの RTT（ミリ秒、最新のレポートの中央値）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtt_read_ms: Option<u64>,
+    /// ネットワーク（"clearnet"、"tor" など）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// 対応 NIP
+    pub supported_nips: Vec<u16>,
+    /// 利用条件（"auth"、"payment" など。"!" で始まるものは不要の意味のため除外）
+    pub requirements: Vec<String>,
+    /// 国コード
+    pub countries: Vec<String>,
+    /// ジオハッシュ
+    pub geohashes: Vec<String>,
+    /// このセッションのリレープールに含まれているか
+    pub in_pool: bool,
+}
+
+/// リレーの計測結果（`benchmark_relays`）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RelayBenchmark {
+    /// 順位（1 が最良）
+    pub rank: usize,
+    /// リレー URL
+    pub url: String,
+    /// リレープールに含まれているか
+    pub in_pool: bool,
+    /// 接続できたか
+    pub connected: bool,
+    /// 接続にかかった時間（ミリ秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<u64>,
+    /// 標準フィルタでの取得にかかった時間（ミリ秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_ms: Option<u64>,
+    /// 標準フィルタで取得できたイベント数
+    pub events: u64,
+    /// リレー名（NIP-11）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// NIP-50（検索）に対応しているか（NIP-11 を取得できなかった場合は省略）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_search: Option<bool>,
+    /// NIP-45（COUNT）に対応しているか（NIP-11 を取得できなかった場合は省略）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_count: Option<bool>,
+    /// 接続・取得のエラー
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RelayBenchmark {
+    /// 並べ替えのキー（接続でき、取得に成功し、イベントを返したリレーを優先し、その中で合計時間の短い順）
+    fn ranking_key(&self) -> (bool, bool, bool, u64) {
+        let total_ms = self.connect_ms.unwrap_or(u64::MAX).saturating_add(self.query_ms.unwrap_or(u64::MAX));
+        (!self.connected, self.query_ms.is_none(), self.events == 0, total_ms)
+    }
+}
+
+/// NWC ウォレットの接続確認結果（NIP-47）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WalletConnectionStatus {
+    /// NWC URI のリレー
+    pub relay_url: String,
+    /// リレーに接続できたか
+    pub relay_reachable: bool,
+    /// リレーへの接続にかかった時間（ミリ秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relay_latency_ms: Option<u64>,
+    /// ウォレットサービスの公開鍵（hex）
+    pub wallet_pubkey: String,
+    /// ウォレットサービスが対応するメソッド（Kind 13194 の情報イベント）
+    pub supported_methods: Vec<String>,
+    /// ウォレットサービスが対応する暗号化方式
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub encryption: Vec<String>,
+    /// ウォレットサービスが送信する通知の種類
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notifications: Vec<String>,
+    /// この接続に許可されたメソッド（get_info）
+    pub permitted_methods: Vec<String>,
+    /// ノードのエイリアス
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// ノードのネットワーク（mainnet、testnet など）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// ノードが認識しているブロック高
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_height: Option<u32>,
+    /// get_info が失敗した理由
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub get_info_error: Option<String>,
+    /// 残高（sats、get_balance が許可されている場合のみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance_sats: Option<u64>,
+    /// このサーバーでの支払い上限と使用状況
+    pub budget: WalletBudget,
+    /// 支払いに使えるか（リレーに接続でき、pay_invoice が許可されている）
+    pub can_pay: bool,
+}
+
+/// ダイレクトメッセージ情報（NIP-04）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DirectMessageInfo {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// nevent 形式のイベント ID
+    pub nevent: String,
+    /// 送信者の情報
+    pub author: AuthorInfo,
+    /// 復号済みメッセージ内容（読み取り専用モードでは復号できない旨の表示）
+    pub content: String,
+    /// 復号できたか（publickey のみの読み取り専用モードでは false）
+    pub decrypted: bool,
+    /// メッセージの方向（"sent" または "received"）
+    pub direction: String,
+    /// 会話相手の pubkey (hex)
+    pub peer_pubkey: String,
+    /// 作成日時の Unix タイムスタンプ
+    pub created_at: u64,
+}
+
+/// 購読で受信したイベント
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SubscriptionEvent {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// nevent 形式のイベント ID
+    pub nevent: String,
+    /// イベントの Kind
+    pub kind: u16,
+    /// 著者情報
+    pub author: AuthorInfo,
+    /// 内容（DM は復号済み）
+    pub content: String,
+    /// 作成日時の Unix タイムスタンプ
+    pub created_at: u64,
+}
+
+/// イベントのエクスポート結果
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct EventExport {
+    /// 書き出したファイルのパス
+    pub path: String,
+    /// 書き出したイベント数
+    pub count: u64,
+    /// Kind ごとのイベント数
+    pub kinds: std::collections::BTreeMap<u16, u64>,
+    /// 最も古いイベントの日時
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest_at: Option<u64>,
+    /// 最も新しいイベントの日時
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub newest_at: Option<u64>,
+    /// 上限に達したため古いイベントが含まれていない可能性があるか
+    pub truncated: bool,
+}
+
+/// イベントのインポート結果
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct EventImport {
+    /// 読み込んだファイルのパス
+    pub path: String,
+    /// 署名を検証できたイベント数
+    pub valid: u64,
+    /// 読み込めなかった・署名が不正な行数
+    pub invalid: u64,
+    /// 読み込めなかった行の詳細（先頭の一部のみ）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<crate::backup::BackupLineError>,
+    /// 1 つ以上のリレーに受け付けられたイベント数
+    pub published: u64,
+    /// どのリレーにも受け付けられなかったイベント数
+    pub failed: u64,
+    /// 送信先のリレー
+    pub relays: Vec<String>,
+    /// 検証のみ行ったか
+    pub dry_run: bool,
+}
+
+/// OpenTimestamps 証明の状態
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OtsStatus {
+    /// 対象のイベント ID（hex）
+    pub event_id: String,
+    /// "pending"（Bitcoin での確定待ち）または "attested"（Kind 1040 を公開済み）
+    pub status: String,
+    /// 確定待ちのカレンダーサーバー
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pending_calendars: Vec<String>,
+    /// 証明が取り込まれた Bitcoin のブロック高
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bitcoin_block: Option<u64>,
+    /// 公開したアテステーションイベント (Kind 1040) の ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attestation_id: Option<String>,
+    /// 今回の呼び出しで証明を新たに作成したか
+    pub created: bool,
+    /// アテステーションイベントを受け付けたリレー
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_to: Option<Vec<String>>,
+    /// アテステーションイベントを受け付けなかったリレー
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_relays: Option<Vec<FailedRelay>>,
+}
+
+impl OtsStatus {
+    fn pending(event_id: &str, proof: &crate::ots::OtsTimestamp, created: bool) -> Self {
+        let mut pending_calendars: Vec<String> =
+            proof.pending_attestations().into_iter().map(|(uri, _)| uri).collect();
+        pending_calendars.sort();
+        pending_calendars.dedup();
+        Self {
+            event_id: event_id.to_string(),
+            status: "pending".to_string(),
+            pending_calendars,
+            bitcoin_block: None,
+            attestation_id: None,
+            created,
+            published_to: None,
+            failed_relays: None,
+        }
+    }
+}
+
+/// 指定リレーへの再送信結果
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct EventRebroadcast {
+    /// 送信先のリレー
+    pub relay: String,
+    /// 対象として見つかったイベント数
+    pub found: u64,
+    /// リレーに受け付けられたイベント数
+    pub published: u64,
+    /// 拒否された・送信に失敗したイベント数
+    pub failed: u64,
+    /// 拒否されたイベント（先頭の一部のみ）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rejected: Vec<RejectedEvent>,
+    /// 上限に達したため古いイベントが含まれていない可能性があるか
+    pub truncated: bool,
+}
+
+/// イベント公開のリレーごとの結果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PublishResult {
+    /// 公開したイベントの ID
+    #[serde(skip)]
+    pub id: EventId,
+    /// 著者と受け付けたリレーをヒントに含めた nevent
+    pub nevent: String,
+    /// イベントを受け付けたリレー
+    pub published_to: Vec<String>,
+    /// イベントを受け付けなかったリレー
+    pub failed_relays: Vec<FailedRelay>,
+}
+
+impl PublishResult {
+    /// 送信結果から作成（`author` は nevent に含める著者）
+    pub(crate) fn new(output: Output<EventId>, author: Option<PublicKey>) -> Self {
+        let mut published_to: Vec<String> = output.success.iter().map(|url| url.to_string()).collect();
+        published_to.sort();
+        let mut failed_relays: Vec<FailedRelay> = output.failed.iter()
+            .map(|(url, reason)| FailedRelay { relay: url.to_string(), reason: reason.clone() })
+            .collect();
+        failed_relays.sort_by(|a, b| a.relay.cmp(&b.relay));
+        Self {
+            id: output.val,
+            nevent: encode_nevent(&output.val, author, &published_to),
+            published_to,
+            failed_relays,
+        }
+    }
+}
+
+/// 公開したイベントの配信確認の結果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PublishVerification {
+    /// 1 つ以上のリレーからイベントを取得できたか
+    pub verified: bool,
+    /// イベントを取得できたリレー
+    pub served_by: Vec<String>,
+    /// 受け付けたのにイベントを返さなかったリレー
+    pub missing: Vec<FailedRelay>,
+}
+
+/// 送信待ちキューのイベント
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PendingPublishInfo {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// nevent 形式のイベント ID
+    pub nevent: String,
+    /// イベントの Kind
+    pub kind: u16,
+    /// 本文の先頭部分
+    pub content_preview: String,
+    /// キューに追加した日時
+    pub queued_at: u64,
+    /// 再送を試みた回数
+    pub attempts: u32,
+    /// 次に自動で再送する日時（自動再送の上限回数に達した場合は None）
+    pub next_attempt_at: Option<u64>,
+    /// 直近の送信エラー
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+impl From<&crate::storage::PendingPublish> for PendingPublishInfo {
+    fn from(pending: &crate::storage::PendingPublish) -> Self {
+        let event = &pending.event;
+        Self {
+            id: event.id.to_hex(),
+            nevent: encode_nevent(&event.id, Some(event.pubkey), &[]),
+            kind: event.kind.as_u16(),
+            content_preview: truncate_preview(&event.content, DM_PREVIEW_CHARS),
+            queued_at: pending.queued_at,
+            attempts: pending.attempts,
+            next_attempt_at: (pending.attempts < PENDING_PUBLISH_MAX_ATTEMPTS).then_some(pending.next_attempt_at),
+            last_error: pending.last_error.clone(),
+        }
+    }
+}
+
+/// 送信待ちキューの再送・削除の結果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PendingFlush {
+    /// 再送できたイベント
+    pub published: Vec<FlushedPublish>,
+    /// 再送できなかったイベント
+    pub failed: Vec<RejectedEvent>,
+    /// 送信せずに削除したイベント数
+    pub discarded: usize,
+    /// キューに残っているイベント数
+    pub remaining: usize,
+}
+
+/// 送信待ちキューから再送できたイベント
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FlushedPublish {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// リレーごとの結果
+    #[serde(flatten)]
+    pub result: PublishResult,
+}
+
+/// イベントを受け付けなかったリレー
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FailedRelay {
+    /// リレーの URL
+    pub relay: String,
+    /// 失敗理由（リレーの OK メッセージまたは送信エラー）
+    pub reason: String,
+}
+
+/// リレーに拒否されたイベント
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RejectedEvent {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// 拒否理由（リレーの OK メッセージ）
+    pub reason: String,
+}
+
+/// DM の会話（相手ごとのまとめ）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DmConversation {
+    /// 会話相手の情報
+    pub peer: AuthorInfo,
+    /// 最新メッセージのプレビュー（復号済み、先頭のみ）
+    pub last_message: String,
+    /// 最新メッセージの方向（"sent" または "received"）
+    pub last_direction: String,
+    /// 最新メッセージの日時（Unix タイムスタンプ）
+    pub last_message_at: u64,
+    /// 取得範囲内のメッセージ数
+    pub message_count: u64,
+    /// 未読の受信メッセージ数
+    pub unread_count: u64,
+}
+
+/// リレーリスト情報（NIP-65）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RelayListInfo {
+    /// hex 形式の公開鍵
+    pub pubkey: String,
+    /// npub 形式の公開鍵
+    pub npub: String,
+    /// リレー一覧
+    pub relays: Vec<RelayListEntry>,
+}
+
+/// リレーリストのエントリ
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RelayListEntry {
+    /// リレー URL
+    pub url: String,
+    /// 読み取り可能
+    pub read: bool,
+    /// 書き込み可能
+    pub write: bool,
+}
+
+/// 記事投稿のパラメータ
+#[derive(Debug, Clone)]
+pub struct ArticleParams {
+    /// 記事タイトル
+    pub title: String,
+    /// Markdown コンテンツ
+    pub content: String,
+    /// 識別子（d タグ、未指定時はタイトルから自動生成）
+    pub identifier: Option<String>,
+    /// 要約
+    pub summary: Option<String>,
+    /// ヘッダー画像 URL
+    pub image: Option<String>,
+    /// トピックハッシュタグ
+    pub tags: Option<Vec<String>>,
+    /// 公開日時の Unix タイムスタンプ
+    pub published_at: Option<u64>,
+}
+
+/// シリーズ作成のパラメータ（`create_publication`）
+#[derive(Debug, Clone, Default)]
+pub struct PublicationParams {
+    /// シリーズのタイトル
+    pub title: String,
+    /// 識別子（d タグ、未指定時はタイトルから自動生成）
+    pub identifier: Option<String>,
+    /// 説明
+    pub description: Option<String>,
+    /// カバー画像 URL
+    pub image: Option<String>,
+    /// 最初に含める記事のアドレス（順番どおり）
+    pub articles: Vec<String>,
+}
+
+/// 記事のシリーズ（NIP-51 キュレーションセット, Kind 30004）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PublicationInfo {
+    /// naddr 形式のアドレス
+    pub naddr: String,
+    /// 識別子（d タグ）
+    pub identifier: String,
+    /// タイトル
+    pub title: String,
+    /// 説明
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// カバー画像 URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// 作成者の情報
+    pub author: AuthorInfo,
+    /// 作成（更新）日時の Unix タイムスタンプ
+    pub created_at: u64,
+    /// シリーズ内の記事（順番どおり）
+    pub articles: Vec<PublicationEntry>,
+}
+
+/// シリーズ内の記事
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PublicationEntry {
+    /// シリーズ内の位置（1 始まり）
+    pub position: usize,
+    /// 記事の naddr
+    pub naddr: String,
+    /// 記事を取得できたか
+    pub found: bool,
+    /// 記事（取得できなかった場合は省略）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub article: Option<ArticleInfo>,
+}
+
+/// 記事のバージョン履歴
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ArticleHistoryInfo {
+    /// 識別子（d タグ）
+    pub identifier: String,
+    /// 記事の naddr
+    pub naddr: String,
+    /// バージョン（古い順）
+    pub versions: Vec<ArticleVersionSummary>,
+}
+
+/// 記事の 1 つのバージョンの概要
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ArticleVersionSummary {
+    /// バージョン番号（1 始まり、古い順）
+    pub version: usize,
+    /// イベント ID（hex）
+    pub event_id: String,
+    /// 記事タイトル
+    pub title: String,
+    /// 要約
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// 作成日時の Unix タイムスタンプ
+    pub created_at: u64,
+    /// 本文の文字数
+    pub characters: usize,
+    /// 直前のバージョンから追加された行数
+    pub added_lines: usize,
+    /// 直前のバージョンから削除された行数
+    pub removed_lines: usize,
+}
+
+/// バージョン履歴のある記事の概要
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ArticleHistoryOverview {
+    /// 識別子（d タグ）
+    pub identifier: String,
+    /// 記事の naddr
+    pub naddr: String,
+    /// 最新バージョンのタイトル
+    pub title: String,
+    /// 保存されているバージョン数
+    pub versions: usize,
+    /// 最新バージョンの作成日時
+    pub updated_at: u64,
+}
+
+/// 変更前後の値
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TextChange {
+    /// 変更前
+    pub before: String,
+    /// 変更後
+    pub after: String,
+}
+
+/// 記事の 2 つのバージョンの比較結果
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ArticleVersionDiff {
+    /// 識別子（d タグ）
+    pub identifier: String,
+    /// 記事の naddr
+    pub naddr: String,
+    /// 比較元のバージョン番号
+    pub from: usize,
+    /// 比較先のバージョン番号
+    pub to: usize,
+    /// 比較元のイベント ID
+    pub from_event_id: String,
+    /// 比較先のイベント ID
+    pub to_event_id: String,
+    /// 比較元の作成日時
+    pub from_created_at: u64,
+    /// 比較先の作成日時
+    pub to_created_at: u64,
+    /// タイトルの変更（変更がない場合は省略）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title_changed: Option<TextChange>,
+    /// 要約の変更
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary_changed: Option<TextChange>,
+    /// ヘッダー画像の変更
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_changed: Option<TextChange>,
+    /// 追加されたハッシュタグ
+    pub tags_added: Vec<String>,
+    /// 削除されたハッシュタグ
+    pub tags_removed: Vec<String>,
+    /// 本文の行単位の差分
+    pub diff: crate::diff::TextDiff,
+}
+
+/// 記事情報（NIP-23 長文コンテンツ）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ArticleInfo {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// nevent 形式のイベント ID
+    pub nevent: String,
+    /// naddr 形式のアドレス（アドレス可能なイベント用）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub naddr: Option<String>,
+    /// 識別子（d タグ）
+    pub identifier: String,
+    /// 記事タイトル
+    pub title: String,
+    /// 要約
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// ヘッダー画像 URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Markdown コンテンツ
+    pub content: String,
+    /// 著者情報
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<AuthorInfo>,
+    /// 公開日時の Unix タイムスタンプ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<u64>,
+    /// 作成日時の Unix タイムスタンプ
+    pub created_at: u64,
+    /// ハッシュタグ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// 下書きかどうか
+    pub is_draft: bool,
+}
+
+// ========================================
+// ユーティリティ関数
+// ========================================
+
+/// フォロー中のアカウントのコンタクトリストから、フォローのおすすめ候補を順位付け
+///
+/// 候補ごとにフォローしている自分のフォロー（元のフォロー順）を集め、その人数の多い順
+/// （同数なら公開鍵順）に並べます。自分・フォロー済み・`excluded` が true の候補と、
+/// `min_overlap` 人未満の候補は除外します。
+fn rank_follow_candidates(
+    me: PublicKey,
+    follows: &[PublicKey],
+    lists: &HashMap<PublicKey, Event>,
+    min_overlap: usize,
+    excluded: impl Fn(&PublicKey) -> bool,
+) -> Vec<(PublicKey, Vec<PublicKey>)> {
+    let following: std::collections::HashSet<&PublicKey> = follows.iter().collect();
+    let mut candidates: HashMap<PublicKey, Vec<PublicKey>> = HashMap::new();
+    for follow in follows {
+        let Some(list) = lists.get(follow) else { continue };
+        let mut seen = std::collections::HashSet::new();
+        for candidate in list.tags.public_keys() {
+            if *candidate == me || following.contains(candidate) || !seen.insert(*candidate) {
+                continue;
+            }
+            candidates.entry(*candidate).or_default().push(*follow);
+        }
+    }
+
+    let mut ranked: Vec<(PublicKey, Vec<PublicKey>)> = candidates.into_iter()
+        .filter(|(candidate, followed_by)| followed_by.len() >= min_overlap.max(1) && !excluded(candidate))
+        .collect();
+    ranked.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+/// バージョン番号（1 始まり、負の値は最新から数える）を 1 始まりの番号に解決
+fn resolve_version_number(number: i64, count: usize) -> Result<usize> {
+    let resolved = if number < 0 { count as i64 + 1 + number } else { number };
+    if resolved < 1 || resolved > count as i64 {
+        return Err(anyhow!("バージョン {} はありません（1〜{} を指定してください）", number, count));
+    }
+    Ok(resolved as usize)
+}
+
+/// 比較する 2 つのバージョン番号を解決（`to` の省略時は最新、`from` の省略時は `to` の直前）
+///
+/// `from` を省略して `to` が最初のバージョンの場合は、直前のバージョンがないためエラーにします。
+fn resolve_diff_versions(from: Option<i64>, to: Option<i64>, count: usize) -> Result<(usize, usize)> {
+    let to = resolve_version_number(to.unwrap_or(-1), count)?;
+    let from = match from {
+        Some(from) => resolve_version_number(from, count)?,
+        None if to == 1 => {
+            return Err(anyhow!("バージョン 1 より前のバージョンはないため、from を指定してください"));
+        }
+        None => to - 1,
+    };
+    Ok((from, to))
+}
+
+/// タイトルから URL 用スラッグを生成
+pub(crate) fn slug_from_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else if c == ' ' {
+                '-'
+            } else {
+                // 日本語等のマルチバイト文字はそのまま保持
+                c
+            }
+        })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string()
+}
+
+/// イベントのタグから指定されたキーの値を抽出
+pub(crate) fn extract_tag_value(event: &Event, key: &str) -> Option<String> {
+    event.tags.iter().find_map(|tag| {
+        let values = tag.as_slice();
+        if values.len() >= 2 && values[0] == key {
+            Some(values[1].to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// リポスト (Kind 6, NIP-18) の content に埋め込まれた元のノート（署名が正しい Kind 1 のみ）
+fn embedded_reposted_note(repost: &Event) -> Option<Event> {
+    let event = Event::from_json(&repost.content).ok()?;
+    (event.kind == Kind::TextNote && event.verify().is_ok()).then_some(event)
+}
+
+/// 置換可能イベントを（Kind, d タグ）ごとに最新の 1 件にまとめる（Kind・d タグ順）
+fn latest_replaceable_events(events: Vec<Event>) -> Vec<Event> {
+    let mut latest: std::collections::BTreeMap<(u16, String), Event> = std::collections::BTreeMap::new();
+    for event in events {
+        let key = (event.kind.as_u16(), event.tags.identifier().unwrap_or_default().to_string());
+        match latest.get(&key) {
+            Some(existing) if existing.created_at >= event.created_at => {}
+            _ => {
+                latest.insert(key, event);
+            }
+        }
+    }
+    latest.into_values().collect()
+}
+
+/// 設定ファイルの delegation.token（NIP-26 の delegation タグの JSON）をパースし、委任先の鍵に対する署名を検証
+fn parse_delegation_token(token: &str, delegatee: &PublicKey) -> Result<DelegationTag> {
+    let delegation = DelegationTag::from_json(token.trim())
+        .context("設定ファイルの delegation.token が不正です（[\"delegation\", \"<委任者の hex 公開鍵>\", \"<条件>\", \"<署名>\"] 形式）")?;
+    verify_delegation_signature(
+        &delegation.delegator_pubkey(),
+        delegation.signature(),
+        delegatee,
+        &delegation.conditions(),
+    )
+    .context("delegation.token の署名が delegatee-key の公開鍵に対するものではありません")?;
+    Ok(delegation)
+}
+
+/// NIP-26 の委任で署名されたイベントの委任者
+///
+/// delegation タグの署名が検証でき、イベントの種類・作成日時が委任の条件を満たす場合のみ返します。
+fn delegator_of(event: &Event) -> Option<PublicKey> {
+    let tag = event.tags.iter().find(|tag| tag.kind() == TagKind::Delegation)?;
+    let delegation = DelegationTag::try_from(tag.as_slice().to_vec()).ok()?;
+    delegation.validate(&event.pubkey, &EventProperties::from_event(event)).ok()?;
+    Some(delegation.delegator_pubkey())
+}
+
+/// イベントの作成者（委任署名されたイベントは委任者、それ以外は署名者）
+///
+/// 取得済みのイベントの表示に使うもので、著者を指定した取得には影響しません（リレーへの問い合わせは署名者の公開鍵で行われます）。
+fn event_author(event: &Event) -> PublicKey {
+    delegator_of(event).unwrap_or(event.pubkey)
+}
+
+/// 作成者と署名者が異なる（委任署名された）場合の署名者の npub
+fn signed_by(event: &Event, author: &PublicKey) -> Option<String> {
+    (event.pubkey != *author).then(|| event.pubkey.to_bech32().unwrap_or_default())
+}
+
+/// ノートが他のノートへのリプライ（NIP-10）かどうかを判定
+///
+/// root / reply マーカー付きの e タグ、またはマーカーのない e タグ（非推奨の位置指定方式）があればリプライとみなします。
+/// mention マーカーの e タグと q タグ（引用）はリプライとしません。
+fn is_reply(event: &Event) -> bool {
+    event.kind == Kind::TextNote && reply_e_tags(event).iter().any(|(_, marker)| is_reply_marker(*marker))
+}
+
+/// イベントの e タグ（イベント ID の hex, NIP-10 のマーカー）の一覧（空のマーカーは None）
+fn reply_e_tags(event: &Event) -> Vec<(&str, Option<&str>)> {
+    event.tags.iter()
+        .map(|tag| tag.as_slice())
+        .filter(|values| values.len() >= 2 && values[0] == "e")
+        .map(|values| (values[1].as_str(), values.get(3).map(|m| m.as_str()).filter(|m| !m.is_empty())))
+        .collect()
+}
+
+/// e タグのマーカーがリプライを表すか（root / reply、またはマーカーなしの位置指定方式）
+fn is_reply_marker(marker: Option<&str>) -> bool {
+    matches!(marker, None | Some("root") | Some("reply"))
+}
+
+/// ノートが `target` へのリプライ（NIP-10）かどうかを判定
+///
+/// `target` を指す e タグが root / reply マーカー付き、またはマーカーなし（位置指定方式）の場合にリプライとみなします。
+/// mention マーカーで `target` に言及しているだけのノートはリプライとしません。
+fn is_reply_to(event: &Event, target: &EventId) -> bool {
+    let target = target.to_hex();
+    event.kind == Kind::TextNote
+        && reply_e_tags(event).iter().any(|(id, marker)| *id == target && is_reply_marker(*marker))
+}
+
+/// リプライの直接の親ノート（NIP-10）
+///
+/// reply マーカーの e タグ、なければ root マーカーの e タグ、マーカーがなければ最後の e タグを親とします。
+/// mention マーカーの e タグは親としません。
+fn reply_parent(event: &Event) -> Option<EventId> {
+    let e_tags = reply_e_tags(event);
+    let parent = e_tags.iter().find(|(_, marker)| *marker == Some("reply"))
+        .or_else(|| e_tags.iter().find(|(_, marker)| *marker == Some("root")))
+        .or_else(|| e_tags.iter().rev().find(|(_, marker)| marker.is_none()))?;
+    EventId::from_hex(parent.0).ok()
+}
+
+/// リプライのスレッドのルートノート（NIP-10）
+///
+/// root マーカーの e タグ、マーカーがなければ最初の e タグをルートとします。
+/// reply マーカーのみの場合はその e タグをルートとみなします。mention マーカーの e タグは使いません。
+fn reply_root(event: &Event) -> Option<EventId> {
+    let e_tags = reply_e_tags(event);
+    let root = e_tags.iter().find(|(_, marker)| *marker == Some("root"))
+        .or_else(|| e_tags.iter().find(|(_, marker)| marker.is_none()))
+        .or_else(|| e_tags.iter().find(|(_, marker)| *marker == Some("reply")))?;
+    EventId::from_hex(root.0).ok()
+}
+
+/// 著者のノートと対象ノートの関連の種類（`get_reply_context`）
+///
+/// 同じスレッドのノート、対象ノートへの返信、対象ノートと共通のハッシュタグを持つノートの順に判定し、
+/// 関連がなければ None を返します。
+fn note_relation(event: &Event, target: &Event, thread_root: EventId, hashtags: &[String]) -> Option<&'static str> {
+    if reply_parent(event) == Some(target.id) {
+        return Some("reply_to_note");
+    }
+    if event.id == thread_root || reply_root(event) == Some(thread_root) {
+        return Some("same_thread");
+    }
+    event_hashtags(event).iter()
+        .any(|tag| hashtags.contains(tag))
+        .then_some("shared_hashtag")
+}
+
+/// イベントが指定ノートの引用（NIP-18）かどうかを判定
+///
+/// q タグ、mention マーカー付き e タグ、本文中の nostr:note / nostr:nevent 参照のいずれかで判定します。
+fn is_quote_of(event: &Event, target: &EventId) -> bool {
+    let target_hex = target.to_hex();
+
+    let tagged = event.tags.iter().any(|tag| {
+        let values = tag.as_slice();
+        values.len() >= 2
+            && values[1] == target_hex
+            && (values[0] == "q" || (values[0] == "e" && values.get(3).map(|m| m.as_str()) == Some("mention")))
+    });
+
+    tagged || crate::content::extract_nostr_references(&event.content)
+        .iter()
+        .filter(|r| r.ref_type == "note" || r.ref_type == "nevent")
+        .any(|r| NostrClient::parse_event_id(&r.bech32).ok().as_ref() == Some(target))
+}
+
+/// ユーザー検索の一致度スコアを計算（大きいほど一致度が高い）
+///
+/// name / display_name の完全一致 > 前方一致 > 部分一致 > NIP-05 一致の順に評価し、
+/// 一致しない場合は 0 を返します。
+fn user_match_score(author: &AuthorInfo, query: &str) -> u32 {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return 0;
+    }
+
+    let names = [author.name.as_deref(), author.display_name.as_deref()];
+    let mut score = 0;
+
+    for name in names.into_iter().flatten() {
+        let name = name.to_lowercase();
+        let s = if name == query {
+            100
+        } else if name.starts_with(&query) {
+            75
+        } else if name.contains(&query) {
+            50
+        } else {
+            0
+        };
+        score = score.max(s);
+    }
+
+    if let Some(ref nip05) = author.nip05 {
+        if nip05.to_lowercase().contains(&query) {
+            score = score.max(40);
+        }
+    }
+
+    if author.npub == query || author.pubkey == query {
+        score = 100;
+    }
+
+    score
+}
+
+/// キャッシュされたミュートリスト（取得日時, ミュートリスト）
+type CachedMuteList = (std::time::Instant, Arc<MuteList>);
+
+/// キャッシュされたコンタクトリスト（取得日時, フォロー中の公開鍵）
+type CachedContactList = (std::time::Instant, Vec<PublicKey>);
+
+/// キャッシュされたノートの件数（取得日時, 件数）
+type CachedNoteCounts = (std::time::Instant, NoteCounts);
+
+/// ノートが受け取った反応の件数（`enrich_notes_with_counts`）
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct NoteCounts {
+    reactions: u64,
+    replies: u64,
+    reposts: u64,
+    /// Zap の合計額（未検証のレシートがある・取得できなかった場合は None）
+    zap_sats: Option<u64>,
+}
+
+/// ノートのリアクション数・リプライ数などのキャッシュの有効期間
+const NOTE_COUNT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// キャッシュされたリンクプレビュー（取得日時, プレビュー）。取得できなかった URL は None
+type CachedLinkPreview = (std::time::Instant, Option<crate::link_preview::LinkPreview>);
+
+/// リンクプレビューキャッシュの有効期間（取得できなかった URL も再取得しない）
+const LINK_PREVIEW_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+/// 1 回の呼び出しで取得するリンクプレビューの最大数
+const MAX_LINK_PREVIEWS: usize = 20;
+
+/// すべてのリレーが切断されているときに自動再接続を待つ時間
+const RELAY_RECONNECT_WAIT: Duration = Duration::from_secs(3);
+
+/// 接続処理中のリレーの準備ができるのを待つ時間
+const RELAY_READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 接続中のリレーがない場合のエラーメッセージ
+const NO_CONNECTED_RELAYS_MESSAGE: &str =
+    "接続中のリレーがありません。ネットワーク接続と設定ファイルの relays を確認してください";
+
+/// 起動時の接続確認でリレーへの接続を待つ時間
+const STARTUP_CONNECTIVITY_CHECK_WAIT: Duration = Duration::from_secs(10);
+
+/// NIP-51 のキュレーションセット（記事のシリーズ）の Kind
+const KIND_CURATION_SET: u16 = 30004;
+
+/// シリーズの記事の本文プレビューの最大文字数（`include_content` が false の場合）
+const PUBLICATION_PREVIEW_CHARS: usize = 200;
+
+/// NIP-51 の絵文字リストの Kind
+const KIND_EMOJI_LIST: u16 = 10030;
+
+/// NIP-51 の絵文字セットの Kind
+const KIND_EMOJI_SET: u16 = 30030;
+
+/// NIP-66 のリレー発見イベントの Kind
+const KIND_RELAY_DISCOVERY: u16 = 30166;
+
+/// NIP-66 のリレーモニターの告知の Kind
+const KIND_RELAY_MONITOR: u16 = 10166;
+
+/// NIP-66 のモニターのレポートを取得するリレー（`monitor_relays` 未指定時）
+const DEFAULT_MONITOR_RELAYS: &[&str] = &[
+    "wss://relay.nostr.watch",
+    "wss://relaypag.es",
+    "wss://monitorlizard.nostr1.com",
+];
+
+/// モニターの告知に確認間隔がない場合に仮定する間隔
+const DEFAULT_MONITOR_FREQUENCY_SECS: u64 = 3600;
+
+/// モニターの告知の確認間隔として受け付ける範囲（告知は誰でも公開できるため極端な値を丸める）
+const MONITOR_FREQUENCY_RANGE_SECS: std::ops::RangeInclusive<u64> = 60..=86_400;
+
+/// 取得するモニターのレポートの期間
+const RELAY_DISCOVERY_LOOKBACK_SECS: u64 = 7 * 86_400;
+
+/// 取得するモニターのレポートの最大数
+const RELAY_DISCOVERY_FETCH_LIMIT: usize = 5000;
+
+/// 一度に計測できるリレーの最大数
+const MAX_BENCHMARK_RELAYS: usize = 20;
+
+/// リレーの計測で接続を待つ時間
+const BENCHMARK_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// リレーの計測で標準フィルタの取得を待つ時間
+const BENCHMARK_QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// リレーの計測に使う標準フィルタの件数
+const BENCHMARK_QUERY_LIMIT: usize = 50;
+
+/// NWC ウォレットの接続確認で各問い合わせを待つ時間
+const WALLET_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// キャッシュされたアカウントの作成時期の確認結果（確認日時, 最小日数以上経っているか）
+type CachedAccountAge = (std::time::Instant, bool);
+
+/// 作成から最小日数に満たないと判定したアカウントの再確認までの間隔
+///
+/// 最小日数以上経っていると判定したアカウントは再確認しません。
+const YOUNG_ACCOUNT_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+/// アカウントの作成時期の確認で 1 回の REQ に含めるフィルタ数
+const ACCOUNT_AGE_FILTERS_PER_REQUEST: usize = 10;
+
+/// 指定したリレーに NIP-45 COUNT を発行し、結果の最大値を返す（全て失敗した場合は None）
+///
+/// 各リレーへの送信はレート制限に従います。
+async fn count_on_relays(limiter: &Arc<RateLimiter>, relays: Vec<Relay>, filter: Filter) -> Option<u64> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for relay in relays {
+        let filter = filter.clone();
+        let limiter = limiter.clone();
+        tasks.spawn(async move {
+            limiter.acquire(&[relay.url().as_str()]).await;
+            relay.count_events(vec![filter], Duration::from_secs(5)).await
+        });
+    }
+
+    let mut best: Option<u64> = None;
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(Ok(count)) = result {
+            best = Some(best.map_or(count as u64, |b| b.max(count as u64)));
+        }
+    }
+    best
+}
+
+/// 告知 (Kind 10166) を公開しているモニターのレポートだけを残す
+fn announced_monitor_reports(reports: Vec<Event>, announcements: &[Event]) -> Vec<Event> {
+    let announced: std::collections::HashSet<PublicKey> = announcements.iter()
+        .filter(|e| e.kind == Kind::from(KIND_RELAY_MONITOR))
+        .map(|e| e.pubkey)
+        .collect();
+    reports.into_iter().filter(|e| announced.contains(&e.pubkey)).collect()
+}
+
+/// モニターの告知 (Kind 10166) の frequency タグから、モニターごとの確認間隔（秒）を取得
+///
+/// 確認間隔は `MONITOR_FREQUENCY_RANGE_SECS` の範囲に丸めます。
+fn monitor_frequencies(announcements: &[Event]) -> HashMap<PublicKey, u64> {
+    let mut latest: HashMap<PublicKey, &Event> = HashMap::new();
+    for event in announcements {
+        if latest.get(&event.pubkey).is_none_or(|e| e.created_at < event.created_at) {
+            latest.insert(event.pubkey, event);
+        }
+    }
+    latest.into_iter()
+        .filter_map(|(pk, event)| {
+            let frequency = extract_tag_value(event, "frequency")?.parse::<u64>().ok()?;
+            Some((pk, frequency.clamp(*MONITOR_FREQUENCY_RANGE_SECS.start(), *MONITOR_FREQUENCY_RANGE_SECS.end())))
+        })
+        .collect()
+}
+
+/// リレー発見イベント (Kind 30166) をリレーごとに集計し、良い順に並べる
+///
+/// モニターごとに最新のレポートだけを使い、確認間隔（不明な場合は DEFAULT_MONITOR_FREQUENCY_SECS）の
+/// 2 倍より古いレポートは停止中とみなします。
+fn aggregate_relay_reports(reports: &[Event], frequencies: &HashMap<PublicKey, u64>, now: u64) -> Vec<DiscoveredRelay> {
+    let mut latest: HashMap<(String, PublicKey), &Event> = HashMap::new();
+    for event in reports.iter().filter(|e| e.kind == Kind::from(KIND_RELAY_DISCOVERY)) {
+        let Some(url) = event.tags.identifier().and_then(|d| RelayUrl::parse(d).ok()) else { continue };
+        let key = (url.to_string(), event.pubkey);
+        if latest.get(&key).is_none_or(|e| e.created_at < event.created_at) {
+            latest.insert(key, event);
+        }
+    }
+
+    let mut by_relay: std::collections::BTreeMap<String, Vec<&Event>> = std::collections::BTreeMap::new();
+    for ((url, _), event) in latest {
+        by_relay.entry(url).or_default().push(event);
+    }
+
+    let median = |mut values: Vec<u64>| -> Option<u64> {
+        values.sort_unstable();
+        values.get(values.len().checked_sub(1)? / 2).copied()
+    };
+    let mut relays: Vec<DiscoveredRelay> = by_relay.into_iter()
+        .map(|(url, events)| {
+            let fresh: Vec<&Event> = events.iter()
+                .filter(|e| {
+                    let frequency = frequencies.get(&e.pubkey).copied().unwrap_or(DEFAULT_MONITOR_FREQUENCY_SECS);
+                    now.saturating_sub(e.created_at.as_u64()) <= frequency.saturating_mul(2)
+                })
+                .copied()
+                .collect();
+            let rtt = |name: &str| median(fresh.iter().filter_map(|e| extract_tag_value(e, name)?.parse().ok()).collect());
+            let values = |name: &str| -> Vec<String> {
+                let mut values: Vec<String> = events.iter()
+                    .flat_map(|e| e.tags.iter())
+                    .filter(|tag| tag.as_slice().first().is_some_and(|k| k == name))
+                    .filter_map(|tag| tag.content().map(str::to_string))
+                    .collect();
+                values.sort();
+                values.dedup();
+                values
+            };
+            let mut countries: Vec<String> = events.iter()
+                .flat_map(|e| e.tags.iter())
+                .filter(|tag| {
+                    let parts = tag.as_slice();
+                    parts.first().is_some_and(|k| k == "l")
+                        && parts.get(2).is_some_and(|ns| ns == "countryCode" || ns == "ISO-3166-1")
+                })
+                .filter_map(|tag| tag.content().map(str::to_uppercase))
+                .collect();
+            countries.sort();
+            countries.dedup();
+
+            DiscoveredRelay {
+                monitors: events.len() as u64,
+                fresh_monitors: fresh.len() as u64,
+                availability: fresh.len() as f64 / events.len() as f64,
+                last_seen_at: events.iter().map(|e| e.created_at.as_u64()).max().unwrap_or_default(),
+                rtt_open_ms: rtt("rtt-open"),
+                rtt_read_ms: rtt("rtt-read"),
+                network: values("n").into_iter().next(),
+                supported_nips: values("N").iter().filter_map(|n| n.parse().ok()).collect::<std::collections::BTreeSet<u16>>().into_iter().collect(),
+                requirements: values("R").into_iter().filter(|r| !r.starts_with('!')).collect(),
+                countries,
+                geohashes: values("g"),
+                url,
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    relays.sort_by(|a, b| {
+        b.availability.total_cmp(&a.availability)
+            .then(b.monitors.cmp(&a.monitors))
+            .then(a.rtt_open_ms.unwrap_or(u64::MAX).cmp(&b.rtt_open_ms.unwrap_or(u64::MAX)))
+            .then(a.url.cmp(&b.url))
+    });
+    relays
+}
+
+/// 専用の接続でリレーの接続時間と標準フィルタ（最新のテキストノート）の取得時間を計測
+async fn benchmark_relay(url: RelayUrl, in_pool: bool) -> RelayBenchmark {
+    let measure = async {
+        let relay = Relay::new(url.clone());
+        let started = std::time::Instant::now();
+        relay.connect(Some(BENCHMARK_CONNECT_TIMEOUT)).await;
+        if !relay.is_connected() {
+            return (None, None, 0, Some("接続できませんでした".to_string()));
+        }
+        let connect_ms = started.elapsed().as_millis() as u64;
+
+        let filter = Filter::new().kind(Kind::TextNote).limit(BENCHMARK_QUERY_LIMIT);
+        let started = std::time::Instant::now();
+        let result = relay.fetch_events(vec![filter], BENCHMARK_QUERY_TIMEOUT, ReqExitPolicy::ExitOnEOSE).await;
+        let query_ms = started.elapsed().as_millis() as u64;
+        if let Err(e) = relay.disconnect() {
+            debug!("計測用の接続の切断に失敗 ({}): {}", url, e);
+        }
+        match result {
+            Ok(events) => (Some(connect_ms), Some(query_ms), events.len() as u64, None),
+            Err(e) => (Some(connect_ms), None, 0, Some(format!("取得に失敗しました: {}", e))),
+        }
+    };
+    let ((connect_ms, query_ms, events, error), information) =
+        tokio::join!(measure, crate::nip11::fetch_relay_information(url.as_str()));
+    let information = information.ok();
+
+    RelayBenchmark {
+        rank: 0,
+        url: url.to_string(),
+        in_pool,
+        connected: connect_ms.is_some(),
+        connect_ms,
+        query_ms,
+        events,
+        name: information.as_ref().and_then(|info| info.name.clone()),
+        supports_search: information.as_ref().map(|info| info.supports(50)),
+        supports_count: information.as_ref().map(|info| info.supports(45)),
+        error,
+    }
+}
+
+/// 複数の件数取得方法をまとめる（すべて同じならその方法、異なる場合は "mixed"）
+fn combine_count_methods(methods: &[&'static str]) -> &'static str {
+    match methods.first() {
+        Some(first) if methods.iter().all(|m| m == first) => first,
+        Some(_) => "mixed",
+        None => "fetch",
+    }
+}
+
+/// キャッシュされたフォローグラフ（取得日時, ホップ数, グラフ）
+type CachedTrustGraph = (std::time::Instant, u8, Arc<TrustGraph>);
+
+/// フォローグラフのキャッシュ有効期間
+const TRUST_GRAPH_CACHE_TTL: Duration = Duration::from_secs(1800);
+
+/// フォローグラフ構築時に 1 つのフィルタに含める著者数
+const TRUST_GRAPH_AUTHORS_PER_FILTER: usize = 250;
+
+/// リプライの文脈で関連ノートを探す、著者の最近のノートの数
+const REPLY_CONTEXT_AUTHOR_WINDOW: usize = 50;
+
+/// フォローのおすすめに含める「フォローしている自分のフォロー」の最大数
+const RECOMMENDATION_SAMPLE_FOLLOWERS: usize = 5;
+
+/// ミュートリストのキャッシュ有効期間
+const MUTE_LIST_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// 通知の種類
+pub const NOTIFICATION_TYPES: &[&str] = &["mention", "reaction", "zap", "repost", "follow"];
+
+/// PoW フィルタ適用時に取得件数を増やす倍率（除外される分を補う）
+const POW_FETCH_MULTIPLIER: u64 = 5;
+
+/// PoW フィルタ適用時の取得件数の上限
+const POW_FETCH_MAX: u64 = 500;
+
+/// アカウント移行で新しい鍵にコピーするリストの Kind
+///
+/// コンタクトリスト・NIP-65 リレーリスト・NIP-51 のリスト（ミュート・ピン・ブックマーク・コミュニティ・
+/// 公開チャット・検索リレー・興味・絵文字・DM リレー）とセット（フォロー・リレー・ブックマーク・興味・絵文字）。
+const MIGRATED_LIST_KINDS: &[u16] = &[
+    3, 10002, 10000, 10001, 10003, 10004, 10005, 10007, 10015, 10030, 10050,
+    30000, 30002, 30003, 30015, 30030,
+];
+
+/// リプライを除外するときに取得件数を増やす倍率
+const REPLY_FILTER_FETCH_MULTIPLIER: u64 = 3;
+
+/// 言語で絞り込むときに取得件数を増やす倍率
+const LANGUAGE_FILTER_FETCH_MULTIPLIER: u64 = 3;
+
+/// PoW フィルタを考慮した取得件数
+fn pow_fetch_limit(limit: u64, min_pow: u8) -> usize {
+    if min_pow == 0 {
+        limit as usize
+    } else {
+        (limit * POW_FETCH_MULTIPLIER).min(POW_FETCH_MAX).max(limit) as usize
+    }
+}
+
+/// 署名（およびイベント ID）が不正なイベントを除外
+pub(crate) fn drop_invalid_signatures(events: Vec<Event>) -> Vec<Event> {
+    let total = events.len();
+    let verified: Vec<Event> = events.into_iter().filter(|e| e.verify().is_ok()).collect();
+    if verified.len() < total {
+        warn!("署名が不正なイベントを {} 件除外しました", total - verified.len());
+    }
+    verified
+}
+
+/// インポート・再送信の結果に含める不正な行・拒否されたイベントの最大数
+const IMPORT_MAX_REPORTED_ERRORS: usize = 20;
+
+/// 購読の種類
+pub const SUBSCRIPTION_TYPES: &[&str] = &["notifications", "dms", "timeline", "live_chat"];
+
+/// 既知のフォロワー一覧に新規フォロワーを記録（変更があった場合 true）
+///
+/// `current` は現在のフォロワーの hex 公開鍵のリストで、新規フォロワーは初めて確認した日時 `now` で記録します。
+/// コンタクトリストの作成日時は本人が自由に設定でき、フォロー以外の更新でも変わるため使いません。
+/// 既知のフォロワーは最初に記録した日時を保持します。一覧が空の初回は既存のフォロワー全員を
+/// 追跡開始前（日時 0）として記録し、新規扱いしません。
+fn record_new_followers(known: &mut HashMap<String, u64>, current: &[String], now: u64) -> bool {
+    let seeding = known.is_empty();
+    let mut changed = false;
+    for follower in current {
+        if !known.contains_key(follower) {
+            known.insert(follower.clone(), if seeding { 0 } else { now });
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// フォロワー数のスナップショットを記録する間隔
+const FOLLOWER_SNAPSHOT_INTERVAL_SECS: u64 = 6 * 3600;
+
+/// フォロワー数のスナップショットが必要かを確認する間隔
+const FOLLOWER_SNAPSHOT_CHECK_INTERVAL_SECS: u64 = 600;
+
+/// 起動してから最初にフォロワー数のスナップショットを確認するまでの待ち時間
+const FOLLOWER_SNAPSHOT_STARTUP_DELAY_SECS: u64 = 60;
+
+/// 会話一覧の作成時に取得する DM の最大数（送受信それぞれ）
+const DM_CONVERSATION_FETCH_LIMIT: u64 = 500;
+
+/// NIP-17 の DM 受信リレーリストの Kind
+const KIND_DM_RELAY_LIST: u16 = 10050;
+
+/// 受信者 1 人あたりに追加で送信する受信用リレーの最大数
+const INBOX_RELAYS_PER_RECIPIENT: usize = 3;
+
+/// 1 回の公開で追加で送信する受信用リレーの最大数
+const MAX_INBOX_RELAYS: usize = 12;
+
+/// 1 回の `post_thread` で公開できるノート数の上限
+const MAX_THREAD_NOTES: usize = 25;
+
+/// nevent / nprofile に含めるリレーヒントの最大数
+const NEVENT_RELAY_HINTS: usize = 3;
+
+/// 識別子のリレーヒントから追加で問い合わせるリレーの最大数
+const MAX_FETCH_RELAY_HINTS: usize = 5;
+
+/// 公開したイベントを配信確認で再取得するときのタイムアウト（秒）
+const PUBLISH_VERIFY_TIMEOUT_SECS: u64 = 5;
+
+/// 送信待ちキューを確認する間隔（秒）
+const PENDING_PUBLISH_RETRY_INTERVAL_SECS: u64 = 30;
+
+/// 送信待ちのイベントを再送するまでの最初の待ち時間（秒）。失敗するたびに倍にする
+const PENDING_PUBLISH_BASE_DELAY_SECS: u64 = 30;
+
+/// 送信待ちのイベントを再送するまでの最大の待ち時間（秒）
+const PENDING_PUBLISH_MAX_DELAY_SECS: u64 = 3600;
+
+/// 送信待ちのイベントを自動で再送する最大回数（以降は flush_pending でのみ再送）
+const PENDING_PUBLISH_MAX_ATTEMPTS: u32 = 20;
+
+/// DM プレビューの最大文字数
+const DM_PREVIEW_CHARS: usize = 80;
+
+/// 署名者がなく DM を復号できない場合（publickey のみの読み取り専用モード）の表示
+const UNDECRYPTED_DM_CONTENT: &str = "（暗号化されたメッセージ: 読み取り専用モードでは復号できません）";
+
+/// アカウント分析で取得する自分のノートの最大数
+const MAX_ANALYTICS_NOTES: usize = 1000;
+
+/// アカウント分析で取得する反応（リアクション・リプライ・リポスト・Zap）の最大数
+const MAX_ANALYTICS_INTERACTIONS: usize = 5000;
+
+/// 集計結果のノートのプレビューの最大文字数
+const DIGEST_PREVIEW_CHARS: usize = 80;
+
+/// 参照先ノートのプレビューの最大文字数
+const REFERENCE_PREVIEW_CHARS: usize = 140;
+
+/// 1 回の呼び出しで解決する `nostr:` 参照の最大数
+const MAX_RESOLVED_REFERENCES: usize = 50;
+
+/// ノートのハッシュタグ（t タグと本文中の #タグ、小文字・重複なし）
+fn event_hashtags(event: &Event) -> Vec<String> {
+    let mut tags: Vec<String> = event.tags.hashtags().map(|t| t.to_lowercase()).collect();
+    tags.extend(crate::content::extract_hashtags(&event.content.to_lowercase()));
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// 受け取った反応の集計結果（`tally_account_interactions`）
+#[derive(Default)]
+struct InteractionTally {
+    received: InteractionCounts,
+    mentions: u64,
+    by_note: HashMap<EventId, InteractionCounts>,
+    by_account: HashMap<PublicKey, InteractionCounts>,
+}
+
+/// `own` を p タグで参照するイベントを、反応の種類・対象ノート・反応したアカウントごとに集計
+///
+/// `notes` は `own` のノートで、リアクション・リポスト・Zap は e タグ、リプライは返信先が
+/// これらのノートのときにノートごとの件数に数えます。返信先が `notes` にないリプライは言及として数え、
+/// 自分自身による反応（自分への Zap を含む）は除きます。
+fn tally_account_interactions(own: &PublicKey, notes: &[Event], interactions: &[Event]) -> InteractionTally {
+    let note_ids: std::collections::HashSet<EventId> = notes.iter().map(|e| e.id).collect();
+    let mut tally = InteractionTally::default();
+
+    for event in interactions {
+        let actor = if event.kind == Kind::ZapReceipt { zap_receipt_sender(event) } else { Some(event_author(event)) };
+        if actor == Some(*own) {
+            continue;
+        }
+        let targets: Vec<EventId> = match event.kind {
+            Kind::TextNote => match reply_parent(event) {
+                Some(parent) if note_ids.contains(&parent) => vec![parent],
+                _ => {
+                    tally.mentions += 1;
+                    continue;
+                }
+            },
+            _ => {
+                let mut ids: Vec<EventId> = event.tags.event_ids().filter(|id| note_ids.contains(id)).copied().collect();
+                ids.sort();
+                ids.dedup();
+                ids
+            }
+        };
+
+        let zap_sats = if event.kind == Kind::ZapReceipt {
+            crate::bolt11::amount_sats(&extract_tag_value(event, "bolt11").unwrap_or_default())
+        } else {
+            0
+        };
+        let add = |counts: &mut InteractionCounts| counts.add(event.kind, zap_sats);
+
+        add(&mut tally.received);
+        for id in targets {
+            add(tally.by_note.entry(id).or_default());
+        }
+        if let Some(actor) = actor {
+            add(tally.by_account.entry(actor).or_default());
+        }
+    }
+    tally
+}
+
+/// ノートの投稿頻度を集計（`days` は 1 日あたりの件数の計算に使う集計期間）
+fn posting_stats(notes: &[Event], days: u64) -> PostingStats {
+    let mut stats = PostingStats::default();
+    let mut daily: std::collections::BTreeMap<u64, u64> = std::collections::BTreeMap::new();
+
+    for note in notes {
+        let created_at = note.created_at.as_u64();
+        stats.notes += 1;
+        if is_reply(note) {
+            stats.replies += 1;
+        } else {
+            stats.original_notes += 1;
+        }
+        *daily.entry(created_at / 86_400 * 86_400).or_default() += 1;
+        stats.hourly[(created_at % 86_400 / 3600) as usize] += 1;
+    }
+
+    stats.notes_per_day = stats.notes as f64 / days.max(1) as f64;
+    stats.active_days = daily.len() as u64;
+    stats.daily = daily.into_iter()
+        .map(|(day_start, notes)| DailyPostCount { day_start, notes })
+        .collect();
+    stats
+}
+
+/// ハッシュタグの指定を（小文字の正規形, フィルタに使う表記）に正規化
+///
+/// 先頭の `#` を除きます。t タグは大文字小文字を区別して照合されるため、
+/// 大文字を含む場合は指定された表記もフィルタに含めます。
+fn normalize_hashtag(input: &str) -> Result<(String, Vec<String>)> {
+    let tag = input.trim().trim_start_matches('#');
+    if tag.is_empty() || tag.chars().any(char::is_whitespace) {
+        return Err(anyhow!("無効なハッシュタグです: {}", input));
+    }
+    let normalized = tag.to_lowercase();
+    let mut spellings = vec![normalized.clone()];
+    if tag != normalized {
+        spellings.push(tag.to_string());
+    }
+    Ok((normalized, spellings))
+}
+
+/// `since` から `until` までの日ごとのノート数（UTC、古い順、ノートがない日も含む）
+fn daily_note_counts(events: &[Event], since: u64, until: u64) -> Vec<DailyPostCount> {
+    let first_day = since / 86_400;
+    let last_day = until / 86_400;
+    let mut counts = vec![0u64; (last_day - first_day + 1) as usize];
+    for event in events {
+        let day = event.created_at.as_u64() / 86_400;
+        if (first_day..=last_day).contains(&day) {
+            counts[(day - first_day) as usize] += 1;
+        }
+    }
+    counts.into_iter()
+        .enumerate()
+        .map(|(i, notes)| DailyPostCount { day_start: (first_day + i as u64) * 86_400, notes })
+        .collect()
+}
+
+/// 件数の多い順に上位 `n` 件を返す（同数の場合はキーの昇順）
+fn top_counts<K: Ord>(counts: HashMap<K, u64>, n: usize) -> Vec<(K, u64)> {
+    let mut sorted: Vec<(K, u64)> = counts.into_iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sorted.truncate(n);
+    sorted
+}
+
+/// DM イベントの会話相手を取得（送信メッセージは p タグ、受信メッセージは送信者）
+fn dm_peer(event: &Event, own: &PublicKey) -> Option<PublicKey> {
+    if event.pubkey == *own {
+        event.tags.public_keys().next().copied()
+    } else {
+        Some(event.pubkey)
+    }
+}
 
-        // SHA-256 の検証
-        if descriptor.sha256 != sha256_hex {
-            warn!(
-                "Blossom サーバーから返された SHA-256 が一致しません: expected={}, got={}",
-                sha256_hex, descriptor.sha256
-            );
+/// イベント識別子のリストから取得用のフィルタを作成（ID は 1 つのフィルタにまとめる）
+fn pointer_filters<'a>(pointers: impl Iterator<Item = &'a EventPointer>) -> Vec<Filter> {
+    let mut ids = Vec::new();
+    let mut filters = Vec::new();
+    for pointer in pointers {
+        match pointer {
+            EventPointer::Id(id) => ids.push(*id),
+            EventPointer::Coordinate(c) => filters.push(Filter::from(c)),
         }
+    }
+    if !ids.is_empty() {
+        filters.push(Filter::new().ids(ids.clone()).limit(ids.len()));
+    }
+    filters
+}
 
-        Ok(descriptor)
+/// 取得したイベントから識別子が指すイベントを探す（座標の場合は最新版）
+fn find_pointed_event<'a>(events: &'a [Event], pointer: &EventPointer) -> Option<&'a Event> {
+    match pointer {
+        EventPointer::Id(id) => events.iter().find(|e| e.id == *id),
+        EventPointer::Coordinate(c) => events.iter()
+            .filter(|e| e.kind == c.kind
+                && e.pubkey == c.public_key
+                && e.tags.identifier().unwrap_or_default() == c.identifier)
+            .max_by_key(|e| e.created_at),
     }
+}
 
-    /// すべてのリレーから切断します。
-    pub async fn disconnect(&self) {
-        let _ = self.client.disconnect().await;
-        let mut connected = self.connected.write().await;
-        *connected = false;
+/// テキストを指定文字数で切り詰め、改行を空白に置き換える
+fn truncate_preview(text: &str, max_chars: usize) -> String {
+    let flat: String = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() > max_chars {
+        format!("{}…", flat.chars().take(max_chars).collect::<String>())
+    } else {
+        flat
     }
 }
 
-// ========================================
-// データ構造体
-// ========================================
+/// 読み取りリレーの URL と接続状態の一覧
+async fn read_relay_statuses(client: &Client) -> Vec<(RelayUrl, RelayStatus)> {
+    client.relays().await
+        .into_iter()
+        .filter(|(_, relay)| relay.flags().has_read())
+        .map(|(url, relay)| (url, relay.status()))
+        .collect()
+}
 
-/// ノートの情報（表示用）
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct NoteInfo {
-    /// hex 形式のイベント ID
-    pub id: String,
-    /// リンク用の nevent 形式のイベント ID
-    pub nevent: String,
-    /// 著者情報
-    pub author: AuthorInfo,
-    /// ノートの内容
-    pub content: String,
-    /// 作成日時の Unix タイムスタンプ
-    pub created_at: u64,
-    /// リアクション数（将来の拡張用）
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub reactions: Option<u64>,
-    /// リプライ数（将来の拡張用）
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub replies: Option<u64>,
+/// 読み取りリレーの接続状態から、イベントを取得できない理由を返す（接続中のリレーがあれば None）
+fn relay_connectivity_problem(statuses: &[(RelayUrl, RelayStatus)]) -> Option<String> {
+    if statuses.is_empty() {
+        return Some("読み取り用のリレーが設定されていません。設定ファイルの relays で read を有効にしてください".to_string());
+    }
+    if statuses.iter().any(|(_, status)| *status == RelayStatus::Connected) {
+        return None;
+    }
+    let details: Vec<String> = statuses.iter()
+        .map(|(url, status)| format!("{} ({})", url, status))
+        .collect();
+    Some(format!("{}: {}", NO_CONNECTED_RELAYS_MESSAGE, details.join(", ")))
 }
 
-/// プロフィール情報
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct ProfileInfo {
-    /// hex 形式の公開鍵
-    pub pubkey: String,
-    /// npub 形式の公開鍵
-    pub npub: String,
-    /// ユーザー名
-    pub name: Option<String>,
-    /// 表示名
-    pub display_name: Option<String>,
-    /// 自己紹介文
-    pub about: Option<String>,
-    /// プロフィール画像 URL
-    pub picture: Option<String>,
-    /// バナー画像 URL
-    pub banner: Option<String>,
-    /// NIP-05 識別子
-    pub nip05: Option<String>,
-    /// Lightning アドレス (LUD-16)
-    pub lud16: Option<String>,
-    /// ウェブサイト URL
-    pub website: Option<String>,
+/// 接続中のリレーがないときに接続を待つ上限時間
+///
+/// 接続処理中（未接続・接続待ち・接続中）のリレーがあれば RELAY_READY_TIMEOUT、
+/// すべて切断・終了していれば自動再接続を待つ RELAY_RECONNECT_WAIT を返します。
+fn relay_wait_limit(statuses: &[(RelayUrl, RelayStatus)]) -> Duration {
+    let connecting = statuses.iter().any(|(_, status)| {
+        matches!(status, RelayStatus::Initialized | RelayStatus::Pending | RelayStatus::Connecting)
+    });
+    if connecting { RELAY_READY_TIMEOUT } else { RELAY_RECONNECT_WAIT }
 }
 
-/// プロフィール統計情報（Phase 3: プロフィールカード用）
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct ProfileStats {
-    /// フォロー中の数
-    pub following: u64,
-    /// フォロワー数（推定値、リレーの対応状況に依存）
-    pub followers: u64,
-    /// ノート投稿数（推定値）
-    pub notes: u64,
+/// 起動時にリレーへの接続状況を確認してログに出力
+///
+/// STARTUP_CONNECTIVITY_CHECK_WAIT の間にすべての読み取りリレーへの接続を待ち、
+/// 1 つも接続できなければ警告します。
+async fn check_startup_connectivity(client: Client) {
+    let started = std::time::Instant::now();
+    loop {
+        let statuses = read_relay_statuses(&client).await;
+        let connected = statuses.iter().filter(|(_, status)| *status == RelayStatus::Connected).count();
+        if connected == statuses.len() || started.elapsed() >= STARTUP_CONNECTIVITY_CHECK_WAIT {
+            match relay_connectivity_problem(&statuses) {
+                Some(problem) => warn!("{}", problem),
+                None => info!("リレーに接続しました: {}/{}", connected, statuses.len()),
+            }
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
 }
 
-/// スレッド情報（Phase 2）
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct ThreadInfo {
-    /// ルートノート
-    pub root: NoteInfo,
-    /// リプライ一覧（ネスト構造）
-    pub replies: Vec<ThreadReply>,
-    /// リプライの総数
-    pub total_replies: u64,
-    /// 取得したリプライの深さ
-    pub depth: u64,
+/// ウォレットサービスの情報イベント (Kind 13194) を解析
+///
+/// 戻り値は（対応メソッド, 暗号化方式, 通知の種類）です。いずれも空白区切りで記載されています。
+fn parse_wallet_info_event(event: &Event) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let split = |value: Option<String>| -> Vec<String> {
+        value.map(|v| v.split_whitespace().map(String::from).collect()).unwrap_or_default()
+    };
+    (
+        split(Some(event.content.clone())),
+        split(extract_tag_value(event, "encryption")),
+        split(extract_tag_value(event, "notifications")),
+    )
 }
 
-/// スレッドのリプライ（ネスト可能）
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct ThreadReply {
-    /// リプライノート
-    pub note: NoteInfo,
-    /// さらにネストされたリプライ
-    pub replies: Vec<ThreadReply>,
+/// 支払いが上限設定の範囲内か確認
+///
+/// `ledger` は（Unix タイムスタンプ, sats）の支払い履歴で、直近 24 時間分のみを日次上限に数えます。
+fn check_payment_budget(
+    limits: &crate::config::PaymentLimits,
+    ledger: &[(u64, u64)],
+    now: u64,
+    amount_sats: u64,
+) -> Result<()> {
+    if let Some(max) = limits.max_per_payment_sats {
+        if amount_sats > max {
+            return Err(anyhow!(
+                "支払い額 {} sats が 1 回あたりの上限 ({} sats) を超えています",
+                amount_sats, max
+            ));
+        }
+    }
+
+    if let Some(daily) = limits.daily_limit_sats {
+        let spent: u64 = ledger.iter()
+            .filter(|(ts, _)| ts + 86_400 > now)
+            .map(|(_, sats)| sats)
+            .sum();
+        if spent.saturating_add(amount_sats) > daily {
+            return Err(anyhow!(
+                "直近 24 時間の支払い上限 ({} sats) を超えます（支払い済み: {} sats）",
+                daily, spent
+            ));
+        }
+    }
+
+    Ok(())
 }
 
-/// 通知情報（Phase 2）
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct NotificationInfo {
-    /// hex 形式のイベント ID
-    pub id: String,
-    /// nevent 形式のイベント ID
-    pub nevent: String,
-    /// 通知の種類（"mention" または "reaction"）
-    pub notification_type: String,
-    /// 通知元の著者情報
-    pub author: AuthorInfo,
-    /// コンテンツ（リアクションの場合は絵文字、メンションの場合はノート内容）
-    pub content: String,
-    /// リアクション対象のノート ID
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub target_note_id: Option<String>,
-    /// 作成日時の Unix タイムスタンプ
-    pub created_at: u64,
+/// NIP-19 識別子が指すイベント
+#[derive(Debug, Clone, PartialEq)]
+enum EventPointer {
+    /// イベント ID（nevent、note、hex）
+    Id(EventId),
+    /// アドレス可能イベントの座標（naddr）
+    Coordinate(Coordinate),
 }
 
-// ========================================
-// Phase 4: データ構造体
-// ========================================
+/// 現在の Unix タイムスタンプ（秒）を取得
+/// 本文中の NIP-27 メンション（`nostr:npub1...` / `nostr:nprofile1...`）から公開鍵を抽出する
+fn mentioned_pubkeys(content: &str) -> Vec<PublicKey> {
+    let mut pubkeys = Vec::new();
+    for (idx, _) in content.match_indices("nostr:") {
+        let rest = &content[idx + "nostr:".len()..];
+        let end = rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len());
+        let pk = match Nip19::from_bech32(&rest[..end]) {
+            Ok(Nip19::Pubkey(pk)) => pk,
+            Ok(Nip19::Profile(profile)) => profile.public_key,
+            _ => continue,
+        };
+        if !pubkeys.contains(&pk) {
+            pubkeys.push(pk);
+        }
+    }
+    pubkeys
+}
 
-/// Zap レシート情報（NIP-57）
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct ZapReceiptInfo {
-    /// hex 形式のイベント ID
-    pub id: String,
-    /// nevent 形式のイベント ID
-    pub nevent: String,
-    /// Zap 送信者の情報（Zap リクエストから取得）
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub sender: Option<AuthorInfo>,
-    /// Zap 金額（sats）
-    pub amount_sats: u64,
-    /// Zap コメント
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub comment: Option<String>,
-    /// Zap 対象のノート ID
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub target_note_id: Option<String>,
-    /// Zap 対象の pubkey
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub target_pubkey: Option<String>,
-    /// 作成日時の Unix タイムスタンプ
-    pub created_at: u64,
+/// シリーズ（キュレーションセット）の a タグが指す記事の座標（順番どおり、重複・リレーヒントは除く）
+fn publication_entries<'a>(tags: impl IntoIterator<Item = &'a Tag>) -> Vec<Coordinate> {
+    let mut entries: Vec<Coordinate> = Vec::new();
+    for tag in tags {
+        let values = tag.as_slice();
+        if values.len() < 2 || values[0] != "a" {
+            continue;
+        }
+        let Ok(c) = Coordinate::parse(&values[1]) else { continue };
+        let entry = Coordinate::new(c.kind, c.public_key).identifier(c.identifier);
+        if !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+    entries
 }
 
-/// ダイレクトメッセージ情報（NIP-04）
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct DirectMessageInfo {
-    /// hex 形式のイベント ID
-    pub id: String,
-    /// nevent 形式のイベント ID
-    pub nevent: String,
-    /// 送信者の情報
-    pub author: AuthorInfo,
-    /// 復号済みメッセージ内容
-    pub content: String,
-    /// メッセージの方向（"sent" または "received"）
-    pub direction: String,
-    /// 会話相手の pubkey (hex)
-    pub peer_pubkey: String,
-    /// 作成日時の Unix タイムスタンプ
-    pub created_at: u64,
+/// シリーズの記事の並びに `entry` を挿入し、挿入した位置（1 始まり）を返す
+///
+/// `position` を省略すると末尾に追加します。既に含まれている記事は `position` を指定した場合のみ移動し、
+/// 指定しない場合はエラーにします。範囲外の `position` は末尾として扱います。
+fn insert_publication_entry(entries: &mut Vec<Coordinate>, entry: Coordinate, position: Option<usize>) -> Result<usize> {
+    if let Some(current) = entries.iter().position(|e| *e == entry) {
+        if position.is_none() {
+            return Err(anyhow!("記事「{}」は既にシリーズの {} 件目に含まれています", entry.identifier, current + 1));
+        }
+        entries.remove(current);
+    }
+    let index = position.map_or(entries.len(), |p| p.saturating_sub(1).min(entries.len()));
+    entries.insert(index, entry);
+    Ok(index + 1)
 }
 
-/// リレーリスト情報（NIP-65）
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct RelayListInfo {
-    /// hex 形式の公開鍵
-    pub pubkey: String,
-    /// npub 形式の公開鍵
-    pub npub: String,
-    /// リレー一覧
-    pub relays: Vec<RelayListEntry>,
+/// カスタム絵文字のショートコードとして使える文字列か（NIP-30: 英数字・`_`・`-`）
+fn is_valid_shortcode(shortcode: &str) -> bool {
+    !shortcode.is_empty() && shortcode.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
 }
 
-/// リレーリストのエントリ
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct RelayListEntry {
-    /// リレー URL
-    pub url: String,
-    /// 読み取り可能
-    pub read: bool,
-    /// 書き込み可能
-    pub write: bool,
+/// イベントの emoji タグ（NIP-30）から絵文字を抽出する
+fn parse_emoji_tags(event: &Event) -> Vec<CustomEmoji> {
+    let mut emojis: Vec<CustomEmoji> = Vec::new();
+    for tag in event.tags.iter() {
+        let values = tag.as_slice();
+        if values.len() < 3 || values[0] != "emoji" || !is_valid_shortcode(&values[1]) {
+            continue;
+        }
+        if !emojis.iter().any(|e| e.shortcode == values[1]) {
+            emojis.push(CustomEmoji { shortcode: values[1].clone(), url: values[2].clone() });
+        }
+    }
+    emojis
 }
 
-/// 記事投稿のパラメータ
-#[derive(Debug, Clone)]
-pub struct ArticleParams {
-    /// 記事タイトル
-    pub title: String,
-    /// Markdown コンテンツ
-    pub content: String,
-    /// 識別子（d タグ、未指定時はタイトルから自動生成）
-    pub identifier: Option<String>,
-    /// 要約
-    pub summary: Option<String>,
-    /// ヘッダー画像 URL
-    pub image: Option<String>,
-    /// トピックハッシュタグ
-    pub tags: Option<Vec<String>>,
-    /// 公開日時の Unix タイムスタンプ
-    pub published_at: Option<u64>,
+/// 絵文字リスト (Kind 10030) の a タグが参照している絵文字セットの座標
+fn emoji_set_references(list: &Event) -> Vec<Coordinate> {
+    list.tags.iter()
+        .filter_map(|tag| {
+            let values = tag.as_slice();
+            (values.len() >= 2 && values[0] == "a").then(|| Coordinate::parse(&values[1]).ok()).flatten()
+        })
+        .filter(|c| c.kind == Kind::from(KIND_EMOJI_SET))
+        .collect()
 }
 
-/// 記事情報（NIP-23 長文コンテンツ）
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct ArticleInfo {
-    /// hex 形式のイベント ID
-    pub id: String,
-    /// nevent 形式のイベント ID
-    pub nevent: String,
-    /// naddr 形式のアドレス（アドレス可能なイベント用）
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub naddr: Option<String>,
-    /// 識別子（d タグ）
-    pub identifier: String,
-    /// 記事タイトル
-    pub title: String,
-    /// 要約
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub summary: Option<String>,
-    /// ヘッダー画像 URL
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub image: Option<String>,
-    /// Markdown コンテンツ
-    pub content: String,
-    /// 著者情報
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub author: Option<AuthorInfo>,
-    /// 公開日時の Unix タイムスタンプ
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub published_at: Option<u64>,
-    /// 作成日時の Unix タイムスタンプ
-    pub created_at: u64,
-    /// ハッシュタグ
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub tags: Option<Vec<String>>,
-    /// 下書きかどうか
-    pub is_draft: bool,
+/// 本文中の `:shortcode:` 形式のショートコード（出現順、重複なし）
+fn emoji_shortcodes(content: &str) -> Vec<String> {
+    let mut shortcodes: Vec<String> = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find(':') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find(':') else { break };
+        let candidate = &after[..end];
+        if is_valid_shortcode(candidate) {
+            if !shortcodes.iter().any(|s| s == candidate) {
+                shortcodes.push(candidate.to_string());
+            }
+            rest = &after[end + 1..];
+        } else {
+            // 閉じのコロンが次のショートコードの開始かもしれないため、そこから探し直す
+            rest = after;
+        }
+    }
+    shortcodes
+}
+
+/// 本文中のショートコードのうち `emojis` に登録されているものの emoji タグ
+fn emoji_tags(content: &str, emojis: &HashMap<String, String>) -> Vec<Tag> {
+    emoji_shortcodes(content)
+        .into_iter()
+        .filter_map(|shortcode| {
+            let url = emojis.get(&shortcode)?.clone();
+            Some(CustomEmoji { shortcode, url }.to_tag())
+        })
+        .collect()
+}
+
+/// リアクションのプリセットの 1 時間あたりの上限を確認するヘルパー
+///
+/// `sent_at` はこのプリセットでリアクションを送信した Unix タイムスタンプ。
+fn check_reaction_rate(name: &str, preset: &crate::config::ReactionPreset, sent_at: &[u64], now: u64) -> Result<()> {
+    let Some(max) = preset.max_per_hour else {
+        return Ok(());
+    };
+    let recent: Vec<u64> = sent_at.iter().copied().filter(|ts| ts + 3_600 > now).collect();
+    if recent.len() < max as usize {
+        return Ok(());
+    }
+    let retry_after = recent.iter().min().map_or(0, |oldest| oldest + 3_600 - now);
+    Err(anyhow!(
+        "プリセット「{}」のリアクションは 1 時間あたり {} 回までです（約 {} 分後に再送信できます）",
+        name,
+        max,
+        retry_after.div_ceil(60)
+    ))
+}
+
+/// 画像 URL が設定されたリアクションのプリセットの emoji タグ（NIP-30）を作成するヘルパー
+fn preset_emoji_tags(name: &str, preset: &crate::config::ReactionPreset) -> Result<Vec<Tag>> {
+    let Some(url) = preset.emoji_url.as_deref() else {
+        return Ok(Vec::new());
+    };
+    let shortcode = preset.content.strip_prefix(':').and_then(|c| c.strip_suffix(':')).unwrap_or_default();
+    if !is_valid_shortcode(shortcode) {
+        return Err(anyhow!(
+            "プリセット「{}」の emoji-url を使うには content を :shortcode: 形式にしてください: {}",
+            name,
+            preset.content
+        ));
+    }
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Err(anyhow!("プリセット「{}」の emoji-url は http(s):// で始まる必要があります: {}", name, url));
+    }
+    Ok(vec![CustomEmoji { shortcode: shortcode.to_string(), url: url.to_string() }.to_tag()])
+}
+
+/// プールの書き込みリレーに署名済みイベントを送信し、リレーごとの結果を返すヘルパー
+///
+/// `Client::send_event` はすべてのリレーで失敗するとリレーごとの理由を返さないため、
+/// 送信待ちキューに保存するか判断できるよう、リレーごとに送信して結果をまとめます。
+async fn send_event_to_write_relays(client: &Client, event: &Event) -> Output<EventId> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for (url, relay) in client.relays().await {
+        if !relay.flags().has_write() {
+            continue;
+        }
+        let event = event.clone();
+        tasks.spawn(async move { (url, relay.send_event(event).await) });
+    }
+
+    let mut output = Output { val: event.id, success: std::collections::HashSet::new(), failed: HashMap::new() };
+    while let Some(result) = tasks.join_next().await {
+        match result {
+            Ok((url, Ok(_))) => {
+                output.success.insert(url);
+            }
+            Ok((url, Err(e))) => {
+                output.failed.insert(url, e.to_string());
+            }
+            Err(e) => warn!("イベント送信タスクの実行に失敗: {}", e),
+        }
+    }
+    output
 }
 
-// ========================================
-// ユーティリティ関数
-// ========================================
+/// どのリレーにも送信できなかった理由を「リレー: 理由」の形でまとめる
+fn describe_publish_failures(output: &Output<EventId>) -> String {
+    if output.failed.is_empty() {
+        return "書き込み有効なリレーがありません".to_string();
+    }
+    let mut reasons: Vec<String> = output.failed.iter()
+        .map(|(url, reason)| format!("{}: {}", url, reason))
+        .collect();
+    reasons.sort();
+    reasons.join(", ")
+}
 
-/// タイトルから URL 用スラッグを生成
-fn slug_from_title(title: &str) -> String {
-    title
-        .to_lowercase()
-        .chars()
-        .map(|c| {
-            if c.is_alphanumeric() || c == '-' || c == '_' {
-                c
-            } else if c == ' ' {
-                '-'
-            } else {
-                // 日本語等のマルチバイト文字はそのまま保持
-                c
+/// すべての書き込みリレーがイベントを拒否したか（NIP-01 の OK メッセージの接頭辞で判定）
+///
+/// `blocked:`・`invalid:` などの拒否は再送しても受け付けられないため、送信待ちキューに入れません。
+/// 接続の失敗・タイムアウトや `rate-limited:`・`error:`・`auth-required:` は一時的な失敗として扱います。
+fn is_rejected_by_all(output: &Output<EventId>) -> bool {
+    !output.failed.is_empty()
+        && output.failed.values().all(|reason| matches!(
+            MachineReadablePrefix::parse(reason),
+            Some(
+                MachineReadablePrefix::Duplicate
+                | MachineReadablePrefix::Pow
+                | MachineReadablePrefix::Blocked
+                | MachineReadablePrefix::Invalid
+                | MachineReadablePrefix::Restricted
+            )
+        ))
+}
+
+/// 再送の試行回数に応じた次の再送までの待ち時間（指数バックオフ）
+fn pending_publish_backoff(attempts: u32) -> u64 {
+    PENDING_PUBLISH_BASE_DELAY_SECS
+        .saturating_mul(1 << attempts.min(16))
+        .min(PENDING_PUBLISH_MAX_DELAY_SECS)
+}
+
+/// フォロワー数のスナップショットを定期的に記録するタスクを起動する。
+/// 公開鍵を設定していない場合と、前回の記録から `FOLLOWER_SNAPSHOT_INTERVAL_SECS` 経っていない場合は何もしない。
+pub fn spawn_follower_snapshots(
+    client: Arc<RwLock<NostrClient>>,
+    mut shutdown: tokio::sync::watch::Receiver<bool>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        // 起動直後はリレーへの接続を待つ
+        let start = tokio::time::Instant::now() + Duration::from_secs(FOLLOWER_SNAPSHOT_STARTUP_DELAY_SECS);
+        let mut interval = tokio::time::interval_at(start, Duration::from_secs(FOLLOWER_SNAPSHOT_CHECK_INTERVAL_SECS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.changed() => break,
             }
-        })
-        .collect::<String>()
-        .trim_matches('-')
-        .to_string()
+            // 取得中に NIP-46 サイナー・アカウントの切り替えを妨げないよう、複製してからロックを外す
+            let client = client.read().await.clone();
+            let Some(pk) = client.public_key() else { continue };
+            let last = crate::storage::load_follower_history(&pk.to_hex()).last_snapshot_at().unwrap_or(0);
+            if current_unix_timestamp().saturating_sub(last) < FOLLOWER_SNAPSHOT_INTERVAL_SECS {
+                continue;
+            }
+            match client.record_follower_snapshot().await {
+                Ok(history) => debug!("フォロワー数を記録しました: {} 人", history.snapshots.last().map_or(0, |s| s.followers)),
+                Err(e) => warn!("フォロワー数の記録に失敗: {}", e),
+            }
+        }
+    })
 }
 
-/// イベントのタグから指定されたキーの値を抽出
-fn extract_tag_value(event: &Event, key: &str) -> Option<String> {
-    event.tags.iter().find_map(|tag| {
-        let values = tag.as_slice();
-        if values.len() >= 2 && values[0] == key {
-            Some(values[1].to_string())
-        } else {
-            None
+/// 送信待ちキューの再送タスクを起動する。
+/// リレーに届かなかったイベントを、再送時刻に達したものから定期的に再送する。
+/// シャットダウン時は最後にもう一度再送してから終了する。
+pub fn spawn_pending_publish_retry(
+    client: Arc<RwLock<NostrClient>>,
+    mut shutdown: tokio::sync::watch::Receiver<bool>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(PENDING_PUBLISH_RETRY_INTERVAL_SECS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            let shutting_down = tokio::select! {
+                _ = interval.tick() => false,
+                _ = shutdown.changed() => true,
+            };
+            // 再送中に NIP-46 サイナー・アカウントの切り替えを妨げないよう、複製してからロックを外す
+            let client = client.read().await.clone();
+            // ドライランモードでは送信待ちのイベントも再送しない
+            if client.dry_run() {
+                if shutting_down {
+                    break;
+                }
+                continue;
+            }
+            match client.flush_pending_publishes(&[], false).await {
+                Ok(flush) if !flush.published.is_empty() || !flush.failed.is_empty() => info!(
+                    "送信待ちのイベントを再送しました: 成功 {} 件、失敗 {} 件（残り {} 件）",
+                    flush.published.len(),
+                    flush.failed.len(),
+                    flush.remaining
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("送信待ちキューの再送に失敗: {}", e),
+            }
+            if shutting_down {
+                break;
+            }
         }
     })
 }
 
-/// 現在の Unix タイムスタンプ（秒）を取得
-fn current_unix_timestamp() -> u64 {
+/// クライアントのデータベースが記録した、イベントを受信したリレーを取得する
+async fn seen_on_relays_in(client: &Client, events: &[Event]) -> HashMap<EventId, Vec<String>> {
+    let database = client.database();
+    let mut seen_on = HashMap::new();
+    for event in events {
+        match database.event_seen_on_relays(&event.id).await {
+            Ok(Some(relays)) => {
+                let mut relays: Vec<String> = relays.iter().map(|url| url.to_string()).collect();
+                relays.sort();
+                seen_on.insert(event.id, relays);
+            }
+            Ok(None) => {}
+            Err(e) => debug!("受信元リレーの取得に失敗 ({}): {}", event.id, e),
+        }
+    }
+    seen_on
+}
+
+/// 受信元リレーの一覧から、イベントのリレーヒントを取り出す
+pub(crate) fn relay_hints<'a>(seen_on: &'a HashMap<EventId, Vec<String>>, id: &EventId) -> &'a [String] {
+    seen_on.get(id).map(Vec::as_slice).unwrap_or_default()
+}
+
+/// 著者とリレーヒント（受信・公開したリレー）を含めた nevent を作成する
+pub fn encode_nevent(id: &EventId, author: Option<PublicKey>, relays: &[String]) -> String {
+    let mut nip19 = Nip19Event::new(*id, relays.iter().take(NEVENT_RELAY_HINTS));
+    if let Some(author) = author {
+        nip19 = nip19.author(author);
+    }
+    nip19.to_bech32().unwrap_or_default()
+}
+
+/// リレーヒントを含めた nprofile を作成する（ヒントがない場合は None）
+fn encode_nprofile(pk: &PublicKey, relays: &[String]) -> Option<String> {
+    if relays.is_empty() {
+        return None;
+    }
+    Nip19Profile::new(*pk, relays.iter().take(NEVENT_RELAY_HINTS))
+        .ok()
+        .and_then(|profile| profile.to_bech32().ok())
+}
+
+pub(crate) fn current_unix_timestamp() -> u64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map(|d| d.as_secs())
@@ -1952,3 +9886,1323 @@ fn build_article_tags(
 
     tags
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "mock-relay")]
+    use crate::zaps::{test_zap_invoice, TEST_ZAP_REQUEST, ZAP_PROVIDER_CACHE_TTL};
+
+    fn author(name: Option<&str>, display_name: Option<&str>, nip05: Option<&str>) -> AuthorInfo {
+        AuthorInfo {
+            pubkey: "0".repeat(64),
+            npub: "npub1test".to_string(),
+            nprofile: None,
+            name: name.map(String::from),
+            display_name: display_name.map(String::from),
+            picture: None,
+            nip05: nip05.map(String::from),
+            nip05_verified: false,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_record_new_followers() {
+        // 初回は既存のフォロワーを追跡開始前として記録する
+        let mut known = HashMap::new();
+        assert!(record_new_followers(&mut known, &["alice".to_string()], 100));
+        assert_eq!(known["alice"], 0);
+
+        // 既知のフォロワーは最初に記録した日時を保持する
+        assert!(!record_new_followers(&mut known, &["alice".to_string()], 200));
+        assert_eq!(known["alice"], 0);
+
+        // 新規フォロワーは初めて確認した日時で記録する
+        assert!(record_new_followers(&mut known, &["alice".to_string(), "bob".to_string()], 300));
+        assert_eq!(known["bob"], 300);
+    }
+
+    #[test]
+    fn test_embedded_reposted_note() {
+        let keys = Keys::generate();
+        let note = EventBuilder::text_note("元のノート").sign_with_keys(&keys).unwrap();
+        let repost = EventBuilder::repost(&note, None).sign_with_keys(&keys).unwrap();
+        assert_eq!(embedded_reposted_note(&repost).map(|e| e.id), Some(note.id));
+
+        // 埋め込みが改ざんされている・空の場合は e タグから取得する
+        let mut json: serde_json::Value = serde_json::from_str(&note.as_json()).unwrap();
+        json["content"] = serde_json::json!("改ざん");
+        let tampered = EventBuilder::new(Kind::Repost, json.to_string()).sign_with_keys(&keys).unwrap();
+        assert!(embedded_reposted_note(&tampered).is_none());
+        let empty = EventBuilder::new(Kind::Repost, "").sign_with_keys(&keys).unwrap();
+        assert!(embedded_reposted_note(&empty).is_none());
+    }
+
+    #[test]
+    fn test_combine_count_methods() {
+        assert_eq!(combine_count_methods(&["nip45", "nip45"]), "nip45");
+        assert_eq!(combine_count_methods(&["fetch", "fetch"]), "fetch");
+        assert_eq!(combine_count_methods(&["nip45", "fetch"]), "mixed");
+    }
+
+    #[test]
+    fn test_drop_invalid_signatures() {
+        let keys = Keys::generate();
+        let valid = EventBuilder::text_note("本物").sign_with_keys(&keys).unwrap();
+
+        // 署名後に内容を書き換えたイベント
+        let mut json: serde_json::Value = serde_json::from_str(&valid.as_json()).unwrap();
+        json["content"] = serde_json::json!("改ざん");
+        let tampered = Event::from_json(json.to_string()).unwrap();
+
+        let events = drop_invalid_signatures(vec![valid.clone(), tampered]);
+        assert_eq!(events, vec![valid]);
+    }
+
+    #[test]
+    fn test_top_counts() {
+        let counts: HashMap<String, u64> = [("rust", 3), ("nostr", 5), ("bitcoin", 3), ("mcp", 1)]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        let top = top_counts(counts, 3);
+        assert_eq!(
+            top,
+            vec![("nostr".to_string(), 5), ("bitcoin".to_string(), 3), ("rust".to_string(), 3)]
+        );
+    }
+
+    #[test]
+    fn test_event_hashtags() {
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("今日も #Nostr と #rust")
+            .tag(Tag::hashtag("nostr"))
+            .sign_with_keys(&keys)
+            .unwrap();
+        assert_eq!(event_hashtags(&event), vec!["nostr".to_string(), "rust".to_string()]);
+    }
+
+    #[test]
+    fn test_truncate_preview() {
+        assert_eq!(truncate_preview("こんにちは\n世界", 80), "こんにちは 世界");
+        assert_eq!(truncate_preview("abcdef", 3), "abc…");
+    }
+
+    #[test]
+    fn test_rank_follow_candidates() {
+        let me = Keys::generate();
+        let [carol, dave, erin] = std::array::from_fn(|_| Keys::generate().public_key());
+        let contact_list = |keys: &Keys, contacts: &[PublicKey]| EventBuilder::new(Kind::ContactList, "")
+            .tags(contacts.iter().map(|pk| Tag::public_key(*pk)))
+            .sign_with_keys(keys)
+            .unwrap();
+
+        let alice_keys = Keys::generate();
+        let bob_keys = Keys::generate();
+        let (alice, bob) = (alice_keys.public_key(), bob_keys.public_key());
+        let follows = vec![alice, bob];
+        let lists = HashMap::from([
+            // 自分・フォロー済み・重複した p タグは数えない
+            (alice, contact_list(&alice_keys, &[me.public_key(), bob, carol, dave, dave, erin])),
+            (bob, contact_list(&bob_keys, &[carol, dave])),
+        ]);
+
+        let ranked = rank_follow_candidates(me.public_key(), &follows, &lists, 1, |_| false);
+        let order: Vec<PublicKey> = ranked.iter().map(|(pk, _)| *pk).collect();
+        let (first, second) = if carol < dave { (carol, dave) } else { (dave, carol) };
+        assert_eq!(order, vec![first, second, erin]);
+        assert_eq!(ranked[0].1, vec![alice, bob]);
+
+        let ranked = rank_follow_candidates(me.public_key(), &follows, &lists, 2, |pk| *pk == dave);
+        assert_eq!(ranked, vec![(carol, vec![alice, bob])]);
+    }
+
+    #[test]
+    fn test_resolve_version_number() {
+        assert_eq!(resolve_version_number(1, 3).unwrap(), 1);
+        assert_eq!(resolve_version_number(3, 3).unwrap(), 3);
+        assert_eq!(resolve_version_number(-1, 3).unwrap(), 3);
+        assert_eq!(resolve_version_number(-3, 3).unwrap(), 1);
+        assert!(resolve_version_number(0, 3).is_err());
+        assert!(resolve_version_number(4, 3).is_err());
+        assert!(resolve_version_number(-4, 3).is_err());
+
+        assert_eq!(resolve_diff_versions(None, None, 3).unwrap(), (2, 3));
+        assert_eq!(resolve_diff_versions(None, Some(2), 3).unwrap(), (1, 2));
+        assert_eq!(resolve_diff_versions(Some(3), Some(1), 3).unwrap(), (3, 1));
+        assert!(resolve_diff_versions(None, Some(1), 3).is_err());
+        assert!(resolve_diff_versions(None, Some(-3), 3).is_err());
+    }
+
+    #[test]
+    fn test_check_payment_budget() {
+        let limits = crate::config::PaymentLimits {
+            max_per_payment_sats: Some(1_000),
+            daily_limit_sats: Some(2_000),
+        };
+        let now = 1_700_000_000;
+
+        assert!(check_payment_budget(&limits, &[], now, 1_000).is_ok());
+        assert!(check_payment_budget(&limits, &[], now, 1_001).is_err());
+
+        // 直近 24 時間の支払いのみ日次上限に数える
+        let ledger = vec![(now - 100, 900), (now - 200, 900), (now - 90_000, 1_000)];
+        assert!(check_payment_budget(&limits, &ledger, now, 200).is_ok());
+        assert!(check_payment_budget(&limits, &ledger, now, 201).is_err());
+
+        // 上限未設定なら制限なし
+        let unlimited = crate::config::PaymentLimits::default();
+        assert!(check_payment_budget(&unlimited, &ledger, now, 1_000_000).is_ok());
+    }
+
+    #[test]
+    fn test_check_reaction_rate() {
+        let preset = crate::config::ReactionPreset {
+            content: "🔥".to_string(),
+            emoji_url: None,
+            max_per_hour: Some(2),
+        };
+        let now = 1_700_000_000;
+
+        assert!(check_reaction_rate("fire", &preset, &[], now).is_ok());
+        // 1 時間より前の送信は数えない
+        assert!(check_reaction_rate("fire", &preset, &[now - 100, now - 4_000], now).is_ok());
+        let err = check_reaction_rate("fire", &preset, &[now - 100, now - 3_000], now).unwrap_err();
+        assert!(err.to_string().contains("10 分後"));
+
+        let unlimited = crate::config::ReactionPreset { max_per_hour: None, ..preset };
+        assert!(check_reaction_rate("fire", &unlimited, &[now; 100], now).is_ok());
+    }
+
+    #[test]
+    fn test_preset_emoji_tags() {
+        let mut preset = crate::config::ReactionPreset {
+            content: ":zapface:".to_string(),
+            emoji_url: Some("https://example.com/zapface.png".to_string()),
+            max_per_hour: None,
+        };
+        let tags = preset_emoji_tags("zapface", &preset).unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].as_slice(), ["emoji", "zapface", "https://example.com/zapface.png"]);
+
+        preset.content = "🔥".to_string();
+        assert!(preset_emoji_tags("zapface", &preset).is_err());
+
+        preset.emoji_url = None;
+        assert!(preset_emoji_tags("zapface", &preset).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tally_account_interactions() {
+        let me = Keys::generate();
+        let alice = Keys::generate();
+        let bob = Keys::generate();
+        let note = EventBuilder::text_note("gm").sign_with_keys(&me).unwrap();
+        let other = EventBuilder::text_note("other").sign_with_keys(&bob).unwrap();
+        let sign = |keys: &Keys, builder: EventBuilder| builder.sign_with_keys(keys).unwrap();
+        let zap = {
+            let request = sign(&bob, EventBuilder::new(Kind::ZapRequest, ""));
+            sign(&me, EventBuilder::new(Kind::ZapReceipt, "").tags(vec![
+                Tag::public_key(me.public_key()),
+                Tag::event(note.id),
+                Tag::custom(TagKind::custom("bolt11".to_string()), vec![crate::bolt11::test_invoice("lnbc10u", None)]),
+                Tag::custom(TagKind::custom("description".to_string()), vec![request.as_json()]),
+            ]))
+        };
+        let interactions = vec![
+            sign(&alice, EventBuilder::reaction(&note, "+")),
+            sign(&alice, EventBuilder::text_note("reply").tags(vec![Tag::event(note.id), Tag::public_key(me.public_key())])),
+            sign(&bob, EventBuilder::repost(&note, None)),
+            sign(&bob, EventBuilder::text_note("mention").tags(vec![Tag::event(other.id), Tag::public_key(me.public_key())])),
+            // 自分自身の反応は数えない
+            sign(&me, EventBuilder::reaction(&note, "+")),
+            zap,
+        ];
+
+        let tally = tally_account_interactions(&me.public_key(), std::slice::from_ref(&note), &interactions);
+        let expected = InteractionCounts { reactions: 1, replies: 1, reposts: 1, zaps: 1, zap_sats: 1_000 };
+        assert_eq!(tally.received, expected);
+        assert_eq!(tally.mentions, 1);
+        assert_eq!(tally.by_note[&note.id], expected);
+        assert_eq!(tally.by_account[&alice.public_key()].total(), 2);
+        assert_eq!(tally.by_account[&bob.public_key()], InteractionCounts { reposts: 1, zaps: 1, zap_sats: 1_000, ..Default::default() });
+        assert!(!tally.by_account.contains_key(&me.public_key()));
+
+        // 極端な金額の Zap でも溢れない
+        let mut counts = InteractionCounts::default();
+        counts.add(Kind::ZapReceipt, u64::MAX - 1);
+        counts.add(Kind::ZapReceipt, u64::MAX - 1);
+        assert_eq!((counts.zaps, counts.zap_sats), (2, u64::MAX));
+    }
+
+    #[test]
+    fn test_aggregate_relay_reports() {
+        let monitor_a = Keys::generate();
+        let monitor_b = Keys::generate();
+        let now = 1_700_000_000;
+        let report = |monitor: &Keys, relay: &str, age: u64, tags: &[&[&str]]| {
+            let mut all = vec![Tag::identifier(relay)];
+            all.extend(tags.iter().map(|t| Tag::parse(t.iter().copied()).unwrap()));
+            EventBuilder::new(Kind::from(KIND_RELAY_DISCOVERY), "")
+                .tags(all)
+                .custom_created_at(Timestamp::from(now - age))
+                .sign_with_keys(monitor)
+                .unwrap()
+        };
+        let reports = vec![
+            report(&monitor_a, "wss://fast.example.com", 60, &[&["rtt-open", "100"], &["N", "50"], &["N", "45"], &["n", "clearnet"], &["l", "JP", "countryCode"]]),
+            report(&monitor_b, "wss://fast.example.com", 120, &[&["rtt-open", "300"], &["N", "50"], &["R", "!payment"]]),
+            // 古いレポートは新しいもので置き換えられる
+            report(&monitor_b, "wss://fast.example.com", 5000, &[&["rtt-open", "9000"]]),
+            report(&monitor_a, "wss://slow.example.com", 60, &[&["rtt-open", "50"], &["R", "auth"], &["g", "xn76"]]),
+            // 確認間隔の 2 倍より古いレポートしかないモニターは停止中とみなす
+            report(&monitor_b, "wss://slow.example.com", 8000, &[&["rtt-open", "50"]]),
+        ];
+        let frequencies = HashMap::from([(monitor_b.public_key(), 3600)]);
+
+        let relays = aggregate_relay_reports(&reports, &frequencies, now);
+        assert_eq!(relays.len(), 2);
+        let fast = &relays[0];
+        assert_eq!((fast.url.as_str(), fast.monitors, fast.fresh_monitors), ("wss://fast.example.com", 2, 2));
+        assert_eq!(fast.rtt_open_ms, Some(100));
+        assert_eq!(fast.supported_nips, vec![45, 50]);
+        assert!(fast.requirements.is_empty());
+        assert_eq!(fast.countries, vec!["JP".to_string()]);
+        let slow = &relays[1];
+        assert_eq!((slow.fresh_monitors, slow.availability), (1, 0.5));
+
+        let query = RelayDiscoveryQuery { nips: vec![50], ..Default::default() };
+        assert!(query.matches(fast) && !query.matches(slow));
+        let query = RelayDiscoveryQuery { open_only: true, geohash: Some("XN".to_string()), ..Default::default() };
+        assert!(!query.matches(slow));
+        let query = RelayDiscoveryQuery { country: Some("jp".to_string()), network: Some("clearnet".to_string()), ..Default::default() };
+        assert!(query.matches(fast) && !query.matches(slow));
+
+        // 極端な確認間隔でもオーバーフローしない
+        let frequencies = HashMap::from([(monitor_b.public_key(), u64::MAX)]);
+        assert_eq!(aggregate_relay_reports(&reports, &frequencies, now)[1].fresh_monitors, 2);
+
+        // 告知の確認間隔は範囲内に丸め、告知のないモニターのレポートは除く
+        let announcement = |monitor: &Keys, frequency: &str| {
+            EventBuilder::new(Kind::from(KIND_RELAY_MONITOR), "")
+                .tag(Tag::parse(["frequency", frequency]).unwrap())
+                .sign_with_keys(monitor)
+                .unwrap()
+        };
+        let announcements = vec![announcement(&monitor_b, "99999999999")];
+        assert_eq!(monitor_frequencies(&announcements)[&monitor_b.public_key()], 86_400);
+        let announced = announced_monitor_reports(reports, &announcements);
+        assert_eq!(announced.len(), 3);
+        assert!(announced.iter().all(|e| e.pubkey == monitor_b.public_key()));
+    }
+
+    #[test]
+    fn test_publication_entries() {
+        let keys = Keys::generate();
+        let article = |d: &str| Coordinate::new(Kind::LongFormTextNote, keys.public_key()).identifier(d);
+        let publication = EventBuilder::new(Kind::from(KIND_CURATION_SET), "")
+            .tags(vec![
+                Tag::identifier("series"),
+                Tag::coordinate(article("part-1")),
+                Tag::parse(["a", &format!("30023:{}:part-2", keys.public_key().to_hex()), "wss://relay.example.com"]).unwrap(),
+                Tag::coordinate(article("part-1")),
+            ])
+            .sign_with_keys(&keys)
+            .unwrap();
+        let mut entries = publication_entries(publication.tags.iter());
+        assert_eq!(entries, vec![article("part-1"), article("part-2")]);
+
+        assert_eq!(insert_publication_entry(&mut entries, article("part-3"), None).unwrap(), 3);
+        assert_eq!(insert_publication_entry(&mut entries, article("intro"), Some(1)).unwrap(), 1);
+        assert!(insert_publication_entry(&mut entries, article("part-2"), None).is_err());
+        // 既に含まれている記事は指定した位置に移動し、範囲外は末尾として扱う
+        assert_eq!(insert_publication_entry(&mut entries, article("intro"), Some(99)).unwrap(), 4);
+        let order: Vec<&str> = entries.iter().map(|c| c.identifier.as_str()).collect();
+        assert_eq!(order, vec!["part-1", "part-2", "part-3", "intro"]);
+    }
+
+    #[test]
+    fn test_emoji_shortcodes() {
+        assert_eq!(emoji_shortcodes("gm :pepe: and :soon_tm::pepe:"), vec!["pepe", "soon_tm"]);
+        assert_eq!(emoji_shortcodes("時刻 12:30 :blob-cat: end:"), vec!["blob-cat"]);
+        assert!(emoji_shortcodes("no emoji: here").is_empty());
+        assert!(emoji_shortcodes(":: : :").is_empty());
+
+        let emojis = HashMap::from([("pepe".to_string(), "https://example.com/pepe.png".to_string())]);
+        let tags = emoji_tags(":pepe: :unknown:", &emojis);
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].as_slice(), &["emoji", "pepe", "https://example.com/pepe.png"]);
+    }
+
+    #[test]
+    fn test_emoji_collection_shortcode_map() {
+        let keys = Keys::generate();
+        let emoji = |shortcode: &str, url: &str| CustomEmoji { shortcode: shortcode.to_string(), url: url.to_string() };
+        let set = |in_emoji_list: bool, emojis: Vec<CustomEmoji>| EmojiSetInfo {
+            naddr: String::new(),
+            identifier: String::new(),
+            title: None,
+            author: AuthorInfo::from_public_key(&keys.public_key()),
+            in_emoji_list,
+            emojis,
+            created_at: 0,
+        };
+        let collection = EmojiCollection {
+            emojis: vec![emoji("a", "list")],
+            sets: vec![
+                set(false, vec![emoji("a", "own"), emoji("b", "own"), emoji("c", "own")]),
+                set(true, vec![emoji("b", "referenced")]),
+            ],
+        };
+        let map = collection.shortcode_map();
+        assert_eq!((map["a"].as_str(), map["b"].as_str(), map["c"].as_str()), ("list", "referenced", "own"));
+
+        let event = EventBuilder::new(Kind::from(KIND_EMOJI_SET), "")
+            .tags(vec![
+                Tag::identifier("cats"),
+                emoji("cat", "https://example.com/cat.png").to_tag(),
+                emoji("cat", "https://example.com/dup.png").to_tag(),
+                Tag::parse(["emoji", "bad code", "https://example.com/bad.png"]).unwrap(),
+            ])
+            .sign_with_keys(&keys)
+            .unwrap();
+        assert_eq!(parse_emoji_tags(&event), vec![emoji("cat", "https://example.com/cat.png")]);
+    }
+
+    #[test]
+    fn test_normalize_hashtag() {
+        assert_eq!(normalize_hashtag("#nostr").unwrap(), ("nostr".to_string(), vec!["nostr".to_string()]));
+        assert_eq!(
+            normalize_hashtag(" #Nostr ").unwrap(),
+            ("nostr".to_string(), vec!["nostr".to_string(), "Nostr".to_string()])
+        );
+        assert!(normalize_hashtag("#").is_err());
+        assert!(normalize_hashtag("two words").is_err());
+    }
+
+    #[test]
+    fn test_daily_note_counts() {
+        let keys = Keys::generate();
+        let day = 86_400 * 20_000;
+        let note = |at: u64| EventBuilder::text_note("#nostr").custom_created_at(Timestamp::from(at)).sign_with_keys(&keys).unwrap();
+        let events = vec![note(day + 10), note(day + 20), note(day + 2 * 86_400 + 5), note(day - 1)];
+
+        let daily = daily_note_counts(&events, day + 100, day + 2 * 86_400 + 100);
+        assert_eq!(daily.iter().map(|d| d.notes).collect::<Vec<_>>(), vec![2, 0, 1]);
+        assert_eq!(daily[1].day_start, day + 86_400);
+    }
+
+    #[test]
+    fn test_posting_stats() {
+        let keys = Keys::generate();
+        let at = |secs: u64, builder: EventBuilder| builder.custom_created_at(Timestamp::from(secs)).sign_with_keys(&keys).unwrap();
+        let day = 86_400 * 20_000;
+        let root = at(day + 3600, EventBuilder::text_note("root"));
+        let notes = vec![
+            root.clone(),
+            at(day + 3600 + 60, EventBuilder::text_note("reply").tag(Tag::event(root.id))),
+            at(day + 86_400 + 13 * 3600, EventBuilder::text_note("next day")),
+        ];
+
+        let stats = posting_stats(&notes, 2);
+        assert_eq!((stats.notes, stats.original_notes, stats.replies), (3, 2, 1));
+        assert_eq!(stats.notes_per_day, 1.5);
+        assert_eq!(stats.active_days, 2);
+        assert_eq!(stats.daily[0].day_start, day);
+        assert_eq!(stats.daily[0].notes, 2);
+        assert_eq!((stats.hourly[1], stats.hourly[13]), (2, 1));
+    }
+
+    #[test]
+    fn test_latest_replaceable_events() {
+        let keys = Keys::generate();
+        let list = |kind: u16, d: Option<&str>, at: u64| {
+            let mut builder = EventBuilder::new(Kind::from(kind), "").custom_created_at(Timestamp::from(at));
+            if let Some(d) = d {
+                builder = builder.tag(Tag::identifier(d));
+            }
+            builder.sign_with_keys(&keys).unwrap()
+        };
+
+        let old_contacts = list(3, None, 100);
+        let contacts = list(3, None, 200);
+        let friends = list(30000, Some("friends"), 100);
+        let old_work = list(30000, Some("work"), 300);
+        let work = list(30000, Some("work"), 400);
+
+        let latest = latest_replaceable_events(vec![
+            old_work, contacts.clone(), friends.clone(), work.clone(), old_contacts,
+        ]);
+        let ids: Vec<EventId> = latest.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![contacts.id, friends.id, work.id]);
+    }
+
+    #[test]
+    fn test_is_reply() {
+        let keys = Keys::generate();
+        let target = EventId::all_zeros().to_hex();
+        let note = |tags: Vec<Vec<&str>>| {
+            let tags = tags.into_iter().map(|t| Tag::parse(t).unwrap());
+            EventBuilder::text_note("hi").tags(tags).sign_with_keys(&keys).unwrap()
+        };
+
+        assert!(!is_reply(&note(vec![])));
+        assert!(!is_reply(&note(vec![vec!["q", &target]])));
+        assert!(!is_reply(&note(vec![vec!["e", &target, "", "mention"]])));
+        assert!(!is_reply(&note(vec![vec!["p", &keys.public_key().to_hex()]])));
+        assert!(is_reply(&note(vec![vec!["e", &target, "", "root"]])));
+        assert!(is_reply(&note(vec![vec!["e", &target, "wss://relay.example.com", "reply"]])));
+        assert!(is_reply(&note(vec![vec!["e", &target]])));
+        assert!(is_reply(&note(vec![vec!["e", &target, ""]])));
+    }
+
+    #[test]
+    fn test_is_reply_to() {
+        let keys = Keys::generate();
+        let target = EventId::all_zeros();
+        let other = EventId::from_slice(&[1; 32]).unwrap().to_hex();
+        let note = |tags: Vec<Vec<&str>>| {
+            let tags = tags.into_iter().map(|t| Tag::parse(t).unwrap());
+            EventBuilder::text_note("hi").tags(tags).sign_with_keys(&keys).unwrap()
+        };
+        let hex = target.to_hex();
+
+        assert!(!is_reply_to(&note(vec![vec!["e", &hex, "", "mention"]]), &target));
+        assert!(!is_reply_to(&note(vec![vec!["e", &other, "", "reply"], vec!["e", &hex, "", "mention"]]), &target));
+        assert!(is_reply_to(&note(vec![vec!["e", &hex, "", "root"]]), &target));
+        assert!(is_reply_to(&note(vec![vec!["e", &other, "", "root"], vec!["e", &hex, "", "reply"]]), &target));
+        assert!(is_reply_to(&note(vec![vec!["e", &hex]]), &target));
+    }
+
+    #[test]
+    fn test_reply_parent() {
+        let keys = Keys::generate();
+        let root = EventId::all_zeros();
+        let parent = EventId::from_slice(&[1; 32]).unwrap();
+        let note = |tags: Vec<Vec<String>>| {
+            let tags = tags.into_iter().map(|t| Tag::parse(t).unwrap());
+            EventBuilder::text_note("hi").tags(tags).sign_with_keys(&keys).unwrap()
+        };
+        let e = |id: &EventId, marker: &str| vec!["e".to_string(), id.to_hex(), String::new(), marker.to_string()];
+        let positional = |id: &EventId| vec!["e".to_string(), id.to_hex()];
+
+        assert_eq!(reply_parent(&note(vec![e(&root, "root"), e(&parent, "reply")])), Some(parent));
+        assert_eq!(reply_parent(&note(vec![e(&root, "root")])), Some(root));
+        assert_eq!(reply_parent(&note(vec![e(&root, "root"), e(&parent, "mention")])), Some(root));
+        assert_eq!(reply_parent(&note(vec![positional(&root), positional(&parent)])), Some(parent));
+        assert_eq!(reply_parent(&note(vec![e(&parent, "mention")])), None);
+        assert_eq!(reply_parent(&note(vec![])), None);
+    }
+
+    #[test]
+    fn test_reply_root_and_note_relation() {
+        let keys = Keys::generate();
+        let root = EventId::all_zeros();
+        let parent = EventId::from_slice(&[1; 32]).unwrap();
+        let note = |content: &str, tags: Vec<Vec<String>>| {
+            let tags = tags.into_iter().map(|t| Tag::parse(t).unwrap());
+            EventBuilder::text_note(content).tags(tags).sign_with_keys(&keys).unwrap()
+        };
+        let e = |id: &EventId, marker: &str| vec!["e".to_string(), id.to_hex(), String::new(), marker.to_string()];
+        let positional = |id: &EventId| vec!["e".to_string(), id.to_hex()];
+
+        assert_eq!(reply_root(&note("hi", vec![e(&root, "root"), e(&parent, "reply")])), Some(root));
+        assert_eq!(reply_root(&note("hi", vec![positional(&root), positional(&parent)])), Some(root));
+        assert_eq!(reply_root(&note("hi", vec![e(&parent, "reply")])), Some(parent));
+        assert_eq!(reply_root(&note("hi", vec![e(&parent, "mention")])), None);
+
+        let target = note("#rust の話", vec![e(&root, "root"), e(&parent, "reply")]);
+        let hashtags = event_hashtags(&target);
+        let reply = note("続き", vec![e(&root, "root"), e(&target.id, "reply")]);
+        let same_thread = note("別の返信", vec![e(&root, "root")]);
+        let tagged = note("今日も #Rust", vec![]);
+        let unrelated = note("おはよう", vec![]);
+
+        assert_eq!(note_relation(&reply, &target, root, &hashtags), Some("reply_to_note"));
+        assert_eq!(note_relation(&same_thread, &target, root, &hashtags), Some("same_thread"));
+        assert_eq!(note_relation(&tagged, &target, root, &hashtags), Some("shared_hashtag"));
+        assert_eq!(note_relation(&unrelated, &target, root, &hashtags), None);
+    }
+
+    #[test]
+    fn test_delegator_of() {
+        let delegator = Keys::generate();
+        let delegatee = Keys::generate();
+        let conditions = "kind=1&created_at>1600000000".parse::<Conditions>().unwrap();
+        let delegation = DelegationTag::new(&delegator, &delegatee.public_key(), conditions);
+        let token = delegation.as_json();
+        let note = |kind: Kind, delegation: &DelegationTag| {
+            let tag = Tag::from_standardized(TagStandard::Delegation {
+                delegator: delegation.delegator_pubkey(),
+                conditions: delegation.conditions(),
+                sig: delegation.signature(),
+            });
+            EventBuilder::new(kind, "hi").tag(tag).sign_with_keys(&delegatee).unwrap()
+        };
+
+        let delegated = note(Kind::TextNote, &delegation);
+        assert_eq!(delegator_of(&delegated), Some(delegator.public_key()));
+        assert_eq!(event_author(&delegated), delegator.public_key());
+        assert_eq!(signed_by(&delegated, &delegator.public_key()), Some(delegatee.public_key().to_bech32().unwrap()));
+
+        // 条件外の Kind と、別の鍵への委任は委任者のものとみなさない
+        let reaction = note(Kind::Reaction, &delegation);
+        assert_eq!(event_author(&reaction), delegatee.public_key());
+        let other = DelegationTag::new(&delegator, &Keys::generate().public_key(), delegation.conditions());
+        assert_eq!(delegator_of(&note(Kind::TextNote, &other)), None);
+        let plain = EventBuilder::text_note("hi").sign_with_keys(&delegatee).unwrap();
+        assert_eq!(delegator_of(&plain), None);
+        assert_eq!(signed_by(&plain, &plain.pubkey), None);
+
+        assert!(parse_delegation_token(&token, &delegatee.public_key()).is_ok());
+        assert!(parse_delegation_token(&token, &Keys::generate().public_key()).is_err());
+        assert!(parse_delegation_token("not json", &delegatee.public_key()).is_err());
+    }
+
+    #[test]
+    fn test_relay_connectivity_problem() {
+        let url = |s: &str| RelayUrl::parse(s).unwrap();
+        assert!(relay_connectivity_problem(&[]).unwrap().contains("読み取り用のリレー"));
+
+        let down = vec![
+            (url("wss://a.example.com"), RelayStatus::Disconnected),
+            (url("wss://b.example.com"), RelayStatus::Connecting),
+        ];
+        let problem = relay_connectivity_problem(&down).unwrap();
+        assert!(problem.starts_with(NO_CONNECTED_RELAYS_MESSAGE));
+        assert!(problem.contains("wss://a.example.com"));
+
+        assert_eq!(relay_wait_limit(&down), RELAY_READY_TIMEOUT);
+        assert_eq!(relay_wait_limit(&down[..1]), RELAY_RECONNECT_WAIT);
+
+        let mut partly = down;
+        partly.push((url("wss://c.example.com"), RelayStatus::Connected));
+        assert_eq!(relay_connectivity_problem(&partly), None);
+    }
+
+    #[test]
+    fn test_parse_wallet_info_event() {
+        let event = EventBuilder::new(Kind::WalletConnectInfo, "pay_invoice get_balance  get_info notifications")
+            .tags(vec![
+                Tag::custom(TagKind::custom("encryption".to_string()), vec!["nip44_v2 nip04".to_string()]),
+                Tag::custom(TagKind::custom("notifications".to_string()), vec!["payment_received".to_string()]),
+            ])
+            .sign_with_keys(&Keys::generate())
+            .unwrap();
+
+        let (methods, encryption, notifications) = parse_wallet_info_event(&event);
+        assert_eq!(methods, vec!["pay_invoice", "get_balance", "get_info", "notifications"]);
+        assert_eq!(encryption, vec!["nip44_v2", "nip04"]);
+        assert_eq!(notifications, vec!["payment_received"]);
+
+        // 古いウォレットは encryption タグを持たない
+        let legacy = EventBuilder::new(Kind::WalletConnectInfo, "pay_invoice")
+            .sign_with_keys(&Keys::generate())
+            .unwrap();
+        assert_eq!(parse_wallet_info_event(&legacy), (vec!["pay_invoice".to_string()], vec![], vec![]));
+    }
+
+    #[test]
+    fn test_publish_result_from_output() {
+        let ok = RelayUrl::parse("wss://b.example.com").unwrap();
+        let failed = RelayUrl::parse("wss://a.example.com").unwrap();
+        let output = Output {
+            val: EventId::all_zeros(),
+            success: [ok].into_iter().collect(),
+            failed: [(failed, "blocked: spam".to_string())].into_iter().collect(),
+        };
+
+        let result = PublishResult::new(output, None);
+        assert_eq!(result.published_to, vec!["wss://b.example.com".to_string()]);
+        assert_eq!(result.failed_relays.len(), 1);
+        assert_eq!(result.failed_relays[0].relay, "wss://a.example.com");
+        assert_eq!(result.failed_relays[0].reason, "blocked: spam");
+    }
+
+    #[test]
+    fn test_is_rejected_by_all() {
+        let a = RelayUrl::parse("wss://a.example.com").unwrap();
+        let b = RelayUrl::parse("wss://b.example.com").unwrap();
+        let output = |failed: Vec<(&RelayUrl, &str)>| Output {
+            val: EventId::all_zeros(),
+            success: std::collections::HashSet::new(),
+            failed: failed.into_iter().map(|(url, reason)| (url.clone(), reason.to_string())).collect(),
+        };
+
+        assert!(is_rejected_by_all(&output(vec![(&a, "blocked: spam"), (&b, "invalid: bad signature")])));
+        // 一時的な失敗のリレーがあれば再送の対象にする
+        assert!(!is_rejected_by_all(&output(vec![(&a, "blocked: spam"), (&b, "relay not connected")])));
+        assert!(!is_rejected_by_all(&output(vec![(&a, "rate-limited: slow down")])));
+        // 書き込みリレーがない場合も接続の問題として扱う
+        assert!(!is_rejected_by_all(&output(Vec::new())));
+
+        assert_eq!(
+            describe_publish_failures(&output(vec![(&b, "invalid: bad signature"), (&a, "blocked: spam")])),
+            "wss://a.example.com: blocked: spam, wss://b.example.com: invalid: bad signature"
+        );
+    }
+
+    #[test]
+    fn test_pending_publish_backoff() {
+        assert_eq!(pending_publish_backoff(0), 30);
+        assert_eq!(pending_publish_backoff(1), 60);
+        assert_eq!(pending_publish_backoff(3), 240);
+        assert_eq!(pending_publish_backoff(7), PENDING_PUBLISH_MAX_DELAY_SECS);
+        assert_eq!(pending_publish_backoff(u32::MAX), PENDING_PUBLISH_MAX_DELAY_SECS);
+    }
+
+    #[test]
+    fn test_encode_nevent_and_nprofile() {
+        let id = EventId::all_zeros();
+        let pk = Keys::generate().public_key();
+        let relays: Vec<String> = (1..=5).map(|i| format!("wss://relay{}.example.com", i)).collect();
+
+        let decoded = Nip19Event::from_bech32(&encode_nevent(&id, Some(pk), &relays)).unwrap();
+        assert_eq!(decoded.event_id, id);
+        assert_eq!(decoded.author, Some(pk));
+        assert_eq!(decoded.relays.len(), NEVENT_RELAY_HINTS);
+        assert_eq!(decoded.relays[0], "wss://relay1.example.com");
+
+        let bare = Nip19Event::from_bech32(&encode_nevent(&id, None, &[])).unwrap();
+        assert_eq!(bare.author, None);
+        assert!(bare.relays.is_empty());
+
+        assert!(encode_nprofile(&pk, &[]).is_none());
+        let profile = Nip19Profile::from_bech32(&encode_nprofile(&pk, &relays).unwrap()).unwrap();
+        assert_eq!(profile.public_key, pk);
+        assert_eq!(profile.relays.len(), NEVENT_RELAY_HINTS);
+    }
+
+    #[test]
+    fn test_find_pointed_event() {
+        let keys = Keys::generate();
+        let note = EventBuilder::text_note("ノート").sign_with_keys(&keys).unwrap();
+        let article = |content: &str, at: u64| EventBuilder::new(Kind::LongFormTextNote, content)
+            .tag(Tag::identifier("post"))
+            .custom_created_at(Timestamp::from(at))
+            .sign_with_keys(&keys)
+            .unwrap();
+        let old = article("v1", 1_000);
+        let new = article("v2", 2_000);
+        let events = vec![note.clone(), old, new.clone()];
+
+        let coordinate = Coordinate::new(Kind::LongFormTextNote, keys.public_key()).identifier("post");
+        let pointers = [EventPointer::Id(note.id), EventPointer::Id(new.id), EventPointer::Coordinate(coordinate.clone())];
+        // ID は 1 つのフィルタにまとめる
+        assert_eq!(pointer_filters(pointers.iter()).len(), 2);
+
+        assert_eq!(find_pointed_event(&events, &EventPointer::Id(note.id)).map(|e| e.id), Some(note.id));
+        assert_eq!(find_pointed_event(&events, &EventPointer::Coordinate(coordinate)).map(|e| e.id), Some(new.id));
+        let missing = Coordinate::new(Kind::LongFormTextNote, keys.public_key()).identifier("other");
+        assert!(find_pointed_event(&events, &EventPointer::Coordinate(missing)).is_none());
+    }
+
+    #[test]
+    fn test_parse_nip19_identifiers() {
+        let pk = Keys::generate().public_key();
+        let relay = "wss://relay.example.com";
+
+        let nprofile = encode_nprofile(&pk, &[relay.to_string()]).unwrap();
+        let (parsed, hints) = NostrClient::parse_profile_pointer(&format!("nostr:{}", nprofile)).unwrap();
+        assert_eq!(parsed, pk);
+        assert_eq!(hints, vec![relay.to_string()]);
+        assert_eq!(NostrClient::parse_public_key(&pk.to_bech32().unwrap()).unwrap(), pk);
+        assert_eq!(NostrClient::parse_public_key(&pk.to_hex()).unwrap(), pk);
+
+        let id = EventId::all_zeros();
+        let nevent = encode_nevent(&id, Some(pk), &[relay.to_string()]);
+        let (pointer, hints) = NostrClient::parse_event_pointer(&nevent).unwrap();
+        assert_eq!(pointer, EventPointer::Id(id));
+        assert_eq!(hints, vec![relay.to_string()]);
+        assert_eq!(NostrClient::parse_event_id(&id.to_bech32().unwrap()).unwrap(), id);
+
+        let mut coordinate = Coordinate::new(Kind::LongFormTextNote, pk).identifier("article");
+        coordinate.relays = vec![RelayUrl::parse(relay).unwrap()];
+        let naddr = coordinate.to_bech32().unwrap();
+        let (pointer, hints) = NostrClient::parse_event_pointer(&naddr).unwrap();
+        match pointer {
+            EventPointer::Coordinate(parsed) => {
+                assert_eq!(parsed.public_key, pk);
+                assert_eq!(parsed.identifier, "article");
+            }
+            other => panic!("naddr が座標として解釈されていません: {:?}", other),
+        }
+        assert_eq!(hints, vec![relay.to_string()]);
+        assert!(NostrClient::parse_event_id(&naddr).is_err());
+    }
+
+    #[test]
+    fn test_mentioned_pubkeys() {
+        let pk = Keys::generate().public_key();
+        let npub = pk.to_bech32().unwrap();
+        let nprofile = Nip19Profile::new(pk, ["wss://relay.example.com"]).unwrap().to_bech32().unwrap();
+        let content = format!("hi nostr:{npub}, again nostr:{nprofile}. nostr:note1invalid npub1bare");
+
+        assert_eq!(mentioned_pubkeys(&content), vec![pk]);
+        assert!(mentioned_pubkeys("no mentions here").is_empty());
+    }
+
+    #[test]
+    fn test_user_match_score_ranking() {
+        let exact = author(Some("alice"), None, None);
+        let prefix = author(Some("alice_btc"), None, None);
+        let contains = author(None, Some("Wonderland Alice"), None);
+        let nip05 = author(Some("bob"), None, Some("alice@example.com"));
+        let none = author(Some("bob"), None, None);
+
+        assert_eq!(user_match_score(&exact, "Alice"), 100);
+        assert_eq!(user_match_score(&prefix, "alice"), 75);
+        assert_eq!(user_match_score(&contains, "alice"), 50);
+        assert_eq!(user_match_score(&nip05, "alice"), 40);
+        assert_eq!(user_match_score(&none, "alice"), 0);
+        assert_eq!(user_match_score(&exact, "  "), 0);
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_benchmark() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        for i in 0..3 {
+            relay.insert(EventBuilder::text_note(format!("note {}", i)).sign_with_keys(&keys).unwrap()).await;
+        }
+        let client = mock_relay_client(&relay, &keys).await;
+
+        // 待ち受けていないポートは接続できないリレーとして最後に並ぶ
+        let unreachable = "ws://127.0.0.1:1".to_string();
+        let results = client.benchmark_relays(&[unreachable.clone(), relay.url().to_string()]).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!((results[0].rank, results[0].connected, results[0].events), (1, true, 3));
+        assert!(results[0].in_pool && results[0].query_ms.is_some());
+        assert_eq!(results[1].url, RelayUrl::parse(&unreachable).unwrap().to_string());
+        assert!(!results[1].connected && results[1].error.is_some());
+
+        assert!(client.benchmark_relays(&["not a url".to_string()]).await.is_err());
+        client.disconnect().await;
+    }
+
+    /// モックリレーだけに接続したクライアント（`keys` で署名）
+    #[cfg(feature = "mock-relay")]
+    async fn mock_relay_client(relay: &crate::mock_relay::MockRelay, keys: &Keys) -> NostrClient {
+        NostrClient::new(mock_relay_config(relay, keys)).await.unwrap()
+    }
+
+    #[cfg(feature = "mock-relay")]
+    fn mock_relay_config(relay: &crate::mock_relay::MockRelay, keys: &Keys) -> NostrClientConfig {
+        let relays = vec![relay.url().to_string()];
+        NostrClientConfig {
+            secret_key: Some(keys.secret_key().to_secret_hex()),
+            public_key: None,
+            relays: relays.clone(),
+            write_relays: relays,
+            search_relays: Vec::new(),
+            nwc_uri: None,
+            payment_limits: Default::default(),
+            web_of_trust: None,
+            contact_list_cache_ttl: Duration::ZERO,
+            rate_limit: Default::default(),
+            signature_verification: SignatureVerification::Off,
+            min_pow: 0,
+            content_filters: Default::default(),
+            sensitive_content: SensitiveContentPolicy::Show,
+            languages: Vec::new(),
+            auth_mode: crate::config::AuthMode::Local,
+            nip46_config: None,
+            delegation: None,
+            output: Default::default(),
+            dry_run: false,
+            default_reaction: None,
+            reaction_presets: HashMap::new(),
+        }
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_delegation_signing() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let delegator = Keys::generate();
+        let delegatee = Keys::generate();
+        let conditions: Conditions = "kind=24242".parse().unwrap();
+        let mut config = mock_relay_config(&relay, &delegatee);
+        config.secret_key = None;
+        config.delegation = Some(crate::config::DelegationConfig {
+            token: DelegationTag::new(&delegator, &delegatee.public_key(), conditions).as_json(),
+            delegatee_key: delegatee.secret_key().to_secret_hex(),
+        });
+        let client = NostrClient::new(config).await.unwrap();
+
+        // Blossom の認証イベントにも delegation タグを付ける
+        let auth = client.sign_blossom_auth(&"0".repeat(64), 10, "image/png").await.unwrap();
+        assert_eq!(auth.pubkey, delegatee.public_key());
+        assert_eq!(delegator_of(&auth), Some(delegator.public_key()));
+
+        // DM は委任者として暗号化できないため送信しない
+        let err = client.send_dm(&Keys::generate().public_key().to_hex(), "こんにちは").await.unwrap_err();
+        assert!(err.to_string().contains("委任"));
+        assert!(relay.events().await.is_empty());
+
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_reaction_rate_limit() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        // 送信履歴は共通のファイルに保存されるため、実行ごとに別のプリセット名を使う
+        let name = format!("limited-{}", keys.public_key().to_hex());
+        let mut config = mock_relay_config(&relay, &keys);
+        config.reaction_presets.insert(name.clone(), crate::config::ReactionPreset {
+            content: "🔥".to_string(),
+            emoji_url: None,
+            max_per_hour: Some(1),
+        });
+        let client = NostrClient::new(config).await.unwrap();
+        let note = client.post_note("リアクションしてください").await.unwrap();
+
+        // 送信に失敗した分は上限に数えない
+        assert!(client.react_to_note(&EventId::all_zeros().to_hex(), Some(&name)).await.is_err());
+        client.react_to_note(&note.id.to_hex(), Some(&name)).await.unwrap();
+        let err = client.react_to_note(&note.id.to_hex(), Some(&name)).await.unwrap_err();
+        assert!(err.to_string().contains(&name));
+
+        // 送信履歴はファイルに保存する（確認後はテスト用のプリセットを取り除く）
+        let saved = crate::storage::load_reaction_log();
+        assert_eq!(saved.get(&name).map(Vec::len), Some(1));
+        let mut log = saved;
+        log.remove(&name);
+        crate::storage::save_reaction_log(&log).unwrap();
+
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_rebroadcast_restores_relays() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let read_only = crate::mock_relay::MockRelay::run().await.unwrap();
+        let outside = crate::mock_relay::MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        let client = mock_relay_client(&relay, &keys).await;
+        client.post_note("再送信するノート").await.unwrap();
+
+        // 読み取り専用でプールにあるリレーは、送信後に書き込みフラグを外す
+        client.client.add_read_relay(read_only.url()).await.unwrap();
+        let result = client.rebroadcast_events(read_only.url(), &[1], None, None, 10).await.unwrap();
+        assert_eq!(result.published, 1);
+        let pooled = client.client.relay(read_only.url()).await.unwrap();
+        assert!(pooled.flags().has_read());
+        assert!(!pooled.flags().has_write());
+
+        // プールになかったリレーは送信後に削除する
+        let result = client.rebroadcast_events(outside.url(), &[1], None, None, 10).await.unwrap();
+        assert_eq!(result.published, 1);
+        assert!(client.client.relay(outside.url()).await.is_err());
+
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_export_my_events() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        let client = mock_relay_client(&relay, &keys).await;
+        client.post_note("1 件目").await.unwrap();
+        client.post_note("2 件目").await.unwrap();
+
+        let dir = std::env::temp_dir().join(format!("rust-nostr-mcp-export-{}", keys.public_key().to_hex()));
+        let path = dir.join("events.jsonl");
+        let path_str = path.to_str().unwrap();
+        let export = client.export_my_events(Some(path_str), &[1], 1, false).await.unwrap();
+        assert_eq!(export.count, 1);
+        assert!(export.truncated);
+
+        // 既存のファイルは overwrite を指定したときだけ上書きする
+        assert!(client.export_my_events(Some(path_str), &[1], 10, false).await.is_err());
+        let export = client.export_my_events(Some(path_str), &[1], 10, true).await.unwrap();
+        assert_eq!(export.count, 2);
+        assert!(!export.truncated);
+
+        let _ = std::fs::remove_dir_all(dir);
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_rejected_event_is_not_queued() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        let client = mock_relay_client(&relay, &keys).await;
+        client.ensure_connected_relays().await.unwrap();
+
+        // 署名が不正なイベントはリレーに invalid: で拒否されるため、送信待ちキューに入れない
+        let valid = EventBuilder::text_note("本物").sign_with_keys(&keys).unwrap();
+        let mut tampered: serde_json::Value = serde_json::from_str(&valid.as_json()).unwrap();
+        tampered["content"] = serde_json::json!("改ざん");
+        let tampered = Event::from_json(tampered.to_string()).unwrap();
+        let err = client.send_event(tampered.clone()).await.unwrap_err();
+        assert!(err.to_string().contains("invalid:"));
+        let queued = client.get_pending_publishes().await;
+        assert!(queued.iter().all(|pending| pending.id != tampered.id.to_hex()));
+
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_hint_relays_are_released() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let hinted = crate::mock_relay::MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        let note = EventBuilder::text_note("ヒントのリレーだけにあるノート").sign_with_keys(&keys).unwrap();
+        hinted.insert(note.clone()).await;
+        let client = mock_relay_client(&relay, &keys).await;
+        client.ensure_connected_relays().await.unwrap();
+
+        let events = client
+            .fetch_events_with_hints(vec![Filter::new().id(note.id)], &[hinted.url().to_string()], Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(events.into_iter().any(|event| event.id == note.id));
+
+        // 取得後はヒントのリレーをプールから削除する
+        assert!(client.client.relay(hinted.url()).await.is_err());
+        assert!(client.gossip_relay_users.lock().await.is_empty());
+        assert!(client.client.relay(relay.url()).await.is_ok());
+
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_resolve_references_applies_filters() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        let author = Keys::generate();
+        let plain = EventBuilder::text_note("ふつうのノート").sign_with_keys(&author).unwrap();
+        let muted = EventBuilder::text_note("ネタバレを含むノート").sign_with_keys(&author).unwrap();
+        let sensitive = EventBuilder::text_note("警告付きのノート")
+            .tag(Tag::parse(["content-warning", "spoiler"]).unwrap())
+            .sign_with_keys(&author)
+            .unwrap();
+        for event in [&plain, &muted, &sensitive] {
+            relay.insert(event.clone()).await;
+        }
+        let references: Vec<String> = [&plain, &muted, &sensitive].iter()
+            .map(|event| event.id.to_bech32().unwrap())
+            .collect();
+
+        let mut config = mock_relay_config(&relay, &keys);
+        config.content_filters.muted_words = vec!["ネタバレ".to_string()];
+        config.sensitive_content = SensitiveContentPolicy::Flag;
+        let client = NostrClient::new(config).await.unwrap();
+        let resolved = client.resolve_references(&references).await;
+        assert_eq!(resolved[&references[0]].preview.as_deref(), Some("ふつうのノート"));
+        assert!(!resolved.contains_key(&references[1]));
+        let flagged = &resolved[&references[2]];
+        assert_eq!(flagged.content_warning.as_deref(), Some("spoiler"));
+        assert!(!flagged.preview.as_deref().unwrap().contains("警告付きのノート"));
+        client.disconnect().await;
+
+        let mut config = mock_relay_config(&relay, &keys);
+        config.sensitive_content = SensitiveContentPolicy::Hide;
+        let client = NostrClient::new(config).await.unwrap();
+        let resolved = client.resolve_references(&references).await;
+        assert!(resolved.contains_key(&references[1]));
+        assert!(!resolved.contains_key(&references[2]));
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_follower_snapshot() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        let follow = |follower: &Keys, created_at: u64| EventBuilder::new(Kind::ContactList, "")
+            .tag(Tag::public_key(keys.public_key()))
+            .custom_created_at(Timestamp::from(created_at))
+            .sign_with_keys(follower)
+            .unwrap();
+        let alice = Keys::generate();
+        relay.insert(follow(&alice, 1_000)).await;
+        let client = mock_relay_client(&relay, &keys).await;
+
+        // 初回は既存のフォロワーを増加として記録しない
+        let history = client.record_follower_snapshot().await.unwrap();
+        assert!(history.changes.is_empty());
+        assert_eq!(history.snapshots.last().map(|s| s.followers), Some(1));
+
+        // 増加はコンタクトリストの作成日時ではなく、初めて確認した日時で記録する
+        let bob = Keys::generate();
+        relay.insert(follow(&bob, 2_000)).await;
+        let before = current_unix_timestamp();
+        let history = client.record_follower_snapshot().await.unwrap();
+        let gained: Vec<&crate::storage::FollowerChange> = history.changes.iter().filter(|c| c.change == "gained").collect();
+        assert_eq!(gained.len(), 1);
+        assert_eq!(gained[0].pubkey, bob.public_key().to_hex());
+        assert!(gained[0].at >= before);
+        assert_eq!(history.snapshots.last().map(|s| s.followers), Some(2));
+
+        // 同じフォロワーを二重に数えない
+        let history = client.record_follower_snapshot().await.unwrap();
+        assert_eq!(history.changes.len(), 1);
+
+        let account = keys.public_key().to_hex();
+        let _ = crate::storage::save_known_followers(&account, &HashMap::new());
+        let _ = crate::storage::save_follower_history(&account, &Default::default());
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_lightning_invoice_amount_overflow() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let client = mock_relay_client(&relay, &Keys::generate()).await;
+
+        // msat に換算できない金額は LNURL エンドポイントに問い合わせる前にエラーにする
+        let err = client.get_lightning_invoice("alice@example.com", u64::MAX, None, None, false).await.unwrap_err();
+        assert!(err.to_string().contains("大きすぎます"));
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_live_streams_with_chat() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let client = mock_relay_client(&relay, &Keys::generate()).await;
+
+        let now = Timestamp::now().as_u64();
+        let mut coordinates = Vec::new();
+        for (identifier, messages) in [("stream-a", 3), ("stream-b", 1)] {
+            let host = Keys::generate();
+            let stream = EventBuilder::new(Kind::LiveEvent, "")
+                .tags([
+                    Tag::identifier(identifier),
+                    Tag::parse(["title", identifier]).unwrap(),
+                    Tag::parse(["status", "live"]).unwrap(),
+                ])
+                .sign_with_keys(&host)
+                .unwrap();
+            relay.insert(stream).await;
+            let coordinate = Coordinate::new(Kind::LiveEvent, host.public_key()).identifier(identifier);
+            for i in 0..messages {
+                let message = EventBuilder::new(Kind::LiveEventMessage, format!("{} {}", identifier, i))
+                    .tag(Tag::coordinate(coordinate.clone()))
+                    .custom_created_at(Timestamp::from(now - 10 + i))
+                    .sign_with_keys(&Keys::generate())
+                    .unwrap();
+                relay.insert(message).await;
+            }
+            coordinates.push(coordinate);
+        }
+
+        let streams = client.get_live_streams(false, true, 2, 10).await.unwrap();
+        assert_eq!(streams.len(), 2);
+        let chat_of = |title: &str| -> Vec<String> {
+            streams.iter()
+                .find(|s| s.title == title)
+                .and_then(|s| s.chat.as_ref())
+                .map(|chat| chat.iter().map(|m| m.content.clone()).collect())
+                .unwrap()
+        };
+        assert_eq!(chat_of("stream-a"), vec!["stream-a 1", "stream-a 2"]);
+        assert_eq!(chat_of("stream-b"), vec!["stream-b 0"]);
+
+        let naddr = coordinates[1].to_bech32().unwrap();
+        let chat = client.get_live_chat(&naddr, 10).await.unwrap();
+        assert_eq!(chat.len(), 1);
+        assert_eq!(chat[0].content, "stream-b 0");
+
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_create_account_refused_in_dry_run() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        let client = Arc::new(tokio::sync::RwLock::new(mock_relay_client(&relay, &keys).await));
+        let registry = Arc::new(crate::nip46::Nip46Registry::new(crate::nip46::Nip46Config {
+            relays: vec![relay.url().to_string()],
+            perms: None,
+            bunker_uri: None,
+        }));
+        let executor = crate::tools::ToolExecutor::new(client.clone(), registry, Default::default());
+
+        let config_path = crate::config::Config::config_path().unwrap();
+        let config_before = std::fs::read(&config_path).ok();
+        let result = executor
+            .execute("create_nostr_account", serde_json::json!({"name": "dry", "replace_existing_key": true, "dry_run": true}))
+            .await;
+        assert!(result.unwrap_err().to_string().contains("ドライラン"));
+        assert_eq!(std::fs::read(&config_path).ok(), config_before);
+        assert_eq!(client.read().await.public_key(), Some(keys.public_key()));
+        assert!(relay.events().await.is_empty());
+
+        client.read().await.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_calendar_events() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        let client = mock_relay_client(&relay, &keys).await;
+
+        let now = current_unix_timestamp();
+        let calendar_event = |d: &str, start: u64, created_at: u64| {
+            EventBuilder::new(Kind::from(31923), "")
+                .tags(vec![
+                    Tag::identifier(d),
+                    Tag::custom(TagKind::custom("title".to_string()), vec![d.to_string()]),
+                    Tag::custom(TagKind::custom("start".to_string()), vec![start.to_string()]),
+                ])
+                .custom_created_at(Timestamp::from(created_at))
+                .sign_with_keys(&keys)
+                .unwrap()
+        };
+        // 古く作成された先の予定、過去の予定、作り直されて日時が変わった予定
+        relay.insert(calendar_event("later", now + 5 * 86_400, now - 300 * 86_400)).await;
+        relay.insert(calendar_event("past", now - 86_400, now - 10)).await;
+        relay.insert(calendar_event("moved", now + 86_400, now - 100)).await;
+        relay.insert(calendar_event("moved", now + 2 * 86_400, now - 50)).await;
+
+        let (events, truncated) = client.get_calendar_events(false, 30, 10).await.unwrap();
+        assert!(!truncated);
+        let titles: Vec<&str> = events.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["moved", "later"]);
+        assert_eq!(events[0].start_timestamp, now + 2 * 86_400);
+
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_post_and_fetch() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        let client = mock_relay_client(&relay, &keys).await;
+
+        let posted = client.post_note("モックリレーへの投稿").await.unwrap();
+        assert_eq!(posted.published_to, vec![relay.url().to_string()]);
+        assert!(posted.failed_relays.is_empty());
+        assert!(relay.events().await.iter().any(|e| e.id == posted.id));
+
+        // フォローがないのでグローバルタイムラインとして取得される
+        let timeline = client.get_timeline(10, 0, true, &LanguageFilter::default()).await.unwrap().0;
+        assert_eq!(timeline.len(), 1);
+        assert_eq!(timeline[0].id, posted.id.to_hex());
+        assert_eq!(timeline[0].content, "モックリレーへの投稿");
+        assert_eq!(timeline[0].author.pubkey, keys.public_key().to_hex());
+        assert_eq!(timeline[0].seen_on, vec![relay.url().to_string()]);
+
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_dry_run() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let client = mock_relay_client(&relay, &Keys::generate()).await;
+
+        let (posted, events) = crate::dry_run::scope(client.post_note("ドライランの投稿")).await;
+        let posted = posted.unwrap();
+        assert!(posted.published_to.is_empty());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, posted.id);
+        assert_eq!(events[0].content, "ドライランの投稿");
+        assert!(events[0].verify().is_ok());
+        assert!(relay.events().await.is_empty());
+        assert!(crate::storage::load_pending_publishes().iter().all(|p| p.event.id != posted.id));
+
+        // ドライランで公開したローカル下書きは削除しない
+        client.save_local_draft(ArticleParams {
+            title: "ドライランの下書き".to_string(),
+            content: "本文".to_string(),
+            identifier: Some("dry-run-draft".to_string()),
+            summary: None,
+            image: None,
+            tags: None,
+            published_at: None,
+        }).unwrap();
+        let (published, events) = crate::dry_run::scope(client.publish_local_draft("dry-run-draft", None)).await;
+        assert!(published.unwrap().1.published_to.is_empty());
+        assert_eq!(events.len(), 1);
+        assert!(relay.events().await.is_empty());
+        assert!(client.list_local_drafts().unwrap().iter().any(|d| d.identifier == "dry-run-draft"));
+
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_thread() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let client = mock_relay_client(&relay, &Keys::generate()).await;
+
+        let root = client.post_note("ルート").await.unwrap();
+        let reply = client.reply_to_note(&root.id.to_hex(), "リプライ").await.unwrap();
+        let nested = client.reply_to_note(&reply.id.to_hex(), "リプライへのリプライ").await.unwrap();
+
+        let thread = client.get_thread(&root.id.to_hex(), 3).await.unwrap();
+        assert_eq!(thread.root.content, "ルート");
+        assert_eq!(thread.total_replies, 2);
+        assert_eq!(thread.replies.len(), 1);
+        assert_eq!(thread.replies[0].note.id, reply.id.to_hex());
+        assert_eq!(thread.replies[0].replies.len(), 1);
+        assert_eq!(thread.replies[0].replies[0].note.id, nested.id.to_hex());
+
+        client.disconnect().await;
+    }
+
+    #[cfg(feature = "mock-relay")]
+    #[tokio::test]
+    async fn test_mock_relay_zap_receipts() {
+        let relay = crate::mock_relay::MockRelay::run().await.unwrap();
+        let keys = Keys::generate();
+        let client = mock_relay_client(&relay, &keys).await;
+        let note = client.post_note("Zap してください").await.unwrap();
+
+        // 受取人の LNURL プロバイダーの nostrPubkey を取得済みにする
+        let provider = Keys::generate();
+        client.zap_provider_cache.write().await
+            .insert(keys.public_key(), (std::time::Instant::now(), Some(provider.public_key()), ZAP_PROVIDER_CACHE_TTL));
+
+        let receipt = |signer: &Keys, recipient: PublicKey, description: &str| {
+            EventBuilder::new(Kind::ZapReceipt, "")
+                .tags(vec![
+                    Tag::event(note.id),
+                    Tag::public_key(recipient),
+                    Tag::custom(TagKind::custom("bolt11".to_string()), vec![test_zap_invoice()]),
+                    Tag::custom(TagKind::custom("description".to_string()), vec![description.to_string()]),
+                ])
+                .sign_with_keys(signer)
+                .unwrap()
+        };
+        let genuine = receipt(&provider, keys.public_key(), TEST_ZAP_REQUEST);
+        let forged = receipt(&provider, keys.public_key(), r#"{"kind":9734,"content":"forged","tags":[]}"#);
+        // 任意の鍵で署名されたレシートは、description hash が正しくても無効
+        let spoofed = receipt(&Keys::generate(), keys.public_key(), TEST_ZAP_REQUEST);
+        // プロバイダーが分からない受取人へのレシートは未検証
+        let unverified = receipt(&provider, Keys::generate().public_key(), TEST_ZAP_REQUEST);
+        for event in [&genuine, &forged, &spoofed, &unverified] {
+            relay.insert(event.clone()).await;
+        }
+
+        let receipts = client.get_zap_receipts(&note.id.to_hex(), 10).await.unwrap();
+        assert_eq!(receipts.len(), 4);
+        let find = |event: &Event| receipts.iter().find(|r| r.id == event.id.to_hex()).unwrap();
+        assert!(find(&genuine).valid);
+        assert_eq!(find(&genuine).status, "valid");
+        assert_eq!(find(&genuine).amount_sats, 1000);
+        assert_eq!(find(&genuine).target_note_id.as_deref(), Some(note.id.to_hex().as_str()));
+        assert_eq!(find(&forged).invalid_reason.as_deref(), Some("description_hash_mismatch"));
+        assert_eq!(find(&spoofed).invalid_reason.as_deref(), Some("unexpected_signer"));
+        assert!(!find(&unverified).valid);
+        assert_eq!(find(&unverified).status, "unverified");
+
+        client.disconnect().await;
+    }
+}
@@ -0,0 +1,632 @@
+//! OpenTimestamps モジュール (NIP-03)
+//!
+//! イベント ID を OpenTimestamps のカレンダーサーバーに送信してタイムスタンプ証明（.ots）を作成し、
+//! Bitcoin のブロックに取り込まれた後に証明をアップグレードします。
+//! 証明の作成から Bitcoin での確定までは数時間かかるため、作成途中の証明は
+//! データディレクトリの `ots/<イベント ID>.ots` に保存します。
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::debug;
+
+/// デフォルトの OpenTimestamps カレンダーサーバー
+pub const DEFAULT_CALENDARS: &[&str] = &[
+    "https://a.pool.opentimestamps.org",
+    "https://b.pool.opentimestamps.org",
+    "https://a.pool.eternitywall.com",
+];
+
+/// 作成途中の証明の保存先ディレクトリ名
+const OTS_DIR: &str = "ots";
+
+/// .ots ファイルのマジックヘッダー
+const HEADER_MAGIC: &[u8] = b"\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94";
+
+/// .ots ファイルのメジャーバージョン
+const MAJOR_VERSION: u64 = 1;
+
+/// カレンダーサーバーへのリクエストに付与する Accept ヘッダー
+const OTS_MEDIA_TYPE: &str = "application/vnd.opentimestamps.v1";
+
+/// カレンダーサーバーへのリクエストのタイムアウト（応答しないカレンダーで待ち続けない）
+const CALENDAR_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 証明ツリーの最大の深さ（不正なデータによる無限再帰を防ぐ）
+const MAX_DEPTH: usize = 256;
+
+/// 可変長バイト列の最大長
+const MAX_VARBYTES_LEN: u64 = 8192;
+
+const TAG_ATTESTATION: u8 = 0x00;
+const TAG_FORK: u8 = 0xff;
+
+const OP_SHA1: u8 = 0x02;
+const OP_RIPEMD160: u8 = 0x03;
+const OP_SHA256: u8 = 0x08;
+const OP_KECCAK256: u8 = 0x67;
+const OP_APPEND: u8 = 0xf0;
+const OP_PREPEND: u8 = 0xf1;
+const OP_REVERSE: u8 = 0xf2;
+const OP_HEXLIFY: u8 = 0xf3;
+
+const PENDING_TAG: [u8; 8] = [0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e];
+const BITCOIN_TAG: [u8; 8] = [0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01];
+
+/// 証明に含まれるアテステーション
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attestation {
+    /// カレンダーサーバーで Bitcoin への取り込み待ち
+    Pending(String),
+    /// Bitcoin のブロック高
+    Bitcoin(u64),
+    /// 未対応のアテステーション（そのまま保持する）
+    Unknown([u8; 8], Vec<u8>),
+}
+
+/// 証明の各ステップで適用する演算
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    Append(Vec<u8>),
+    Prepend(Vec<u8>),
+    Sha256,
+    /// コミットメントを計算しない単項演算（SHA-1・RIPEMD-160・Keccak-256 など）
+    Other(u8),
+}
+
+impl Op {
+    fn tag(&self) -> u8 {
+        match self {
+            Op::Append(_) => OP_APPEND,
+            Op::Prepend(_) => OP_PREPEND,
+            Op::Sha256 => OP_SHA256,
+            Op::Other(tag) => *tag,
+        }
+    }
+
+    /// メッセージに演算を適用する（未対応の演算は空のメッセージを返す）
+    fn apply(&self, msg: &[u8]) -> Vec<u8> {
+        match self {
+            Op::Append(arg) => [msg, arg.as_slice()].concat(),
+            Op::Prepend(arg) => [arg.as_slice(), msg].concat(),
+            Op::Sha256 => Sha256::digest(msg).to_vec(),
+            Op::Other(_) => Vec::new(),
+        }
+    }
+}
+
+/// タイムスタンプ証明のツリー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtsTimestamp {
+    /// このノードのコミットメント
+    pub msg: Vec<u8>,
+    /// このノードのアテステーション
+    pub attestations: Vec<Attestation>,
+    /// このノードから分岐する演算と、その結果のノード
+    pub ops: Vec<(Op, OtsTimestamp)>,
+}
+
+impl OtsTimestamp {
+    pub fn new(msg: Vec<u8>) -> Self {
+        Self {
+            msg,
+            attestations: Vec::new(),
+            ops: Vec::new(),
+        }
+    }
+
+    /// 別の証明（同じコミットメントから始まるもの）を統合する
+    pub fn merge(&mut self, other: OtsTimestamp) {
+        for attestation in other.attestations {
+            if !self.attestations.contains(&attestation) {
+                self.attestations.push(attestation);
+            }
+        }
+        for (op, child) in other.ops {
+            match self.ops.iter_mut().find(|(o, _)| *o == op) {
+                Some((_, existing)) => existing.merge(child),
+                None => self.ops.push((op, child)),
+            }
+        }
+    }
+
+    /// Bitcoin のアテステーションのブロック高（昇順、重複なし）
+    pub fn bitcoin_heights(&self) -> Vec<u64> {
+        let mut heights = Vec::new();
+        self.walk(&mut |node| {
+            for attestation in &node.attestations {
+                if let Attestation::Bitcoin(height) = attestation {
+                    heights.push(*height);
+                }
+            }
+        });
+        heights.sort_unstable();
+        heights.dedup();
+        heights
+    }
+
+    /// Bitcoin のアテステーションに至る枝だけを残した証明（Bitcoin のアテステーションがなければ None）
+    ///
+    /// 取り込み待ち・未対応のアテステーションと、Bitcoin のアテステーションに至らない枝を取り除きます。
+    pub fn completed(&self) -> Option<OtsTimestamp> {
+        let attestations: Vec<Attestation> = self.attestations.iter()
+            .filter(|a| matches!(a, Attestation::Bitcoin(_)))
+            .cloned()
+            .collect();
+        let ops: Vec<(Op, OtsTimestamp)> = self.ops.iter()
+            .filter_map(|(op, child)| child.completed().map(|child| (op.clone(), child)))
+            .collect();
+        if attestations.is_empty() && ops.is_empty() {
+            return None;
+        }
+        Some(Self { msg: self.msg.clone(), attestations, ops })
+    }
+
+    /// Bitcoin への取り込み待ちのカレンダー URI と、そのコミットメント
+    pub fn pending_attestations(&self) -> Vec<(String, Vec<u8>)> {
+        let mut pending = Vec::new();
+        self.walk(&mut |node| {
+            for attestation in &node.attestations {
+                if let Attestation::Pending(uri) = attestation {
+                    pending.push((uri.clone(), node.msg.clone()));
+                }
+            }
+        });
+        pending
+    }
+
+    fn walk(&self, f: &mut impl FnMut(&OtsTimestamp)) {
+        f(self);
+        for (_, child) in &self.ops {
+            child.walk(f);
+        }
+    }
+
+    /// 取り込み待ちのアテステーションを、カレンダーから取得した証明で置き換える
+    fn apply_upgrade(&mut self, uri: &str, commitment: &[u8], upgraded: &OtsTimestamp) -> bool {
+        if self.msg == commitment {
+            let pending = Attestation::Pending(uri.to_string());
+            if let Some(pos) = self.attestations.iter().position(|a| *a == pending) {
+                self.attestations.remove(pos);
+                self.merge(upgraded.clone());
+                return true;
+            }
+        }
+        self.ops
+            .iter_mut()
+            .any(|(_, child)| child.apply_upgrade(uri, commitment, upgraded))
+    }
+
+    fn serialize(&self, out: &mut Vec<u8>) {
+        let total = self.attestations.len() + self.ops.len();
+        let mut index = 0;
+        let mut next_item = |out: &mut Vec<u8>| {
+            index += 1;
+            if index < total {
+                out.push(TAG_FORK);
+            }
+        };
+
+        for attestation in &self.attestations {
+            next_item(out);
+            out.push(TAG_ATTESTATION);
+            let (tag, payload) = match attestation {
+                Attestation::Pending(uri) => {
+                    let mut payload = Vec::new();
+                    write_varbytes(&mut payload, uri.as_bytes());
+                    (PENDING_TAG, payload)
+                }
+                Attestation::Bitcoin(height) => {
+                    let mut payload = Vec::new();
+                    write_varuint(&mut payload, *height);
+                    (BITCOIN_TAG, payload)
+                }
+                Attestation::Unknown(tag, payload) => (*tag, payload.clone()),
+            };
+            out.extend_from_slice(&tag);
+            write_varbytes(out, &payload);
+        }
+
+        for (op, child) in &self.ops {
+            next_item(out);
+            out.push(op.tag());
+            if let Op::Append(arg) | Op::Prepend(arg) = op {
+                write_varbytes(out, arg);
+            }
+            child.serialize(out);
+        }
+    }
+
+    fn deserialize(reader: &mut Reader, msg: Vec<u8>, depth: usize) -> Result<Self> {
+        if depth > MAX_DEPTH {
+            return Err(anyhow!("証明ツリーが深すぎます"));
+        }
+        let mut timestamp = OtsTimestamp::new(msg);
+        loop {
+            let tag = reader.byte()?;
+            if tag == TAG_FORK {
+                let tag = reader.byte()?;
+                timestamp.read_item(reader, tag, depth)?;
+            } else {
+                timestamp.read_item(reader, tag, depth)?;
+                break;
+            }
+        }
+        Ok(timestamp)
+    }
+
+    fn read_item(&mut self, reader: &mut Reader, tag: u8, depth: usize) -> Result<()> {
+        if tag == TAG_ATTESTATION {
+            let attestation_tag: [u8; 8] = reader.bytes(8)?.try_into().expect("8 bytes");
+            let payload = reader.varbytes()?;
+            let mut payload_reader = Reader::new(&payload);
+            let attestation = match attestation_tag {
+                PENDING_TAG => {
+                    let uri = String::from_utf8(payload_reader.varbytes()?)
+                        .context("カレンダー URI が UTF-8 ではありません")?;
+                    Attestation::Pending(uri)
+                }
+                BITCOIN_TAG => Attestation::Bitcoin(payload_reader.varuint()?),
+                _ => Attestation::Unknown(attestation_tag, payload),
+            };
+            self.attestations.push(attestation);
+            return Ok(());
+        }
+
+        let op = match tag {
+            OP_APPEND => Op::Append(reader.varbytes()?),
+            OP_PREPEND => Op::Prepend(reader.varbytes()?),
+            OP_SHA256 => Op::Sha256,
+            OP_SHA1 | OP_RIPEMD160 | OP_KECCAK256 | OP_REVERSE | OP_HEXLIFY => Op::Other(tag),
+            _ => return Err(anyhow!("未対応の演算です: 0x{:02x}", tag)),
+        };
+        let child_msg = op.apply(&self.msg);
+        let child = OtsTimestamp::deserialize(reader, child_msg, depth + 1)?;
+        self.ops.push((op, child));
+        Ok(())
+    }
+}
+
+/// 証明を .ots ファイル形式（SHA-256 のダイジェストに対する証明）にシリアライズ
+pub fn serialize_file(timestamp: &OtsTimestamp) -> Vec<u8> {
+    let mut out = HEADER_MAGIC.to_vec();
+    write_varuint(&mut out, MAJOR_VERSION);
+    out.push(OP_SHA256);
+    out.extend_from_slice(&timestamp.msg);
+    timestamp.serialize(&mut out);
+    out
+}
+
+/// 証明を Kind 1040 の content 用に Base64 エンコード
+pub fn encode_base64(timestamp: &OtsTimestamp) -> String {
+    base64::engine::general_purpose::STANDARD.encode(serialize_file(timestamp))
+}
+
+/// .ots ファイルをパース
+pub fn deserialize_file(data: &[u8]) -> Result<OtsTimestamp> {
+    let mut reader = Reader::new(data);
+    if reader.bytes(HEADER_MAGIC.len()).ok() != Some(HEADER_MAGIC) {
+        return Err(anyhow!("OpenTimestamps の証明ファイルではありません"));
+    }
+    let version = reader.varuint()?;
+    if version != MAJOR_VERSION {
+        return Err(anyhow!("未対応の証明ファイルのバージョンです: {}", version));
+    }
+    if reader.byte()? != OP_SHA256 {
+        return Err(anyhow!("SHA-256 以外のダイジェストには対応していません"));
+    }
+    let digest = reader.bytes(32)?.to_vec();
+    let timestamp = OtsTimestamp::deserialize(&mut reader, digest, 0)?;
+    if !reader.is_empty() {
+        return Err(anyhow!("証明ファイルの末尾に不要なデータがあります"));
+    }
+    Ok(timestamp)
+}
+
+/// ダイジェストをカレンダーサーバーに送信して証明を作成
+///
+/// すべてのカレンダーの応答を 1 つの証明にまとめます。1 つも応答がなければエラーになります。
+pub async fn stamp(digest: &[u8; 32], calendars: &[&str]) -> Result<OtsTimestamp> {
+    let client = calendar_client()?;
+    let mut timestamp = OtsTimestamp::new(digest.to_vec());
+    let mut errors = Vec::new();
+
+    for calendar in calendars {
+        let url = format!("{}/digest", calendar.trim_end_matches('/'));
+        debug!("OpenTimestamps カレンダーに送信: {}", url);
+        let result = async {
+            let response = client
+                .post(&url)
+                .header("Accept", OTS_MEDIA_TYPE)
+                .body(digest.to_vec())
+                .send()
+                .await
+                .context("カレンダーサーバーへの接続に失敗")?;
+            if !response.status().is_success() {
+                return Err(anyhow!("カレンダーサーバーのエラー ({})", response.status()));
+            }
+            let body = response.bytes().await.context("応答の読み込みに失敗")?;
+            OtsTimestamp::deserialize(&mut Reader::new(&body), digest.to_vec(), 0)
+        }
+        .await;
+
+        match result {
+            Ok(calendar_timestamp) => timestamp.merge(calendar_timestamp),
+            Err(e) => errors.push(format!("{}: {}", calendar, e)),
+        }
+    }
+
+    if timestamp.attestations.is_empty() && timestamp.ops.is_empty() {
+        return Err(anyhow!(
+            "どのカレンダーサーバーからも証明を取得できませんでした: {}",
+            errors.join(", ")
+        ));
+    }
+    Ok(timestamp)
+}
+
+/// 取り込み待ちのアテステーションをカレンダーサーバーに問い合わせてアップグレード
+///
+/// Bitcoin のアテステーションを含む証明を取得できたものだけを置き換え、置き換えた数を返します。
+/// 接続・応答の読み込み・証明のパースに失敗したカレンダーはスキップします。
+pub async fn upgrade(timestamp: &mut OtsTimestamp) -> Result<usize> {
+    let client = calendar_client()?;
+    let mut upgraded_count = 0;
+
+    for (uri, commitment) in timestamp.pending_attestations() {
+        if commitment.is_empty() {
+            continue;
+        }
+        if !uri.starts_with("https://") {
+            debug!("HTTPS 以外のカレンダーはスキップします: {}", uri);
+            continue;
+        }
+        let url = format!("{}/timestamp/{}", uri.trim_end_matches('/'), hex(&commitment));
+        debug!("OpenTimestamps 証明のアップグレード: {}", url);
+
+        let result = async {
+            let response = client
+                .get(&url)
+                .header("Accept", OTS_MEDIA_TYPE)
+                .send()
+                .await
+                .context("カレンダーサーバーへの接続に失敗")?;
+            if !response.status().is_success() {
+                debug!("まだ確定していません ({}): {}", response.status(), uri);
+                return Ok(None);
+            }
+            let body = response.bytes().await.context("応答の読み込みに失敗")?;
+            OtsTimestamp::deserialize(&mut Reader::new(&body), commitment.clone(), 0).map(Some)
+        }
+        .await;
+
+        let upgraded = match result {
+            Ok(Some(upgraded)) => upgraded,
+            Ok(None) => continue,
+            Err(e) => {
+                debug!("カレンダーサーバーからのアップグレードに失敗: {}: {:#}", uri, e);
+                continue;
+            }
+        };
+        if upgraded.bitcoin_heights().is_empty() {
+            continue;
+        }
+        if timestamp.apply_upgrade(&uri, &commitment, &upgraded) {
+            upgraded_count += 1;
+        }
+    }
+
+    Ok(upgraded_count)
+}
+
+/// カレンダーサーバー用の HTTP クライアント（`CALENDAR_TIMEOUT` でタイムアウト）
+fn calendar_client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(CALENDAR_TIMEOUT)
+        .build()
+        .context("HTTP クライアントの作成に失敗")
+}
+
+/// 作成途中の証明の保存先（`ots/<イベント ID>.ots`）
+pub fn proof_path(event_id_hex: &str) -> Result<PathBuf> {
+    Ok(crate::storage::data_dir()?
+        .join(OTS_DIR)
+        .join(format!("{}.ots", event_id_hex)))
+}
+
+/// 保存済みの証明を読み込む（存在しない場合は None）
+pub fn load_proof(event_id_hex: &str) -> Result<Option<OtsTimestamp>> {
+    let path = proof_path(event_id_hex)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read(&path).context(format!("証明ファイルの読み込みに失敗: {}", path.display()))?;
+    deserialize_file(&data).map(Some)
+}
+
+/// 証明を保存する
+pub fn save_proof(event_id_hex: &str, timestamp: &OtsTimestamp) -> Result<()> {
+    let path = proof_path(event_id_hex)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("証明の保存先ディレクトリの作成に失敗しました")?;
+    }
+    fs::write(&path, serialize_file(timestamp))
+        .context(format!("証明ファイルの書き込みに失敗: {}", path.display()))
+}
+
+/// 保存済みの証明を削除する
+pub fn remove_proof(event_id_hex: &str) -> Result<()> {
+    let path = proof_path(event_id_hex)?;
+    if path.exists() {
+        fs::remove_file(&path).context(format!("証明ファイルの削除に失敗: {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn write_varuint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_varbytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_varuint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// .ots 形式のバイト列の読み取り
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| anyhow!("証明データが途中で終わっています"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn varuint(&mut self) -> Result<u64> {
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= 64 {
+                return Err(anyhow!("可変長整数が大きすぎます"));
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn varbytes(&mut self) -> Result<Vec<u8>> {
+        let len = self.varuint()?;
+        if len > MAX_VARBYTES_LEN {
+            return Err(anyhow!("可変長バイト列が長すぎます: {}", len));
+        }
+        Ok(self.bytes(len as usize)?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_roundtrip() {
+        let digest = [0x11u8; 32];
+        let mut timestamp = OtsTimestamp::new(digest.to_vec());
+        let mut calendar_a = OtsTimestamp::new(digest.to_vec());
+        let appended = Op::Append(vec![0xaa; 16]);
+        let mut child = OtsTimestamp::new(appended.apply(&digest));
+        let mut leaf = OtsTimestamp::new(Op::Sha256.apply(&child.msg));
+        leaf.attestations
+            .push(Attestation::Pending("https://a.pool.opentimestamps.org".to_string()));
+        child.ops.push((Op::Sha256, leaf));
+        calendar_a.ops.push((appended, child));
+        timestamp.merge(calendar_a);
+        timestamp.attestations.push(Attestation::Bitcoin(800_000));
+
+        let data = serialize_file(&timestamp);
+        assert!(data.starts_with(HEADER_MAGIC));
+        let parsed = deserialize_file(&data).unwrap();
+        assert_eq!(parsed, timestamp);
+        assert_eq!(parsed.bitcoin_heights(), vec![800_000]);
+
+        let pending = parsed.pending_attestations();
+        assert_eq!(pending.len(), 1);
+        let mut expected = digest.to_vec();
+        expected.extend_from_slice(&[0xaa; 16]);
+        assert_eq!(pending[0].1, Sha256::digest(&expected).to_vec());
+
+        assert!(deserialize_file(&data[..data.len() - 1]).is_err());
+        assert!(deserialize_file(b"not an ots file").is_err());
+    }
+
+    #[test]
+    fn test_apply_upgrade() {
+        let uri = "https://b.pool.opentimestamps.org";
+        let mut timestamp = OtsTimestamp::new(vec![0x22; 32]);
+        let prepended = Op::Prepend(vec![0x01, 0x02]);
+        let mut child = OtsTimestamp::new(prepended.apply(&timestamp.msg));
+        child.attestations.push(Attestation::Pending(uri.to_string()));
+        let commitment = child.msg.clone();
+        timestamp.ops.push((prepended, child));
+
+        let mut upgraded = OtsTimestamp::new(commitment.clone());
+        let mut block = OtsTimestamp::new(Op::Sha256.apply(&commitment));
+        block.attestations.push(Attestation::Bitcoin(850_123));
+        upgraded.ops.push((Op::Sha256, block));
+
+        assert!(timestamp.apply_upgrade(uri, &commitment, &upgraded));
+        assert!(timestamp.pending_attestations().is_empty());
+        assert_eq!(timestamp.bitcoin_heights(), vec![850_123]);
+        assert!(!timestamp.apply_upgrade(uri, &commitment, &upgraded));
+    }
+
+    #[test]
+    fn test_completed() {
+        let mut timestamp = OtsTimestamp::new(vec![0x33; 32]);
+        assert!(timestamp.completed().is_none());
+
+        let appended = Op::Append(vec![0xbb; 8]);
+        let mut pending = OtsTimestamp::new(appended.apply(&timestamp.msg));
+        pending.attestations.push(Attestation::Pending("https://a.pool.opentimestamps.org".to_string()));
+        timestamp.ops.push((appended, pending));
+        assert!(timestamp.completed().is_none());
+
+        let mut block = OtsTimestamp::new(Op::Sha256.apply(&timestamp.msg));
+        block.attestations.push(Attestation::Pending("https://b.pool.opentimestamps.org".to_string()));
+        block.attestations.push(Attestation::Bitcoin(860_000));
+        timestamp.ops.push((Op::Sha256, block));
+
+        let completed = timestamp.completed().unwrap();
+        assert!(completed.pending_attestations().is_empty());
+        assert_eq!(completed.bitcoin_heights(), vec![860_000]);
+        assert_eq!(completed.ops.len(), 1);
+        assert_eq!(completed.ops[0].0, Op::Sha256);
+    }
+
+    #[test]
+    fn test_varuint() {
+        for value in [0u64, 1, 127, 128, 300, 850_000, u64::MAX] {
+            let mut out = Vec::new();
+            write_varuint(&mut out, value);
+            assert_eq!(Reader::new(&out).varuint().unwrap(), value);
+        }
+    }
+}
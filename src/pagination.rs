@@ -0,0 +1,277 @@
+//! ページング取得モジュール
+//!
+//! リレーごとに読み取り位置を持ち、`until` を遡って上限件数までイベントを取得します。
+
+use anyhow::{anyhow, Result};
+use nostr_sdk::prelude::*;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::config::SignatureVerification;
+use crate::dedup::EventDedup;
+use crate::nostr_client::{drop_invalid_signatures, NostrClient};
+
+/// `paginate_events` がリレーごとに送る REQ の最大数
+const MAX_PAGINATION_REQUESTS: usize = 10;
+
+/// 多くのリレーが 1 回の REQ で返す件数の上限（`max_events` が大きいページング取得の REQ 数の目安）
+const PAGINATION_PAGE_SIZE: usize = 500;
+
+/// ページング取得でリレーに送る要求（リレー, フィルタ）
+pub(crate) type RelayPageRequest = (RelayUrl, Filter);
+
+/// ページング取得のリレーごとの応答
+pub(crate) type RelayPage = (RelayUrl, Result<Vec<Event>>);
+
+impl NostrClient {
+    /// 読み取りリレーごとにページングしてイベントを取得するヘルパー（新しい順に最大 `max_events` 件）
+    ///
+    /// 接続中の読み取りリレーそれぞれに `paginate_events` で `until` を遡って取得します。
+    pub(crate) async fn paginate_pool_events(&self, filter: Filter, max_events: usize, timeout: Duration) -> Result<Vec<Event>> {
+        self.ensure_connected_relays().await?;
+        let relays = self.connected_read_relay_urls().await;
+        let drop_invalid = self.signature_verification == SignatureVerification::Drop;
+        paginate_events(filter, relays, max_events, |requests| async move {
+            let urls: Vec<String> = requests.iter().map(|(url, _)| url.to_string()).collect();
+            self.rate_limiter.acquire(&urls).await;
+            fetch_relay_pages(&self.client, requests, timeout, drop_invalid).await
+        })
+        .await
+    }
+}
+
+/// リレーの 1 回の応答件数の上限を超える件数を、リレーごとに `until` を遡る複数回の取得でまとめて取得
+///
+/// リレーごとに読み取り位置（そのリレーから取得した最も古い作成日時）を持ち、初回はフィルタのまま、
+/// 2 回目以降はその位置を `until` にして残りの件数を要求し、ID で重複を除きます。リレーによって
+/// 遡れる範囲が違っても、他のリレーの古いイベントに合わせて取りこぼすことはありません。
+/// 新しいイベントを返さなくなったリレーと、読み取り位置が取得済みの上位 `max_events` 件より古くなった
+/// リレーは打ち切り、すべて打ち切るか `MAX_PAGINATION_REQUESTS` 回（`max_events` を `PAGINATION_PAGE_SIZE`
+/// 件ずつ取得するのに足りない場合はその回数）取得したら、新しい順に最大
+/// `max_events` 件を返します。同じ秒のイベントを取りこぼさないよう `until` は最も古い作成日時を含めます。
+/// 初回にすべてのリレーで失敗した場合はエラーを返し、それ以外で失敗したリレーは打ち切ります。
+pub(crate) async fn paginate_events<F, Fut>(filter: Filter, relays: Vec<RelayUrl>, max_events: usize, mut fetch: F) -> Result<Vec<Event>>
+where
+    F: FnMut(Vec<RelayPageRequest>) -> Fut,
+    Fut: std::future::Future<Output = Vec<RelayPage>>,
+{
+    if max_events == 0 {
+        return Ok(Vec::new());
+    }
+    let mut dedup = EventDedup::new();
+    // リレー → （読み取り位置, そのリレーから取得した件数）
+    let mut cursors: HashMap<RelayUrl, (Option<Timestamp>, usize)> = relays.into_iter()
+        .map(|relay| (relay, (filter.until, 0)))
+        .collect();
+    if cursors.is_empty() {
+        return Err(anyhow!("取得できるリレーがありません"));
+    }
+
+    let max_pages = MAX_PAGINATION_REQUESTS.max(max_events.div_ceil(PAGINATION_PAGE_SIZE));
+    for page in 0..max_pages {
+        let requests: Vec<RelayPageRequest> = cursors.iter()
+            .map(|(relay, (until, fetched))| {
+                // `until` と同じ秒の取得済みイベントも再び返されるため、その分を多めに要求する
+                let boundary = dedup.iter()
+                    .filter(|e| Some(e.created_at) == *until && dedup.seen_on(&e.id).contains(relay))
+                    .count();
+                let mut page_filter = filter.clone().limit(max_events.saturating_sub(*fetched) + boundary);
+                if let Some(until) = until {
+                    page_filter = page_filter.until(*until);
+                }
+                (relay.clone(), page_filter)
+            })
+            .collect();
+
+        let mut last_error = None;
+        let mut succeeded = false;
+        for (relay, result) in fetch(requests).await {
+            let events = match result {
+                Ok(events) => events,
+                Err(e) => {
+                    warn!("リレー {} のページング取得を中断（取得済み {} 件）: {}", relay, dedup.len(), e);
+                    cursors.remove(&relay);
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            succeeded = true;
+            let mut added = 0;
+            let mut oldest: Option<Timestamp> = None;
+            for event in events {
+                oldest = Some(oldest.map_or(event.created_at, |o| o.min(event.created_at)));
+                if !dedup.seen_on(&event.id).contains(&relay) {
+                    added += 1;
+                }
+                dedup.insert(event, Some(&relay));
+            }
+            if page > 0 {
+                debug!("ページング取得: {} から {} 件追加（累計 {} 件）", relay, added, dedup.len());
+            }
+            match cursors.get_mut(&relay) {
+                Some(cursor) if added > 0 => *cursor = (oldest, cursor.1 + added),
+                _ => {
+                    cursors.remove(&relay);
+                }
+            }
+        }
+        if page == 0 && !succeeded {
+            return Err(last_error.unwrap_or_else(|| anyhow!("イベントを取得できませんでした")));
+        }
+
+        // 取得済みの上位 `max_events` 件より古い位置まで遡ったリレーからは、結果に入るイベントは得られない
+        if dedup.len() >= max_events {
+            let mut created: Vec<Timestamp> = dedup.iter().map(|e| e.created_at).collect();
+            created.sort_unstable_by_key(|t| Reverse(*t));
+            let threshold = created[max_events - 1];
+            cursors.retain(|_, (until, _)| until.is_none_or(|until| until > threshold));
+        }
+        if cursors.is_empty() {
+            break;
+        }
+    }
+
+    let mut events = dedup.into_events();
+    events.sort_by_key(|e| Reverse(e.created_at));
+    events.truncate(max_events);
+    Ok(events)
+}
+
+/// リレーごとに異なるフィルタでイベントを並行して取得するヘルパー（`paginate_events` 用）
+///
+/// `drop_invalid` の場合は署名が不正なイベントを除外します。
+pub(crate) async fn fetch_relay_pages(client: &Client, requests: Vec<RelayPageRequest>, timeout: Duration, drop_invalid: bool) -> Vec<RelayPage> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for (url, filter) in requests {
+        let client = client.clone();
+        tasks.spawn(async move {
+            let result = client.fetch_events_from(vec![url.clone()], vec![filter], timeout).await;
+            (url, result)
+        });
+    }
+
+    let mut pages = Vec::new();
+    while let Some(Ok((url, result))) = tasks.join_next().await {
+        let result = result
+            .map(|events| {
+                let events: Vec<Event> = events.into_iter().collect();
+                if drop_invalid { drop_invalid_signatures(events) } else { events }
+            })
+            .map_err(anyhow::Error::from);
+        pages.push((url, result));
+    }
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1 回の応答を 100 件に制限するリレーを模して、保存済みのイベントを返す
+    fn serve_page(stored: &[Event], filter: &Filter) -> Vec<Event> {
+        let mut events: Vec<Event> = stored.iter()
+            .filter(|e| filter.until.is_none_or(|until| e.created_at <= until))
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| Reverse(e.created_at));
+        events.truncate(filter.limit.unwrap_or(usize::MAX).min(100));
+        events
+    }
+
+    fn notes_at(keys: &Keys, created_at: impl Iterator<Item = u64>) -> Vec<Event> {
+        created_at
+            .enumerate()
+            .map(|(i, at)| {
+                EventBuilder::text_note(format!("note {}", i))
+                    .custom_created_at(Timestamp::from(at))
+                    .sign_with_keys(keys)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_paginate_events() {
+        // 同じ秒のイベントを含む 350 件から取得する
+        let stored = notes_at(&Keys::generate(), (0..350u64).map(|i| 1_700_000_000 + i / 2));
+        let url = RelayUrl::parse("wss://a.example.com").unwrap();
+        let relay = |requests: Vec<RelayPageRequest>| {
+            let pages: Vec<RelayPage> = requests.into_iter()
+                .map(|(url, filter)| (url, Ok(serve_page(&stored, &filter))))
+                .collect();
+            async move { pages }
+        };
+
+        let events = paginate_events(Filter::new(), vec![url.clone()], 250, relay).await.unwrap();
+        assert_eq!(events.len(), 250);
+        let ids: std::collections::HashSet<EventId> = events.iter().map(|e| e.id).collect();
+        assert_eq!(ids.len(), 250);
+        assert!(events.windows(2).all(|w| w[0].created_at >= w[1].created_at));
+        assert_eq!(events.last().unwrap().created_at, Timestamp::from(1_700_000_000 + 50));
+
+        // 保存されている件数より多く要求した場合は、新しいイベントがなくなった時点で終わる
+        let events = paginate_events(Filter::new(), vec![url.clone()], 500, relay).await.unwrap();
+        assert_eq!(events.len(), 350);
+
+        // 2 回目以降の失敗では取得済みのイベントを返す
+        let mut calls = 0;
+        let events = paginate_events(Filter::new(), vec![url.clone()], 250, |requests| {
+            calls += 1;
+            let failed = calls > 1;
+            let page = relay(requests);
+            async move {
+                let pages = page.await;
+                if failed {
+                    pages.into_iter().map(|(url, _)| (url, Err(anyhow!("timeout")))).collect()
+                } else {
+                    pages
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(events.len(), 100);
+
+        // 初回にすべて失敗した場合はエラー
+        let failing = |requests: Vec<RelayPageRequest>| {
+            let pages: Vec<RelayPage> = requests.into_iter().map(|(url, _)| (url, Err(anyhow!("timeout")))).collect();
+            async move { pages }
+        };
+        assert!(paginate_events(Filter::new(), vec![url.clone()], 250, failing).await.is_err());
+
+        // 0 件の要求ではリレーに問い合わせない
+        let events = paginate_events(Filter::new(), vec![url], 0, failing).await.unwrap();
+        assert!(events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_paginate_events_two_relays() {
+        // A は直近に密集した 150 件、B は 10 秒おきに遡る 150 件を持つ。1 回目の応答の最も古い作成日時は
+        // A が 1901、B が 1010 で、全体の最も古い日時から遡ると A の 1900〜1851 を取りこぼす
+        let keys = Keys::generate();
+        let stored_a = notes_at(&keys, (0..150u64).map(|i| 2000 - i));
+        let stored_b = notes_at(&keys, (0..150u64).map(|i| 2000 - 10 * i - 5));
+        let a = RelayUrl::parse("wss://a.example.com").unwrap();
+        let b = RelayUrl::parse("wss://b.example.com").unwrap();
+        let fetch = |requests: Vec<RelayPageRequest>| {
+            let pages: Vec<RelayPage> = requests.into_iter()
+                .map(|(url, filter)| {
+                    let stored = if url == a { &stored_a } else { &stored_b };
+                    (url, Ok(serve_page(stored, &filter)))
+                })
+                .collect();
+            async move { pages }
+        };
+
+        let events = paginate_events(Filter::new(), vec![a.clone(), b.clone()], 250, fetch).await.unwrap();
+        assert_eq!(events.len(), 250);
+        let ids: std::collections::HashSet<EventId> = events.iter().map(|e| e.id).collect();
+        assert!(stored_a.iter().all(|e| ids.contains(&e.id)));
+        // 結果は両方のリレーを合わせた新しい順の上位 250 件
+        let mut all: Vec<&Event> = stored_a.iter().chain(&stored_b).collect();
+        all.sort_by_key(|e| Reverse(e.created_at));
+        assert_eq!(events.last().unwrap().created_at, all[249].created_at);
+    }
+}
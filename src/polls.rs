@@ -0,0 +1,371 @@
+//! 投票モジュール (NIP-88)
+//!
+//! 投票 (Kind 1068) の作成と回答 (Kind 1018) の送信・集計を扱います。
+
+use anyhow::{anyhow, Context, Result};
+use nostr_sdk::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::info;
+
+use crate::nostr_client::{current_unix_timestamp, encode_nevent, extract_tag_value, relay_hints, AuthorInfo, NostrClient, PublishResult};
+
+/// 投票の選択肢（NIP-88）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PollOption {
+    /// 選択肢 ID
+    pub id: String,
+    /// 選択肢のラベル
+    pub label: String,
+}
+
+/// 投票情報（Kind 1068, NIP-88）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PollInfo {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// nevent 形式のイベント ID
+    pub nevent: String,
+    /// 質問文
+    pub question: String,
+    /// 選択肢
+    pub options: Vec<PollOption>,
+    /// 投票形式（singlechoice / multiplechoice）
+    pub poll_type: String,
+    /// 締切の Unix タイムスタンプ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<u64>,
+    /// 作成者の情報
+    pub author: AuthorInfo,
+}
+
+/// 選択肢ごとの集計結果
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PollOptionResult {
+    /// 選択肢 ID
+    pub id: String,
+    /// 選択肢のラベル
+    pub label: String,
+    /// 得票数
+    pub votes: u64,
+    /// 得票率（%、小数第 1 位まで）
+    pub percentage: f64,
+}
+
+/// 投票の集計結果
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PollResults {
+    /// 投票情報
+    pub poll: PollInfo,
+    /// 有効な投票者数
+    pub total_votes: u64,
+    /// 選択肢ごとの結果
+    pub results: Vec<PollOptionResult>,
+    /// 締切済みかどうか
+    pub ended: bool,
+    /// 自分が選択している選択肢 ID（未回答・未認証の場合は空）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub my_votes: Vec<String>,
+}
+
+impl NostrClient {
+    /// 投票 (Kind 1068, NIP-88) を作成します。
+    pub async fn create_poll(
+        &self,
+        question: &str,
+        options: &[String],
+        multiple_choice: bool,
+        expires_in: Option<u64>,
+    ) -> Result<(PollInfo, PublishResult)> {
+        self.require_write_access()?;
+
+        if options.len() < 2 {
+            return Err(anyhow!("選択肢は 2 つ以上指定してください"));
+        }
+
+        let poll_options: Vec<PollOption> = options.iter()
+            .enumerate()
+            .map(|(i, label)| PollOption { id: i.to_string(), label: label.clone() })
+            .collect();
+        let poll_type = if multiple_choice { "multiplechoice" } else { "singlechoice" };
+        let ends_at = expires_in.map(|secs| current_unix_timestamp().saturating_add(secs));
+
+        let mut tags: Vec<Tag> = poll_options.iter()
+            .map(|o| Tag::custom(TagKind::custom("option".to_string()), vec![o.id.clone(), o.label.clone()]))
+            .collect();
+        tags.push(Tag::custom(TagKind::custom("polltype".to_string()), vec![poll_type.to_string()]));
+        if let Some(ts) = ends_at {
+            tags.push(Tag::custom(TagKind::custom("endsAt".to_string()), vec![ts.to_string()]));
+        }
+        // 回答は自分が読むリレーに送ってもらう
+        for relay in self.read_relay_urls().await {
+            tags.push(Tag::custom(TagKind::Relay, vec![relay.to_string()]));
+        }
+
+        let builder = EventBuilder::new(Kind::from(1068), question).tags(tags);
+
+        let output = self.send_event_builder(builder).await
+            .context("投票の作成に失敗しました")?;
+
+        let event_id = *output.id();
+        info!("投票を作成しました: {}", event_id);
+        let published = PublishResult::new(output, self.public_key);
+
+        let pk = self.public_key.ok_or_else(|| anyhow!("公開鍵が取得できません"))?;
+        let poll = PollInfo {
+            id: event_id.to_hex(),
+            nevent: published.nevent.clone(),
+            question: question.to_string(),
+            options: poll_options,
+            poll_type: poll_type.to_string(),
+            ends_at,
+            author: AuthorInfo::from_public_key(&pk),
+        };
+        Ok((poll, published))
+    }
+
+    /// 投票に回答 (Kind 1018, NIP-88) します。
+    ///
+    /// `choices` には選択肢の ID またはラベルを指定できます。
+    pub async fn vote_poll(&self, poll_id: &str, choices: &[String]) -> Result<(PublishResult, Vec<PollOption>)> {
+        self.require_write_access()?;
+
+        let poll_event = self.resolve_event(poll_id, "投票").await?;
+        if poll_event.kind != Kind::from(1068) {
+            return Err(anyhow!("指定されたイベントは投票 (Kind 1068) ではありません"));
+        }
+
+        let poll = Self::event_to_poll(&poll_event, &HashMap::new(), &[]);
+        if poll.ends_at.is_some_and(|ends_at| ends_at < current_unix_timestamp()) {
+            return Err(anyhow!("この投票は締め切られています"));
+        }
+
+        let mut selected: Vec<PollOption> = Vec::new();
+        for choice in choices {
+            let option = poll.options.iter()
+                .find(|o| o.id == *choice)
+                .or_else(|| poll.options.iter().find(|o| o.label.eq_ignore_ascii_case(choice)))
+                .ok_or_else(|| anyhow!("無効な選択肢です: {}", choice))?;
+            if !selected.iter().any(|o| o.id == option.id) {
+                selected.push(option.clone());
+            }
+        }
+
+        if selected.is_empty() {
+            return Err(anyhow!("選択肢を 1 つ以上指定してください"));
+        }
+        if poll.poll_type != "multiplechoice" && selected.len() > 1 {
+            return Err(anyhow!("この投票は単一選択です。選択肢を 1 つだけ指定してください"));
+        }
+
+        let mut tags = vec![Tag::event(poll_event.id)];
+        tags.extend(selected.iter().map(|o| {
+            Tag::custom(TagKind::custom("response".to_string()), vec![o.id.clone()])
+        }));
+
+        let builder = EventBuilder::new(Kind::from(1018), "").tags(tags);
+
+        let output = self.send_event_builder(builder).await
+            .context("投票の送信に失敗しました")?;
+
+        let response_id = *output.id();
+        info!("投票に回答しました: {}", response_id);
+        Ok((PublishResult::new(output, self.public_key), selected))
+    }
+
+    /// 投票結果を集計します（NIP-88）。
+    ///
+    /// 同じ公開鍵からの回答は最新の 1 件のみを有効とし、締切後の回答は除外します。
+    pub async fn get_poll_results(&self, poll_id: &str) -> Result<PollResults> {
+        let poll_event = self.resolve_event(poll_id, "投票").await?;
+        if poll_event.kind != Kind::from(1068) {
+            return Err(anyhow!("指定されたイベントは投票 (Kind 1068) ではありません"));
+        }
+
+        let filter = Filter::new()
+            .kind(Kind::from(1018))
+            .event(poll_event.id)
+            .limit(5000);
+
+        let responses = self.fetch_events(vec![filter], Duration::from_secs(15))
+            .await
+            .context("投票の回答の取得に失敗しました")?;
+        let responses: Vec<Event> = responses.into_iter().collect();
+
+        let profiles = self.fetch_profiles(&[poll_event.pubkey]).await;
+        let seen_on = self.seen_on_relays(std::slice::from_ref(&poll_event)).await;
+        let poll = Self::event_to_poll(&poll_event, &profiles, relay_hints(&seen_on, &poll_event.id));
+
+        let (counts, total_votes) = tally_poll_responses(&poll, &responses);
+        let results = poll.options.iter()
+            .map(|o| {
+                let votes = counts.get(&o.id).copied().unwrap_or(0);
+                PollOptionResult {
+                    id: o.id.clone(),
+                    label: o.label.clone(),
+                    votes,
+                    percentage: if total_votes > 0 {
+                        (votes as f64 / total_votes as f64 * 1000.0).round() / 10.0
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect();
+
+        let ended = poll.ends_at.is_some_and(|ends_at| ends_at < current_unix_timestamp());
+
+        // 自分の最新の回答（締切後の回答は除外）
+        let my_votes = self.public_key
+            .and_then(|pk| {
+                responses.iter()
+                    .filter(|r| r.pubkey == pk)
+                    .filter(|r| poll.ends_at.is_none_or(|ends_at| r.created_at.as_u64() <= ends_at))
+                    .max_by_key(|r| r.created_at)
+            })
+            .map(|r| poll_response_choices(&poll, r))
+            .unwrap_or_default();
+
+        Ok(PollResults {
+            poll,
+            total_votes,
+            results,
+            ended,
+            my_votes,
+        })
+    }
+
+    /// 投票イベントから表示用情報に変換するヘルパー
+    fn event_to_poll(event: &Event, profiles: &HashMap<PublicKey, AuthorInfo>, relays: &[String]) -> PollInfo {
+        // ["option", <id>, <label>]
+        let options = event.tags.iter()
+            .filter(|tag| tag.kind() == TagKind::custom("option".to_string()))
+            .filter_map(|tag| {
+                let values = tag.as_slice();
+                Some(PollOption {
+                    id: values.get(1)?.clone(),
+                    label: values.get(2).cloned().unwrap_or_default(),
+                })
+            })
+            .collect();
+
+        PollInfo {
+            id: event.id.to_hex(),
+            nevent: encode_nevent(&event.id, Some(event.pubkey), relays),
+            question: event.content.clone(),
+            options,
+            poll_type: extract_tag_value(event, "polltype").unwrap_or_else(|| "singlechoice".to_string()),
+            ends_at: extract_tag_value(event, "endsAt").and_then(|s| s.parse().ok()),
+            author: profiles
+                .get(&event.pubkey)
+                .cloned()
+                .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey)),
+        }
+    }
+}
+
+/// 投票の回答を集計（NIP-88）
+///
+/// 公開鍵ごとに最新の回答 1 件のみを有効とし、締切後の回答と存在しない選択肢は除外します。
+/// 単一選択の投票では最初の response タグのみを数えます。
+/// 戻り値は（選択肢 ID ごとの得票数, 有効な投票者数）です。
+fn tally_poll_responses(poll: &PollInfo, responses: &[Event]) -> (HashMap<String, u64>, u64) {
+    let mut latest: HashMap<PublicKey, &Event> = HashMap::new();
+    for response in responses {
+        if poll.ends_at.is_some_and(|ends_at| response.created_at.as_u64() > ends_at) {
+            continue;
+        }
+        match latest.get(&response.pubkey) {
+            Some(existing) if existing.created_at >= response.created_at => {}
+            _ => {
+                latest.insert(response.pubkey, response);
+            }
+        }
+    }
+
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    let mut voters = 0;
+    for response in latest.values() {
+        let choices = poll_response_choices(poll, response);
+        if choices.is_empty() {
+            continue;
+        }
+        voters += 1;
+        for id in choices {
+            *counts.entry(id).or_insert(0) += 1;
+        }
+    }
+
+    (counts, voters)
+}
+
+/// 回答イベントから有効な選択肢 ID を取り出す（単一選択の投票では先頭の 1 つのみ）
+fn poll_response_choices(poll: &PollInfo, response: &Event) -> Vec<String> {
+    // 同じ選択肢が離れて繰り返されても 1 票として数える（順序は回答のまま）
+    let mut seen = std::collections::HashSet::new();
+    let mut choices: Vec<String> = response.tags.iter()
+        .filter(|tag| tag.kind() == TagKind::custom("response".to_string()))
+        .filter_map(|tag| tag.content().map(String::from))
+        .filter(|id| poll.options.iter().any(|o| o.id == *id))
+        .filter(|id| seen.insert(id.clone()))
+        .collect();
+    if poll.poll_type != "multiplechoice" {
+        choices.truncate(1);
+    }
+    choices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tally_poll_responses_dedup() {
+        let poll = PollInfo {
+            id: String::new(),
+            nevent: String::new(),
+            question: "好きな色は？".to_string(),
+            options: vec![
+                PollOption { id: "0".to_string(), label: "赤".to_string() },
+                PollOption { id: "1".to_string(), label: "青".to_string() },
+            ],
+            poll_type: "singlechoice".to_string(),
+            ends_at: Some(1_000),
+            author: AuthorInfo::from_public_key(&Keys::generate().public_key()),
+        };
+        let response = |keys: &Keys, choices: &[&str], created_at: u64| {
+            EventBuilder::new(Kind::from(1018), "")
+                .tags(choices.iter().map(|c| {
+                    Tag::custom(TagKind::custom("response".to_string()), vec![c.to_string()])
+                }))
+                .custom_created_at(Timestamp::from(created_at))
+                .sign_with_keys(keys)
+                .unwrap()
+        };
+
+        let alice = Keys::generate();
+        let bob = Keys::generate();
+        let carol = Keys::generate();
+        let responses = vec![
+            // alice は投票し直したので最新の回答のみ有効
+            response(&alice, &["0"], 100),
+            response(&alice, &["1"], 200),
+            // 単一選択なので最初の選択肢のみ
+            response(&bob, &["1", "0"], 100),
+            // 締切後の回答は無効
+            response(&carol, &["0"], 2_000),
+        ];
+
+        let (counts, voters) = tally_poll_responses(&poll, &responses);
+        assert_eq!(voters, 2);
+        assert_eq!(counts.get("1"), Some(&2));
+        assert_eq!(counts.get("0"), None);
+
+        // 複数選択では、離れて繰り返された選択肢も 1 票として数える
+        let multiple = PollInfo { poll_type: "multiplechoice".to_string(), ..poll };
+        let (counts, voters) = tally_poll_responses(&multiple, &[response(&alice, &["0", "1", "0"], 100)]);
+        assert_eq!(voters, 1);
+        assert_eq!(counts.get("0"), Some(&1));
+        assert_eq!(counts.get("1"), Some(&1));
+    }
+}
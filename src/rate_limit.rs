@@ -0,0 +1,143 @@
+//! リレーへのリクエストのレート制限モジュール
+//!
+//! リレーごとのトークンバケットで REQ / COUNT / EVENT の送信頻度を制限し、
+//! エージェントが短時間に大量のツール呼び出しを行ってもリレーから接続を拒否されないようにします。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::config::{RateLimit, RateLimitConfig};
+
+/// トークンバケット
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// リレーごとのトークンバケットによるレート制限
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// 設定からレート制限を作成
+    pub fn new(mut config: RateLimitConfig) -> Self {
+        config.relays = config
+            .relays
+            .into_iter()
+            .map(|(url, limit)| (normalize_url(&url), limit))
+            .collect();
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// リレーの制限を取得
+    fn limit_for(&self, url: &str) -> RateLimit {
+        self.config
+            .relays
+            .get(url)
+            .copied()
+            .unwrap_or(self.config.default)
+    }
+
+    /// 指定したすべてのリレーでリクエストを 1 回送信できるまで待機し、トークンを消費します。
+    pub async fn acquire<S: AsRef<str>>(&self, relays: &[S]) {
+        let urls: Vec<String> = relays.iter().map(|u| normalize_url(u.as_ref())).collect();
+        loop {
+            match self.try_acquire(&urls, Instant::now()) {
+                None => return,
+                Some(wait) => {
+                    debug!("レート制限により {:?} 待機します", wait);
+                    tokio::time::sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    /// トークンを消費できればすべてのリレーで消費して None、できなければ待機時間を返す
+    fn try_acquire(&self, urls: &[String], now: Instant) -> Option<Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut wait = Duration::ZERO;
+        for url in urls {
+            let limit = self.limit_for(url);
+            if limit.requests_per_minute == 0 {
+                continue;
+            }
+            let capacity = limit.burst.max(1) as f64;
+            let per_sec = limit.requests_per_minute as f64 / 60.0;
+
+            let bucket = buckets.entry(url.clone()).or_insert(TokenBucket {
+                tokens: capacity,
+                updated_at: now,
+            });
+            let elapsed = now.saturating_duration_since(bucket.updated_at).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+            bucket.updated_at = now;
+
+            if bucket.tokens < 1.0 {
+                wait = wait.max(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec));
+            }
+        }
+
+        if !wait.is_zero() {
+            return Some(wait);
+        }
+
+        for url in urls {
+            if let Some(bucket) = buckets.get_mut(url) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        None
+    }
+}
+
+/// リレー URL を比較用に正規化（末尾のスラッシュを除去）
+fn normalize_url(url: &str) -> String {
+    url.trim().trim_end_matches('/').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let mut config = RateLimitConfig {
+            default: RateLimit { requests_per_minute: 60, burst: 2 },
+            ..Default::default()
+        };
+        config.relays.insert(
+            "wss://unlimited.example/".to_string(),
+            RateLimit { requests_per_minute: 0, burst: 1 },
+        );
+        let limiter = RateLimiter::new(config);
+        let relays = vec!["wss://relay.example".to_string()];
+        let now = Instant::now();
+
+        // バースト分は即座に送信できる
+        assert!(limiter.try_acquire(&relays, now).is_none());
+        assert!(limiter.try_acquire(&relays, now).is_none());
+
+        // 3 回目は 1 秒待機が必要
+        let wait = limiter.try_acquire(&relays, now).unwrap();
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+
+        // 1 秒後には補充されている
+        assert!(limiter.try_acquire(&relays, now + Duration::from_secs(1)).is_none());
+
+        // 無制限のリレーは待機しない
+        let unlimited = vec!["wss://unlimited.example".to_string()];
+        for _ in 0..10 {
+            assert!(limiter.try_acquire(&unlimited, now).is_none());
+        }
+    }
+}
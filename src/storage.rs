@@ -0,0 +1,623 @@
+//! ローカル状態の永続化モジュール
+//!
+//! 既読カーソルやローカル下書きなど、サーバー再起動後も保持したい状態を
+//! データディレクトリ（~/.local/share/rust-nostr-mcp 等）に JSON で保存します。
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// 既読状態ファイル名
+const READ_STATE_FILE: &str = "read_state.json";
+
+/// 既知のフォロワー一覧ファイル名
+const KNOWN_FOLLOWERS_FILE: &str = "known_followers.json";
+
+/// ローカル下書きファイル名
+const LOCAL_DRAFTS_FILE: &str = "local_drafts.json";
+
+/// ウォッチリストファイル名
+const WATCH_LIST_FILE: &str = "watch_list.json";
+
+/// 送信待ちキューのファイル名
+const PENDING_PUBLISHES_FILE: &str = "pending_publishes.json";
+
+/// 支払い履歴のファイル名
+const PAYMENT_LOG_FILE: &str = "payment_log.json";
+
+/// リアクションのプリセットの送信履歴のファイル名
+const REACTION_LOG_FILE: &str = "reaction_log.json";
+
+/// フォロワー数の推移のファイル名
+const FOLLOWER_HISTORY_FILE: &str = "follower_history.json";
+
+/// 公開した記事のバージョン履歴のファイル名
+const ARTICLE_HISTORY_FILE: &str = "article_history.json";
+
+/// 記事ごとに保持するバージョンの最大数（古いものから削除）
+const MAX_ARTICLE_VERSIONS: usize = 50;
+
+/// アドレス帳のファイル名
+const ADDRESS_BOOK_FILE: &str = "address_book.json";
+
+/// アドレス帳の別名の最大文字数
+const MAX_ALIAS_CHARS: usize = 64;
+
+/// 保持するフォロワー数のスナップショットの最大数（古いものから削除）
+const MAX_FOLLOWER_SNAPSHOTS: usize = 2000;
+
+/// 保持するフォロワーの増減の記録の最大数（古いものから削除）
+const MAX_FOLLOWER_CHANGES: usize = 2000;
+
+/// データディレクトリのパスを取得
+pub fn data_dir() -> Result<PathBuf> {
+    Ok(dirs::data_dir()
+        .context("データディレクトリを特定できません")?
+        .join("rust-nostr-mcp"))
+}
+
+/// データディレクトリ内の JSON ファイルを読み込む（存在しない・壊れている場合はデフォルト値）
+pub fn load_json<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    match data_dir() {
+        Ok(dir) => load_json_from(&dir.join(file_name)),
+        Err(e) => {
+            warn!("{}", e);
+            T::default()
+        }
+    }
+}
+
+/// データディレクトリ内の JSON ファイルに保存する
+pub fn save_json<T: Serialize>(file_name: &str, value: &T) -> Result<()> {
+    save_json_to(&data_dir()?.join(file_name), value)
+}
+
+fn load_json_from<T: DeserializeOwned + Default>(path: &Path) -> T {
+    let Ok(content) = fs::read_to_string(path) else {
+        return T::default();
+    };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        warn!("ローカル状態ファイルのパースに失敗しました ({:?}): {}", path, e);
+        T::default()
+    })
+}
+
+fn save_json_to<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("データディレクトリの作成に失敗しました")?;
+    }
+    let content = serde_json::to_string_pretty(value)
+        .context("ローカル状態のシリアライズに失敗しました")?;
+
+    // 書き込み途中で中断されても壊れないよう、一時ファイル経由で置き換える
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, content).context("ローカル状態ファイルの書き込みに失敗しました")?;
+    fs::rename(&tmp_path, path).context("ローカル状態ファイルの置き換えに失敗しました")?;
+    Ok(())
+}
+
+/// アカウントごとの既読状態
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReadState {
+    /// DM の会話相手（hex 公開鍵）ごとの既読日時
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub dm_read_at: HashMap<String, u64>,
+    /// すべての DM を既読にした日時
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dm_all_read_at: Option<u64>,
+    /// 通知を最後に既読にした日時
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications_read_at: Option<u64>,
+}
+
+impl ReadState {
+    /// 会話相手の既読カーソル（この日時以前のメッセージは既読）
+    pub fn dm_cursor(&self, peer_hex: &str) -> u64 {
+        let peer = self.dm_read_at.get(peer_hex).copied().unwrap_or(0);
+        peer.max(self.dm_all_read_at.unwrap_or(0))
+    }
+
+    /// 会話相手の DM を既読にする（None の場合はすべて）
+    pub fn mark_dms_read(&mut self, peer_hex: Option<&str>, at: u64) {
+        match peer_hex {
+            Some(peer) => {
+                self.dm_read_at.insert(peer.to_string(), at);
+            }
+            None => {
+                self.dm_all_read_at = Some(at);
+                self.dm_read_at.clear();
+            }
+        }
+    }
+}
+
+/// アカウント（hex 公開鍵）ごとに保存された状態を読み込む
+fn load_account_state<T: DeserializeOwned + Default>(file_name: &str, account_hex: &str) -> T {
+    let mut states: HashMap<String, T> = load_json(file_name);
+    states.remove(account_hex).unwrap_or_default()
+}
+
+/// アカウント（hex 公開鍵）ごとの状態を保存する
+fn save_account_state<T: Serialize + DeserializeOwned + Clone>(
+    file_name: &str,
+    account_hex: &str,
+    state: &T,
+) -> Result<()> {
+    let mut states: HashMap<String, T> = load_json(file_name);
+    states.insert(account_hex.to_string(), state.clone());
+    save_json(file_name, &states)
+}
+
+/// アカウント（hex 公開鍵）の既読状態を読み込む
+pub fn load_read_state(account_hex: &str) -> ReadState {
+    load_account_state(READ_STATE_FILE, account_hex)
+}
+
+/// アカウント（hex 公開鍵）の既読状態を保存する
+pub fn save_read_state(account_hex: &str, state: &ReadState) -> Result<()> {
+    save_account_state(READ_STATE_FILE, account_hex, state)
+}
+
+/// アカウントの既知のフォロワー（hex 公開鍵 → 初めて確認した日時）を読み込む
+pub fn load_known_followers(account_hex: &str) -> HashMap<String, u64> {
+    load_account_state(KNOWN_FOLLOWERS_FILE, account_hex)
+}
+
+/// アカウントの既知のフォロワーを保存する
+pub fn save_known_followers(account_hex: &str, followers: &HashMap<String, u64>) -> Result<()> {
+    save_account_state(KNOWN_FOLLOWERS_FILE, account_hex, followers)
+}
+
+/// ある時点のフォロワー数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowerSnapshot {
+    /// 記録した日時
+    pub taken_at: u64,
+    /// フォロワー数
+    pub followers: u64,
+}
+
+/// フォロワーの増減（"gained" または "lost"）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowerChange {
+    /// フォロワーの hex 公開鍵
+    pub pubkey: String,
+    /// "gained"（フォローされた）または "lost"（フォローを外された）
+    pub change: String,
+    /// 増加はフォローを初めて確認した日時、減少はフォローを外したコンタクトリストの作成日時
+    pub at: u64,
+}
+
+/// アカウントのフォロワー数の推移と増減の記録
+///
+/// 現在のフォロワーは新規フォロワーの通知と共通の既知のフォロワー一覧（`load_known_followers`）で管理します。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FollowerHistory {
+    /// フォロワー数のスナップショット（古い順）
+    #[serde(default)]
+    pub snapshots: Vec<FollowerSnapshot>,
+    /// フォロワーの増減（古い順）
+    #[serde(default)]
+    pub changes: Vec<FollowerChange>,
+}
+
+impl FollowerHistory {
+    /// フォロワー数のスナップショットと、前回からの増減を追加する
+    ///
+    /// `gained` は前回のスナップショット以降に初めて確認したフォロワー（hex 公開鍵, 確認した日時）、
+    /// `lost` はコンタクトリストから外れたことを確認できた元フォロワー（hex 公開鍵, コンタクトリストの作成日時）です。
+    pub fn record(&mut self, now: u64, followers: u64, gained: &[(String, u64)], lost: &[(String, u64)]) {
+        for (pubkey, at) in gained {
+            self.changes.push(FollowerChange { pubkey: pubkey.clone(), change: "gained".to_string(), at: *at });
+        }
+        for (pubkey, at) in lost {
+            self.changes.push(FollowerChange { pubkey: pubkey.clone(), change: "lost".to_string(), at: *at });
+        }
+        self.changes.sort_by_key(|c| c.at);
+        if self.changes.len() > MAX_FOLLOWER_CHANGES {
+            self.changes.drain(..self.changes.len() - MAX_FOLLOWER_CHANGES);
+        }
+
+        self.snapshots.push(FollowerSnapshot { taken_at: now, followers });
+        if self.snapshots.len() > MAX_FOLLOWER_SNAPSHOTS {
+            self.snapshots.drain(..self.snapshots.len() - MAX_FOLLOWER_SNAPSHOTS);
+        }
+    }
+
+    /// 最後にスナップショットを記録した日時
+    pub fn last_snapshot_at(&self) -> Option<u64> {
+        self.snapshots.last().map(|s| s.taken_at)
+    }
+}
+
+/// アカウントのフォロワー数の推移を読み込む
+pub fn load_follower_history(account_hex: &str) -> FollowerHistory {
+    load_account_state(FOLLOWER_HISTORY_FILE, account_hex)
+}
+
+/// アカウントのフォロワー数の推移を保存する
+pub fn save_follower_history(account_hex: &str, history: &FollowerHistory) -> Result<()> {
+    save_account_state(FOLLOWER_HISTORY_FILE, account_hex, history)
+}
+
+/// ローカルに保存した記事の下書き（リレーには送信しない）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalDraft {
+    /// 識別子（公開時の d タグ）
+    pub identifier: String,
+    /// 記事タイトル
+    pub title: String,
+    /// Markdown コンテンツ
+    pub content: String,
+    /// 要約
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// ヘッダー画像 URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// トピックハッシュタグ
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// 最初に保存した日時
+    pub created_at: u64,
+    /// 最後に保存した日時
+    pub updated_at: u64,
+}
+
+/// 下書きを追加・更新する（既存の下書きは最初の保存日時を引き継ぐ）
+pub fn upsert_local_draft(drafts: &mut BTreeMap<String, LocalDraft>, mut draft: LocalDraft) -> LocalDraft {
+    if let Some(existing) = drafts.get(&draft.identifier) {
+        draft.created_at = existing.created_at;
+    }
+    drafts.insert(draft.identifier.clone(), draft.clone());
+    draft
+}
+
+/// アカウントのローカル下書き（識別子 → 下書き）を読み込む
+pub fn load_local_drafts(account_hex: &str) -> BTreeMap<String, LocalDraft> {
+    load_account_state(LOCAL_DRAFTS_FILE, account_hex)
+}
+
+/// アカウントのローカル下書きを保存する
+pub fn save_local_drafts(account_hex: &str, drafts: &BTreeMap<String, LocalDraft>) -> Result<()> {
+    save_account_state(LOCAL_DRAFTS_FILE, account_hex, drafts)
+}
+
+/// ウォッチリストに登録したアカウント（自分のコンタクトリストとは独立）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedAccount {
+    /// 用途のメモ（例: "競合", "ニュース"）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// 登録した日時
+    pub added_at: u64,
+}
+
+/// ウォッチリスト（hex 公開鍵 → 登録情報）を読み込む。
+/// 鍵を設定していなくても使えるよう、アカウントごとではなく共通で保存する
+pub fn load_watch_list() -> BTreeMap<String, WatchedAccount> {
+    load_json(WATCH_LIST_FILE)
+}
+
+/// ウォッチリストを保存する
+pub fn save_watch_list(list: &BTreeMap<String, WatchedAccount>) -> Result<()> {
+    save_json(WATCH_LIST_FILE, list)
+}
+
+/// 公開した記事（Kind 30023）の 1 つのバージョン
+///
+/// 記事は置き換え可能イベントのため、リレーには最新版しか残らない。編集前の内容を確認できるよう
+/// 公開のたびにローカルに保存する。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArticleVersion {
+    /// イベント ID（hex）
+    pub event_id: String,
+    /// 記事タイトル
+    pub title: String,
+    /// 要約
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// ヘッダー画像 URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// トピックハッシュタグ
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Markdown コンテンツ
+    pub content: String,
+    /// イベントの作成日時
+    pub created_at: u64,
+}
+
+impl ArticleVersion {
+    /// 内容（タイトル・要約・画像・タグ・本文）が同じか
+    fn same_content(&self, other: &ArticleVersion) -> bool {
+        self.title == other.title
+            && self.summary == other.summary
+            && self.image == other.image
+            && self.tags == other.tags
+            && self.content == other.content
+    }
+}
+
+/// 記事のバージョン履歴（識別子 → バージョン、古い順）
+pub type ArticleHistory = BTreeMap<String, Vec<ArticleVersion>>;
+
+/// 記事のバージョンを履歴に追加する。記録済みのイベント、または直前のバージョンと同じ内容の場合は
+/// 追加せずに false を返す
+pub fn record_article_version(history: &mut ArticleHistory, identifier: &str, version: ArticleVersion) -> bool {
+    let versions = history.entry(identifier.to_string()).or_default();
+    if versions.iter().any(|v| v.event_id == version.event_id) {
+        return false;
+    }
+    let position = versions.partition_point(|v| v.created_at <= version.created_at);
+    if position > 0 && versions[position - 1].same_content(&version) {
+        return false;
+    }
+    versions.insert(position, version);
+    if versions.len() > MAX_ARTICLE_VERSIONS {
+        let excess = versions.len() - MAX_ARTICLE_VERSIONS;
+        versions.drain(..excess);
+    }
+    true
+}
+
+/// アカウントの記事のバージョン履歴を読み込む
+pub fn load_article_history(account_hex: &str) -> ArticleHistory {
+    load_account_state(ARTICLE_HISTORY_FILE, account_hex)
+}
+
+/// アカウントの記事のバージョン履歴を保存する
+pub fn save_article_history(account_hex: &str, history: &ArticleHistory) -> Result<()> {
+    save_account_state(ARTICLE_HISTORY_FILE, account_hex, history)
+}
+
+/// アドレス帳に登録した別名（ペットネーム）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactAlias {
+    /// hex 公開鍵
+    pub pubkey: String,
+    /// メモ（任意）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// 登録（更新）した日時
+    pub added_at: u64,
+}
+
+/// 別名を正規化する（前後の空白と先頭の `@` を除き、小文字に揃える）
+///
+/// 空・長すぎる・空白や `@` `:` `/` を含む、または公開鍵（npub / nprofile / nsec / 64 桁の hex）と
+/// 紛らわしい文字列は別名として使えないため None を返します。
+pub fn normalize_alias(alias: &str) -> Option<String> {
+    let alias = alias.trim().trim_start_matches('@').to_lowercase();
+    let looks_like_key = ["npub1", "nprofile1", "nsec1"].iter().any(|prefix| alias.starts_with(prefix))
+        || (alias.len() == 64 && alias.chars().all(|c| c.is_ascii_hexdigit()));
+    let valid = !alias.is_empty()
+        && alias.chars().count() <= MAX_ALIAS_CHARS
+        && !alias.chars().any(|c| c.is_whitespace() || matches!(c, '@' | ':' | '/'))
+        && !looks_like_key;
+    valid.then_some(alias)
+}
+
+/// アドレス帳（正規化した別名 → 登録情報）を読み込む。
+/// ウォッチリストと同じく、アカウントごとではなく共通で保存する
+pub fn load_address_book() -> BTreeMap<String, ContactAlias> {
+    load_json(ADDRESS_BOOK_FILE)
+}
+
+/// アドレス帳を保存する
+pub fn save_address_book(book: &BTreeMap<String, ContactAlias>) -> Result<()> {
+    save_json(ADDRESS_BOOK_FILE, book)
+}
+
+/// 別名に登録された hex 公開鍵を返す（別名として使えない文字列ではファイルを読まない）
+pub fn resolve_alias(alias: &str) -> Option<String> {
+    let alias = normalize_alias(alias)?;
+    load_address_book().remove(&alias).map(|entry| entry.pubkey)
+}
+
+/// どのリレーにも送信できず、再送を待っている署名済みイベント
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingPublish {
+    /// 署名済みのイベント
+    pub event: nostr_sdk::Event,
+    /// キューに追加した日時
+    pub queued_at: u64,
+    /// 再送を試みた回数
+    #[serde(default)]
+    pub attempts: u32,
+    /// 次に自動で再送する日時
+    pub next_attempt_at: u64,
+    /// 直近の送信エラー
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// 送信待ちキューを読み込む。
+/// 署名済みのイベントをそのまま再送するため、アカウントごとではなく共通で保存する
+pub fn load_pending_publishes() -> Vec<PendingPublish> {
+    load_json(PENDING_PUBLISHES_FILE)
+}
+
+/// 送信待ちキューを保存する
+pub fn save_pending_publishes(queue: &[PendingPublish]) -> Result<()> {
+    save_json(PENDING_PUBLISHES_FILE, &queue)
+}
+
+/// このサーバーから行った支払いの記録（Zap・Lightning アドレスへの支払い）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentRecord {
+    /// 支払った日時
+    pub paid_at: u64,
+    /// 金額（sats）
+    pub amount_sats: u64,
+    /// 支払いの種類（"zap" または "lightning"）
+    pub kind: String,
+    /// 受取人の公開鍵（hex）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient_pubkey: Option<String>,
+    /// 支払い先の Lightning アドレスまたは LNURL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lightning_address: Option<String>,
+    /// Zap 対象のノート ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note_id: Option<String>,
+    /// インボイスの支払いハッシュ（hex）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_hash: Option<String>,
+}
+
+/// 支払い履歴を読み込む。
+/// NWC ウォレットはアカウントと独立して設定するため、アカウントごとではなく共通で保存する
+pub fn load_payment_log() -> Vec<PaymentRecord> {
+    load_json(PAYMENT_LOG_FILE)
+}
+
+/// 支払い履歴に 1 件追加して保存する
+pub fn append_payment_record(record: PaymentRecord) -> Result<()> {
+    let mut log = load_payment_log();
+    log.push(record);
+    save_json(PAYMENT_LOG_FILE, &log)
+}
+
+/// リアクションのプリセットごとの送信日時（UNIX 秒）を読み込む。
+/// プリセットは設定ファイルで定義するため、アカウントごとではなく共通で保存する
+pub fn load_reaction_log() -> HashMap<String, Vec<u64>> {
+    load_json(REACTION_LOG_FILE)
+}
+
+/// リアクションのプリセットごとの送信日時を保存する
+pub fn save_reaction_log(log: &HashMap<String, Vec<u64>>) -> Result<()> {
+    save_json(REACTION_LOG_FILE, log)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dm_cursor() {
+        let mut state = ReadState::default();
+        assert_eq!(state.dm_cursor("alice"), 0);
+
+        state.mark_dms_read(Some("alice"), 100);
+        assert_eq!(state.dm_cursor("alice"), 100);
+        assert_eq!(state.dm_cursor("bob"), 0);
+
+        state.mark_dms_read(None, 50);
+        assert_eq!(state.dm_cursor("alice"), 50);
+        assert_eq!(state.dm_cursor("bob"), 50);
+    }
+
+    #[test]
+    fn test_upsert_local_draft() {
+        let draft = |content: &str, at: u64| LocalDraft {
+            identifier: "my-article".to_string(),
+            title: "タイトル".to_string(),
+            content: content.to_string(),
+            summary: None,
+            image: None,
+            tags: Vec::new(),
+            created_at: at,
+            updated_at: at,
+        };
+        let mut drafts = BTreeMap::new();
+        upsert_local_draft(&mut drafts, draft("初稿", 100));
+        let updated = upsert_local_draft(&mut drafts, draft("改稿", 200));
+
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(updated.created_at, 100);
+        assert_eq!(updated.updated_at, 200);
+        assert_eq!(drafts["my-article"].content, "改稿");
+    }
+
+    #[test]
+    fn test_record_article_version() {
+        let version = |id: &str, content: &str, at: u64| ArticleVersion {
+            event_id: id.to_string(),
+            title: "記事".to_string(),
+            summary: None,
+            image: None,
+            tags: Vec::new(),
+            content: content.to_string(),
+            created_at: at,
+        };
+        let mut history = ArticleHistory::new();
+
+        assert!(record_article_version(&mut history, "post", version("a", "v1", 100)));
+        assert!(record_article_version(&mut history, "post", version("c", "v3", 300)));
+        // 記録済みのイベントや、直前と同じ内容の再公開は記録しない
+        assert!(!record_article_version(&mut history, "post", version("a", "v1", 100)));
+        assert!(!record_article_version(&mut history, "post", version("d", "v3", 400)));
+        // 後から見つかった古いバージョンは作成日時の順に挿入する
+        assert!(record_article_version(&mut history, "post", version("b", "v2", 200)));
+        let ids: Vec<&str> = history["post"].iter().map(|v| v.event_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+
+        for i in 0..MAX_ARTICLE_VERSIONS as u64 {
+            record_article_version(&mut history, "post", version(&format!("x{}", i), &format!("x{}", i), 1000 + i));
+        }
+        assert_eq!(history["post"].len(), MAX_ARTICLE_VERSIONS);
+        assert_eq!(history["post"][0].event_id, "x0");
+    }
+
+    #[test]
+    fn test_normalize_alias() {
+        assert_eq!(normalize_alias(" @Alice ").as_deref(), Some("alice"));
+        assert_eq!(normalize_alias("たろう").as_deref(), Some("たろう"));
+        assert_eq!(normalize_alias("jack.d-2").as_deref(), Some("jack.d-2"));
+        assert!(normalize_alias("").is_none());
+        assert!(normalize_alias("@").is_none());
+        assert!(normalize_alias("alice bob").is_none());
+        assert!(normalize_alias("alice@example.com").is_none());
+        assert!(normalize_alias("nostr:npub1abc").is_none());
+        assert!(normalize_alias("npub1qqqqqq").is_none());
+        assert!(normalize_alias(&"ab".repeat(32)).is_none());
+        assert!(normalize_alias(&"a".repeat(65)).is_none());
+    }
+
+    #[test]
+    fn test_follower_history_record() {
+        let entries = |entries: &[(&str, u64)]| -> Vec<(String, u64)> {
+            entries.iter().map(|(pk, at)| (pk.to_string(), *at)).collect()
+        };
+        let mut history = FollowerHistory::default();
+
+        history.record(1000, 2, &[], &[]);
+        assert!(history.changes.is_empty());
+        assert_eq!(history.last_snapshot_at(), Some(1000));
+
+        history.record(2000, 2, &entries(&[("carol", 2000)]), &entries(&[("bob", 1800)]));
+        let changes: Vec<(&str, &str, u64)> = history.changes.iter()
+            .map(|c| (c.pubkey.as_str(), c.change.as_str(), c.at))
+            .collect();
+        assert_eq!(changes, vec![("bob", "lost", 1800), ("carol", "gained", 2000)]);
+        assert_eq!(history.snapshots.iter().map(|s| s.followers).collect::<Vec<_>>(), vec![2, 2]);
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let path = std::env::temp_dir()
+            .join(format!("rust-nostr-mcp-test-{}", std::process::id()))
+            .join("state.json");
+
+        let mut state = ReadState::default();
+        state.mark_dms_read(Some("alice"), 42);
+        state.notifications_read_at = Some(7);
+        save_json_to(&path, &state).unwrap();
+
+        let loaded: ReadState = load_json_from(&path);
+        assert_eq!(loaded.dm_cursor("alice"), 42);
+        assert_eq!(loaded.notifications_read_at, Some(7));
+
+        // 存在しないファイルはデフォルト値
+        let missing: ReadState = load_json_from(&path.with_file_name("missing.json"));
+        assert_eq!(missing.dm_cursor("alice"), 0);
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
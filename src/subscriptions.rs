@@ -0,0 +1,414 @@
+//! 購読管理モジュール
+//!
+//! 通知・DM・ライブチャットなどの長時間の REQ 購読を保持し、受信したイベントを
+//! バッファしてツールから取り出せるようにします。
+//!
+//! - 同じフィルタの購読は 1 つにまとめます（名前が異なっても REQ は重複させません。`since` は比較しません）
+//! - 共有している購読のバッファは名前ごとの読み取り位置で取り出すため、ある名前で取り出しても他の名前の未取得分は残ります
+//! - EOSE を受信するまでは過去のイベント、以降はリアルタイムのイベントとして扱います
+//! - リレーの再接続時は nostr-sdk のリレープールが購読を自動的に再送します
+
+use anyhow::{anyhow, Result};
+use nostr_sdk::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+/// 1 つの購読でバッファするイベントの最大数（超えた分は古いものから破棄）
+const MAX_BUFFERED_EVENTS: usize = 500;
+
+/// 同時に保持できる購読の最大数
+const MAX_SUBSCRIPTIONS: usize = 20;
+
+/// 重複除去のために覚えておくイベント ID の最大数（超えた分は古いものから忘れる）
+const MAX_SEEN_EVENT_IDS: usize = 5000;
+
+/// 購読の状態
+#[derive(Debug)]
+struct Subscription {
+    /// リレーに送信した購読 ID
+    id: SubscriptionId,
+    /// フィルタ（重複判定用の JSON 表現）
+    filters_key: String,
+    /// この購読を参照している名前と、その名前で次に取り出すイベントの通し番号
+    names: HashMap<String, u64>,
+    /// いずれかの名前で未取得のイベント
+    buffer: VecDeque<Event>,
+    /// バッファの先頭のイベントの通し番号
+    buffer_start: u64,
+    /// 受信済みイベント ID（重複除去用、最大 `MAX_SEEN_EVENT_IDS` 件）
+    seen: HashSet<EventId>,
+    /// `seen` のイベント ID を受信した順に並べたもの（古いものを忘れるため）
+    seen_order: VecDeque<EventId>,
+    /// EOSE を受信したか
+    eose: bool,
+    /// 受信したイベントの総数
+    received: u64,
+    /// バッファから破棄したイベント数
+    dropped: u64,
+    /// 購読開始日時（Unix タイムスタンプ）
+    started_at: u64,
+}
+
+/// 購読の概要（ツールへの返却用）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SubscriptionInfo {
+    /// 購読名
+    pub name: String,
+    /// リレーに送信した購読 ID
+    pub subscription_id: String,
+    /// 未取得のイベント数
+    pub buffered: usize,
+    /// 受信したイベントの総数
+    pub received: u64,
+    /// バッファ溢れで破棄したイベント数
+    pub dropped: u64,
+    /// 過去イベントの受信が完了したか（EOSE 受信済み）
+    pub eose: bool,
+    /// 同じフィルタを共有している他の購読名
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub shared_with: Vec<String>,
+    /// 購読開始日時（Unix タイムスタンプ）
+    pub started_at: u64,
+}
+
+/// 長時間の購読を管理するマネージャー
+pub struct SubscriptionManager {
+    client: Client,
+    subscriptions: Arc<RwLock<HashMap<SubscriptionId, Subscription>>>,
+}
+
+impl SubscriptionManager {
+    /// クライアントの通知を受信するバックグラウンドタスクを起動してマネージャーを作成
+    pub fn new(client: Client) -> Self {
+        let subscriptions: Arc<RwLock<HashMap<SubscriptionId, Subscription>>> =
+            Arc::new(RwLock::new(HashMap::new()));
+
+        let mut notifications = client.notifications();
+        let subs = subscriptions.clone();
+        tokio::spawn(async move {
+            loop {
+                match notifications.recv().await {
+                    Ok(RelayPoolNotification::Event { subscription_id, event, .. }) => {
+                        let mut subs = subs.write().await;
+                        if let Some(sub) = subs.get_mut(&subscription_id) {
+                            sub.push(*event);
+                        }
+                    }
+                    Ok(RelayPoolNotification::Message {
+                        message: RelayMessage::EndOfStoredEvents(subscription_id),
+                        ..
+                    }) => {
+                        if let Some(sub) = subs.write().await.get_mut(&subscription_id) {
+                            sub.eose = true;
+                        }
+                    }
+                    Ok(RelayPoolNotification::Shutdown) => break,
+                    Ok(_) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("購読の通知を {} 件取りこぼしました", n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            debug!("購読の通知受信タスクを終了しました");
+        });
+
+        Self { client, subscriptions }
+    }
+
+    /// 名前付きで購読を開始します。同じフィルタの購読が既にあれば共有します。
+    ///
+    /// 同じ名前の購読が既にある場合は置き換えます。
+    pub async fn subscribe(&self, name: &str, filters: Vec<Filter>) -> Result<SubscriptionInfo> {
+        let filters_key = filters_key(&filters)?;
+
+        let id = SubscriptionId::generate();
+        // リレーとの通信中に通知の受信を止めないよう、ロックを解放してから REQ の送信・停止を行う
+        let (old_id, registered) = {
+            let mut subs = self.subscriptions.write().await;
+
+            // 同じ名前の購読は置き換える
+            let old_id = detach_name(&mut subs, name);
+
+            let registered = if let Some(sub) = subs.values_mut().find(|s| s.filters_key == filters_key) {
+                let start = sub.buffer_start;
+                sub.names.insert(name.to_string(), start);
+                Ok((sub.info(name), false))
+            } else if subs.len() >= MAX_SUBSCRIPTIONS {
+                Err(anyhow!(
+                    "購読数が上限（{}）に達しています。不要な購読を停止してください。",
+                    MAX_SUBSCRIPTIONS
+                ))
+            } else {
+                // REQ 送信直後に届くイベントを取りこぼさないよう、先に登録しておく
+                let sub = Subscription::new(id.clone(), filters_key, name);
+                let info = sub.info(name);
+                subs.insert(id.clone(), sub);
+                Ok((info, true))
+            };
+            (old_id, registered)
+        };
+
+        if let Some(old_id) = old_id {
+            self.client.unsubscribe(old_id).await;
+        }
+        let (info, is_new) = registered?;
+        if !is_new {
+            return Ok(info);
+        }
+
+        if let Err(e) = self.client.subscribe_with_id(id.clone(), filters, None).await {
+            self.subscriptions.write().await.remove(&id);
+            return Err(anyhow!("購読の開始に失敗しました: {}", e));
+        }
+
+        debug!("購読を開始しました: name={}, id={}", name, id);
+        Ok(info)
+    }
+
+    /// この名前でまだ取り出していないイベントを最大 `limit` 件取り出します（古い順）。
+    ///
+    /// 同じ購読を共有している他の名前の未取得分には影響しません。
+    pub async fn drain(&self, name: &str, limit: usize) -> Result<(Vec<Event>, SubscriptionInfo)> {
+        let mut subs = self.subscriptions.write().await;
+        let sub = subs
+            .values_mut()
+            .find(|s| s.names.contains_key(name))
+            .ok_or_else(|| anyhow!("購読が見つかりません: {}", name))?;
+
+        let events = sub.drain(name, limit);
+        Ok((events, sub.info(name)))
+    }
+
+    /// 購読を停止します。他の名前と共有している場合は REQ を維持します。
+    pub async fn unsubscribe(&self, name: &str) -> Result<()> {
+        let closed = {
+            let mut subs = self.subscriptions.write().await;
+            if !subs.values().any(|s| s.names.contains_key(name)) {
+                return Err(anyhow!("購読が見つかりません: {}", name));
+            }
+            detach_name(&mut subs, name)
+        };
+        if let Some(id) = closed {
+            self.client.unsubscribe(id).await;
+            debug!("購読を停止しました: name={}", name);
+        }
+        Ok(())
+    }
+
+    /// すべての購読の概要を取得します。
+    pub async fn list(&self) -> Vec<SubscriptionInfo> {
+        let subs = self.subscriptions.read().await;
+        let mut infos: Vec<SubscriptionInfo> = subs
+            .values()
+            .flat_map(|sub| sub.names.keys().map(move |name| sub.info(name)))
+            .collect();
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        infos
+    }
+}
+
+impl Subscription {
+    fn new(id: SubscriptionId, filters_key: String, name: &str) -> Self {
+        Self {
+            id,
+            filters_key,
+            names: HashMap::from([(name.to_string(), 0)]),
+            buffer: VecDeque::new(),
+            buffer_start: 0,
+            seen: HashSet::new(),
+            seen_order: VecDeque::new(),
+            eose: false,
+            received: 0,
+            dropped: 0,
+            started_at: Timestamp::now().as_u64(),
+        }
+    }
+
+    /// イベントをバッファに追加（重複は無視、上限を超えたら古いものから破棄）
+    fn push(&mut self, event: Event) {
+        if !self.seen.insert(event.id) {
+            return;
+        }
+        self.seen_order.push_back(event.id);
+        if self.seen_order.len() > MAX_SEEN_EVENT_IDS {
+            if let Some(oldest) = self.seen_order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.received += 1;
+        self.buffer.push_back(event);
+        if self.buffer.len() > MAX_BUFFERED_EVENTS {
+            self.buffer.pop_front();
+            self.buffer_start += 1;
+            self.dropped += 1;
+        }
+    }
+
+    /// 名前の読み取り位置（破棄済みのイベントは飛ばす）
+    fn cursor(&self, name: &str) -> u64 {
+        self.names.get(name).copied().unwrap_or(0).max(self.buffer_start)
+    }
+
+    /// 名前で未取得のイベント数
+    fn pending(&self, name: &str) -> usize {
+        (self.buffer_start + self.buffer.len() as u64 - self.cursor(name)) as usize
+    }
+
+    /// 名前で未取得のイベントを最大 `limit` 件取り出し、全員が取り出したイベントをバッファから消す
+    fn drain(&mut self, name: &str, limit: usize) -> Vec<Event> {
+        let cursor = self.cursor(name);
+        let skip = (cursor - self.buffer_start) as usize;
+        let count = limit.min(self.pending(name));
+        let events = self.buffer.iter().skip(skip).take(count).cloned().collect();
+        self.names.insert(name.to_string(), cursor + count as u64);
+        self.trim();
+        events
+    }
+
+    /// すべての名前で取り出し済みのイベントをバッファから消す
+    fn trim(&mut self) {
+        let Some(min_cursor) = self.names.values().min().copied() else { return };
+        while self.buffer_start < min_cursor && self.buffer.pop_front().is_some() {
+            self.buffer_start += 1;
+        }
+    }
+
+    fn info(&self, name: &str) -> SubscriptionInfo {
+        let mut shared_with: Vec<String> = self.names.keys().filter(|n| *n != name).cloned().collect();
+        shared_with.sort();
+        SubscriptionInfo {
+            name: name.to_string(),
+            subscription_id: self.id.to_string(),
+            buffered: self.pending(name),
+            received: self.received,
+            dropped: self.dropped,
+            eose: self.eose,
+            shared_with,
+            started_at: self.started_at,
+        }
+    }
+}
+
+/// フィルタの重複判定用キー（開始時刻で変わる `since` を除いた JSON 表現）
+fn filters_key(filters: &[Filter]) -> Result<String> {
+    let normalized: Vec<Filter> = filters.iter().cloned().map(Filter::remove_since).collect();
+    Ok(serde_json::to_string(&normalized)?)
+}
+
+/// 購読から名前を外し、参照がなくなった購読の ID を返す（REQ を閉じる必要がある）
+fn detach_name(subs: &mut HashMap<SubscriptionId, Subscription>, name: &str) -> Option<SubscriptionId> {
+    let id = subs.values_mut().find_map(|sub| {
+        if sub.names.remove(name).is_some() {
+            sub.trim();
+            Some(sub.id.clone())
+        } else {
+            None
+        }
+    })?;
+    if subs.get(&id).is_some_and(|sub| sub.names.is_empty()) {
+        subs.remove(&id);
+        Some(id)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscription(id: &str, names: &[&str]) -> Subscription {
+        let mut sub = Subscription::new(SubscriptionId::new(id), String::new(), names[0]);
+        sub.names = names.iter().map(|n| (n.to_string(), 0)).collect();
+        sub
+    }
+
+    fn notes(count: usize) -> Vec<Event> {
+        let keys = Keys::generate();
+        (0..count)
+            .map(|i| EventBuilder::text_note(format!("テスト {}", i)).sign_with_keys(&keys).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_subscription_push_dedup() {
+        let keys = Keys::generate();
+        let event = EventBuilder::text_note("テスト").sign_with_keys(&keys).unwrap();
+        let mut sub = subscription("a", &["notifications"]);
+
+        sub.push(event.clone());
+        sub.push(event);
+        assert_eq!(sub.buffer.len(), 1);
+        assert_eq!(sub.received, 1);
+    }
+
+    #[test]
+    fn test_subscription_seen_is_bounded() {
+        let mut sub = subscription("a", &["notifications"]);
+        for event in notes(MAX_SEEN_EVENT_IDS + 10) {
+            sub.push(event);
+        }
+        assert_eq!(sub.seen.len(), MAX_SEEN_EVENT_IDS);
+        assert_eq!(sub.seen_order.len(), MAX_SEEN_EVENT_IDS);
+        assert_eq!(sub.buffer.len(), MAX_BUFFERED_EVENTS);
+        assert_eq!(sub.pending("notifications"), MAX_BUFFERED_EVENTS);
+    }
+
+    #[test]
+    fn test_subscription_drain_per_name() {
+        let events = notes(3);
+        let mut sub = subscription("a", &["x", "y"]);
+        for event in &events {
+            sub.push(event.clone());
+        }
+
+        // 共有中の他の名前の未取得分は残る
+        let drained = sub.drain("x", 2);
+        assert_eq!(drained.iter().map(|e| e.id).collect::<Vec<_>>(), vec![events[0].id, events[1].id]);
+        assert_eq!(sub.pending("x"), 1);
+        assert_eq!(sub.pending("y"), 3);
+        assert_eq!(sub.buffer.len(), 3);
+
+        let drained = sub.drain("y", 10);
+        assert_eq!(drained.len(), 3);
+        assert_eq!(sub.pending("y"), 0);
+        // 全員が取り出したイベントはバッファから消える
+        assert_eq!(sub.buffer.len(), 1);
+        assert_eq!(sub.drain("x", 10)[0].id, events[2].id);
+        assert!(sub.buffer.is_empty());
+
+        // 名前を外すと、その名前だけが未取得のイベントも消える
+        sub.push(notes(1).remove(0));
+        sub.drain("x", 10);
+        let mut subs = HashMap::from([(SubscriptionId::new("a"), sub)]);
+        assert_eq!(detach_name(&mut subs, "y"), None);
+        assert!(subs[&SubscriptionId::new("a")].buffer.is_empty());
+    }
+
+    #[test]
+    fn test_filters_key_ignores_since() {
+        let filter = Filter::new().kind(Kind::TextNote).hashtag("nostr");
+        let a = filters_key(&[filter.clone().since(Timestamp::from(100))]).unwrap();
+        let b = filters_key(&[filter.clone().since(Timestamp::from(200))]).unwrap();
+        let c = filters_key(&[filter.hashtag("rust")]).unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_detach_name() {
+        let mut subs = HashMap::new();
+        subs.insert(SubscriptionId::new("a"), subscription("a", &["x", "y"]));
+
+        // 共有中は REQ を閉じない
+        assert_eq!(detach_name(&mut subs, "x"), None);
+        assert_eq!(subs.len(), 1);
+
+        // 最後の名前が外れたら REQ を閉じる
+        assert_eq!(detach_name(&mut subs, "y"), Some(SubscriptionId::new("a")));
+        assert!(subs.is_empty());
+    }
+}
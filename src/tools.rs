@@ -6,23 +6,93 @@
 //! セキュリティ: 秘密鍵はローカル設定ファイル
 //! (~/.config/rust-nostr-mcp/config.json) に保存され、AI エージェントには渡されません。
 
+mod calendar;
+mod live;
+mod polls;
+mod wiki;
+mod zaps;
+
 use anyhow::{anyhow, Context, Result};
 use base64::Engine;
-use nostr_sdk::ToBech32;
+use nostr_sdk::{Event, EventId, ToBech32};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::sync::Arc;
 use tracing::{debug, info};
 
+use crate::config::{AuthMode, Config, OutputOptions, TrustMode, WebOfTrust};
 use crate::content;
+use crate::filters::{LanguageFilter, TrustGraph};
+use crate::link_preview::LinkPreview;
 use crate::mcp_apps;
-use crate::nip46::Nip46Session;
-use crate::nostr_client::{ArticleParams, DirectMessageInfo, NostrClient, NoteInfo, ThreadReply};
+use crate::nip46::{
+    normalize_signer_label, summarize_perms, Nip46Registry, Nip46Session, DEFAULT_SIGNER_LABEL,
+};
+use crate::nostr_client::{ArticleParams, DirectMessageInfo, NostrClient, NoteInfo, PublicationParams, RelayDiscoveryQuery, ThreadReply, VideoParams, NOTIFICATION_TYPES, SUBSCRIPTION_TYPES};
 
 /// 取得件数の上限
 const MAX_LIMIT: u64 = 100;
 /// 取得件数のデフォルト値
 const DEFAULT_LIMIT: u64 = 20;
+/// タイムライン・検索の取得件数の上限（リレーの応答件数の上限を超える分は `until` を遡って取得）
+const MAX_PAGINATED_LIMIT: u64 = 500;
+/// `compact` / `truncate_content` と設定ファイルの `content-max-chars` を適用する、ノート一覧を返すツール
+const NOTE_OUTPUT_TOOLS: &[&str] = &[
+    "get_nostr_timeline",
+    "get_watched_feeds",
+    "search_nostr_notes",
+    "get_nostr_notes",
+    "get_nostr_thread",
+    "get_reply_context",
+    "get_note_reposts",
+];
+/// タイムライン集計の期間（時間）のデフォルト値と上限
+const DEFAULT_DIGEST_HOURS: u64 = 24;
+const MAX_DIGEST_HOURS: u64 = 168;
+/// タイムライン集計に使うノート数のデフォルト値と上限
+const DEFAULT_DIGEST_WINDOW: u64 = 200;
+const MAX_DIGEST_WINDOW: u64 = 500;
+/// タイムライン集計の各ランキングの件数のデフォルト値と上限
+const DEFAULT_DIGEST_TOP: u64 = 5;
+const MAX_DIGEST_TOP: u64 = 20;
+/// ハッシュタグ集計で比較する期間（日数）のデフォルト値と上限
+const DEFAULT_HASHTAG_DAYS: u64 = 7;
+const MAX_HASHTAG_DAYS: u64 = 30;
+/// ハッシュタグ集計に使うノート数のデフォルト値と上限
+const DEFAULT_HASHTAG_WINDOW: u64 = 500;
+const MAX_HASHTAG_WINDOW: u64 = 2000;
+/// アカウント分析の期間（日数）のデフォルト値と上限
+const DEFAULT_ANALYTICS_DAYS: u64 = 30;
+const MAX_ANALYTICS_DAYS: u64 = 365;
+/// リプライの文脈に含める著者の関連ノート数のデフォルト値と上限
+const DEFAULT_REPLY_CONTEXT_NOTES: u64 = 5;
+const MAX_REPLY_CONTEXT_NOTES: u64 = 20;
+/// フォローのおすすめに必要な重なり（フォローしている自分のフォローの人数）のデフォルト値
+const DEFAULT_RECOMMEND_MIN_OVERLAP: u64 = 2;
+/// リレー発見で返すリレー数のデフォルト値と上限
+const DEFAULT_DISCOVER_RELAYS: u64 = 20;
+const MAX_DISCOVER_RELAYS: u64 = 100;
+/// ローカル下書き一覧のプレビューの最大文字数
+const LOCAL_DRAFT_PREVIEW_CHARS: usize = 200;
+/// エクスポートするイベント数のデフォルト値と上限
+const DEFAULT_EXPORT_MAX_EVENTS: u64 = 10_000;
+const MAX_EXPORT_MAX_EVENTS: u64 = 100_000;
+/// 再送信するイベント数のデフォルト値と上限
+const DEFAULT_REBROADCAST_MAX_EVENTS: u64 = 100;
+const MAX_REBROADCAST_MAX_EVENTS: u64 = 1_000;
+/// スレッド投稿で本文を分割するときの 1 ノートあたりの文字数のデフォルト値と範囲
+const DEFAULT_THREAD_NOTE_CHARS: u64 = 280;
+const MIN_THREAD_NOTE_CHARS: u64 = 50;
+const MAX_THREAD_NOTE_CHARS: u64 = 5_000;
+/// スレッドの番号（「\n\n(12/25)」）のために確保する文字数
+const THREAD_NUMBER_RESERVE: usize = 10;
+
+/// get_nostr_notes で一度に指定できるイベント ID の最大数
+const MAX_BATCH_NOTE_IDS: usize = 100;
+/// get_nostr_profiles で一度に指定できる公開鍵の最大数
+const MAX_BATCH_PROFILES: usize = 100;
+/// migrate_account で DM で移行を通知するフォロワーの最大数
+const MAX_MIGRATION_NOTIFICATIONS: u64 = 100;
 
 /// MCP ツール定義
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,15 +109,6 @@ pub struct ToolDefinition {
     pub meta: Option<Value>,
 }
 
-/// limit パラメータを抽出するヘルパー
-fn extract_limit(arguments: &Value) -> u64 {
-    arguments
-        .get("limit")
-        .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
-        .unwrap_or(DEFAULT_LIMIT)
-        .min(MAX_LIMIT)
-}
-
 /// 必須の文字列パラメータを抽出するヘルパー
 /// 複数のキー名を許容（第一候補、第二候補...）
 fn require_str_param<'a>(arguments: &'a Value, keys: &[&str]) -> Result<&'a str> {
@@ -92,13 +153,243 @@ fn extract_tags_param(arguments: &Value) -> Option<Vec<String>> {
     })
 }
 
+/// 文字列配列パラメータを抽出するヘルパー（未指定時は空）
+fn extract_string_array(arguments: &Value, key: &str) -> Vec<String> {
+    arguments
+        .get(key)
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|item| item.as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+/// `verify: true` が指定された場合、公開したイベントを受け付けたリレー（レスポンスの `published_to`）から
+/// 再取得して配信を確認し、結果を `verification` としてレスポンスに追加するヘルパー
+async fn verify_publish_if_requested(client: &NostrClient, arguments: &Value, event_id: EventId, result: &mut Value) {
+    // ドライランでは公開していないため確認しない
+    if crate::dry_run::is_active() || !arguments.get("verify").and_then(|v| v.as_bool()).unwrap_or(false) {
+        return;
+    }
+    let relays: Vec<String> = result["published_to"]
+        .as_array()
+        .map(|arr| arr.iter().filter_map(|item| item.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    result["verification"] = json!(client.verify_publish(event_id, &relays).await);
+}
+
+/// ドライランで公開しなかったイベントを `dry_run_events` としてレスポンスに追加するヘルパー
+///
+/// `message` には公開していないことを明記します。
+fn attach_dry_run_events(result: &mut Value, events: &[Event]) {
+    let Some(object) = result.as_object_mut() else { return };
+    let message = object.get("message").and_then(|m| m.as_str()).unwrap_or_default().to_string();
+    object.insert("dry_run".to_string(), json!(true));
+    object.insert("dry_run_events".to_string(), json!(events));
+    object.insert("message".to_string(), json!(if events.is_empty() {
+        format!("ドライラン: 公開するイベントはありません。{}", message)
+    } else {
+        format!("ドライラン: {} 件のイベントを署名しましたが、リレーには公開していません（dry_run_events を確認）。{}", events.len(), message)
+    }));
+}
+
+/// `resolve_references` が指定された場合に、結果に含まれる `nostr:` 参照の解決結果を埋め込むヘルパー
+///
+/// `parsed_content.references` の各要素に、解決できた場合は `resolved` を、できなかった場合は `resolved: null` を追加します。
+async fn hydrate_references_if_requested(client: &NostrClient, arguments: &Value, result: &mut Value) {
+    if !arguments.get("resolve_references").and_then(|v| v.as_bool()).unwrap_or(false) {
+        return;
+    }
+    let mut references = Vec::new();
+    visit_parsed_content(result, &mut |parsed| {
+        let refs = parsed["references"].as_array().into_iter().flatten();
+        references.extend(refs.filter_map(|r| r["bech32"].as_str().map(String::from)));
+    });
+    if references.is_empty() {
+        return;
+    }
+    let resolved = client.resolve_references(&references).await;
+    visit_parsed_content(result, &mut |parsed| {
+        let Some(refs) = parsed.get_mut("references").and_then(|r| r.as_array_mut()) else { return };
+        for reference in refs {
+            let info = reference["bech32"].as_str().and_then(|b| resolved.get(b));
+            reference["resolved"] = json!(info);
+        }
+    });
+}
+
+/// `link_previews` が指定された場合に、結果に含まれるリンクの OpenGraph プレビューを埋め込むヘルパー
+///
+/// 取得できたプレビューのみを `parsed_content.link_previews` に追加します。
+async fn attach_link_previews_if_requested(client: &NostrClient, arguments: &Value, result: &mut Value) {
+    if !arguments.get("link_previews").and_then(|v| v.as_bool()).unwrap_or(false) {
+        return;
+    }
+    let mut links = Vec::new();
+    visit_parsed_content(result, &mut |parsed| {
+        let urls = parsed["links"].as_array().into_iter().flatten();
+        links.extend(urls.filter_map(|u| u.as_str().map(String::from)));
+    });
+    if links.is_empty() {
+        return;
+    }
+    let previews = client.fetch_link_previews(&links).await;
+    visit_parsed_content(result, &mut |parsed| {
+        let found: Vec<&LinkPreview> = parsed["links"].as_array().into_iter().flatten()
+            .filter_map(|u| u.as_str().and_then(|u| previews.get(u)))
+            .collect();
+        if !found.is_empty() {
+            parsed["link_previews"] = json!(found);
+        }
+    });
+}
+
+/// JSON 内のすべての `parsed_content` オブジェクトに関数を適用する
+fn visit_parsed_content(value: &mut Value, f: &mut dyn FnMut(&mut Value)) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                if key == "parsed_content" {
+                    f(child);
+                } else {
+                    visit_parsed_content(child, f);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| visit_parsed_content(item, f)),
+        _ => {}
+    }
+}
+
+/// Web of Trust フィルタを解決するヘルパー
+///
+/// `trust_hops` / `trust_mode` パラメータを優先し、未指定時は設定ファイルの `web-of-trust` を使用します。
+/// 設定ファイル由来で未認証の場合などは、フィルタなし（None）として扱います。
+async fn resolve_trust_filter(client: &NostrClient, arguments: &Value) -> Result<Option<(WebOfTrust, Arc<TrustGraph>)>> {
+    let hops_param = arguments
+        .get("trust_hops")
+        .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)));
+    let mode_param = optional_str_param(arguments, "trust_mode")
+        .map(|m| TrustMode::parse(m).ok_or_else(|| anyhow!("trust_mode には drop または rank を指定してください: {}", m)))
+        .transpose()?;
+    let default = client.web_of_trust();
+
+    let wot = match (hops_param, default) {
+        (Some(0), _) => return Ok(None),
+        (Some(hops), default) => WebOfTrust {
+            max_hops: hops.min(2) as u8,
+            mode: mode_param.or(default.map(|d| d.mode)).unwrap_or_default(),
+        },
+        (None, Some(default)) => WebOfTrust {
+            mode: mode_param.unwrap_or(default.mode),
+            ..default
+        },
+        (None, None) => return Ok(None),
+    };
+
+    if hops_param.is_none() && client.public_key().is_none() {
+        return Ok(None);
+    }
+
+    let graph = client.trust_graph(wot.max_hops).await?;
+    Ok(Some((wot, graph)))
+}
+
+/// Kind の配列パラメータ（`kinds`）を抽出するヘルパー
+fn extract_kinds(arguments: &Value) -> Vec<u16> {
+    arguments
+        .get("kinds")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|k| k.as_u64()).map(|k| k as u16).collect())
+        .unwrap_or_default()
+}
+
+/// 最小 PoW 難易度を取得するヘルパー（`min_pow` パラメータ、未指定時は設定ファイルの `min-pow`）
+fn extract_min_pow(client: &NostrClient, arguments: &Value) -> u8 {
+    arguments
+        .get("min_pow")
+        .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+        .map(|v| v.min(u8::MAX as u64) as u8)
+        .unwrap_or_else(|| client.min_pow())
+}
+
+/// 言語フィルタを解決するヘルパー（`languages` パラメータ、未指定時は設定ファイルの `languages`）
+///
+/// 空の配列を指定すると絞り込みを行いません。
+fn resolve_language_filter(client: &NostrClient, arguments: &Value) -> LanguageFilter {
+    if arguments.get("languages").is_some_and(|v| v.is_array()) {
+        LanguageFilter::new(&extract_string_array(arguments, "languages"))
+    } else {
+        client.language_filter().clone()
+    }
+}
+
+/// 言語フィルタの適用結果を JSON に変換
+fn format_language_json(filter: &LanguageFilter, excluded: usize) -> Value {
+    json!({
+        "allowed": filter.languages,
+        "excluded_count": excluded
+    })
+}
+
+/// Web of Trust フィルタの適用結果を JSON に変換
+fn format_trust_json(wot: &WebOfTrust, outside: usize) -> Value {
+    json!({
+        "max_hops": wot.max_hops,
+        "mode": wot.mode,
+        "outside_count": outside
+    })
+}
+
+/// ノートの著者の公開鍵を取得
+fn note_author_key(note: &NoteInfo) -> Option<nostr_sdk::PublicKey> {
+    nostr_sdk::PublicKey::from_hex(&note.author.pubkey).ok()
+}
+
+/// スレッドのリプライに Web of Trust フィルタを再帰的に適用
+fn apply_trust_to_replies(replies: &mut Vec<ThreadReply>, wot: &WebOfTrust, graph: &TrustGraph) -> usize {
+    let mut outside = graph.apply(replies, wot.max_hops, wot.mode, |r| note_author_key(&r.note));
+    for reply in replies.iter_mut() {
+        outside += apply_trust_to_replies(&mut reply.replies, wot, graph);
+    }
+    outside
+}
+
+/// file_path または data（Base64）パラメータからメディアデータを読み込むヘルパー
+/// 戻り値は（データ, ファイル名）です。
+async fn load_media_data(arguments: &Value) -> Result<(Vec<u8>, String)> {
+    let file_path = optional_str_param(arguments, "file_path");
+    let data_base64 = optional_str_param(arguments, "data");
+    let filename_param = optional_str_param(arguments, "filename");
+
+    if let Some(path) = file_path {
+        let file_data = tokio::fs::read(path)
+            .await
+            .context(format!("ファイルの読み込みに失敗: {}", path))?;
+        let name = std::path::Path::new(path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("file")
+            .to_string();
+        Ok((file_data, name))
+    } else if let Some(b64) = data_base64 {
+        let file_data = base64::engine::general_purpose::STANDARD
+            .decode(b64)
+            .context("Base64 データのデコードに失敗")?;
+        let name = filename_param.unwrap_or("file").to_string();
+        Ok((file_data, name))
+    } else {
+        Err(anyhow!(
+            "file_path または data のいずれかを指定してください"
+        ))
+    }
+}
+
 /// ノートを JSON 表示形式にフォーマットするヘルパー（Phase 3: 構造化表示対応）
 fn format_note_json(note: &NoteInfo) -> Value {
     let formatted_time = format_timestamp(note.created_at);
 
     // Phase 3: display_card の構築
     let header = format_display_card_header(&note.author);
-    let footer = format_display_card_footer(note.reactions, note.replies, &formatted_time);
+    let footer = format_display_card_footer(note, &formatted_time);
 
     // Phase 3: コンテンツ解析（メディア・ハッシュタグ・Nostr 参照）
     let parsed = content::parse_content(&note.content);
@@ -113,7 +404,8 @@ fn format_note_json(note: &NoteInfo) -> Value {
             "display_name": note.author.display_name,
             "display": note.author.display(),
             "picture": note.author.picture,
-            "nip05": note.author.nip05
+            "nip05": note.author.nip05,
+            "nip05_verified": note.author.nip05_verified
         },
         "content": note.content,
         "created_at": note.created_at,
@@ -131,6 +423,27 @@ fn format_note_json(note: &NoteInfo) -> Value {
     if let Some(replies) = note.replies {
         result["replies"] = json!(replies);
     }
+    if let Some(reposts) = note.reposts {
+        result["reposts"] = json!(reposts);
+    }
+    if let Some(zap_sats) = note.zap_sats {
+        result["zap_sats"] = json!(zap_sats);
+    }
+    if let Some(verified) = note.verified {
+        result["verified"] = json!(verified);
+    }
+    if let Some(ref warning) = note.content_warning {
+        result["content_warning"] = json!(warning);
+    }
+    if note.content_hidden {
+        result["content_hidden"] = json!(true);
+    }
+    if let Some(ref language) = note.language {
+        result["language"] = json!(language);
+    }
+    if let Some(ref signed_by) = note.signed_by {
+        result["signed_by"] = json!(signed_by);
+    }
 
     // Phase 3: メディア・解析済みコンテンツを追加（空でない場合のみ）
     if !parsed.media.is_empty() {
@@ -144,40 +457,258 @@ fn format_note_json(note: &NoteInfo) -> Value {
         if !parsed.references.is_empty() {
             result["parsed_content"]["references"] = json!(parsed.references);
         }
+        if !parsed.links.is_empty() {
+            result["parsed_content"]["links"] = json!(parsed.links);
+        }
     }
 
     result
 }
 
+/// ノート一覧を返すツールの出力の整形方法（`compact` / `truncate_content` / `fields`）
+struct NoteOutputShape {
+    compact: bool,
+    max_chars: Option<usize>,
+    fields: Vec<String>,
+}
+
+/// ノートの JSON か（`format_note_json` の出力と同じキーを持つオブジェクト）
+fn is_note_json(map: &serde_json::Map<String, Value>) -> bool {
+    map.contains_key("nevent") && map.contains_key("author") && map.contains_key("content")
+}
+
+/// 結果に含まれるノートを、`fields` で指定したフィールドだけに絞り込むヘルパー
+///
+/// フィールドは `author.display` のようにドットで入れ子のキーを指定できます。存在しないフィールドは
+/// 無視し、本文を切り詰めた場合の `content_truncated` は `content` を残すときに一緒に残します。
+/// ノート以外の値（件数・メッセージ・リプライの入れ子構造など）はそのまま残します。
+fn project_note_fields(value: &mut Value, fields: &[String]) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                project_note_fields(item, fields);
+            }
+        }
+        Value::Object(map) if is_note_json(map) => {
+            let mut paths: Vec<&str> = fields.iter().map(String::as_str).collect();
+            if paths.contains(&"content") {
+                paths.push("content_truncated");
+            }
+            *map = project_object(map, &paths);
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                project_note_fields(item, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// オブジェクトから、ドット区切りのパスで指定したフィールドだけを取り出す
+fn project_object(map: &serde_json::Map<String, Value>, paths: &[&str]) -> serde_json::Map<String, Value> {
+    let mut projected = serde_json::Map::new();
+    for path in paths {
+        let (key, rest) = match path.split_once('.') {
+            Some((key, rest)) => (key, Some(rest)),
+            None => (*path, None),
+        };
+        let Some(value) = map.get(key) else { continue };
+        match (rest, value) {
+            (None, _) => {
+                projected.insert(key.to_string(), value.clone());
+            }
+            (Some(rest), Value::Object(inner)) => {
+                let nested = project_object(inner, &[rest]);
+                if nested.is_empty() {
+                    continue;
+                }
+                match projected.get_mut(key) {
+                    Some(Value::Object(existing)) => existing.extend(nested),
+                    Some(_) => {}
+                    None => {
+                        projected.insert(key.to_string(), Value::Object(nested));
+                    }
+                }
+            }
+            (Some(_), _) => {}
+        }
+    }
+    projected
+}
+
+/// ノート一覧の出力を縮小するヘルパー
+///
+/// `compact` の場合は display_card と parsed_content を取り除き、`max_chars` を超える content は
+/// 切り詰めて `content_truncated: true` を付けます（display_card の content も同じ内容にします）。
+/// ネストしたノート（リプライ・リポスト元など）にも適用します。
+fn shape_note_output(value: &mut Value, compact: bool, max_chars: Option<usize>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                shape_note_output(item, compact, max_chars);
+            }
+        }
+        Value::Object(map) => {
+            if compact {
+                map.remove("display_card");
+                map.remove("parsed_content");
+            }
+            let truncated = match (map.get("content").and_then(|v| v.as_str()), max_chars) {
+                (Some(content), Some(max)) if content.chars().count() > max => {
+                    Some(format!("{}…", content.chars().take(max).collect::<String>()))
+                }
+                _ => None,
+            };
+            if let Some(truncated) = truncated {
+                if let Some(card) = map.get_mut("display_card").and_then(|v| v.as_object_mut()) {
+                    card.insert("content".to_string(), json!(truncated));
+                }
+                map.insert("content".to_string(), json!(truncated));
+                map.insert("content_truncated".to_string(), json!(true));
+            }
+            for (key, item) in map.iter_mut() {
+                if key != "display_card" {
+                    shape_note_output(item, compact, max_chars);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 /// display_card のヘッダーを生成（"表示名 (@nip05)" 形式）
+///
+/// NIP-05 識別子は検証済みの場合のみ表示し、未検証なら短縮 npub を使います。
 fn format_display_card_header(author: &crate::nostr_client::AuthorInfo) -> String {
     let display = author.display();
-    if let Some(ref nip05) = author.nip05 {
+    if let Some(ref nip05) = author.nip05.as_ref().filter(|_| author.nip05_verified) {
         format!("{} (@{})", display, nip05)
     } else {
         format!("{} ({})", display, author.short_npub())
     }
 }
 
-/// display_card のフッターを生成（"N リアクション · N リプライ · 時間" 形式）
-fn format_display_card_footer(reactions: Option<u64>, replies: Option<u64>, formatted_time: &str) -> String {
+/// display_card のフッターを生成（"N リアクション · N リプライ · N リポスト · N sats · 時間" 形式）
+fn format_display_card_footer(note: &NoteInfo, formatted_time: &str) -> String {
     let mut parts = Vec::new();
 
-    if let Some(r) = reactions {
+    if let Some(r) = note.reactions {
         if r > 0 {
             parts.push(format!("{} リアクション", r));
         }
     }
-    if let Some(r) = replies {
+    if let Some(r) = note.replies {
         if r > 0 {
             parts.push(format!("{} リプライ", r));
         }
     }
+    if let Some(r) = note.reposts {
+        if r > 0 {
+            parts.push(format!("{} リポスト", r));
+        }
+    }
+    if let Some(sats) = note.zap_sats {
+        if sats > 0 {
+            parts.push(format!("⚡{} sats", sats));
+        }
+    }
     parts.push(formatted_time.to_string());
 
     parts.join(" · ")
 }
 
+/// 公開後の配信確認（`verify`）プロパティのスキーマ
+fn verify_property() -> Value {
+    json!({
+        "type": "boolean",
+        "description": "true の場合、公開後にイベントを受け付けた各リレーから再取得し、実際に取得できるか確認（デフォルト: false）"
+    })
+}
+
+/// 署名のみで公開しない（`dry_run`）プロパティのスキーマ
+fn dry_run_property() -> Value {
+    json!({
+        "type": "boolean",
+        "description": "true の場合、イベントを署名するだけでリレーに公開せず、公開するはずだったイベントの JSON を dry_run_events として返す（デフォルト: false）"
+    })
+}
+
+/// Web of Trust のホップ数（`trust_hops`）プロパティのスキーマ
+fn trust_hops_property() -> Value {
+    json!({
+        "type": "number",
+        "description": "Web of Trust フィルタ: フォローグラフ上でこのホップ数以内（1: フォロー中、2: フォローのフォロー）のアカウントのみ対象とする。0 で無効（デフォルト: 設定ファイルの web-of-trust）"
+    })
+}
+
+/// Web of Trust の適用方法（`trust_mode`）プロパティのスキーマ
+fn trust_mode_property() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["drop", "rank"],
+        "description": "信頼範囲外のアカウントを除外する（drop）か後ろに並べる（rank）か（デフォルト: drop）"
+    })
+}
+
+/// `nostr:` 参照の展開（`resolve_references`）プロパティのスキーマ
+fn resolve_references_property() -> Value {
+    json!({
+        "type": "boolean",
+        "description": "本文中の nostr: 参照（npub、nprofile、note、nevent、naddr）が指すプロフィールやノートを取得し、表示名や本文プレビューを parsed_content.references に埋め込む（デフォルト: false）"
+    })
+}
+
+/// リンクプレビューの取得（`link_previews`）プロパティのスキーマ
+fn link_previews_property() -> Value {
+    json!({
+        "type": "boolean",
+        "description": "本文中のメディア以外のリンクの OpenGraph 情報（タイトル・説明・画像）を取得し、parsed_content.link_previews に埋め込む（デフォルト: false）"
+    })
+}
+
+/// コンパクト出力（`compact`）プロパティのスキーマ
+fn compact_property() -> Value {
+    json!({
+        "type": "boolean",
+        "description": "true の場合、display_card と parsed_content を省いてトークン量を減らします（デフォルト: false）"
+    })
+}
+
+/// 本文の切り詰め（`truncate_content`）プロパティのスキーマ
+fn truncate_content_property() -> Value {
+    json!({
+        "type": "integer",
+        "description": "本文をこの文字数で切り詰め、content_truncated: true を付けます（0 で切り詰めない、省略時は設定ファイルの content-max-chars）"
+    })
+}
+
+/// ノートのフィールド絞り込み（`fields`）プロパティのスキーマ
+fn fields_property() -> Value {
+    json!({
+        "type": "array",
+        "items": { "type": "string" },
+        "description": "各ノートに残すフィールド（例: [\"id\", \"author.display\", \"content\"]）。ドット区切りで入れ子のキーを指定でき、指定しないフィールドは省きます（省略時はすべて）"
+    })
+}
+
+/// 出力形式（`output_format`）プロパティのスキーマ
+fn output_format_property() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["json", "markdown"],
+        "description": "結果の形式。markdown を指定すると読みやすい Markdown で返します（デフォルト: json）"
+    })
+}
+
+/// 記事本文を含めるか（`include_content`）プロパティのスキーマ
+fn include_content_property() -> Value {
+    json!({
+        "type": "boolean",
+        "description": "記事の本文を含める（デフォルト: true）。false の場合は読了時間（reading_time_minutes）と目次（toc）で概要を把握できます"
+    })
+}
+
 /// 利用可能なツールのリストを返します。
 /// `ui_enabled` が `true` の場合、MCP Apps UI メタデータを含めます。
 pub fn get_tool_definitions(ui_enabled: bool) -> Vec<ToolDefinition> {
@@ -189,899 +720,4278 @@ pub fn get_tool_definitions(ui_enabled: bool) -> Vec<ToolDefinition> {
         }
     };
 
-    vec![
+    let mut tools = vec![
         // 既存ツール
         ToolDefinition {
             name: "post_nostr_note".to_string(),
-            description: "Nostr ネットワークにショートテキストノート (Kind 1) を投稿します。書き込みアクセスが必要です（~/.config/rust-nostr-mcp/config.json に秘密鍵を設定）。".to_string(),
+            description: "Nostr ネットワークにショートテキストノート (Kind 1) を投稿します。本文の :shortcode: は自分の絵文字リスト・絵文字セットのカスタム絵文字 (NIP-30) になります。書き込みアクセスが必要です（~/.config/rust-nostr-mcp/config.json に秘密鍵を設定）。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "content": {
                         "type": "string",
                         "description": "投稿するノートのテキスト内容"
-                    }
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
                 },
                 "required": ["content"]
             }),
             meta: meta("post_nostr_note"),
         },
+        ToolDefinition {
+            name: "post_nostr_thread".to_string(),
+            description: "長文を番号付きの連続したノート (Kind 1) のスレッドとして投稿します。content を段落・文末で自動分割するか、segments で区切りを指定します。2 件目以降は NIP-10 の root / reply タグで連結し、すべてのイベント ID を順番に返します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "content": {
+                        "type": "string",
+                        "description": "スレッドに分割して投稿する本文（segments を指定しない場合は必須）"
+                    },
+                    "segments": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "各ノートの本文を順番に指定（指定した場合は content より優先し、分割しない）"
+                    },
+                    "max_length": {
+                        "type": "number",
+                        "description": "content を分割するときの 1 ノートあたりの最大文字数（番号を含む、デフォルト: 280、範囲: 50〜5000）"
+                    },
+                    "numbered": {
+                        "type": "boolean",
+                        "description": "各ノートの末尾に「(1/3)」形式の番号を付ける（デフォルト: true）"
+                    }
+                }
+            }),
+            meta: meta("post_nostr_thread"),
+        },
         ToolDefinition {
             name: "get_nostr_timeline".to_string(),
-            description: "Nostr タイムラインから最新のノートを著者情報付きで取得します。認証済みの場合はフォロー中のユーザーのノート、それ以外はグローバルタイムラインを返します。".to_string(),
+            description: "Nostr タイムラインから最新のノートを著者情報付きで取得します。認証済みの場合はフォロー中のユーザーのノート、それ以外はグローバルタイムラインを返します。リポスト (Kind 6) は元のノートに置き換え、リポストした著者を reposted_by に含めます。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "limit": {
                         "type": "number",
-                        "description": "取得するノートの最大数（デフォルト: 20、最大: 100）"
-                    }
+                        "description": "取得するノートの最大数（デフォルト: 20、最大: 500。リレーの応答件数の上限を超える分は複数回に分けて取得）"
+                    },
+                    "include_status": {
+                        "type": "boolean",
+                        "description": "著者の現在のステータス（NIP-38）を各ノートに付与する（デフォルト: false）"
+                    },
+                    "refresh_contacts": {
+                        "type": "boolean",
+                        "description": "キャッシュ済みのフォローリストを破棄してリレーから再取得する（デフォルト: false）"
+                    },
+                    "include_replies": {
+                        "type": "boolean",
+                        "description": "リプライ（NIP-10 の root / reply マーカー付き、またはマーカーのない e タグを持つノート）を含める。false でスレッドの起点となる投稿のみ（引用は残す。デフォルト: true）"
+                    },
+                    "languages": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "表示する言語（ISO 639-1、例: [\"ja\", \"en\"]）。NIP-32 の言語ラベルまたは本文から判定し、判定できないノートは残す。空の配列で絞り込みなし（デフォルト: 設定ファイルの languages）"
+                    },
+                    "trust_hops": trust_hops_property(),
+                    "trust_mode": trust_mode_property(),
+                    "min_pow": {
+                        "type": "number",
+                        "description": "グローバルタイムライン（未認証またはフォローなし）で要求する最小 PoW 難易度（NIP-13、nonce タグで判定）。0 で無効（デフォルト: 設定ファイルの min-pow）"
+                    },
+                    "resolve_references": resolve_references_property(),
+                    "link_previews": link_previews_property(),
+                    "compact": compact_property(),
+                    "truncate_content": truncate_content_property(),
+                    "fields": fields_property(),
+                    "output_format": output_format_property()
                 }
             }),
             meta: meta("get_nostr_timeline"),
         },
         ToolDefinition {
-            name: "search_nostr_notes".to_string(),
-            description: "NIP-50 検索対応リレーを使用して、指定キーワードを含むノートを検索します。著者情報付きで結果を返します。".to_string(),
+            name: "get_timeline_digest".to_string(),
+            description: "タイムラインの直近のノートをまとめて取得し、ノート本文の代わりに集計結果（ノート数、投稿の多い著者、よく使われたハッシュタグ、リアクションの多いノート）を返します。少ないトークンで全体の動向を把握するのに使用します。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "query": {
-                        "type": "string",
-                        "description": "検索クエリ文字列"
+                    "hours": {
+                        "type": "number",
+                        "description": "集計する期間（時間、デフォルト: 24、最大: 168）"
                     },
-                    "limit": {
+                    "window": {
                         "type": "number",
-                        "description": "結果の最大数（デフォルト: 20、最大: 100）"
+                        "description": "集計に使う最大ノート数（デフォルト: 200、最大: 500）"
+                    },
+                    "top": {
+                        "type": "number",
+                        "description": "各ランキングの件数（デフォルト: 5、最大: 20）"
                     }
-                },
-                "required": ["query"]
+                }
             }),
-            meta: meta("search_nostr_notes"),
+            meta: meta("get_timeline_digest"),
         },
         ToolDefinition {
-            name: "get_nostr_profile".to_string(),
-            description: "公開鍵（npub または hex 形式）で Nostr ユーザーのプロフィール情報を取得します。name、display_name、about、picture、banner、nip05、lud16、website を返します。".to_string(),
+            name: "get_hashtag_stats".to_string(),
+            description: "ハッシュタグ付きのノートを集計し、直近の期間とその前の同じ長さの期間の投稿数と増減率、日ごとの投稿数、よく投稿したアカウント、一緒に使われたハッシュタグを返します。「今週 #nostr の投稿は増えている？」のような質問に使用します。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "pubkey": {
+                    "hashtag": {
                         "type": "string",
-                        "description": "npub (bech32) または hex 形式の公開鍵"
+                        "description": "集計するハッシュタグ（# は省略可）"
+                    },
+                    "days": {
+                        "type": "number",
+                        "description": "比較する期間の長さ（日数、デフォルト: 7、最大: 30）。直近の期間とその前の期間を集計します"
+                    },
+                    "window": {
+                        "type": "number",
+                        "description": "集計に使う最大ノート数（デフォルト: 500、最大: 2000）"
+                    },
+                    "top": {
+                        "type": "number",
+                        "description": "各ランキングの件数（デフォルト: 5、最大: 20）"
                     }
                 },
-                "required": ["pubkey"]
+                "required": ["hashtag"]
             }),
-            meta: meta("get_nostr_profile"),
+            meta: meta("get_hashtag_stats"),
         },
-        // Phase 1: NIP-23 長文コンテンツツール
         ToolDefinition {
-            name: "post_nostr_article".to_string(),
-            description: "Nostr ネットワークに長文記事 (Kind 30023, NIP-23) を投稿します。Markdown コンテンツをサポートします。書き込みアクセスが必要です。".to_string(),
+            name: "add_watch_account".to_string(),
+            description: "アカウントをローカルのウォッチリストに追加します。自分のコンタクトリスト（フォロー）とは独立しており、フォローせずに競合・ニュース・特定の開発者などの活動を追跡できます。登録済みの場合はラベルを更新します。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "title": {
-                        "type": "string",
-                        "description": "記事のタイトル"
-                    },
-                    "content": {
-                        "type": "string",
-                        "description": "Markdown 形式の記事本文"
-                    },
-                    "summary": {
-                        "type": "string",
-                        "description": "記事の要約（任意）"
-                    },
-                    "image": {
+                    "pubkey": {
                         "type": "string",
-                        "description": "ヘッダー画像の URL（任意）"
-                    },
-                    "tags": {
-                        "type": "array",
-                        "items": { "type": "string" },
-                        "description": "トピックハッシュタグ（任意）"
-                    },
-                    "published_at": {
-                        "type": "number",
-                        "description": "公開日時の Unix タイムスタンプ（任意、未指定時は現在時刻）"
+                        "description": "追加するアカウントの公開鍵（npub、nprofile または hex）"
                     },
-                    "identifier": {
+                    "label": {
                         "type": "string",
-                        "description": "記事の識別子（d タグ、任意。未指定時はタイトルから自動生成）"
+                        "description": "用途のメモ（任意、例: \"競合\"、\"ニュース\"）"
                     }
                 },
-                "required": ["title", "content"]
+                "required": ["pubkey"]
             }),
-            meta: meta("post_nostr_article"),
+            meta: meta("add_watch_account"),
         },
         ToolDefinition {
-            name: "get_nostr_articles".to_string(),
-            description: "Nostr ネットワークから長文記事 (Kind 30023, NIP-23) を取得します。著者やハッシュタグでフィルタリングできます。".to_string(),
+            name: "remove_watch_account".to_string(),
+            description: "アカウントをローカルのウォッチリストから削除します。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "author": {
+                    "pubkey": {
                         "type": "string",
-                        "description": "著者の公開鍵でフィルタ（npub または hex 形式、任意）"
-                    },
-                    "tags": {
-                        "type": "array",
-                        "items": { "type": "string" },
-                        "description": "ハッシュタグでフィルタ（任意）"
-                    },
-                    "limit": {
-                        "type": "number",
-                        "description": "取得する記事の最大数（デフォルト: 20、最大: 100）"
+                        "description": "削除するアカウントの公開鍵（npub、nprofile または hex）"
                     }
-                }
+                },
+                "required": ["pubkey"]
             }),
-            meta: meta("get_nostr_articles"),
+            meta: meta("remove_watch_account"),
         },
         ToolDefinition {
-            name: "save_nostr_draft".to_string(),
-            description: "記事を下書き (Kind 30024) として Nostr に保存します。後で編集・公開できます。書き込みアクセスが必要です。".to_string(),
+            name: "add_contact_alias".to_string(),
+            description: "ローカルのアドレス帳に別名（ペットネーム）を登録します。登録後は、公開鍵を受け取るすべてのツールで npub の代わりに \"alice\" のような別名を指定できます。登録済みの別名は公開鍵とメモを上書きします。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "title": {
-                        "type": "string",
-                        "description": "記事のタイトル"
-                    },
-                    "content": {
-                        "type": "string",
-                        "description": "Markdown 形式の記事本文"
-                    },
-                    "summary": {
+                    "alias": {
                         "type": "string",
-                        "description": "記事の要約（任意）"
+                        "description": "別名（大文字小文字を区別しない、空白・@・:・/ を含まない 64 文字以内）"
                     },
-                    "image": {
+                    "pubkey": {
                         "type": "string",
-                        "description": "ヘッダー画像の URL（任意）"
-                    },
-                    "tags": {
-                        "type": "array",
-                        "items": { "type": "string" },
-                        "description": "トピックハッシュタグ（任意）"
+                        "description": "別名を付けるアカウントの公開鍵（npub、nprofile または hex）"
                     },
-                    "identifier": {
+                    "note": {
                         "type": "string",
-                        "description": "記事の識別子（d タグ、任意。未指定時はタイトルから自動生成）"
+                        "description": "メモ（任意）"
                     }
                 },
-                "required": ["title", "content"]
+                "required": ["alias", "pubkey"]
             }),
-            meta: meta("save_nostr_draft"),
+            meta: meta("add_contact_alias"),
         },
         ToolDefinition {
-            name: "get_nostr_drafts".to_string(),
-            description: "自分の下書き記事 (Kind 30024) を取得します。認証が必要です（秘密鍵が設定されている必要があります）。".to_string(),
+            name: "remove_contact_alias".to_string(),
+            description: "ローカルのアドレス帳から別名を削除します。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "limit": {
-                        "type": "number",
-                        "description": "取得する下書きの最大数（デフォルト: 20、最大: 100）"
+                    "alias": {
+                        "type": "string",
+                        "description": "削除する別名"
                     }
-                }
+                },
+                "required": ["alias"]
             }),
-            meta: meta("get_nostr_drafts"),
+            meta: meta("remove_contact_alias"),
         },
-        // Phase 2: タイムライン拡張機能
         ToolDefinition {
-            name: "get_nostr_thread".to_string(),
-            description: "ノートのスレッド（リプライツリー）を取得します。指定したノートとそのリプライを階層構造で返します。".to_string(),
+            name: "resolve_alias".to_string(),
+            description: "ローカルのアドレス帳で別名が指す公開鍵とプロフィールを返します。alias を省略すると登録済みの別名をすべて返します。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "note_id": {
+                    "alias": {
                         "type": "string",
-                        "description": "対象ノートのイベント ID（hex、nevent、note 形式対応）"
-                    },
-                    "depth": {
-                        "type": "number",
-                        "description": "取得するリプライの深さ（デフォルト: 3、最大: 10）"
+                        "description": "調べる別名（任意、省略時はすべて）"
                     }
-                },
-                "required": ["note_id"]
+                }
             }),
-            meta: meta("get_nostr_thread"),
+            meta: meta("resolve_alias"),
         },
         ToolDefinition {
-            name: "react_to_note".to_string(),
-            description: "ノートにリアクション (Kind 7, NIP-25) を送信します。デフォルトは「+」（いいね）です。書き込みアクセスが必要です。".to_string(),
+            name: "get_watched_feeds".to_string(),
+            description: "ウォッチリストに登録したアカウントの最近のノートをまとめて取得します。各アカウントのラベル・投稿数・最終投稿日時も返します。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "note_id": {
-                        "type": "string",
-                        "description": "リアクション対象のイベント ID（hex、nevent、note 形式対応）"
+                    "limit": {
+                        "type": "number",
+                        "description": "取得するノートの最大数（デフォルト: 20、最大: 100）"
                     },
-                    "reaction": {
-                        "type": "string",
-                        "description": "リアクション文字（デフォルト: \"+\"、絵文字も可）"
-                    }
-                },
-                "required": ["note_id"]
+                    "hours": {
+                        "type": "number",
+                        "description": "直近何時間のノートに限定するか（任意、最大: 168）"
+                    },
+                    "resolve_references": resolve_references_property(),
+                    "link_previews": link_previews_property(),
+                    "compact": compact_property(),
+                    "truncate_content": truncate_content_property(),
+                    "fields": fields_property(),
+                    "output_format": output_format_property()
+                }
             }),
-            meta: meta("react_to_note"),
+            meta: meta("get_watched_feeds"),
         },
         ToolDefinition {
-            name: "reply_to_note".to_string(),
-            description: "既存のノートに返信を投稿します（NIP-10 スレッディング対応）。書き込みアクセスが必要です。".to_string(),
+            name: "search_nostr_notes".to_string(),
+            description: "NIP-50 検索対応リレーを使用して、指定キーワードを含むノートを検索します。著者情報付きで結果を返します。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "note_id": {
+                    "query": {
                         "type": "string",
-                        "description": "返信先のイベント ID（hex、nevent、note 形式対応）"
+                        "description": "検索クエリ文字列"
                     },
-                    "content": {
-                        "type": "string",
-                        "description": "返信のテキスト内容"
-                    }
+                    "limit": {
+                        "type": "number",
+                        "description": "結果の最大数（デフォルト: 20、最大: 500。リレーの応答件数の上限を超える分は複数回に分けて取得）"
+                    },
+                    "languages": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "表示する言語（ISO 639-1、例: [\"ja\", \"en\"]）。NIP-32 の言語ラベルまたは本文から判定し、判定できないノートは残す。空の配列で絞り込みなし（デフォルト: 設定ファイルの languages）"
+                    },
+                    "trust_hops": trust_hops_property(),
+                    "trust_mode": trust_mode_property(),
+                    "min_pow": {
+                        "type": "number",
+                        "description": "要求する最小 PoW 難易度（NIP-13、nonce タグで判定）。オープンリレーのスパム除外に使用。0 で無効（デフォルト: 設定ファイルの min-pow）"
+                    },
+                    "resolve_references": resolve_references_property(),
+                    "link_previews": link_previews_property(),
+                    "compact": compact_property(),
+                    "truncate_content": truncate_content_property(),
+                    "fields": fields_property(),
+                    "output_format": output_format_property()
                 },
-                "required": ["note_id", "content"]
+                "required": ["query"]
             }),
-            meta: meta("reply_to_note"),
+            meta: meta("search_nostr_notes"),
         },
         ToolDefinition {
-            name: "get_nostr_notifications".to_string(),
-            description: "自分のノートへのメンションやリアクションを取得します。認証が必要です。".to_string(),
+            name: "get_nostr_notes".to_string(),
+            description: "複数のイベント ID のノートを 1 回の取得でまとめて取得します。著者情報・リアクション数・リプライ数付きで指定順に返し、見つからなかった ID は not_found に含めます。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "since": {
-                        "type": "number",
-                        "description": "この Unix タイムスタンプ以降の通知のみ取得（任意）"
+                    "ids": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "取得するノートのイベント ID のリスト（hex、note、nevent、naddr 形式対応、最大 100 件）"
                     },
-                    "limit": {
-                        "type": "number",
-                        "description": "取得する通知の最大数（デフォルト: 20、最大: 100）"
-                    }
-                }
+                    "resolve_references": resolve_references_property(),
+                    "link_previews": link_previews_property(),
+                    "compact": compact_property(),
+                    "truncate_content": truncate_content_property(),
+                    "fields": fields_property(),
+                    "output_format": output_format_property()
+                },
+                "required": ["ids"]
             }),
-            meta: meta("get_nostr_notifications"),
+            meta: meta("get_nostr_notes"),
         },
-        // Phase 4: 高度な機能
         ToolDefinition {
-            name: "send_zap".to_string(),
-            description: "ノートまたはプロフィールに Lightning Zap (NIP-57) を送信します。NWC (Nostr Wallet Connect) の設定が必要です。書き込みアクセスが必要です。".to_string(),
+            name: "get_nostr_profiles".to_string(),
+            description: "複数のユーザーのプロフィール（name、display_name、about、picture、banner、nip05、lud16、website）を 1 回の取得でまとめて取得します。キャッシュ済みのプロフィールはリレーに問い合わせません。見つからなかったユーザーは not_found に含めます。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "target": {
-                        "type": "string",
-                        "description": "Zap 対象のイベント ID（hex、nevent、note 形式）または公開鍵（npub または hex 形式）"
-                    },
-                    "amount": {
-                        "type": "number",
-                        "description": "sats 単位の金額"
+                    "pubkeys": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "公開鍵のリスト（npub、nprofile または hex 形式、最大 100 件）"
                     },
-                    "comment": {
-                        "type": "string",
-                        "description": "Zap コメント（任意）"
-                    }
+                    "output_format": output_format_property()
                 },
-                "required": ["target", "amount"]
+                "required": ["pubkeys"]
             }),
-            meta: meta("send_zap"),
+            meta: meta("get_nostr_profiles"),
         },
         ToolDefinition {
-            name: "get_zap_receipts".to_string(),
-            description: "ノートの Zap レシート (Kind 9735, NIP-57) を取得します。送信者・金額・コメント情報付きで返します。".to_string(),
+            name: "get_nostr_profile".to_string(),
+            description: "公開鍵（npub、nprofile または hex 形式）で Nostr ユーザーのプロフィール情報を取得します。name、display_name、about、picture、banner、nip05、lud16、website を返します。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "note_id": {
+                    "pubkey": {
                         "type": "string",
-                        "description": "対象ノートのイベント ID（hex、nevent、note 形式対応）"
+                        "description": "npub / nprofile (bech32) または hex 形式の公開鍵"
                     },
-                    "limit": {
-                        "type": "number",
-                        "description": "取得するレシートの最大数（デフォルト: 20、最大: 100）"
-                    }
+                    "include_stats": {
+                        "type": "boolean",
+                        "description": "フォロー数・フォロワー数・ノート数の統計を取得する（デフォルト: true、false で高速なプロフィールのみの取得）"
+                    },
+                    "output_format": output_format_property()
                 },
-                "required": ["note_id"]
+                "required": ["pubkey"]
             }),
-            meta: meta("get_zap_receipts"),
+            meta: meta("get_nostr_profile"),
         },
         ToolDefinition {
-            name: "send_dm".to_string(),
-            description: "暗号化されたダイレクトメッセージ (NIP-04) を送信します。書き込みアクセスが必要です。".to_string(),
+            name: "create_nostr_account".to_string(),
+            description: "新しい鍵ペアを生成して設定ファイルの privatekey に保存し、サーバーの署名者を新しいアカウントに切り替えます。初期プロフィール (Kind 0) と、設定中の read / write リレーのリレーリスト (NIP-65, Kind 10002) を公開します。秘密鍵は返しません。既に秘密鍵が設定されている場合は replace_existing_key: true が必要です（元の設定ファイルはバックアップされます）。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "recipient": {
+                    "name": {
                         "type": "string",
-                        "description": "受信者の公開鍵（npub または hex 形式）"
+                        "description": "ユーザー名（プロフィールの name）"
                     },
-                    "content": {
+                    "display_name": {
                         "type": "string",
-                        "description": "メッセージ内容"
+                        "description": "表示名"
+                    },
+                    "about": {
+                        "type": "string",
+                        "description": "自己紹介文"
+                    },
+                    "picture": {
+                        "type": "string",
+                        "description": "プロフィール画像の URL"
+                    },
+                    "website": {
+                        "type": "string",
+                        "description": "ウェブサイトの URL"
+                    },
+                    "lud16": {
+                        "type": "string",
+                        "description": "Zap を受け取る Lightning アドレス"
+                    },
+                    "replace_existing_key": {
+                        "type": "boolean",
+                        "description": "設定ファイルの既存の秘密鍵を新しい鍵で置き換える（元の設定ファイルは config.json.<時刻>.bak に保存。デフォルト: false）"
                     }
                 },
-                "required": ["recipient", "content"]
+                "required": ["name"]
             }),
-            meta: meta("send_dm"),
+            meta: meta("create_nostr_account"),
         },
         ToolDefinition {
-            name: "get_dms".to_string(),
-            description: "暗号化されたダイレクトメッセージ (NIP-04) の会話を取得・復号します。認証が必要です。".to_string(),
+            name: "migrate_account".to_string(),
+            description: "現在のアカウントから新しい鍵への移行をまとめて行います。new_secret_key_file（新しい秘密鍵を書いたファイル）を指定するとプロフィール・コンタクトリスト・リレーリスト・NIP-51 のリスト（非公開項目は新しい鍵宛てに再暗号化）を新しい鍵で公開し直し、現在のアカウントのプロフィールに移行先を追記して、移行を案内するノートを投稿します。notify_followers でフォロワーに DM でも通知できます。失敗した手順は errors に含め、残りの手順を続けます。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "with": {
+                    "new_pubkey": {
                         "type": "string",
-                        "description": "会話相手の公開鍵（npub または hex 形式）でフィルタ（任意）"
+                        "description": "移行先の公開鍵（npub / nprofile / hex）。new_secret_key_file を指定する場合は省略可"
                     },
-                    "limit": {
+                    "new_secret_key_file": {
+                        "type": "string",
+                        "description": "移行先の秘密鍵（nsec / hex）を書いたファイルのパス。指定するとリスト等を新しい鍵にコピーする（秘密鍵は引数では受け付けない）"
+                    },
+                    "message": {
+                        "type": "string",
+                        "description": "移行を案内するノートと DM の本文（移行先の nostr:npub が含まれていなければ末尾に追加。デフォルト: 定型文）"
+                    },
+                    "notify_followers": {
                         "type": "number",
-                        "description": "取得する最大メッセージ数（デフォルト: 20、最大: 100）"
+                        "description": "DM で移行を通知するフォロワーの最大数（デフォルト: 0 で通知しない、最大: 100）"
                     }
                 }
             }),
-            meta: meta("get_dms"),
+            meta: meta("migrate_account"),
         },
         ToolDefinition {
-            name: "get_relay_list".to_string(),
-            description: "ユーザーのリレーリスト (Kind 10002, NIP-65) を取得します。各リレーの読み書き設定を返します。".to_string(),
+            name: "check_follow_relationship".to_string(),
+            description: "2 つのアカウントのフォロー関係（A→B、B→A、相互フォロー）をコンタクトリスト (Kind 3) から判定します。知らない相手に DM や Zap を送る前の確認に使用します。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "pubkey": {
+                    "pubkey_a": {
+                        "type": "string",
+                        "description": "アカウント A の公開鍵（npub、nprofile または hex 形式、省略時は自分）"
+                    },
+                    "pubkey_b": {
                         "type": "string",
-                        "description": "npub (bech32) または hex 形式の公開鍵"
+                        "description": "アカウント B の公開鍵（npub、nprofile または hex 形式）"
                     }
                 },
-                "required": ["pubkey"]
+                "required": ["pubkey_b"]
             }),
-            meta: meta("get_relay_list"),
+            meta: meta("check_follow_relationship"),
         },
-        // Phase 6: NIP-46 Nostr Connect（リモートサイニング）
         ToolDefinition {
-            name: "nostr_connect".to_string(),
-            description: "NIP-46 Nostr Connect を使用してリモートサイナー（Primal、Amber 等）との接続を開始します。QR コードを生成し、スキャンすることでログインできます。bunker:// URI を指定することもできます。".to_string(),
+            name: "recommend_follows".to_string(),
+            description: "フォロー中のアカウントのコンタクトリスト (Kind 3) を分析し、自分がまだフォローしていないアカウントを、フォロー中の何人にフォローされているか（重なり）の多い順におすすめします。ミュート中のアカウントは除外します。公開鍵の設定が必要です。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "bunker_uri": {
-                        "type": "string",
-                        "description": "bunker:// URI（任意。指定しない場合は QR コードを生成）"
+                    "limit": {
+                        "type": "number",
+                        "description": "返すおすすめの最大数（デフォルト: 20、最大: 100）"
+                    },
+                    "min_overlap": {
+                        "type": "number",
+                        "description": "おすすめに含めるのに必要な、そのアカウントをフォローしている自分のフォローの最小人数（デフォルト: 2）"
                     }
                 }
             }),
-            meta: meta("nostr_connect"),
-        },
-        ToolDefinition {
-            name: "nostr_connect_status".to_string(),
-            description: "NIP-46 リモートサイナーの接続状態を確認します。".to_string(),
-            input_schema: json!({
-                "type": "object",
-                "properties": {}
-            }),
-            meta: meta("nostr_connect_status"),
+            meta: meta("recommend_follows"),
         },
         ToolDefinition {
-            name: "nostr_disconnect".to_string(),
-            description: "NIP-46 リモートサイナーとの接続を切断します。".to_string(),
+            name: "search_nostr_users".to_string(),
+            description: "名前や NIP-05 識別子で Nostr ユーザーを検索します。NIP-50 検索対応リレーでプロフィール (Kind 0) を検索し、キャッシュ済みプロフィールとも照合して一致度順に返します。npub がわからない相手を探すのに使用します。".to_string(),
             input_schema: json!({
                 "type": "object",
-                "properties": {}
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "検索する名前・表示名・NIP-05 識別子"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "結果の最大数（デフォルト: 20、最大: 100）"
+                    }
+                },
+                "required": ["query"]
             }),
-            meta: meta("nostr_disconnect"),
+            meta: meta("search_nostr_users"),
         },
-        // NIP-B7: Blossom メディアアップロード
+        // Phase 1: NIP-23 長文コンテンツツール
         ToolDefinition {
-            name: "upload_media".to_string(),
-            description: "Blossom サーバーにメディアファイルをアップロードします (NIP-B7, BUD-02)。アップロード後の URL を返します。書き込みアクセスが必要です。".to_string(),
+            name: "post_nostr_article".to_string(),
+            description: "Nostr ネットワークに長文記事 (Kind 30023, NIP-23) を投稿します。Markdown コンテンツをサポートします。書き込みアクセスが必要です。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "file_path": {
+                    "title": {
                         "type": "string",
-                        "description": "アップロードするローカルファイルのパス（file_path または data のいずれかが必須）"
+                        "description": "記事のタイトル"
                     },
-                    "data": {
+                    "content": {
                         "type": "string",
-                        "description": "Base64 エンコードされたファイルデータ（file_path または data のいずれかが必須）"
+                        "description": "Markdown 形式の記事本文"
                     },
-                    "content_type": {
+                    "summary": {
                         "type": "string",
-                        "description": "ファイルの MIME タイプ（任意、拡張子から自動推測）"
+                        "description": "記事の要約（任意）"
                     },
-                    "server": {
+                    "image": {
                         "type": "string",
-                        "description": "Blossom サーバー URL（任意、未指定時はユーザーのサーバーリストまたはデフォルトを使用）"
+                        "description": "ヘッダー画像の URL（任意）"
                     },
-                    "filename": {
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "トピックハッシュタグ（任意）"
+                    },
+                    "published_at": {
+                        "type": "number",
+                        "description": "公開日時の Unix タイムスタンプ（任意、未指定時は現在時刻）"
+                    },
+                    "identifier": {
                         "type": "string",
-                        "description": "ファイル名（data 使用時の MIME タイプ推測用、任意）"
-                    }
-                }
+                        "description": "記事の識別子（d タグ、任意。未指定時はタイトルから自動生成）"
+                    },
+                    "timestamp": {
+                        "type": "boolean",
+                        "description": "公開後に OpenTimestamps 証明（NIP-03）の作成を開始するか（デフォルト: false）。Bitcoin での確定後に timestamp_event でアテステーションを公開します"
+                    },
+                    "announce": {
+                        "type": "boolean",
+                        "description": "true の場合、タイトル・要約・nostr:naddr 参照を含む紹介ノート (Kind 1) も投稿し、長文記事に対応していないクライアントのフォロワーにも届ける（デフォルト: false）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["title", "content"]
             }),
-            meta: meta("upload_media"),
+            meta: meta("post_nostr_article"),
         },
         ToolDefinition {
-            name: "get_blossom_servers".to_string(),
-            description: "ユーザーの Blossom サーバーリスト (Kind 10063, NIP-B7) を取得します。メディアアップロード先として使用されるサーバーの一覧を返します。".to_string(),
+            name: "get_nostr_articles".to_string(),
+            description: "Nostr ネットワークから長文記事 (Kind 30023, NIP-23) を取得します。著者やハッシュタグでフィルタリングできます。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "pubkey": {
+                    "author": {
                         "type": "string",
-                        "description": "npub (bech32) または hex 形式の公開鍵（任意、未指定時は自分のリスト）"
-                    }
+                        "description": "著者の公開鍵でフィルタ（npub、nprofile または hex 形式、任意）"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "ハッシュタグでフィルタ（任意）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "取得する記事の最大数（デフォルト: 20、最大: 100）"
+                    },
+                    "resolve_references": resolve_references_property(),
+                    "link_previews": link_previews_property(),
+                    "include_content": include_content_property(),
+                    "output_format": output_format_property()
                 }
             }),
-            meta: meta("get_blossom_servers"),
+            meta: meta("get_nostr_articles"),
         },
         ToolDefinition {
-            name: "set_blossom_servers".to_string(),
-            description: "Blossom サーバーリスト (Kind 10063, NIP-B7) を公開します。メディアアップロードに使用するサーバーを設定します。書き込みアクセスが必要です。".to_string(),
+            name: "search_nostr_articles".to_string(),
+            description: "NIP-50 検索対応リレーを使用して、指定キーワードを含む長文記事 (Kind 30023) を検索します。著者情報付きで結果を返します。".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "servers": {
+                    "query": {
+                        "type": "string",
+                        "description": "検索クエリ文字列"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "結果の最大数（デフォルト: 20、最大: 100）"
+                    },
+                    "resolve_references": resolve_references_property(),
+                    "link_previews": link_previews_property(),
+                    "include_content": include_content_property(),
+                    "output_format": output_format_property()
+                },
+                "required": ["query"]
+            }),
+            meta: meta("search_nostr_articles"),
+        },
+        ToolDefinition {
+            name: "save_nostr_draft".to_string(),
+            description: "記事を下書き (Kind 30024) として Nostr に保存します。後で編集・公開できます。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "記事のタイトル"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "Markdown 形式の記事本文"
+                    },
+                    "summary": {
+                        "type": "string",
+                        "description": "記事の要約（任意）"
+                    },
+                    "image": {
+                        "type": "string",
+                        "description": "ヘッダー画像の URL（任意）"
+                    },
+                    "tags": {
                         "type": "array",
                         "items": { "type": "string" },
-                        "description": "Blossom サーバー URL のリスト（例: [\"https://blossom.primal.net\"]）"
+                        "description": "トピックハッシュタグ（任意）"
+                    },
+                    "identifier": {
+                        "type": "string",
+                        "description": "記事の識別子（d タグ、任意。未指定時はタイトルから自動生成）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["title", "content"]
+            }),
+            meta: meta("save_nostr_draft"),
+        },
+        ToolDefinition {
+            name: "get_nostr_drafts".to_string(),
+            description: "自分の下書き記事 (Kind 30024) を取得します。認証が必要です（秘密鍵が設定されている必要があります）。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "limit": {
+                        "type": "number",
+                        "description": "取得する下書きの最大数（デフォルト: 20、最大: 100）"
+                    },
+                    "include_content": include_content_property(),
+                    "output_format": output_format_property()
+                }
+            }),
+            meta: meta("get_nostr_drafts"),
+        },
+        ToolDefinition {
+            name: "save_local_draft".to_string(),
+            description: "記事の下書きをローカル（データディレクトリ）に保存します。リレーには送信されないため、公開するまで内容が外部に出ません。同じ識別子の下書きは上書きされます。認証が必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "記事のタイトル"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "Markdown 形式の記事本文"
+                    },
+                    "summary": {
+                        "type": "string",
+                        "description": "記事の要約（任意）"
+                    },
+                    "image": {
+                        "type": "string",
+                        "description": "ヘッダー画像の URL（任意）"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "トピックハッシュタグ（任意）"
+                    },
+                    "identifier": {
+                        "type": "string",
+                        "description": "記事の識別子（d タグ、任意。未指定時はタイトルから自動生成）"
                     }
                 },
-                "required": ["servers"]
+                "required": ["title", "content"]
             }),
-            meta: meta("set_blossom_servers"),
+            meta: meta("save_local_draft"),
         },
-    ]
-}
+        ToolDefinition {
+            name: "list_local_drafts".to_string(),
+            description: "ローカルに保存した記事の下書きを更新日時の新しい順に一覧表示します。認証が必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "include_content": {
+                        "type": "boolean",
+                        "description": "本文全体を含める（デフォルト: false、プレビューのみ）"
+                    }
+                }
+            }),
+            meta: meta("list_local_drafts"),
+        },
+        ToolDefinition {
+            name: "publish_local_draft".to_string(),
+            description: "ローカルの下書きを長文記事 (Kind 30023) として公開し、ローカルから削除します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "identifier": {
+                        "type": "string",
+                        "description": "公開する下書きの識別子"
+                    },
+                    "published_at": {
+                        "type": "number",
+                        "description": "公開日時の Unix タイムスタンプ（任意、デフォルト: 現在時刻）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["identifier"]
+            }),
+            meta: meta("publish_local_draft"),
+        },
+        ToolDefinition {
+            name: "create_publication".to_string(),
+            description: "複数の長文記事をまとめるシリーズを、NIP-51 のキュレーションセット (Kind 30004) として作成します。articles の順番がシリーズ内の順番になります。連載など、時間をかけて書き進める複数回の記事に使用します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "シリーズのタイトル"
+                    },
+                    "identifier": {
+                        "type": "string",
+                        "description": "シリーズの識別子（d タグ、任意。未指定時はタイトルから自動生成）"
+                    },
+                    "description": {
+                        "type": "string",
+                        "description": "シリーズの説明（任意）"
+                    },
+                    "image": {
+                        "type": "string",
+                        "description": "カバー画像の URL（任意）"
+                    },
+                    "articles": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "最初に含める記事のアドレス（任意、naddr または 30023:<pubkey>:<d> 形式、順番どおり）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["title"]
+            }),
+            meta: meta("create_publication"),
+        },
+        ToolDefinition {
+            name: "add_article_to_publication".to_string(),
+            description: "自分のシリーズ (Kind 30004) に長文記事を追加します。position で位置を指定でき（省略時は末尾）、既に含まれている記事は position の位置に移動します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "publication": {
+                        "type": "string",
+                        "description": "シリーズ（naddr、30004:<pubkey>:<d> 形式、または自分のシリーズの識別子）"
+                    },
+                    "article": {
+                        "type": "string",
+                        "description": "追加する記事のアドレス（naddr または 30023:<pubkey>:<d> 形式）"
+                    },
+                    "position": {
+                        "type": "integer",
+                        "description": "シリーズ内の位置（任意、1 始まり。省略時は末尾）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["publication", "article"]
+            }),
+            meta: meta("add_article_to_publication"),
+        },
+        ToolDefinition {
+            name: "get_publication".to_string(),
+            description: "シリーズ (Kind 30004) を取得し、含まれる長文記事を順番どおりに返します。取得できなかった記事は found: false になります。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "publication": {
+                        "type": "string",
+                        "description": "シリーズ（naddr、30004:<pubkey>:<d> 形式、または自分のシリーズの識別子）"
+                    },
+                    "include_content": {
+                        "type": "boolean",
+                        "description": "記事の本文全体を含める（デフォルト: false、プレビューのみ）"
+                    }
+                },
+                "required": ["publication"]
+            }),
+            meta: meta("get_publication"),
+        },
+        ToolDefinition {
+            name: "get_article_history".to_string(),
+            description: "自分の長文記事 (Kind 30023) のバージョン履歴を返します。記事は置き換え可能イベントのため、公開のたびにローカルに保存した版と、リレー上の最新版を古い順に番号付きで一覧します。article を省略すると履歴のある記事の一覧を返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "article": {
+                        "type": "string",
+                        "description": "記事（自分の記事の識別子、naddr または 30023:<pubkey>:<d> 形式。任意）"
+                    }
+                }
+            }),
+            meta: meta("get_article_history"),
+        },
+        ToolDefinition {
+            name: "diff_article_versions".to_string(),
+            description: "自分の長文記事の 2 つのバージョンを比較し、本文の行単位の差分とタイトル・要約・画像・ハッシュタグの変更を返します。省略時は直前のバージョンと最新のバージョンを比較します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "article": {
+                        "type": "string",
+                        "description": "記事（自分の記事の識別子、naddr または 30023:<pubkey>:<d> 形式）"
+                    },
+                    "from": {
+                        "type": "integer",
+                        "description": "比較元のバージョン番号（任意、get_article_history の番号。負の値は最新から数え、-1 が最新）"
+                    },
+                    "to": {
+                        "type": "integer",
+                        "description": "比較先のバージョン番号（任意、デフォルト: -1 = 最新）"
+                    }
+                },
+                "required": ["article"]
+            }),
+            meta: meta("diff_article_versions"),
+        },
+        // Phase 2: タイムライン拡張機能
+        ToolDefinition {
+            name: "get_nostr_thread".to_string(),
+            description: "ノートのスレッド（リプライツリー）を取得します。指定したノートとそのリプライを階層構造で返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "note_id": {
+                        "type": "string",
+                        "description": "対象ノートのイベント ID（hex、note、nevent、naddr 形式対応）"
+                    },
+                    "depth": {
+                        "type": "number",
+                        "description": "取得するリプライの深さ（デフォルト: 3、最大: 10）"
+                    },
+                    "trust_hops": trust_hops_property(),
+                    "trust_mode": trust_mode_property(),
+                    "resolve_references": resolve_references_property(),
+                    "link_previews": link_previews_property(),
+                    "compact": compact_property(),
+                    "truncate_content": truncate_content_property(),
+                    "fields": fields_property(),
+                    "output_format": output_format_property()
+                },
+                "required": ["note_id"]
+            }),
+            meta: meta("get_nostr_thread"),
+        },
+        ToolDefinition {
+            name: "get_reply_context".to_string(),
+            description: "ノートへのリプライを書くための文脈をまとめて取得します。対象のノート、スレッドのルートノートと直接の親ノート（NIP-10）、著者の最近のノートのうち関連するもの（同じスレッド・対象への返信・共通のハッシュタグ）を 1 回で返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "note_id": {
+                        "type": "string",
+                        "description": "対象ノートのイベント ID（hex、note、nevent 形式対応）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "返す著者の関連ノートの最大数（デフォルト: 5、最大: 20）"
+                    },
+                    "compact": compact_property(),
+                    "truncate_content": truncate_content_property(),
+                    "fields": fields_property(),
+                    "output_format": output_format_property()
+                },
+                "required": ["note_id"]
+            }),
+            meta: meta("get_reply_context"),
+        },
+        ToolDefinition {
+            name: "reveal_note".to_string(),
+            description: "ノートの本文を設定ファイルの sensitive-content に関係なく取得します。コンテンツ警告（NIP-36）や NSFW ラベルにより本文がプレースホルダーに置き換えられたノート（content_hidden: true）を明示的に表示する場合に使用します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "note_id": {
+                        "type": "string",
+                        "description": "対象ノートのイベント ID（hex、note、nevent、naddr 形式対応）"
+                    }
+                },
+                "required": ["note_id"]
+            }),
+            meta: meta("reveal_note"),
+        },
+        ToolDefinition {
+            name: "react_to_note".to_string(),
+            description: "ノートにリアクション (Kind 7, NIP-25) を送信します。デフォルトは「+」（いいね、設定ファイルの default-reaction で変更可）です。設定ファイルのリアクションのプリセット名（get_reaction_presets で確認）も指定できます。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "note_id": {
+                        "type": "string",
+                        "description": "リアクション対象のイベント ID（hex、note、nevent、naddr 形式対応）"
+                    },
+                    "reaction": {
+                        "type": "string",
+                        "description": "リアクション文字（デフォルト: 設定ファイルの default-reaction または \"+\"、絵文字も可。:shortcode: 形式で自分の絵文字セットのカスタム絵文字、プリセット名でプリセットの内容）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["note_id"]
+            }),
+            meta: meta("react_to_note"),
+        },
+        ToolDefinition {
+            name: "get_reaction_presets".to_string(),
+            description: "設定ファイルのリアクションのプリセット（名前・内容・1 時間あたりの上限と直近 1 時間の使用回数）と、reaction 省略時のデフォルトのリアクションを取得します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+            meta: meta("get_reaction_presets"),
+        },
+        ToolDefinition {
+            name: "reply_to_note".to_string(),
+            description: "既存のノートに返信を投稿します（NIP-10 スレッディング対応）。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "note_id": {
+                        "type": "string",
+                        "description": "返信先のイベント ID（hex、note、nevent、naddr 形式対応）"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "返信のテキスト内容"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["note_id", "content"]
+            }),
+            meta: meta("reply_to_note"),
+        },
+        ToolDefinition {
+            name: "get_nostr_notifications".to_string(),
+            description: "自分宛ての通知（メンション・リアクション・Zap・リポスト・新規フォロワー）を取得します。デフォルトでは前回 mark_notifications_read で既読にして以降の新着のみ返します。認証が必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "since": {
+                        "type": "number",
+                        "description": "この Unix タイムスタンプ以降の通知のみ取得（任意、指定時は既読カーソルより優先）"
+                    },
+                    "include_read": {
+                        "type": "boolean",
+                        "description": "既読の通知も含めて取得する（デフォルト: false）"
+                    },
+                    "types": {
+                        "type": "array",
+                        "items": {
+                            "type": "string",
+                            "enum": ["mention", "reaction", "zap", "repost", "follow"]
+                        },
+                        "description": "取得する通知の種類（デフォルト: すべて）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "取得する通知の最大数（デフォルト: 20、最大: 100）"
+                    },
+                    "trust_hops": trust_hops_property(),
+                    "trust_mode": trust_mode_property()
+                }
+            }),
+            meta: meta("get_nostr_notifications"),
+        },
+        ToolDefinition {
+            name: "mark_notifications_read".to_string(),
+            description: "通知を既読にします。既読日時はローカルに保存され、サーバー再起動後も get_nostr_notifications の新着判定に使用されます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "until": {
+                        "type": "number",
+                        "description": "この Unix タイムスタンプまでの通知を既読にする（デフォルト: 現在時刻）"
+                    }
+                }
+            }),
+            meta: meta("mark_notifications_read"),
+        },
+        ToolDefinition {
+            name: "get_note_reposts".to_string(),
+            description: "ノートのリポスト (Kind 6, NIP-18) と引用ノート（q タグまたは nevent 参照を含む Kind 1）を著者情報付きで取得します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "note_id": {
+                        "type": "string",
+                        "description": "対象ノートのイベント ID（hex、note、nevent、naddr 形式対応）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "リポスト・引用それぞれの最大取得数（デフォルト: 20、最大: 100）"
+                    },
+                    "resolve_references": resolve_references_property(),
+                    "link_previews": link_previews_property(),
+                    "compact": compact_property(),
+                    "truncate_content": truncate_content_property(),
+                    "fields": fields_property()
+                },
+                "required": ["note_id"]
+            }),
+            meta: meta("get_note_reposts"),
+        },
+        ToolDefinition {
+            name: "get_note_engagement".to_string(),
+            description: "ノートのエンゲージメント（リアクション数・リプライ数・リポスト数・引用数・Zap 件数と合計金額）を 1 回の呼び出しでまとめて取得します。NIP-45 (COUNT) 対応リレーでは COUNT を使用します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "note_id": {
+                        "type": "string",
+                        "description": "対象ノートのイベント ID（hex、note、nevent、naddr 形式対応）"
+                    }
+                },
+                "required": ["note_id"]
+            }),
+            meta: meta("get_note_engagement"),
+        },
+        ToolDefinition {
+            name: "get_account_analytics".to_string(),
+            description: "アカウントの指定期間の投稿と反応を集計します。投稿頻度（日別・時間帯別、リプライの割合）、受け取ったリアクション・リプライ・リポスト・Zap の合計、反応の多いノート、よく反応してくれるアカウント（フォロワーかどうか付き）を返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pubkey": {
+                        "type": "string",
+                        "description": "npub / nprofile (bech32) または hex 形式の公開鍵（任意、未指定時は自分）"
+                    },
+                    "days": {
+                        "type": "number",
+                        "description": "集計期間（日数、デフォルト: 30、最大: 365）"
+                    },
+                    "top": {
+                        "type": "number",
+                        "description": "反応の多いノートとよく反応してくれるアカウントの件数（デフォルト: 5、最大: 20）"
+                    }
+                }
+            }),
+            meta: meta("get_account_analytics"),
+        },
+        ToolDefinition {
+            name: "get_follower_growth".to_string(),
+            description: "自分のフォロワー数の推移と、期間内に増えた・減ったフォロワーを返します。フォロワー数はローカルに定期的に記録したスナップショット（呼び出し時にも記録）に基づき、増減はコンタクトリスト (Kind 3) の差分から判定します。公開鍵の設定が必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "days": {
+                        "type": "number",
+                        "description": "集計期間（日数、デフォルト: 30、最大: 365）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "返す増えた・減ったフォロワーのそれぞれの最大数（デフォルト: 20、最大: 100）"
+                    }
+                }
+            }),
+            meta: meta("get_follower_growth"),
+        },
+        ToolDefinition {
+            name: "get_lightning_invoice".to_string(),
+            description: "Lightning アドレス (LUD-16)、LNURL (LUD-06)、またはユーザーのプロフィールの Lightning アドレスからインボイス (bolt11) を取得します。NWC がなくても、外部ウォレットで支払うためのインボイスを受け取れます。受取人が公開鍵で指定され認証済みの場合は Zap リクエストを添付します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "target": {
+                        "type": "string",
+                        "description": "Lightning アドレス（name@domain）、LNURL（lnurl1...）、または受取人の公開鍵（npub / nprofile / hex）"
+                    },
+                    "amount": {
+                        "type": "number",
+                        "description": "金額（sats）"
+                    },
+                    "comment": {
+                        "type": "string",
+                        "description": "コメント（任意）"
+                    },
+                    "note_id": {
+                        "type": "string",
+                        "description": "Zap 対象のノート ID（任意、hex、note、nevent、naddr 形式対応）"
+                    },
+                    "zap": {
+                        "type": "boolean",
+                        "description": "可能な場合に Zap リクエストを添付する（デフォルト: true）"
+                    }
+                },
+                "required": ["target", "amount"]
+            }),
+            meta: meta("get_lightning_invoice"),
+        },
+        ToolDefinition {
+            name: "decode_lightning_invoice".to_string(),
+            description: "Lightning インボイス (bolt11) をデコードし、金額・説明・説明のハッシュ・支払いハッシュ・作成日時・有効期限を返します。支払い前の確認や、Zap レシートの bolt11 の確認に使えます。署名は検証しません。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "invoice": {
+                        "type": "string",
+                        "description": "bolt11 インボイス（lnbc...、lightning: プレフィックス可）"
+                    }
+                },
+                "required": ["invoice"]
+            }),
+            meta: meta("decode_lightning_invoice"),
+        },
+        ToolDefinition {
+            name: "pay_lightning_address".to_string(),
+            description: "Lightning アドレス (LUD-16) や LNURL、またはユーザーのプロフィールの Lightning アドレスからインボイスを取得し、設定済みの NWC ウォレットで支払います。send_zap と同じ支払い上限（payment-limits）が適用されます。NWC 設定が必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "target": {
+                        "type": "string",
+                        "description": "Lightning アドレス（name@domain）、LNURL（lnurl1...）、または受取人の公開鍵（npub / nprofile / hex）"
+                    },
+                    "amount": {
+                        "type": "number",
+                        "description": "金額（sats）"
+                    },
+                    "comment": {
+                        "type": "string",
+                        "description": "コメント（任意）"
+                    },
+                    "note_id": {
+                        "type": "string",
+                        "description": "Zap 対象のノート ID（任意、hex、note、nevent、naddr 形式対応）"
+                    }
+                },
+                "required": ["target", "amount"]
+            }),
+            meta: meta("pay_lightning_address"),
+        },
+        ToolDefinition {
+            name: "check_wallet_connection".to_string(),
+            description: "設定済みの NWC (Nostr Wallet Connect, NIP-47) ウォレットとの接続を確認します。NWC URI のリレーへの到達性、ウォレットサービスが対応するメソッド、get_info で取得したこの接続に許可されたメソッド、残高（許可されている場合）、このサーバーの支払い上限と直近 24 時間の使用額を返します。NWC 設定が必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+            meta: meta("check_wallet_connection"),
+        },
+        ToolDefinition {
+            name: "send_dm".to_string(),
+            description: "暗号化されたダイレクトメッセージ (NIP-04) を送信します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "recipient": {
+                        "type": "string",
+                        "description": "受信者の公開鍵（npub、nprofile または hex 形式）"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "メッセージ内容"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["recipient", "content"]
+            }),
+            meta: meta("send_dm"),
+        },
+        ToolDefinition {
+            name: "get_dms".to_string(),
+            description: "暗号化されたダイレクトメッセージ (NIP-04) の会話を取得・復号します。認証が必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "with": {
+                        "type": "string",
+                        "description": "会話相手の公開鍵（npub、nprofile または hex 形式）でフィルタ（任意）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "取得する最大メッセージ数（デフォルト: 20、最大: 100）"
+                    }
+                }
+            }),
+            meta: meta("get_dms"),
+        },
+        ToolDefinition {
+            name: "get_dm_conversations".to_string(),
+            description: "ダイレクトメッセージ (NIP-04) を会話相手ごとにまとめ、最新メッセージのプレビュー・日時・未読数を返します。受信箱の整理に使用します。認証が必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "limit": {
+                        "type": "number",
+                        "description": "取得する最大会話数（デフォルト: 20、最大: 100）"
+                    },
+                    "unread_only": {
+                        "type": "boolean",
+                        "description": "未読メッセージのある会話のみ返す（デフォルト: false）"
+                    }
+                }
+            }),
+            meta: meta("get_dm_conversations"),
+        },
+        ToolDefinition {
+            name: "mark_dms_read".to_string(),
+            description: "ダイレクトメッセージを既読にします。既読状態はローカルに保存され、get_dm_conversations の未読数に反映されます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "with": {
+                        "type": "string",
+                        "description": "既読にする会話相手の公開鍵（npub、nprofile または hex 形式、省略時はすべての会話）"
+                    }
+                }
+            }),
+            meta: meta("mark_dms_read"),
+        },
+        ToolDefinition {
+            name: "export_my_events".to_string(),
+            description: "自分が作成したすべてのイベント（ノート・記事・リスト・プロフィール等）をリレーから取得し、JSONL ファイル（1 行 1 イベント、署名済み）に書き出します。アカウントのバックアップに使用します。認証が必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "書き出し先のファイルパス（省略時はデータディレクトリの backups/<npub>-<日時>.jsonl）"
+                    },
+                    "kinds": {
+                        "type": "array",
+                        "items": { "type": "number" },
+                        "description": "対象の Kind（省略時はすべて）"
+                    },
+                    "max_events": {
+                        "type": "number",
+                        "description": "書き出す最大イベント数（デフォルト: 10000、最大: 100000）"
+                    },
+                    "overwrite": {
+                        "type": "boolean",
+                        "description": "書き出し先のファイルが既にある場合に上書きするか（デフォルト: false）"
+                    }
+                }
+            }),
+            meta: meta("export_my_events"),
+        },
+        ToolDefinition {
+            name: "import_events".to_string(),
+            description: "JSONL バックアップ（export_my_events の出力など、署名済みイベント）を読み込み、署名を検証して設定ファイルの書き込み有効なリレーに再送信します。リレーの移行やアカウントの復旧に使用します。イベントは署名済みのため秘密鍵は不要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "読み込む JSONL ファイルのパス"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "検証のみ行い、リレーには送信しない（デフォルト: false）"
+                    }
+                },
+                "required": ["path"]
+            }),
+            meta: meta("import_events"),
+        },
+        ToolDefinition {
+            name: "rebroadcast_events".to_string(),
+            description: "自分の最近のイベントを指定したリレーに再送信します。新しく参加したリレーに過去の投稿・プロフィール・リストをコピーするのに使用します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "relay": {
+                        "type": "string",
+                        "description": "送信先のリレー URL（wss://...）"
+                    },
+                    "kinds": {
+                        "type": "array",
+                        "items": { "type": "number" },
+                        "description": "対象の Kind（省略時はすべて）"
+                    },
+                    "since": {
+                        "type": "number",
+                        "description": "この Unix タイムスタンプ以降のイベントのみ対象（任意）"
+                    },
+                    "until": {
+                        "type": "number",
+                        "description": "この Unix タイムスタンプ以前のイベントのみ対象（任意）"
+                    },
+                    "max_events": {
+                        "type": "number",
+                        "description": "再送信する最大イベント数（新しい順、デフォルト: 100、最大: 1000）"
+                    }
+                },
+                "required": ["relay"]
+            }),
+            meta: meta("rebroadcast_events"),
+        },
+        ToolDefinition {
+            name: "timestamp_event".to_string(),
+            description: "イベントの OpenTimestamps 証明を作成し、Bitcoin での確定後にアテステーション (Kind 1040, NIP-03) を公開します。確定には数時間かかるため、初回は証明の作成のみ行います。時間をおいて再度呼び出すと証明をアップグレードして公開します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "event_id": {
+                        "type": "string",
+                        "description": "対象のイベント ID（hex / note1 / nevent1 / naddr1 形式）"
+                    }
+                },
+                "required": ["event_id"]
+            }),
+            meta: meta("timestamp_event"),
+        },
+        ToolDefinition {
+            name: "get_pending_publishes".to_string(),
+            description: "どのリレーにも送信できず、送信待ちキューに保存されているイベントの一覧を取得します。キューのイベントはバックグラウンドで指数バックオフしながら再送されます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+            meta: meta("get_pending_publishes"),
+        },
+        ToolDefinition {
+            name: "flush_pending".to_string(),
+            description: "送信待ちキューのイベントを再送時刻を待たずにすぐ再送します。discard を true にすると送信せずにキューから削除します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "event_ids": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "対象のイベント ID（hex、note、nevent）。省略時はキューのすべてのイベント"
+                    },
+                    "discard": {
+                        "type": "boolean",
+                        "description": "true の場合は再送せずにキューから削除（デフォルト: false）"
+                    }
+                }
+            }),
+            meta: meta("flush_pending"),
+        },
+        ToolDefinition {
+            name: "start_subscription".to_string(),
+            description: "通知・DM・タイムライン・ライブチャットのリアルタイム購読を開始します。開始以降に届いたイベントがサーバー側にバッファされ、get_subscription_events で取り出せます。同じ条件の購読は共有されます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "type": {
+                        "type": "string",
+                        "enum": SUBSCRIPTION_TYPES,
+                        "description": "購読の種類（notifications / dms / timeline は認証が必要、live_chat は target が必要）"
+                    },
+                    "target": {
+                        "type": "string",
+                        "description": "live_chat の場合のライブアクティビティのアドレス（naddr 形式）"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "購読名（省略時は種類名。同じ名前の購読は置き換えられます）"
+                    }
+                },
+                "required": ["type"]
+            }),
+            meta: meta("start_subscription"),
+        },
+        ToolDefinition {
+            name: "get_subscription_events".to_string(),
+            description: "購読でバッファされたイベントを古い順に取り出します。取り出したイベントはバッファから削除されます。DM は復号済み、ミュート対象は除外されます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "購読名"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "取り出す最大イベント数（デフォルト: 20、最大: 100）"
+                    }
+                },
+                "required": ["name"]
+            }),
+            meta: meta("get_subscription_events"),
+        },
+        ToolDefinition {
+            name: "stop_subscription".to_string(),
+            description: "購読を停止し、バッファを破棄します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "購読名"
+                    }
+                },
+                "required": ["name"]
+            }),
+            meta: meta("stop_subscription"),
+        },
+        ToolDefinition {
+            name: "list_subscriptions".to_string(),
+            description: "有効な購読の一覧と、未取得のイベント数・EOSE 受信状況を返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+            meta: meta("list_subscriptions"),
+        },
+        ToolDefinition {
+            name: "get_relay_list".to_string(),
+            description: "ユーザーのリレーリスト (Kind 10002, NIP-65) を取得します。各リレーの読み書き設定を返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pubkey": {
+                        "type": "string",
+                        "description": "npub / nprofile (bech32) または hex 形式の公開鍵"
+                    }
+                },
+                "required": ["pubkey"]
+            }),
+            meta: meta("get_relay_list"),
+        },
+        ToolDefinition {
+            name: "benchmark_relays".to_string(),
+            description: "リレーごとに接続時間・標準フィルタ（最新のテキストノート 50 件）での取得時間・取得件数を計測し、速い順に並べたレポートを返します。NIP-11 から検索（NIP-50）と COUNT（NIP-45）への対応も確認します。リレーの選定に使用します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "relays": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "計測するリレーの URL（任意、最大 20 件。省略時は接続中のリレープール）"
+                    }
+                }
+            }),
+            meta: meta("benchmark_relays"),
+        },
+        ToolDefinition {
+            name: "discover_relays".to_string(),
+            description: "NIP-66 のリレーモニターが公開するリレー発見イベント (Kind 30166) から、正常に稼働しているリレーを探します。対応 NIP（検索の 50、COUNT の 45 など）・国・ジオハッシュで絞り込み、稼働率の目安（availability）と RTT の良い順に返します。add_to_pool を指定すると上位のリレーをこのセッションのリレープールに追加します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "nips": {
+                        "type": "array",
+                        "items": { "type": "integer" },
+                        "description": "すべてに対応している必要がある NIP の番号（任意、例: [50, 45]）"
+                    },
+                    "country": {
+                        "type": "string",
+                        "description": "国コード（任意、ISO 3166-1 alpha-2、例: JP）"
+                    },
+                    "geohash": {
+                        "type": "string",
+                        "description": "ジオハッシュの接頭辞（任意、例: xn）"
+                    },
+                    "network": {
+                        "type": "string",
+                        "description": "ネットワーク（任意、clearnet・tor・i2p など）"
+                    },
+                    "open_only": {
+                        "type": "boolean",
+                        "description": "認証（NIP-42）や支払いが必要なリレーを除外する（任意、デフォルト: false）"
+                    },
+                    "min_availability": {
+                        "type": "number",
+                        "description": "稼働率の目安の下限（任意、0〜1、デフォルト: 0）"
+                    },
+                    "monitors": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "レポートを信頼するモニターの公開鍵（任意、npub または hex。省略時は告知 (Kind 10166) を公開しているモニター）"
+                    },
+                    "monitor_relays": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "モニターのレポートを取得するリレー（任意。省略時は wss://relay.nostr.watch など）"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "返すリレーの最大数（任意、デフォルト: 20、最大: 100）"
+                    },
+                    "add_to_pool": {
+                        "type": "integer",
+                        "description": "上位から何件のリレーを読み取りリレーとしてリレープールに追加するか（任意、デフォルト: 0。設定ファイルは変更しません）"
+                    }
+                }
+            }),
+            meta: meta("discover_relays"),
+        },
+        // NIP-38: ユーザーステータス
+        ToolDefinition {
+            name: "set_user_status".to_string(),
+            description: "自分のステータス (Kind 30315, NIP-38) を設定します。「記事を執筆中」のような一般ステータスや再生中の音楽を表示できます。空の content でステータスをクリアします。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "content": {
+                        "type": "string",
+                        "description": "ステータス本文（空文字でクリア）"
+                    },
+                    "status_type": {
+                        "type": "string",
+                        "enum": ["general", "music"],
+                        "description": "ステータス種別（デフォルト: general）"
+                    },
+                    "url": {
+                        "type": "string",
+                        "description": "関連リンク（任意）"
+                    },
+                    "expires_in": {
+                        "type": "number",
+                        "description": "ステータスの有効期間（秒、任意。未指定時は無期限）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["content"]
+            }),
+            meta: meta("set_user_status"),
+        },
+        ToolDefinition {
+            name: "get_user_status".to_string(),
+            description: "ユーザーの現在のステータス (Kind 30315, NIP-38) を取得します。失効済みのステータスは除外されます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pubkey": {
+                        "type": "string",
+                        "description": "npub、nprofile または hex 形式の公開鍵（任意、未指定時は自分）"
+                    },
+                    "pubkeys": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "複数ユーザーをまとめて取得する場合の公開鍵リスト（任意）"
+                    }
+                }
+            }),
+            meta: meta("get_user_status"),
+        },
+        // NIP-58: バッジ
+        ToolDefinition {
+            name: "get_user_badges".to_string(),
+            description: "ユーザーがプロフィールに表示しているバッジ (Kind 30008, NIP-58) を、バッジ定義と授与の検証結果付きで取得します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pubkey": {
+                        "type": "string",
+                        "description": "npub / nprofile (bech32) または hex 形式の公開鍵"
+                    }
+                },
+                "required": ["pubkey"]
+            }),
+            meta: meta("get_user_badges"),
+        },
+        ToolDefinition {
+            name: "get_badge_definition".to_string(),
+            description: "バッジ定義 (Kind 30009, NIP-58) を取得します。名前・説明・画像・発行者を返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "badge": {
+                        "type": "string",
+                        "description": "バッジ定義のアドレス（naddr または 30009:<pubkey>:<d> 形式）"
+                    }
+                },
+                "required": ["badge"]
+            }),
+            meta: meta("get_badge_definition"),
+        },
+        ToolDefinition {
+            name: "award_badge".to_string(),
+            description: "自分が作成したバッジをユーザーに授与します (Kind 8, NIP-58)。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "badge": {
+                        "type": "string",
+                        "description": "バッジ定義のアドレス（naddr または 30009:<pubkey>:<d> 形式）"
+                    },
+                    "recipients": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "授与先ユーザーの公開鍵リスト（npub、nprofile または hex 形式）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["badge", "recipients"]
+            }),
+            meta: meta("award_badge"),
+        },
+        // NIP-30 / NIP-51: カスタム絵文字
+        ToolDefinition {
+            name: "get_emoji_sets".to_string(),
+            description: "絵文字リスト (Kind 10030) と絵文字セット (Kind 30030) を取得します。作成した絵文字セットに加え、絵文字リストで参照している他のユーザーの絵文字セットも返します。ここに含まれる絵文字は、投稿・返信・リアクションで :shortcode: と書くと自動的にカスタム絵文字 (NIP-30) になります。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pubkey": {
+                        "type": "string",
+                        "description": "取得するユーザーの公開鍵（任意、未指定時は自分）"
+                    }
+                }
+            }),
+            meta: meta("get_emoji_sets"),
+        },
+        ToolDefinition {
+            name: "add_emoji".to_string(),
+            description: "自分の絵文字セット (Kind 30030) にカスタム絵文字を追加します。同じショートコードは画像 URL を置き換え、セットがなければ作成して絵文字リスト (Kind 10030) にも登録します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "set": {
+                        "type": "string",
+                        "description": "絵文字セットの識別子（d タグ、例: \"cats\"）"
+                    },
+                    "shortcode": {
+                        "type": "string",
+                        "description": "ショートコード（英数字・_・-、例: \"pepe\"）"
+                    },
+                    "url": {
+                        "type": "string",
+                        "description": "絵文字の画像 URL"
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "絵文字セットのタイトル（任意、指定時は更新）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["set", "shortcode", "url"]
+            }),
+            meta: meta("add_emoji"),
+        },
+        ToolDefinition {
+            name: "remove_emoji".to_string(),
+            description: "自分の絵文字セット (Kind 30030) からカスタム絵文字を削除します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "set": {
+                        "type": "string",
+                        "description": "絵文字セットの識別子（d タグ）"
+                    },
+                    "shortcode": {
+                        "type": "string",
+                        "description": "削除する絵文字のショートコード"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["set", "shortcode"]
+            }),
+            meta: meta("remove_emoji"),
+        },
+        // NIP-71: 動画
+        ToolDefinition {
+            name: "get_video_feed".to_string(),
+            description: "動画イベント (Kind 21/22, NIP-71) を新しい順に取得します。動画 URL・サムネイル・再生時間・解像度ごとのバリエーション（imeta）を返します。認証済みの場合はデフォルトでフォロー中ユーザーの動画に限定します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "video_type": {
+                        "type": "string",
+                        "enum": ["all", "normal", "short"],
+                        "description": "動画の種類（normal: Kind 21、short: Kind 22、デフォルト: all）"
+                    },
+                    "follows_only": {
+                        "type": "boolean",
+                        "description": "フォロー中ユーザーの動画のみ取得する（デフォルト: true、未認証時は無視）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "取得する動画数（デフォルト: 20、最大: 100）"
+                    }
+                }
+            }),
+            meta: meta("get_video_feed"),
+        },
+        ToolDefinition {
+            name: "post_video".to_string(),
+            description: "動画イベント (Kind 21/22, NIP-71) を投稿します。公開済みの動画 URL を指定するか、file_path / data を指定すると Blossom サーバーにアップロードしてから投稿します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "動画のタイトル"
+                    },
+                    "description": {
+                        "type": "string",
+                        "description": "動画の説明（任意）"
+                    },
+                    "url": {
+                        "type": "string",
+                        "description": "公開済みの動画 URL（file_path / data を指定しない場合に必須）"
+                    },
+                    "file_path": {
+                        "type": "string",
+                        "description": "アップロードする動画ファイルのローカルパス（任意）"
+                    },
+                    "data": {
+                        "type": "string",
+                        "description": "Base64 エンコードされた動画データ（任意）"
+                    },
+                    "filename": {
+                        "type": "string",
+                        "description": "data 指定時のファイル名（MIME タイプの推測に使用、任意）"
+                    },
+                    "content_type": {
+                        "type": "string",
+                        "description": "動画の MIME タイプ（例: video/mp4、任意）"
+                    },
+                    "server": {
+                        "type": "string",
+                        "description": "アップロード先の Blossom サーバー URL（任意）"
+                    },
+                    "thumbnail": {
+                        "type": "string",
+                        "description": "サムネイル画像 URL（任意）"
+                    },
+                    "duration": {
+                        "type": "number",
+                        "description": "再生時間（秒、任意）"
+                    },
+                    "dimensions": {
+                        "type": "string",
+                        "description": "解像度（例: 1920x1080、任意）"
+                    },
+                    "short": {
+                        "type": "boolean",
+                        "description": "縦型のショート動画 (Kind 22) として投稿する（デフォルト: false）"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "ハッシュタグのリスト（任意）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["title"]
+            }),
+            meta: meta("post_video"),
+        },
+        // NIP-56: 通報
+        ToolDefinition {
+            name: "report_content".to_string(),
+            description: "ユーザーまたはノートを通報します (Kind 1984, NIP-56)。通報種別（spam、illegal、impersonation、nudity 等）と任意の理由を指定できます。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pubkey": {
+                        "type": "string",
+                        "description": "通報対象ユーザーの公開鍵（npub、nprofile または hex 形式。note_id 指定時は省略可）"
+                    },
+                    "note_id": {
+                        "type": "string",
+                        "description": "通報対象ノートのイベント ID（hex、note、nevent、naddr 形式対応、任意）"
+                    },
+                    "report_type": {
+                        "type": "string",
+                        "enum": ["spam", "illegal", "impersonation", "nudity", "profanity", "malware", "other"],
+                        "description": "通報種別"
+                    },
+                    "reason": {
+                        "type": "string",
+                        "description": "通報理由（任意）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["report_type"]
+            }),
+            meta: meta("report_content"),
+        },
+        // Phase 6: NIP-46 Nostr Connect（リモートサイニング）
+        ToolDefinition {
+            name: "nostr_connect".to_string(),
+            description: "NIP-46 Nostr Connect を使用してリモートサイナー（Primal、Amber 等）との接続を開始します。QR コードを生成し、スキャンすることでログインできます。bunker:// URI を指定することもできます。label を変えると複数のサイナー（個人用・プロジェクト用など）を同時に接続でき、接続が完了したサイナーが署名に使われます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "bunker_uri": {
+                        "type": "string",
+                        "description": "bunker:// URI（任意。指定しない場合は QR コードを生成）"
+                    },
+                    "label": {
+                        "type": "string",
+                        "description": "サイナーの名前（英数字・'-'・'_'、32 文字以内。デフォルト: default）"
+                    }
+                }
+            }),
+            meta: meta("nostr_connect"),
+        },
+        ToolDefinition {
+            name: "nostr_connect_status".to_string(),
+            description: "NIP-46 リモートサイナーの接続状態を確認します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "label": {
+                        "type": "string",
+                        "description": "確認するサイナーの名前（任意。デフォルト: アクティブなサイナー）"
+                    }
+                }
+            }),
+            meta: meta("nostr_connect_status"),
+        },
+        ToolDefinition {
+            name: "nostr_disconnect".to_string(),
+            description: "NIP-46 リモートサイナーとの接続を切断します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "label": {
+                        "type": "string",
+                        "description": "切断するサイナーの名前（任意。デフォルト: アクティブなサイナー）"
+                    }
+                }
+            }),
+            meta: meta("nostr_disconnect"),
+        },
+        ToolDefinition {
+            name: "list_signers".to_string(),
+            description: "接続中の NIP-46 リモートサイナーを一覧表示します。各サイナーの名前・接続状態・npub と、署名に使用中のサイナーを返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+            meta: meta("list_signers"),
+        },
+        ToolDefinition {
+            name: "set_active_signer".to_string(),
+            description: "投稿などの署名に使う NIP-46 リモートサイナーを切り替えます。接続済みのサイナーのみ指定できます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "label": {
+                        "type": "string",
+                        "description": "切り替え先のサイナーの名前（list_signers で確認）"
+                    }
+                },
+                "required": ["label"]
+            }),
+            meta: meta("set_active_signer"),
+        },
+        ToolDefinition {
+            name: "get_signer_info".to_string(),
+            description: "現在の署名者（ローカル秘密鍵 / NIP-46 リモートサイナー / NIP-26 委任 / なし）と公開鍵、署名できる Kind、NIP-04/NIP-44 暗号化の可否を返します。書き込み系ツールを使う前の確認に使えます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+            meta: meta("get_signer_info"),
+        },
+        // NIP-B7: Blossom メディアアップロード
+        ToolDefinition {
+            name: "upload_media".to_string(),
+            description: "Blossom サーバーにメディアファイルをアップロードします (NIP-B7, BUD-02)。アップロード後の URL を返します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "アップロードするローカルファイルのパス（file_path または data のいずれかが必須）"
+                    },
+                    "data": {
+                        "type": "string",
+                        "description": "Base64 エンコードされたファイルデータ（file_path または data のいずれかが必須）"
+                    },
+                    "content_type": {
+                        "type": "string",
+                        "description": "ファイルの MIME タイプ（任意、拡張子から自動推測）"
+                    },
+                    "server": {
+                        "type": "string",
+                        "description": "Blossom サーバー URL（任意、未指定時はユーザーのサーバーリストまたはデフォルトを使用）"
+                    },
+                    "filename": {
+                        "type": "string",
+                        "description": "ファイル名（data 使用時の MIME タイプ推測用、任意）"
+                    }
+                }
+            }),
+            meta: meta("upload_media"),
+        },
+        ToolDefinition {
+            name: "get_blossom_servers".to_string(),
+            description: "ユーザーの Blossom サーバーリスト (Kind 10063, NIP-B7) を取得します。メディアアップロード先として使用されるサーバーの一覧を返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pubkey": {
+                        "type": "string",
+                        "description": "npub / nprofile (bech32) または hex 形式の公開鍵（任意、未指定時は自分のリスト）"
+                    }
+                }
+            }),
+            meta: meta("get_blossom_servers"),
+        },
+        ToolDefinition {
+            name: "set_blossom_servers".to_string(),
+            description: "Blossom サーバーリスト (Kind 10063, NIP-B7) を公開します。メディアアップロードに使用するサーバーを設定します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "servers": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Blossom サーバー URL のリスト（例: [\"https://blossom.primal.net\"]）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["servers"]
+            }),
+            meta: meta("set_blossom_servers"),
+        },
+    ];
+    tools.extend(zaps::tool_definitions(&meta));
+    tools.extend(calendar::tool_definitions(&meta));
+    tools.extend(wiki::tool_definitions(&meta));
+    tools.extend(live::tool_definitions(&meta));
+    tools.extend(polls::tool_definitions(&meta));
+    tools
+}
+
+/// ツール呼び出しを処理するエグゼキュータ
+pub struct ToolExecutor {
+    /// Nostr クライアントインスタンス（NIP-46 切り替えのため RwLock で保護）
+    client: Arc<tokio::sync::RwLock<NostrClient>>,
+    /// NIP-46 セッション（Phase 6、ラベルごとに複数保持）
+    nip46_registry: Arc<Nip46Registry>,
+    /// ノート一覧を返すツールの出力量の設定
+    output: OutputOptions,
+}
+
+impl ToolExecutor {
+    /// 新しいツールエグゼキュータを作成
+    pub fn new(
+        client: Arc<tokio::sync::RwLock<NostrClient>>,
+        nip46_registry: Arc<Nip46Registry>,
+        output: OutputOptions,
+    ) -> Self {
+        Self {
+            client,
+            nip46_registry,
+            output,
+        }
+    }
+
+    /// limit パラメータを抽出するヘルパー（省略時は設定ファイルの `default-limit`、上限は MAX_LIMIT）
+    fn extract_limit(&self, arguments: &Value) -> u64 {
+        self.extract_limit_up_to(arguments, MAX_LIMIT)
+    }
+
+    /// 上限を指定して limit パラメータを抽出するヘルパー（下限は 1）
+    fn extract_limit_up_to(&self, arguments: &Value, max: u64) -> u64 {
+        arguments
+            .get("limit")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or(self.output.default_limit.unwrap_or(DEFAULT_LIMIT))
+            .clamp(1, max)
+    }
+
+    /// 指定されたツールを引数付きで実行します。
+    pub async fn execute(&self, name: &str, arguments: Value) -> Result<Value> {
+        info!("ツール実行: {} 引数: {}", name, arguments);
+
+        let shape = NOTE_OUTPUT_TOOLS.contains(&name).then(|| self.note_output_shape(&arguments));
+        let dry_run_requested = arguments.get("dry_run").and_then(|v| v.as_bool()).unwrap_or(false);
+        let result = if dry_run_requested || self.client.read().await.dry_run() {
+            let (result, events) = crate::dry_run::scope(self.dispatch(name, arguments)).await;
+            result.map(|mut value| {
+                if dry_run_requested || !events.is_empty() {
+                    attach_dry_run_events(&mut value, &events);
+                }
+                value
+            })
+        } else {
+            self.dispatch(name, arguments).await
+        };
+
+        result.map(|mut value| {
+            if let Some(shape) = shape {
+                shape_note_output(&mut value, shape.compact, shape.max_chars);
+                if !shape.fields.is_empty() {
+                    project_note_fields(&mut value, &shape.fields);
+                }
+            }
+            value
+        })
+    }
+
+    /// ツール名に対応するハンドラを呼び出す
+    async fn dispatch(&self, name: &str, arguments: Value) -> Result<Value> {
+        match name {
+            "post_nostr_note" => self.post_note(arguments).await,
+            "post_nostr_thread" => self.post_thread(arguments).await,
+            "get_nostr_timeline" => self.get_timeline(arguments).await,
+            "get_timeline_digest" => self.get_timeline_digest(arguments).await,
+            "get_hashtag_stats" => self.get_hashtag_stats(arguments).await,
+            "add_watch_account" => self.add_watch_account(arguments).await,
+            "remove_watch_account" => self.remove_watch_account(arguments).await,
+            "add_contact_alias" => self.add_contact_alias(arguments).await,
+            "remove_contact_alias" => self.remove_contact_alias(arguments).await,
+            "resolve_alias" => self.resolve_alias(arguments).await,
+            "get_watched_feeds" => self.get_watched_feeds(arguments).await,
+            "search_nostr_notes" => self.search_notes(arguments).await,
+            "get_nostr_notes" => self.get_notes(arguments).await,
+            "get_nostr_profiles" => self.get_profiles(arguments).await,
+            "get_nostr_profile" => self.get_profile(arguments).await,
+            "create_nostr_account" => self.create_account(arguments).await,
+            "migrate_account" => self.migrate_account(arguments).await,
+            "check_follow_relationship" => self.check_follow_relationship(arguments).await,
+            "recommend_follows" => self.recommend_follows(arguments).await,
+            "search_nostr_users" => self.search_users(arguments).await,
+            // Phase 1: NIP-23 長文コンテンツ
+            "post_nostr_article" => self.post_article(arguments).await,
+            "get_nostr_articles" => self.get_articles(arguments).await,
+            "search_nostr_articles" => self.search_articles(arguments).await,
+            "save_nostr_draft" => self.save_draft(arguments).await,
+            "get_nostr_drafts" => self.get_drafts(arguments).await,
+            "save_local_draft" => self.save_local_draft(arguments).await,
+            "list_local_drafts" => self.list_local_drafts(arguments).await,
+            "publish_local_draft" => self.publish_local_draft(arguments).await,
+            "create_publication" => self.create_publication(arguments).await,
+            "add_article_to_publication" => self.add_article_to_publication(arguments).await,
+            "get_publication" => self.get_publication(arguments).await,
+            "get_article_history" => self.get_article_history(arguments).await,
+            "diff_article_versions" => self.diff_article_versions(arguments).await,
+            // Phase 2: タイムライン拡張機能
+            "get_nostr_thread" => self.get_thread(arguments).await,
+            "get_reply_context" => self.get_reply_context(arguments).await,
+            "reveal_note" => self.reveal_note(arguments).await,
+            "react_to_note" => self.react_to_note(arguments).await,
+            "get_reaction_presets" => self.get_reaction_presets().await,
+            "reply_to_note" => self.reply_to_note(arguments).await,
+            "get_nostr_notifications" => self.get_notifications(arguments).await,
+            "mark_notifications_read" => self.mark_notifications_read(arguments).await,
+            "get_note_reposts" => self.get_note_reposts(arguments).await,
+            "get_note_engagement" => self.get_note_engagement(arguments).await,
+            // Phase 4: 高度な機能
+            "send_zap" => self.send_zap(arguments).await,
+            "get_zap_receipts" => self.get_zap_receipts(arguments).await,
+            "get_profile_zap_stats" => self.get_profile_zap_stats(arguments).await,
+            "get_account_analytics" => self.get_account_analytics(arguments).await,
+            "get_follower_growth" => self.get_follower_growth(arguments).await,
+            "get_zap_leaderboard" => self.get_zap_leaderboard(arguments).await,
+            "get_lightning_invoice" => self.get_lightning_invoice(arguments).await,
+            "decode_lightning_invoice" => self.decode_lightning_invoice(arguments).await,
+            "check_wallet_connection" => self.check_wallet_connection().await,
+            "get_spending_report" => self.get_spending_report(arguments).await,
+            "pay_lightning_address" => self.pay_lightning_address(arguments).await,
+            "send_dm" => self.send_dm(arguments).await,
+            "get_dms" => self.get_dms(arguments).await,
+            "get_dm_conversations" => self.get_dm_conversations(arguments).await,
+            "mark_dms_read" => self.mark_dms_read(arguments).await,
+            "export_my_events" => self.export_my_events(arguments).await,
+            "import_events" => self.import_events(arguments).await,
+            "rebroadcast_events" => self.rebroadcast_events(arguments).await,
+            "timestamp_event" => self.timestamp_event(arguments).await,
+            "get_pending_publishes" => self.get_pending_publishes().await,
+            "flush_pending" => self.flush_pending(arguments).await,
+            "start_subscription" => self.start_subscription(arguments).await,
+            "get_subscription_events" => self.get_subscription_events(arguments).await,
+            "stop_subscription" => self.stop_subscription(arguments).await,
+            "list_subscriptions" => self.list_subscriptions().await,
+            "get_relay_list" => self.get_relay_list(arguments).await,
+            "benchmark_relays" => self.benchmark_relays(arguments).await,
+            "discover_relays" => self.discover_relays(arguments).await,
+            // NIP-38: ユーザーステータス
+            "set_user_status" => self.set_user_status(arguments).await,
+            "get_user_status" => self.get_user_status(arguments).await,
+            // NIP-58: バッジ
+            "get_user_badges" => self.get_user_badges(arguments).await,
+            "get_badge_definition" => self.get_badge_definition(arguments).await,
+            "award_badge" => self.award_badge(arguments).await,
+            // NIP-30 / NIP-51: カスタム絵文字
+            "get_emoji_sets" => self.get_emoji_sets(arguments).await,
+            "add_emoji" => self.add_emoji(arguments).await,
+            "remove_emoji" => self.remove_emoji(arguments).await,
+            // NIP-52: カレンダーイベント
+            "create_calendar_event" => self.create_calendar_event(arguments).await,
+            "get_calendar_events" => self.get_calendar_events(arguments).await,
+            "rsvp_calendar_event" => self.rsvp_calendar_event(arguments).await,
+            // NIP-54: Wiki
+            "get_wiki_article" => self.get_wiki_article(arguments).await,
+            "compare_wiki_versions" => self.compare_wiki_versions(arguments).await,
+            "publish_wiki_article" => self.publish_wiki_article(arguments).await,
+            // NIP-53: ライブアクティビティ
+            "get_live_streams" => self.get_live_streams(arguments).await,
+            "get_live_chat" => self.get_live_chat(arguments).await,
+            "post_live_chat_message" => self.post_live_chat_message(arguments).await,
+            // NIP-71: 動画
+            "get_video_feed" => self.get_video_feed(arguments).await,
+            "post_video" => self.post_video(arguments).await,
+            // NIP-88: 投票
+            "create_poll" => self.create_poll(arguments).await,
+            "vote_poll" => self.vote_poll(arguments).await,
+            "get_poll_results" => self.get_poll_results(arguments).await,
+            // NIP-56: 通報
+            "report_content" => self.report_content(arguments).await,
+            // Phase 6: NIP-46 Nostr Connect
+            "nostr_connect" => self.nostr_connect(arguments).await,
+            "nostr_connect_status" => self.nostr_connect_status(arguments).await,
+            "nostr_disconnect" => self.nostr_disconnect(arguments).await,
+            "list_signers" => self.list_signers().await,
+            "get_signer_info" => self.get_signer_info().await,
+            "set_active_signer" => self.set_active_signer(arguments).await,
+            // NIP-B7: Blossom メディアアップロード
+            "upload_media" => self.upload_media(arguments).await,
+            "get_blossom_servers" => self.get_blossom_servers(arguments).await,
+            "set_blossom_servers" => self.set_blossom_servers(arguments).await,
+            _ => Err(anyhow!("不明なツール: {}", name)),
+        }
+    }
+
+    /// `compact`、本文の最大文字数（`truncate_content`、省略時は設定ファイルの `content-max-chars`、0 で切り詰めない）と
+    /// 残すフィールド（`fields`）
+    fn note_output_shape(&self, arguments: &Value) -> NoteOutputShape {
+        let compact = arguments.get("compact").and_then(|v| v.as_bool()).unwrap_or(false);
+        let max_chars = match arguments.get("truncate_content").and_then(|v| v.as_u64()) {
+            Some(chars) => Some(chars as usize).filter(|&chars| chars > 0),
+            None => self.output.content_max_chars,
+        };
+        let fields = extract_string_array(arguments, "fields")
+            .into_iter()
+            .map(|field| field.trim().to_string())
+            .filter(|field| !field.is_empty())
+            .collect();
+        NoteOutputShape { compact, max_chars, fields }
+    }
+
+    /// 新しいノートを投稿
+    async fn post_note(&self, arguments: Value) -> Result<Value> {
+        let content = require_str_param(&arguments, &["content"])?;
+
+        let published = self.client.read().await.post_note(content).await?;
+        let event_id = published.id;
+
+        let mut result = json!({
+            "success": true,
+            "event_id": event_id.to_hex(),
+            "nevent": published.nevent,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": format!("ノートを投稿しました。イベント ID: {}", event_id.to_hex())
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, event_id, &mut result).await;
+        Ok(result)
+    }
+
+    /// 長文をスレッドとして投稿
+    async fn post_thread(&self, arguments: Value) -> Result<Value> {
+        let numbered = arguments
+            .get("numbered")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        let mut segments = extract_string_array(&arguments, "segments");
+        if segments.is_empty() {
+            let content = require_str_param(&arguments, &["content"])?;
+            let max_length = arguments
+                .get("max_length")
+                .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+                .unwrap_or(DEFAULT_THREAD_NOTE_CHARS)
+                .clamp(MIN_THREAD_NOTE_CHARS, MAX_THREAD_NOTE_CHARS) as usize;
+            let reserve = if numbered { THREAD_NUMBER_RESERVE } else { 0 };
+            segments = crate::content::split_into_segments(content, max_length - reserve);
+        }
+
+        debug!("スレッド投稿: {} 件", segments.len());
+
+        let published = self.client.read().await.post_thread(&segments, numbered).await?;
+        let event_ids: Vec<String> = published.iter().map(|p| p.id.to_hex()).collect();
+        let notes: Vec<Value> = published.iter().map(|p| json!({
+            "event_id": p.id.to_hex(),
+            "nevent": p.nevent,
+            "published_to": p.published_to,
+            "failed_relays": p.failed_relays,
+        })).collect();
+
+        Ok(json!({
+            "success": true,
+            "count": published.len(),
+            "root_event_id": event_ids[0],
+            "event_ids": event_ids,
+            "notes": notes,
+            "message": format!("{} 件のノートをスレッドとして投稿しました。", published.len())
+        }))
+    }
+
+    /// タイムラインを取得
+    async fn get_timeline(&self, arguments: Value) -> Result<Value> {
+        let limit = self.extract_limit_up_to(&arguments, MAX_PAGINATED_LIMIT);
+        debug!("タイムライン取得: limit={}", limit);
+
+        let include_status = arguments
+            .get("include_status")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let refresh_contacts = arguments
+            .get("refresh_contacts")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let include_replies = arguments
+            .get("include_replies")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        let client = self.client.read().await;
+        if refresh_contacts {
+            client.invalidate_contact_list_cache().await;
+        }
+        let trust = resolve_trust_filter(&client, &arguments).await?;
+        let min_pow = extract_min_pow(&client, &arguments);
+        let languages = resolve_language_filter(&client, &arguments);
+        let (mut notes, excluded) = client.get_timeline(limit, min_pow, include_replies, &languages).await?;
+        let trust_json = trust.map(|(wot, graph)| {
+            let outside = graph.apply(&mut notes, wot.max_hops, wot.mode, note_author_key);
+            format_trust_json(&wot, outside)
+        });
+        let language_json = (!languages.is_empty()).then(|| format_language_json(&languages, excluded));
+        let mut formatted_notes: Vec<Value> = notes.iter().map(format_note_json).collect();
+
+        // NIP-38: 著者のステータスを付与
+        if include_status {
+            let pubkeys: Vec<nostr_sdk::PublicKey> = notes.iter()
+                .filter_map(|n| nostr_sdk::PublicKey::from_hex(&n.author.pubkey).ok())
+                .collect::<std::collections::HashSet<_>>()
+                .into_iter()
+                .collect();
+
+            match client.get_user_statuses(&pubkeys).await {
+                Ok(statuses) => {
+                    for (note, formatted) in notes.iter().zip(formatted_notes.iter_mut()) {
+                        let status = nostr_sdk::PublicKey::from_hex(&note.author.pubkey)
+                            .ok()
+                            .and_then(|pk| statuses.get(&pk));
+                        if let Some(status) = status {
+                            formatted["author_status"] = json!(status);
+                        }
+                    }
+                }
+                Err(e) => debug!("ステータスの取得に失敗（スキップ）: {}", e),
+            }
+        }
+
+        let mut result = json!({
+            "success": true,
+            "count": notes.len(),
+            "notes": formatted_notes
+        });
+        if let Some(trust) = trust_json {
+            result["trust"] = trust;
+        }
+        if let Some(languages) = language_json {
+            result["languages"] = languages;
+        }
+        hydrate_references_if_requested(&client, &arguments, &mut result).await;
+        attach_link_previews_if_requested(&client, &arguments, &mut result).await;
+        Ok(result)
+    }
+
+    /// タイムラインの集計結果を取得
+    async fn get_timeline_digest(&self, arguments: Value) -> Result<Value> {
+        let get_u64 = |key: &str| arguments
+            .get(key)
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)));
+        let hours = get_u64("hours").unwrap_or(DEFAULT_DIGEST_HOURS).clamp(1, MAX_DIGEST_HOURS);
+        let window = get_u64("window").unwrap_or(DEFAULT_DIGEST_WINDOW).clamp(1, MAX_DIGEST_WINDOW);
+        let top = get_u64("top").unwrap_or(DEFAULT_DIGEST_TOP).clamp(1, MAX_DIGEST_TOP);
+        debug!("タイムライン集計: hours={}, window={}, top={}", hours, window, top);
+
+        let digest = self.client.read().await
+            .get_timeline_digest(hours, window, top as usize)
+            .await?;
+
+        Ok(json!({
+            "success": true,
+            "digest": digest
+        }))
+    }
+
+    /// ハッシュタグの投稿数の推移を集計
+    async fn get_hashtag_stats(&self, arguments: Value) -> Result<Value> {
+        let hashtag = require_str_param(&arguments, &["hashtag"])?;
+        let get_u64 = |key: &str| arguments
+            .get(key)
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)));
+        let days = get_u64("days").unwrap_or(DEFAULT_HASHTAG_DAYS).clamp(1, MAX_HASHTAG_DAYS);
+        let window = get_u64("window").unwrap_or(DEFAULT_HASHTAG_WINDOW).clamp(1, MAX_HASHTAG_WINDOW);
+        let top = get_u64("top").unwrap_or(DEFAULT_DIGEST_TOP).clamp(1, MAX_DIGEST_TOP);
+        debug!("ハッシュタグ集計: hashtag='{}', days={}, window={}, top={}", hashtag, days, window, top);
+
+        let stats = self.client.read().await
+            .get_hashtag_stats(hashtag, days, window, top as usize)
+            .await?;
+        let trend = match stats.change_percent {
+            Some(percent) => format!("（前の {} 日間の {} 件から {:+.0}%）", days, stats.previous_notes, percent),
+            None if stats.sampled => "（取得件数の上限に達したため、前の期間とは比較できません）".to_string(),
+            None => String::new(),
+        };
+        let message = format!("直近 {} 日間の #{} のノートは {} 件です{}。", days, stats.hashtag, stats.current_notes, trend);
+
+        Ok(json!({
+            "success": true,
+            "stats": stats,
+            "message": message
+        }))
+    }
+
+    /// ウォッチリストにアカウントを追加
+    async fn add_watch_account(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("ウォッチリストの変更")?;
+        let pubkey = require_str_param(&arguments, &["pubkey", "npub"])?;
+        let label = optional_str_param(&arguments, "label");
+        debug!("ウォッチリストに追加: {}", pubkey);
+
+        let (pk, added) = self.client.read().await.add_watch_account(pubkey, label)?;
+        let npub = pk.to_bech32().unwrap_or_default();
+
+        Ok(json!({
+            "success": true,
+            "npub": npub,
+            "added": added,
+            "message": if added {
+                format!("{} をウォッチリストに追加しました。", npub)
+            } else {
+                format!("{} は登録済みです。", npub)
+            }
+        }))
+    }
+
+    /// 新しいアカウントを作成して切り替え
+    ///
+    /// 鍵の生成・設定ファイルの書き換え・署名者の切り替えは取り消せないため、ドライランでは実行しません。
+    async fn create_account(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("アカウントの作成")?;
+        let name = require_str_param(&arguments, &["name"])?;
+        let replace_existing_key = arguments
+            .get("replace_existing_key")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        debug!("アカウント作成: name={}", name);
+
+        let metadata = nostr_sdk::Metadata {
+            name: Some(name.to_string()),
+            display_name: optional_str_param(&arguments, "display_name").map(String::from),
+            about: optional_str_param(&arguments, "about").map(String::from),
+            picture: optional_str_param(&arguments, "picture").map(String::from),
+            website: optional_str_param(&arguments, "website").map(String::from),
+            lud16: optional_str_param(&arguments, "lud16").map(String::from),
+            ..Default::default()
+        };
+
+        let mut config = Config::load()?;
+        let has_existing_key = config.privatekey.is_some() || config.delegation.is_some();
+        if has_existing_key && !replace_existing_key {
+            return Err(anyhow!(
+                "設定ファイルに秘密鍵が設定されています。新しい鍵で置き換える場合は replace_existing_key: true を指定してください。"
+            ));
+        }
+        if self.client.read().await.is_nip46_active().await {
+            return Err(anyhow!(
+                "NIP-46 リモートサイナーの使用中はアカウントを作成できません。先に nostr_disconnect で切断してください。"
+            ));
+        }
+
+        // 公開に失敗しても鍵を失わないよう、切り替えの前に設定ファイルへ保存する
+        let keys = nostr_sdk::Keys::generate();
+        let backup = if has_existing_key { Config::backup_file()? } else { None };
+        config.privatekey = Some(keys.secret_key().to_bech32()?);
+        config.publickey = None;
+        config.delegation = None;
+        if config.effective_auth_mode() != AuthMode::Local {
+            config.auth_mode = Some(AuthMode::Local);
+        }
+        config.save()?;
+
+        let account = self.client.write().await.switch_to_new_account(keys, metadata).await?;
+        let message = if account.publish_errors.is_empty() {
+            format!("アカウント {} を作成し、プロフィールとリレーリストを公開しました。", account.npub)
+        } else {
+            format!(
+                "アカウント {} を作成しましたが、一部のイベントを公開できませんでした: {}",
+                account.npub,
+                account.publish_errors.join(" / ")
+            )
+        };
+
+        Ok(json!({
+            "success": true,
+            "account": account,
+            "config_path": Config::config_path()?.display().to_string(),
+            "config_backup": backup.map(|path| path.display().to_string()),
+            "message": message
+        }))
+    }
+
+    /// 現在のアカウントから新しい鍵に移行
+    async fn migrate_account(&self, arguments: Value) -> Result<Value> {
+        let new_pubkey = optional_str_param(&arguments, "new_pubkey");
+        // 秘密鍵が会話の履歴に残らないよう、引数では受け付けずファイルから読み込む
+        if arguments.get("new_secret_key").is_some() {
+            return Err(anyhow!(
+                "秘密鍵は引数で指定できません。nsec を書いたファイルのパスを new_secret_key_file に指定してください。"
+            ));
+        }
+        let new_secret_key_file = optional_str_param(&arguments, "new_secret_key_file").map(std::path::Path::new);
+        let message = optional_str_param(&arguments, "message");
+        let notify_followers = arguments
+            .get("notify_followers")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0)
+            .min(MAX_MIGRATION_NOTIFICATIONS) as usize;
+        debug!("アカウント移行: notify_followers={}", notify_followers);
+
+        let migration = self.client.read().await
+            .migrate_account(new_pubkey, new_secret_key_file, message, notify_followers)
+            .await?;
+        let message = if migration.errors.is_empty() {
+            format!("{} への移行を完了しました。設定ファイルの privatekey を新しい鍵に変更してください。", migration.new_npub)
+        } else {
+            format!("{} への移行で {} 件の手順が失敗しました（errors を確認してください）。", migration.new_npub, migration.errors.len())
+        };
+
+        Ok(json!({
+            "success": migration.errors.is_empty(),
+            "migration": migration,
+            "message": message
+        }))
+    }
+
+    /// ウォッチリストからアカウントを削除
+    async fn remove_watch_account(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("ウォッチリストの変更")?;
+        let pubkey = require_str_param(&arguments, &["pubkey", "npub"])?;
+        debug!("ウォッチリストから削除: {}", pubkey);
+
+        let removed = self.client.read().await.remove_watch_account(pubkey)?;
+
+        Ok(json!({
+            "success": true,
+            "removed": removed,
+            "message": if removed {
+                "ウォッチリストから削除しました。"
+            } else {
+                "このアカウントはウォッチリストに登録されていません。"
+            }
+        }))
+    }
+
+    /// アドレス帳に別名を登録
+    async fn add_contact_alias(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("アドレス帳の変更")?;
+        let alias = require_str_param(&arguments, &["alias"])?;
+        let pubkey = require_str_param(&arguments, &["pubkey", "npub"])?;
+        let note = optional_str_param(&arguments, "note");
+        debug!("アドレス帳に登録: {} → {}", alias, pubkey);
+
+        let (alias, pk, previous) = self.client.read().await.add_contact_alias(alias, pubkey, note)?;
+        let npub = pk.to_bech32().unwrap_or_default();
+        let message = match &previous {
+            Some(previous) if *previous != pk.to_hex() => format!("別名「{}」の登録先を {} に変更しました。", alias, npub),
+            Some(_) => format!("別名「{}」の登録を更新しました。", alias),
+            None => format!("別名「{}」を {} として登録しました。", alias, npub),
+        };
+
+        Ok(json!({
+            "success": true,
+            "alias": alias,
+            "pubkey": pk.to_hex(),
+            "npub": npub,
+            "updated": previous.is_some(),
+            "message": message
+        }))
+    }
+
+    /// アドレス帳から別名を削除
+    async fn remove_contact_alias(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("アドレス帳の変更")?;
+        let alias = require_str_param(&arguments, &["alias"])?;
+        debug!("アドレス帳から削除: {}", alias);
+
+        let removed = self.client.read().await.remove_contact_alias(alias)?;
+
+        Ok(json!({
+            "success": true,
+            "removed": removed,
+            "message": if removed {
+                format!("別名「{}」を削除しました。", alias)
+            } else {
+                format!("別名「{}」はアドレス帳に登録されていません。", alias)
+            }
+        }))
+    }
+
+    /// アドレス帳の別名を解決
+    async fn resolve_alias(&self, arguments: Value) -> Result<Value> {
+        let alias = optional_str_param(&arguments, "alias");
+        debug!("別名の解決: {:?}", alias);
+
+        let contacts = self.client.read().await.get_contact_aliases(alias).await;
+        if let Some(alias) = alias {
+            let Some(contact) = contacts.into_iter().next() else {
+                return Err(anyhow!("別名「{}」はアドレス帳に登録されていません", alias));
+            };
+            return Ok(json!({
+                "success": true,
+                "alias": contact.alias,
+                "pubkey": contact.author.pubkey,
+                "npub": contact.author.npub,
+                "contact": contact,
+                "message": format!("「{}」は {} です。", contact.alias, contact.author.npub)
+            }));
+        }
+
+        Ok(json!({
+            "success": true,
+            "count": contacts.len(),
+            "contacts": contacts,
+            "message": format!("アドレス帳に {} 件の別名が登録されています。", contacts.len())
+        }))
+    }
+
+    /// ウォッチリストのアカウントの最近のノートを取得
+    async fn get_watched_feeds(&self, arguments: Value) -> Result<Value> {
+        let limit = self.extract_limit(&arguments);
+        let hours = arguments
+            .get("hours")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .map(|h| h.clamp(1, MAX_DIGEST_HOURS));
+        debug!("ウォッチリストのフィード取得: limit={}, hours={:?}", limit, hours);
+
+        let feeds = self.client.read().await.get_watched_feeds(limit, hours).await?;
+        if feeds.accounts.is_empty() {
+            return Ok(json!({
+                "success": true,
+                "title": "Watch list",
+                "count": 0,
+                "accounts": [],
+                "notes": [],
+                "message": "ウォッチリストは空です。add_watch_account でアカウントを追加してください。"
+            }));
+        }
+
+        let formatted_notes: Vec<Value> = feeds.notes.iter().map(format_note_json).collect();
+        let mut result = json!({
+            "success": true,
+            "title": "Watch list",
+            "count": feeds.notes.len(),
+            "accounts": feeds.accounts,
+            "notes": formatted_notes
+        });
+        let client = self.client.read().await;
+        hydrate_references_if_requested(&client, &arguments, &mut result).await;
+        attach_link_previews_if_requested(&client, &arguments, &mut result).await;
+        Ok(result)
+    }
+
+    /// ノートを検索
+    async fn search_notes(&self, arguments: Value) -> Result<Value> {
+        let query = require_str_param(&arguments, &["query"])?;
+
+        if query.is_empty() {
+            return Err(anyhow!("query は空にできません"));
+        }
+
+        let limit = self.extract_limit_up_to(&arguments, MAX_PAGINATED_LIMIT);
+        debug!("ノート検索: query='{}', limit={}", query, limit);
+
+        let client = self.client.read().await;
+        let trust = resolve_trust_filter(&client, &arguments).await?;
+        let min_pow = extract_min_pow(&client, &arguments);
+        let languages = resolve_language_filter(&client, &arguments);
+        let (mut notes, excluded) = client.search_notes(query, limit, min_pow, &languages).await?;
+        drop(client);
+
+        let trust_json = trust.map(|(wot, graph)| {
+            let outside = graph.apply(&mut notes, wot.max_hops, wot.mode, note_author_key);
+            format_trust_json(&wot, outside)
+        });
+        let language_json = (!languages.is_empty()).then(|| format_language_json(&languages, excluded));
+        let formatted_notes: Vec<Value> = notes.iter().map(format_note_json).collect();
+
+        let mut result = json!({
+            "success": true,
+            "query": query,
+            "count": notes.len(),
+            "notes": formatted_notes
+        });
+        if let Some(trust) = trust_json {
+            result["trust"] = trust;
+        }
+        if let Some(languages) = language_json {
+            result["languages"] = languages;
+        }
+        let client = self.client.read().await;
+        hydrate_references_if_requested(&client, &arguments, &mut result).await;
+        attach_link_previews_if_requested(&client, &arguments, &mut result).await;
+        Ok(result)
+    }
+
+    /// 複数のノートを ID でまとめて取得
+    async fn get_notes(&self, arguments: Value) -> Result<Value> {
+        let ids = extract_string_array(&arguments, "ids");
+        if ids.is_empty() {
+            return Err(anyhow!("ids に 1 件以上のイベント ID を指定してください"));
+        }
+        if ids.len() > MAX_BATCH_NOTE_IDS {
+            return Err(anyhow!("ids は最大 {} 件までです（{} 件指定されました）", MAX_BATCH_NOTE_IDS, ids.len()));
+        }
+        debug!("ノートの一括取得: {} 件", ids.len());
+
+        let client = self.client.read().await;
+        let (notes, not_found) = client.get_notes_by_ids(&ids).await?;
+        let formatted_notes: Vec<Value> = notes.iter().map(format_note_json).collect();
+
+        let mut result = json!({
+            "success": true,
+            "title": "Notes",
+            "count": notes.len(),
+            "notes": formatted_notes,
+            "not_found": not_found
+        });
+        hydrate_references_if_requested(&client, &arguments, &mut result).await;
+        attach_link_previews_if_requested(&client, &arguments, &mut result).await;
+        Ok(result)
+    }
+
+    /// 複数のプロフィールをまとめて取得
+    async fn get_profiles(&self, arguments: Value) -> Result<Value> {
+        let pubkeys = extract_string_array(&arguments, "pubkeys");
+        if pubkeys.is_empty() {
+            return Err(anyhow!("pubkeys に 1 件以上の公開鍵を指定してください"));
+        }
+        if pubkeys.len() > MAX_BATCH_PROFILES {
+            return Err(anyhow!("pubkeys は最大 {} 件までです（{} 件指定されました）", MAX_BATCH_PROFILES, pubkeys.len()));
+        }
+        debug!("プロフィールの一括取得: {} 件", pubkeys.len());
+
+        let authors = self.client.read().await.get_profiles(&pubkeys).await?;
+        let (found, missing): (Vec<_>, Vec<_>) = authors.into_iter().partition(|a| a.metadata.is_some());
+
+        let profiles: Vec<Value> = found.iter().map(|author| {
+            let metadata = author.metadata.clone().unwrap_or_default();
+            json!({
+                "pubkey": author.pubkey,
+                "npub": author.npub,
+                "nprofile": author.nprofile,
+                "name": metadata.name,
+                "display_name": metadata.display_name,
+                "display": author.display(),
+                "about": metadata.about,
+                "picture": metadata.picture,
+                "banner": metadata.banner,
+                "nip05": metadata.nip05,
+                "lud16": metadata.lud16,
+                "website": metadata.website
+            })
+        }).collect();
+        let not_found: Vec<&str> = missing.iter().map(|a| a.npub.as_str()).collect();
+
+        Ok(json!({
+            "success": true,
+            "count": profiles.len(),
+            "profiles": profiles,
+            "not_found": not_found
+        }))
+    }
+
+    /// プロフィールを取得（Phase 3: プロフィールカード・統計情報付き）
+    async fn get_profile(&self, arguments: Value) -> Result<Value> {
+        let pubkey = require_str_param(&arguments, &["pubkey", "npub"])?;
+        debug!("プロフィール取得: {}", pubkey);
+
+        let include_stats = arguments
+            .get("include_stats")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        // プロフィールと統計情報を並行して取得
+        let client = self.client.read().await;
+        let (profile_result, stats_result) = tokio::join!(
+            client.get_profile(pubkey),
+            async {
+                if include_stats {
+                    Some(client.get_profile_stats(pubkey).await)
+                } else {
+                    None
+                }
+            }
+        );
+        drop(client);
+
+        let profile = profile_result?;
+
+        // Phase 3: プロフィールカードの構築
+        let display_name = profile.display_name.as_ref()
+            .or(profile.name.as_ref())
+            .cloned()
+            .unwrap_or_else(|| {
+                if profile.npub.len() > 16 {
+                    format!("{}...{}", &profile.npub[..12], &profile.npub[profile.npub.len()-4..])
+                } else {
+                    profile.npub.clone()
+                }
+            });
+
+        let mut profile_card = json!({
+            "avatar": profile.picture,
+            "name": display_name,
+            "nip05": profile.nip05,
+            "bio": profile.about
+        });
+
+        // 統計情報を追加（取得に成功した場合のみ）
+        if let Some(Ok(stats)) = stats_result {
+            profile_card["stats"] = json!({
+                "following": stats.following,
+                "followers": stats.followers,
+                "notes": stats.notes,
+                "count_method": stats.count_method
+            });
+        }
+
+        Ok(json!({
+            "success": true,
+            "profile": profile,
+            "profile_card": profile_card
+        }))
+    }
+
+    /// 2 つのアカウントのフォロー関係を確認
+    async fn check_follow_relationship(&self, arguments: Value) -> Result<Value> {
+        let pubkey_a = optional_str_param(&arguments, "pubkey_a");
+        let pubkey_b = require_str_param(&arguments, &["pubkey_b"])?;
+        debug!("フォロー関係確認: a={:?}, b={}", pubkey_a, pubkey_b);
+
+        let relation = self.client.read().await.check_follow_relationship(pubkey_a, pubkey_b).await?;
+
+        let summary = match relation.relationship.as_str() {
+            "mutual" => format!("{} と {} は相互フォローです。", relation.a.display(), relation.b.display()),
+            "a_follows_b" => format!("{} は {} をフォローしています（フォローバックなし）。", relation.a.display(), relation.b.display()),
+            "b_follows_a" => format!("{} は {} をフォローしています（フォローバックなし）。", relation.b.display(), relation.a.display()),
+            _ => format!("{} と {} の間にフォロー関係はありません。", relation.a.display(), relation.b.display()),
+        };
+
+        Ok(json!({
+            "success": true,
+            "relationship": relation,
+            "summary": summary
+        }))
+    }
+
+    /// フォローのおすすめを取得
+    async fn recommend_follows(&self, arguments: Value) -> Result<Value> {
+        let limit = self.extract_limit(&arguments);
+        let min_overlap = arguments
+            .get("min_overlap")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or(DEFAULT_RECOMMEND_MIN_OVERLAP)
+            .max(1);
+        debug!("フォローのおすすめ: limit={}, min_overlap={}", limit, min_overlap);
+
+        let result = self.client.read().await.recommend_follows(limit as usize, min_overlap as usize).await?;
+        let message = if result.recommendations.is_empty() {
+            format!(
+                "フォロー中の {} 人のコンタクトリストから、{} 人以上にフォローされている未フォローのアカウントは見つかりませんでした。",
+                result.analyzed_contact_lists, min_overlap
+            )
+        } else {
+            format!(
+                "フォロー中の {} 人のコンタクトリストを分析し、{} 人の候補から {} 人をおすすめします。",
+                result.analyzed_contact_lists, result.candidates, result.recommendations.len()
+            )
+        };
+
+        Ok(json!({
+            "success": true,
+            "result": result,
+            "message": message
+        }))
+    }
+
+    /// ユーザーを検索
+    async fn search_users(&self, arguments: Value) -> Result<Value> {
+        let query = require_str_param(&arguments, &["query"])?;
+
+        let limit = self.extract_limit(&arguments);
+        debug!("ユーザー検索: query='{}', limit={}", query, limit);
+
+        let users = self.client.read().await.search_users(query, limit).await?;
+
+        let formatted: Vec<Value> = users.iter().map(|user| {
+            json!({
+                "pubkey": user.pubkey,
+                "npub": user.npub,
+                "name": user.name,
+                "display_name": user.display_name,
+                "display": user.display(),
+                "picture": user.picture,
+                "nip05": user.nip05,
+                "nip05_verified": user.nip05_verified
+            })
+        }).collect();
+
+        Ok(json!({
+            "success": true,
+            "query": query,
+            "count": users.len(),
+            "users": formatted
+        }))
+    }
+
+    // ========================================
+    // Phase 1: NIP-23 長文コンテンツツール
+    // ========================================
+
+    /// 長文記事を投稿
+    async fn post_article(&self, arguments: Value) -> Result<Value> {
+        let params = extract_article_params(&arguments)?;
+        let timestamp = arguments
+            .get("timestamp")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let announce = arguments
+            .get("announce")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let client = self.client.read().await;
+        let (article, published) = client.post_article(params).await?;
+
+        let mut result = json!({
+            "success": true,
+            "event_id": article.id,
+            "nevent": article.nevent,
+            "naddr": article.naddr,
+            "identifier": article.identifier,
+            "title": article.title,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": format!("記事「{}」を投稿しました。", article.title)
+        });
+
+        verify_publish_if_requested(&client, &arguments, published.id, &mut result).await;
+
+        // 記事の公開は完了しているため、紹介ノートや証明の作成に失敗してもエラーにはしない
+        if announce {
+            result["announcement"] = match client.announce_article(&article).await {
+                Ok(note) => json!({
+                    "event_id": note.id.to_hex(),
+                    "nevent": note.nevent,
+                    "published_to": note.published_to,
+                    "failed_relays": note.failed_relays,
+                }),
+                Err(e) => json!({ "error": format!("{:#}", e) }),
+            };
+        }
+        if timestamp {
+            result["timestamp"] = match client.timestamp_event(&article.id).await {
+                Ok(status) => json!(status),
+                Err(e) => json!({ "error": e.to_string() }),
+            };
+        }
+
+        Ok(result)
+    }
+
+    /// 長文記事を取得（Phase 3: コンテンツ解析付き）
+    async fn get_articles(&self, arguments: Value) -> Result<Value> {
+        let author = optional_str_param(&arguments, "author");
+        let tags = extract_tags_param(&arguments);
+        let limit = self.extract_limit(&arguments);
+        let include_content = arguments.get("include_content").and_then(|v| v.as_bool()).unwrap_or(true);
+
+        debug!("記事取得: author={:?}, tags={:?}, limit={}", author, tags, limit);
+
+        let articles = self.client.read().await.get_articles(
+            author,
+            tags.as_deref(),
+            limit,
+        ).await?;
+
+        let formatted: Vec<Value> = articles.iter().map(|a| format_article_json(a, include_content)).collect();
+
+        let mut result = json!({
+            "success": true,
+            "count": articles.len(),
+            "articles": formatted
+        });
+        let client = self.client.read().await;
+        hydrate_references_if_requested(&client, &arguments, &mut result).await;
+        attach_link_previews_if_requested(&client, &arguments, &mut result).await;
+        Ok(result)
+    }
+
+    /// 長文記事を検索
+    async fn search_articles(&self, arguments: Value) -> Result<Value> {
+        let query = require_str_param(&arguments, &["query"])?;
+
+        let limit = self.extract_limit(&arguments);
+        debug!("記事検索: query='{}', limit={}", query, limit);
+        let include_content = arguments.get("include_content").and_then(|v| v.as_bool()).unwrap_or(true);
+
+        let articles = self.client.read().await.search_articles(query, limit).await?;
+        let formatted: Vec<Value> = articles.iter().map(|a| format_article_json(a, include_content)).collect();
+
+        let mut result = json!({
+            "success": true,
+            "query": query,
+            "count": articles.len(),
+            "articles": formatted
+        });
+        let client = self.client.read().await;
+        hydrate_references_if_requested(&client, &arguments, &mut result).await;
+        attach_link_previews_if_requested(&client, &arguments, &mut result).await;
+        Ok(result)
+    }
+
+    /// 下書きを保存
+    async fn save_draft(&self, arguments: Value) -> Result<Value> {
+        let mut params = extract_article_params(&arguments)?;
+        params.published_at = None; // 下書きには published_at を設定しない
+        let (article, published) = self.client.read().await.save_draft(params).await?;
+
+        let mut result = json!({
+            "success": true,
+            "event_id": article.id,
+            "nevent": article.nevent,
+            "naddr": article.naddr,
+            "identifier": article.identifier,
+            "title": article.title,
+            "is_draft": true,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": format!("下書き「{}」を保存しました。", article.title)
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, published.id, &mut result).await;
+        Ok(result)
+    }
+
+    /// 下書きをローカルに保存
+    async fn save_local_draft(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("ローカル下書きの保存")?;
+        let params = extract_article_params(&arguments)?;
+        let draft = self.client.read().await.save_local_draft(params)?;
+
+        Ok(json!({
+            "success": true,
+            "identifier": draft.identifier,
+            "title": draft.title,
+            "updated_at": draft.updated_at,
+            "message": format!("下書き「{}」をローカルに保存しました（リレーには送信していません）。", draft.title)
+        }))
+    }
+
+    /// ローカル下書きの一覧を取得
+    async fn list_local_drafts(&self, arguments: Value) -> Result<Value> {
+        let include_content = arguments
+            .get("include_content")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let drafts = self.client.read().await.list_local_drafts()?;
+        let formatted: Vec<Value> = drafts.iter().map(|draft| {
+            let mut item = json!({
+                "identifier": draft.identifier,
+                "title": draft.title,
+                "summary": draft.summary,
+                "tags": draft.tags,
+                "characters": draft.content.chars().count(),
+                "created_at": draft.created_at,
+                "updated_at": draft.updated_at,
+                "formatted_time": format_timestamp(draft.updated_at)
+            });
+            if include_content {
+                item["content"] = json!(draft.content);
+            } else {
+                item["preview"] = json!(draft.content.chars().take(LOCAL_DRAFT_PREVIEW_CHARS).collect::<String>());
+            }
+            item
+        }).collect();
+
+        Ok(json!({
+            "success": true,
+            "count": drafts.len(),
+            "drafts": formatted
+        }))
+    }
+
+    /// ローカル下書きを公開
+    async fn publish_local_draft(&self, arguments: Value) -> Result<Value> {
+        let identifier = require_str_param(&arguments, &["identifier"])?;
+        let published_at = arguments.get("published_at").and_then(|v| v.as_u64());
+        debug!("ローカル下書きの公開: identifier={}", identifier);
+
+        let (article, published) = self.client.read().await.publish_local_draft(identifier, published_at).await?;
+
+        let mut result = json!({
+            "success": true,
+            "event_id": article.id,
+            "nevent": article.nevent,
+            "naddr": article.naddr,
+            "identifier": article.identifier,
+            "title": article.title,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": format!("下書き「{}」を記事として公開しました。", article.title)
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, published.id, &mut result).await;
+        Ok(result)
+    }
+
+    /// 記事のシリーズを作成
+    async fn create_publication(&self, arguments: Value) -> Result<Value> {
+        let params = PublicationParams {
+            title: require_str_param(&arguments, &["title"])?.to_string(),
+            identifier: optional_str_param(&arguments, "identifier").map(str::to_string),
+            description: optional_str_param(&arguments, "description").map(str::to_string),
+            image: optional_str_param(&arguments, "image").map(str::to_string),
+            articles: extract_string_array(&arguments, "articles"),
+        };
+        debug!("シリーズ作成: title='{}', articles={}", params.title, params.articles.len());
+
+        let (publication, published) = self.client.read().await.create_publication(params).await?;
+
+        let mut result = json!({
+            "success": true,
+            "event_id": published.id.to_hex(),
+            "naddr": publication.naddr,
+            "publication": publication,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": format!("シリーズ「{}」を作成しました（{} 件の記事）。", publication.title, publication.articles.len())
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, published.id, &mut result).await;
+        Ok(result)
+    }
+
+    /// シリーズに記事を追加
+    async fn add_article_to_publication(&self, arguments: Value) -> Result<Value> {
+        let publication = require_str_param(&arguments, &["publication", "naddr"])?;
+        let article = require_str_param(&arguments, &["article"])?;
+        let position = arguments.get("position")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .map(|p| p.max(1) as usize);
+        debug!("シリーズに記事を追加: publication='{}', article='{}', position={:?}", publication, article, position);
+
+        let (publication, published, placed) = self.client.read().await
+            .add_article_to_publication(publication, article, position).await?;
+
+        let mut result = json!({
+            "success": true,
+            "event_id": published.id.to_hex(),
+            "naddr": publication.naddr,
+            "position": placed,
+            "publication": publication,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": format!(
+                "シリーズ「{}」の {} 件目に記事を追加しました（全 {} 件）。",
+                publication.title, placed, publication.articles.len()
+            )
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, published.id, &mut result).await;
+        Ok(result)
+    }
+
+    /// シリーズを取得
+    async fn get_publication(&self, arguments: Value) -> Result<Value> {
+        let publication = require_str_param(&arguments, &["publication", "naddr"])?;
+        let include_content = arguments.get("include_content").and_then(|v| v.as_bool()).unwrap_or(false);
+        debug!("シリーズ取得: {}", publication);
+
+        let publication = self.client.read().await.get_publication(publication, include_content).await?;
+        let missing = publication.articles.iter().filter(|a| !a.found).count();
+        let message = if missing == 0 {
+            format!("シリーズ「{}」には {} 件の記事があります。", publication.title, publication.articles.len())
+        } else {
+            format!(
+                "シリーズ「{}」には {} 件の記事があります（うち {} 件は取得できませんでした）。",
+                publication.title, publication.articles.len(), missing
+            )
+        };
+
+        Ok(json!({
+            "success": true,
+            "publication": publication,
+            "message": message
+        }))
+    }
+
+    /// 記事のバージョン履歴を取得
+    async fn get_article_history(&self, arguments: Value) -> Result<Value> {
+        let client = self.client.read().await;
+        let Some(article) = optional_str_param(&arguments, "article") else {
+            let articles = client.list_article_histories()?;
+            return Ok(json!({
+                "success": true,
+                "articles": articles,
+                "message": format!("{} 件の記事にバージョン履歴があります。", articles.len())
+            }));
+        };
+        debug!("記事のバージョン履歴取得: {}", article);
+
+        let history = client.get_article_history(article).await?;
+        Ok(json!({
+            "success": true,
+            "history": history,
+            "message": format!("記事「{}」には {} 件のバージョンがあります。", history.identifier, history.versions.len())
+        }))
+    }
+
+    /// 記事の 2 つのバージョンを比較
+    async fn diff_article_versions(&self, arguments: Value) -> Result<Value> {
+        let article = require_str_param(&arguments, &["article", "identifier"])?;
+        let from = arguments.get("from").and_then(|v| v.as_i64());
+        let to = arguments.get("to").and_then(|v| v.as_i64());
+        debug!("記事のバージョン比較: {} ({:?} → {:?})", article, from, to);
+
+        let diff = self.client.read().await.diff_article_versions(article, from, to).await?;
+        let message = if diff.diff.is_identical() {
+            format!("バージョン {} と {} の本文は同一です。", diff.from, diff.to)
+        } else {
+            format!(
+                "バージョン {} → {}: {} 行追加、{} 行削除されています。",
+                diff.from, diff.to, diff.diff.added, diff.diff.removed
+            )
+        };
+
+        Ok(json!({
+            "success": true,
+            "comparison": diff,
+            "message": message
+        }))
+    }
+
+    // ========================================
+    // Phase 2: タイムライン拡張機能ツール
+    // ========================================
+
+    /// スレッドを取得
+    async fn get_thread(&self, arguments: Value) -> Result<Value> {
+        let note_id = require_str_param(&arguments, &["note_id"])?;
+
+        let depth = arguments
+            .get("depth")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or(3)
+            .min(10);
+
+        debug!("スレッド取得: note_id='{}', depth={}", note_id, depth);
+
+        let client = self.client.read().await;
+        let trust = resolve_trust_filter(&client, &arguments).await?;
+        let mut thread = client.get_thread(note_id, depth).await?;
+        drop(client);
+
+        let trust_json = trust.map(|(wot, graph)| {
+            let outside = apply_trust_to_replies(&mut thread.replies, &wot, &graph);
+            format_trust_json(&wot, outside)
+        });
+
+        let formatted_replies: Vec<Value> = thread.replies.iter()
+            .map(format_thread_reply)
+            .collect();
+
+        let mut result = json!({
+            "success": true,
+            "root": format_note_json(&thread.root),
+            "replies": formatted_replies,
+            "total_replies": thread.total_replies,
+            "depth": thread.depth
+        });
+        if let Some(trust) = trust_json {
+            result["trust"] = trust;
+        }
+        let client = self.client.read().await;
+        hydrate_references_if_requested(&client, &arguments, &mut result).await;
+        attach_link_previews_if_requested(&client, &arguments, &mut result).await;
+        Ok(result)
+    }
+
+    /// リプライを書くための文脈を取得
+    async fn get_reply_context(&self, arguments: Value) -> Result<Value> {
+        let note_id = require_str_param(&arguments, &["note_id"])?;
+        let limit = arguments
+            .get("limit")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or(DEFAULT_REPLY_CONTEXT_NOTES)
+            .min(MAX_REPLY_CONTEXT_NOTES);
+        debug!("リプライの文脈取得: note_id='{}', limit={}", note_id, limit);
+
+        let context = self.client.read().await.get_reply_context(note_id, limit as usize).await?;
+        let related: Vec<Value> = context.related.iter()
+            .map(|related| {
+                let mut note = format_note_json(&related.note);
+                note["relation"] = json!(related.relation);
+                note
+            })
+            .collect();
+
+        let message = match (&context.root, context.is_reply) {
+            (Some(_), _) => format!("スレッドのルートノートと、著者の関連ノート {} 件を取得しました。", related.len()),
+            (None, true) => format!("スレッドのルートノートは見つかりませんでした。著者の関連ノート {} 件を取得しました。", related.len()),
+            (None, false) => format!("スレッドの最初のノートです。著者の関連ノート {} 件を取得しました。", related.len()),
+        };
+
+        Ok(json!({
+            "success": true,
+            "note": format_note_json(&context.note),
+            "is_reply": context.is_reply,
+            "root": context.root.as_ref().map(format_note_json),
+            "parent": context.parent.as_ref().map(format_note_json),
+            "related": related,
+            "message": message
+        }))
+    }
+
+    /// センシティブなノートの本文を取得
+    async fn reveal_note(&self, arguments: Value) -> Result<Value> {
+        let note_id = require_str_param(&arguments, &["note_id"])?;
+        debug!("ノートの本文を表示: note_id='{}'", note_id);
+
+        let note = self.client.read().await.reveal_note(note_id).await?;
+
+        Ok(json!({
+            "success": true,
+            "note": format_note_json(&note)
+        }))
+    }
+
+    /// リアクションを送信
+    async fn react_to_note(&self, arguments: Value) -> Result<Value> {
+        let note_id = require_str_param(&arguments, &["note_id"])?;
+        let reaction = optional_str_param(&arguments, "reaction");
+
+        debug!("リアクション送信: note_id='{}', reaction={:?}", note_id, reaction);
+
+        let (published, reaction, preset) = self.client.read().await.react_to_note(note_id, reaction).await?;
+        let event_id = published.id;
+
+        let message = match &preset {
+            Some(name) => format!("リアクション「{}」（プリセット「{}」）を送信しました。", reaction, name),
+            None => format!("リアクション「{}」を送信しました。", reaction),
+        };
+        let mut result = json!({
+            "success": true,
+            "event_id": event_id.to_hex(),
+            "nevent": published.nevent,
+            "reaction": reaction,
+            "preset": preset,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": message
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, event_id, &mut result).await;
+        Ok(result)
+    }
+
+    /// リアクションのプリセットを取得
+    async fn get_reaction_presets(&self) -> Result<Value> {
+        let presets = self.client.read().await.get_reaction_presets().await;
+        let count = presets.presets.len();
+        Ok(json!({
+            "success": true,
+            "default_reaction": presets.default_reaction,
+            "presets": presets.presets,
+            "count": count,
+            "message": if count == 0 {
+                "リアクションのプリセットは設定されていません（設定ファイルの reaction-presets で追加できます）。".to_string()
+            } else {
+                format!("{} 件のリアクションのプリセットがあります。", count)
+            }
+        }))
+    }
+
+    /// ノートに返信
+    async fn reply_to_note(&self, arguments: Value) -> Result<Value> {
+        let note_id = require_str_param(&arguments, &["note_id"])?;
+        let content = require_str_param(&arguments, &["content"])?;
+
+        debug!("返信投稿: note_id='{}'", note_id);
+
+        let published = self.client.read().await.reply_to_note(note_id, content).await?;
+        let event_id = published.id;
+
+        let mut result = json!({
+            "success": true,
+            "event_id": event_id.to_hex(),
+            "nevent": published.nevent,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": "返信を投稿しました。"
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, event_id, &mut result).await;
+        Ok(result)
+    }
+
+    /// 通知を取得
+    async fn get_notifications(&self, arguments: Value) -> Result<Value> {
+        let include_read = arguments
+            .get("include_read")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let client = self.client.read().await;
+        let read_at = client.notifications_read_at();
+
+        // since 未指定時は既読カーソル以降の新着のみ取得
+        let since = arguments
+            .get("since")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .or(if include_read { None } else { read_at.map(|t| t + 1) });
+
+        let types = extract_string_array(&arguments, "types");
+        if let Some(unknown) = types.iter().find(|t| !NOTIFICATION_TYPES.contains(&t.as_str())) {
+            return Err(anyhow!(
+                "不明な通知の種類です: {}（{} のいずれかを指定してください）",
+                unknown,
+                NOTIFICATION_TYPES.join(", ")
+            ));
+        }
+
+        let limit = self.extract_limit(&arguments);
+        debug!("通知取得: since={:?}, types={:?}, limit={}", since, types, limit);
+
+        let trust = resolve_trust_filter(&client, &arguments).await?;
+        let mut notifications = client.get_notifications(since, &types, limit).await?;
+        let trust_json = trust.map(|(wot, graph)| {
+            let outside = graph.apply(&mut notifications, wot.max_hops, wot.mode, |n| {
+                nostr_sdk::PublicKey::from_hex(&n.author.pubkey).ok()
+            });
+            format_trust_json(&wot, outside)
+        });
+
+        let formatted: Vec<Value> = notifications.iter().map(|n| {
+            json!({
+                "id": n.id,
+                "nevent": n.nevent,
+                "type": n.notification_type,
+                "author": {
+                    "pubkey": n.author.pubkey,
+                    "npub": n.author.npub,
+                    "name": n.author.name,
+                    "display_name": n.author.display_name,
+                    "display": n.author.display(),
+                    "picture": n.author.picture,
+                    "nip05": n.author.nip05,
+                    "nip05_verified": n.author.nip05_verified
+                },
+                "content": n.content,
+                "target_note_id": n.target_note_id,
+                "amount_sats": n.amount_sats,
+                "created_at": n.created_at,
+                "formatted_time": format_timestamp(n.created_at)
+            })
+        }).collect();
+
+        let mut result = json!({
+            "success": true,
+            "count": notifications.len(),
+            "read_at": read_at,
+            "notifications": formatted
+        });
+        if let Some(trust) = trust_json {
+            result["trust"] = trust;
+        }
+        Ok(result)
+    }
+
+    /// 通知を既読にする
+    async fn mark_notifications_read(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("通知の既読の記録")?;
+        let until = arguments
+            .get("until")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)));
+        debug!("通知既読: until={:?}", until);
+
+        let read_at = self.client.read().await.mark_notifications_read(until)?;
+
+        Ok(json!({
+            "success": true,
+            "read_at": read_at,
+            "formatted_time": format_timestamp(read_at),
+            "message": "通知を既読にしました。"
+        }))
+    }
+
+    /// ノートのリポストと引用を取得
+    async fn get_note_reposts(&self, arguments: Value) -> Result<Value> {
+        let note_id = require_str_param(&arguments, &["note_id"])?;
+
+        let limit = self.extract_limit(&arguments);
+        debug!("リポスト取得: note_id='{}', limit={}", note_id, limit);
+
+        let info = self.client.read().await.get_note_reposts(note_id, limit).await?;
+
+        let reposts: Vec<Value> = info.reposts.iter().map(|repost| {
+            json!({
+                "id": repost.id,
+                "nevent": repost.nevent,
+                "author": {
+                    "pubkey": repost.author.pubkey,
+                    "npub": repost.author.npub,
+                    "name": repost.author.name,
+                    "display_name": repost.author.display_name,
+                    "display": repost.author.display(),
+                    "picture": repost.author.picture,
+                    "nip05": repost.author.nip05,
+                    "nip05_verified": repost.author.nip05_verified
+                },
+                "created_at": repost.created_at,
+                "formatted_time": format_timestamp(repost.created_at)
+            })
+        }).collect();
+
+        let quotes: Vec<Value> = info.quotes.iter().map(format_note_json).collect();
+
+        let mut result = json!({
+            "success": true,
+            "note_id": info.note_id,
+            "repost_count": info.reposts.len(),
+            "quote_count": info.quotes.len(),
+            "reposts": reposts,
+            "quotes": quotes
+        });
+        let client = self.client.read().await;
+        hydrate_references_if_requested(&client, &arguments, &mut result).await;
+        attach_link_previews_if_requested(&client, &arguments, &mut result).await;
+        Ok(result)
+    }
+
+    /// ノートのエンゲージメントをまとめて取得
+    async fn get_note_engagement(&self, arguments: Value) -> Result<Value> {
+        let note_id = require_str_param(&arguments, &["note_id"])?;
+        debug!("エンゲージメント取得: note_id='{}'", note_id);
+
+        let engagement = self.client.read().await.get_note_engagement(note_id).await?;
+
+        Ok(json!({
+            "success": true,
+            "engagement": engagement,
+            "summary": format!(
+                "{} リアクション · {} リプライ · {} リポスト · {} 引用 · {} Zap ({} sats)",
+                format_optional_count(engagement.reactions),
+                format_optional_count(engagement.replies),
+                format_optional_count(engagement.reposts),
+                format_optional_count(engagement.quotes),
+                format_optional_count(engagement.zap_count),
+                format_optional_count(engagement.zap_total_sats)
+            )
+        }))
+    }
+
+    /// 下書き一覧を取得（Phase 3: コンテンツ解析付き）
+    async fn get_drafts(&self, arguments: Value) -> Result<Value> {
+        let limit = self.extract_limit(&arguments);
+        debug!("下書き取得: limit={}", limit);
+        let include_content = arguments.get("include_content").and_then(|v| v.as_bool()).unwrap_or(true);
+
+        let drafts = self.client.read().await.get_drafts(limit).await?;
+
+        let formatted: Vec<Value> = drafts.iter().map(|a| format_article_json(a, include_content)).collect();
+
+        Ok(json!({
+            "success": true,
+            "count": drafts.len(),
+            "drafts": formatted
+        }))
+    }
+
+    // ========================================
+    // Phase 4: 高度な機能ツール
+    // ========================================
+
+    /// アカウントの投稿と反応を集計
+    async fn get_account_analytics(&self, arguments: Value) -> Result<Value> {
+        let client = self.client.read().await;
+
+        let pubkey = match optional_str_param(&arguments, "pubkey") {
+            Some(pk) => pk.to_string(),
+            None => client.public_key()
+                .ok_or_else(|| anyhow!("pubkey を指定するか、認証してください。"))?
+                .to_hex(),
+        };
+        let get_u64 = |key: &str| arguments
+            .get(key)
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)));
+        let days = get_u64("days").unwrap_or(DEFAULT_ANALYTICS_DAYS).clamp(1, MAX_ANALYTICS_DAYS);
+        let top = get_u64("top").unwrap_or(DEFAULT_DIGEST_TOP).clamp(1, MAX_DIGEST_TOP);
+
+        debug!("アカウント分析: pubkey='{}', days={}, top={}", pubkey, days, top);
+
+        let analytics = client.get_account_analytics(&pubkey, days, top as usize).await?;
+        let received = &analytics.received;
+        let message = format!(
+            "過去 {} 日間に {} 件投稿し、リアクション {} 件・リプライ {} 件・リポスト {} 件・Zap {} 回（{} sats）を受け取りました。",
+            days, analytics.posting.notes, received.reactions, received.replies, received.reposts, received.zaps, received.zap_sats
+        );
+
+        Ok(json!({
+            "success": true,
+            "analytics": analytics,
+            "message": message
+        }))
+    }
+
+    /// フォロワー数の推移を取得
+    async fn get_follower_growth(&self, arguments: Value) -> Result<Value> {
+        let days = arguments
+            .get("days")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or(DEFAULT_ANALYTICS_DAYS)
+            .clamp(1, MAX_ANALYTICS_DAYS);
+        let limit = self.extract_limit(&arguments);
+
+        debug!("フォロワー推移: days={}, limit={}", days, limit);
+
+        let growth = self.client.read().await.get_follower_growth(days, limit as usize).await?;
+        let message = match growth.baseline {
+            Some(ref baseline) if baseline.taken_at > growth.since => format!(
+                "現在のフォロワーは {} 人です。記録を始めてから {:+} 人（増加 {} 人・減少 {} 人）。",
+                growth.followers, growth.change, growth.gained_count, growth.lost_count
+            ),
+            Some(_) => format!(
+                "現在のフォロワーは {} 人です。過去 {} 日間で {:+} 人（増加 {} 人・減少 {} 人）。",
+                growth.followers, days, growth.change, growth.gained_count, growth.lost_count
+            ),
+            None => format!("現在のフォロワーは {} 人です。", growth.followers),
+        };
+
+        Ok(json!({
+            "success": true,
+            "growth": growth,
+            "message": message
+        }))
+    }
+
+    /// Lightning インボイスを取得（NWC 不要）
+    async fn get_lightning_invoice(&self, arguments: Value) -> Result<Value> {
+        let target = require_str_param(&arguments, &["target", "lightning_address"])?;
+        let amount = arguments
+            .get("amount")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .ok_or_else(|| anyhow!("必須パラメータが不足: amount"))?;
+        let comment = optional_str_param(&arguments, "comment");
+        let note_id = optional_str_param(&arguments, "note_id");
+        let zap = arguments.get("zap").and_then(|v| v.as_bool()).unwrap_or(true);
+
+        debug!("インボイス取得: target='{}', amount={}, zap={}", target, amount, zap);
+
+        let invoice = self.client.read().await
+            .get_lightning_invoice(target, amount, comment, note_id, zap)
+            .await?;
+
+        Ok(json!({
+            "success": true,
+            "invoice": invoice,
+            "message": format!(
+                "{} sats のインボイスを取得しました。Lightning ウォレットで支払ってください。",
+                invoice.amount_sats
+            )
+        }))
+    }
+
+    /// Lightning インボイスをデコード
+    async fn decode_lightning_invoice(&self, arguments: Value) -> Result<Value> {
+        let invoice = require_str_param(&arguments, &["invoice", "bolt11"])?;
+        debug!("インボイスのデコード: {}", invoice);
+
+        let decoded = crate::bolt11::decode(invoice)?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let expired = decoded.is_expired(now);
+
+        let amount = match decoded.amount_msat {
+            Some(msat) if msat.is_multiple_of(1000) => format!("{} sats", msat / 1000),
+            Some(msat) => format!("{} msat", msat),
+            None => "金額指定なし".to_string(),
+        };
+        let message = if expired {
+            format!("{} のインボイスです（期限切れ）。", amount)
+        } else {
+            format!("{} のインボイスです。", amount)
+        };
+
+        Ok(json!({
+            "success": true,
+            "invoice": decoded,
+            "amount_sats": decoded.amount_sats(),
+            "expires_at": decoded.expires_at(),
+            "expired": expired,
+            "formatted_time": format_timestamp(decoded.timestamp),
+            "message": message
+        }))
+    }
 
-/// ツール呼び出しを処理するエグゼキュータ
-pub struct ToolExecutor {
-    /// Nostr クライアントインスタンス（NIP-46 切り替えのため RwLock で保護）
-    client: Arc<tokio::sync::RwLock<NostrClient>>,
-    /// NIP-46 セッション（Phase 6）
-    nip46_session: Arc<Nip46Session>,
-}
+    /// Lightning アドレスに NWC で支払い
+    async fn pay_lightning_address(&self, arguments: Value) -> Result<Value> {
+        let target = require_str_param(&arguments, &["target", "lightning_address"])?;
+        let amount = arguments
+            .get("amount")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .ok_or_else(|| anyhow!("必須パラメータが不足: amount"))?;
+        let comment = optional_str_param(&arguments, "comment");
+        let note_id = optional_str_param(&arguments, "note_id");
 
-impl ToolExecutor {
-    /// 新しいツールエグゼキュータを作成
-    pub fn new(client: Arc<tokio::sync::RwLock<NostrClient>>, nip46_session: Arc<Nip46Session>) -> Self {
-        Self {
-            client,
-            nip46_session,
-        }
+        debug!("Lightning 支払い: target='{}', amount={}", target, amount);
+
+        let payment = self.client.read().await
+            .pay_lightning_address(target, amount, comment, note_id)
+            .await?;
+        let message = format!(
+            "{} sats を {} に支払いました。",
+            payment.invoice.amount_sats, payment.invoice.lightning_address
+        );
+
+        Ok(json!({
+            "success": true,
+            "payment": payment,
+            "message": message
+        }))
     }
 
-    /// 指定されたツールを引数付きで実行します。
-    pub async fn execute(&self, name: &str, arguments: Value) -> Result<Value> {
-        info!("ツール実行: {} 引数: {}", name, arguments);
+    /// NWC ウォレットとの接続を確認
+    async fn check_wallet_connection(&self) -> Result<Value> {
+        debug!("NWC ウォレットの接続確認");
+
+        let status = self.client.read().await.check_wallet_connection().await?;
+        let message = if !status.relay_reachable {
+            format!("NWC リレー {} に接続できません。", status.relay_url)
+        } else if status.can_pay {
+            match status.balance_sats {
+                Some(balance) => format!("ウォレットに接続できます（残高 {} sats）。", balance),
+                None => "ウォレットに接続できます。".to_string(),
+            }
+        } else if let Some(ref error) = status.get_info_error {
+            format!("NWC リレーには接続できましたが、ウォレットから応答がありません: {}", error)
+        } else {
+            "ウォレットに接続できますが、この接続には支払い (pay_invoice) の権限がありません。".to_string()
+        };
 
-        match name {
-            "post_nostr_note" => self.post_note(arguments).await,
-            "get_nostr_timeline" => self.get_timeline(arguments).await,
-            "search_nostr_notes" => self.search_notes(arguments).await,
-            "get_nostr_profile" => self.get_profile(arguments).await,
-            // Phase 1: NIP-23 長文コンテンツ
-            "post_nostr_article" => self.post_article(arguments).await,
-            "get_nostr_articles" => self.get_articles(arguments).await,
-            "save_nostr_draft" => self.save_draft(arguments).await,
-            "get_nostr_drafts" => self.get_drafts(arguments).await,
-            // Phase 2: タイムライン拡張機能
-            "get_nostr_thread" => self.get_thread(arguments).await,
-            "react_to_note" => self.react_to_note(arguments).await,
-            "reply_to_note" => self.reply_to_note(arguments).await,
-            "get_nostr_notifications" => self.get_notifications(arguments).await,
-            // Phase 4: 高度な機能
-            "send_zap" => self.send_zap(arguments).await,
-            "get_zap_receipts" => self.get_zap_receipts(arguments).await,
-            "send_dm" => self.send_dm(arguments).await,
-            "get_dms" => self.get_dms(arguments).await,
-            "get_relay_list" => self.get_relay_list(arguments).await,
-            // Phase 6: NIP-46 Nostr Connect
-            "nostr_connect" => self.nostr_connect(arguments).await,
-            "nostr_connect_status" => self.nostr_connect_status().await,
-            "nostr_disconnect" => self.nostr_disconnect().await,
-            // NIP-B7: Blossom メディアアップロード
-            "upload_media" => self.upload_media(arguments).await,
-            "get_blossom_servers" => self.get_blossom_servers(arguments).await,
-            "set_blossom_servers" => self.set_blossom_servers(arguments).await,
-            _ => Err(anyhow!("不明なツール: {}", name)),
-        }
+        Ok(json!({
+            "success": true,
+            "wallet": status,
+            "message": message
+        }))
     }
 
-    /// 新しいノートを投稿
-    async fn post_note(&self, arguments: Value) -> Result<Value> {
+    /// ダイレクトメッセージを送信
+    async fn send_dm(&self, arguments: Value) -> Result<Value> {
+        let recipient = require_str_param(&arguments, &["recipient"])?;
         let content = require_str_param(&arguments, &["content"])?;
 
-        let event_id = self.client.read().await.post_note(content).await?;
+        debug!("DM 送信: recipient='{}'", recipient);
+
+        let published = self.client.read().await.send_dm(recipient, content).await?;
+        let event_id = published.id;
 
-        Ok(json!({
+        let mut result = json!({
             "success": true,
             "event_id": event_id.to_hex(),
-            "nevent": event_id.to_bech32().unwrap_or_default(),
-            "message": format!("ノートを投稿しました。イベント ID: {}", event_id.to_hex())
+            "nevent": published.nevent,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": "ダイレクトメッセージを送信しました。"
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, event_id, &mut result).await;
+        Ok(result)
+    }
+
+    /// ダイレクトメッセージを取得
+    async fn get_dms(&self, arguments: Value) -> Result<Value> {
+        let with = optional_str_param(&arguments, "with");
+
+        let limit = self.extract_limit(&arguments);
+        debug!("DM 取得: with={:?}, limit={}", with, limit);
+
+        let messages = self.client.read().await.get_dms(with, limit).await?;
+
+        let formatted: Vec<Value> = messages.iter().map(format_dm_json).collect();
+
+        Ok(json!({
+            "success": true,
+            "count": messages.len(),
+            "messages": formatted
         }))
     }
 
-    /// タイムラインを取得
-    async fn get_timeline(&self, arguments: Value) -> Result<Value> {
-        let limit = extract_limit(&arguments);
-        debug!("タイムライン取得: limit={}", limit);
+    /// DM の会話一覧を取得
+    async fn get_dm_conversations(&self, arguments: Value) -> Result<Value> {
+        let limit = self.extract_limit(&arguments);
+        let unread_only = arguments
+            .get("unread_only")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        debug!("DM 会話一覧取得: limit={}, unread_only={}", limit, unread_only);
+
+        let mut conversations = self.client.read().await.get_dm_conversations(limit).await?;
+        if unread_only {
+            conversations.retain(|c| c.unread_count > 0);
+        }
 
-        let notes = self.client.read().await.get_timeline(limit).await?;
-        let formatted_notes: Vec<Value> = notes.iter().map(format_note_json).collect();
+        let total_unread: u64 = conversations.iter().map(|c| c.unread_count).sum();
 
         Ok(json!({
             "success": true,
-            "count": notes.len(),
-            "notes": formatted_notes
+            "count": conversations.len(),
+            "total_unread": total_unread,
+            "conversations": conversations
         }))
     }
 
-    /// ノートを検索
-    async fn search_notes(&self, arguments: Value) -> Result<Value> {
-        let query = require_str_param(&arguments, &["query"])?;
+    /// DM を既読にする
+    async fn mark_dms_read(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("DM の既読の記録")?;
+        let with = optional_str_param(&arguments, "with");
+        debug!("DM 既読: with={:?}", with);
 
-        if query.is_empty() {
-            return Err(anyhow!("query は空にできません"));
-        }
+        self.client.read().await.mark_dms_read(with).await?;
 
-        let limit = extract_limit(&arguments);
-        debug!("ノート検索: query='{}', limit={}", query, limit);
+        Ok(json!({
+            "success": true,
+            "message": match with {
+                Some(_) => "会話を既読にしました。",
+                None => "すべての会話を既読にしました。",
+            }
+        }))
+    }
 
-        let notes = self.client.read().await.search_notes(query, limit).await?;
-        let formatted_notes: Vec<Value> = notes.iter().map(format_note_json).collect();
+    // ========================================
+    // バックアップツール
+    // ========================================
+
+    /// 自分のイベントを JSONL ファイルにエクスポート
+    async fn export_my_events(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("ファイルへのエクスポート")?;
+        let path = optional_str_param(&arguments, "path");
+        let kinds = extract_kinds(&arguments);
+        let max_events = arguments
+            .get("max_events")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or(DEFAULT_EXPORT_MAX_EVENTS)
+            .clamp(1, MAX_EXPORT_MAX_EVENTS);
+        let overwrite = arguments
+            .get("overwrite")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        debug!("イベントのエクスポート: path={:?}, kinds={:?}, max_events={}", path, kinds, max_events);
+
+        let export = self.client.read().await
+            .export_my_events(path, &kinds, max_events as usize, overwrite)
+            .await?;
 
         Ok(json!({
             "success": true,
-            "query": query,
-            "count": notes.len(),
-            "notes": formatted_notes
+            "message": format!("{} 件のイベントを {} に書き出しました。", export.count, export.path),
+            "export": export
         }))
     }
 
-    /// プロフィールを取得（Phase 3: プロフィールカード・統計情報付き）
-    async fn get_profile(&self, arguments: Value) -> Result<Value> {
-        let pubkey = require_str_param(&arguments, &["pubkey", "npub"])?;
-        debug!("プロフィール取得: {}", pubkey);
+    /// JSONL バックアップからイベントをインポートしてリレーに再送信
+    async fn import_events(&self, arguments: Value) -> Result<Value> {
+        let path = require_str_param(&arguments, &["path"])?;
+        let dry_run = arguments
+            .get("dry_run")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        debug!("イベントのインポート: path={}, dry_run={}", path, dry_run);
+
+        let import = self.client.read().await.import_events(path, dry_run).await?;
+
+        let message = if import.dry_run {
+            format!("{} 件のイベントを検証しました（不正 {} 件）。送信は行っていません。", import.valid, import.invalid)
+        } else {
+            format!("{} 件のイベントを再送信しました（失敗 {} 件、不正 {} 件）。", import.published, import.failed, import.invalid)
+        };
+
+        Ok(json!({
+            "success": true,
+            "message": message,
+            "import": import
+        }))
+    }
+
+    /// 自分のイベントを指定リレーに再送信
+    async fn rebroadcast_events(&self, arguments: Value) -> Result<Value> {
+        let relay = require_str_param(&arguments, &["relay"])?;
+        let kinds = extract_kinds(&arguments);
+        let get_u64 = |key: &str| arguments
+            .get(key)
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)));
+        let since = get_u64("since");
+        let until = get_u64("until");
+        let max_events = get_u64("max_events")
+            .unwrap_or(DEFAULT_REBROADCAST_MAX_EVENTS)
+            .clamp(1, MAX_REBROADCAST_MAX_EVENTS);
+        debug!(
+            "イベントの再送信: relay={}, kinds={:?}, since={:?}, until={:?}, max_events={}",
+            relay, kinds, since, until, max_events
+        );
+
+        let result = self.client.read().await
+            .rebroadcast_events(relay, &kinds, since, until, max_events as usize)
+            .await?;
+
+        Ok(json!({
+            "success": true,
+            "message": format!(
+                "{} 件中 {} 件のイベントを {} に再送信しました。",
+                result.found, result.published, result.relay
+            ),
+            "rebroadcast": result
+        }))
+    }
+
+    /// 送信待ちキューのイベントを取得
+    async fn get_pending_publishes(&self) -> Result<Value> {
+        let pending = self.client.read().await.get_pending_publishes().await;
+
+        Ok(json!({
+            "success": true,
+            "count": pending.len(),
+            "pending": pending
+        }))
+    }
+
+    /// 送信待ちキューのイベントを再送または削除
+    async fn flush_pending(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("送信待ちキューの操作")?;
+        let event_ids = extract_string_array(&arguments, "event_ids");
+        let discard = arguments
+            .get("discard")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        debug!("送信待ちキュー: event_ids={:?}, discard={}", event_ids, discard);
 
-        // プロフィールと統計情報を順次取得
         let client = self.client.read().await;
-        let profile_result = client.get_profile(pubkey).await;
-        let stats_result = client.get_profile_stats(pubkey).await;
-        drop(client);
+        let flush = if discard {
+            client.discard_pending_publishes(&event_ids).await?
+        } else {
+            client.flush_pending_publishes(&event_ids, true).await?
+        };
 
-        let profile = profile_result?;
+        let message = if discard {
+            format!("{} 件のイベントを送信待ちキューから削除しました。", flush.discarded)
+        } else {
+            format!(
+                "{} 件を再送しました（失敗 {} 件、キューに残り {} 件）。",
+                flush.published.len(),
+                flush.failed.len(),
+                flush.remaining
+            )
+        };
 
-        // Phase 3: プロフィールカードの構築
-        let display_name = profile.display_name.as_ref()
-            .or(profile.name.as_ref())
-            .cloned()
-            .unwrap_or_else(|| {
-                if profile.npub.len() > 16 {
-                    format!("{}...{}", &profile.npub[..12], &profile.npub[profile.npub.len()-4..])
-                } else {
-                    profile.npub.clone()
-                }
-            });
+        Ok(json!({
+            "success": true,
+            "published": flush.published,
+            "failed": flush.failed,
+            "discarded": flush.discarded,
+            "remaining": flush.remaining,
+            "message": message
+        }))
+    }
 
-        let mut profile_card = json!({
-            "avatar": profile.picture,
-            "name": display_name,
-            "nip05": profile.nip05,
-            "bio": profile.about
-        });
+    /// イベントの OpenTimestamps 証明を作成・公開
+    async fn timestamp_event(&self, arguments: Value) -> Result<Value> {
+        let event_id = require_str_param(&arguments, &["event_id"])?;
+        debug!("OpenTimestamps: event_id={}", event_id);
 
-        // 統計情報を追加（取得に成功した場合のみ）
-        if let Ok(stats) = stats_result {
-            profile_card["stats"] = json!({
-                "following": stats.following,
-                "followers": stats.followers,
-                "notes": stats.notes
-            });
-        }
+        let status = self.client.read().await.timestamp_event(event_id).await?;
+
+        let message = match (status.bitcoin_block, status.created) {
+            (Some(block), _) => format!(
+                "Bitcoin ブロック {} で確定した証明をアテステーション (Kind 1040) として公開しました。",
+                block
+            ),
+            (None, true) => "OpenTimestamps 証明を作成しました。Bitcoin での確定には数時間かかります。時間をおいて再度実行してください。".to_string(),
+            (None, false) => "証明はまだ Bitcoin で確定していません。時間をおいて再度実行してください。".to_string(),
+        };
 
         Ok(json!({
             "success": true,
-            "profile": profile,
-            "profile_card": profile_card
+            "message": message,
+            "timestamp": status
         }))
     }
 
     // ========================================
-    // Phase 1: NIP-23 長文コンテンツツール
+    // 購読（リアルタイム取得）ツール
     // ========================================
 
-    /// 長文記事を投稿
-    async fn post_article(&self, arguments: Value) -> Result<Value> {
-        let params = extract_article_params(&arguments)?;
-        let article = self.client.read().await.post_article(params).await?;
+    /// 購読を開始
+    async fn start_subscription(&self, arguments: Value) -> Result<Value> {
+        let kind = require_str_param(&arguments, &["type"])?;
+        let target = optional_str_param(&arguments, "target");
+        let name = optional_str_param(&arguments, "name");
+        debug!("購読開始: type={}, target={:?}, name={:?}", kind, target, name);
+
+        let info = self.client.read().await.start_subscription(kind, target, name).await?;
 
         Ok(json!({
             "success": true,
-            "event_id": article.id,
-            "nevent": article.nevent,
-            "naddr": article.naddr,
-            "identifier": article.identifier,
-            "title": article.title,
-            "message": format!("記事「{}」を投稿しました。", article.title)
+            "message": if info.shared_with.is_empty() {
+                "購読を開始しました。get_subscription_events で新着イベントを取得できます。"
+            } else {
+                "同じ条件の購読が既にあるため、共有して開始しました。"
+            },
+            "subscription": info
         }))
     }
 
-    /// 長文記事を取得（Phase 3: コンテンツ解析付き）
-    async fn get_articles(&self, arguments: Value) -> Result<Value> {
-        let author = optional_str_param(&arguments, "author");
-        let tags = extract_tags_param(&arguments);
-        let limit = extract_limit(&arguments);
+    /// 購読のイベントを取り出す
+    async fn get_subscription_events(&self, arguments: Value) -> Result<Value> {
+        let name = require_str_param(&arguments, &["name"])?;
+        let limit = self.extract_limit(&arguments);
+        debug!("購読イベント取得: name={}, limit={}", name, limit);
 
-        debug!("記事取得: author={:?}, tags={:?}, limit={}", author, tags, limit);
+        let (events, info) = self.client.read().await.poll_subscription(name, limit).await?;
 
-        let articles = self.client.read().await.get_articles(
-            author,
-            tags.as_deref(),
-            limit,
-        ).await?;
+        Ok(json!({
+            "success": true,
+            "count": events.len(),
+            "remaining": info.buffered,
+            "subscription": info,
+            "events": events
+        }))
+    }
+
+    /// 購読を停止
+    async fn stop_subscription(&self, arguments: Value) -> Result<Value> {
+        let name = require_str_param(&arguments, &["name"])?;
+        debug!("購読停止: name={}", name);
 
-        let formatted: Vec<Value> = articles.iter().map(format_article_json).collect();
+        self.client.read().await.stop_subscription(name).await?;
 
         Ok(json!({
             "success": true,
-            "count": articles.len(),
-            "articles": formatted
+            "message": format!("購読「{}」を停止しました。", name)
         }))
     }
 
-    /// 下書きを保存
-    async fn save_draft(&self, arguments: Value) -> Result<Value> {
-        let mut params = extract_article_params(&arguments)?;
-        params.published_at = None; // 下書きには published_at を設定しない
-        let article = self.client.read().await.save_draft(params).await?;
+    /// 購読の一覧を取得
+    async fn list_subscriptions(&self) -> Result<Value> {
+        let subscriptions = self.client.read().await.list_subscriptions().await;
 
         Ok(json!({
             "success": true,
-            "event_id": article.id,
-            "nevent": article.nevent,
-            "naddr": article.naddr,
-            "identifier": article.identifier,
-            "title": article.title,
-            "is_draft": true,
-            "message": format!("下書き「{}」を保存しました。", article.title)
+            "count": subscriptions.len(),
+            "subscriptions": subscriptions
         }))
     }
 
     // ========================================
-    // Phase 2: タイムライン拡張機能ツール
+    // NIP-38: ユーザーステータスツール
     // ========================================
 
-    /// スレッドを取得
-    async fn get_thread(&self, arguments: Value) -> Result<Value> {
-        let note_id = require_str_param(&arguments, &["note_id"])?;
-
-        let depth = arguments
-            .get("depth")
-            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
-            .unwrap_or(3)
-            .min(10);
+    /// ユーザーステータスを設定
+    async fn set_user_status(&self, arguments: Value) -> Result<Value> {
+        let content = arguments
+            .get("content")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("必須パラメータが不足: content"))?;
+        let status_type = optional_str_param(&arguments, "status_type").unwrap_or("general");
+        let url = optional_str_param(&arguments, "url");
+        let expires_in = arguments
+            .get("expires_in")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)));
 
-        debug!("スレッド取得: note_id='{}', depth={}", note_id, depth);
+        debug!("ステータス設定: type='{}', expires_in={:?}", status_type, expires_in);
 
-        let thread = self.client.read().await.get_thread(note_id, depth).await?;
+        let published = self.client.read().await
+            .set_user_status(status_type, content, url, expires_in)
+            .await?;
 
-        let formatted_replies: Vec<Value> = thread.replies.iter()
-            .map(|reply| format_thread_reply(reply))
-            .collect();
+        let message = if content.is_empty() {
+            format!("ステータス（{}）をクリアしました。", status_type)
+        } else {
+            format!("ステータス（{}）を「{}」に設定しました。", status_type, content)
+        };
 
-        Ok(json!({
+        let mut result = json!({
             "success": true,
-            "root": format_note_json(&thread.root),
-            "replies": formatted_replies,
-            "total_replies": thread.total_replies,
-            "depth": thread.depth
-        }))
+            "event_id": published.id.to_hex(),
+            "status_type": status_type,
+            "content": content,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": message
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, published.id, &mut result).await;
+        Ok(result)
     }
 
-    /// リアクションを送信
-    async fn react_to_note(&self, arguments: Value) -> Result<Value> {
-        let note_id = require_str_param(&arguments, &["note_id"])?;
-        let reaction = optional_str_param(&arguments, "reaction").unwrap_or("+");
+    /// ユーザーステータスを取得
+    async fn get_user_status(&self, arguments: Value) -> Result<Value> {
+        let client = self.client.read().await;
+
+        let mut pubkey_strs: Vec<String> = extract_string_array(&arguments, "pubkeys");
+        if let Some(pk) = optional_str_param(&arguments, "pubkey") {
+            pubkey_strs.push(pk.to_string());
+        }
+        if pubkey_strs.is_empty() {
+            let own = client.public_key()
+                .ok_or_else(|| anyhow!("pubkey を指定するか、認証してください。"))?;
+            pubkey_strs.push(own.to_hex());
+        }
 
-        debug!("リアクション送信: note_id='{}', reaction='{}'", note_id, reaction);
+        debug!("ステータス取得: {:?}", pubkey_strs);
 
-        let event_id = self.client.read().await.react_to_note(note_id, reaction).await?;
+        let users: Vec<Value> = client.get_user_status(&pubkey_strs).await?
+            .into_iter()
+            .map(|(pk, statuses)| {
+                json!({
+                    "pubkey": pk.to_hex(),
+                    "npub": pk.to_bech32().unwrap_or_default(),
+                    "statuses": statuses
+                })
+            })
+            .collect();
 
         Ok(json!({
             "success": true,
-            "event_id": event_id.to_hex(),
-            "nevent": event_id.to_bech32().unwrap_or_default(),
-            "reaction": reaction,
-            "message": format!("リアクション「{}」を送信しました。", reaction)
+            "count": users.len(),
+            "users": users
         }))
     }
 
-    /// ノートに返信
-    async fn reply_to_note(&self, arguments: Value) -> Result<Value> {
-        let note_id = require_str_param(&arguments, &["note_id"])?;
-        let content = require_str_param(&arguments, &["content"])?;
+    // ========================================
+    // NIP-58: バッジツール
+    // ========================================
 
-        debug!("返信投稿: note_id='{}'", note_id);
+    /// ユーザーのバッジ一覧を取得
+    async fn get_user_badges(&self, arguments: Value) -> Result<Value> {
+        let pubkey = require_str_param(&arguments, &["pubkey", "npub"])?;
+        debug!("バッジ取得: {}", pubkey);
 
-        let event_id = self.client.read().await.reply_to_note(note_id, content).await?;
+        let badges = self.client.read().await.get_user_badges(pubkey).await?;
 
         Ok(json!({
             "success": true,
-            "event_id": event_id.to_hex(),
-            "nevent": event_id.to_bech32().unwrap_or_default(),
-            "message": "返信を投稿しました。"
+            "count": badges.len(),
+            "badges": badges
         }))
     }
 
-    /// 通知を取得
-    async fn get_notifications(&self, arguments: Value) -> Result<Value> {
-        let since = arguments
-            .get("since")
-            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)));
-
-        let limit = extract_limit(&arguments);
-        debug!("通知取得: since={:?}, limit={}", since, limit);
+    /// バッジ定義を取得
+    async fn get_badge_definition(&self, arguments: Value) -> Result<Value> {
+        let badge = require_str_param(&arguments, &["badge", "naddr"])?;
+        debug!("バッジ定義取得: {}", badge);
 
-        let notifications = self.client.read().await.get_notifications(since, limit).await?;
-
-        let formatted: Vec<Value> = notifications.iter().map(|n| {
-            json!({
-                "id": n.id,
-                "nevent": n.nevent,
-                "type": n.notification_type,
-                "author": {
-                    "pubkey": n.author.pubkey,
-                    "npub": n.author.npub,
-                    "name": n.author.name,
-                    "display_name": n.author.display_name,
-                    "display": n.author.display(),
-                    "picture": n.author.picture,
-                    "nip05": n.author.nip05
-                },
-                "content": n.content,
-                "target_note_id": n.target_note_id,
-                "created_at": n.created_at,
-                "formatted_time": format_timestamp(n.created_at)
-            })
-        }).collect();
+        let definition = self.client.read().await.get_badge_definition(badge).await?;
 
         Ok(json!({
             "success": true,
-            "count": notifications.len(),
-            "notifications": formatted
+            "badge": definition
         }))
     }
 
-    /// 下書き一覧を取得（Phase 3: コンテンツ解析付き）
-    async fn get_drafts(&self, arguments: Value) -> Result<Value> {
-        let limit = extract_limit(&arguments);
-        debug!("下書き取得: limit={}", limit);
+    /// バッジを授与
+    async fn award_badge(&self, arguments: Value) -> Result<Value> {
+        let badge = require_str_param(&arguments, &["badge", "naddr"])?;
+        let recipients = extract_string_array(&arguments, "recipients");
 
-        let drafts = self.client.read().await.get_drafts(limit).await?;
+        debug!("バッジ授与: badge='{}', recipients={}", badge, recipients.len());
 
-        let formatted: Vec<Value> = drafts.iter().map(format_article_json).collect();
+        let published = self.client.read().await.award_badge(badge, &recipients).await?;
+        let event_id = published.id;
 
-        Ok(json!({
+        let mut result = json!({
             "success": true,
-            "count": drafts.len(),
-            "drafts": formatted
-        }))
+            "event_id": event_id.to_hex(),
+            "nevent": published.nevent,
+            "recipients": recipients.len(),
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": format!("{} 人にバッジを授与しました。", recipients.len())
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, event_id, &mut result).await;
+        Ok(result)
     }
 
     // ========================================
-    // Phase 4: 高度な機能ツール
+    // NIP-30 / NIP-51: カスタム絵文字ツール
     // ========================================
 
-    /// Zap を送信
-    async fn send_zap(&self, arguments: Value) -> Result<Value> {
-        let target = require_str_param(&arguments, &["target"])?;
-        let amount = arguments
-            .get("amount")
-            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
-            .ok_or_else(|| anyhow!("必須パラメータが不足: amount"))?;
-
-        if amount == 0 {
-            return Err(anyhow!("amount は 0 より大きい必要があります"));
-        }
-
-        let comment = optional_str_param(&arguments, "comment");
+    /// 絵文字リストと絵文字セットを取得
+    async fn get_emoji_sets(&self, arguments: Value) -> Result<Value> {
+        let pubkey = optional_str_param(&arguments, "pubkey");
+        debug!("絵文字セット取得: {:?}", pubkey);
 
-        debug!("Zap 送信: target='{}', amount={}, comment={:?}", target, amount, comment);
+        let collection = self.client.read().await.get_emoji_sets(pubkey).await?;
+        let total: usize = collection.emojis.len() + collection.sets.iter().map(|s| s.emojis.len()).sum::<usize>();
 
-        self.client.read().await.send_zap(target, amount, comment).await
+        Ok(json!({
+            "success": true,
+            "emojis": collection.emojis,
+            "sets": collection.sets,
+            "message": format!("{} 件の絵文字セットと計 {} 件の絵文字が見つかりました。", collection.sets.len(), total)
+        }))
     }
 
-    /// Zap レシートを取得
-    async fn get_zap_receipts(&self, arguments: Value) -> Result<Value> {
-        let note_id = require_str_param(&arguments, &["note_id"])?;
+    /// 絵文字セットに絵文字を追加
+    async fn add_emoji(&self, arguments: Value) -> Result<Value> {
+        let set = require_str_param(&arguments, &["set", "identifier"])?;
+        let shortcode = require_str_param(&arguments, &["shortcode"])?;
+        let url = require_str_param(&arguments, &["url"])?;
+        let title = optional_str_param(&arguments, "title");
+        debug!("絵文字追加: set='{}', shortcode='{}'", set, shortcode);
 
-        let limit = extract_limit(&arguments);
-        debug!("Zap レシート取得: note_id='{}', limit={}", note_id, limit);
+        let (emoji_set, published, created) = self.client.read().await.add_emoji(set, shortcode, url, title).await?;
+        let event_id = published.id;
 
-        let receipts = self.client.read().await.get_zap_receipts(note_id, limit).await?;
+        let mut result = json!({
+            "success": true,
+            "event_id": event_id.to_hex(),
+            "set": emoji_set,
+            "created": created,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": if created {
+                format!("絵文字セット「{}」を作成し、:{}: を追加しました。", emoji_set.identifier, shortcode.trim_matches(':'))
+            } else {
+                format!("絵文字セット「{}」に :{}: を追加しました。", emoji_set.identifier, shortcode.trim_matches(':'))
+            }
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, event_id, &mut result).await;
+        Ok(result)
+    }
 
-        let total_sats: u64 = receipts.iter().map(|r| r.amount_sats).sum();
+    /// 絵文字セットから絵文字を削除
+    async fn remove_emoji(&self, arguments: Value) -> Result<Value> {
+        let set = require_str_param(&arguments, &["set", "identifier"])?;
+        let shortcode = require_str_param(&arguments, &["shortcode"])?;
+        debug!("絵文字削除: set='{}', shortcode='{}'", set, shortcode);
 
-        let formatted: Vec<Value> = receipts.iter().map(|receipt| {
-            let mut result = json!({
-                "id": receipt.id,
-                "nevent": receipt.nevent,
-                "amount_sats": receipt.amount_sats,
-                "created_at": receipt.created_at,
-                "formatted_time": format_timestamp(receipt.created_at)
-            });
+        let (emoji_set, published) = self.client.read().await.remove_emoji(set, shortcode).await?;
+        let event_id = published.id;
 
-            if let Some(ref sender) = receipt.sender {
-                result["sender"] = json!({
-                    "pubkey": sender.pubkey,
-                    "npub": sender.npub,
-                    "name": sender.name,
-                    "display_name": sender.display_name,
-                    "display": sender.display(),
-                    "picture": sender.picture,
-                    "nip05": sender.nip05
-                });
-            }
+        let mut result = json!({
+            "success": true,
+            "event_id": event_id.to_hex(),
+            "set": emoji_set,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": format!("絵文字セット「{}」から :{}: を削除しました。", emoji_set.identifier, shortcode.trim_matches(':'))
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, event_id, &mut result).await;
+        Ok(result)
+    }
 
-            if let Some(ref comment) = receipt.comment {
-                result["comment"] = json!(comment);
-            }
+    // ========================================
+    // NIP-71: 動画ツール
+    // ========================================
 
-            if let Some(ref target_note_id) = receipt.target_note_id {
-                result["target_note_id"] = json!(target_note_id);
-            }
+    /// 動画フィードを取得
+    async fn get_video_feed(&self, arguments: Value) -> Result<Value> {
+        let short = match optional_str_param(&arguments, "video_type").unwrap_or("all") {
+            "short" => Some(true),
+            "normal" => Some(false),
+            _ => None,
+        };
+        let follows_only = arguments
+            .get("follows_only")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let limit = self.extract_limit(&arguments);
 
-            if let Some(ref target_pubkey) = receipt.target_pubkey {
-                result["target_pubkey"] = json!(target_pubkey);
-            }
+        debug!("動画フィード取得: short={:?}, follows_only={}, limit={}", short, follows_only, limit);
 
-            result
-        }).collect();
+        let videos = self.client.read().await
+            .get_video_feed(follows_only, short, limit)
+            .await?;
 
         Ok(json!({
             "success": true,
-            "count": receipts.len(),
-            "total_sats": total_sats,
-            "zap_receipts": formatted
+            "count": videos.len(),
+            "videos": videos
         }))
     }
 
-    /// ダイレクトメッセージを送信
-    async fn send_dm(&self, arguments: Value) -> Result<Value> {
-        let recipient = require_str_param(&arguments, &["recipient"])?;
-        let content = require_str_param(&arguments, &["content"])?;
+    /// 動画を投稿（必要に応じて Blossom にアップロード）
+    async fn post_video(&self, arguments: Value) -> Result<Value> {
+        let title = require_str_param(&arguments, &["title"])?.to_string();
+        let content_type_param = optional_str_param(&arguments, "content_type");
 
-        debug!("DM 送信: recipient='{}'", recipient);
+        let mut media = if let Some(url) = optional_str_param(&arguments, "url") {
+            crate::content::MediaMetadata {
+                url: url.to_string(),
+                mime_type: content_type_param.map(String::from),
+                ..Default::default()
+            }
+        } else {
+            if optional_str_param(&arguments, "file_path").is_none() && optional_str_param(&arguments, "data").is_none() {
+                return Err(anyhow!("url、file_path、data のいずれかを指定してください"));
+            }
+            let (data, filename) = load_media_data(&arguments)
+                .await
+                .context("動画ファイルの読み込みに失敗しました")?;
+            let content_type = content_type_param
+                .unwrap_or_else(|| crate::blossom::guess_content_type(&filename));
+            if !content_type.starts_with("video/") {
+                return Err(anyhow!("動画ファイルではありません（{}）", content_type));
+            }
+            let server_url = self.resolve_blossom_server(optional_str_param(&arguments, "server")).await;
 
-        let event_id = self.client.read().await.send_dm(recipient, content).await?;
+            debug!("動画アップロード: file={}, type={}, server={}", filename, content_type, server_url);
 
-        Ok(json!({
+            let descriptor = self.client.read().await
+                .upload_media(data, content_type, &server_url)
+                .await?;
+
+            crate::content::MediaMetadata {
+                url: descriptor.url,
+                mime_type: Some(content_type.to_string()),
+                sha256: Some(descriptor.sha256),
+                size: Some(descriptor.size),
+                ..Default::default()
+            }
+        };
+
+        media.image = optional_str_param(&arguments, "thumbnail").map(String::from);
+        media.dim = optional_str_param(&arguments, "dimensions").map(String::from);
+        media.duration = arguments.get("duration").and_then(|v| v.as_f64());
+
+        let params = VideoParams {
+            title,
+            description: optional_str_param(&arguments, "description").map(String::from),
+            media,
+            short: arguments.get("short").and_then(|v| v.as_bool()).unwrap_or(false),
+            hashtags: extract_tags_param(&arguments),
+        };
+
+        debug!("動画投稿: title='{}', short={}", params.title, params.short);
+
+        let (video, published) = self.client.read().await.post_video(params).await?;
+        let message = format!("動画「{}」を投稿しました。", video.title);
+
+        let mut result = json!({
             "success": true,
-            "event_id": event_id.to_hex(),
-            "nevent": event_id.to_bech32().unwrap_or_default(),
-            "message": "ダイレクトメッセージを送信しました。"
-        }))
+            "video": video,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": message
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, published.id, &mut result).await;
+        Ok(result)
     }
 
-    /// ダイレクトメッセージを取得
-    async fn get_dms(&self, arguments: Value) -> Result<Value> {
-        let with = optional_str_param(&arguments, "with");
+    // ========================================
+    // NIP-56: 通報ツール
+    // ========================================
 
-        let limit = extract_limit(&arguments);
-        debug!("DM 取得: with={:?}, limit={}", with, limit);
+    /// ユーザーまたはノートを通報
+    async fn report_content(&self, arguments: Value) -> Result<Value> {
+        let pubkey = optional_str_param(&arguments, "pubkey");
+        let note_id = optional_str_param(&arguments, "note_id");
+        let report_type = require_str_param(&arguments, &["report_type", "type"])?;
+        let reason = optional_str_param(&arguments, "reason");
 
-        let messages = self.client.read().await.get_dms(with, limit).await?;
+        debug!("通報: pubkey={:?}, note_id={:?}, type='{}'", pubkey, note_id, report_type);
 
-        let formatted: Vec<Value> = messages.iter().map(format_dm_json).collect();
+        let published = self.client.read().await
+            .report_content(pubkey, note_id, report_type, reason)
+            .await?;
+        let event_id = published.id;
 
-        Ok(json!({
+        let mut result = json!({
             "success": true,
-            "count": messages.len(),
-            "messages": formatted
-        }))
+            "event_id": event_id.to_hex(),
+            "nevent": published.nevent,
+            "report_type": report_type,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": format!("通報（{}）を送信しました。", report_type)
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, event_id, &mut result).await;
+        Ok(result)
     }
 
     // ========================================
@@ -1091,20 +5001,24 @@ impl ToolExecutor {
     /// NIP-46 接続を開始（QR コード生成またはバンカー接続）
     /// Step 6-3/6-4: 接続完了時に自動的に NostrClient のサイナーを切り替え
     async fn nostr_connect(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("リモートサイナーへの接続")?;
         let bunker_uri = optional_str_param(&arguments, "bunker_uri");
+        let label = normalize_signer_label(optional_str_param(&arguments, "label"))?;
+        let session = self.nip46_registry.session(&label).await;
 
         if let Some(uri) = bunker_uri {
             // バンカー方式: 即座に接続
-            debug!("NIP-46 バンカー接続: {}", uri);
-            self.nip46_session.start_bunker_connect(uri).await?;
+            debug!("NIP-46 バンカー接続 ({}): {}", label, uri);
+            session.start_bunker_connect(uri).await?;
 
             // 接続成功 → NostrClient にサイナーを設定
-            self.activate_nip46_signer().await?;
+            self.activate_nip46_signer(&label, &session).await?;
 
-            let status = self.nip46_session.status_json().await;
+            let status = session.status_json().await;
             Ok(json!({
                 "success": true,
                 "mode": "bunker",
+                "label": label,
                 "status": status["status"],
                 "message": "NIP-46 バンカー接続が完了しました。リモート署名が有効です。",
                 "user_pubkey": status.get("user_pubkey"),
@@ -1112,12 +5026,13 @@ impl ToolExecutor {
             }))
         } else {
             // クライアント発行方式: QR コード生成
-            debug!("NIP-46 クライアント接続開始（QR コード生成）");
-            let result = self.nip46_session.start_client_connect().await?;
+            debug!("NIP-46 クライアント接続開始（QR コード生成, {}）", label);
+            let result = session.start_client_connect().await?;
 
             // バックグラウンドで接続完了を監視し、接続完了時にサイナーを切り替える
-            let session = self.nip46_session.clone();
+            let registry = self.nip46_registry.clone();
             let client = self.client.clone();
+            let label_for_task = label.clone();
             tokio::spawn(async move {
                 // 接続完了を定期的にチェック（最大120秒）
                 for _ in 0..60 {
@@ -1129,7 +5044,8 @@ impl ToolExecutor {
                                 if let Err(e) = client_guard.enable_nip46_signer(signer, pubkey).await {
                                     tracing::warn!("NIP-46 サイナーの有効化に失敗: {}", e);
                                 } else {
-                                    tracing::info!("NIP-46 サイナーをバックグラウンドで有効化しました");
+                                    registry.set_active(&label_for_task).await;
+                                    tracing::info!("NIP-46 サイナー '{}' をバックグラウンドで有効化しました", label_for_task);
                                 }
                             }
                         }
@@ -1141,6 +5057,7 @@ impl ToolExecutor {
             Ok(json!({
                 "success": true,
                 "mode": "client",
+                "label": label,
                 "status": "waiting",
                 "message": "QR コードをリモートサイナーアプリ（Primal、Amber 等）でスキャンしてください。接続完了時に自動的にリモート署名が有効になります。",
                 "connect_uri": result.connect_uri,
@@ -1149,42 +5066,235 @@ impl ToolExecutor {
         }
     }
 
-    /// NIP-46 セッションのサイナーを NostrClient に設定するヘルパー
-    async fn activate_nip46_signer(&self) -> Result<()> {
-        if let Some(signer) = self.nip46_session.get_nostr_connect().await {
-            if let Some(pubkey) = self.nip46_session.connected_pubkey().await {
+    /// NIP-46 セッションのサイナーを NostrClient に設定し、アクティブなサイナーとして記録するヘルパー
+    async fn activate_nip46_signer(&self, label: &str, session: &Nip46Session) -> Result<()> {
+        if let Some(signer) = session.get_nostr_connect().await {
+            if let Some(pubkey) = session.connected_pubkey().await {
                 let mut client_guard = self.client.write().await;
                 client_guard.enable_nip46_signer(signer, pubkey).await?;
+                self.nip46_registry.set_active(label).await;
             }
         }
         Ok(())
     }
 
+    /// 対象のサイナーのラベルを決める（省略時はアクティブなサイナー、なければ default）
+    async fn resolve_signer_label(&self, arguments: &Value) -> Result<String> {
+        match optional_str_param(arguments, "label") {
+            Some(label) => normalize_signer_label(Some(label)),
+            None => Ok(self
+                .nip46_registry
+                .active_label()
+                .await
+                .unwrap_or_else(|| DEFAULT_SIGNER_LABEL.to_string())),
+        }
+    }
+
     /// NIP-46 接続ステータスを確認
-    async fn nostr_connect_status(&self) -> Result<Value> {
+    async fn nostr_connect_status(&self, arguments: Value) -> Result<Value> {
         debug!("NIP-46 接続ステータス確認");
-        let status = self.nip46_session.status_json().await;
-        let nip46_active = self.client.read().await.is_nip46_active().await;
+        let label = self.resolve_signer_label(&arguments).await?;
+        let active_label = self.nip46_registry.active_label().await;
+        let status = match self.nip46_registry.get(&label).await {
+            Some(session) => session.status_json().await,
+            None => json!({
+                "status": "disconnected",
+                "message": "NIP-46 リモートサイナーに接続されていません。"
+            }),
+        };
+        let client = self.client.read().await;
+        let nip46_active = client.is_nip46_active().await;
 
         Ok(json!({
             "success": true,
+            "label": label,
+            "active_signer": active_label,
             "connection": status,
-            "signer_active": nip46_active
+            "signer_active": nip46_active && active_label.as_deref() == Some(label.as_str()),
+            "write_access": client.has_write_access()
         }))
     }
 
     /// NIP-46 リモートサイナーとの接続を切断
-    async fn nostr_disconnect(&self) -> Result<Value> {
-        debug!("NIP-46 切断");
-        self.nip46_session.disconnect().await?;
+    async fn nostr_disconnect(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("リモートサイナーの切断")?;
+        let label = self.resolve_signer_label(&arguments).await?;
+        debug!("NIP-46 切断: {}", label);
+        let session = self
+            .nip46_registry
+            .get(&label)
+            .await
+            .ok_or_else(|| anyhow!("サイナー '{}' は接続されていません", label))?;
+        session.disconnect().await?;
+
+        // アクティブなサイナーだった場合は NostrClient のサイナーも無効化
+        if self.nip46_registry.remove(&label).await {
+            let mut client_guard = self.client.write().await;
+            client_guard.disable_nip46_signer().await;
+
+            return Ok(json!({
+                "success": true,
+                "label": label,
+                "message": "NIP-46 リモートサイナーとの接続を切断しました。読み取り専用モードに戻ります。set_active_signer で他のサイナーに切り替えられます。"
+            }));
+        }
+
+        Ok(json!({
+            "success": true,
+            "label": label,
+            "message": format!("サイナー '{}' との接続を切断しました。", label)
+        }))
+    }
+
+    /// 登録済みのリモートサイナーを一覧表示
+    async fn list_signers(&self) -> Result<Value> {
+        debug!("NIP-46 サイナー一覧");
+        let active_label = self.nip46_registry.active_label().await;
+
+        let mut signers = Vec::new();
+        for (label, session) in self.nip46_registry.sessions().await {
+            let status = session.status_json().await;
+            signers.push(json!({
+                "label": label,
+                "active": active_label.as_deref() == Some(label.as_str()),
+                "status": status["status"],
+                "user_npub": status.get("user_npub")
+            }));
+        }
+
+        Ok(json!({
+            "success": true,
+            "active_signer": active_label,
+            "count": signers.len(),
+            "signers": signers
+        }))
+    }
+
+    /// 書き込みに使うリモートサイナーを切り替える
+    async fn set_active_signer(&self, arguments: Value) -> Result<Value> {
+        self.client.read().await.refuse_in_dry_run("署名者の切り替え")?;
+        let label = normalize_signer_label(Some(require_str_param(&arguments, &["label"])?))?;
+        debug!("NIP-46 アクティブサイナー切り替え: {}", label);
+
+        let session = self
+            .nip46_registry
+            .get(&label)
+            .await
+            .ok_or_else(|| anyhow!("サイナー '{}' は登録されていません。nostr_connect で接続してください", label))?;
+        if !session.is_connected().await {
+            let status = session.status_json().await;
+            return Err(anyhow!(
+                "サイナー '{}' は接続済みではありません（{}）",
+                label,
+                status["status"].as_str().unwrap_or("unknown")
+            ));
+        }
+
+        self.activate_nip46_signer(&label, &session).await?;
+        let pubkey = session.connected_pubkey().await;
 
-        // NostrClient のサイナーも無効化
-        let mut client_guard = self.client.write().await;
-        client_guard.disable_nip46_signer().await;
+        Ok(json!({
+            "success": true,
+            "active_signer": label,
+            "user_npub": pubkey.and_then(|pk| pk.to_bech32().ok()),
+            "message": format!("サイナー '{}' で署名するように切り替えました。", label)
+        }))
+    }
+
+    /// 現在の署名者と、その署名・暗号化の可否を返す
+    async fn get_signer_info(&self) -> Result<Value> {
+        debug!("署名者情報の取得");
+        let client = self.client.read().await;
+        let nip46_active = client.is_nip46_active().await;
+        let write_access = client.has_write_access();
+        let pubkey = client.public_key();
+        let degraded = client.nip46_degraded().map(str::to_string);
+        let delegation = client.delegation_conditions();
+        drop(client);
+
+        let all_permissions = json!({
+            "sign_kinds": "all",
+            "nip04": { "encrypt": true, "decrypt": true },
+            "nip44": { "encrypt": true, "decrypt": true }
+        });
+
+        let (signer, label, scope, permissions, message) = if nip46_active {
+            let label = self.nip46_registry.active_label().await;
+            let requested = match &label {
+                Some(label) => match self.nip46_registry.get(label).await {
+                    Some(session) => session.requested_perms().await,
+                    None => None,
+                },
+                None => None,
+            };
+            match requested {
+                Some(perms) => (
+                    "nip46",
+                    label,
+                    "scoped",
+                    summarize_perms(&perms),
+                    "NIP-46 リモートサイナーで署名します。接続時に要求した権限の範囲で署名・暗号化できます。",
+                ),
+                None => (
+                    "nip46",
+                    label,
+                    "signer-defined",
+                    all_permissions,
+                    "NIP-46 リモートサイナーで署名します。権限はサイナー側の設定に依存し、操作ごとに承認を求められる場合があります。",
+                ),
+            }
+        } else if let Some(conditions) = delegation.filter(|_| write_access) {
+            let kinds: Vec<u16> = conditions.inner().into_iter()
+                .filter_map(|condition| match condition {
+                    nostr_sdk::nips::nip26::Condition::Kind(kind) => Some(kind),
+                    _ => None,
+                })
+                .collect();
+            (
+                "delegation",
+                None,
+                "scoped",
+                json!({
+                    "sign_kinds": if kinds.is_empty() { json!("all") } else { json!(kinds) },
+                    "conditions": conditions.to_string(),
+                    "nip04": { "encrypt": false, "decrypt": false },
+                    "nip44": { "encrypt": false, "decrypt": false }
+                }),
+                "NIP-26 の委任で署名します。委任の条件（conditions）を満たすイベントのみ公開でき、暗号化は委任者の鍵では行えません。委任者としての帰属は表示のみで、著者を指定した取得では委任者のノートとして検索されません。",
+            )
+        } else if write_access {
+            (
+                "local",
+                None,
+                "full",
+                all_permissions,
+                "ローカル秘密鍵で署名します。すべての Kind の署名と NIP-04/NIP-44 暗号化が可能です。",
+            )
+        } else {
+            (
+                "none",
+                None,
+                "none",
+                json!({
+                    "sign_kinds": [],
+                    "nip04": { "encrypt": false, "decrypt": false },
+                    "nip44": { "encrypt": false, "decrypt": false }
+                }),
+                "署名者がありません（読み取り専用モード）。設定ファイルに nsec を設定するか、nostr_connect で接続してください。",
+            )
+        };
 
         Ok(json!({
             "success": true,
-            "message": "NIP-46 リモートサイナーとの接続を切断しました。読み取り専用モードに戻ります。"
+            "signer": signer,
+            "label": label,
+            "pubkey": pubkey.map(|pk| pk.to_hex()),
+            "npub": pubkey.and_then(|pk| pk.to_bech32().ok()),
+            "write_access": write_access,
+            "degraded": degraded,
+            "scope": scope,
+            "permissions": permissions,
+            "message": message
         }))
     }
 
@@ -1194,59 +5304,18 @@ impl ToolExecutor {
 
     /// メディアファイルを Blossom サーバーにアップロード
     async fn upload_media(&self, arguments: Value) -> Result<Value> {
-        let file_path = optional_str_param(&arguments, "file_path");
-        let data_base64 = optional_str_param(&arguments, "data");
         let content_type_param = optional_str_param(&arguments, "content_type");
         let server_param = optional_str_param(&arguments, "server");
-        let filename_param = optional_str_param(&arguments, "filename");
 
         // ファイルデータの取得
-        let (data, guessed_filename) = if let Some(path) = file_path {
-            let file_data = tokio::fs::read(path)
-                .await
-                .context(format!("ファイルの読み込みに失敗: {}", path))?;
-            let name = std::path::Path::new(path)
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("file")
-                .to_string();
-            (file_data, name)
-        } else if let Some(b64) = data_base64 {
-            let file_data = base64::engine::general_purpose::STANDARD
-                .decode(b64)
-                .context("Base64 データのデコードに失敗")?;
-            let name = filename_param.unwrap_or("file").to_string();
-            (file_data, name)
-        } else {
-            return Err(anyhow!(
-                "file_path または data のいずれかを指定してください"
-            ));
-        };
+        let (data, guessed_filename) = load_media_data(&arguments).await?;
 
         // MIME タイプの決定
         let content_type = content_type_param
             .unwrap_or_else(|| crate::blossom::guess_content_type(&guessed_filename));
 
         // Blossom サーバー URL の決定
-        let server_url = if let Some(server) = server_param {
-            server.to_string()
-        } else {
-            // 1. ユーザーの Kind 10063 サーバーリストから取得を試みる
-            let servers = self
-                .client
-                .read()
-                .await
-                .get_blossom_servers(None)
-                .await
-                .unwrap_or_default();
-
-            if let Some(first) = servers.first() {
-                first.clone()
-            } else {
-                // 2. デフォルトサーバーを使用
-                crate::blossom::DEFAULT_BLOSSOM_SERVERS[0].to_string()
-            }
-        };
+        let server_url = self.resolve_blossom_server(server_param).await;
 
         debug!(
             "メディアアップロード: file={}, type={}, server={}",
@@ -1272,6 +5341,30 @@ impl ToolExecutor {
         }))
     }
 
+    /// Blossom サーバー URL を決定するヘルパー
+    /// 指定がなければユーザーの Kind 10063 サーバーリスト、次にデフォルトサーバーを使用します。
+    async fn resolve_blossom_server(&self, server_param: Option<&str>) -> String {
+        if let Some(server) = server_param {
+            server.to_string()
+        } else {
+            // 1. ユーザーの Kind 10063 サーバーリストから取得を試みる
+            let servers = self
+                .client
+                .read()
+                .await
+                .get_blossom_servers(None)
+                .await
+                .unwrap_or_default();
+
+            if let Some(first) = servers.first() {
+                first.clone()
+            } else {
+                // 2. デフォルトサーバーを使用
+                crate::blossom::DEFAULT_BLOSSOM_SERVERS[0].to_string()
+            }
+        }
+    }
+
     /// Blossom サーバーリストを取得
     async fn get_blossom_servers(&self, arguments: Value) -> Result<Value> {
         let pubkey = optional_str_param(&arguments, "pubkey");
@@ -1310,20 +5403,24 @@ impl ToolExecutor {
 
         debug!("Blossom サーバーリスト設定: {:?}", servers);
 
-        let event_id = self
+        let published = self
             .client
             .read()
             .await
             .publish_blossom_servers(&servers)
             .await?;
 
-        Ok(json!({
+        let mut result = json!({
             "success": true,
-            "event_id": event_id.to_hex(),
+            "event_id": published.id.to_hex(),
             "count": servers.len(),
             "servers": servers,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
             "message": format!("Blossom サーバーリストを公開しました ({} サーバー)", servers.len())
-        }))
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, published.id, &mut result).await;
+        Ok(result)
     }
 
     /// リレーリストを取得
@@ -1350,12 +5447,91 @@ impl ToolExecutor {
             "relays": formatted_relays
         }))
     }
+
+    /// リレーの接続・取得速度を計測
+    async fn benchmark_relays(&self, arguments: Value) -> Result<Value> {
+        let relays = extract_string_array(&arguments, "relays");
+        debug!("リレー計測: {:?}", relays);
+
+        let results = self.client.read().await.benchmark_relays(&relays).await?;
+        let reachable = results.iter().filter(|b| b.connected).count();
+        let message = match results.first().filter(|b| b.connected) {
+            Some(best) => format!(
+                "{} 件中 {} 件のリレーに接続できました。最も速いのは {} です（接続 {} ms・取得 {} ms）。",
+                results.len(), reachable, best.url,
+                best.connect_ms.unwrap_or_default(), best.query_ms.map_or("-".to_string(), |ms| ms.to_string())
+            ),
+            None => format!("{} 件のリレーのいずれにも接続できませんでした。", results.len()),
+        };
+
+        Ok(json!({
+            "success": true,
+            "count": results.len(),
+            "reachable": reachable,
+            "relays": results,
+            "message": message
+        }))
+    }
+
+    /// NIP-66 のモニターのレポートからリレーを探す
+    async fn discover_relays(&self, arguments: Value) -> Result<Value> {
+        let get_u64 = |key: &str| arguments.get(key).and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)));
+        let query = RelayDiscoveryQuery {
+            nips: arguments.get("nips")
+                .and_then(|v| v.as_array())
+                .map(|nips| nips.iter().filter_map(|n| n.as_u64()).filter_map(|n| u16::try_from(n).ok()).collect())
+                .unwrap_or_default(),
+            country: optional_str_param(&arguments, "country").map(str::to_string),
+            geohash: optional_str_param(&arguments, "geohash").map(str::to_string),
+            network: optional_str_param(&arguments, "network").map(str::to_string),
+            open_only: arguments.get("open_only").and_then(|v| v.as_bool()).unwrap_or(false),
+            monitors: extract_string_array(&arguments, "monitors"),
+            monitor_relays: extract_string_array(&arguments, "monitor_relays"),
+            min_availability: arguments.get("min_availability").and_then(|v| v.as_f64()).unwrap_or(0.0),
+        };
+        if !(0.0..=1.0).contains(&query.min_availability) {
+            return Err(anyhow!("min_availability は 0〜1 の範囲で指定してください"));
+        }
+        let limit = get_u64("limit").unwrap_or(DEFAULT_DISCOVER_RELAYS).min(MAX_DISCOVER_RELAYS) as usize;
+        let add_to_pool = get_u64("add_to_pool").unwrap_or(0).min(limit as u64) as usize;
+        debug!("リレー発見: {:?} limit={} add_to_pool={}", query, limit, add_to_pool);
+
+        let discovery = self.client.read().await.discover_relays(query, limit, add_to_pool).await?;
+        let message = if discovery.reports == 0 {
+            "リレーモニターのレポート (Kind 30166) が見つかりませんでした。monitor_relays を指定してください。".to_string()
+        } else if discovery.added_to_pool.is_empty() {
+            format!(
+                "{} 件のモニターのレポートから、条件に合うリレーが {} 件見つかりました。",
+                discovery.monitors, discovery.matched
+            )
+        } else {
+            format!(
+                "{} 件のモニターのレポートから、条件に合うリレーが {} 件見つかりました。{} 件をリレープールに追加しました。",
+                discovery.monitors, discovery.matched, discovery.added_to_pool.len()
+            )
+        };
+
+        Ok(json!({
+            "success": true,
+            "monitors": discovery.monitors,
+            "reports": discovery.reports,
+            "matched": discovery.matched,
+            "count": discovery.relays.len(),
+            "relays": discovery.relays,
+            "added_to_pool": discovery.added_to_pool,
+            "message": message
+        }))
+    }
 }
 
 /// 記事を JSON 表示形式にフォーマットするヘルパー（Phase 3: コンテンツ解析対応）
-fn format_article_json(article: &crate::nostr_client::ArticleInfo) -> Value {
+///
+/// 推定読了時間（`reading_time_minutes`）と見出しの目次（`toc`）を付けます。`include_content` が false の場合は
+/// 本文を省き、一覧を要約するときにコンテキストを消費しないようにします。
+fn format_article_json(article: &crate::nostr_client::ArticleInfo, include_content: bool) -> Value {
     let formatted_time = format_timestamp(article.created_at);
     let parsed = content::parse_content(&article.content);
+    let toc = content::extract_headings(&article.content);
 
     let mut result = json!({
         "id": article.id,
@@ -1371,8 +5547,17 @@ fn format_article_json(article: &crate::nostr_client::ArticleInfo) -> Value {
         "created_at": article.created_at,
         "formatted_time": formatted_time,
         "tags": article.tags,
-        "is_draft": article.is_draft
+        "is_draft": article.is_draft,
+        "reading_time_minutes": content::estimate_reading_minutes(&article.content)
     });
+    if !toc.is_empty() {
+        result["toc"] = json!(toc);
+    }
+    if !include_content {
+        if let Some(fields) = result.as_object_mut() {
+            fields.remove("content");
+        }
+    }
 
     // Phase 3: メディア検出
     if !parsed.media.is_empty() {
@@ -1388,6 +5573,9 @@ fn format_article_json(article: &crate::nostr_client::ArticleInfo) -> Value {
         if !parsed.references.is_empty() {
             parsed_content["references"] = json!(parsed.references);
         }
+        if !parsed.links.is_empty() {
+            parsed_content["links"] = json!(parsed.links);
+        }
         result["parsed_content"] = parsed_content;
     }
 
@@ -1397,7 +5585,7 @@ fn format_article_json(article: &crate::nostr_client::ArticleInfo) -> Value {
 /// スレッドリプライを再帰的に JSON にフォーマット
 fn format_thread_reply(reply: &ThreadReply) -> Value {
     let children: Vec<Value> = reply.replies.iter()
-        .map(|r| format_thread_reply(r))
+        .map(format_thread_reply)
         .collect();
 
     json!({
@@ -1421,7 +5609,8 @@ fn format_dm_json(dm: &DirectMessageInfo) -> Value {
             "display_name": dm.author.display_name,
             "display": dm.author.display(),
             "picture": dm.author.picture,
-            "nip05": dm.author.nip05
+            "nip05": dm.author.nip05,
+            "nip05_verified": dm.author.nip05_verified
         },
         "peer_pubkey": dm.peer_pubkey,
         "content": dm.content,
@@ -1430,6 +5619,11 @@ fn format_dm_json(dm: &DirectMessageInfo) -> Value {
     })
 }
 
+/// 取得できなかった件数（None）を 0 と区別して表示する
+fn format_optional_count(count: Option<u64>) -> String {
+    count.map_or_else(|| "不明".to_string(), |count| count.to_string())
+}
+
 /// Unix タイムスタンプを人間が読める相対時間にフォーマット
 fn format_timestamp(timestamp: u64) -> String {
     let now = std::time::SystemTime::now()
@@ -1456,3 +5650,83 @@ fn format_timestamp(timestamp: u64) -> String {
             .unwrap_or_else(|| timestamp.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(content: &str) -> Value {
+        json!({
+            "nevent": "nevent1abc",
+            "author": {"display": "alice", "npub": "npub1alice", "nip05": "alice@example.com"},
+            "content": content,
+            "created_at": 1_700_000_000,
+            "display_card": {"header": "alice", "content": content},
+            "parsed_content": {"references": []}
+        })
+    }
+
+    #[test]
+    fn test_format_optional_count() {
+        assert_eq!(format_optional_count(Some(0)), "0");
+        assert_eq!(format_optional_count(Some(42)), "42");
+        assert_eq!(format_optional_count(None), "不明");
+    }
+
+    #[test]
+    fn test_shape_note_output() {
+        let mut value = json!({"notes": [note("abcdef")], "count": 1});
+        shape_note_output(&mut value, true, Some(3));
+        let shaped = &value["notes"][0];
+        assert!(shaped.get("display_card").is_none());
+        assert!(shaped.get("parsed_content").is_none());
+        assert_eq!(shaped["content"], "abc…");
+        assert_eq!(shaped["content_truncated"], true);
+        assert_eq!(value["count"], 1);
+
+        // 上限以内の本文はそのまま
+        let mut value = json!([note("abc")]);
+        shape_note_output(&mut value, false, Some(3));
+        assert_eq!(value[0]["content"], "abc");
+        assert!(value[0].get("content_truncated").is_none());
+        assert!(value[0].get("display_card").is_some());
+    }
+
+    #[test]
+    fn test_project_note_fields() {
+        let fields = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // ドット区切りの入れ子のキーと、存在しないフィールド
+        let mut value = json!({"notes": [note("hello")], "count": 1});
+        project_note_fields(&mut value, &fields(&["id", "author.display", "author.missing", "missing.key", "nevent"]));
+        assert_eq!(value["notes"][0], json!({"author": {"display": "alice"}, "nevent": "nevent1abc"}));
+        assert_eq!(value["count"], 1);
+
+        // 同じ親の複数の入れ子キーはひとつのオブジェクトにまとめる
+        let mut value = json!([note("hello")]);
+        project_note_fields(&mut value, &fields(&["author.display", "author.npub"]));
+        assert_eq!(value[0], json!({"author": {"display": "alice", "npub": "npub1alice"}}));
+
+        // スカラー値の下のパスは無視する
+        let mut value = json!([note("hello")]);
+        project_note_fields(&mut value, &fields(&["content.length"]));
+        assert_eq!(value[0], json!({}));
+    }
+
+    #[test]
+    fn test_project_note_fields_with_compact() {
+        let fields = vec!["content".to_string(), "display_card".to_string()];
+
+        // 切り詰めた本文を残すときは content_truncated も残す
+        let mut value = json!([note("abcdef")]);
+        shape_note_output(&mut value, true, Some(3));
+        project_note_fields(&mut value, &fields);
+        assert_eq!(value[0], json!({"content": "abc…", "content_truncated": true}));
+
+        // content を残さない場合は content_truncated も省く
+        let mut value = json!([note("abcdef")]);
+        shape_note_output(&mut value, false, Some(3));
+        project_note_fields(&mut value, &["author.display".to_string()]);
+        assert_eq!(value[0], json!({"author": {"display": "alice"}}));
+    }
+}
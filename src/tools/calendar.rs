@@ -0,0 +1,199 @@
+//! カレンダーイベントツール (NIP-52)
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use tracing::debug;
+
+use crate::calendar::CalendarEventParams;
+use super::{dry_run_property, extract_tags_param, optional_str_param, require_str_param, verify_property, verify_publish_if_requested, ToolDefinition, ToolExecutor};
+
+/// カレンダーイベントのツール定義
+pub(super) fn tool_definitions(meta: &dyn Fn(&str) -> Option<Value>) -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "create_calendar_event".to_string(),
+            description: "カレンダーイベントを作成します (NIP-52)。start に YYYY-MM-DD を指定すると終日イベント (Kind 31922)、Unix タイムスタンプまたは RFC 3339 日時を指定すると時刻ベースのイベント (Kind 31923) になります。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "イベントのタイトル"
+                    },
+                    "start": {
+                        "type": "string",
+                        "description": "開始日時（YYYY-MM-DD、Unix タイムスタンプ、または RFC 3339 形式。例: 2026-05-01T19:00:00+09:00）"
+                    },
+                    "end": {
+                        "type": "string",
+                        "description": "終了日時（start と同じ形式、任意）"
+                    },
+                    "description": {
+                        "type": "string",
+                        "description": "イベントの説明（任意）"
+                    },
+                    "location": {
+                        "type": "string",
+                        "description": "場所（住所や URL、任意）"
+                    },
+                    "timezone": {
+                        "type": "string",
+                        "description": "タイムゾーン（IANA 形式。例: Asia/Tokyo、時刻ベースのみ、任意）"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "ハッシュタグのリスト（任意）"
+                    },
+                    "identifier": {
+                        "type": "string",
+                        "description": "イベントの識別子（d タグ）。同じ識別子で再作成すると更新になります（任意）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["title", "start"]
+            }),
+            meta: meta("create_calendar_event"),
+        },
+        ToolDefinition {
+            name: "get_calendar_events".to_string(),
+            description: "今後のカレンダーイベント (Kind 31922/31923, NIP-52) を開始日時の早い順に取得します。認証済みの場合はデフォルトでフォロー中ユーザーのイベントに限定します。作成日時の新しい順に一定件数まで遡って探すため、上限に達した場合は truncated が true になります。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "follows_only": {
+                        "type": "boolean",
+                        "description": "フォロー中ユーザーのイベントのみ取得する（デフォルト: true、未認証時は無視）"
+                    },
+                    "days_ahead": {
+                        "type": "number",
+                        "description": "何日先までのイベントを取得するか（デフォルト: 30）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "取得するイベント数（デフォルト: 20、最大: 100）"
+                    }
+                }
+            }),
+            meta: meta("get_calendar_events"),
+        },
+        ToolDefinition {
+            name: "rsvp_calendar_event".to_string(),
+            description: "カレンダーイベントに出欠を返信します (Kind 31925, NIP-52)。同じイベントへの再返信は前回の返信を置き換えます。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "event": {
+                        "type": "string",
+                        "description": "カレンダーイベントのアドレス（naddr または <kind>:<pubkey>:<d> 形式）"
+                    },
+                    "status": {
+                        "type": "string",
+                        "enum": ["accepted", "declined", "tentative"],
+                        "description": "出欠ステータス"
+                    },
+                    "free_busy": {
+                        "type": "string",
+                        "enum": ["free", "busy"],
+                        "description": "その時間帯の予定（デフォルト: busy、declined の場合は無視）"
+                    },
+                    "note": {
+                        "type": "string",
+                        "description": "主催者へのメッセージ（任意）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["event", "status"]
+            }),
+            meta: meta("rsvp_calendar_event"),
+        },
+    ]
+}
+
+impl ToolExecutor {
+    /// カレンダーイベントを作成
+    pub(super) async fn create_calendar_event(&self, arguments: Value) -> Result<Value> {
+        let params = CalendarEventParams {
+            title: require_str_param(&arguments, &["title"])?.to_string(),
+            start: require_str_param(&arguments, &["start"])?.to_string(),
+            end: optional_str_param(&arguments, "end").map(String::from),
+            description: optional_str_param(&arguments, "description").map(String::from),
+            location: optional_str_param(&arguments, "location").map(String::from),
+            timezone: optional_str_param(&arguments, "timezone").map(String::from),
+            hashtags: extract_tags_param(&arguments),
+            identifier: optional_str_param(&arguments, "identifier").map(String::from),
+        };
+
+        debug!("カレンダーイベント作成: title='{}', start='{}'", params.title, params.start);
+
+        let (event, published) = self.client.read().await.create_calendar_event(params).await?;
+        let message = format!("カレンダーイベント「{}」を作成しました。", event.title);
+
+        let mut result = json!({
+            "success": true,
+            "event": event,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": message
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, published.id, &mut result).await;
+        Ok(result)
+    }
+
+    /// 今後のカレンダーイベントを取得
+    pub(super) async fn get_calendar_events(&self, arguments: Value) -> Result<Value> {
+        let follows_only = arguments
+            .get("follows_only")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let days_ahead = arguments
+            .get("days_ahead")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or(30);
+        let limit = self.extract_limit(&arguments);
+
+        debug!("カレンダーイベント取得: follows_only={}, days_ahead={}, limit={}", follows_only, days_ahead, limit);
+
+        let (events, truncated) = self.client.read().await
+            .get_calendar_events(follows_only, days_ahead, limit)
+            .await?;
+
+        let mut result = json!({
+            "success": true,
+            "count": events.len(),
+            "events": events,
+            "truncated": truncated
+        });
+        if truncated {
+            result["message"] = json!("取得件数の上限に達したため、それより前に作成されたイベントは含まれていない可能性があります。");
+        }
+        Ok(result)
+    }
+
+    /// カレンダーイベントに RSVP
+    pub(super) async fn rsvp_calendar_event(&self, arguments: Value) -> Result<Value> {
+        let event = require_str_param(&arguments, &["event", "naddr"])?;
+        let status = require_str_param(&arguments, &["status"])?;
+        let free_busy = optional_str_param(&arguments, "free_busy");
+        let note = optional_str_param(&arguments, "note");
+
+        debug!("RSVP: event='{}', status='{}'", event, status);
+
+        let published = self.client.read().await
+            .rsvp_calendar_event(event, status, free_busy, note)
+            .await?;
+
+        let mut result = json!({
+            "success": true,
+            "event_id": published.id.to_hex(),
+            "status": status,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": format!("出欠（{}）を返信しました。", status)
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, published.id, &mut result).await;
+        Ok(result)
+    }
+}
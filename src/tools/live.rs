@@ -0,0 +1,148 @@
+//! ライブアクティビティツール (NIP-53)
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use tracing::debug;
+
+use super::{dry_run_property, require_str_param, verify_property, verify_publish_if_requested, ToolDefinition, ToolExecutor, DEFAULT_LIMIT, MAX_LIMIT};
+
+/// ライブアクティビティのツール定義
+pub(super) fn tool_definitions(meta: &dyn Fn(&str) -> Option<Value>) -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "get_live_streams".to_string(),
+            description: "配信中のライブアクティビティ (Kind 30311, NIP-53) を取得します。タイトル・配信 URL・参加者・ステータスを返します。認証済みの場合はデフォルトでフォロー中ユーザーの配信に限定し、include_chat でライブチャットも取得できます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "follows_only": {
+                        "type": "boolean",
+                        "description": "フォロー中ユーザーの配信のみ取得する（デフォルト: true、未認証時はリレー全体から取得）"
+                    },
+                    "include_chat": {
+                        "type": "boolean",
+                        "description": "各配信のライブチャット (Kind 1311) を含める（デフォルト: false）"
+                    },
+                    "chat_limit": {
+                        "type": "number",
+                        "description": "配信ごとに取得するチャットメッセージ数（デフォルト: 20）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "取得する配信数（デフォルト: 20、最大: 100）"
+                    }
+                }
+            }),
+            meta: meta("get_live_streams"),
+        },
+        ToolDefinition {
+            name: "get_live_chat".to_string(),
+            description: "ライブアクティビティのチャットメッセージ (Kind 1311, NIP-53) を古い順に取得します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "stream": {
+                        "type": "string",
+                        "description": "ライブアクティビティのアドレス（naddr または 30311:<pubkey>:<d> 形式）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "取得するメッセージ数（デフォルト: 20、最大: 100）"
+                    }
+                },
+                "required": ["stream"]
+            }),
+            meta: meta("get_live_chat"),
+        },
+        ToolDefinition {
+            name: "post_live_chat_message".to_string(),
+            description: "ライブアクティビティのチャットにメッセージを投稿します (Kind 1311, NIP-53)。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "stream": {
+                        "type": "string",
+                        "description": "ライブアクティビティのアドレス（naddr または 30311:<pubkey>:<d> 形式）"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "メッセージ本文"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["stream", "content"]
+            }),
+            meta: meta("post_live_chat_message"),
+        },
+    ]
+}
+
+impl ToolExecutor {
+    /// 配信中のライブアクティビティを取得
+    pub(super) async fn get_live_streams(&self, arguments: Value) -> Result<Value> {
+        let follows_only = arguments
+            .get("follows_only")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let include_chat = arguments
+            .get("include_chat")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let chat_limit = arguments
+            .get("chat_limit")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or(DEFAULT_LIMIT)
+            .min(MAX_LIMIT);
+        let limit = self.extract_limit(&arguments);
+
+        debug!("ライブ配信取得: follows_only={}, include_chat={}, limit={}", follows_only, include_chat, limit);
+
+        let streams = self.client.read().await
+            .get_live_streams(follows_only, include_chat, chat_limit, limit)
+            .await?;
+
+        Ok(json!({
+            "success": true,
+            "count": streams.len(),
+            "streams": streams
+        }))
+    }
+
+    /// ライブチャットを取得
+    pub(super) async fn get_live_chat(&self, arguments: Value) -> Result<Value> {
+        let stream = require_str_param(&arguments, &["stream", "naddr"])?;
+        let limit = self.extract_limit(&arguments);
+
+        debug!("ライブチャット取得: stream='{}', limit={}", stream, limit);
+
+        let messages = self.client.read().await.get_live_chat(stream, limit).await?;
+
+        Ok(json!({
+            "success": true,
+            "count": messages.len(),
+            "messages": messages
+        }))
+    }
+
+    /// ライブチャットにメッセージを投稿
+    pub(super) async fn post_live_chat_message(&self, arguments: Value) -> Result<Value> {
+        let stream = require_str_param(&arguments, &["stream", "naddr"])?;
+        let content = require_str_param(&arguments, &["content"])?;
+
+        debug!("ライブチャット投稿: stream='{}'", stream);
+
+        let published = self.client.read().await.post_live_chat_message(stream, content).await?;
+        let event_id = published.id;
+
+        let mut result = json!({
+            "success": true,
+            "event_id": event_id.to_hex(),
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": "ライブチャットに投稿しました。"
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, event_id, &mut result).await;
+        Ok(result)
+    }
+}
@@ -0,0 +1,152 @@
+//! 投票ツール (NIP-88)
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use tracing::debug;
+
+use super::{dry_run_property, extract_string_array, optional_str_param, require_str_param, verify_property, verify_publish_if_requested, ToolDefinition, ToolExecutor};
+
+/// 投票のツール定義
+pub(super) fn tool_definitions(meta: &dyn Fn(&str) -> Option<Value>) -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "create_poll".to_string(),
+            description: "投票 (Kind 1068, NIP-88) を作成します。選択肢と締切（有効期間）を指定できます。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "question": {
+                        "type": "string",
+                        "description": "質問文"
+                    },
+                    "options": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "選択肢のラベル（2 つ以上）"
+                    },
+                    "multiple_choice": {
+                        "type": "boolean",
+                        "description": "複数選択を許可する（デフォルト: false）"
+                    },
+                    "expires_in": {
+                        "type": "number",
+                        "description": "投票の有効期間（秒、任意。未指定時は無期限）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["question", "options"]
+            }),
+            meta: meta("create_poll"),
+        },
+        ToolDefinition {
+            name: "vote_poll".to_string(),
+            description: "投票に回答します (Kind 1018, NIP-88)。再度回答すると前回の回答を置き換えます。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "poll_id": {
+                        "type": "string",
+                        "description": "投票のイベント ID（hex、note、nevent、naddr 形式対応）"
+                    },
+                    "options": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "選択する選択肢の ID またはラベル（単一選択の投票では 1 つ）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["poll_id", "options"]
+            }),
+            meta: meta("vote_poll"),
+        },
+        ToolDefinition {
+            name: "get_poll_results".to_string(),
+            description: "投票 (Kind 1068, NIP-88) の結果を集計します。1 人 1 票（最新の回答のみ有効）で、締切後の回答は除外されます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "poll_id": {
+                        "type": "string",
+                        "description": "投票のイベント ID（hex、note、nevent、naddr 形式対応）"
+                    }
+                },
+                "required": ["poll_id"]
+            }),
+            meta: meta("get_poll_results"),
+        },
+    ]
+}
+
+impl ToolExecutor {
+    /// 投票を作成
+    pub(super) async fn create_poll(&self, arguments: Value) -> Result<Value> {
+        let question = require_str_param(&arguments, &["question", "content"])?;
+        let options = extract_string_array(&arguments, "options");
+        let multiple_choice = arguments
+            .get("multiple_choice")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let expires_in = arguments
+            .get("expires_in")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)));
+
+        debug!("投票作成: options={}, multiple_choice={}", options.len(), multiple_choice);
+
+        let (poll, published) = self.client.read().await
+            .create_poll(question, &options, multiple_choice, expires_in)
+            .await?;
+
+        let mut result = json!({
+            "success": true,
+            "poll": poll,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": "投票を作成しました。"
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, published.id, &mut result).await;
+        Ok(result)
+    }
+
+    /// 投票に回答
+    pub(super) async fn vote_poll(&self, arguments: Value) -> Result<Value> {
+        let poll_id = require_str_param(&arguments, &["poll_id", "note_id"])?;
+        let mut choices = extract_string_array(&arguments, "options");
+        if let Some(option) = optional_str_param(&arguments, "option") {
+            choices.push(option.to_string());
+        }
+
+        debug!("投票回答: poll_id='{}', choices={:?}", poll_id, choices);
+
+        let (published, selected) = self.client.read().await
+            .vote_poll(poll_id, &choices)
+            .await?;
+        let labels: Vec<&str> = selected.iter().map(|o| o.label.as_str()).collect();
+
+        let mut result = json!({
+            "success": true,
+            "event_id": published.id.to_hex(),
+            "poll_id": poll_id,
+            "selected": selected,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": format!("「{}」に投票しました。", labels.join("」「"))
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, published.id, &mut result).await;
+        Ok(result)
+    }
+
+    /// 投票結果を取得
+    pub(super) async fn get_poll_results(&self, arguments: Value) -> Result<Value> {
+        let poll_id = require_str_param(&arguments, &["poll_id", "note_id"])?;
+        debug!("投票結果取得: {}", poll_id);
+
+        let results = self.client.read().await.get_poll_results(poll_id).await?;
+
+        Ok(json!({
+            "success": true,
+            "results": results
+        }))
+    }
+}
@@ -0,0 +1,164 @@
+//! Wiki ツール (NIP-54)
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use tracing::debug;
+
+use super::{dry_run_property, optional_str_param, require_str_param, verify_property, verify_publish_if_requested, ToolDefinition, ToolExecutor};
+
+/// Wiki のツール定義
+pub(super) fn tool_definitions(meta: &dyn Fn(&str) -> Option<Value>) -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "get_wiki_article".to_string(),
+            description: "Wiki 記事 (Kind 30818, NIP-54) をトピック名で取得します。トピック名は正規化された d タグで検索され、著者ごとの最新バージョンを新しい順に返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "topic": {
+                        "type": "string",
+                        "description": "トピック名（例: \"Nostr Protocol\"。小文字化・ハイフン区切りに正規化されます）"
+                    },
+                    "author": {
+                        "type": "string",
+                        "description": "特定の著者のバージョンに限定する場合の公開鍵（npub、nprofile または hex 形式、任意）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "取得するバージョン数（デフォルト: 20、最大: 100）"
+                    }
+                },
+                "required": ["topic"]
+            }),
+            meta: meta("get_wiki_article"),
+        },
+        ToolDefinition {
+            name: "compare_wiki_versions".to_string(),
+            description: "同じトピックについて 2 人の著者が書いた Wiki 記事 (Kind 30818, NIP-54) を比較し、行単位の差分を返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "topic": {
+                        "type": "string",
+                        "description": "トピック名"
+                    },
+                    "base_author": {
+                        "type": "string",
+                        "description": "比較元の著者の公開鍵（npub、nprofile または hex 形式）"
+                    },
+                    "other_author": {
+                        "type": "string",
+                        "description": "比較先の著者の公開鍵（npub、nprofile または hex 形式）"
+                    }
+                },
+                "required": ["topic", "base_author", "other_author"]
+            }),
+            meta: meta("compare_wiki_versions"),
+        },
+        ToolDefinition {
+            name: "publish_wiki_article".to_string(),
+            description: "Wiki 記事 (Kind 30818, NIP-54) を公開または編集します。本文は AsciiDoc 形式です。fork_of に他の著者を指定すると、そのバージョンを元にした編集（フォーク）として公開します。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "topic": {
+                        "type": "string",
+                        "description": "トピック名（d タグに正規化されます）"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "記事本文（AsciiDoc 形式）"
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "表示用タイトル（任意、未指定時はトピック名）"
+                    },
+                    "summary": {
+                        "type": "string",
+                        "description": "要約（任意）"
+                    },
+                    "fork_of": {
+                        "type": "string",
+                        "description": "編集元バージョンの著者の公開鍵（npub、nprofile または hex 形式、任意）"
+                    },
+                    "verify": verify_property(),
+                    "dry_run": dry_run_property()
+                },
+                "required": ["topic", "content"]
+            }),
+            meta: meta("publish_wiki_article"),
+        },
+    ]
+}
+
+impl ToolExecutor {
+    /// Wiki 記事を取得
+    pub(super) async fn get_wiki_article(&self, arguments: Value) -> Result<Value> {
+        let topic = require_str_param(&arguments, &["topic"])?;
+        let author = optional_str_param(&arguments, "author");
+        let limit = self.extract_limit(&arguments);
+
+        debug!("Wiki 記事取得: topic='{}', author={:?}", topic, author);
+
+        let versions = self.client.read().await
+            .get_wiki_articles(topic, author, limit)
+            .await?;
+
+        Ok(json!({
+            "success": true,
+            "count": versions.len(),
+            "versions": versions
+        }))
+    }
+
+    /// Wiki 記事の 2 つのバージョンを比較
+    pub(super) async fn compare_wiki_versions(&self, arguments: Value) -> Result<Value> {
+        let topic = require_str_param(&arguments, &["topic"])?;
+        let base_author = require_str_param(&arguments, &["base_author"])?;
+        let other_author = require_str_param(&arguments, &["other_author"])?;
+
+        debug!("Wiki 記事比較: topic='{}'", topic);
+
+        let comparison = self.client.read().await
+            .compare_wiki_versions(topic, base_author, other_author)
+            .await?;
+
+        let message = if comparison.diff.is_identical() {
+            "2 つのバージョンの本文は同一です。".to_string()
+        } else {
+            format!("{} 行追加、{} 行削除されています。", comparison.diff.added, comparison.diff.removed)
+        };
+
+        Ok(json!({
+            "success": true,
+            "comparison": comparison,
+            "message": message
+        }))
+    }
+
+    /// Wiki 記事を公開
+    pub(super) async fn publish_wiki_article(&self, arguments: Value) -> Result<Value> {
+        let topic = require_str_param(&arguments, &["topic"])?;
+        let content = require_str_param(&arguments, &["content"])?;
+        let title = optional_str_param(&arguments, "title");
+        let summary = optional_str_param(&arguments, "summary");
+        let fork_of = optional_str_param(&arguments, "fork_of");
+
+        debug!("Wiki 記事公開: topic='{}', fork_of={:?}", topic, fork_of);
+
+        let (article, published) = self.client.read().await
+            .publish_wiki_article(topic, content, title, summary, fork_of)
+            .await?;
+        let message = format!("Wiki 記事「{}」を公開しました。", article.title);
+
+        let mut result = json!({
+            "success": true,
+            "article": article,
+            "published_to": published.published_to,
+            "failed_relays": published.failed_relays,
+            "message": message
+        });
+        verify_publish_if_requested(&*self.client.read().await, &arguments, published.id, &mut result).await;
+        Ok(result)
+    }
+}
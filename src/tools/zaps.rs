@@ -0,0 +1,333 @@
+//! Zap ツール (NIP-57)
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use tracing::debug;
+
+use super::{format_timestamp, optional_str_param, require_str_param, ToolDefinition, ToolExecutor, MAX_LIMIT};
+
+/// Zap と支払い集計のツール定義
+pub(super) fn tool_definitions(meta: &dyn Fn(&str) -> Option<Value>) -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "send_zap".to_string(),
+            description: "ノートまたはプロフィールに Lightning Zap (NIP-57) を送信します。NWC (Nostr Wallet Connect) の設定が必要です。書き込みアクセスが必要です。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "target": {
+                        "type": "string",
+                        "description": "Zap 対象のイベント ID（hex、note、nevent、naddr 形式）または公開鍵（npub、nprofile または hex 形式）"
+                    },
+                    "amount": {
+                        "type": "number",
+                        "description": "sats 単位の金額"
+                    },
+                    "comment": {
+                        "type": "string",
+                        "description": "Zap コメント（任意）"
+                    }
+                },
+                "required": ["target", "amount"]
+            }),
+            meta: meta("send_zap"),
+        },
+        ToolDefinition {
+            name: "get_zap_receipts".to_string(),
+            description: "ノートの Zap レシート (Kind 9735, NIP-57) を取得します。送信者・金額・コメント情報と、真正性の検証結果（valid / status / invalid_reason、受取人の LNURL プロバイダーが分からないレシートは unverified）付きで返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "note_id": {
+                        "type": "string",
+                        "description": "対象ノートのイベント ID（hex、note、nevent、naddr 形式対応）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "取得するレシートの最大数（デフォルト: 20、最大: 100）"
+                    }
+                },
+                "required": ["note_id"]
+            }),
+            meta: meta("get_zap_receipts"),
+        },
+        ToolDefinition {
+            name: "get_profile_zap_stats".to_string(),
+            description: "ユーザーが指定期間内に受け取った Zap (Kind 9735, NIP-57) を集計します。合計 sats・回数・平均と、金額の多い送信者（トップ Zapper）を返します。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pubkey": {
+                        "type": "string",
+                        "description": "npub / nprofile (bech32) または hex 形式の公開鍵（任意、未指定時は自分）"
+                    },
+                    "days": {
+                        "type": "number",
+                        "description": "集計期間（日数、デフォルト: 30）"
+                    },
+                    "top": {
+                        "type": "number",
+                        "description": "返すトップ Zapper の人数（デフォルト: 10）"
+                    }
+                }
+            }),
+            meta: meta("get_profile_zap_stats"),
+        },
+        ToolDefinition {
+            name: "get_zap_leaderboard".to_string(),
+            description: "ノートまたはユーザーへの Zap (Kind 9735, NIP-57) を送信者ごとに集計し、合計金額の多い順にランキングで返します。「誰が一番 Zap したか」を 1 回の呼び出しで取得できます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "note_id": {
+                        "type": "string",
+                        "description": "対象ノートのイベント ID（hex、note、nevent、naddr 形式対応）"
+                    },
+                    "pubkey": {
+                        "type": "string",
+                        "description": "対象ユーザーの公開鍵（npub、nprofile または hex 形式。note_id を指定しない場合）"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "ランキングに含める人数（デフォルト: 20、最大: 100）"
+                    }
+                }
+            }),
+            meta: meta("get_zap_leaderboard"),
+        },
+        ToolDefinition {
+            name: "get_spending_report".to_string(),
+            description: "自分の支払い（送った Zap と NWC での Lightning 支払い）を日ごと・週ごと、受取人ごとに集計します。このサーバーのローカル支払い履歴と、リレーに公開された自分の Zap レシートを合わせて集計するため、他のクライアントから送った Zap も含まれます。「今月いくら Zap した？」のような質問に使えます。".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "days": {
+                        "type": "number",
+                        "description": "集計期間（日数、デフォルト: 30）"
+                    },
+                    "since": {
+                        "type": "number",
+                        "description": "集計期間の開始（Unix タイムスタンプ、任意。指定時は days より優先）"
+                    },
+                    "group_by": {
+                        "type": "string",
+                        "enum": ["day", "week"],
+                        "description": "期間ごとの集計単位（デフォルト: day、週は月曜始まり、UTC）"
+                    },
+                    "top": {
+                        "type": "number",
+                        "description": "返す受取人の数（デフォルト: 10）"
+                    }
+                }
+            }),
+            meta: meta("get_spending_report"),
+        },
+    ]
+}
+
+impl ToolExecutor {
+    /// Zap を送信
+    pub(super) async fn send_zap(&self, arguments: Value) -> Result<Value> {
+        let target = require_str_param(&arguments, &["target"])?;
+        let amount = arguments
+            .get("amount")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .ok_or_else(|| anyhow!("必須パラメータが不足: amount"))?;
+
+        if amount == 0 {
+            return Err(anyhow!("amount は 0 より大きい必要があります"));
+        }
+
+        let comment = optional_str_param(&arguments, "comment");
+
+        debug!("Zap 送信: target='{}', amount={}, comment={:?}", target, amount, comment);
+
+        self.client.read().await.send_zap(target, amount, comment).await
+    }
+
+    /// Zap レシートを取得
+    pub(super) async fn get_zap_receipts(&self, arguments: Value) -> Result<Value> {
+        let note_id = require_str_param(&arguments, &["note_id"])?;
+
+        let limit = self.extract_limit(&arguments);
+        debug!("Zap レシート取得: note_id='{}', limit={}", note_id, limit);
+
+        let receipts = self.client.read().await.get_zap_receipts(note_id, limit).await?;
+
+        // 真正性を確認できないレシートは合計に含めない
+        let total_sats = receipts.iter().filter(|r| r.valid).fold(0u64, |total, r| total.saturating_add(r.amount_sats));
+        let invalid_count = receipts.iter().filter(|r| !r.valid).count();
+
+        let formatted: Vec<Value> = receipts.iter().map(|receipt| {
+            let mut result = json!({
+                "id": receipt.id,
+                "nevent": receipt.nevent,
+                "amount_sats": receipt.amount_sats,
+                "valid": receipt.valid,
+                "status": receipt.status,
+                "created_at": receipt.created_at,
+                "formatted_time": format_timestamp(receipt.created_at)
+            });
+
+            if let Some(ref reason) = receipt.invalid_reason {
+                result["invalid_reason"] = json!(reason);
+            }
+
+            if let Some(ref sender) = receipt.sender {
+                result["sender"] = json!({
+                    "pubkey": sender.pubkey,
+                    "npub": sender.npub,
+                    "name": sender.name,
+                    "display_name": sender.display_name,
+                    "display": sender.display(),
+                    "picture": sender.picture,
+                    "nip05": sender.nip05,
+                    "nip05_verified": sender.nip05_verified
+                });
+            }
+
+            if let Some(ref comment) = receipt.comment {
+                result["comment"] = json!(comment);
+            }
+
+            if let Some(ref target_note_id) = receipt.target_note_id {
+                result["target_note_id"] = json!(target_note_id);
+            }
+
+            if let Some(ref target_pubkey) = receipt.target_pubkey {
+                result["target_pubkey"] = json!(target_pubkey);
+            }
+
+            result
+        }).collect();
+
+        Ok(json!({
+            "success": true,
+            "note_id": note_id,
+            "count": receipts.len(),
+            "total_sats": total_sats,
+            "invalid_count": invalid_count,
+            "zap_receipts": formatted
+        }))
+    }
+
+    /// ユーザーが受け取った Zap を集計
+    pub(super) async fn get_profile_zap_stats(&self, arguments: Value) -> Result<Value> {
+        let client = self.client.read().await;
+
+        let pubkey = match optional_str_param(&arguments, "pubkey") {
+            Some(pk) => pk.to_string(),
+            None => client.public_key()
+                .ok_or_else(|| anyhow!("pubkey を指定するか、認証してください。"))?
+                .to_hex(),
+        };
+        let days = arguments
+            .get("days")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or(30);
+        let top = arguments
+            .get("top")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or(10)
+            .min(MAX_LIMIT);
+
+        debug!("プロフィール Zap 集計: pubkey='{}', days={}, top={}", pubkey, days, top);
+
+        let stats = client.get_profile_zap_stats(&pubkey, days, top as usize).await?;
+        let message = format!(
+            "過去 {} 日間に {} 回、合計 {} sats の Zap を受け取りました。",
+            days, stats.zap_count, stats.total_sats
+        );
+
+        Ok(json!({
+            "success": true,
+            "stats": stats,
+            "message": message
+        }))
+    }
+
+    /// Zap ランキングを取得
+    pub(super) async fn get_zap_leaderboard(&self, arguments: Value) -> Result<Value> {
+        let note_id = optional_str_param(&arguments, "note_id");
+        let pubkey = optional_str_param(&arguments, "pubkey");
+        let limit = self.extract_limit(&arguments);
+
+        debug!("Zap ランキング取得: note_id={:?}, pubkey={:?}, limit={}", note_id, pubkey, limit);
+
+        let leaderboard = self.client.read().await
+            .get_zap_leaderboard(note_id, pubkey, limit as usize)
+            .await?;
+
+        let ranking: Vec<Value> = leaderboard.entries.iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                json!({
+                    "rank": i + 1,
+                    "sender": {
+                        "pubkey": entry.sender.pubkey,
+                        "npub": entry.sender.npub,
+                        "display": entry.sender.display(),
+                        "picture": entry.sender.picture
+                    },
+                    "total_sats": entry.total_sats,
+                    "zap_count": entry.zap_count
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "success": true,
+            "target": leaderboard.target,
+            "target_type": leaderboard.target_type,
+            "total_sats": leaderboard.total_sats,
+            "zap_count": leaderboard.zap_count,
+            "unique_zappers": leaderboard.unique_zappers,
+            "invalid_zap_count": leaderboard.invalid_zap_count,
+            "ranking": ranking
+        }))
+    }
+
+    /// 自分の支払いを集計
+    pub(super) async fn get_spending_report(&self, arguments: Value) -> Result<Value> {
+        let days = arguments
+            .get("days")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or(30);
+        let since = arguments
+            .get("since")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or_else(|| {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                now.saturating_sub(days.saturating_mul(86_400))
+            });
+        let weekly = match optional_str_param(&arguments, "group_by").unwrap_or("day") {
+            "day" => false,
+            "week" => true,
+            other => return Err(anyhow!("group_by には day または week を指定してください: {}", other)),
+        };
+        let top = arguments
+            .get("top")
+            .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+            .unwrap_or(10)
+            .min(MAX_LIMIT);
+
+        debug!("支払いレポート: since={}, weekly={}, top={}", since, weekly, top);
+
+        let report = self.client.read().await
+            .get_spending_report(since, weekly, top as usize)
+            .await?;
+        let message = format!(
+            "{} 以降に {} 回、合計 {} sats を支払いました。",
+            format_timestamp(since), report.payment_count, report.total_sats
+        );
+
+        Ok(json!({
+            "success": true,
+            "report": report,
+            "message": message
+        }))
+    }
+}
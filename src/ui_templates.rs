@@ -2,13 +2,30 @@
 //!
 //! MCP Apps 用の HTML テンプレートをビルド時に `include_str!()` で
 //! バイナリに埋め込み、実行時にプレースホルダーを置換して提供します。
+//!
+//! 設定ディレクトリの `ui/<テンプレート名>.html`（例: `~/.config/rust-nostr-mcp/ui/note-card.html`）
+//! が存在する場合は、埋め込みテンプレートの代わりにそのファイルを使用します。
+//! ファイルはリソース読み込みのたびに読み直すため、再ビルドや再起動なしで変更が反映されます。
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
 
 /// 共通 CSS スタイル（テーマ変数のフォールバック値付き）
 const COMMON_CSS: &str = include_str!("../ui/common.css");
 
+/// iframe からツールを呼び出す共通スクリプト（MCP Apps の tools/call ブリッジ）
+const TOOL_BRIDGE_JS: &str = include_str!("../ui/tool-bridge.js");
+
+/// ホストのテーマ（ライト/ダーク・CSS 変数）を適用する共通スクリプト
+const HOST_THEME_JS: &str = include_str!("../ui/host-theme.js");
+
 /// ノートカードテンプレート
 const NOTE_CARD_HTML: &str = include_str!("../ui/note-card.html");
 
+/// タイムライン（ノート一覧）フィードテンプレート
+const TIMELINE_LIST_HTML: &str = include_str!("../ui/timeline-list.html");
+
 /// 記事プレビューカードテンプレート
 const ARTICLE_CARD_HTML: &str = include_str!("../ui/article-card.html");
 
@@ -18,6 +35,9 @@ const PROFILE_CARD_HTML: &str = include_str!("../ui/profile-card.html");
 /// Zap ボタン UI テンプレート
 const ZAP_BUTTON_HTML: &str = include_str!("../ui/zap-button.html");
 
+/// 投票カードテンプレート（NIP-88）
+const POLL_CARD_HTML: &str = include_str!("../ui/poll-card.html");
+
 /// NIP-46 QR コード接続画面テンプレート
 const CONNECT_QR_HTML: &str = include_str!("../ui/connect-qr.html");
 
@@ -25,9 +45,11 @@ const CONNECT_QR_HTML: &str = include_str!("../ui/connect-qr.html");
 #[cfg(test)]
 const TEMPLATE_NAMES: &[&str] = &[
     "note-card",
+    "timeline-list",
     "article-card",
     "profile-card",
     "zap-button",
+    "poll-card",
     "connect-qr",
 ];
 
@@ -35,27 +57,74 @@ const TEMPLATE_NAMES: &[&str] = &[
 fn get_raw_template(name: &str) -> Option<&'static str> {
     match name {
         "note-card" => Some(NOTE_CARD_HTML),
+        "timeline-list" => Some(TIMELINE_LIST_HTML),
         "article-card" => Some(ARTICLE_CARD_HTML),
         "profile-card" => Some(PROFILE_CARD_HTML),
         "zap-button" => Some(ZAP_BUTTON_HTML),
+        "poll-card" => Some(POLL_CARD_HTML),
         "connect-qr" => Some(CONNECT_QR_HTML),
         _ => None,
     }
 }
 
 /// テンプレート名から処理済み HTML を取得する。
-/// `{{COMMON_CSS}}` プレースホルダーを共通 CSS で置換する。
+/// `{{COMMON_CSS}}` プレースホルダーを共通 CSS で、`{{TOOL_BRIDGE}}` をツール呼び出しブリッジで、
+/// `{{HOST_THEME}}` をホストテーマの適用スクリプトで置換する。
+///
+/// ユーザーの上書きテンプレートがあればそちらを優先する（プレースホルダーの置換は同じ）。
 pub fn get_template(name: &str) -> Option<String> {
-    get_raw_template(name).map(|html| html.replace("{{COMMON_CSS}}", COMMON_CSS))
+    render_template(name, override_dir().as_deref())
+}
+
+/// 上書きテンプレートを置くディレクトリ（設定ファイルと同じディレクトリの `ui/`）
+fn override_dir() -> Option<PathBuf> {
+    let config_path = crate::config::Config::config_path().ok()?;
+    Some(config_path.parent()?.join("ui"))
+}
+
+/// 上書きディレクトリを指定してテンプレートを処理するヘルパー
+///
+/// 既知のテンプレート名のみ上書きを探すため、任意のファイルは読み込まない。
+fn render_template(name: &str, override_dir: Option<&Path>) -> Option<String> {
+    let embedded = get_raw_template(name)?;
+    let html = override_dir
+        .and_then(|dir| read_override(&dir.join(format!("{}.html", name))))
+        .map(Cow::Owned)
+        .unwrap_or(Cow::Borrowed(embedded));
+
+    Some(
+        html.replace("{{COMMON_CSS}}", COMMON_CSS)
+            .replace("{{TOOL_BRIDGE}}", TOOL_BRIDGE_JS)
+            .replace("{{HOST_THEME}}", HOST_THEME_JS),
+    )
+}
+
+/// 上書きテンプレートを読み込む（存在しない・読み込めない場合は埋め込みテンプレートを使う）
+fn read_override(path: &Path) -> Option<String> {
+    if !path.is_file() {
+        return None;
+    }
+    match std::fs::read_to_string(path) {
+        Ok(html) => {
+            debug!("上書きテンプレートを使用します: {}", path.display());
+            Some(html)
+        }
+        Err(e) => {
+            warn!("上書きテンプレートの読み込みに失敗したため埋め込みテンプレートを使用します: {}: {}", path.display(), e);
+            None
+        }
+    }
 }
 
 /// テンプレートの説明を返す
 pub fn get_template_description(name: &str) -> &'static str {
     match name {
         "note-card" => "Nostr ノートのリッチプレビューカード",
+        "timeline-list" => "Nostr タイムラインのスクロール可能なフィード",
         "article-card" => "Nostr 長文記事のプレビューカード",
         "profile-card" => "Nostr ユーザープロフィールカード",
         "zap-button" => "Lightning Zap 送信 UI",
+        "poll-card" => "Nostr 投票の結果表示・回答 UI",
         "connect-qr" => "NIP-46 Nostr Connect QR コード接続画面",
         _ => "",
     }
@@ -65,9 +134,11 @@ pub fn get_template_description(name: &str) -> &'static str {
 pub fn get_template_display_name(name: &str) -> &'static str {
     match name {
         "note-card" => "Nostr Note Card",
+        "timeline-list" => "Nostr Timeline",
         "article-card" => "Nostr Article Preview",
         "profile-card" => "Nostr Profile Card",
         "zap-button" => "Nostr Zap Button",
+        "poll-card" => "Nostr Poll",
         "connect-qr" => "Nostr Connect QR",
         _ => "",
     }
@@ -106,6 +177,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tool_bridge_injected() {
+        for name in TEMPLATE_NAMES {
+            let html = get_template(name).unwrap();
+            assert!(
+                !html.contains("{{TOOL_BRIDGE}}"),
+                "Template '{}' should have the tool bridge injected",
+                name
+            );
+        }
+        assert!(get_template("zap-button").unwrap().contains("function callTool"));
+        assert!(get_template("poll-card").unwrap().contains("function callTool"));
+        assert!(get_template("connect-qr").unwrap().contains("function callTool"));
+    }
+
+    #[test]
+    fn test_host_theme_injected() {
+        for name in TEMPLATE_NAMES {
+            let html = get_template(name).unwrap();
+            assert!(
+                !html.contains("{{HOST_THEME}}"),
+                "Template '{}' should have the host theme script injected",
+                name
+            );
+            assert!(
+                html.contains("function applyHostContext") && html.contains("applyHostContext(initResult?.hostContext)"),
+                "Template '{}' should apply the host context from ui/initialize",
+                name
+            );
+            assert!(
+                html.contains(":root[data-theme=\"dark\"]"),
+                "Template '{}' should contain the dark theme defaults",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_override_template() {
+        let dir = std::env::temp_dir().join(format!("rust-nostr-mcp-ui-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("note-card.html"),
+            "<!DOCTYPE html><style>{{COMMON_CSS}}</style><p>custom</p></html>",
+        )
+        .unwrap();
+        std::fs::write(dir.join("nonexistent.html"), "<p>ignored</p>").unwrap();
+
+        let html = render_template("note-card", Some(&dir)).unwrap();
+        assert!(html.contains("<p>custom</p>"));
+        assert!(html.contains("box-sizing"));
+        assert!(!html.contains("{{COMMON_CSS}}"));
+
+        // 上書きのないテンプレートは埋め込みのものを使う
+        let html = render_template("article-card", Some(&dir)).unwrap();
+        assert!(!html.contains("<p>custom</p>"));
+        assert_eq!(Some(html), render_template("article-card", None));
+
+        // 未知のテンプレート名はファイルがあっても読み込まない
+        assert!(render_template("nonexistent", Some(&dir)).is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_unknown_template_returns_none() {
         assert!(get_template("nonexistent").is_none());
@@ -0,0 +1,244 @@
+//! Wiki モジュール (NIP-54)
+//!
+//! トピック名を正規化した d タグで Wiki 記事 (Kind 30818) を取得・比較・公開します。
+
+use anyhow::{anyhow, Context, Result};
+use nostr_sdk::prelude::*;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::info;
+
+use crate::nostr_client::{extract_tag_value, AuthorInfo, NostrClient, PublishResult};
+
+/// Wiki 記事の比較時に取得するバージョン数の上限
+const MAX_WIKI_VERSIONS: u64 = 100;
+
+/// Wiki 記事情報（NIP-54）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WikiArticleInfo {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// naddr 形式のアドレス
+    pub naddr: String,
+    /// 正規化されたトピック名（d タグ）
+    pub topic: String,
+    /// 表示用タイトル
+    pub title: String,
+    /// 要約
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// 本文（AsciiDoc）
+    pub content: String,
+    /// 作成日時（Unix タイムスタンプ）
+    pub created_at: u64,
+    /// フォーク元のアドレス（30818:<pubkey>:<d>）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<String>,
+    /// 著者の情報
+    pub author: AuthorInfo,
+}
+
+/// Wiki 記事の比較結果（NIP-54）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WikiComparison {
+    /// 正規化されたトピック名
+    pub topic: String,
+    /// 比較元のバージョン
+    pub base: WikiArticleInfo,
+    /// 比較先のバージョン
+    pub other: WikiArticleInfo,
+    /// 行単位の差分
+    pub diff: crate::diff::TextDiff,
+}
+
+impl NostrClient {
+    /// Wiki 記事 (Kind 30818) を取得します（NIP-54）。
+    ///
+    /// トピック名を正規化した d タグで検索し、著者ごとの最新バージョンを新しい順に返します。
+    pub async fn get_wiki_articles(&self, topic: &str, author: Option<&str>, limit: u64) -> Result<Vec<WikiArticleInfo>> {
+        let d_tag = normalize_wiki_topic(topic);
+        if d_tag.is_empty() {
+            return Err(anyhow!("無効なトピック名です: {}", topic));
+        }
+
+        let mut filter = Filter::new()
+            .kind(Kind::from(30818))
+            .identifier(d_tag.clone())
+            .limit(200);
+
+        if let Some(author_str) = author {
+            filter = filter.author(Self::parse_public_key(author_str)?);
+        }
+
+        let events = self.fetch_events(vec![filter], Duration::from_secs(10))
+            .await
+            .context("Wiki 記事の取得に失敗しました")?;
+
+        // 著者ごとに最新バージョンのみ残す
+        let mut latest: HashMap<PublicKey, Event> = HashMap::new();
+        for event in events.into_iter() {
+            match latest.get(&event.pubkey) {
+                Some(existing) if existing.created_at >= event.created_at => {}
+                _ => {
+                    latest.insert(event.pubkey, event);
+                }
+            }
+        }
+
+        let mut events_vec: Vec<Event> = latest.into_values().collect();
+        events_vec.sort_by_key(|e| Reverse(e.created_at));
+        events_vec.truncate(limit as usize);
+
+        let pubkeys = Self::collect_pubkeys(&events_vec);
+        let profiles = self.fetch_profiles(&pubkeys).await;
+
+        Ok(events_vec.iter()
+            .map(|event| Self::event_to_wiki_article(event, &profiles))
+            .collect())
+    }
+
+    /// 同じトピックの 2 人の著者による Wiki 記事を比較します（NIP-54）。
+    pub async fn compare_wiki_versions(&self, topic: &str, base_author: &str, other_author: &str) -> Result<WikiComparison> {
+        let base_pk = Self::parse_public_key(base_author)?;
+        let other_pk = Self::parse_public_key(other_author)?;
+
+        let versions = self.get_wiki_articles(topic, None, MAX_WIKI_VERSIONS).await?;
+        let find = |pk: PublicKey| versions.iter()
+            .find(|v| v.author.pubkey == pk.to_hex())
+            .cloned()
+            .ok_or_else(|| anyhow!("{} による「{}」の Wiki 記事が見つかりません", pk.to_bech32().unwrap_or_default(), topic));
+
+        let base = find(base_pk)?;
+        let other = find(other_pk)?;
+        let diff = crate::diff::diff_lines(&base.content, &other.content);
+
+        Ok(WikiComparison {
+            topic: normalize_wiki_topic(topic),
+            base,
+            other,
+            diff,
+        })
+    }
+
+    /// Wiki 記事を公開または編集します（Kind 30818, NIP-54）。
+    ///
+    /// `fork_of` に他の著者の公開鍵を指定すると、その著者のバージョンを元にした
+    /// フォークとして a / e タグ（fork マーカー）を付与します。
+    pub async fn publish_wiki_article(
+        &self,
+        topic: &str,
+        content: &str,
+        title: Option<&str>,
+        summary: Option<&str>,
+        fork_of: Option<&str>,
+    ) -> Result<(WikiArticleInfo, PublishResult)> {
+        self.require_write_access()?;
+
+        let d_tag = normalize_wiki_topic(topic);
+        if d_tag.is_empty() {
+            return Err(anyhow!("無効なトピック名です: {}", topic));
+        }
+
+        let mut tags = vec![
+            Tag::identifier(d_tag.clone()),
+            Tag::custom(TagKind::Title, vec![title.unwrap_or(topic).to_string()]),
+        ];
+        if let Some(s) = summary {
+            tags.push(Tag::custom(TagKind::Summary, vec![s.to_string()]));
+        }
+
+        if let Some(source_author) = fork_of {
+            let source = self.get_wiki_articles(topic, Some(source_author), 1).await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow!("フォーク元の Wiki 記事が見つかりません"))?;
+            let a_value = format!("30818:{}:{}", source.author.pubkey, d_tag);
+            tags.push(Tag::custom(TagKind::custom("a".to_string()), vec![a_value, String::new(), "fork".to_string()]));
+            tags.push(Tag::custom(TagKind::custom("e".to_string()), vec![source.id, String::new(), "fork".to_string()]));
+        }
+
+        let builder = EventBuilder::new(Kind::from(30818), content).tags(tags);
+
+        let event = self.sign_event_builder(builder).await
+            .context("Wiki 記事の署名に失敗しました")?;
+        let output = self.send_event(event.clone()).await
+            .context("Wiki 記事の公開に失敗しました")?;
+
+        info!("Wiki 記事を公開しました ({}): {}", d_tag, event.id);
+
+        let profiles = self.fetch_profiles(&[event.pubkey]).await;
+        Ok((Self::event_to_wiki_article(&event, &profiles), PublishResult::new(output, self.public_key)))
+    }
+
+    /// Wiki 記事イベントから表示用情報に変換するヘルパー
+    fn event_to_wiki_article(event: &Event, profiles: &HashMap<PublicKey, AuthorInfo>) -> WikiArticleInfo {
+        let topic = extract_tag_value(event, "d").unwrap_or_default();
+
+        // ["a", "30818:<pubkey>:<d>", <relay>, "fork"]
+        let forked_from = event.tags.iter()
+            .filter(|tag| tag.kind() == TagKind::a())
+            .map(|tag| tag.as_slice())
+            .find(|values| values.get(3).map(String::as_str) == Some("fork"))
+            .and_then(|values| values.get(1).cloned());
+
+        WikiArticleInfo {
+            id: event.id.to_hex(),
+            naddr: Coordinate::new(event.kind, event.pubkey)
+                .identifier(&topic)
+                .to_bech32()
+                .unwrap_or_default(),
+            title: extract_tag_value(event, "title").unwrap_or_else(|| topic.clone()),
+            topic,
+            summary: extract_tag_value(event, "summary"),
+            content: event.content.clone(),
+            created_at: event.created_at.as_u64(),
+            forked_from,
+            author: profiles
+                .get(&event.pubkey)
+                .cloned()
+                .unwrap_or_else(|| AuthorInfo::from_public_key(&event.pubkey)),
+        }
+    }
+}
+
+/// Wiki のトピック名を d タグ用に正規化（NIP-54）
+///
+/// 文字（非 ASCII の文字を含む）は小文字化し、数字はそのまま残します。空白と `-` は区切りの `-` に、
+/// それ以外の記号は取り除き、連続する `-` は 1 つにまとめて前後の `-` を除きます。
+/// 文字も数字も含まないトピック名は空文字列を返します。
+fn normalize_wiki_topic(topic: &str) -> String {
+    let mut normalized = String::with_capacity(topic.len());
+    let mut pending_separator = false;
+    for c in topic.chars() {
+        if c.is_alphanumeric() {
+            if pending_separator && !normalized.is_empty() {
+                normalized.push('-');
+            }
+            pending_separator = false;
+            normalized.extend(c.to_lowercase());
+        } else if c.is_whitespace() || c == '-' {
+            pending_separator = true;
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_wiki_topic() {
+        assert_eq!(normalize_wiki_topic("Nostr Protocol"), "nostr-protocol");
+        // 記号は取り除き、区切りの連続や前後の区切りはまとめる
+        assert_eq!(normalize_wiki_topic("  What's  NIP-54? "), "whats-nip-54");
+        assert_eq!(normalize_wiki_topic("-- Rust -- async --"), "rust-async");
+        assert_eq!(normalize_wiki_topic("日本語 の記事"), "日本語-の記事");
+        // 数字だけのトピックもそのまま使える
+        assert_eq!(normalize_wiki_topic("2024"), "2024");
+        assert_eq!(normalize_wiki_topic("Bitcoin 21"), "bitcoin-21");
+        // 文字も数字もないトピックは無効
+        assert_eq!(normalize_wiki_topic("!!!"), "");
+    }
+}
@@ -0,0 +1,1089 @@
+//! Zap モジュール (NIP-57)
+//!
+//! Zap レシート (Kind 9735) の取得・検証・集計と Zap の送信、自分の支払いの集計を扱います。
+
+use anyhow::{anyhow, Context, Result};
+use nostr_sdk::prelude::*;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, info};
+
+use crate::nostr_client::{current_unix_timestamp, encode_nevent, extract_tag_value, relay_hints, AuthorInfo, NostrClient};
+
+/// キャッシュされた LNURL プロバイダーの nostrPubkey（取得日時, 公開鍵, 有効期間）。Zap 非対応・取得失敗なら None
+pub(crate) type CachedZapProvider = (std::time::Instant, Option<PublicKey>, Duration);
+
+/// LNURL プロバイダーの nostrPubkey キャッシュの有効期間
+pub(crate) const ZAP_PROVIDER_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+/// LNURL プロバイダーの取得に失敗した受取人を再取得しない期間
+const ZAP_PROVIDER_FAILURE_TTL: Duration = Duration::from_secs(600);
+
+/// 支払いレポートでローカルの Zap 記録と Zap レシートを同じ支払いとみなす時刻の差（秒）
+const SPENDING_MATCH_WINDOW_SECS: u64 = 600;
+
+/// 受取人の LNURL プロバイダーが分からず、レシートの署名者を確認できない場合の理由
+const ZAP_RECEIPT_UNVERIFIED: &str = "unknown_provider";
+
+/// Zap レシート情報（NIP-57）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ZapReceiptInfo {
+    /// hex 形式のイベント ID
+    pub id: String,
+    /// nevent 形式のイベント ID
+    pub nevent: String,
+    /// Zap 送信者の情報（Zap リクエストから取得）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender: Option<AuthorInfo>,
+    /// Zap 金額（sats）
+    pub amount_sats: u64,
+    /// Zap コメント
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Zap 対象のノート ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_note_id: Option<String>,
+    /// Zap 対象の pubkey
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_pubkey: Option<String>,
+    /// 作成日時の Unix タイムスタンプ
+    pub created_at: u64,
+    /// 真正性の検証に成功したか（NIP-57 Appendix F）
+    pub valid: bool,
+    /// 検証結果（valid / invalid / unverified: 受取人の LNURL プロバイダーが分からず署名者を確認できない）
+    pub status: String,
+    /// 検証に失敗した理由（missing_bolt11 / missing_description / invalid_bolt11 / description_hash_mismatch /
+    /// missing_amount / amount_mismatch / unexpected_signer / unknown_provider）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invalid_reason: Option<String>,
+}
+
+/// 送信者ごとの Zap 集計
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ZapperTotal {
+    /// Zap 送信者の情報
+    pub sender: AuthorInfo,
+    /// 合計金額（sats）
+    pub total_sats: u64,
+    /// Zap 回数
+    pub zap_count: u64,
+}
+
+/// Zap ランキング（NIP-57）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ZapLeaderboard {
+    /// 対象（hex 形式のイベント ID または公開鍵）
+    pub target: String,
+    /// 対象の種類（note / profile）
+    pub target_type: String,
+    /// 合計金額（sats）
+    pub total_sats: u64,
+    /// Zap 回数
+    pub zap_count: u64,
+    /// Zap した人数
+    pub unique_zappers: u64,
+    /// 真正性を確認できず集計から除外した Zap レシートの数
+    pub invalid_zap_count: u64,
+    /// 金額の降順に並べた送信者ごとの集計
+    pub entries: Vec<ZapperTotal>,
+}
+
+/// ユーザーが受け取った Zap の集計（NIP-57）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ProfileZapStats {
+    /// hex 形式の公開鍵
+    pub pubkey: String,
+    /// npub 形式の公開鍵
+    pub npub: String,
+    /// 集計期間の開始（Unix タイムスタンプ）
+    pub since: u64,
+    /// 集計期間の終了（Unix タイムスタンプ）
+    pub until: u64,
+    /// 受け取った合計金額（sats）
+    pub total_sats: u64,
+    /// Zap 回数
+    pub zap_count: u64,
+    /// 1 回あたりの平均金額（sats）
+    pub average_sats: u64,
+    /// 真正性を確認できず集計から除外した Zap レシートの数
+    pub invalid_zap_count: u64,
+    /// 金額の多い送信者
+    pub top_zappers: Vec<ZapperTotal>,
+}
+
+/// 期間ごとの支払い合計
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SpendingPeriodTotal {
+    /// 期間の開始（Unix タイムスタンプ、UTC）
+    pub start: u64,
+    /// 期間の開始日（YYYY-MM-DD）
+    pub label: String,
+    /// 合計金額（sats）
+    pub total_sats: u64,
+    /// 支払い回数
+    pub count: u64,
+}
+
+/// 受取人ごとの支払い合計
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecipientSpending {
+    /// 受取人の情報（Nostr ユーザーの場合）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<AuthorInfo>,
+    /// 支払い先の Lightning アドレス（受取人の公開鍵が分からない場合）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lightning_address: Option<String>,
+    /// 合計金額（sats）
+    pub total_sats: u64,
+    /// 支払い回数
+    pub count: u64,
+}
+
+/// 支払いレポート（Zap・Lightning 支払い）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SpendingReport {
+    /// 集計期間の開始（Unix タイムスタンプ）
+    pub since: u64,
+    /// 集計期間の終了（Unix タイムスタンプ）
+    pub until: u64,
+    /// 集計単位（"day" または "week"）
+    pub group_by: String,
+    /// 合計金額（sats）
+    pub total_sats: u64,
+    /// 支払い回数
+    pub payment_count: u64,
+    /// 期間ごとの合計（古い順、支払いのない期間は含まない）
+    pub periods: Vec<SpendingPeriodTotal>,
+    /// 金額の多い受取人
+    pub recipients: Vec<RecipientSpending>,
+    /// ローカルの支払い履歴から集計した件数
+    pub local_payments: u64,
+    /// ローカルの履歴になく、公開された Zap レシートから集計した件数
+    pub zap_receipts: u64,
+}
+
+/// 支払いレポートの集計対象となる 1 件の支払い
+#[derive(Debug, Clone)]
+struct SpendingEntry {
+    paid_at: u64,
+    amount_sats: u64,
+    /// 受取人の公開鍵（hex）
+    recipient: Option<String>,
+    lightning_address: Option<String>,
+}
+
+impl NostrClient {
+    /// ノートの Zap レシート (Kind 9735) を取得します。
+    pub async fn get_zap_receipts(&self, note_id: &str, limit: u64) -> Result<Vec<ZapReceiptInfo>> {
+        let event_id = self.resolve_event_id(note_id).await?;
+
+        // Kind 9735 (Zap Receipt) を取得
+        let filter = Filter::new()
+            .kind(Kind::ZapReceipt)
+            .event(event_id)
+            .limit(limit as usize);
+
+        let events = self.fetch_events(vec![filter], Duration::from_secs(10))
+            .await
+            .context("Zap レシートの取得に失敗しました")?;
+
+        let events_vec: Vec<Event> = events.into_iter().collect();
+        let problems = self.check_zap_receipts(&events_vec, true).await;
+        let mut receipts = Vec::new();
+
+        for event in &events_vec {
+            let receipt = self.parse_zap_receipt(event, problems.get(&event.id).copied()).await;
+            receipts.push(receipt);
+        }
+
+        receipts.sort_by_key(|x| Reverse(x.created_at));
+        receipts.truncate(limit as usize);
+
+        Ok(receipts)
+    }
+
+    /// ユーザーが受け取った Zap を集計します（NIP-57）。
+    ///
+    /// 指定期間内にユーザーを p タグで参照する Kind 9735 レシートを合計し、
+    /// 金額の多い送信者を上位 `top_n` 人まで返します。
+    pub async fn get_profile_zap_stats(&self, pubkey_str: &str, days: u64, top_n: usize) -> Result<ProfileZapStats> {
+        let pk = Self::parse_public_key(pubkey_str)?;
+        let until = current_unix_timestamp();
+        let since = until.saturating_sub(days.saturating_mul(86_400));
+
+        let filter = Filter::new()
+            .kind(Kind::ZapReceipt)
+            .pubkey(pk)
+            .since(Timestamp::from(since))
+            .limit(5000);
+
+        let events = self.fetch_events(vec![filter], Duration::from_secs(15))
+            .await
+            .context("Zap レシートの取得に失敗しました")?;
+        let mut events_vec: Vec<Event> = events.into_iter().collect();
+        let invalid_zap_count = self.retain_valid_zap_receipts(&mut events_vec).await;
+
+        let (total_sats, zap_count, by_sender) = tally_zaps_by_sender(&events_vec);
+        let top_zappers = self.build_zapper_totals(by_sender, top_n).await;
+
+        Ok(ProfileZapStats {
+            pubkey: pk.to_hex(),
+            npub: pk.to_bech32().unwrap_or_default(),
+            since,
+            until,
+            total_sats,
+            zap_count,
+            average_sats: total_sats.checked_div(zap_count).unwrap_or(0),
+            invalid_zap_count,
+            top_zappers,
+        })
+    }
+
+    /// ノートまたはユーザーへの Zap を送信者ごとに集計し、金額順のランキングを返します（NIP-57）。
+    ///
+    /// `note_id` を指定した場合はそのノートへの Zap、`pubkey` を指定した場合は
+    /// そのユーザーが受け取ったすべての Zap が対象になります。
+    pub async fn get_zap_leaderboard(&self, note_id: Option<&str>, pubkey: Option<&str>, top_n: usize) -> Result<ZapLeaderboard> {
+        let (filter, target, target_type) = match (note_id, pubkey) {
+            (Some(id), _) => {
+                let event_id = self.resolve_event_id(id).await?;
+                (Filter::new().event(event_id), event_id.to_hex(), "note")
+            }
+            (None, Some(pk_str)) => {
+                let pk = Self::parse_public_key(pk_str)?;
+                (Filter::new().pubkey(pk), pk.to_hex(), "profile")
+            }
+            (None, None) => return Err(anyhow!("note_id または pubkey のいずれかを指定してください")),
+        };
+
+        let filter = filter.kind(Kind::ZapReceipt).limit(5000);
+
+        let events = self.fetch_events(vec![filter], Duration::from_secs(15))
+            .await
+            .context("Zap レシートの取得に失敗しました")?;
+        let mut events_vec: Vec<Event> = events.into_iter().collect();
+        let invalid_zap_count = self.retain_valid_zap_receipts(&mut events_vec).await;
+
+        let (total_sats, zap_count, by_sender) = tally_zaps_by_sender(&events_vec);
+        let unique_zappers = by_sender.len() as u64;
+        let entries = self.build_zapper_totals(by_sender, top_n).await;
+
+        Ok(ZapLeaderboard {
+            target,
+            target_type: target_type.to_string(),
+            total_sats,
+            zap_count,
+            unique_zappers,
+            invalid_zap_count,
+            entries,
+        })
+    }
+
+    /// 送信者ごとの集計結果にプロフィールを付与し、上位 `top_n` 件に絞るヘルパー
+    async fn build_zapper_totals(&self, by_sender: Vec<(PublicKey, u64, u64)>, top_n: usize) -> Vec<ZapperTotal> {
+        let top: Vec<(PublicKey, u64, u64)> = by_sender.into_iter().take(top_n).collect();
+        let pubkeys: Vec<PublicKey> = top.iter().map(|(pk, _, _)| *pk).collect();
+        let profiles = self.fetch_profiles(&pubkeys).await;
+
+        top.into_iter()
+            .map(|(pk, total_sats, zap_count)| ZapperTotal {
+                sender: profiles
+                    .get(&pk)
+                    .cloned()
+                    .unwrap_or_else(|| AuthorInfo::from_public_key(&pk)),
+                total_sats,
+                zap_count,
+            })
+            .collect()
+    }
+
+    /// Zap レシートイベントをパースするヘルパー
+    ///
+    /// `problem` には check_zap_receipts で見つかった検証失敗の理由を渡します。
+    async fn parse_zap_receipt(&self, event: &Event, problem: Option<&str>) -> ZapReceiptInfo {
+        // bolt11 タグから金額を抽出
+        let bolt11 = extract_tag_value(event, "bolt11").unwrap_or_default();
+        let amount_sats = crate::bolt11::amount_sats(&bolt11);
+
+        // description タグから Zap リクエストを取得（送信者・コメント情報）
+        let description = extract_tag_value(event, "description");
+        let (sender_pubkey, comment) = if let Some(ref desc) = description {
+            Self::parse_zap_request_description(desc)
+        } else {
+            (None, None)
+        };
+
+        // 送信者のプロフィールを取得
+        let sender = if let Some(pk_hex) = &sender_pubkey {
+            if let Ok(pk) = PublicKey::from_hex(pk_hex) {
+                let profiles = self.fetch_profiles(&[pk]).await;
+                profiles.get(&pk).cloned()
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        // 対象ノート ID とpubkey を取得
+        let target_note_id = event.tags.iter().find_map(|tag| {
+            let values = tag.as_slice();
+            if values.len() >= 2 && values[0] == "e" {
+                Some(values[1].to_string())
+            } else {
+                None
+            }
+        });
+
+        let target_pubkey = event.tags.iter().find_map(|tag| {
+            let values = tag.as_slice();
+            if values.len() >= 2 && values[0] == "p" {
+                Some(values[1].to_string())
+            } else {
+                None
+            }
+        });
+
+        let seen_on = self.seen_on_relays(std::slice::from_ref(event)).await;
+
+        ZapReceiptInfo {
+            id: event.id.to_hex(),
+            nevent: encode_nevent(&event.id, Some(event.pubkey), relay_hints(&seen_on, &event.id)),
+            sender,
+            amount_sats,
+            comment,
+            target_note_id,
+            target_pubkey,
+            created_at: event.created_at.as_u64(),
+            valid: problem.is_none(),
+            status: zap_receipt_status(problem).to_string(),
+            invalid_reason: problem.map(String::from),
+        }
+    }
+
+    /// Zap リクエストの description JSON から送信者 pubkey とコメントを抽出
+    pub(crate) fn parse_zap_request_description(description: &str) -> (Option<String>, Option<String>) {
+        if let Ok(event) = serde_json::from_str::<serde_json::Value>(description) {
+            let pubkey = event.get("pubkey")
+                .and_then(|v| v.as_str())
+                .map(String::from);
+            let comment = event.get("content")
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .map(String::from);
+            (pubkey, comment)
+        } else {
+            (None, None)
+        }
+    }
+
+    /// キャッシュ済みの受取人の LNURL プロバイダーの nostrPubkey（未取得・期限切れなら None）
+    async fn cached_zap_provider_pubkey(&self, recipient: &PublicKey) -> Option<Option<PublicKey>> {
+        self.zap_provider_cache.read().await
+            .get(recipient)
+            .filter(|(fetched_at, _, ttl)| fetched_at.elapsed() < *ttl)
+            .map(|(_, provider, _)| *provider)
+    }
+
+    /// 受取人の LNURL プロバイダーの nostrPubkey を取得（キャッシュ付き）
+    ///
+    /// プロフィールの lud16 / lud06 から LNURL-pay 情報を取得し、Zap レシートの署名者となる
+    /// 公開鍵を返します。Lightning アドレスがない・Zap に対応していない場合は None です。
+    /// LNURL エンドポイントへの接続に失敗した場合も、同じ受取人のレシートごとに問い合わせないよう
+    /// None として短時間（`ZAP_PROVIDER_FAILURE_TTL`）キャッシュします。
+    async fn zap_provider_pubkey(&self, recipient: &PublicKey) -> Option<PublicKey> {
+        if let Some(provider) = self.cached_zap_provider_pubkey(recipient).await {
+            return provider;
+        }
+
+        let address = self.fetch_profiles(&[*recipient]).await
+            .remove(recipient)
+            .and_then(|author| author.metadata)
+            .and_then(|m| m.lud16.filter(|a| !a.is_empty()).or(m.lud06.filter(|a| !a.is_empty())));
+        let (provider, ttl) = match address {
+            Some(address) => match crate::lnurl::fetch_pay_info(&address).await {
+                Ok(pay_info) => (
+                    pay_info.nostr_pubkey.and_then(|hex| PublicKey::from_hex(&hex).ok()),
+                    ZAP_PROVIDER_CACHE_TTL,
+                ),
+                Err(e) => {
+                    debug!("LNURL プロバイダーの取得に失敗（署名者の確認をスキップ）: {}: {:#}", address, e);
+                    (None, ZAP_PROVIDER_FAILURE_TTL)
+                }
+            },
+            None => (None, ZAP_PROVIDER_CACHE_TTL),
+        };
+
+        let mut cache = self.zap_provider_cache.write().await;
+        cache.retain(|_, (fetched_at, _, ttl)| fetched_at.elapsed() < *ttl);
+        cache.insert(*recipient, (std::time::Instant::now(), provider, ttl));
+        provider
+    }
+
+    /// Zap レシートの真正性を検証（NIP-57 Appendix F）
+    ///
+    /// 戻り値は検証に失敗したレシートの ID と理由のマップです。Zap レシート以外のイベントは無視します。
+    /// 受取人の LNURL プロバイダーの nostrPubkey が分からない（Lightning アドレスがない・取得に失敗した）
+    /// レシートは署名者を確認できないため、未検証（`unknown_provider`）として有効なレシートに含めません。
+    /// `lookup_providers` が false の場合は LNURL エンドポイントに問い合わせず、キャッシュ済みの
+    /// プロバイダーだけで確認します（キャッシュにない受取人へのレシートは未検証）。
+    pub(crate) async fn check_zap_receipts(&self, events: &[Event], lookup_providers: bool) -> HashMap<EventId, &'static str> {
+        let receipts: Vec<&Event> = events.iter().filter(|e| e.kind == Kind::ZapReceipt).collect();
+
+        let mut providers: HashMap<PublicKey, Option<PublicKey>> = HashMap::new();
+        for recipient in receipts.iter().filter_map(|r| zap_receipt_recipient(r)) {
+            if let std::collections::hash_map::Entry::Vacant(entry) = providers.entry(recipient) {
+                let provider = if lookup_providers {
+                    self.zap_provider_pubkey(&recipient).await
+                } else {
+                    self.cached_zap_provider_pubkey(&recipient).await.flatten()
+                };
+                entry.insert(provider);
+            }
+        }
+
+        receipts
+            .into_iter()
+            .filter_map(|receipt| {
+                let provider = zap_receipt_recipient(receipt)
+                    .and_then(|recipient| providers.get(&recipient).copied().flatten());
+                zap_receipt_problem(receipt, provider.as_ref()).map(|problem| (receipt.id, problem))
+            })
+            .collect()
+    }
+
+    /// 真正性を確認できない Zap レシート（無効・未検証）を取り除き、除外した件数を返す
+    pub(crate) async fn retain_valid_zap_receipts(&self, events: &mut Vec<Event>) -> u64 {
+        let problems = self.check_zap_receipts(events, true).await;
+        if !problems.is_empty() {
+            debug!("無効な Zap レシートを除外: {} 件", problems.len());
+            events.retain(|e| !problems.contains_key(&e.id));
+        }
+        problems.len() as u64
+    }
+
+    /// ノートまたはプロフィールに Zap を送信します（NWC 設定が必要）。
+    pub async fn send_zap(&self, target: &str, amount_sats: u64, comment: Option<&str>) -> Result<serde_json::Value> {
+        self.require_write_access()?;
+
+        if !self.client.has_zapper().await {
+            return Err(anyhow!(
+                "Zap 送信には NWC (Nostr Wallet Connect) の設定が必要です。\
+                設定ファイルに \"nwc-uri\" を追加してください。"
+            ));
+        }
+
+        // target がイベント ID かpubkey かを判定
+        let zap_entity: ZapEntity = if target.starts_with("npub") || target.starts_with("nprofile") || (target.len() == 64 && target.chars().all(|c| c.is_ascii_hexdigit())) {
+            // pubkey として解釈を試みる（ただし64文字hex以外も考慮）
+            if let Ok(pk) = Self::parse_public_key(target) {
+                ZapEntity::from(pk)
+            } else if let Ok(eid) = self.resolve_event_id(target).await {
+                ZapEntity::from(eid)
+            } else {
+                return Err(anyhow!("無効な target です。イベント ID または公開鍵を指定してください。"));
+            }
+        } else {
+            // イベント ID として解釈
+            if let Ok(eid) = self.resolve_event_id(target).await {
+                ZapEntity::from(eid)
+            } else if let Ok(pk) = Self::parse_public_key(target) {
+                ZapEntity::from(pk)
+            } else {
+                return Err(anyhow!("無効な target です。イベント ID または公開鍵を指定してください。"));
+            }
+        };
+
+        let details = if let Some(msg) = comment {
+            Some(ZapDetails::new(ZapType::Public).message(msg))
+        } else {
+            Some(ZapDetails::new(ZapType::Public))
+        };
+
+        // 上限の確認から履歴の記録までをロックして、同時の支払いが同じ残りの上限を使わないようにする
+        let _payment = self.payment_lock.lock().await;
+        self.check_payment_limits(amount_sats).await?;
+        self.refuse_in_dry_run("Zap の送信")?;
+
+        let (recipient_pubkey, note_id) = match &zap_entity {
+            ZapEntity::PublicKey(pk) => (Some(pk.to_hex()), None),
+            ZapEntity::Event(id) => (None, Some(id.to_hex())),
+        };
+        self.client.zap(zap_entity, amount_sats, details).await
+            .context("Zap の送信に失敗しました")?;
+        self.record_payment(crate::storage::PaymentRecord {
+            paid_at: current_unix_timestamp(),
+            amount_sats,
+            kind: "zap".to_string(),
+            recipient_pubkey,
+            lightning_address: None,
+            note_id,
+            payment_hash: None,
+        }).await;
+
+        info!("Zap を送信しました: {} sats → {}", amount_sats, target);
+
+        Ok(serde_json::json!({
+            "success": true,
+            "amount_sats": amount_sats,
+            "target": target,
+            "message": format!("{} sats の Zap を送信しました。", amount_sats)
+        }))
+    }
+
+    /// 自分の支払い（Zap・Lightning 支払い）を期間・受取人ごとに集計します。
+    ///
+    /// このサーバーで記録したローカルの支払い履歴に、リレーに公開された自分の Zap レシート
+    /// （P タグが自分の Kind 9735）を合わせて集計するため、他のクライアントから送った Zap も含まれます。
+    /// 両方にある支払いは支払いハッシュ、または受取人・金額・時刻の近さで重複を除きます。
+    /// `weekly` が true の場合は週（月曜始まり、UTC）ごと、false の場合は日ごとに集計します。
+    pub async fn get_spending_report(&self, since: u64, weekly: bool, top_n: usize) -> Result<SpendingReport> {
+        let until = current_unix_timestamp();
+        let local: Vec<crate::storage::PaymentRecord> = crate::storage::load_payment_log()
+            .into_iter()
+            .filter(|record| record.paid_at >= since)
+            .collect();
+
+        let mut receipts: Vec<Event> = match self.public_key {
+            Some(pk) => {
+                let filter = Filter::new()
+                    .kind(Kind::ZapReceipt)
+                    .custom_tag(SingleLetterTag::uppercase(Alphabet::P), vec![pk.to_hex()])
+                    .since(Timestamp::from(since))
+                    .limit(5000);
+                self.fetch_events(vec![filter], Duration::from_secs(15))
+                    .await
+                    .context("Zap レシートの取得に失敗しました")?
+                    .into_iter()
+                    .filter(|receipt| zap_receipt_sender(receipt) == Some(pk))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        // 自分が送った Zap なので、送信者の確認に加えて bolt11 と description ハッシュが
+        // 整合していれば十分とし、受取人ごとの LNURL プロバイダ検証は行わない
+        receipts.retain(|receipt| zap_receipt_problem(receipt, Some(&receipt.pubkey)).is_none());
+
+        // 受取人が記録されていないノートへの Zap は、ノートの作成者を受取人とする
+        let unresolved: Vec<EventId> = local.iter()
+            .filter(|record| record.recipient_pubkey.is_none())
+            .filter_map(|record| record.note_id.as_deref())
+            .filter_map(|id| EventId::from_hex(id).ok())
+            .collect();
+        let note_authors: HashMap<String, String> = if unresolved.is_empty() {
+            HashMap::new()
+        } else {
+            self.fetch_events(vec![Filter::new().ids(unresolved)], Duration::from_secs(10))
+                .await
+                .map(|events| events.into_iter().map(|e| (e.id.to_hex(), e.pubkey.to_hex())).collect())
+                .unwrap_or_default()
+        };
+
+        let mut entries: Vec<SpendingEntry> = local.iter()
+            .map(|record| SpendingEntry {
+                paid_at: record.paid_at,
+                amount_sats: record.amount_sats,
+                recipient: record.recipient_pubkey.clone()
+                    .or_else(|| record.note_id.as_ref().and_then(|id| note_authors.get(id).cloned())),
+                lightning_address: record.lightning_address.clone(),
+            })
+            .collect();
+        let local_payments = entries.len() as u64;
+        let zap_receipts = merge_zap_receipts_into_spending(&mut entries, &local, &receipts);
+
+        let (periods, by_recipient) = summarize_spending(&entries, weekly);
+        let total_sats = entries.iter().fold(0u64, |acc, e| acc.saturating_add(e.amount_sats));
+        let payment_count = entries.len() as u64;
+
+        let top: Vec<(String, u64, u64)> = by_recipient.into_iter().take(top_n).collect();
+        let pubkeys: Vec<PublicKey> = top.iter()
+            .filter_map(|(key, _, _)| PublicKey::from_hex(key).ok())
+            .collect();
+        let profiles = self.fetch_profiles(&pubkeys).await;
+        let recipients = top.into_iter()
+            .map(|(key, total_sats, count)| match PublicKey::from_hex(&key) {
+                Ok(pk) => RecipientSpending {
+                    recipient: Some(profiles.get(&pk).cloned().unwrap_or_else(|| AuthorInfo::from_public_key(&pk))),
+                    lightning_address: None,
+                    total_sats,
+                    count,
+                },
+                Err(_) => RecipientSpending {
+                    recipient: None,
+                    lightning_address: (!key.is_empty()).then_some(key),
+                    total_sats,
+                    count,
+                },
+            })
+            .collect();
+
+        Ok(SpendingReport {
+            since,
+            until,
+            group_by: if weekly { "week" } else { "day" }.to_string(),
+            total_sats,
+            payment_count,
+            periods,
+            recipients,
+            local_payments,
+            zap_receipts,
+        })
+    }
+}
+
+/// Zap レシートから送信者の公開鍵を取得
+///
+/// description タグの Zap リクエストの pubkey を優先し、なければ P タグを使用します。
+pub(crate) fn zap_receipt_sender(event: &Event) -> Option<PublicKey> {
+    extract_tag_value(event, "description")
+        .and_then(|desc| NostrClient::parse_zap_request_description(&desc).0)
+        .or_else(|| extract_tag_value(event, "P"))
+        .and_then(|hex| PublicKey::from_hex(&hex).ok())
+}
+
+/// Zap レシートの受取人（p タグ）の公開鍵を取得
+fn zap_receipt_recipient(event: &Event) -> Option<PublicKey> {
+    extract_tag_value(event, "p").and_then(|hex| PublicKey::from_hex(&hex).ok())
+}
+
+/// Zap レシートが NIP-57 Appendix F の検証に失敗する理由を返す（問題がなければ None）
+///
+/// bolt11 が受取ノードの署名付きでデコードできること、description タグ（Zap リクエスト）の SHA-256 が
+/// bolt11 の description hash と一致すること、Zap リクエストに `amount` タグがあれば bolt11 の金額と
+/// 一致すること、レシートが `provider`（受取人の LNURL プロバイダーの nostrPubkey）で署名されていることを
+/// 確認します。`provider` が None の場合は署名者を確認できないため [`ZAP_RECEIPT_UNVERIFIED`] を返します。
+fn zap_receipt_problem(receipt: &Event, provider: Option<&PublicKey>) -> Option<&'static str> {
+    let Some(bolt11) = extract_tag_value(receipt, "bolt11") else {
+        return Some("missing_bolt11");
+    };
+    let Some(description) = extract_tag_value(receipt, "description") else {
+        return Some("missing_description");
+    };
+    let Ok(invoice) = crate::bolt11::decode(&bolt11) else {
+        return Some("invalid_bolt11");
+    };
+    if !crate::bolt11::description_hash_matches(&bolt11, &description) {
+        return Some("description_hash_mismatch");
+    }
+    let Some(amount_msat) = invoice.amount_msat else {
+        return Some("missing_amount");
+    };
+    if zap_request_amount(&description).is_some_and(|requested| requested != amount_msat.to_string()) {
+        return Some("amount_mismatch");
+    }
+    match provider {
+        None => Some(ZAP_RECEIPT_UNVERIFIED),
+        Some(pk) if *pk != receipt.pubkey => Some("unexpected_signer"),
+        Some(_) => None,
+    }
+}
+
+/// Zap レシートの検証結果（valid / invalid / unverified）
+fn zap_receipt_status(problem: Option<&str>) -> &'static str {
+    match problem {
+        None => "valid",
+        Some(ZAP_RECEIPT_UNVERIFIED) => "unverified",
+        Some(_) => "invalid",
+    }
+}
+
+/// Zap リクエスト（description タグの JSON）の `amount` タグの値（ミリサトシ）
+fn zap_request_amount(description: &str) -> Option<String> {
+    let request: serde_json::Value = serde_json::from_str(description).ok()?;
+    request["tags"].as_array()?.iter().find_map(|tag| {
+        let tag = tag.as_array()?;
+        (tag.first()?.as_str()? == "amount").then(|| tag.get(1)?.as_str().map(String::from))?
+    })
+}
+
+/// Zap レシートを送信者ごとに集計
+///
+/// 戻り値は（合計 sats, Zap 回数, 合計金額の降順に並べた (送信者, 合計 sats, 回数) のリスト）です。
+/// 送信者を特定できないレシートは合計にのみ含めます。
+fn tally_zaps_by_sender(receipts: &[Event]) -> (u64, u64, Vec<(PublicKey, u64, u64)>) {
+    let mut total_sats = 0;
+    let mut by_sender: HashMap<PublicKey, (u64, u64)> = HashMap::new();
+
+    for receipt in receipts {
+        let amount = crate::bolt11::amount_sats(&extract_tag_value(receipt, "bolt11").unwrap_or_default());
+        total_sats += amount;
+        if let Some(sender) = zap_receipt_sender(receipt) {
+            let entry = by_sender.entry(sender).or_insert((0, 0));
+            entry.0 += amount;
+            entry.1 += 1;
+        }
+    }
+
+    let mut ranked: Vec<(PublicKey, u64, u64)> = by_sender.into_iter()
+        .map(|(pk, (sats, count))| (pk, sats, count))
+        .collect();
+    ranked.sort_by_key(|(_, sats, count)| (Reverse(*sats), Reverse(*count)));
+
+    (total_sats, receipts.len() as u64, ranked)
+}
+
+/// Zap レシートの金額（bolt11 の金額、sats）をノートごとに合計
+///
+/// レシートの e タグが `event_ids` のいずれかを指すものだけを数えます。`problems`（`check_zap_receipts`）
+/// で無効なレシートは数えず、未検証のレシートがあるノートは合計額が分からないため None にします。
+pub(crate) fn zap_totals_by_note(
+    receipts: &[Event],
+    problems: &HashMap<EventId, &'static str>,
+    event_ids: &[EventId],
+) -> HashMap<EventId, Option<u64>> {
+    let mut totals: HashMap<EventId, Option<u64>> = event_ids.iter().map(|id| (*id, Some(0))).collect();
+    for receipt in receipts {
+        let Some(target) = receipt.tags.event_ids().find(|id| event_ids.contains(id)) else { continue };
+        let Some(total) = totals.get_mut(target) else { continue };
+        match problems.get(&receipt.id) {
+            None => {
+                let amount = crate::bolt11::amount_sats(&extract_tag_value(receipt, "bolt11").unwrap_or_default());
+                *total = total.map(|t| t.saturating_add(amount));
+            }
+            Some(&ZAP_RECEIPT_UNVERIFIED) => *total = None,
+            Some(_) => {}
+        }
+    }
+    totals
+}
+
+/// ローカルの支払い履歴にない Zap レシートを支払いとして追加し、追加した件数を返す
+///
+/// 支払いハッシュが一致するレシート、または受取人（またはノート）と金額が同じで
+/// 時刻の差が SPENDING_MATCH_WINDOW_SECS 以内の Zap 記録があるレシートは重複とみなします。
+fn merge_zap_receipts_into_spending(
+    entries: &mut Vec<SpendingEntry>,
+    local: &[crate::storage::PaymentRecord],
+    receipts: &[Event],
+) -> u64 {
+    let mut matched = vec![false; local.len()];
+    let mut added = 0;
+
+    for receipt in receipts {
+        let bolt11 = extract_tag_value(receipt, "bolt11").unwrap_or_default();
+        let decoded = crate::bolt11::decode(&bolt11).ok();
+        let amount_sats = decoded.as_ref().and_then(|i| i.amount_sats()).unwrap_or(0);
+        let payment_hash = decoded.and_then(|i| i.payment_hash);
+        let recipient = extract_tag_value(receipt, "p");
+        let note_id = extract_tag_value(receipt, "e");
+        let created_at = receipt.created_at.as_u64();
+
+        let duplicate = local.iter().enumerate().position(|(i, record)| {
+            if matched[i] {
+                return false;
+            }
+            if payment_hash.is_some() && record.payment_hash == payment_hash {
+                return true;
+            }
+            record.payment_hash.is_none()
+                && record.kind == "zap"
+                && record.amount_sats == amount_sats
+                && record.paid_at.abs_diff(created_at) <= SPENDING_MATCH_WINDOW_SECS
+                && ((record.recipient_pubkey.is_some() && record.recipient_pubkey == recipient)
+                    || (record.note_id.is_some() && record.note_id == note_id))
+        });
+        match duplicate {
+            Some(i) => matched[i] = true,
+            None => {
+                entries.push(SpendingEntry {
+                    paid_at: created_at,
+                    amount_sats,
+                    recipient,
+                    lightning_address: None,
+                });
+                added += 1;
+            }
+        }
+    }
+    added
+}
+
+/// 集計期間の開始時刻（UTC の日、または月曜始まりの週）
+fn spending_period_start(timestamp: u64, weekly: bool) -> u64 {
+    let day = timestamp / 86_400;
+    if weekly {
+        // 1970-01-01 は木曜日
+        (day - (day + 3) % 7) * 86_400
+    } else {
+        day * 86_400
+    }
+}
+
+/// 支払いを期間ごと・受取人ごとに集計
+///
+/// 戻り値は（古い順の期間ごとの合計, 合計金額の降順に並べた (受取人, 合計 sats, 回数) のリスト）です。
+/// 受取人は公開鍵（hex）、なければ Lightning アドレス、どちらもなければ空文字列です。
+fn summarize_spending(entries: &[SpendingEntry], weekly: bool) -> (Vec<SpendingPeriodTotal>, Vec<(String, u64, u64)>) {
+    let mut periods: std::collections::BTreeMap<u64, (u64, u64)> = std::collections::BTreeMap::new();
+    let mut by_recipient: HashMap<String, (u64, u64)> = HashMap::new();
+
+    for entry in entries {
+        let period = periods.entry(spending_period_start(entry.paid_at, weekly)).or_insert((0, 0));
+        period.0 = period.0.saturating_add(entry.amount_sats);
+        period.1 += 1;
+
+        let key = entry.recipient.clone()
+            .or_else(|| entry.lightning_address.clone())
+            .unwrap_or_default();
+        let recipient = by_recipient.entry(key).or_insert((0, 0));
+        recipient.0 = recipient.0.saturating_add(entry.amount_sats);
+        recipient.1 += 1;
+    }
+
+    let periods = periods.into_iter()
+        .map(|(start, (total_sats, count))| SpendingPeriodTotal {
+            start,
+            label: chrono::DateTime::from_timestamp(start as i64, 0)
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            total_sats,
+            count,
+        })
+        .collect();
+
+    let mut ranked: Vec<(String, u64, u64)> = by_recipient.into_iter()
+        .map(|(key, (sats, count))| (key, sats, count))
+        .collect();
+    ranked.sort_by_key(|(_, sats, count)| (Reverse(*sats), Reverse(*count)));
+
+    (periods, ranked)
+}
+
+/// テスト用の Zap リクエスト（レシートの description タグの値）
+#[cfg(test)]
+pub(crate) const TEST_ZAP_REQUEST: &str = r#"{"kind":9734,"content":"","tags":[]}"#;
+
+/// description_hash = sha256(TEST_ZAP_REQUEST)、支払いハッシュ = 0x00..0x1f の 10u の署名済みインボイス
+#[cfg(test)]
+pub(crate) fn test_zap_invoice() -> String {
+    crate::bolt11::test_invoice("lnbc10u", Some(TEST_ZAP_REQUEST))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 金額のみを持つ署名済みインボイス（10u / 50u / 20u）
+    fn invoice_10u() -> String {
+        crate::bolt11::test_invoice("lnbc10u", None)
+    }
+
+    fn invoice_50u() -> String {
+        crate::bolt11::test_invoice("lnbc50u", None)
+    }
+
+    fn invoice_20u() -> String {
+        crate::bolt11::test_invoice("lnbc20u", None)
+    }
+
+    #[test]
+    fn test_tally_zaps_by_sender() {
+        let receiver = Keys::generate();
+        let zap_receipt = |sender: &Keys, bolt11: &str| {
+            let request = EventBuilder::new(Kind::ZapRequest, "")
+                .sign_with_keys(sender)
+                .unwrap();
+            EventBuilder::new(Kind::ZapReceipt, "")
+                .tags(vec![
+                    Tag::public_key(receiver.public_key()),
+                    Tag::custom(TagKind::custom("bolt11".to_string()), vec![bolt11.to_string()]),
+                    Tag::custom(TagKind::custom("description".to_string()), vec![request.as_json()]),
+                ])
+                .sign_with_keys(&receiver)
+                .unwrap()
+        };
+
+        let alice = Keys::generate();
+        let bob = Keys::generate();
+        let receipts = vec![
+            zap_receipt(&alice, &invoice_10u()),
+            zap_receipt(&bob, &invoice_50u()),
+            zap_receipt(&alice, &invoice_20u()),
+        ];
+
+        let (total_sats, zap_count, ranked) = tally_zaps_by_sender(&receipts);
+        assert_eq!(total_sats, 8_000);
+        assert_eq!(zap_count, 3);
+        assert_eq!(ranked[0], (bob.public_key(), 5_000, 1));
+        assert_eq!(ranked[1], (alice.public_key(), 3_000, 2));
+    }
+
+    #[test]
+    fn test_zap_totals_by_note() {
+        let keys = Keys::generate();
+        let note_a = EventId::all_zeros();
+        let note_b = EventId::from_slice(&[1; 32]).unwrap();
+        let other = EventId::from_slice(&[2; 32]).unwrap();
+        let receipt = |target: EventId, bolt11: &str| EventBuilder::new(Kind::ZapReceipt, "")
+            .tags(vec![
+                Tag::event(target),
+                Tag::custom(TagKind::custom("bolt11".to_string()), vec![bolt11.to_string()]),
+            ])
+            .sign_with_keys(&keys)
+            .unwrap();
+
+        let note_c = EventId::from_slice(&[3; 32]).unwrap();
+        let note_d = EventId::from_slice(&[4; 32]).unwrap();
+        let receipts = vec![
+            receipt(note_a, &invoice_10u()),
+            receipt(note_a, &invoice_20u()),
+            receipt(note_b, &invoice_50u()),
+            receipt(other, &invoice_50u()),
+            // 無効なレシートは数えない
+            receipt(note_b, &invoice_20u()),
+            // 未検証のレシートがあるノートは合計額が分からない
+            receipt(note_c, &invoice_10u()),
+        ];
+        let problems = HashMap::from([
+            (receipts[4].id, "description_hash_mismatch"),
+            (receipts[5].id, ZAP_RECEIPT_UNVERIFIED),
+        ]);
+        let totals = zap_totals_by_note(&receipts, &problems, &[note_a, note_b, note_c, note_d]);
+        assert_eq!(totals.get(&note_a), Some(&Some(3_000)));
+        assert_eq!(totals.get(&note_b), Some(&Some(5_000)));
+        assert_eq!(totals.get(&note_c), Some(&None));
+        assert_eq!(totals.get(&note_d), Some(&Some(0)));
+        assert!(!totals.contains_key(&other));
+    }
+
+    #[test]
+    fn test_summarize_spending() {
+        let entry = |paid_at: u64, amount_sats: u64, recipient: Option<&str>, address: Option<&str>| SpendingEntry {
+            paid_at,
+            amount_sats,
+            recipient: recipient.map(String::from),
+            lightning_address: address.map(String::from),
+        };
+        // 2024-01-01 (月) 00:00 UTC
+        let monday = 1_704_067_200;
+        let entries = vec![
+            entry(monday + 3_600, 100, Some("alice"), None),
+            entry(monday + 86_400 + 10, 500, Some("bob"), Some("bob@example.com")),
+            entry(monday + 6 * 86_400, 200, Some("alice"), None),
+            entry(monday + 7 * 86_400, 50, None, Some("shop@example.com")),
+        ];
+
+        let (days, ranked) = summarize_spending(&entries, false);
+        assert_eq!(days.len(), 4);
+        assert_eq!(days[0], SpendingPeriodTotal { start: monday, label: "2024-01-01".to_string(), total_sats: 100, count: 1 });
+        assert_eq!(days[1].label, "2024-01-02");
+        assert_eq!(ranked[0], ("bob".to_string(), 500, 1));
+        assert_eq!(ranked[1], ("alice".to_string(), 300, 2));
+        assert_eq!(ranked[2], ("shop@example.com".to_string(), 50, 1));
+
+        // 週は月曜始まり
+        let (weeks, _) = summarize_spending(&entries, true);
+        assert_eq!(weeks.len(), 2);
+        assert_eq!((weeks[0].start, weeks[0].total_sats, weeks[0].count), (monday, 800, 3));
+        assert_eq!(weeks[1].label, "2024-01-08");
+
+        // 合計はオーバーフローせず飽和する
+        let huge = vec![entry(monday, u64::MAX, Some("alice"), None), entry(monday + 10, 1, Some("alice"), None)];
+        let (days, ranked) = summarize_spending(&huge, false);
+        assert_eq!(days[0].total_sats, u64::MAX);
+        assert_eq!(ranked[0], ("alice".to_string(), u64::MAX, 2));
+    }
+
+    #[test]
+    fn test_merge_zap_receipts_into_spending() {
+        let me = Keys::generate();
+        let alice = Keys::generate();
+        let provider = Keys::generate();
+        let receipt = |bolt11: &str, recipient: &Keys, created_at: u64| {
+            EventBuilder::new(Kind::ZapReceipt, "")
+                .tags(vec![
+                    Tag::public_key(recipient.public_key()),
+                    Tag::custom(TagKind::custom("P".to_string()), vec![me.public_key().to_hex()]),
+                    Tag::custom(TagKind::custom("bolt11".to_string()), vec![bolt11.to_string()]),
+                ])
+                .custom_created_at(Timestamp::from(created_at))
+                .sign_with_keys(&provider)
+                .unwrap()
+        };
+        let record = |kind: &str, amount_sats: u64, paid_at: u64, payment_hash: Option<&str>| crate::storage::PaymentRecord {
+            paid_at,
+            amount_sats,
+            kind: kind.to_string(),
+            recipient_pubkey: Some(alice.public_key().to_hex()),
+            lightning_address: None,
+            note_id: None,
+            payment_hash: payment_hash.map(String::from),
+        };
+
+        let local = vec![
+            // Lightning 支払い（Zap リクエスト付き）: 支払いハッシュで照合
+            record("lightning", 1_000, 10_000, Some("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f")),
+            // send_zap の記録: 受取人・金額・時刻で照合
+            record("zap", 5_000, 20_000, None),
+        ];
+        let receipts = vec![
+            receipt(&test_zap_invoice(), &alice, 10_005),
+            receipt(&invoice_50u(), &alice, 20_030),
+            // 他のクライアントから送った Zap
+            receipt(&invoice_20u(), &alice, 30_000),
+            // 時刻が離れている同額の Zap は別の支払い
+            receipt(&invoice_50u(), &alice, 90_000),
+        ];
+
+        let mut entries = Vec::new();
+        let added = merge_zap_receipts_into_spending(&mut entries, &local, &receipts);
+        assert_eq!(added, 2);
+        assert_eq!(entries[0].amount_sats, 2_000);
+        assert_eq!(entries[0].recipient, Some(alice.public_key().to_hex()));
+        assert_eq!((entries[1].paid_at, entries[1].amount_sats), (90_000, 5_000));
+    }
+
+    #[test]
+    fn test_zap_receipt_problem() {
+        let provider = Keys::generate();
+        let receipt_with = |signer: &Keys, bolt11: &str, description: &str| {
+            EventBuilder::new(Kind::ZapReceipt, "")
+                .tags(vec![
+                    Tag::custom(TagKind::custom("bolt11".to_string()), vec![bolt11.to_string()]),
+                    Tag::custom(TagKind::custom("description".to_string()), vec![description.to_string()]),
+                ])
+                .sign_with_keys(signer)
+                .unwrap()
+        };
+        let receipt = |signer: &Keys, description: &str| receipt_with(signer, &test_zap_invoice(), description);
+
+        let genuine = receipt(&provider, TEST_ZAP_REQUEST);
+        assert_eq!(zap_receipt_problem(&genuine, Some(&provider.public_key())), None);
+        // プロバイダーが不明なら署名者を確認できないため未検証
+        assert_eq!(zap_receipt_problem(&genuine, None), Some(ZAP_RECEIPT_UNVERIFIED));
+        assert_eq!(zap_receipt_status(zap_receipt_problem(&genuine, None)), "unverified");
+
+        let spoofed = receipt(&Keys::generate(), TEST_ZAP_REQUEST);
+        assert_eq!(zap_receipt_problem(&spoofed, Some(&provider.public_key())), Some("unexpected_signer"));
+        assert_eq!(zap_receipt_status(Some("unexpected_signer")), "invalid");
+
+        let tampered = receipt(&provider, r#"{"kind":9734,"content":"forged","tags":[]}"#);
+        assert_eq!(zap_receipt_problem(&tampered, Some(&provider.public_key())), Some("description_hash_mismatch"));
+
+        let no_invoice = EventBuilder::new(Kind::ZapReceipt, "").sign_with_keys(&provider).unwrap();
+        assert_eq!(zap_receipt_problem(&no_invoice, None), Some("missing_bolt11"));
+
+        // Zap リクエストの amount（ミリサトシ）が bolt11 の金額と異なるレシートは無効
+        let requested = |amount: &str| format!(r#"{{"kind":9734,"content":"","tags":[["amount","{}"]]}}"#, amount);
+        let check = |description: &str| {
+            let bolt11 = crate::bolt11::test_invoice("lnbc10u", Some(description));
+            zap_receipt_problem(&receipt_with(&provider, &bolt11, description), Some(&provider.public_key()))
+        };
+        assert_eq!(check(&requested("1000000")), None);
+        assert_eq!(check(&requested("21000")), Some("amount_mismatch"));
+        let no_amount = crate::bolt11::test_invoice("lnbc", Some(TEST_ZAP_REQUEST));
+        assert_eq!(
+            zap_receipt_problem(&receipt_with(&provider, &no_amount, TEST_ZAP_REQUEST), Some(&provider.public_key())),
+            Some("missing_amount")
+        );
+
+        // 改ざんされた bolt11 は無効
+        let mut forged = test_zap_invoice();
+        forged.replace_range(4..6, "20");
+        assert_eq!(
+            zap_receipt_problem(&receipt_with(&provider, &forged, TEST_ZAP_REQUEST), Some(&provider.public_key())),
+            Some("invalid_bolt11")
+        );
+    }
+}
@@ -0,0 +1,27 @@
+/* Nostr MCP Apps - Host theme
+ * Applies the host context from the MCP Apps handshake (the `ui/initialize` result and
+ * later `ui/notifications/host-context-changed` notifications) so cards follow the host.
+ * - `theme` ("light" / "dark") switches the dark defaults defined in common.css.
+ * - `styles.variables` are CSS custom properties (e.g. --color-background-primary, or an
+ *   accent such as --color-text-info) set on :root, overriding the built-in values.
+ * Notifications may carry only the changed fields, so missing fields are left as they are.
+ */
+
+function applyHostContext(ctx) {
+  if (!ctx) return;
+  const root = document.documentElement;
+
+  if (ctx.theme === "light" || ctx.theme === "dark") {
+    root.dataset.theme = ctx.theme;
+  }
+
+  const variables = ctx.styles?.variables || {};
+  for (const [name, value] of Object.entries(variables)) {
+    if (!name.startsWith("--")) continue;
+    if (value === null || value === undefined || value === "") {
+      root.style.removeProperty(name);
+    } else {
+      root.style.setProperty(name, String(value));
+    }
+  }
+}
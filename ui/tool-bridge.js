@@ -0,0 +1,47 @@
+/* Nostr MCP Apps - Tool call bridge
+ * Calls server tools from inside the iframe via the host (`tools/call` over postMessage).
+ * The host may ask the user to confirm the call first, so a call can stay pending for a
+ * while, be rejected by the user, or never be answered. Those cases are reported as
+ * distinct ToolCallError kinds so templates can show the right message.
+ * Templates must define `sendRpc(method, params)` before this script runs.
+ */
+
+const TOOL_CALL_TIMEOUT_MS = 120000;
+
+class ToolCallError extends Error {
+  /* kind: "rejected" (host or user declined), "timeout" (no answer), "tool" (tool returned an error) */
+  constructor(kind, message) {
+    super(message);
+    this.kind = kind;
+  }
+}
+
+async function callTool(name, args) {
+  let timer;
+  const timeout = new Promise((_, reject) => {
+    timer = setTimeout(
+      () => reject(new ToolCallError("timeout", "No response from the host")),
+      TOOL_CALL_TIMEOUT_MS
+    );
+  });
+
+  let result;
+  try {
+    result = await Promise.race([sendRpc("tools/call", { name, arguments: args }), timeout]);
+  } catch (e) {
+    if (e instanceof ToolCallError) throw e;
+    throw new ToolCallError("rejected", e?.message || "The tool call was not approved");
+  } finally {
+    clearTimeout(timer);
+  }
+
+  const text = result?.content?.[0]?.text || "";
+  if (result?.isError) {
+    throw new ToolCallError("tool", text || "The tool call failed");
+  }
+  try {
+    return JSON.parse(text || "{}");
+  } catch (e) {
+    return { message: text };
+  }
+}